@@ -13,12 +13,10 @@ use std::{
 };
 use walkdir::WalkDir;
 use chrono::{DateTime, Utc};
+use sha2::{Sha256, Digest};
 
 // ---------------------------------- CONFIGURATION START ----------------------------------
 
-/// which compressor to use to serve the static files
-const COMPRESSOR: Compressors = Compressors::GZip;
-
 // web-app
 //////////
 
@@ -31,6 +29,9 @@ const ANGULAR_WEB_APP_NAME: &str = "kickass-app-template";
 /// for the `web-app/`, should we build a regular Angular site or a blazing fast pre-rendered, universal one?
 const ANGULAR_WEB_APP_BUILD_TYPE: AngularBuildTypes = AngularBuildTypes::PreRenderedUniversal;
 
+/// where, in `web-app/`, the typed API client is regenerated into -- out of the OpenAPI document, written there as well
+const ANGULAR_WEB_APP_API_DIR: &str = "src/app/api";
+
 // web-egui
 ///////////
 
@@ -52,9 +53,30 @@ const ANGULAR_WEB_STATS_NAME: &str = "coreui-free-angular-admin-template";
 /// for the `web-stats/`, should we build a regular Angular site or a blazing fast pre-rendered, universal one?
 const ANGULAR_WEB_STATS_BUILD_TYPE: AngularBuildTypes = AngularBuildTypes::Regular;
 
+// gRPC
+///////
+
+/// the gRPC services' definitions -- in relation to the project's root. See `src/frontend/grpc`
+const GRPC_PROTO_FILES: &[&str] = &["proto/demo.proto"];
+
+/// where the definitions above look for their imports
+const GRPC_PROTO_DIR: &str = "proto";
+
+/// the file (in `OUT_DIR`) the definitions are described in, for the gRPC reflection service
+const GRPC_DESCRIPTOR_FILE_NAME: &str = "grpc_descriptor.bin";
+
+// OpenAPI
+//////////
+
+/// the Rocket routes the OpenAPI document is extracted from -- in relation to the project's root. See `generate_openapi_document()`
+const OPENAPI_ROUTES_FILE: &str = "src/frontend/web/api.rs";
+
+/// the OpenAPI document's file name -- written to `ANGULAR_WEB_APP_API_DIR`
+const OPENAPI_DOCUMENT_FILE_NAME: &str = "openapi.json";
+
 // ----------------------------------- CONFIGURATION END -----------------------------------
 
-/// how smaller (in bytes) the compressed file must be, in comparison to the plain version, for us to serve it in the compressed form
+/// how smaller (in bytes) a compressed variant must be, in comparison to the plain version, for us to embed it & serve it to the clients accepting it
 const COMPRESSION_THRESHOLD: usize = 100;
 
 /// builds a production-ready website using regular Angular scripts
@@ -64,6 +86,10 @@ const REGULAR_ANGULAR_BUILD_COMMAND: &str = "ng build --aot --build-optimizer --
 /// -- look at 'angular.json' for which routes will participate on the pre-rendering
 const PRE_RENDERED_UNIVERSAL_BUILD_COMMAND: &str = "npm run prerender";
 
+/// regenerates the typed Angular API client out of the OpenAPI document (both in `ANGULAR_WEB_APP_API_DIR`) -- requires Java,
+/// for `openapi-generator-cli` (fetched by `npx`)
+const API_CLIENT_GENERATION_COMMAND: &str = "npx --yes @openapitools/openapi-generator-cli generate -g typescript-angular --skip-validate-spec";
+
 /// builds the production-ready egui for web application (note that some dependencies must be pre-installed, so running this
 /// command by hand first is a good idea)
 const EGUI_WEB_BUILD_COMMAND: &str = "export RUSTFLAGS='--cfg=web_sys_unstable_apis'; ~/.cargo/bin/trunk build --release";
@@ -81,19 +107,14 @@ enum AngularBuildTypes {
     Regular,
 }
 
-/// Options for embedded files compression
-#[derive(Debug)]
-enum Compressors {
-    /// must be supported by all browsers
-    GZip,
-    /// offers ~15% better compression ratios for text, when compared to gzip -- not accepted by Firefox 94.0.1 (2021, nov, 24) when accessing via HTTP
-    Brotli,
-}
-
 fn main() {
 
     eprintln!("Running kickass-app-template custom build.rs:");
 
+    compile_grpc_protos();
+
+    generate_openapi_document();
+
     #[cfg(debug_assertions)]
     on_non_release();
 
@@ -104,6 +125,338 @@ fn main() {
     println!("cargo:rerun-if-changed=web-app/src");
     println!("cargo:rerun-if-changed=web-egui/src");
     println!("cargo:rerun-if-changed=web-stats/src");
+    println!("cargo:rerun-if-changed={}", GRPC_PROTO_DIR);
+    println!("cargo:rerun-if-changed={}", OPENAPI_ROUTES_FILE);
+}
+
+/// generates the gRPC services' code -- along with their descriptors -- into `OUT_DIR`, for `src/frontend/grpc` to include.
+/// A vendored `protoc` is used, so none needs to be installed
+fn compile_grpc_protos() {
+    let protoc = protoc_bin_vendored::protoc_bin_path()
+        .unwrap_or_else(|err| panic!("Could not find the vendored `protoc`: {}", err));
+    env::set_var("PROTOC", protoc);
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("Cargo didn't set `OUT_DIR`"));
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join(GRPC_DESCRIPTOR_FILE_NAME))
+        .compile(GRPC_PROTO_FILES, &[GRPC_PROTO_DIR])
+        .unwrap_or_else(|err| panic!("Could not compile the gRPC definitions {:?}: {}", GRPC_PROTO_FILES, err));
+}
+
+/// writes the OpenAPI document for the routes in `OPENAPI_ROUTES_FILE` into `web-app/` -- see `ANGULAR_WEB_APP_API_DIR` -- only touching it
+/// if its contents changed, so `web-app/src` isn't seen as changed by every build
+fn generate_openapi_document() {
+    let sources = fs::read_to_string(OPENAPI_ROUTES_FILE)
+        .unwrap_or_else(|err| panic!("Could not read the routes in '{}': {}", OPENAPI_ROUTES_FILE, err));
+    let document = openapi_document(&sources);
+    let document_path = Path::new(ANGULAR_WEB_APP_DIR_NAME).join(ANGULAR_WEB_APP_API_DIR).join(OPENAPI_DOCUMENT_FILE_NAME);
+    if fs::read_to_string(&document_path).ok().as_deref() != Some(document.as_str()) {
+        eprintln!("\tWriting the OpenAPI document for the routes in '{}' to '{}'", OPENAPI_ROUTES_FILE, document_path.display());
+        fs::create_dir_all(document_path.parent().unwrap()).expect("Could not create the directory for the OpenAPI document");
+        fs::write(&document_path, document).unwrap_or_else(|err| panic!("Could not write the OpenAPI document to '{}': {}", document_path.display(), err));
+    }
+}
+
+/// runs `API_CLIENT_GENERATION_COMMAND` for the Angular app in `angular_dir_name`
+fn generate_api_client(angular_dir_name: &str) {
+    let full_generation_command = format!("cd './{}' && {} -i '{}/{}' -o '{}'", angular_dir_name, API_CLIENT_GENERATION_COMMAND,
+                                          ANGULAR_WEB_APP_API_DIR, OPENAPI_DOCUMENT_FILE_NAME, ANGULAR_WEB_APP_API_DIR);
+    let shell = if cfg!(target_os = "windows") { "cmd" } else { "sh" };
+    eprintln!("\t\tRunning the API client generation ==> '{}'", full_generation_command);
+    let exit_status = Command::new(shell)
+        .args(["-c", &full_generation_command])
+        .spawn().unwrap_or_else(|err| panic!("Failed to start the API client generation command '{}': {}", full_generation_command, err))
+        .wait().unwrap();
+    if !exit_status.success() {
+        panic!("The API client generation command '{}' failed: {}", full_generation_command, exit_status);
+    }
+}
+
+/// A route of `OPENAPI_ROUTES_FILE`, as extracted from its sources
+struct ApiRoute {
+    /// `get`, `post`, ...
+    method:        String,
+    /// in OpenAPI's notation -- `/stats/history/{metric}`
+    path:          String,
+    /// the route's doc comment
+    description:   String,
+    /// the function's name
+    operation_id:  String,
+    /// name, Rust type & where it goes (`path` or `query`)
+    parameters:    Vec<(String, String, &'static str)>,
+    /// the Rust type of the JSON body, if any
+    body:          Option<String>,
+    /// the function's return type
+    response:      String,
+    /// if the `Authenticated` request guard is required
+    authenticated: bool,
+}
+
+/// builds the OpenAPI (3.0) document for the Rocket routes in `sources` -- extracted from their attributes, doc comments & signatures,
+/// as well as the serializable structs & enums defined along with them. Types defined elsewhere are documented as plain objects
+fn openapi_document(sources: &str) -> String {
+    let base_path = sources.lines()
+        .find_map(|line| line.trim().strip_prefix("pub const BASE_PATH: &str = \"").and_then(|rest| rest.split('"').next()))
+        .unwrap_or_else(|| panic!("`BASE_PATH` was not found in '{}'", OPENAPI_ROUTES_FILE));
+    let schemas = extract_api_schemas(sources);
+    let known_types: Vec<&str> = schemas.iter().map(|(name, _schema)| name.as_str()).collect();
+    let mut routes = extract_api_routes(sources);
+    routes.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut paths: Vec<(String, Vec<String>)> = vec![];
+    for route in routes {
+        let mut parameters: Vec<String> = vec![];
+        for (name, rust_type, location) in &route.parameters {
+            parameters.push(format!(r#"{{"name":{},"in":"{}","required":{},"schema":{}}}"#,
+                                    json_string(name), location, *location == "path" || generic_argument(rust_type, "Option").is_none(), json_schema(rust_type, &known_types)));
+        }
+        let mut operation = vec![
+            format!(r#""operationId":{}"#, json_string(&route.operation_id)),
+            format!(r#""description":{}"#, json_string(&route.description)),
+        ];
+        if !parameters.is_empty() {
+            operation.push(format!(r#""parameters":[{}]"#, parameters.join(",")));
+        }
+        if let Some(body) = &route.body {
+            operation.push(format!(r#""requestBody":{{"required":true,"content":{{"application/json":{{"schema":{}}}}}}}"#, json_schema(body, &known_types)));
+        }
+        operation.push(format!(r#""responses":{{"200":{}}}"#, json_response(&route.response, &known_types)));
+        if route.authenticated {
+            operation.push(String::from(r#""security":[{"apiKey":[]},{"bearer":[]},{"basic":[]}]"#));
+        }
+        let operation = format!("      \"{}\": {{{}}}", route.method, operation.join(","));
+        let path = format!("{}{}", base_path, route.path);
+        match paths.last_mut() {
+            Some((last_path, operations)) if *last_path == path => operations.push(operation),
+            _ => paths.push((path, vec![operation])),
+        }
+    }
+    let paths: Vec<String> = paths.into_iter()
+        .map(|(path, operations)| format!("    {}: {{\n{}\n    }}", json_string(&path), operations.join(",\n")))
+        .collect();
+    let schemas: Vec<String> = schemas.into_iter()
+        .map(|(name, schema)| format!("      {}: {}", json_string(&name), schema))
+        .collect();
+    format!(r#"{{
+  "openapi": "3.0.3",
+  "info": {{"title": {}, "version": {}}},
+  "paths": {{
+{}
+  }},
+  "components": {{
+    "schemas": {{
+{}
+    }},
+    "securitySchemes": {{
+      "apiKey": {{"type": "apiKey", "in": "header", "name": "X-API-Key"}},
+      "bearer": {{"type": "http", "scheme": "bearer"}},
+      "basic": {{"type": "http", "scheme": "basic"}}
+    }}
+  }}
+}}
+"#, json_string(&format!("{} API", env::var("CARGO_PKG_NAME").unwrap_or_default())), json_string(&env::var("CARGO_PKG_VERSION").unwrap_or_default()),
+        paths.join(",\n"), schemas.join(",\n"))
+}
+
+/// the routes in `sources` -- functions annotated with `#[get(...)]`, `#[post(...)]`, ...
+fn extract_api_routes(sources: &str) -> Vec<ApiRoute> {
+    let mut routes = vec![];
+    let mut docs: Vec<&str> = vec![];
+    let mut lines = sources.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+            continue
+        }
+        let Some((method, attribute)) = ["get", "post", "put", "patch", "delete"].iter()
+            .find_map(|method| line.strip_prefix(&format!("#[{}(", method)).map(|attribute| (method.to_string(), attribute))) else {
+            if !line.starts_with("#[") {
+                docs.clear();
+            }
+            continue
+        };
+        // the function's signature -- up to its body
+        let mut signature = String::new();
+        for line in lines.by_ref() {
+            signature.push_str(line.trim());
+            signature.push(' ');
+            if line.trim_end().ends_with('{') {
+                break
+            }
+        }
+        let uri = attribute.split('"').nth(1).unwrap_or_else(|| panic!("Route without an URI in '{}': {}", OPENAPI_ROUTES_FILE, line));
+        let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+        let data = attribute.split_once("data = \"<").and_then(|(_before, rest)| rest.split('>').next());
+        let arguments = signature_arguments(&signature);
+        let argument_type = |name: &str| arguments.iter()
+            .find(|(argument, _rust_type)| argument == name)
+            .map(|(_argument, rust_type)| rust_type.clone())
+            .unwrap_or_else(|| panic!("The parameter `{}` of the route '{}' was not found in its signature: {}", name, uri, signature));
+        let dynamic = |segment: &str| segment.strip_prefix('<').and_then(|segment| segment.strip_suffix('>')).map(|name| name.trim_end_matches("..").to_string());
+        let mut parameters = vec![];
+        let path = path.split('/')
+            .map(|segment| match dynamic(segment) {
+                Some(name) => {
+                    parameters.push((name.clone(), argument_type(&name), "path"));
+                    format!("{{{}}}", name)
+                },
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        for name in query.split('&').filter_map(dynamic) {
+            parameters.push((name.clone(), argument_type(&name), "query"));
+        }
+        let operation_id = signature.split("fn ").nth(1).and_then(|rest| rest.split(['(', '<']).next()).unwrap_or_default().trim().to_string();
+        routes.push(ApiRoute {
+            method,
+            path,
+            description:   docs.join(" ").replace("\\ ", " "),
+            operation_id,
+            parameters,
+            body:          data.map(argument_type),
+            response:      signature.split_once("->").map_or("", |(_arguments, response)| response).trim().trim_end_matches('{').trim().to_string(),
+            authenticated: arguments.iter().any(|(_argument, rust_type)| rust_type == "Authenticated"),
+        });
+        docs.clear();
+    }
+    routes
+}
+
+/// the arguments of the function in `signature` -- their names & Rust types
+fn signature_arguments(signature: &str) -> Vec<(String, String)> {
+    let Some(start) = signature.find('(') else { return vec![] };
+    let mut arguments = vec![];
+    let mut depth = 0;
+    let mut argument = String::new();
+    for c in signature[start+1..].chars() {
+        match c {
+            '(' | '<' | '[' => depth += 1,
+            ')' | '>' | ']' if depth > 0 => depth -= 1,
+            ')' => break,
+            ',' if depth == 0 => {
+                arguments.push(std::mem::take(&mut argument));
+                continue
+            },
+            _ => (),
+        }
+        argument.push(c);
+    }
+    arguments.push(argument);
+    arguments.into_iter()
+        .filter_map(|argument| argument.split_once(':').map(|(name, rust_type)| (name.trim().trim_start_matches("mut ").to_string(), rust_type.trim().to_string())))
+        .collect()
+}
+
+/// the schemas of the serializable structs (with named fields) & unit enums of `sources` -- by name
+fn extract_api_schemas(sources: &str) -> Vec<(String, String)> {
+    let mut schemas = vec![];
+    let mut serializable = false;
+    let mut lines = sources.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.starts_with("#[derive(") {
+            serializable = ["Serialize", "Deserialize", "FromFormField"].iter().any(|derive| line.contains(derive));
+            continue
+        }
+        if line.starts_with("#[") || line.starts_with("///") {
+            continue
+        }
+        let declaration = line.trim_start_matches("pub ");
+        let kind = if declaration.starts_with("struct ") { "struct" } else if declaration.starts_with("enum ") { "enum" } else { "" };
+        if !serializable || kind.is_empty() || !declaration.ends_with('{') {
+            serializable = false;
+            continue
+        }
+        serializable = false;
+        let name = declaration[kind.len()..].trim_end_matches('{').trim().to_string();
+        let members: Vec<String> = lines.by_ref()
+            .map(|line| line.split("//").next().unwrap_or_default().trim().trim_end_matches(',').trim_start_matches("pub ").to_string())
+            .take_while(|member| member != "}")
+            .filter(|member| !member.is_empty() && !member.starts_with("#["))
+            .collect();
+        let schema = if kind == "enum" {
+            format!(r#"{{"type":"string","enum":[{}]}}"#, members.iter().map(|variant| json_string(variant)).collect::<Vec<_>>().join(","))
+        } else {
+            let fields: Vec<(String, String)> = members.iter()
+                .filter_map(|member| member.split_once(':').map(|(field, rust_type)| (field.trim().to_string(), rust_type.trim().to_string())))
+                .collect();
+            let required: Vec<String> = fields.iter()
+                .filter(|(_field, rust_type)| generic_argument(rust_type, "Option").is_none())
+                .map(|(field, _rust_type)| json_string(field))
+                .collect();
+            let properties: Vec<String> = fields.iter()
+                .map(|(field, rust_type)| format!("{}:{}", json_string(field), json_schema(rust_type, &[])))
+                .collect();
+            format!(r#"{{"type":"object","required":[{}],"properties":{{{}}}}}"#, required.join(","), properties.join(","))
+        };
+        schemas.push((name, schema));
+    }
+    schemas
+}
+
+/// the OpenAPI response for a route returning `rust_type` -- JSON, Server-Sent Events, text or nothing
+fn json_response(rust_type: &str, known_types: &[&str]) -> String {
+    if let Some(json) = rust_type.find("Json<").map(|start| &rust_type[start..]) {
+        // the `Json<...>` -- up to its matching '>'
+        let mut depth = 0;
+        let end = json.char_indices()
+            .find_map(|(index, c)| match c {
+                '<' => { depth += 1; None },
+                '>' => { depth -= 1; if depth == 0 { Some(index) } else { None } },
+                _ => None,
+            })
+            .unwrap_or(json.len() - 1);
+        format!(r#"{{"description":"OK","content":{{"application/json":{{"schema":{}}}}}}}"#, json_schema(&json[..=end], known_types))
+    } else if rust_type.ends_with("Json") {
+        String::from(r#"{"description":"OK","content":{"application/json":{"schema":{"type":"object"}}}}"#)
+    } else if rust_type.contains("EventStream") {
+        String::from(r#"{"description":"OK","content":{"text/event-stream":{"schema":{"type":"string"}}}}"#)
+    } else if rust_type.contains("String") {
+        String::from(r#"{"description":"OK","content":{"text/plain":{"schema":{"type":"string"}}}}"#)
+    } else {
+        String::from(r#"{"description":"OK"}"#)
+    }
+}
+
+/// the JSON schema for the Rust type `rust_type` -- as far as can be told by its name: the `known_types` are referred to in the
+/// document's components, while other types are documented as plain objects
+fn json_schema(rust_type: &str, known_types: &[&str]) -> String {
+    let rust_type = rust_type.trim().trim_start_matches('&').trim();
+    if let Some(inner) = generic_argument(rust_type, "Option").or_else(|| generic_argument(rust_type, "Json")) {
+        return json_schema(inner, known_types)
+    }
+    if let Some(inner) = generic_argument(rust_type, "Vec") {
+        return format!(r#"{{"type":"array","items":{}}}"#, json_schema(inner, known_types))
+    }
+    match rust_type {
+        "str" | "String" => String::from(r#"{"type":"string"}"#),
+        "bool" => String::from(r#"{"type":"boolean"}"#),
+        "f32" | "f64" => String::from(r#"{"type":"number"}"#),
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" => String::from(r#"{"type":"integer"}"#),
+        known if known_types.contains(&known) => format!(r##"{{"$ref":"#/components/schemas/{}"}}"##, known),
+        _ => String::from(r#"{"type":"object"}"#),
+    }
+}
+
+/// the argument of `rust_type`, if it is the `generic` type -- `generic_argument("Option<u32>", "Option") == Some("u32")`
+fn generic_argument<'a>(rust_type: &'a str, generic: &str) -> Option<&'a str> {
+    rust_type.strip_prefix(generic)?.trim_start().strip_prefix('<')?.strip_suffix('>').map(str::trim)
+}
+
+/// `text` as a JSON string literal
+fn json_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"'  => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
 }
 
 fn on_non_release() {
@@ -148,6 +501,10 @@ fn on_release() {
             });
     };
 
+    // typed API client -- before building the app using it
+    eprintln!("Regenerating web-app's typed API client:");
+    generate_api_client(ANGULAR_WEB_APP_DIR_NAME);
+
     // angular apps
     eprintln!("Processing Angular apps:");
     for (angular_dir, angular_app_name, build_type, root_index_html_rename) in [
@@ -271,7 +628,7 @@ fn load_dist_files(dist_path: &str, root_index_html_rename: &str, ignored_files:
     let mut current_dir = env::current_dir().unwrap();
     current_dir = current_dir.join(dist_path);
     let root_dir = PathBuf::from(&current_dir);
-    eprintln!("\tIncorporating all files from '{:?}' into the executable -- and compressing them with {:?}", root_dir, COMPRESSOR);
+    eprintln!("\tIncorporating all files from '{:?}' into the executable -- and compressing them with gzip & brotli", root_dir);
     WalkDir::new(current_dir)
         .into_iter()
         .filter_entry(|entry| entry
@@ -300,8 +657,9 @@ fn load_dist_files(dist_path: &str, root_index_html_rename: &str, ignored_files:
     files_contents
 }
 
-/// saves (possibly compressing) 'static_files' into a const hash map for use by the web server & application when
-/// clients request them. Additionally, defines some constants related to compression & optimizing the browser's cache.\
+/// saves 'static_files' -- along with their gzip & brotli variants, when worth it -- into a const hash map for use by the web server
+/// & application when clients request them: the variant to send is negotiated with each client (see `src/frontend/web/files.rs`).
+/// Additionally, defines some constants related to optimizing the browser's cache.\
 /// 'file_links' refers to 'static_files' in the form {link_name = real_file_name, ...}\
 fn save_static_files(static_files: HashMap<String, Vec<u8>>, file_links: HashMap<String, String>) {
     const CACHE_MAX_AGE_SECONDS:       u64 = 3600 * 24 * 365;
@@ -323,6 +681,7 @@ fn save_static_files(static_files: HashMap<String, Vec<u8>>, file_links: HashMap
 
     let file_header = r#"
 // Auto-generated by build.rs. See there for docs.
+// Included by `src/frontend/web/embedded_files.rs`, where `EmbeddedFileData` is defined.
 
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
@@ -330,7 +689,7 @@ use once_cell::sync::Lazy;
 "#;
 
     let hash_map_header = r#"
-pub static STATIC_FILES: Lazy<HashMap<&'static str, (/*compressed*/bool, /*contents*/&'static [u8])>> = Lazy::new(|| {
+pub static STATIC_FILES: Lazy<HashMap<&'static str, &'static EmbeddedFileData>> = Lazy::new(|| {
     let mut m = HashMap::new();"#;
 
     let function_and_file_footers = r#"
@@ -342,25 +701,20 @@ pub static STATIC_FILES: Lazy<HashMap<&'static str, (/*compressed*/bool, /*conte
 
     // file constants
     for (file_name, file_contents) in &static_files {
-        let compressed_bytes = compress(&file_name, &file_contents);
-        if compressed_bytes.len() + COMPRESSION_THRESHOLD < file_contents.len() {
-            // serve it compressed (text)
-            writer.write(word_wrap(format!("\n// \"{}\": {} compressed / {} plain ==> compressed to {:.2}% of the original\n\
-                                       static {}: (bool, &[u8]) = (true, &{:?});\n",
-                                 file_name, compressed_bytes.len(), file_contents.len(), (compressed_bytes.len() as f64 / file_contents.len() as f64) * 100.0,
-                                 file_name_as_token(file_name), compressed_bytes.as_slice())).as_bytes() ).unwrap();
-        } else {
-            // serve it plain (images, videos, ...)
-            writer.write(word_wrap(format!("\n// \"{}\": {} compressed / {} plain ==> would be {:.2}% of the original\n\
-                                         static {}: (bool, &[u8]) = (false, &{:?});\n",
-                                 file_name, compressed_bytes.len(), file_contents.len(), (compressed_bytes.len() as f64 / file_contents.len() as f64) * 100.0,
-                                 file_name_as_token(file_name), file_contents.as_slice())).as_bytes() ).unwrap();
-        }
+        // compressed variants are only kept if worth it -- not for images, videos, ...
+        let worth_it = |compressed_bytes: Vec<u8>| Some(compressed_bytes).filter(|compressed_bytes| compressed_bytes.len() + COMPRESSION_THRESHOLD < file_contents.len());
+        let gzip_bytes = worth_it(gzip_compress(&file_name, &file_contents));
+        let brotli_bytes = worth_it(brotli_compress(&file_name, &file_contents));
+        let ratio = |compressed_bytes: &Option<Vec<u8>>| compressed_bytes.as_ref()
+            .map_or("not worth it".to_string(), |compressed_bytes| format!("{} ({:.2}% of the original)", compressed_bytes.len(), (compressed_bytes.len() as f64 / file_contents.len() as f64) * 100.0));
+        let variant = |compressed_bytes: &Option<Vec<u8>>| compressed_bytes.as_ref()
+            .map_or("None".to_string(), |compressed_bytes| format!("Some(&{:?})", compressed_bytes.as_slice()));
+        writer.write_all(word_wrap(format!("\n// \"{}\": {} plain / gzip: {} / brotli: {}\n\
+                                       static {}: EmbeddedFileData = EmbeddedFileData {{ digest: {:?}, identity: &{:?}, gzip: {}, brotli: {} }};\n",
+                             file_name, file_contents.len(), ratio(&gzip_bytes), ratio(&brotli_bytes),
+                             file_name_as_token(file_name), digest(&file_contents), file_contents.as_slice(), variant(&gzip_bytes), variant(&brotli_bytes))).as_bytes() ).unwrap();
     }
 
-    // Content-Encoding (compressor) constant
-    writer.write(format!("\npub const CONTENT_ENCODING: &str = \"{}\";\n", compressor_http_header()).as_bytes()).unwrap();
-
     // date constants
     let now_time: DateTime<Utc> = Utc::now();
     let expiration_time = DateTime::<Utc>::from(SystemTime::from(now_time).add(Duration::from_secs(EXPIRATION_DURATION_SECONDS)));
@@ -377,17 +731,26 @@ pub static STATIC_FILES: Lazy<HashMap<&'static str, (/*compressed*/bool, /*conte
     // contents (hash map)
     writer.write("    // links\n".as_bytes() ).unwrap();
     for (link_name, real_file_name) in &file_links {
-        writer.write(format!("    m.insert(\"{}\", {});\n", link_name, file_name_as_token(real_file_name)).as_bytes() ).unwrap();
+        writer.write_all(format!("    m.insert(\"{}\", &{});\n", link_name, file_name_as_token(real_file_name)).as_bytes() ).unwrap();
     }
     writer.write("    // files\n".as_bytes() ).unwrap();
     for (file_name, _file_contents) in &static_files {
-        writer.write(format!("    m.insert(\"{}\", {});\n", file_name, file_name_as_token(file_name)).as_bytes() ).unwrap();
+        writer.write_all(format!("    m.insert(\"{}\", &{});\n", file_name, file_name_as_token(file_name)).as_bytes() ).unwrap();
     }
 
     // footer
     writer.write(function_and_file_footers.as_bytes() ).unwrap();
 }
 
+/// the digest of a file with the given contents -- from which the `ETag`s of its variants are built -- so they only change when
+/// the contents do, surviving rebuilds & being the same for all instances of the application
+fn digest(file_contents: &[u8]) -> String {
+    let digest = Sha256::digest(file_contents);
+    digest[..16].iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 /// nastily guarantees we won't end up with unreasonably big lines
 /// (by splitting them at spaces) -- in order not to break file editors
 fn word_wrap(mut chunk: String) -> String {
@@ -405,22 +768,6 @@ fn word_wrap(mut chunk: String) -> String {
     chunk
 }
 
-/// façade for compressors -- compress the given data respecting the global configs
-fn compress(file_name: &String, file_content: &Vec<u8>) -> Vec<u8> {
-    match COMPRESSOR {
-        Compressors::GZip => gzip_compress(&file_name, &file_content),
-        Compressors::Brotli => brotli_compress(&file_name, &file_content),
-    }
-}
-
-/// returns the corresponding 'Content-Encoding' HTTP header value for the chosen 'COMPRESSOR'
-fn compressor_http_header() -> &'static str {
-    match COMPRESSOR {
-        Compressors::GZip => "gzip",
-        Compressors::Brotli => "br",
-    }
-}
-
 use flate2::{
     Compression,
     write::GzEncoder,
@@ -434,7 +781,7 @@ fn gzip_compress(file_name: &String, file_content: &Vec<u8>) -> Vec<u8> {
 }
 
 
-/// equivalent of 'brotli -q 11 -w 24'
+/// equivalent of 'brotli -q 11 -w 24' -- ~15% better compression ratios for text, when compared to gzip, but only accepted by browsers over HTTPS
 fn brotli_compress(_file_name: &String, file_content: &Vec<u8>) -> Vec<u8> {
     let mut brotlied_bytes = Vec::new();
     let mut brotli = brotli::CompressorWriter::new(&mut brotlied_bytes, 4096, 11, 24);
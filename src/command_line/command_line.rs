@@ -1,6 +1,7 @@
 //! See [super]
 
 use crate::config::*;
+use std::collections::BTreeMap;
 use structopt::{StructOpt};
 
 
@@ -15,14 +16,22 @@ pub fn merge_config_file_and_command_line_options(app_config_from_file: Config,
         println!("'{}' Command Line options: {:#?}", APP_NAME, command_line_options);
         println!("'{}' Config file options: {:#?}", APP_NAME, app_config_from_file);
     }
-    let app_config_from_command_line = config_from_command_line_options(&command_line_options);
-    let effective_config = config_ops::merge_configs(app_config_from_file, app_config_from_command_line);
+    let effective_config = effective_config(app_config_from_file, &command_line_options);
     if DEBUG {
         println!("'{}' Effective config: {:#?}", APP_NAME, effective_config);
     }
     effective_config
 }
 
+/// the config in effect: `app_config_from_file` overridden by `command_line_options` -- panicking if they are inconsistent
+pub fn effective_config(app_config_from_file: Config, command_line_options: &CommandLineOptions) -> Config {
+    let app_config_from_command_line = config_from_command_line_options(command_line_options);
+    let mut effective_config = config_ops::merge_configs(app_config_from_file, app_config_from_command_line);
+    override_service_ports(&mut effective_config, command_line_options);
+    override_log_format(&mut effective_config, command_line_options);
+    effective_config
+}
+
 /// Command-line options
 #[derive(Debug,StructOpt)]
 #[structopt(about = "
@@ -47,6 +56,46 @@ pub struct CommandLineOptions {
     #[structopt(long)]
     log_to_file: Option<String>,
 
+    /// Overrides the format of the console logs: `plain` or `pretty` -- colored & compact, for local development
+    #[structopt(long)]
+    log_format: Option<LogFormat>,
+
+    /// Forks `console daemon` into the background, writing its PID to the PID file set in the config file (Unix only)
+    #[structopt(long)]
+    pub daemonize: bool,
+
+    /// Overrides the web server's port -- requires its `rocket_config` to be `Provided` in the config file
+    #[structopt(long)]
+    http_port: Option<u16>,
+
+    /// Overrides the socket server's port
+    #[structopt(long)]
+    socket_port: Option<u16>,
+
+    /// Overrides the gRPC service's port
+    #[structopt(long)]
+    grpc_port: Option<u16>,
+
+    /// Doesn't start the web server, even if enabled in the config file
+    #[structopt(long)]
+    disable_web: bool,
+
+    /// Doesn't start the socket server, even if enabled in the config file
+    #[structopt(long)]
+    disable_socket_server: bool,
+
+    /// Doesn't start the socket client, even if enabled in the config file
+    #[structopt(long)]
+    disable_socket_client: bool,
+
+    /// Doesn't start the Telegram service, even if enabled in the config file
+    #[structopt(long)]
+    disable_telegram: bool,
+
+    /// Doesn't start the gRPC service, even if enabled in the config file
+    #[structopt(long)]
+    disable_grpc: bool,
+
     /// Which UI to use to run the application
     #[structopt(subcommand)]
     pub runner: UiOptions,
@@ -60,24 +109,110 @@ pub struct CommandLineOptions {
 
 }
 
+/// The console logs' formats -- see `--log-format`
+#[derive(Debug,PartialEq,Clone,Copy)]
+pub enum LogFormat {
+    /// [LoggingOptions::ToConsole]
+    Plain,
+    /// [LoggingOptions::ToConsolePretty]
+    Pretty,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(log_format: &str) -> Result<Self, Self::Err> {
+        match log_format {
+            "plain"  => Ok(LogFormat::Plain),
+            "pretty" => Ok(LogFormat::Pretty),
+            unknown => Err(format!("Unknown log format '{}' -- known ones are: plain & pretty", unknown)),
+        }
+    }
+}
+
 /// Creates the application-wide `Config` out of the command-line options
-/// -- even if the config is incomplete.
-fn config_from_command_line_options(command_line_options: &CommandLineOptions) -> Config {
+/// -- even if the config is incomplete. Services may only be disabled here -- their ports are overridden by [override_service_ports()]
+pub fn config_from_command_line_options(command_line_options: &CommandLineOptions) -> Config {
+    fn disabled_if<T>(disabled: bool) -> ExtendedOption<T> {
+        if disabled { ExtendedOption::Disabled } else { ExtendedOption::Unset }
+    }
     Config {
         log: if let Some(file_path) = &command_line_options.log_to_file {
                  LoggingOptions::ToFile {
                      file_path:        file_path.to_string(),
                      rotation_size:    0,
                      rotations_kept:   0,
-                     compress_rotated: false,
+                     compress_rotated: LogCompression::None,
                  }
              } else if command_line_options.quiet {
                  LoggingOptions::Quiet
              } else {
                  LoggingOptions::ToConsole
              },
-        services: ExtendedOption::Unset,
+        log_filters: BTreeMap::new(),
+        log_redactions: vec![],
+        log_sampling: ExtendedOption::Unset,
+        access_log: ExtendedOption::Unset,
+        services: ExtendedOption::Enabled(ServicesConfig {
+            web:           disabled_if(command_line_options.disable_web),
+            socket_server: disabled_if(command_line_options.disable_socket_server),
+            socket_client: disabled_if(command_line_options.disable_socket_client),
+            telegram:      disabled_if(command_line_options.disable_telegram),
+            grpc:          disabled_if(command_line_options.disable_grpc),
+        }),
         tokio_threads: -1,
-        ui: ExtendedOption::Enabled(command_line_options.runner),
+        timezone: None,
+        leader_election: ExtendedOption::Unset,
+        locks: ExtendedOption::Unset,
+        degrade: ExtendedOption::Unset,
+        webhooks: ExtendedOption::Unset,
+        auth: ExtendedOption::Unset,
+        job_queue: ExtendedOption::Unset,
+        metrics_history: ExtendedOption::Unset,
+        run_as: ExtendedOption::Unset,
+        hot_reload: ExtendedOption::Unset,
+        scheduler: ExtendedOption::Unset,
+        daemon: ExtendedOption::Unset,
+        storage: ExtendedOption::Unset,
+        outbox: ExtendedOption::Unset,
+        supervisor: ExtendedOption::Unset,
+        telemetry: ExtendedOption::Unset,
+        self_monitor: ExtendedOption::Unset,
+        ui: ExtendedOption::Enabled(command_line_options.runner.clone()),
+    }
+}
+
+/// applies the `--http-port`, `--socket-port` & `--grpc-port` overrides to the merged `config` -- which must have the corresponding services enabled
+fn override_service_ports(config: &mut Config, command_line_options: &CommandLineOptions) {
+    if let Some(port) = command_line_options.http_port {
+        match &mut config.services.web {
+            ExtendedOption::Enabled(WebConfig { rocket_config: RocketConfigOptions::Provided { http_port, .. }, .. }) => *http_port = port,
+            ExtendedOption::Enabled(_) => panic!("`--http-port` requires the web service's `rocket_config` to be `Provided` in the config file -- rather than using `Rocket.toml`"),
+            _ => panic!("`--http-port` was given, but the web service is not enabled"),
+        }
+    }
+    if let Some(port) = command_line_options.socket_port {
+        match &mut config.services.socket_server {
+            ExtendedOption::Enabled(socket_server) => socket_server.port = port,
+            _ => panic!("`--socket-port` was given, but the socket server is not enabled"),
+        }
+    }
+    if let Some(port) = command_line_options.grpc_port {
+        match &mut config.services.grpc {
+            ExtendedOption::Enabled(grpc) => grpc.port = port,
+            _ => panic!("`--grpc-port` was given, but the gRPC service is not enabled"),
+        }
+    }
+}
+
+/// applies the `--log-format` override to the merged `config` -- which must be logging to the console
+fn override_log_format(config: &mut Config, command_line_options: &CommandLineOptions) {
+    let Some(log_format) = command_line_options.log_format else { return };
+    match (&config.log, log_format) {
+        (LoggingOptions::ToConsole | LoggingOptions::ToConsolePretty { .. }, LogFormat::Plain) => config.log = LoggingOptions::ToConsole,
+        (LoggingOptions::ToConsole, LogFormat::Pretty) => config.log = LoggingOptions::ToConsolePretty { shorten_modules: true, align_key_values: false },
+        // the pretty options set in the config file are kept
+        (LoggingOptions::ToConsolePretty { .. }, LogFormat::Pretty) => (),
+        _ => panic!("`--log-format` was given, but the logs are not sent to the console -- drop `--quiet` & `--log-to-file`"),
     }
 }
@@ -3,16 +3,23 @@
 //! -- the file will be placed along the `${0}.config.ron` config file to
 //! serve as it's documentation
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+};
 use serde::{Serialize, Deserialize};
 use structopt::{StructOpt};
+use sloggers::types::Severity;
 
 
 /// CONFIG FILE DOCUMENTATION
 /// (feel free to move comments & possible values close to the data)
 ///
+/// Strings may contain `${ENV_VAR}` placeholders -- expanded with the environment variables when the file is loaded, so the
+/// same file may serve several environments. Missing variables are errors. Use `$${` for a literal `${`.
+///
 /// Root for this Application's config
-#[derive(Debug,PartialEq,Serialize,Deserialize)]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
 pub struct Config {
 
     // kickass-app-template
@@ -20,12 +27,86 @@ pub struct Config {
 
     /// Specifies what the application should do with it's log messages
     pub log: LoggingOptions,
+    /// Per-target log level overrides, so noisy components may be silenced (or chatty ones investigated) without losing the app-level logs.
+    /// Keys match whole `::` segments of the log target or module path: our services may be addressed by "web", "telegram", "socket_server"
+    /// & "logic", while the frameworks by "rocket", "teloxide", ... Levels are: trace, debug, info, warning, error & critical.
+    /// The "*" key sets the level for all the other targets -- replacing the app-wide default (debug on debug builds, info otherwise).
+    /// Example: {"socket_server": trace, "rocket": warning, "*": error}
+    /// (levels more verbose than the ones compiled in -- see the `log` features in Cargo.toml -- have no effect)
+    pub log_filters: BTreeMap<String, Severity>,
+    /// Regular expressions for sensitive data to be masked out of all log messages (on every drain) -- if a match has a
+    /// group named `secret`, only that group is masked, otherwise the whole match is. The Telegram token, notification
+    /// chat ids & webhook secrets are always masked, even if they don't match any of these.
+    /// Defaults cover Telegram-like bot tokens, e-mails & `api_key=...`, `password: ...`-like values
+    pub log_redactions: Vec<String>,
+    /// Log sampling & burst suppression -- protects the disk (and the readability of the logs) during floods
+    pub log_sampling: ExtendedOption<LogSamplingConfig>,
+    /// Dedicated log for the accesses to our services -- HTTP requests, socket connections & disconnections and Telegram commands --
+    /// in a combined-log-like format, so ops tooling may ingest them apart from the application's log
+    pub access_log: ExtendedOption<AccessLogConfig>,
     /// Services (and their configs) to be enabled
     pub services: ExtendedOption<ServicesConfig>,
     /// The number of threads to dedicate to Tokio -- if not 1, make it no greater than the number of CPUs,
     /// unless you (wrongly) are waiting on Tokio threads.
     /// Set it to 0 to use all available CPUs the process has access to
     pub tokio_threads: i16,
+    /// The IANA timezone (like "America/Sao_Paulo" or "UTC") of the timestamps in the logs & reports and in which the scheduler evaluates
+    /// its cron expressions -- DST transitions included. `None` keeps the system's local timezone. Not hot reloadable
+    pub timezone: Option<String>,
+    /// For deployments running several instances of this application: elects one of them as the leader, so
+    /// scheduled jobs & singleton tasks (like the "daemon" job's demo pipeline) run on exactly one instance
+    pub leader_election: ExtendedOption<LeaderElectionConfig>,
+    /// Backend for the locks offered to the business logic -- like the ones serializing the runs of the "demo" scheduled job. See [crate::runtime::Locks].
+    /// Disabled by default. Example: `locks: Enabled(Local)`
+    pub locks: ExtendedOption<LocksBackend>,
+    /// Graceful degradation switches, so operators may shed load or silence integrations during incidents.
+    /// Their initial state is set here, but they may be toggled at runtime
+    pub degrade: ExtendedOption<DegradeConfig>,
+    /// Outbound webhooks: application events POSTed (as HMAC signed JSON) to external integrations -- see [crate::runtime::Webhooks]
+    pub webhooks: ExtendedOption<WebhooksConfig>,
+    /// Identity management for the protected web routes, the Telegram operator commands, ... -- see [crate::runtime::AuthProvider].
+    /// When disabled, the protected web routes are open & Telegram operators are the chats receiving the notifications
+    pub auth: ExtendedOption<AuthConfig>,
+    /// Durable job queue, consumed by a pool of workers with retries & dead-lettering -- see [crate::runtime::JobQueue]
+    pub job_queue: ExtendedOption<JobQueueConfig>,
+    /// In-process history of key metrics (socket & web cache counters, job queue backlog, ...) -- so recent trends may be charted without
+    /// deploying Prometheus. See [crate::runtime::MetricsHistory].\
+    /// Disabled by default. Example, for 24h at 10s: `metrics_history: Enabled((resolution_seconds: 10, retention_seconds: 86400))`
+    pub metrics_history: ExtendedOption<MetricsHistoryConfig>,
+    /// Unix only: the user (& group) to switch to once the services have bound their ports -- so privileged ports (like 80 & 443)
+    /// may be bound as root while everything else runs unprivileged. See [crate::runtime::drop_privileges()]
+    pub run_as: ExtendedOption<RunAsConfig>,
+    /// Config hot reload: the config file is watched & its changes are applied without restarting -- for the settings that support it
+    /// (the log filters, Telegram's notification chat ids & the socket server's abuse limits). Other changes are only applied on restart.
+    /// See [crate::runtime::ConfigReload]. Disabled by default. Example: `hot_reload: Enabled((poll_interval_millis: 2000))`
+    pub hot_reload: ExtendedOption<HotReloadConfig>,
+    /// Jobs ran periodically -- by cron expressions or fixed intervals -- on the Tokio runtime. Their code is registered by the business
+    /// logic, under the names given here. See [crate::scheduler::Scheduler]
+    pub scheduler: ExtendedOption<SchedulerConfig>,
+    /// Background mode, for `console daemon --daemonize` (Unix only): the PID file written -- & locked, so other instances refuse to start.
+    /// If disabled -- the default -- `--daemonize` is refused. See [crate::runtime::daemonize()].
+    /// Example: `daemon: Enabled((pid_file: "kickass-app-template.pid"))`
+    pub daemon: ExtendedOption<DaemonConfig>,
+    /// Embedded key-value store for the business logic's persistent state -- snapshotted to a file. See [crate::runtime::Storage].\
+    /// Disabled by default. Example: `storage: Enabled((file_path: "kickass-app-template.storage.ron", autosave_interval_seconds: 60))`
+    pub storage: ExtendedOption<StorageConfig>,
+    /// At-least-once delivery of the business logic's Telegram messages & webhook events: intents persisted to the `storage` (which must be
+    /// enabled) & dispatched in the background, with retries & idempotency keys -- failures being requeued through the admin API.
+    /// See [crate::runtime::Outbox]
+    pub outbox: ExtendedOption<OutboxConfig>,
+    /// Restarts of the services (Telegram, web, socket server & client and gRPC) when they end -- with an exponential backoff between them &
+    /// a Telegram alert on crash loops. If disabled -- the default -- services ending with failure stay down. See [crate::runtime::Supervisor].\
+    /// Example: `supervisor: Enabled((default_restart_policy: OnFailure, restart_policies: {}, initial_backoff_millis: 1000, max_backoff_millis: 60000,
+    /// crash_loop_restarts: 5, crash_loop_window_seconds: 600))`
+    pub supervisor: ExtendedOption<SupervisorConfig>,
+    /// Distributed tracing: the spans of the socket messages' processing, the web requests, the Telegram updates & the scheduled jobs are
+    /// exported to an OpenTelemetry collector (Jaeger, Tempo, ...) through OTLP. See [crate::logging::telemetry]
+    pub telemetry: ExtendedOption<TelemetryConfig>,
+    /// Self-monitoring of the process' resident memory, CPU usage & Tokio's scheduling delay -- warning (& acting, if so configured) when
+    /// they stay above their limits. The latest samples are exposed on `/api/stats/self-monitor`. See [crate::runtime::SelfMonitor].\
+    /// Disabled by default. Example: `self_monitor: Enabled((sampling_interval_seconds: 10, max_resident_memory_mib: 1024, max_cpu_percent: 90.0,
+    /// max_scheduling_delay_millis: 100, breached_samples_to_act: 6, on_breach: LogOnly))`
+    pub self_monitor: ExtendedOption<SelfMonitorConfig>,
 
     // business logic
     /////////////////
@@ -37,7 +118,7 @@ pub struct Config {
 }
 
 /// UI options -- how the application will interact with users
-#[derive(Debug,PartialEq,Clone,Copy,Serialize,Deserialize,StructOpt)]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize,StructOpt)]
 pub enum UiOptions {
     // /// Collects running environment data to determine the best possible Ui to use:
     // /// if DISPLAY env is available, `Egui` is used, otherwise use `Terminal` if
@@ -45,8 +126,9 @@ pub enum UiOptions {
     // Automatic,
     /// Runs the application's console UI -- run `${0} console --help` for more details
     Console(Jobs),
-    /// Runs the application's Terminal UI
-    Terminal,
+    /// Runs the application's Terminal UI -- or attaches it to a running instance, with `--connect`.
+    /// Example: `${0} terminal --connect ws://10.0.0.1:8000 --admin-token <token>`
+    Terminal(TerminalOptions),
     /// Runs the application's EGui UI
     Egui,
 }
@@ -55,12 +137,25 @@ pub enum UiOptions {
 pub struct ServicesConfig {
     pub web:           ExtendedOption<WebConfig>,
     pub socket_server: ExtendedOption<SocketServerConfig>,
-    pub telegram:      ExtendedOption<TelegramConfig>,
+    /// Outgoing connection to the socket server of another instance -- see [crate::frontend::socket_client::SocketClient]
+    pub socket_client: ExtendedOption<SocketClientConfig>,
+    /// The Telegram bots -- each running its own dispatcher. The first one is the primary: it sends the [crate::frontend::telegram::TelegramNotifier]'s
+    /// push messages & the status reports. The others are addressable by name -- see [crate::runtime::Runtime::do_for_telegram_ui()].\
+    /// A single bot, not in a list -- as in the config files predating multiple bots -- is also accepted: it becomes the only one
+    #[serde(deserialize_with = "super::config_impls::deserialize_telegram_bots")]
+    pub telegram:      ExtendedOption<Vec<TelegramConfig>>,
+    /// The demo protocol of the socket server, offered through gRPC -- see [crate::frontend::grpc::GrpcServer].\
+    /// Disabled by default, as it serves plaintext & anonymous calls. Example: `grpc: Enabled((interface: "127.0.0.1", port: 50051, reflection: true))`
+    pub grpc:          ExtendedOption<GrpcConfig>,
 }
 
-/// The telegram service
+/// A Telegram bot -- see [ServicesConfig::telegram]
 #[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
 pub struct TelegramConfig {
+    /// The name the bot is registered under, in the [crate::runtime::Runtime] -- like "main" or "support". Must be unique.
+    /// Defaults to "main" -- see [TelegramConfig::DEFAULT_NAME]
+    #[serde(default = "TelegramConfig::default_name")]
+    pub name: String,
     /// Telegram's bot token, obtained from "BotFather's" bot:
     /// 1) Open TelegramApp and search for BotFather
     /// 2) Send /newbot (or /help)
@@ -69,6 +164,48 @@ pub struct TelegramConfig {
     pub bot: TelegramBotOptions,
     /// chat ids where send notifications will land on
     pub notification_chat_ids: Vec<i64>,
+    /// Where the `Stateful` bot keeps its dialogues
+    pub dialogue_storage: TelegramDialogueStorage,
+    /// Per-user limits on the commands sent to the `Stateless` bot -- users going above them are asked to slow down
+    pub command_limits: TelegramCommandLimits,
+    /// Pacing of the push messages (MTs) sent by the business logic -- see [crate::frontend::telegram::TelegramNotifier]. Only used by the primary bot
+    pub mt_limits: TelegramMtLimits,
+    /// If set, a status report (uptime, message counters & the scheduled jobs' statuses) is sent to the notification chats on this cron
+    /// expression -- as in [JobSchedule::Cron]. Like "0 9 * * *", for every day at 09:00. Ran by the scheduler, even if it is disabled.
+    /// Only used by the primary bot
+    pub status_report_cron: Option<String>,
+}
+
+/// Per-user command limits for the Telegram bots -- see [TelegramConfig::command_limits]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct TelegramCommandLimits {
+    /// Max number of commands each user may send in any given minute -- 0 for unlimited
+    pub max_commands_per_minute: u32,
+    /// Max number of commands each user may send in a day (in the local timezone) -- 0 for unlimited
+    pub max_commands_per_day: u32,
+}
+
+/// Limits for the push messages (MTs), respecting Telegram's -- see [TelegramConfig::mt_limits]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct TelegramMtLimits {
+    /// Max number of messages waiting to be sent -- when full, the oldest ones are dropped
+    pub max_queued_messages: usize,
+    /// Max number of messages sent per second, across all chats -- Telegram refuses more than ~30
+    pub max_messages_per_second: u32,
+    /// Min time between two messages sent to the same chat -- Telegram refuses more than ~1 per second
+    pub min_chat_interval_millis: u64,
+}
+
+/// Storage backends for the Telegram dialogues -- see [TelegramConfig::dialogue_storage]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum TelegramDialogueStorage {
+    /// Dialogues are lost whenever the application restarts
+    InMemory,
+    /// Dialogues survive restarts & deploys -- and are shared by all instances connected to the same Redis server
+    Redis {
+        /// Example: "redis://127.0.0.1:6379/"
+        url: String,
+    },
 }
 
 /// Available bots to handle Telegram interaction
@@ -97,16 +234,26 @@ pub enum RocketConfigOptions {
     /// Instructs Rocket to read configs from it's `Rocket.toml` file. Notice that Rocket will look
     /// for such file in the current working directory, rather than on the executable's location.
     StandardRocketTomlFile,
-    /// When using use only HTTP, using this variant may be desireable, as it avoids the need of managing
-    /// another configuration file: `Rocket.toml` -- Rocket's config.
+    /// Using this variant may be desireable, as it avoids the need of managing another configuration file: `Rocket.toml` -- Rocket's config.
     Provided {
-        /// Port to listen to HTTP connections
+        /// Port to listen to HTTP (or HTTPS, if `tls` is enabled) connections
         http_port:  u16,
         /// How many tokio async tasks should be used to process the incoming requests?
         workers: u16,
+        /// If enabled, connections are served through HTTPS only
+        tls: ExtendedOption<WebTlsConfig>,
     }
 }
 
+/// The certificate & key for serving HTTPS -- see [RocketConfigOptions::Provided::tls]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct WebTlsConfig {
+    /// PEM file with the certificate chain -- Rocket's `certs`
+    pub cert_file: String,
+    /// PEM file with the private key (PKCS#8 or RSA) -- Rocket's `key`
+    pub key_file:  String,
+}
+
 /// The HTTP/HTTPS service
 #[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
 pub struct WebConfig {
@@ -116,19 +263,117 @@ pub struct WebConfig {
     pub rocket_config: RocketConfigOptions,
     /// If set, enables [crate::frontend::web::sanity_check] routes -- allowing this executable to be probed for it's running sanity
     pub sanity_check_routes: bool,
-    /// If set, enables [crates::frontend::web::stats] routes -- exposing runtime metrics
+    /// If set (and if the metrics recorder could be installed), enables [crate::frontend::web::stats] routes -- exposing the metrics registry
+    /// as JSON & in the Prometheus text format
     pub stats_routes: bool,
-    /// If set, enables [crates::frontend::web::logs_following] routes -- exposing online logs for the app
+    /// If set, enables the `/api/logs/tail` route -- streaming the app's logs, as followed by `console tail`. See [crate::logging::log_tail]
     pub logs_following_routes: bool,
-    /// If set, enables [crates::frontend::web::ogre_events_following] routes -- exposing online `Ogre Events` for the app
+    /// If set (and if the socket server is enabled), enables the `POST /api/socket-proxy` route -- forwarding messages to the socket server on
+    /// behalf of the authenticated web clients, who are, then, subject to its limits & bans. See [crate::frontend::web::socket_proxy]
+    pub socket_proxy_routes: bool,
+    /// If set (and if the socket server is enabled), enables the `/api/ws` WebSocket route -- bridging browsers to the socket server's
+    /// protocol, on their behalf, so they receive its messages without polling. See [crate::frontend::web::websocket]
+    pub websocket_routes: bool,
+    /// If set, enables the `/api/events/follow` route -- streaming, as Server-Sent Events, the events published to the internal event bus
+    /// from now on. See [crate::frontend::web::events] & [crate::runtime::EventBus]
     pub ogre_events_following_routes: bool,
-    /// If set, enables [crates::frontend::web::ogre_events_queue] routes -- exposing `Ogre Events` designed to be consumed by external services
+    /// If set, enables the `/api/events/queue` route -- streaming the internal event bus' events to external services, which may resume
+    /// after the last one they consumed (as long as it is among the most recent ones kept). See [crate::frontend::web::events]
     pub ogre_events_queue_routes: bool,
     /// If set, enables the Angular application present in `web-app/`, exposing it's [crate::frontend::web::backend]
     /// routes and all related static files (see [crate::frontend::web::embedded_files])
     pub web_app: bool,
+    /// If set (along with [Self::web_app]), the static files are served from this directory -- like `web-app/dist/kickass-app-template` --
+    /// instead of the ones embedded in the executable, with caching disabled: frontend developers may rebuild the Angular / egui apps
+    /// & simply reload the browser, without recompiling. See [crate::frontend::web::files]
+    pub static_files_dir: Option<String>,
     /// Prepends the given string to all our HTTP/HTTPS routes
     pub routes_prefix: String,
+    /// Inbound webhooks, by name, received on `/api/hooks/<name>` -- see [crate::frontend::web::hooks].
+    /// Example, for GitHub: {"github": (secret: "s3cr3t", signature_header: "X-Hub-Signature-256", signature_prefix: "sha256=", max_requests_per_minute: 60)}
+    pub inbound_hooks: BTreeMap<String, InboundHookConfig>,
+    /// If set, enables the `/api/admin` routes -- shutdown, config reload, log filters & socket clients -- to the requests presenting
+    /// this token as `Authorization: Bearer <token>`. See [crate::frontend::web::admin]
+    pub admin_token: Option<String>,
+    /// Redirects answered before any route is matched -- the first matching rule wins. See [crate::frontend::web::redirects].
+    /// Example, for a moved page & a canonical host:
+    /// [(host: None, from: Exact("/old-page"), to: "/new-page", status: 308),
+    ///  (host: Some("example.com"), from: Regex("^(/.*)$"), to: "https://www.example.com$1", status: 301)]
+    pub redirects: Vec<RedirectRule>,
+    /// Internal rewrites of the request paths, applied (after the redirects) before any route is matched -- the first matching rule wins.
+    /// Example, for a legacy path: [(from: Regex("^/legacy/(.*)$"), to: "/api/$1")]
+    pub rewrites: Vec<RewriteRule>,
+    /// What to serve for the files every browser & crawler asks for -- so they don't get 404s. See [crate::frontend::web::default_files]
+    pub default_files: DefaultFilesConfig,
+}
+
+/// The files served even if the web app is disabled -- see [WebConfig::default_files]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct DefaultFilesConfig {
+    /// `/favicon.ico` -- the built-in one is the web app's `web-app/src/favicon.ico`
+    pub favicon: DefaultFileSource,
+    /// `/robots.txt` -- the built-in one allows everything but the `/api/` routes
+    pub robots_txt: DefaultFileSource,
+    /// `/.well-known/security.txt` (RFC 9116) -- the built-in one points to `security@<the requested host>` & expires in a year
+    pub security_txt: DefaultFileSource,
+}
+
+/// Where the contents of a default file come from -- see [DefaultFilesConfig]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum DefaultFileSource {
+    /// The built-in contents
+    BuiltIn,
+    /// The contents of this file on disk -- read at startup
+    File(String),
+    /// These very contents
+    Content(String),
+    /// Not served -- "404 Not Found"
+    Disabled,
+}
+
+/// How [RedirectRule]s & [RewriteRule]s match the request paths
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum PathPattern {
+    /// Matches only this very path
+    Exact(String),
+    /// Matches paths for which this regular expression finds a match -- anchor it with `^...$` to match whole paths.
+    /// Its capture groups may be referenced in the target as `$1`, `$2`, `${name}`, ...
+    Regex(String),
+}
+
+/// A redirect -- see [WebConfig::redirects]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct RedirectRule {
+    /// If given, only requests to this host (as in their `Host` header, without the port) are redirected
+    pub host: Option<String>,
+    /// The paths to redirect
+    pub from: PathPattern,
+    /// Where to redirect to -- a path or an absolute URL. The query string, if any, is carried over when `to` has none
+    pub to: String,
+    /// The redirect status code: 301, 302, 303, 307 or 308
+    pub status: u16,
+}
+
+/// An internal rewrite -- see [WebConfig::rewrites]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct RewriteRule {
+    /// The paths to rewrite
+    pub from: PathPattern,
+    /// The path to serve instead -- with the original query string, unless it has one
+    pub to: String,
+}
+
+/// An inbound webhook -- see [WebConfig::inbound_hooks]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct InboundHookConfig {
+    /// The key the sender uses to sign the payloads with HMAC-SHA256 -- requests with missing or invalid signatures are refused
+    pub secret: String,
+    /// The header carrying the signature -- like "X-Hub-Signature-256" (GitHub) or "X-Webhook-Signature" (our own outbound webhooks)
+    pub signature_header: String,
+    /// What precedes the hex encoded signature in `signature_header` -- like "sha256="
+    pub signature_prefix: String,
+    /// Requests above this rate are refused with "429 Too Many Requests" -- 0 for no limit
+    pub max_requests_per_minute: u32,
 }
 
 /// The socket server
@@ -138,20 +383,256 @@ pub struct SocketServerConfig {
     pub interface: String,
     /// what port to listen to
     pub port:      u16,
-    /// How many tokio async tasks should be used to process the incoming requests?
-    /// If you delegate it to events (or similar), this should be 1;
-    /// If you fully process the request in the worker task (bad practice), measure and pick your optimal number.
-    pub workers: u16,
+    /// Plain TCP, framed TCP, UDP or TLS -- the protocol & processors are the same for all
+    pub transport: SocketTransport,
+    /// How the messages are represented on the wire -- which also determines how they are framed
+    pub wire_format: SocketWireFormat,
+    /// How the incoming requests should be processed -- resolved at startup.
+    /// If you delegate them to events (or similar), `Serial` is the fastest;
+    /// If you fully process the requests in the processor (bad practice), measure and pick your optimal strategy.\
+    /// Notice only `Serial` keeps each client's answers in the order of its requests: `Concurrent`, `Parallel` & `Adaptive` (while
+    /// concurrent) send them as they are ready -- so clients pipelining their requests must tell the answers apart by their contents
+    pub processor: SocketProcessorStrategy,
+    /// On shutdown, how long to wait for the processor to answer the requests it already received -- before disconnecting the clients
+    pub flush_timeout_millis: u64,
+    /// What to do with the requests arriving while the processor's queue is full
+    pub overflow_policy: SocketOverflowPolicy,
+    /// What to do when processing a request panics
+    pub panic_policy: SocketPanicPolicy,
+    /// Up to how many client addresses to keep statistics for (messages, bytes, errors, latency, ...) -- bounding the memory used for them.
+    /// When full, disconnected clients are forgotten to give room to new ones. 0 disables the statistics
+    pub max_tracked_endpoints: usize,
+    /// If enabled, raw inbound & outbound frames are dumped (hex & escaped) to a capture file -- for diagnosing client interop issues.
+    /// Dumping may be paused & resumed at runtime -- see [crate::frontend::socket_server::WireDump]
+    pub wire_dump: ExtendedOption<WireDumpConfig>,
+    /// Messages longer than this many bytes are answered with `MessageTooLong` -- after which their clients are disconnected & banned for `ban_seconds`.
+    /// Length prefixed messages are refused by their prefix, before being received
+    pub max_message_length: usize,
+    /// Clients sending this many malformed messages are answered with `TooManyMalformedMessages`, disconnected & banned for `ban_seconds`
+    /// -- 0 for no limit
+    pub max_malformed_messages: u32,
+    /// For how long the new connections of the clients disconnected due to the limits above are refused (with `Banned`)
+    pub ban_seconds: u64,
+    /// If enabled, the messages each client sends above the given rate are answered with `TooBusy` & not processed -- so a flooding
+    /// client can't starve the others. Abusive clients may also be disconnected & banned for `ban_seconds`.\
+    /// Disabled by default. Example: `ingress_limit: Enabled((messages_per_second: 1000, burst_messages: 2000, max_violations: 0))`
+    pub ingress_limit: ExtendedOption<IngressLimitConfig>,
+    /// If enabled, clients must authenticate with `Login { token }` -- verified as set here -- before sending any other messages,
+    /// which are answered with `Unauthenticated` until then
+    pub login: ExtendedOption<SocketLogin>,
+    /// If enabled, the answers sent to the clients are delayed so they don't go above the given rates -- so streaming large answers
+    /// won't saturate the host's uplink nor starve the other clients. See `frontend/socket_server/egress_throttle.rs`
+    pub egress_limit: ExtendedOption<EgressLimitConfig>,
+    /// Up to how many clients (or UDP sessions) may be connected at once -- new connections above it are answered with `TooManyClients`
+    /// & closed. 0 for no limit
+    pub max_connections: usize,
+    /// If enabled, clients with too many answers pending -- too slow in reading them or too fast in requesting them -- are flagged or
+    /// disconnected, so they can't pile up the server's memory. See [SendQueueLimitConfig].\
+    /// Disabled by default. Example: `per_client_send_queue: Enabled((max_pending_answers: 1024, disconnect: false))`
+    pub per_client_send_queue: ExtendedOption<SendQueueLimitConfig>,
+    /// If enabled, the clients negotiating the `keepalive` feature are sent `KeepAlive`s -- whose answers measure their round-trip times,
+    /// reported on `/api/stats/socket` & alerted on the event bus when above the SLA. See [KeepAliveConfig]
+    pub keepalive: ExtendedOption<KeepAliveConfig>,
+    /// If enabled, clients may `Subscribe` to topics of the event bus -- having their events pushed -- & `Resume` their subscriptions
+    /// after reconnecting, getting what they missed replayed. See [SubscriptionsConfig]
+    pub subscriptions: ExtendedOption<SubscriptionsConfig>,
+}
+
+/// Available strategies for processing the socket server's requests -- see [SocketServerConfig::processor]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketProcessorStrategy {
+    /// A single async task processes one request at a time -- the fastest for simple workloads
+    Serial,
+    /// A single async task processes up to `buffer` requests concurrently -- for requests waiting on I/O.
+    /// Answers are sent as they are ready, so a client's may come out of order
+    Concurrent { buffer: usize },
+    /// Requests are processed in parallel by `workers` threads (0 for one per CPU) -- for CPU-bound requests
+    Parallel { workers: usize },
+    /// A single async task processes one request at a time while the processor's queue occupancy (`socket_processor_queue_occupancy`
+    /// on `/api/metrics`) is below `high_occupancy` -- & up to `buffer` concurrently above it, until it drains to half of that.
+    /// For requests usually quick, but sometimes waiting on I/O
+    Adaptive { buffer: usize, high_occupancy: usize },
+}
+
+/// What the socket server does with requests arriving while its processor's queue is full -- see [SocketServerConfig::overflow_policy].
+/// Occurrences are counted, by policy, on `/api/metrics`
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketOverflowPolicy {
+    /// Answers `TooBusy`, so clients may retry later -- notice answering happens in the network thread, which may amplify the load
+    ReplyTooBusy,
+    /// Silently drops the request
+    Drop,
+    /// Holds the request for up to `timeout_millis`, waiting for room in the queue (then drops it) -- along with the next requests of the
+    /// same client, so they keep their order. Other clients are not affected
+    Block { timeout_millis: u64 },
+    /// Disconnects the client
+    Disconnect,
+}
+
+/// How the socket server is exposed -- see [SocketServerConfig::transport]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketTransport {
+    /// Plain TCP -- with the binary wire formats, messages are length prefixed, as in [Self::Framed]
+    Plain,
+    /// Plain TCP with length prefixed messages (`message-io`'s `FramedTcp`), for all wire formats -- so textual messages may span several lines
+    Framed,
+    /// UDP -- one or more messages per datagram, which limits their size to ~64KiB. As there are no connections, a client's first
+    /// datagram starts its session (as if it had connected), which ends after `session_timeout_millis` without receiving any from it
+    Udp { session_timeout_millis: u64 },
+    /// TLS over TCP, terminated in-process -- so no external proxy is needed. See [crate::frontend::socket_server::tls].
+    /// `cert_file` is the PEM certificate chain (leaf first) & `key_file`, its PEM (PKCS #8) private key.\
+    /// Decrypted connections are handed to a plain listener on an ephemeral loopback port, which refuses any connection not made by the terminator
+    Tls { cert_file: String, key_file: String },
+    /// Unix domain socket (Unix only), for clients on the same host -- `interface` & `port` are ignored. As in [Self::Plain], messages are
+    /// length prefixed with the binary wire formats. The socket file is created on start (replacing a stale one) & removed on shutdown.
+    /// Access is controlled by the socket file's permissions -- so clients are disconnected, but never banned (`ban_seconds` is ignored).
+    /// See [crate::frontend::socket_server::unix_socket]
+    Unix { socket_file: String },
+}
+
+/// How the socket protocol's messages are serialized -- see [SocketServerConfig::wire_format]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketWireFormat {
+    /// Human readable & writeable -- one message per line, so clients may be as simple as `telnet`
+    Ron,
+    /// One message per line -- for clients in languages with no good RON support (like browsers' & TypeScript's)
+    Json,
+    /// The most compact & fastest -- messages are length prefixed (`message-io`'s `FramedTcp`), as they may contain any bytes -- or one per datagram, with UDP
+    Bincode,
+}
+
+/// What the socket server does when its processor panics -- see [SocketServerConfig::panic_policy].\
+/// Occurrences are counted on `/api/metrics`
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketPanicPolicy {
+    /// The panic is logged & the request is answered with `ProcessorError` -- the processor keeps serving the other requests
+    Isolate,
+    /// The panic brings the socket server down, as if it wasn't caught -- for debugging processors, having their panics spotted early
+    Crash,
+}
+
+/// Wire-level dumping of the socket server's traffic
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct WireDumpConfig {
+    /// If `false`, dumping starts paused -- to be resumed at runtime, when needed
+    pub active: bool,
+    /// The client addresses whose traffic should be dumped -- either IPs ("10.0.0.7") or IPs & ports ("10.0.0.7:41234").
+    /// Leave it empty to dump all traffic
+    pub endpoints: Vec<String>,
+    /// Where to write the dumps to -- rotated files get the `.1`, `.2`, ... suffixes
+    pub capture_file: String,
+    /// The capture file is rotated when it would exceed this many bytes
+    pub max_file_size: u64,
+    /// How many rotated capture files to keep -- older ones are deleted
+    pub max_rotated_files: u32,
+}
+
+/// How the tokens of the socket server's `Login` handshake are verified -- see [SocketServerConfig::login]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum SocketLogin {
+    /// Only the listed tokens are accepted -- for simple setups, not requiring [Config::auth]
+    StaticTokens(Vec<String>),
+    /// Tokens are verified by the [Config::auth] provider -- as API keys or, if not one, as access tokens (JWT / OIDC) or as the session
+    /// tokens web users get from `/api/session/socket-token`
+    AuthProvider,
+    /// Tokens are verified by the business logic -- see [crate::logic::verify_socket_token()]
+    Logic,
+}
+
+/// Message rate limits for each of the socket server's clients -- see [SocketServerConfig::ingress_limit]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct IngressLimitConfig {
+    /// Up to how many messages per second each client may send
+    pub messages_per_second: u32,
+    /// How many messages each client may send at once, above its rate, after being idle for a while
+    pub burst_messages: u32,
+    /// Clients exceeding the rate for this many messages in a row are disconnected & banned -- 0 to never disconnect them
+    pub max_violations: u32,
+}
+
+/// Bandwidth limits for the socket server's answers -- see [SocketServerConfig::egress_limit]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct EgressLimitConfig {
+    /// Up to how many bytes per second may be sent to each client -- 0 for no limit
+    pub per_connection_bytes_per_second: u64,
+    /// How many bytes each client may receive at once, above its rate, after being idle for a while
+    pub per_connection_burst_bytes: u64,
+    /// Up to how many bytes per second may be sent to all clients together -- 0 for no limit
+    pub global_bytes_per_second: u64,
+    /// How many bytes all clients together may receive at once, above the global rate, after the server was idle for a while
+    pub global_burst_bytes: u64,
+}
+
+/// Backpressure for the socket server's clients -- see [SocketServerConfig::per_client_send_queue]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SendQueueLimitConfig {
+    /// Up to how many answers may be pending for each client -- dispatched to the processor (or held by the egress limits), but not yet sent
+    pub max_pending_answers: usize,
+    /// What happens to the clients above `max_pending_answers`: if `true`, they are disconnected; otherwise, they are flagged (logged &
+    /// counted as errors in their stats) & have their requests answered with `TooBusy` until their queues drain
+    pub disconnect: bool,
+}
+
+/// Server-initiated keepalives -- see [SocketServerConfig::keepalive]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct KeepAliveConfig {
+    /// How often each client is sent a `KeepAlive`
+    pub interval_millis: u64,
+    /// Clients whose p95 round trip goes above this are alerted on the event bus (once, until they get back below it) -- 0 for no alerts
+    pub max_p95_round_trip_millis: u64,
+}
+
+/// Resumable push subscriptions -- see [SocketServerConfig::subscriptions]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SubscriptionsConfig {
+    /// The event bus topics clients may subscribe to -- others are refused
+    pub topics: Vec<String>,
+    /// How many of the last events pushed are kept, per subscription, for replaying to the clients resuming it
+    pub buffer_size: usize,
+    /// For how long the subscriptions of disconnected clients are kept, waiting for them to `Resume` them
+    pub resume_window_millis: u64,
+}
+
+/// The gRPC service -- along with the standard health & (optionally) reflection services
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct GrpcConfig {
+    /// the interface's IP to listen to -- 0.0.0.0 will cause listening to all network interfaces
+    pub interface:  String,
+    /// what port to listen to
+    pub port:       u16,
+    /// If true, the server reflection service is offered -- allowing tools like `grpcurl` to list & call the methods without the `.proto` files
+    pub reflection: bool,
+}
+
+/// The socket client -- connecting to the socket server of another instance, speaking the same protocol.\
+/// The other instance's server must use the RON [SocketServerConfig::wire_format]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SocketClientConfig {
+    /// the `host:port` of the socket server to connect to
+    pub server_address: String,
+    /// How long to wait for the connection to be established
+    pub connect_timeout_millis: u64,
+    /// How long to wait before reconnecting, after the connection failed or was lost
+    pub reconnect_delay_millis: u64,
+    /// How often to send `Ping`s while connected, keeping the connection alive -- 0 to disable
+    pub ping_interval_millis: u64,
 }
 
 /// Logging options -- what to do with log messages
-#[derive(Debug,PartialEq,Serialize,Deserialize)]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
 pub enum LoggingOptions {
     /// Simply ignore them
     Quiet,
     /// Output them to stdout
     ToConsole,
-    /// Save them to the specified file, with the specified options:
+    /// Output them to stdout, in a human-friendly format for local development: compact timestamps & colored levels -- see [crate::logging]
+    ToConsolePretty {
+        /// Reduces the modules' parents to their initials -- `kickass_app_template::frontend::web::api` is shown as `k::f::w::api`
+        shorten_modules: bool,
+        /// Starts the key-values in the same column, for the short messages
+        align_key_values: bool,
+    },
+    /// Save them to the specified file, with the specified options -- a SIGHUP rotates & reopens it (as `logrotate` expects):
     ToFile {
         /// File to use a basis for rotation or appending
         file_path: String,
@@ -159,21 +640,433 @@ pub enum LoggingOptions {
         rotation_size: usize,
         /// The upper limit of rotations to keep before deleting old ones -- example: 64
         rotations_kept: usize,
-        /// Performs a gzip compression after a rotation?
-        compress_rotated: bool,
+        /// How to compress the rotated files -- done in the background, so the logging is never held by it
+        compress_rotated: LogCompression,
+    },
+}
+
+/// Access log options -- see [Config::access_log]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct AccessLogConfig {
+    /// File to use a basis for rotation or appending -- example: "/var/log/kickass-app-template.access.log"
+    pub file_path: String,
+    /// The maximum size (bytes) for the access log file before a rotation kicks in -- example: 1024*1024*1024 = 1073741824
+    pub rotation_size: usize,
+    /// The upper limit of rotations to keep before deleting old ones -- example: 64
+    pub rotations_kept: usize,
+    /// How to compress the rotated files -- done in the background, so the logging is never held by it
+    pub compress_rotated: LogCompression,
+}
+
+/// Compression for the rotated log files -- see [LoggingOptions::ToFile] & [AccessLogConfig]
+#[derive(Debug,PartialEq,Clone,Copy,Serialize,Deserialize)]
+pub enum LogCompression {
+    /// Rotated files are kept as they are
+    None,
+    /// Rotated files get the `.gz` suffix
+    Gzip,
+    /// Rotated files get the `.zst` suffix -- `level` goes from 1 (the fastest) to 22 (the smallest). 3 is zstd's default
+    Zstd { level: i32 },
+}
+
+/// Log sampling & burst suppression options -- see [Config::log_sampling]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct LogSamplingConfig {
+    /// Consecutive identical messages arriving within this window (millis) from the first one are collapsed
+    /// into a single "Last message repeated N times" -- 0 disables it
+    pub repeat_window_millis: u64,
+    /// Above this number of trace & debug messages per second, for each target, sampling kicks in -- 0 disables it
+    pub max_debug_rate: u32,
+    /// When sampling, 1 out of every `sample_one_in` trace & debug messages is logged
+    pub sample_one_in: u32,
+    /// Per-target overrides for `max_debug_rate` -- keys follow the same rules as in `log_filters`.
+    /// Example: {"socket_server": 100}
+    pub target_rates: BTreeMap<String, u32>,
+}
+
+/// Leader election options -- see [Config::leader_election]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct LeaderElectionConfig {
+    /// The lease file, on a storage shared by all instances (NFS, ...), through which the leader is elected
+    pub lease_file: String,
+    /// How long (millis) the leadership lasts without being renewed -- the leader renews it every 1/3 of this.
+    /// Keep it well above the clock skew between the hosts
+    pub lease_duration_millis: u64,
+    /// Identifies this instance among the others -- leave it empty for "<hostname>:<pid>"
+    pub instance_id: String,
+}
+
+/// Locks backends -- see [Config::locks]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum LocksBackend {
+    /// Single-node locks: only serializes tasks within this process
+    Local,
+    /// Locks shared by all instances connected to the same Redis server
+    Redis {
+        /// Example: "redis://127.0.0.1:6379/"
+        url: String,
+        /// Prepended to the resource names to form the Redis keys -- example: "kickass-app-template:locks:"
+        key_prefix: String,
     },
 }
 
+/// Graceful degradation switches -- see [Config::degrade]
+#[derive(Debug,PartialEq,Clone,Default,Serialize,Deserialize)]
+pub struct DegradeConfig {
+    /// If true, Telegram push notifications are discarded -- the bot still answers to users, though
+    pub disable_telegram_notifications: bool,
+    /// If true, the web API refuses (with "503 Service Unavailable") requests that would change the application's state
+    pub read_only_api: bool,
+    /// If true, the application starts in maintenance mode: the web server answers "503 Service Unavailable" (but for the
+    /// monitoring routes), while the socket server answers `Maintenance` & refuses new connections
+    pub maintenance: bool,
+    /// Shown to users while in maintenance mode -- as `{"maintenance": "<message>"}` for the API, plain HTML for the web app
+    /// & the `Maintenance` contents for socket clients. If empty, a generic message is used
+    pub maintenance_message: String,
+}
+
+/// Outbound webhooks -- see [Config::webhooks]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct WebhooksConfig {
+    /// The external integrations, by name -- example: {"crm": (url: "https://crm.example.com/hooks/kickass", secret: "s3cr3t", events: ["order.*"])}
+    pub targets: BTreeMap<String, WebhookTarget>,
+    /// How many times to try delivering each event before giving up on it
+    pub max_attempts: u32,
+    /// How long (millis) to wait before the first retry -- doubled at each subsequent retry
+    pub retry_delay_millis: u64,
+    /// How many of the most recent deliveries to keep for inspection through `/api/webhooks/deliveries`
+    pub deliveries_kept: usize,
+}
+
+/// An external integration, to which events are POSTed -- see [WebhooksConfig]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct WebhookTarget {
+    /// Where to POST the events to
+    pub url: String,
+    /// The key for the HMAC-SHA256 signature of the payloads, sent in the `X-Webhook-Signature` header as "sha256=<hex>" -- leave it empty not to sign
+    pub secret: String,
+    /// The events this target is interested in -- either event names or prefixes ending in `*`. Empty for all events
+    pub events: Vec<String>,
+}
+
+/// Authentication providers -- see [Config::auth]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum AuthConfig {
+    /// Identities listed right here
+    Static { identities: Vec<IdentityConfig> },
+    /// Identities listed in a RON file -- a list of [IdentityConfig]s -- reloaded whenever it changes
+    File { file_path: String },
+    /// Bearer tokens verified by an OpenID Connect provider -- through its userinfo endpoint
+    Oidc {
+        /// Example: "https://accounts.example.com/oauth2/userinfo"
+        userinfo_url: String,
+        /// The userinfo claim naming the identity -- example: "email"
+        name_claim: String,
+        /// The userinfo claim listing the identity's roles -- example: "roles"
+        roles_claim: String,
+    },
+}
+
+/// An identity, for the [AuthConfig::Static] & [AuthConfig::File] providers
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct IdentityConfig {
+    /// Example: "alice" -- or, for Telegram users, "telegram:<user id>"
+    pub name: String,
+    /// The argon2 hash of the password, as a PHC string -- example: "$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>", generated with a
+    /// random salt by `echo -n 's3cr3t' | argon2 "$(openssl rand -base64 12)" -id -e`.
+    /// Leave it empty not to allow password logins
+    pub password_hash: String,
+    /// Accepted in the `X-API-Key` header
+    pub api_keys: Vec<String>,
+    /// Example: ["operator"] -- allowing operator commands, like the Telegram `/maintenance`
+    pub roles: Vec<String>,
+}
+
+/// Durable job queue -- see [Config::job_queue]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct JobQueueConfig {
+    /// Where the jobs are persisted -- in its `pending/` & `dead/` (for the dead letters) subdirectories, created if needed
+    pub spool_dir: String,
+    /// How many jobs may run at the same time
+    pub workers: u16,
+    /// How many times to try running each job before moving it to the dead letters
+    pub max_attempts: u32,
+    /// How long (millis) to wait before the first retry -- doubled at each subsequent retry
+    pub retry_delay_millis: u64,
+}
+
+/// The in-process history of key metrics -- see [Config::metrics_history]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct MetricsHistoryConfig {
+    /// How often (seconds) to sample the metrics
+    pub resolution_seconds: u64,
+    /// For how long (seconds) to keep the samples -- memory is bounded by `retention_seconds / resolution_seconds` samples for each metric
+    pub retention_seconds: u64,
+}
+
+/// The unprivileged identity to run as -- see [Config::run_as]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct RunAsConfig {
+    /// The user name to switch to -- whose supplementary groups are also taken
+    pub user: String,
+    /// The group name to switch to -- if `None`, the user's primary group is used
+    pub group: Option<String>,
+    /// If `false` (the default), refuses to go on if the process would still be running as root after the switch (like when `user` is `root`)
+    pub allow_root: bool,
+}
+
+/// How the config file is watched -- see [Config::hot_reload]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct HotReloadConfig {
+    /// How often to check the config file's modification time -- reloading it if it changed
+    pub poll_interval_millis: u64,
+}
+
+/// The scheduled jobs -- see [Config::scheduler]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SchedulerConfig {
+    pub jobs: Vec<ScheduledJobConfig>,
+}
+
+/// A job ran by the scheduler -- whose code must be registered, by the business logic, under the same `name`
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct ScheduledJobConfig {
+    pub name:     String,
+    pub schedule: JobSchedule,
+    /// Critical jobs keep running in maintenance mode -- the others are skipped while it is on. See [crate::runtime::DegradeSwitches].
+    /// Optional: `false` if absent
+    #[serde(default)]
+    pub critical: bool,
+}
+
+/// When a scheduled job runs -- see [ScheduledJobConfig::schedule]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub enum JobSchedule {
+    /// A cron expression, evaluated in the local timezone: "minute hour day-of-month month day-of-week" -- each field accepting
+    /// `*`, numbers, ranges (`1-5`), steps (`0-30/10` -- or `*` followed by `/15`, for every 15) & lists of those (`0,30`). Example: "30 3 * * 1-5" for 03:30, Monday to Friday
+    Cron(String),
+    /// At fixed intervals, counted from the application's start
+    Every { seconds: u64 },
+}
+
+/// Background mode options -- see [Config::daemon]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct DaemonConfig {
+    /// Where the daemon's PID is written -- locked while it runs & removed when it ends. Relative paths are resolved against the directory
+    /// the application is started from (which the daemon keeps as its working directory)
+    pub pid_file: String,
+}
+
+/// The persistent state's snapshots -- see [Config::storage]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct StorageConfig {
+    /// The snapshot file -- loaded on start & rewritten with the changes
+    pub file_path: String,
+    /// How often (seconds) to save the changes -- 0 to only save them on the graceful shutdown
+    pub autosave_interval_seconds: u64,
+}
+
+/// The notifications' outbox -- see [Config::outbox]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct OutboxConfig {
+    /// How often (millis) to look for due deliveries -- new intents are dispatched right away
+    pub poll_interval_millis: u64,
+    /// How many times to try delivering each intent before marking it as failed -- to be requeued through the admin API
+    pub max_attempts: u32,
+    /// How long (millis) to wait before the first retry -- doubled at each subsequent retry
+    pub retry_delay_millis: u64,
+    /// How many of the delivered intents' idempotency keys to remember -- intents enqueued again with one of them are ignored
+    pub delivered_keys_kept: usize,
+}
+
+/// How the services are restarted -- see [Config::supervisor]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SupervisorConfig {
+    /// The policy for the services not in `restart_policies`
+    pub default_restart_policy: RestartPolicy,
+    /// Per-service policies, by the service names: "telegram", "web", "socket-server", "socket-client" & "grpc".
+    /// Example: {"telegram": Always, "grpc": Never}
+    pub restart_policies: BTreeMap<String, RestartPolicy>,
+    /// How long (millis) to wait before the first restart -- doubled at each subsequent one, up to `max_backoff_millis`.
+    /// Runs lasting longer than `max_backoff_millis` reset it
+    pub initial_backoff_millis: u64,
+    pub max_backoff_millis: u64,
+    /// This many restarts of a service within `crash_loop_window_seconds` make a crash loop -- alerted to Telegram's notification chats.
+    /// 0 disables the alerts
+    pub crash_loop_restarts: u32,
+    pub crash_loop_window_seconds: u64,
+}
+
+/// When a service is restarted -- see [SupervisorConfig]
+#[derive(Debug,PartialEq,Clone,Copy,Serialize,Deserialize)]
+pub enum RestartPolicy {
+    /// The service stays down once it ends
+    Never,
+    /// The service is restarted if it ends with an error (or panics)
+    OnFailure,
+    /// The service is restarted whenever it ends -- even if stopped by an operator (see `console repl`'s `stop`). Not on shutdowns, though
+    Always,
+}
+
+/// Where & how the tracing spans are exported -- see [Config::telemetry]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct TelemetryConfig {
+    /// The OTLP/HTTP endpoint of the collector -- `/v1/traces` is appended to it. Example: "http://localhost:4318"
+    pub otlp_endpoint: String,
+    /// How this application is identified in the traces -- its `service.name`
+    pub service_name: String,
+    /// The fraction (from 0.0 to 1.0) of the traces started here to be exported -- the ones continued from other services follow their decision
+    pub sampling_ratio: f64,
+    /// How long (millis) to wait for the collector on each export
+    pub export_timeout_millis: u64,
+}
+
+/// The limits watched by the self-monitor -- see [Config::self_monitor]. 0 disables a limit
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize)]
+pub struct SelfMonitorConfig {
+    /// How often (seconds) the resources are sampled
+    pub sampling_interval_seconds: u64,
+    /// The resident memory (RSS), in MiB
+    pub max_resident_memory_mib: u64,
+    /// The CPU usage -- user + system -- in percent of a single CPU: may be above 100 for multi-threaded processes
+    pub max_cpu_percent: f64,
+    /// How late (millis) Tokio's timers fire -- tasks waiting that long for a worker mean the workers are too busy (or blocked)
+    pub max_scheduling_delay_millis: u64,
+    /// For how many consecutive samples a limit must be breached before acting -- so spikes are tolerated
+    pub breached_samples_to_act: u32,
+    /// What to do -- besides warning on each breaching sample -- once a limit was breached for `breached_samples_to_act` samples
+    pub on_breach: WatchdogAction,
+}
+
+/// What the self-monitor does once a limit is breached -- see [SelfMonitorConfig::on_breach]
+#[derive(Debug,PartialEq,Clone,Copy,Serialize,Deserialize)]
+pub enum WatchdogAction {
+    /// Only the warnings are logged
+    LogOnly,
+    /// Alerts Telegram's notification chats
+    TelegramAlert,
+    /// Alerts Telegram's notification chats, then gracefully shuts the services down -- for the process manager (systemd, Kubernetes, ...)
+    /// to start a fresh instance
+    Restart,
+}
+
 /////  EVERYTHING BELOW THIS LINE WILL NOT BE INCLUDED IN THE APPLICATION'S CONFIG FILE  /////
 
 /// Jobs that this application supports. Maps to the command line options [crate::command_line::Jobs]
-#[derive(Debug,PartialEq,Clone,Copy,Serialize,Deserialize,StructOpt)]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize,StructOpt)]
 pub enum Jobs {
     /// Long-Runner: Starts the service, only quitting when a SIG_TERM is received
     Daemon,
     /// Inspects & shows the effective configs & runtime used by the application, then quits
     CheckConfig,
+    /// Generates a standalone Rust client crate for the socket protocol -- into `<executable>.client-sdk/` -- then quits
+    GenerateClientSdk,
+    /// Follows the logs of a running instance -- through its `/api/logs/tail` route -- until interrupted.
+    /// Example: `${0} console tail --level debug --target socket_server`
+    Tail(TailOptions),
+    /// Interactive console, running along with the services: inspects the runtime state, flips the degrade switches, stops services,
+    /// pushes test messages to the socket clients & broadcasts Telegram messages -- with line editing & history. `help` lists the commands
+    Repl,
+    /// Dry run: checks the environment the services would run in -- the config, the ports, the Telegram token & the log files -- without
+    /// starting them, then quits. Fails if any of the checks do, for use in deployment scripts. Example: `${0} console doctor --telegram-get-me`
+    #[structopt(visible_alias = "dry-run")]
+    Doctor(DoctorOptions),
+    /// Inspects the scheduled jobs of a running instance. Example: `${0} console schedule list`
+    Schedule(ScheduleCommands),
+    /// Shows -- or flips -- the degrade switches of a running instance, through its admin API. Example: `${0} console switch maintenance on`
+    Switch(SwitchOptions),
     // ...
+    /// Runs several of the above jobs, in order, reporting their outcomes at the end -- example: `${0} console run check-config generate-client-sdk`
+    Run(JobsSequence),
+}
+
+/// The jobs for [Jobs::Run]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize,StructOpt)]
+pub struct JobsSequence {
+    /// The jobs to run, in order: daemon, check-config, generate-client-sdk, ...
+    pub jobs: Vec<Jobs>,
+    /// A file listing more jobs to run (after the ones given in the command line) -- one per line, `#` starting comments
+    #[structopt(long)]
+    pub jobs_file: Option<String>,
+    /// Keeps running the next jobs when one fails -- by default, the sequence stops on the first failure
+    #[structopt(long)]
+    pub continue_on_error: bool,
+}
+
+/// The options for [Jobs::Tail]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize,StructOpt)]
+pub struct TailOptions {
+    /// The least severe level to show: critical, error, warn, info, debug or trace
+    #[structopt(long, default_value = "info")]
+    pub level: String,
+    /// Only shows the records from this target, in whole `::` segments -- like `socket_server` or `rocket`
+    #[structopt(long)]
+    pub target: Option<String>,
+    /// How many of the records already logged to show first -- all the ones kept by the instance, if absent
+    #[structopt(long)]
+    pub recent: Option<usize>,
+    /// The running instance's web server -- like "http://10.0.0.1:8000". Defaults to the port in the config file, on localhost
+    #[structopt(long)]
+    pub url: Option<String>,
+    /// Sent in the `X-API-Key` header, for instances requiring authentication
+    #[structopt(long)]
+    pub api_key: Option<String>,
+    /// Doesn't color the records by level
+    #[structopt(long)]
+    pub no_color: bool,
+}
+
+/// The sub-commands of [Jobs::Schedule]
+#[derive(Debug,PartialEq,Clone,Serialize,Deserialize,StructOpt)]
+pub enum ScheduleCommands {
+    /// Lists the scheduled jobs of a running instance -- through its `/api/stats/scheduler` route -- with their next runs
+    List(ScheduleListOptions),
+}
+
+/// The options for [ScheduleCommands::List]
+#[derive(Debug,PartialEq,Clone,Default,Serialize,Deserialize,StructOpt)]
+pub struct ScheduleListOptions {
+    /// The running instance's web server -- like "http://10.0.0.1:8000". Defaults to the port in the config file, on localhost
+    #[structopt(long)]
+    pub url: Option<String>,
+}
+
+/// The options for [Jobs::Switch]
+#[derive(Debug,PartialEq,Clone,Default,Serialize,Deserialize,StructOpt)]
+pub struct SwitchOptions {
+    /// The switch to flip: maintenance, read-only or telegram-notifications. All of them are just shown, if absent
+    pub name: Option<String>,
+    /// `on` or `off` -- required along with `name`
+    pub state: Option<String>,
+    /// The running instance's web server -- like "http://10.0.0.1:8000". Defaults to the port in the config file, on localhost
+    #[structopt(long)]
+    pub url: Option<String>,
+    /// The running instance's `admin_token` -- see [WebConfig::admin_token]. Defaults to the one in the config file
+    #[structopt(long)]
+    pub admin_token: Option<String>,
+}
+
+/// The options for [UiOptions::Terminal]
+#[derive(Debug,PartialEq,Clone,Default,Serialize,Deserialize,StructOpt)]
+pub struct TerminalOptions {
+    /// Attaches to the running instance whose web server is at this address -- like "ws://10.0.0.1:8000" -- rather than running the
+    /// services: its state is shown & commands are sent to it through the `/api/admin/console` WebSocket. Quitting leaves it running
+    #[structopt(long)]
+    pub connect: Option<String>,
+    /// The running instance's `admin_token` -- see [WebConfig::admin_token]. Required along with `--connect`
+    #[structopt(long)]
+    pub admin_token: Option<String>,
+}
+
+/// The options for [Jobs::Doctor]
+#[derive(Debug,PartialEq,Clone,Default,Serialize,Deserialize,StructOpt)]
+pub struct DoctorOptions {
+    /// Also asks Telegram if the token is valid -- through its `getMe` method
+    #[structopt(long)]
+    pub telegram_get_me: bool,
+    /// Doesn't color the report
+    #[structopt(long)]
+    pub no_color: bool,
 }
 
 /// A simple extension to the default `Option` to allow distinction for the None state (is it unset or forcibly disabled?)
@@ -216,38 +1109,117 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             log:           LoggingOptions::ToConsole,
+            log_filters:   BTreeMap::new(),
+            log_redactions: vec![
+                String::from(r"\b\d{8,10}:[A-Za-z0-9_-]{35}\b"),
+                String::from(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+                String::from(r#"(?i)\b(api[_-]?key|secret|password|passwd|token)\b["']?\s*[:=]\s*["']?(?P<secret>[^\s"',;]+)"#),
+            ],
+            log_sampling:  ExtendedOption::Enabled(LogSamplingConfig {
+                               repeat_window_millis: 10_000,
+                               max_debug_rate:       1000,
+                               sample_one_in:        100,
+                               target_rates:         BTreeMap::new(),
+                           }),
+            access_log:    ExtendedOption::Disabled,
             services:      ExtendedOption::Enabled(
                                ServicesConfig {
-                                   telegram: ExtendedOption::Enabled(TelegramConfig {
+                                   telegram: ExtendedOption::Enabled(vec![TelegramConfig {
+                                           name:  TelegramConfig::default_name(),
                                            token: String::from("<<Open TelegramApp, search for BotFather, send /newbot>>"),
                                            bot:   TelegramBotOptions::Stateless,
                                            notification_chat_ids: vec![
                                                9999999999,    // james smith
                                                9999999999,    // mary johnson
                                            ],
-                                       }),
+                                           dialogue_storage: TelegramDialogueStorage::InMemory,
+                                           command_limits: TelegramCommandLimits {
+                                               max_commands_per_minute: 10,
+                                               max_commands_per_day:    500,
+                                           },
+                                           mt_limits: TelegramMtLimits {
+                                               max_queued_messages:      1000,
+                                               max_messages_per_second:  25,
+                                               min_chat_interval_millis: 1000,
+                                           },
+                                           status_report_cron: None,
+                                       }]),
                                    web: ExtendedOption::Enabled(WebConfig {
                                        profile: RocketProfiles::Debug,
                                        rocket_config: RocketConfigOptions::Provided {
                                            http_port: 8000,
                                            workers:   1,
+                                           tls:       ExtendedOption::Disabled,
                                        },
                                        sanity_check_routes:          false,
                                        stats_routes:                 false,
                                        logs_following_routes:        false,
+                                       socket_proxy_routes:          false,
+                                       websocket_routes:             false,
                                        ogre_events_following_routes: false,
                                        ogre_events_queue_routes:     false,
                                        web_app:                      true,
-                                       routes_prefix: "".to_string()
+                                       static_files_dir:             None,
+                                       routes_prefix: "".to_string(),
+                                       inbound_hooks: BTreeMap::new(),
+                                       admin_token:   None,
+                                       redirects:     vec![],
+                                       rewrites:      vec![],
+                                       default_files: DefaultFilesConfig {
+                                           favicon:      DefaultFileSource::BuiltIn,
+                                           robots_txt:   DefaultFileSource::BuiltIn,
+                                           security_txt: DefaultFileSource::BuiltIn,
+                                       },
                                    }),
                                    socket_server: ExtendedOption::Enabled(SocketServerConfig {
                                        interface: "0.0.0.0".to_string(),
                                        port: 9758,
-                                       workers: 1,
+                                       transport: SocketTransport::Plain,
+                                       wire_format: SocketWireFormat::Ron,
+                                       processor: SocketProcessorStrategy::Serial,
+                                       flush_timeout_millis: 5000,
+                                       overflow_policy: SocketOverflowPolicy::ReplyTooBusy,
+                                       panic_policy: SocketPanicPolicy::Isolate,
+                                       max_tracked_endpoints: 1024,
+                                       wire_dump: ExtendedOption::Disabled,
+                                       max_message_length: 8192,
+                                       max_malformed_messages: 16,
+                                       ban_seconds: 60,
+                                       ingress_limit: ExtendedOption::Disabled,
+                                       login: ExtendedOption::Disabled,
+                                       egress_limit: ExtendedOption::Disabled,
+                                       max_connections: 1024,
+                                       per_client_send_queue: ExtendedOption::Disabled,
+                                       keepalive: ExtendedOption::Disabled,
+                                       subscriptions: ExtendedOption::Disabled,
                                    }),
+                                   socket_client: ExtendedOption::Disabled,
+                                   grpc: ExtendedOption::Disabled,
                                }
                            ),
             tokio_threads: 0,
+            timezone:      None,
+            leader_election: ExtendedOption::Disabled,
+            locks:         ExtendedOption::Disabled,
+            degrade:       ExtendedOption::Enabled(DegradeConfig {
+                               disable_telegram_notifications: false,
+                               read_only_api:                  false,
+                               maintenance:                    false,
+                               maintenance_message:            String::from("We are under maintenance -- please, come back in a few minutes"),
+                           }),
+            webhooks:      ExtendedOption::Disabled,
+            auth:          ExtendedOption::Disabled,
+            job_queue:     ExtendedOption::Disabled,
+            metrics_history: ExtendedOption::Disabled,
+            run_as:        ExtendedOption::Disabled,
+            hot_reload:    ExtendedOption::Disabled,
+            scheduler:     ExtendedOption::Disabled,
+            daemon:        ExtendedOption::Disabled,
+            storage:       ExtendedOption::Disabled,
+            outbox:        ExtendedOption::Disabled,
+            supervisor:    ExtendedOption::Disabled,
+            telemetry:     ExtendedOption::Disabled,
+            self_monitor:  ExtendedOption::Disabled,
             ui:            ExtendedOption::Enabled(UiOptions::Console(Jobs::Daemon)),
         }
     }
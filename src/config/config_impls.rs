@@ -1,6 +1,14 @@
 //! Contains implementations (of business rules) on any models declared in `config_models.rs`
 
 use super::*;
+use std::{
+    fmt,
+    str::FromStr,
+};
+use serde::{
+    Deserialize, Deserializer,
+    de::{Visitor, SeqAccess, MapAccess, value::{SeqAccessDeserializer, MapAccessDeserializer}},
+};
 
 
 impl Config {
@@ -9,7 +17,7 @@ impl Config {
     /// -- in this case, special care should be taken so that log messages don't get mangled with the output
     /// (for instance, waits must be set)
     pub fn is_console_output_shared(&self) -> bool {
-        if let LoggingOptions::ToConsole = self.log {
+        if let LoggingOptions::ToConsole | LoggingOptions::ToConsolePretty { .. } = self.log {
             self.services.telegram.is_enabled() ||
             self.services.web.is_enabled() /*||
             self.ogre_workers.is_enabled()*/
@@ -18,3 +26,77 @@ impl Config {
         }
     }
 }
+
+impl TelegramConfig {
+
+    /// the name of the bots not naming themselves -- like the single one of the config files predating multiple bots
+    pub const DEFAULT_NAME: &'static str = "main";
+
+    /// see [TelegramConfig::DEFAULT_NAME]
+    pub fn default_name() -> String {
+        String::from(Self::DEFAULT_NAME)
+    }
+}
+
+/// deserializes [ServicesConfig::telegram] from either a list of bots or a single one -- keeping the config files written before
+/// multiple bots were supported, like `telegram: Enabled((token: "...", bot: Stateless, ...))`, loadable
+pub fn deserialize_telegram_bots<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ExtendedOption<Vec<TelegramConfig>>, D::Error> {
+
+    struct OneOrMany(Vec<TelegramConfig>);
+
+    impl<'de> Deserialize<'de> for OneOrMany {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(OneOrManyVisitor)
+        }
+    }
+
+    struct OneOrManyVisitor;
+
+    impl<'de> Visitor<'de> for OneOrManyVisitor {
+        type Value = OneOrMany;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of Telegram bots -- or a single one")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, bots: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(bots))
+                .map(OneOrMany)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, bot: A) -> Result<Self::Value, A::Error> {
+            TelegramConfig::deserialize(MapAccessDeserializer::new(bot))
+                .map(|bot| OneOrMany(vec![bot]))
+        }
+    }
+
+    Ok(match ExtendedOption::<OneOrMany>::deserialize(deserializer)? {
+        ExtendedOption::Unset                    => ExtendedOption::Unset,
+        ExtendedOption::Disabled                 => ExtendedOption::Disabled,
+        ExtendedOption::Enabled(OneOrMany(bots)) => ExtendedOption::Enabled(bots),
+    })
+}
+
+impl UiOptions {
+
+    /// tells if the services (Telegram, web & socket server) should run along with this UI -- client jobs,
+    /// like [Jobs::Tail], [Jobs::Schedule], [Jobs::Switch] & the remote Terminal UI, talk to an already running instance instead, while [Jobs::Doctor] checks the environment they would run in
+    pub fn runs_services(&self) -> bool {
+        !matches!(self, UiOptions::Console(Jobs::Tail(_) | Jobs::Schedule(_) | Jobs::Switch(_) | Jobs::Doctor(_)) | UiOptions::Terminal(TerminalOptions { connect: Some(_), .. }))
+    }
+}
+
+/// Parses the job names given to [Jobs::Run] -- sequences may not be nested
+impl FromStr for Jobs {
+    type Err = String;
+
+    fn from_str(job_name: &str) -> Result<Self, Self::Err> {
+        match job_name.trim() {
+            "daemon"              => Ok(Jobs::Daemon),
+            "check-config"        => Ok(Jobs::CheckConfig),
+            "generate-client-sdk" => Ok(Jobs::GenerateClientSdk),
+            "repl"                => Ok(Jobs::Repl),
+            unknown => Err(format!("Unknown job '{}' -- known ones are: daemon, check-config, generate-client-sdk & repl", unknown)),
+        }
+    }
+}
@@ -3,7 +3,7 @@
 use super::{config,*};
 use std::fs;
 use regex::Regex;
-use crate::LoggingOptions;
+use sha2::{Sha256, Digest};
 
 
 /// Returns the result of merging the given `low_priority` and `high_priority` configs,
@@ -21,18 +21,40 @@ pub fn merge_configs(mut low_priority: Config, mut high_priority: Config) -> Con
     // case: file logging is partially specified in the high priority -- pieces of the low priority (or default values) fills in
     if let LoggingOptions::ToFile { file_path: ref _file_path, rotation_size: mut _rotation_size, rotations_kept: mut _rotations_kept, compress_rotated: mut _compress_rotated } = high_priority.log {
         if _rotation_size == 0 {
-            if let LoggingOptions::ToFile { file_path: _l_file_path, rotation_size: l_rotation_size, rotations_kept: l_rotations_kept, compress_rotated: l_compress_rotated } = low_priority.log {
+            if let LoggingOptions::ToFile { file_path: ref _l_file_path, rotation_size: l_rotation_size, rotations_kept: l_rotations_kept, compress_rotated: l_compress_rotated } = low_priority.log {
                 _rotation_size    = l_rotation_size;
                 _rotations_kept   = l_rotations_kept;
                 _compress_rotated = l_compress_rotated;
             } else {
                 _rotation_size    = 1024*1024*1024;
                 _rotations_kept   = 64;
-                _compress_rotated = true;
+                _compress_rotated = LogCompression::Gzip;
             }
         }
     }
 
+    // case: console logging in the high priority (the command line's default) -- the low priority's pretty format, if set, is kept.
+    // `--log-format` is applied after the merge -- see `command_line::effective_config()`
+    if let (LoggingOptions::ToConsole, LoggingOptions::ToConsolePretty { .. }) = (&high_priority.log, &low_priority.log) {
+        high_priority.log = std::mem::replace(&mut low_priority.log, LoggingOptions::Quiet);
+    }
+
+    // case: log filters are, currently, only definable in the `low_priority`
+    if high_priority.log_filters.is_empty() {
+        high_priority.log_filters = std::mem::take(&mut low_priority.log_filters);
+    }
+    if high_priority.log_redactions.is_empty() {
+        high_priority.log_redactions = std::mem::take(&mut low_priority.log_redactions);
+    }
+    if let ExtendedOption::Unset = high_priority.log_sampling {
+        high_priority.log_sampling = std::mem::replace(&mut low_priority.log_sampling, ExtendedOption::Unset);
+    }
+
+    // case: the access log is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.access_log {
+        high_priority.access_log = std::mem::replace(&mut low_priority.access_log, ExtendedOption::Unset);
+    }
+
     // TODO: case fix: command-line always specifies a UI... so there is no point in having it into the config file
     //high_priority.ui = high_priority.ui;
 
@@ -41,30 +63,36 @@ pub fn merge_configs(mut low_priority: Config, mut high_priority: Config) -> Con
         high_priority.services = ExtendedOption::Enabled(ServicesConfig {
             web:           ExtendedOption::Unset,
             socket_server: ExtendedOption::Unset,
-            telegram:      ExtendedOption::Unset
+            socket_client: ExtendedOption::Unset,
+            telegram:      ExtendedOption::Unset,
+            grpc:          ExtendedOption::Unset,
         });
     }
     if !low_priority.services.is_enabled() {
         low_priority.services = ExtendedOption::Enabled(ServicesConfig {
             web:           ExtendedOption::Unset,
             socket_server: ExtendedOption::Unset,
-            telegram:      ExtendedOption::Unset
+            socket_client: ExtendedOption::Unset,
+            telegram:      ExtendedOption::Unset,
+            grpc:          ExtendedOption::Unset,
         });
     }
 
-    // case: Telegram service is, currently, only definable in the `low_priority`
-    if let ExtendedOption::Enabled(l_telegram) = &low_priority.services.telegram {
-        high_priority.services.telegram = ExtendedOption::Enabled(l_telegram.clone());
+    // case: services are only definable in the `low_priority` -- the `high_priority` may, though, disable them (see `--disable-web` & friends)
+    if let ExtendedOption::Unset = high_priority.services.telegram {
+        high_priority.services.telegram = low_priority.services.telegram.clone();
     }
-
-    // case: Rocket service is, currently, only definable in the `low_priority`
-    if let ExtendedOption::Enabled(l_web) = &low_priority.services.web {
-        high_priority.services.web = ExtendedOption::Enabled(l_web.clone());
+    if let ExtendedOption::Unset = high_priority.services.web {
+        high_priority.services.web = low_priority.services.web.clone();
     }
-
-    // case: Socket server is, currently, only definable in the `low_priority`
-    if let ExtendedOption::Enabled(l_socket_server) = &low_priority.services.socket_server {
-        high_priority.services.socket_server = ExtendedOption::Enabled(l_socket_server.clone());
+    if let ExtendedOption::Unset = high_priority.services.socket_server {
+        high_priority.services.socket_server = low_priority.services.socket_server.clone();
+    }
+    if let ExtendedOption::Unset = high_priority.services.socket_client {
+        high_priority.services.socket_client = low_priority.services.socket_client.clone();
+    }
+    if let ExtendedOption::Unset = high_priority.services.grpc {
+        high_priority.services.grpc = low_priority.services.grpc.clone();
     }
 
     // case: tokio_threads: defaults to 0 -- considered as unset if < 0
@@ -76,6 +104,91 @@ pub fn merge_configs(mut low_priority: Config, mut high_priority: Config) -> Con
         0
     };
 
+    // case: timezone: the system's if unset in both
+    if high_priority.timezone.is_none() {
+        high_priority.timezone = low_priority.timezone.take();
+    }
+
+    // case: leader election is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.leader_election {
+        high_priority.leader_election = std::mem::replace(&mut low_priority.leader_election, ExtendedOption::Unset);
+    }
+
+    // case: locks are, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.locks {
+        high_priority.locks = std::mem::replace(&mut low_priority.locks, ExtendedOption::Unset);
+    }
+
+    // case: degradation switches are, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.degrade {
+        high_priority.degrade = std::mem::replace(&mut low_priority.degrade, ExtendedOption::Unset);
+    }
+
+    // case: webhooks are, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.webhooks {
+        high_priority.webhooks = std::mem::replace(&mut low_priority.webhooks, ExtendedOption::Unset);
+    }
+
+    // case: auth is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.auth {
+        high_priority.auth = std::mem::replace(&mut low_priority.auth, ExtendedOption::Unset);
+    }
+
+    // case: the job queue is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.job_queue {
+        high_priority.job_queue = std::mem::replace(&mut low_priority.job_queue, ExtendedOption::Unset);
+    }
+
+    // case: metrics_history
+    if let ExtendedOption::Unset = high_priority.metrics_history {
+        high_priority.metrics_history = std::mem::replace(&mut low_priority.metrics_history, ExtendedOption::Unset);
+    }
+
+    // case: the user to run as is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.run_as {
+        high_priority.run_as = std::mem::replace(&mut low_priority.run_as, ExtendedOption::Unset);
+    }
+
+    // case: the config hot reload is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.hot_reload {
+        high_priority.hot_reload = std::mem::replace(&mut low_priority.hot_reload, ExtendedOption::Unset);
+    }
+
+    // case: Scheduler
+    if let ExtendedOption::Unset = high_priority.scheduler {
+        high_priority.scheduler = std::mem::replace(&mut low_priority.scheduler, ExtendedOption::Unset);
+    }
+
+    // case: the background mode
+    if let ExtendedOption::Unset = high_priority.daemon {
+        high_priority.daemon = std::mem::replace(&mut low_priority.daemon, ExtendedOption::Unset);
+    }
+
+    // case: the storage is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.storage {
+        high_priority.storage = std::mem::replace(&mut low_priority.storage, ExtendedOption::Unset);
+    }
+
+    // case: the outbox is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.outbox {
+        high_priority.outbox = std::mem::replace(&mut low_priority.outbox, ExtendedOption::Unset);
+    }
+
+    // case: the supervisor is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.supervisor {
+        high_priority.supervisor = std::mem::replace(&mut low_priority.supervisor, ExtendedOption::Unset);
+    }
+
+    // case: the telemetry is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.telemetry {
+        high_priority.telemetry = std::mem::replace(&mut low_priority.telemetry, ExtendedOption::Unset);
+    }
+
+    // case: the self-monitor is, currently, only definable in the `low_priority`
+    if let ExtendedOption::Unset = high_priority.self_monitor {
+        high_priority.self_monitor = std::mem::replace(&mut low_priority.self_monitor, ExtendedOption::Unset);
+    }
+
     // APP's merges goes here
     /////////////////////////
 
@@ -103,12 +216,70 @@ pub fn load_or_create_default(config_file_path: &str) -> Result<Config, Box<dyn
 }
 
 /// loads the application-wide configuration from the given `config_file_path`, if possible
-fn load_from_file(config_file_path: &str) -> Result<Config, Box<dyn std::error::Error>> {
+/// -- expanding the `${ENV_VAR}` placeholders in its strings (see [interpolate_env_vars()])
+pub fn load_from_file(config_file_path: &str) -> Result<Config, Box<dyn std::error::Error>> {
     let ron_file_contents = fs::read_to_string(config_file_path)?;
+    let ron_file_contents = interpolate_env_vars(&ron_file_contents, |var_name| std::env::var(var_name).ok())
+        .map_err(|err| format!("config_ops.rs: Error expanding the environment variables in file '{}': {}", config_file_path, err))?;
     let ron_options = ron::Options::default()
         .with_default_extension(ron_extensions());
     ron_options.from_str(&ron_file_contents)
-        .map_err(|err| Box::from(format!("config_ops.rs: Error deserializing contents of file '{}' as RON: {} -- HINT: delete the config file and let it be regenerated with all the default options", config_file_path, err)))
+        .map_err(|err| Box::from(format!("config_ops.rs: Error deserializing contents of file '{}' as RON: {} -- HINT: fix the reported line & column (the possible values are documented at the end of the file) -- or backup & delete the config file to have it regenerated with all the default options", config_file_path, err)))
+}
+
+/// Expands the `${ENV_VAR}` placeholders found inside the string literals of `ron_contents` with the values given by `lookup()`
+/// -- comments are left untouched. `$${` escapes a literal `${`.\
+/// Fails, telling the line, if `lookup()` doesn't know a variable -- or if a placeholder is not closed
+fn interpolate_env_vars(ron_contents: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut interpolated = String::with_capacity(ron_contents.len());
+    let mut rest = ron_contents;
+    let mut in_string = false;
+    let line = |rest: &str| ron_contents[..ron_contents.len() - rest.len()].matches('\n').count() + 1;
+    while let Some(c) = rest.chars().next() {
+        let consumed = if in_string {
+            if rest.starts_with("$${") {
+                interpolated.push_str("${");
+                3
+            } else if let Some(placeholder) = rest.strip_prefix("${") {
+                let end = placeholder.find(['}', '"'])
+                    .filter(|&end| placeholder[end..].starts_with('}'))
+                    .ok_or_else(|| format!("line {}: unclosed `${{` placeholder -- use `$${{` for a literal `${{`", line(rest)))?;
+                let var_name = &placeholder[..end];
+                let value = lookup(var_name)
+                    .ok_or_else(|| format!("line {}: environment variable `{}` is not set", line(rest), var_name))?;
+                // the value lands inside a RON string
+                interpolated.push_str(&value.replace('\\', "\\\\").replace('"', "\\\""));
+                2 + end + 1
+            } else if c == '\\' {
+                // copies escape sequences verbatim -- so `\"` doesn't end the string
+                let escaped_len = rest[1..].chars().next().map_or(0, char::len_utf8);
+                interpolated.push_str(&rest[..1 + escaped_len]);
+                1 + escaped_len
+            } else {
+                in_string = c != '"';
+                interpolated.push(c);
+                c.len_utf8()
+            }
+        } else {
+            let comment_len = if rest.starts_with("//") {
+                rest.find('\n').unwrap_or(rest.len())
+            } else if rest.starts_with("/*") {
+                rest.find("*/").map_or(rest.len(), |end| end + 2)
+            } else {
+                0
+            };
+            if comment_len > 0 {
+                interpolated.push_str(&rest[..comment_len]);
+                comment_len
+            } else {
+                in_string = c == '"';
+                interpolated.push(c);
+                c.len_utf8()
+            }
+        };
+        rest = &rest[consumed..];
+    }
+    Ok(interpolated)
 }
 
 /// transcription of the config model, for documentation purposes when writing the default config file
@@ -116,7 +287,7 @@ const CONFIG_MODELS_DOCS: &str = include_str!("config.rs");
 
 /// saves the application-wide `config` to `config_file_path`,
 /// including documentation from the original [config_model] sources
-fn save_to_file(config: &Config, config_file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub fn save_to_file(config: &Config, config_file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
     let data_section = ron::ser::to_string_pretty(
         &config,
         ron::ser::PrettyConfig::new()
@@ -142,6 +313,17 @@ fn save_to_file(config: &Config, config_file_path: &str) -> Result<(), Box<dyn s
         .map_err(|err| Box::from(format!("config_ops.rs: Error writing default RON config to file '{}': {}", config_file_path, err)))
 }
 
+/// Identifies the given `config` in logs & metrics -- the first 16 hex digits of the SHA-256 of its RON representation.\
+/// Used on the effective (merged) config, so operators may tell if two instances (or two runs) share the same settings
+/// -- and if the config file was edited without being applied (see [crate::runtime::monitor_config_drift()])
+pub fn digest(config: &Config) -> String {
+    let ron = ron::ser::to_string(config)
+        .expect("BUG! configs should always be serializable");
+    let mut digest = hex::encode(Sha256::digest(ron.as_bytes()));
+    digest.truncate(16);
+    digest
+}
+
 /// builds & returns the RON extensions used to load and save our .ron files
 fn ron_extensions() -> ron::extensions::Extensions {
     let mut extensions = ron::extensions::Extensions::empty();
@@ -156,6 +338,7 @@ fn ron_extensions() -> ron::extensions::Extensions {
 #[cfg(any(test, feature = "dox"))]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
 
     const TEST_CONFIG_FILE: &str = "/tmp/kickass-app-template-tests.config.ron";
 
@@ -175,8 +358,9 @@ mod tests {
             .expect("Could not save config file");
 
         // load the -- now existing -- file
-        let _result = load_from_file(TEST_CONFIG_FILE)
+        let result = load_from_file(TEST_CONFIG_FILE)
             .expect("Could not load config file just created by save. Does RON have a bug or its default saving & loading parameters needs tweaking?");
+        assert_eq!(digest(&result), digest(&Config::default()), "The digest of the saved & loaded config should match the original one -- or drifts would be falsely reported");
 
         // check load_or_create_default() for existing file
         let _result = load_or_create_default(TEST_CONFIG_FILE)
@@ -189,6 +373,25 @@ mod tests {
             .expect("Could not load_or_create_default() for a non existing file");
     }
 
+    /// assures placeholders are expanded only inside strings -- along with escaping & error reporting
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn env_vars_interpolation() {
+        let lookup = |var_name: &str| match var_name {
+            "HOST"  => Some(String::from("db.example.com")),
+            "TOKEN" => Some(String::from(r#"a"b\c"#)),
+            _ => None,
+        };
+        let ron = r#"(url: "redis://${HOST}:6379/", token: "${TOKEN}", literal: "$${HOST}", escaped: "\"${HOST}\"") // ${UNSET} in comments is ignored"#;
+        assert_eq!(interpolate_env_vars(ron, lookup).expect("Interpolation should have worked"),
+                   r#"(url: "redis://db.example.com:6379/", token: "a\"b\\c", literal: "${HOST}", escaped: "\"db.example.com\"") // ${UNSET} in comments is ignored"#);
+        let err = interpolate_env_vars("(\n  url: \"${UNSET}\",\n)", lookup)
+            .expect_err("Missing variables should be reported");
+        assert_eq!(err, "line 2: environment variable `UNSET` is not set");
+        let err = interpolate_env_vars(r#"(url: "${HOST")"#, lookup)
+            .expect_err("Unclosed placeholders should be reported");
+        assert!(err.contains("unclosed"), "Unexpected error message: {}", err);
+    }
+
     /// assures [merge_configs()] addresses all cases
     #[test]
     fn merging_completenes() {
@@ -196,8 +399,29 @@ mod tests {
         // checks high priority is honored
         let low = Config {
             log:           LoggingOptions::Quiet,
+            log_filters:   BTreeMap::new(),
+            log_redactions: vec![],
+            log_sampling:  ExtendedOption::Unset,
+            access_log:    ExtendedOption::Unset,
             services:      ExtendedOption::Unset,
             tokio_threads: 0,
+            timezone:      None,
+            leader_election: ExtendedOption::Unset,
+            locks:         ExtendedOption::Unset,
+            degrade:       ExtendedOption::Unset,
+            webhooks:      ExtendedOption::Unset,
+            auth:          ExtendedOption::Unset,
+            job_queue:     ExtendedOption::Unset,
+            metrics_history: ExtendedOption::Unset,
+            run_as:        ExtendedOption::Unset,
+            hot_reload:    ExtendedOption::Unset,
+            scheduler:     ExtendedOption::Unset,
+            daemon:        ExtendedOption::Unset,
+            storage:       ExtendedOption::Unset,
+            outbox:        ExtendedOption::Unset,
+            supervisor:    ExtendedOption::Unset,
+            telemetry:     ExtendedOption::Unset,
+            self_monitor:  ExtendedOption::Unset,
             ui:            ExtendedOption::Unset,
 
         };
@@ -210,8 +434,29 @@ mod tests {
         let low = Config::default();
         let high = Config {
             log:           LoggingOptions::ToConsole,
+            log_filters:   BTreeMap::new(),
+            log_redactions: vec![],
+            log_sampling:  ExtendedOption::Unset,
+            access_log:    ExtendedOption::Unset,
             services:      ExtendedOption::Unset,
             tokio_threads: 0,
+            timezone:      None,
+            leader_election: ExtendedOption::Unset,
+            locks:         ExtendedOption::Unset,
+            degrade:       ExtendedOption::Unset,
+            webhooks:      ExtendedOption::Unset,
+            auth:          ExtendedOption::Unset,
+            job_queue:     ExtendedOption::Unset,
+            metrics_history: ExtendedOption::Unset,
+            run_as:        ExtendedOption::Unset,
+            hot_reload:    ExtendedOption::Unset,
+            scheduler:     ExtendedOption::Unset,
+            daemon:        ExtendedOption::Unset,
+            storage:       ExtendedOption::Unset,
+            outbox:        ExtendedOption::Unset,
+            supervisor:    ExtendedOption::Unset,
+            telemetry:     ExtendedOption::Unset,
+            self_monitor:  ExtendedOption::Unset,
             ui:            ExtendedOption::Unset,
 
         };
@@ -221,4 +466,56 @@ mod tests {
 
     }
 
+    /// assures services defined in the `low_priority` may be disabled by the `high_priority` -- like through `--disable-web`
+    #[test]
+    fn disabling_services() {
+        let low = Config::default();
+        let high = Config {
+            services: ExtendedOption::Enabled(ServicesConfig {
+                web:           ExtendedOption::Disabled,
+                socket_server: ExtendedOption::Unset,
+                socket_client: ExtendedOption::Unset,
+                telegram:      ExtendedOption::Unset,
+                grpc:          ExtendedOption::Unset,
+            }),
+            ..Config::default()
+        };
+        let merged = merge_configs(low, high);
+        assert_eq!(merged.services.web, ExtendedOption::Disabled, "The web service should have been disabled by the high priority config");
+        assert_eq!(merged.services.socket_server, Config::default().services.socket_server, "The socket server should have been kept from the low priority config");
+    }
+
+    /// assures the single Telegram bot of the config files predating multiple bots is still loaded -- as the only one, named "main"
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn single_telegram_bot() {
+        let ron_options = ron::Options::default()
+            .with_default_extension(ron_extensions());
+        let single_bot = r#"(
+            web: Unset, socket_server: Unset, socket_client: Unset, grpc: Unset,
+            telegram: Enabled((
+                token: "123456789:AAEhBP0av28uw6mhkB5-RtXmEyIMX3tTaEU",
+                bot: Stateful,
+                notification_chat_ids: [9999999999],
+                dialogue_storage: InMemory,
+                command_limits: (max_commands_per_minute: 20, max_commands_per_day: 0),
+                mt_limits: (max_queued_messages: 1000, max_messages_per_second: 25, min_chat_interval_millis: 1000),
+                status_report_cron: "0 9 * * *",
+            )),
+        )"#;
+        let services: ServicesConfig = ron_options.from_str(single_bot)
+            .expect("The single bot shape should have been accepted");
+        let ExtendedOption::Enabled(telegram_bots) = &services.telegram else { panic!("Telegram should have been enabled -- got {:?}", services.telegram) };
+        assert_eq!(telegram_bots.len(), 1, "The single bot should have become the only one");
+        assert_eq!(telegram_bots[0].name, TelegramConfig::DEFAULT_NAME, "The single bot should have been given the default name");
+        assert_eq!(telegram_bots[0].status_report_cron.as_deref(), Some("0 9 * * *"), "The single bot's fields should have been kept");
+
+        // the list shape -- as saved -- loads back the same
+        let saved = ron::ser::to_string_pretty(&services, ron::ser::PrettyConfig::new().extensions(ron_extensions()))
+            .expect("Serializing the services config");
+        assert!(saved.contains("telegram: Enabled(["), "The bots should have been saved as a list: {}", saved);
+        let reloaded: ServicesConfig = ron_options.from_str(&saved)
+            .expect("The saved list of bots should have been loaded back");
+        assert_eq!(reloaded, services, "The bots should have survived the round trip");
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,333 @@
+//! Checks the effective config for the problems the deserialization can't catch -- malformed Telegram tokens & chat ids, services
+//! competing for the same port, invalid regexes, ... -- reporting them all at once, each with its field path & how to fix it.\
+//! Ran by `main.rs` before anything starts (& on every config reload), as these would otherwise only show up later, one at a time,
+//! as obscure runtime failures
+
+use super::*;
+use std::{
+    fmt,
+    path::Path,
+};
+use regex::Regex;
+
+
+/// Telegram chat ids are at most 52 bits long -- counting the `-100` prefix of supergroups & channels
+const MAX_TELEGRAM_CHAT_ID: u64 = 1 << 52;
+
+/// Admin tokens shorter than this are easily guessed -- see [WebConfig::admin_token]
+const MIN_ADMIN_TOKEN_LENGTH: usize = 32;
+
+/// The services' names, as in [SupervisorConfig::restart_policies]
+const SUPERVISED_SERVICES: &[&str] = &["telegram", "web", "socket-server", "socket-client", "grpc"];
+
+
+/// A problem found by [validate()]
+#[derive(Debug, PartialEq)]
+pub struct ConfigProblem {
+    /// where the problem is, as in the config file -- example: `services.telegram[0].token`
+    pub field_path: String,
+    pub problem:    String,
+    /// a suggestion on how to fix it
+    pub fix:        String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -- HINT: {}", self.field_path, self.problem, self.fix)
+    }
+}
+
+/// Returns all the problems found in `config` -- which should be the effective one, as the command line may change it
+pub fn validate(config: &Config) -> Result<(), Vec<ConfigProblem>> {
+    let mut problems = vec![];
+    let mut problem = |field_path: String, problem: String, fix: &str| problems.push(ConfigProblem { field_path, problem, fix: fix.to_string() });
+
+    if let LoggingOptions::ToFile { file_path, .. } = &config.log {
+        if file_path.trim().is_empty() {
+            problem(String::from("log.file_path"), String::from("the log file is not set"),
+                    "set it to a writable path -- or log to the console: `log: ToConsole`");
+        }
+    }
+    for (i, pattern) in config.log_redactions.iter().enumerate() {
+        if let Err(err) = Regex::new(pattern) {
+            problem(format!("log_redactions[{}]", i), format!("'{}' is not a valid regex: {}", pattern, err),
+                    "fix the expression -- use `regex::escape()`d literals (`\\.`, `\\+`, ...) for the special characters");
+        }
+    }
+
+    if let Some(timezone) = &config.timezone {
+        if timezone.parse::<chrono_tz::Tz>().is_err() {
+            problem(String::from("timezone"), format!("'{}' is not an IANA timezone", timezone),
+                    "use a name from the tz database, like \"America/Sao_Paulo\" or \"UTC\" -- or `None`, for the system's local timezone");
+        }
+    }
+
+    if let ExtendedOption::Enabled(AuthConfig::Static { identities }) = &config.auth {
+        for (i, identity) in identities.iter().enumerate() {
+            if !identity.password_hash.is_empty() && argon2::PasswordHash::new(&identity.password_hash).is_err() {
+                problem(format!("auth.identities[{}].password_hash", i), String::from("the password hash is not an argon2 PHC string"),
+                        "set it to the output of `echo -n '<password>' | argon2 \"$(openssl rand -base64 12)\" -id -e` -- or leave it empty, not to allow password logins");
+            }
+        }
+    }
+
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(telegram_bots) = &services.telegram {
+            if telegram_bots.is_empty() {
+                problem(String::from("services.telegram"), String::from("no bots are listed"),
+                        "list at least one bot -- or disable the service: `telegram: Disabled` or `--disable-telegram`");
+            }
+            for (i, telegram) in telegram_bots.iter().enumerate() {
+                if telegram.name.trim().is_empty() {
+                    problem(format!("services.telegram[{}].name", i), String::from("the name is empty"),
+                            "name the bot -- like \"main\" or \"support\" -- so the business logic may address it");
+                } else if telegram_bots[..i].iter().any(|other| other.name == telegram.name) {
+                    problem(format!("services.telegram[{}].name", i), format!("'{}' names another bot as well", telegram.name),
+                            "give each bot a name of its own -- the unnamed ones are called \"main\"");
+                }
+                if telegram.token.trim().is_empty() {
+                    problem(format!("services.telegram[{}].token", i), String::from("the token is empty"),
+                            "set it to the token given by Telegram's @BotFather -- or disable the service: `telegram: Disabled` or `--disable-telegram`");
+                } else if !is_telegram_token(&telegram.token) {
+                    problem(format!("services.telegram[{}].token", i), String::from("the token is not in the '<bot id>:<secret>' format"),
+                            "set it to the token given by Telegram's @BotFather, like \"123456789:AAEhBP0av28uw6mhkB5-RtXmEyIMX3tTaEU\" -- or disable the service: `telegram: Disabled` or `--disable-telegram`");
+                }
+                for (j, chat_id) in telegram.notification_chat_ids.iter().enumerate() {
+                    if *chat_id == 0 || chat_id.unsigned_abs() > MAX_TELEGRAM_CHAT_ID {
+                        problem(format!("services.telegram[{}].notification_chat_ids[{}]", i, j), format!("{} is not a Telegram chat id", chat_id),
+                                "use the ids of users (positive), groups (negative) or supergroups & channels (negative, starting with -100) -- as told by bots like @userinfobot");
+                    }
+                }
+            }
+        }
+
+        // the TCP ports each service listens to
+        let mut listeners = vec![];
+        if let ExtendedOption::Enabled(WebConfig { rocket_config: RocketConfigOptions::Provided { http_port, .. }, .. }) = &services.web {
+            listeners.push(("services.web.rocket_config.http_port", "0.0.0.0", *http_port));
+        }
+        if let ExtendedOption::Enabled(socket_server) = &services.socket_server {
+            if !matches!(socket_server.transport, SocketTransport::Udp { .. } | SocketTransport::Unix { .. }) {
+                listeners.push(("services.socket_server.port", socket_server.interface.as_str(), socket_server.port));
+            }
+            if let ExtendedOption::Enabled(keepalive) = &socket_server.keepalive {
+                if keepalive.interval_millis == 0 {
+                    problem(String::from("services.socket_server.keepalive.interval_millis"), String::from("keepalives can't be sent continuously"),
+                            "set it to how often the clients should be probed -- example: 15000");
+                }
+            }
+            if let ExtendedOption::Enabled(subscriptions) = &socket_server.subscriptions {
+                if subscriptions.topics.is_empty() {
+                    problem(String::from("services.socket_server.subscriptions.topics"), String::from("no topics may be subscribed to"),
+                            "list the event bus topics clients may follow -- example: [\"alerts\"]");
+                }
+            }
+            if let SocketTransport::Unix { socket_file } = &socket_server.transport {
+                if cfg!(not(unix)) {
+                    problem(String::from("services.socket_server.transport"), String::from("Unix sockets are only available on Unix"),
+                            "use the `Plain` transport, listening to the loopback interface: `interface: \"127.0.0.1\"`");
+                } else if socket_file.trim().is_empty() {
+                    problem(String::from("services.socket_server.transport.socket_file"), String::from("the socket file is not set"),
+                            "set it to a path the application may create files in -- example: \"/run/kickass-app-template.sock\"");
+                }
+            }
+        }
+        if let ExtendedOption::Enabled(grpc) = &services.grpc {
+            listeners.push(("services.grpc.port", grpc.interface.as_str(), grpc.port));
+        }
+        for (i, (field_path, interface, port)) in listeners.iter().enumerate() {
+            let colliding = listeners[..i].iter()
+                .find(|(_, other_interface, other_port)| *port != 0 && port == other_port && interfaces_overlap(interface, other_interface));
+            if let Some((other_field_path, _, _)) = colliding {
+                problem(field_path.to_string(), format!("port {} is also used by `{}`", port, other_field_path),
+                        "give each service its own port -- or use `--http-port`, `--socket-port` & `--grpc-port` to change them for this run");
+            }
+        }
+
+        if let ExtendedOption::Enabled(socket_client) = &services.socket_client {
+            let port = socket_client.server_address.rsplit_once(':')
+                .and_then(|(host, port)| (!host.is_empty()).then_some(port))
+                .and_then(|port| port.parse::<u16>().ok());
+            if port.is_none() {
+                problem(String::from("services.socket_client.server_address"), format!("'{}' is not in the 'host:port' format", socket_client.server_address),
+                        "set it to the other instance's socket server address -- example: \"127.0.0.1:9758\"");
+            }
+        }
+
+        if let ExtendedOption::Enabled(web) = &services.web {
+            for (name, hook) in &web.inbound_hooks {
+                if hook.secret.is_empty() {
+                    problem(format!("services.web.inbound_hooks[\"{}\"].secret", name), String::from("the secret is empty, so any signature would be accepted"),
+                            "set it to the secret shared with the sender of the hook");
+                }
+            }
+            if let Some(admin_token) = &web.admin_token {
+                if admin_token.len() < MIN_ADMIN_TOKEN_LENGTH {
+                    problem(String::from("services.web.admin_token"), format!("the token has less than {} characters", MIN_ADMIN_TOKEN_LENGTH),
+                            "use a random one -- like the output of `openssl rand -hex 32` -- or disable the admin API: `admin_token: None`");
+                }
+            }
+            if let Some(static_files_dir) = &web.static_files_dir {
+                if !web.web_app {
+                    problem(String::from("services.web.static_files_dir"), String::from("the static files are only served along with the web app, which is disabled"),
+                            "enable it -- `web_app: true` -- or unset the directory: `static_files_dir: None`");
+                } else if !Path::new(static_files_dir).is_dir() {
+                    problem(String::from("services.web.static_files_dir"), format!("'{}' is not a directory", static_files_dir),
+                            "point it to the built web app -- like \"web-app/dist/kickass-app-template\" -- or serve the embedded files: `static_files_dir: None`");
+                }
+            }
+        }
+    }
+
+    if let ExtendedOption::Enabled(webhooks) = &config.webhooks {
+        for (name, target) in &webhooks.targets {
+            if !target.url.starts_with("http://") && !target.url.starts_with("https://") {
+                problem(format!("webhooks.targets[\"{}\"].url", name), format!("'{}' is not an HTTP(S) URL", target.url),
+                        "use the full URL, starting with \"https://\"");
+            }
+        }
+    }
+
+    if let ExtendedOption::Enabled(scheduler) = &config.scheduler {
+        for (i, job) in scheduler.jobs.iter().enumerate() {
+            if scheduler.jobs[..i].iter().any(|other_job| other_job.name == job.name) {
+                problem(format!("scheduler.jobs[{}].name", i), format!("job '{}' is scheduled more than once", job.name),
+                        "merge the schedules into a single cron expression -- or remove the duplicate");
+            }
+            if let JobSchedule::Every { seconds: 0 } = job.schedule {
+                problem(format!("scheduler.jobs[{}].schedule", i), String::from("jobs can't run every 0 seconds"),
+                        "use at least `Every(seconds: 1)`");
+            }
+        }
+    }
+
+    if let ExtendedOption::Enabled(outbox) = &config.outbox {
+        if !config.storage.is_enabled() {
+            problem(String::from("outbox"), String::from("the outbox persists its intents to the storage, which is disabled"),
+                    "enable the `storage` -- or disable the outbox: `outbox: Disabled`");
+        }
+        if outbox.poll_interval_millis == 0 {
+            problem(String::from("outbox.poll_interval_millis"), String::from("the outbox can't be polled every 0ms"),
+                    "use something like 1000");
+        }
+    }
+
+    if let ExtendedOption::Enabled(supervisor) = &config.supervisor {
+        if supervisor.initial_backoff_millis == 0 || supervisor.initial_backoff_millis > supervisor.max_backoff_millis {
+            problem(String::from("supervisor.initial_backoff_millis"),
+                    format!("{}ms is not between 1ms & `max_backoff_millis` ({}ms)", supervisor.initial_backoff_millis, supervisor.max_backoff_millis),
+                    "use something like 1000 -- services failing right away would, otherwise, be restarted in a busy loop");
+        }
+        for service_name in supervisor.restart_policies.keys() {
+            if !SUPERVISED_SERVICES.contains(&service_name.as_str()) {
+                problem(format!("supervisor.restart_policies[\"{}\"]", service_name), format!("'{}' is not one of the services", service_name),
+                        "use the services' names: \"telegram\", \"web\", \"socket-server\", \"socket-client\" or \"grpc\"");
+            }
+        }
+    }
+
+    if let ExtendedOption::Enabled(self_monitor) = &config.self_monitor {
+        if self_monitor.sampling_interval_seconds == 0 {
+            problem(String::from("self_monitor.sampling_interval_seconds"), String::from("resources can't be sampled every 0 seconds"),
+                    "use something like 10");
+        }
+        if self_monitor.max_cpu_percent < 0.0 {
+            problem(String::from("self_monitor.max_cpu_percent"), format!("{}% is negative", self_monitor.max_cpu_percent),
+                    "use something like 90.0 -- or 0.0 to disable the limit");
+        }
+    }
+
+    if let ExtendedOption::Enabled(telemetry) = &config.telemetry {
+        if !telemetry.otlp_endpoint.starts_with("http://") && !telemetry.otlp_endpoint.starts_with("https://") {
+            problem(String::from("telemetry.otlp_endpoint"), format!("'{}' is not an HTTP(S) URL", telemetry.otlp_endpoint),
+                    "use the collector's OTLP/HTTP address -- example: \"http://localhost:4318\"");
+        }
+        if !(0.0..=1.0).contains(&telemetry.sampling_ratio) {
+            problem(String::from("telemetry.sampling_ratio"), format!("{} is not between 0.0 & 1.0", telemetry.sampling_ratio),
+                    "use 1.0 to export all traces, 0.1 for 10% of them, ...");
+        }
+    }
+
+    // APP's validations goes here
+    //////////////////////////////
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Shows `problems` to the operator -- one per line, preceded by where they came from
+pub fn problems_report(config_file_path: &str, problems: &[ConfigProblem]) -> String {
+    let problems = problems.iter()
+        .map(|problem| format!("  - {}", problem))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("The config (loaded from '{}' & merged with the command line options) has problems:\n{}", config_file_path, problems)
+}
+
+/// tells if `token` looks like the ones given by Telegram's @BotFather: "<bot id>:<secret>"
+pub fn is_telegram_token(token: &str) -> bool {
+    token.split_once(':')
+        .map_or(false, |(bot_id, secret)| !bot_id.is_empty() && bot_id.chars().all(|c| c.is_ascii_digit()) &&
+                                          secret.len() >= 30 && secret.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+}
+
+/// tells if listening to both interfaces could cause a port collision
+fn interfaces_overlap(interface: &str, other_interface: &str) -> bool {
+    let is_any = |interface: &str| ["0.0.0.0", "::", "[::]", ""].contains(&interface);
+    interface == other_interface || is_any(interface) || is_any(other_interface)
+}
+
+
+/// Unit tests the [config_validation](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+    /// the default config, with a well formed Telegram token
+    fn valid_config() -> Config {
+        let mut config = Config::default();
+        config.services.telegram[0].token = String::from("123456789:AAEhBP0av28uw6mhkB5-RtXmEyIMX3tTaEU");
+        config
+    }
+
+    /// the gRPC service -- disabled by default -- listening to `port`
+    fn grpc(port: u16) -> ExtendedOption<GrpcConfig> {
+        ExtendedOption::Enabled(GrpcConfig {
+            interface:  String::from("0.0.0.0"),
+            port,
+            reflection: true,
+        })
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn valid_configs() {
+        assert_eq!(validate(&valid_config()), Ok(()), "The default config, with a Telegram token, should be valid");
+        let mut config = valid_config();
+        config.services.telegram = ExtendedOption::Disabled;
+        config.services.socket_server.transport = SocketTransport::Udp { session_timeout_millis: 1000 };
+        config.services.grpc = grpc(config.services.socket_server.port);
+        assert_eq!(validate(&config), Ok(()), "UDP ports don't collide with TCP ones & disabled services are not validated");
+    }
+
+    /// assures all problems are reported at once -- each with its field path
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn all_problems_are_reported() {
+        let mut config = valid_config();
+        config.services.telegram[0].token = String::from("  ");
+        config.services.telegram[0].notification_chat_ids = vec![123456789, 0, -1001234567890];
+        config.services.grpc = grpc(8000);
+        config.log_redactions.push(String::from("(unclosed"));
+        let problems = validate(&config).expect_err("Problems should have been found");
+        let field_paths: Vec<&str> = problems.iter().map(|problem| problem.field_path.as_str()).collect();
+        assert_eq!(field_paths, vec![
+            "log_redactions[3]",
+            "services.telegram[0].token",
+            "services.telegram[0].notification_chat_ids[1]",
+            "services.grpc.port",
+        ]);
+        assert!(problems[3].problem.contains("services.web.rocket_config.http_port"), "The collision should tell the other service: {}", problems[3]);
+    }
+}
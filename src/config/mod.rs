@@ -8,6 +8,7 @@
 pub mod config;
 pub mod config_impls;
 pub mod config_ops;
+pub mod config_validation;
 pub use config::*;
 
 
@@ -0,0 +1,110 @@
+//! The `console doctor` job (a.k.a. `dry-run`): checks the environment the services would run in -- without starting them -- printing
+//! a pass/fail report. Meant for deployment scripts, as it fails (so the exit code tells) if any of the checks do:
+//!   * the config, as validated on startup -- see [crate::config::config_validation]
+//!   * the ports of the enabled services are bindable
+//!   * the Telegram token is well formed -- & accepted by Telegram, with `--telegram-get-me`
+//!   * the log files (the application's & the access log's) are writable
+
+use crate::config::{Config, ExtendedOption, LoggingOptions, DoctorOptions, RocketConfigOptions, SocketTransport, config_validation};
+use std::fs::OpenOptions;
+use crossterm::style::Stylize;
+use tokio::net::{TcpListener, UdpSocket};
+use teloxide::{Bot, requests::Requester};
+
+
+/// The outcome of one of the checks
+struct Check {
+    name:    String,
+    /// what was found -- `Err` if it should be fixed
+    outcome: Result<String, String>,
+}
+
+pub async fn doctor(options: &DoctorOptions, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let mut checks = vec![];
+    let mut check = |name: &str, outcome: Result<String, String>| checks.push(Check { name: name.to_string(), outcome });
+
+    match config_validation::validate(config) {
+        Ok(()) => check("config", Ok(String::from("no problems found"))),
+        Err(problems) => for problem in problems {
+            check("config", Err(problem.to_string()));
+        },
+    }
+
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(web) = &services.web {
+            match &web.rocket_config {
+                RocketConfigOptions::Provided { http_port, .. } => check("web port", bind_tcp(&format!("0.0.0.0:{}", http_port)).await),
+                RocketConfigOptions::StandardRocketTomlFile => check("web port", Ok(String::from("set in `Rocket.toml` -- not checked"))),
+            }
+        }
+        if let ExtendedOption::Enabled(socket_server) = &services.socket_server {
+            let address = format!("{}:{}", socket_server.interface, socket_server.port);
+            match &socket_server.transport {
+                SocketTransport::Udp { .. }           => check("socket server port", bind_udp(&address).await),
+                SocketTransport::Unix { socket_file } => check("socket server file", Ok(format!("'{}' -- not checked", socket_file))),
+                _                                     => check("socket server port", bind_tcp(&address).await),
+            }
+        }
+        if let ExtendedOption::Enabled(grpc) = &services.grpc {
+            check("gRPC port", bind_tcp(&format!("{}:{}", grpc.interface, grpc.port)).await);
+        }
+        if let ExtendedOption::Enabled(telegram_bots) = &services.telegram {
+            for telegram in telegram_bots {
+                let outcome = if !config_validation::is_telegram_token(&telegram.token) {
+                    Err(String::from("the token is not in the '<bot id>:<secret>' format"))
+                } else if options.telegram_get_me {
+                    Bot::new(&telegram.token).get_me().await
+                        .map(|me| format!("accepted by Telegram, as @{}", me.username()))
+                        .map_err(|err| format!("refused by Telegram: {}", err))
+                } else {
+                    Ok(String::from("well formed -- use `--telegram-get-me` to have Telegram verify it"))
+                };
+                check(&format!("telegram token ({})", telegram.name), outcome);
+            }
+        }
+    }
+
+    if let LoggingOptions::ToFile { file_path, .. } = &config.log {
+        check("log file", writable(file_path));
+    }
+    if let ExtendedOption::Enabled(access_log) = &config.access_log {
+        check("access log file", writable(&access_log.file_path));
+    }
+
+    let failures = checks.iter().filter(|check| check.outcome.is_err()).count();
+    println!("Doctor's report:");
+    for Check { name, outcome } in &checks {
+        let (status, details) = match outcome {
+            Ok(details)  => (if options.no_color { "PASS".to_string() } else { "PASS".green().to_string() }, details),
+            Err(details) => (if options.no_color { "FAIL".to_string() } else { "FAIL".red().bold().to_string() }, details),
+        };
+        println!("  [{}] {:20} {}", status, name, details);
+    }
+    if failures > 0 {
+        Err(Box::from(format!("{} of the {} checks failed", failures, checks.len())))
+    } else {
+        println!("All {} checks passed", checks.len());
+        Ok(())
+    }
+}
+
+/// binds -- then releases -- `address`, telling if the service would be able to listen to it
+async fn bind_tcp(address: &str) -> Result<String, String> {
+    TcpListener::bind(address).await
+        .map(|_| format!("{} (TCP) is bindable", address))
+        .map_err(|err| format!("could not bind to {} (TCP): {} -- is another instance running?", address, err))
+}
+
+/// see [bind_tcp()]
+async fn bind_udp(address: &str) -> Result<String, String> {
+    UdpSocket::bind(address).await
+        .map(|_| format!("{} (UDP) is bindable", address))
+        .map_err(|err| format!("could not bind to {} (UDP): {} -- is another instance running?", address, err))
+}
+
+/// opens `file_path` for appending -- creating it, as the logger would
+fn writable(file_path: &str) -> Result<String, String> {
+    OpenOptions::new().create(true).append(true).open(file_path)
+        .map(|_| format!("'{}' is writable", file_path))
+        .map_err(|err| format!("could not open '{}' for writing: {}", file_path, err))
+}
@@ -1,16 +1,138 @@
-use crate::{runtime::Runtime, config::{Config, Jobs}, logic, frontend};
+mod tail;
+mod schedule;
+mod switch;
+mod repl;
+mod doctor;
+
+use crate::{runtime::Runtime, config::{Config, Jobs, JobsSequence}, logic, frontend};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::RwLock;
+use log::{info, warn, error};
 
 
+/// runs the console `job`, then shuts the services down -- unless a shutdown is requested in the meantime (see [crate::runtime::Shutdown]):
+/// then the job is cancelled & the services are left for the signal handler to shut down
 pub async fn async_run(job: &Jobs, runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let shutdown = Arc::clone(&runtime.read().await.shutdown);
+    let job_run = async {
+        match job {
+            Jobs::Run(sequence) => run_sequence(sequence, runtime, config).await,
+            Jobs::Tail(options) => tail::tail(options, config).await,
+            Jobs::Schedule(command) => schedule::schedule(command, config).await,
+            Jobs::Switch(options) => switch::switch(options, config).await,
+            Jobs::Doctor(options) => doctor::doctor(options, config).await,
+            job                 => run_job(job, runtime, config).await,
+        }
+    };
+    tokio::select! {
+        result = job_run => result?,
+        () = shutdown.wait() => {
+            warn!("Job {:?} cancelled: a shutdown was requested", job);
+            return Ok(())
+        },
+    }
     match job {
-        Jobs::CheckConfig => logic::check_config(runtime, config).await?,
-        Jobs::Daemon      => logic::long_runner(runtime, config).await?,
+        // client jobs & the doctor run no services -- so there is nothing to shutdown
+        Jobs::Tail(_) | Jobs::Schedule(_) | Jobs::Switch(_) | Jobs::Doctor(_) => Ok(()),
+        _ => frontend::shutdown_tokio_services(runtime).await,
     }
-    frontend::shutdown_tokio_services(runtime).await
 }
 
 /// on this example, our app's console frontend only uses Async Rust -- so we don't do nothing here
 pub fn run(_job: &Jobs, _runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     Ok(())
-}
\ No newline at end of file
+}
+
+/// runs the `job` -- accounting for the resources it used (see [crate::runtime::JobAccounting])
+async fn run_job(job: &Jobs, runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let job_accounting = Arc::clone(&runtime.read().await.job_accounting);
+    job_accounting.account(&format!("{:?}", job), async {
+        match job {
+            Jobs::CheckConfig       => logic::check_config(runtime, config).await,
+            Jobs::Daemon            => logic::long_runner(runtime, config).await,
+            Jobs::GenerateClientSdk => logic::generate_client_sdk(runtime, config).await,
+            Jobs::Repl              => repl::repl(runtime).await,
+            Jobs::Tail(_)           => Err(Box::from("`tail` only ends when interrupted, so it may not be part of a job sequence")),
+            Jobs::Schedule(_)       => Err(Box::from("`schedule` inspects a running instance, so it may not be part of a job sequence")),
+            Jobs::Switch(_)         => Err(Box::from("`switch` flips the switches of a running instance, so it may not be part of a job sequence")),
+            Jobs::Doctor(_)         => Err(Box::from("`doctor` checks the environment without the services running, so it may not be part of a job sequence")),
+            Jobs::Run(_)            => Err(Box::from("Job sequences may not be nested")),
+        }
+    }).await
+}
+
+/// What happened to each job of a [Jobs::Run] sequence -- reported once it ends
+#[derive(Debug)]
+pub enum JobOutcome {
+    Succeeded { job: Jobs, elapsed: Duration },
+    Failed    { job: Jobs, elapsed: Duration, error: String },
+    /// not ran, due to a previous failure
+    Skipped   { job: Jobs },
+}
+
+impl fmt::Display for JobOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobOutcome::Succeeded { job, elapsed }        => write!(f, "{:?}: succeeded in {:?}", job, elapsed),
+            JobOutcome::Failed    { job, elapsed, error } => write!(f, "{:?}: FAILED in {:?} -- {}", job, elapsed, error),
+            JobOutcome::Skipped   { job }                 => write!(f, "{:?}: skipped", job),
+        }
+    }
+}
+
+/// runs the jobs in `sequence`, in order -- stopping on the first failure unless [JobsSequence::continue_on_error] is set.\
+/// The outcomes are reported at the end & the sequence fails if any of its jobs did
+async fn run_sequence(sequence: &JobsSequence, runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let mut jobs = sequence.jobs.clone();
+    if let Some(jobs_file) = &sequence.jobs_file {
+        jobs.extend(read_jobs_file(jobs_file)?);
+    }
+    if jobs.is_empty() {
+        return Err(Box::from("No jobs to run: list them in the command line or in `--jobs-file`"))
+    }
+    let mut outcomes = Vec::with_capacity(jobs.len());
+    let mut failed = false;
+    for job in jobs {
+        if failed && !sequence.continue_on_error {
+            outcomes.push(JobOutcome::Skipped { job });
+            continue
+        }
+        info!("Running job {:?}...", job);
+        let start = Instant::now();
+        match run_job(&job, runtime, config).await {
+            Ok(())   => outcomes.push(JobOutcome::Succeeded { job, elapsed: start.elapsed() }),
+            Err(err) => {
+                error!("Job {:?} failed: {}", job, err);
+                failed = true;
+                outcomes.push(JobOutcome::Failed { job, elapsed: start.elapsed(), error: err.to_string() });
+            },
+        }
+    }
+    let report = outcomes.iter()
+        .map(|outcome| format!("  - {}", outcome))
+        .collect::<Vec<_>>()
+        .join("\n");
+    println!("Jobs outcomes:\n{}", report);
+    if failed {
+        Err(Box::from(format!("Some jobs failed:\n{}", report)))
+    } else {
+        Ok(())
+    }
+}
+
+/// the jobs listed in `jobs_file` -- one per line, `#` starting comments
+fn read_jobs_file(jobs_file: &str) -> Result<Vec<Jobs>, Box<dyn std::error::Error + Sync + Send>> {
+    let contents = std::fs::read_to_string(jobs_file)
+        .map_err(|err| format!("Could not read the jobs file '{}': {}", jobs_file, err))?;
+    contents.lines()
+        .enumerate()
+        .map(|(line_number, line)| (line_number, line.split('#').next().unwrap_or("").trim()))
+        .filter(|(_line_number, job_name)| !job_name.is_empty())
+        .map(|(line_number, job_name)| job_name.parse()
+            .map_err(|err| Box::from(format!("Jobs file '{}', line {}: {}", jobs_file, line_number + 1, err))))
+        .collect()
+}
@@ -0,0 +1,187 @@
+//! The `console repl` job: an interactive prompt, running along with the services, through which operators inspect & drive the
+//! running application -- with line editing & a history, kept in `<executable>.repl-history`.
+//!
+//! Type `help` for the commands & `quit` (or Ctrl-D) to end it -- shutting the services down. Logs sent to the console get mixed
+//! with the prompt: consider logging to a file while using it.
+
+use crate::{
+    runtime::{Runtime, SWITCHES},
+    frontend::{self, Service, ServiceHealth, socket_server::{ClientMessages, ServerMessages}},
+};
+use std::{
+    sync::Arc,
+    net::SocketAddr,
+};
+use rustyline::{DefaultEditor, error::ReadlineError};
+use tokio::sync::RwLock;
+
+
+const PROMPT: &str = "> ";
+
+const HELP: &str = "\
+Commands:
+  status                                  shows the services' health, the warmup, the degrade switches & the connected socket clients
+  switch <switch> <on|off>                flips a degrade switch: maintenance, read-only or telegram-notifications
+  stop <service>                          shuts a service down, for good: telegram, web, socket-server, socket-client or grpc
+  send <address|all> <text>               pushes `Pung(<text>)` to the socket client at <address> -- or to all of them
+  broadcast <text>                        sends <text> to Telegram's notification chats
+  request <message>                       sends <message> (in RON, like `Ping`) to the other instance, through the socket client
+  help                                    shows this help
+  quit                                    ends the REPL -- shutting the services down";
+
+
+pub async fn repl(runtime: &RwLock<Runtime>) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let history_file = format!("{}.repl-history", runtime.read().await.executable_path);
+    let mut editor = DefaultEditor::new()
+        .map_err(|err| format!("Could not start the line editor: {}", err))?;
+    // absent on the first run
+    editor.load_history(&history_file).ok();
+    println!("{} console -- type `help` for the commands", env!("CARGO_PKG_NAME"));
+    loop {
+        // reading blocks until a line is entered -- so it is done out of Tokio's workers
+        let (returned_editor, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline(PROMPT);
+            (editor, line)
+        }).await?;
+        editor = returned_editor;
+        let line = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => break,
+            Err(err) => return Err(Box::from(format!("Could not read the console: {}", err))),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue
+        }
+        editor.add_history_entry(line).ok();
+        let (command, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if command == "quit" || command == "exit" {
+            break
+        }
+        match execute(command, arguments.trim(), runtime).await {
+            Ok(output) => println!("{}", output),
+            Err(err) => println!("ERROR: {}", err),
+        }
+    }
+    if let Err(err) = editor.save_history(&history_file) {
+        println!("Could not save the REPL history to '{}': {}", history_file, err);
+    }
+    Ok(())
+}
+
+/// runs the REPL `command`, returning what should be shown to the operator
+async fn execute(command: &str, arguments: &str, runtime: &RwLock<Runtime>) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    match command {
+        "help"      => Ok(HELP.to_string()),
+        "status"    => Ok(status(runtime).await),
+        "switch"    => switch(arguments, runtime).await,
+        "stop"      => frontend::stop_service(runtime, arguments).await,
+        "send"      => send(arguments, runtime).await,
+        "broadcast" => broadcast(arguments, runtime).await,
+        "request"   => request(arguments, runtime).await,
+        unknown     => Err(Box::from(format!("Unknown command '{}' -- type `help` for the known ones", unknown))),
+    }
+}
+
+async fn status(runtime: &RwLock<Runtime>) -> String {
+    let mut services = frontend::telegram_healths(runtime).await;
+    services.extend([
+        (String::from("web"),           Runtime::do_if_web_server_is_present(runtime, |web_server| Box::pin(async move { web_server.health() })).await),
+        (String::from("socket-server"), Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.health() })).await),
+        (String::from("socket-client"), Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.health() })).await),
+        (String::from("grpc"),          Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| Box::pin(async move { grpc_server.health() })).await),
+    ]);
+    let mut lines = vec![String::from("Services:")];
+    for (name, health) in services {
+        let health = match health {
+            Some(ServiceHealth::Starting)     => "starting",
+            Some(ServiceHealth::Running)      => "running",
+            Some(ServiceHealth::ShuttingDown) => "shutting down",
+            None                              => "not running",
+        };
+        lines.push(format!("  {:14} {}", name, health));
+    }
+    if let Some(connected) = Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.is_connected() })).await {
+        lines.push(format!("Socket client: {}", if connected { "connected" } else { "not connected" }));
+    }
+    let runtime = runtime.read().await;
+    lines.push(format!("Warmup: {:?}", runtime.warmup.report().status));
+    lines.push(String::from("Degrade switches:"));
+    lines.push(format!("  maintenance             {}", on_off(runtime.degrade.maintenance())));
+    lines.push(format!("  read-only               {}", on_off(runtime.degrade.read_only_api())));
+    lines.push(format!("  telegram-notifications  {}", on_off(!runtime.degrade.disable_telegram_notifications())));
+    let stats = runtime.socket_server_stats.report();
+    let connected: Vec<_> = stats.endpoints.iter().filter(|endpoint| endpoint.connected).collect();
+    lines.push(format!("Socket clients: {} connected", connected.len()));
+    for endpoint in connected {
+        lines.push(format!("  {:21} in: {} messages, out: {} messages, errors: {}, idle for {:.1}s",
+                           endpoint.address, endpoint.messages_in, endpoint.messages_out, endpoint.errors, endpoint.idle_seconds));
+    }
+    lines.join("\n")
+}
+
+async fn switch(arguments: &str, runtime: &RwLock<Runtime>) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    let (switch_name, state) = arguments.split_once(char::is_whitespace)
+        .ok_or("Usage: switch <maintenance|read-only|telegram-notifications> <on|off>")?;
+    let on = match state.trim() {
+        "on"  => true,
+        "off" => false,
+        other => return Err(Box::from(format!("Unknown state '{}' -- use `on` or `off`", other))),
+    };
+    let degrade = Arc::clone(&runtime.read().await.degrade);
+    if !degrade.set(switch_name, on) {
+        return Err(Box::from(format!("Unknown switch '{}' -- known ones are: {}", switch_name, SWITCHES.join(", "))))
+    }
+    Ok(format!("{} is now {}", switch_name, on_off(on)))
+}
+
+async fn send(arguments: &str, runtime: &RwLock<Runtime>) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    let (target, text) = arguments.split_once(char::is_whitespace)
+        .ok_or("Usage: send <address|all> <text>")?;
+    let target = match target {
+        "all" => None,
+        address => Some(address.parse::<SocketAddr>()
+                           .map_err(|err| format!("'{}' is not a client address, like 127.0.0.1:45678: {}", address, err))?),
+    };
+    let text = text.trim().to_string();
+    Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move {
+        let addresses = target.map_or_else(|| socket_server.connected_clients(), |address| vec![address]);
+        let mut outcomes = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            match socket_server.push(address, ServerMessages::Pung(text.clone())) {
+                Ok(())   => outcomes.push(format!("  {}: sent", address)),
+                Err(err) => outcomes.push(format!("  {}: {}", address, err)),
+            }
+        }
+        if outcomes.is_empty() {
+            String::from("No socket clients are connected")
+        } else {
+            outcomes.join("\n")
+        }
+    })).await
+        .ok_or_else(|| Box::from("The socket server is not running"))
+}
+
+async fn broadcast(text: &str, runtime: &RwLock<Runtime>) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    if text.is_empty() {
+        return Err(Box::from("Usage: broadcast <text>"))
+    }
+    let telegram_notifier = runtime.read().await.telegram_notifier.clone()
+        .ok_or("Telegram is not enabled in the config")?;
+    telegram_notifier.notify(text, false);
+    Ok(String::from("Queued for Telegram's notification chats"))
+}
+
+async fn request(message: &str, runtime: &RwLock<Runtime>) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    let message: ClientMessages = ron::from_str(message)
+        .map_err(|err| format!("Usage: request <message>, with <message> in RON, like `Ping` -- '{}' is not a known one: {}", message, err))?;
+    let sender = Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.sender() })).await
+        .ok_or("The socket client is not running")?;
+    sender.send(message)
+        .map_err(|err| format!("The socket client is no longer taking requests: {}", err))?;
+    Ok(String::from("Queued for the other instance -- its answer goes through the socket client's processor"))
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on { "on" } else { "off" }
+}
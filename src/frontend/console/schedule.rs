@@ -0,0 +1,61 @@
+//! The `console schedule` jobs: inspect the scheduled jobs of a running instance -- through its `/api/stats/scheduler` route,
+//! served when [crate::config::Config::scheduler] is enabled.\
+//! Jobs are disabled & re-enabled through the admin API: `PUT /api/admin/scheduler/jobs/<name>/enabled`
+
+use super::tail::configured_url;
+use crate::{
+    config::{Config, ScheduleCommands, ScheduleListOptions},
+    scheduler::ScheduledJobStatus,
+};
+use rocket::serde::json;
+
+
+pub async fn schedule(command: &ScheduleCommands, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    match command {
+        ScheduleCommands::List(options) => list(options, config).await,
+    }
+}
+
+/// prints the scheduled jobs -- one per line -- with their statuses & next runs
+async fn list(options: &ScheduleListOptions, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let base_url = options.url.clone()
+        .unwrap_or_else(|| configured_url(config));
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/stats/scheduler", base_url.trim_end_matches('/')))
+        .send().await
+        .map_err(|err| format!("Could not connect to '{}': {}", base_url, err))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Box::from(format!("'{}' runs no scheduler -- see `scheduler` in its config file", base_url)))
+    }
+    if !response.status().is_success() {
+        return Err(Box::from(format!("'{}' refused to list its scheduled jobs: {} -- {}", base_url, response.status(), response.text().await.unwrap_or_default())))
+    }
+    let body = response.text().await
+        .map_err(|err| format!("Could not read the scheduled jobs of '{}': {}", base_url, err))?;
+    let jobs: Vec<ScheduledJobStatus> = json::from_str(&body)
+        .map_err(|err| format!("Unparseable scheduled jobs from '{}': {}", base_url, err))?;
+    if jobs.is_empty() {
+        println!("'{}' has no scheduled jobs", base_url);
+        return Ok(())
+    }
+    let name_width     = jobs.iter().map(|job| job.name.len()).max().unwrap_or(0).max("JOB".len());
+    let schedule_width = jobs.iter().map(|job| job.schedule.len()).max().unwrap_or(0).max("SCHEDULE".len());
+    println!("{:name_width$}  {:schedule_width$}  {:12}  {:32}  {:>6}  {:>8}", "JOB", "SCHEDULE", "STATUS", "NEXT RUN", "RUNS", "FAILURES");
+    for job in jobs {
+        let status = if !job.registered {
+            "unregistered"
+        } else if job.running {
+            "running"
+        } else if !job.enabled {
+            "disabled"
+        } else {
+            "enabled"
+        };
+        println!("{:name_width$}  {:schedule_width$}  {:12}  {:32}  {:>6}  {:>8}",
+                 job.name, job.schedule, status, job.next_run.as_deref().unwrap_or("-"), job.runs, job.failures);
+        if let Some(last_error) = &job.last_error {
+            println!("    last run ({}) failed: {}", job.last_run.as_deref().unwrap_or("?"), last_error);
+        }
+    }
+    Ok(())
+}
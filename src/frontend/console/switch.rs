@@ -0,0 +1,68 @@
+//! The `console switch` job: inspects & flips the degrade switches of a running instance -- see [crate::runtime::DegradeSwitches] --
+//! through its admin API (`/api/admin/switches`), served even in maintenance mode. Requires [crate::config::WebConfig::admin_token]
+
+use super::tail::configured_url;
+use crate::{
+    config::{Config, SwitchOptions, ExtendedOption},
+    runtime::{SwitchesReport, SWITCHES},
+};
+use rocket::serde::json;
+
+
+/// flips the `name`d switch to `on` or `off`, if given -- then prints the states of all of them
+pub async fn switch(options: &SwitchOptions, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let base_url = options.url.clone()
+        .unwrap_or_else(|| configured_url(config));
+    let admin_token = options.admin_token.clone()
+        .or_else(|| configured_admin_token(config))
+        .ok_or_else(|| String::from("No admin token: pass `--admin-token` or set `admin_token` in the web config"))?;
+    let switches_url = format!("{}/api/admin/switches", base_url.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+
+    if let Some(name) = &options.name {
+        let on = match options.state.as_deref() {
+            Some("on")  => true,
+            Some("off") => false,
+            _ => return Err(Box::from(format!("Usage: switch {} on|off", name))),
+        };
+        if !SWITCHES.contains(&name.as_str()) {
+            return Err(Box::from(format!("Unknown switch '{}' -- known ones are: {}", name, SWITCHES.join(", "))))
+        }
+        let response = client.put(format!("{}/{}", switches_url, name))
+            .bearer_auth(&admin_token)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(on.to_string())
+            .send().await
+            .map_err(|err| format!("Could not connect to '{}': {}", base_url, err))?;
+        if !response.status().is_success() {
+            return Err(Box::from(format!("'{}' refused to flip '{}': {} -- {}", base_url, name, response.status(), response.text().await.unwrap_or_default())))
+        }
+    }
+
+    let response = client.get(&switches_url)
+        .bearer_auth(&admin_token)
+        .send().await
+        .map_err(|err| format!("Could not connect to '{}': {}", base_url, err))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Box::from(format!("'{}' serves no admin API -- see `admin_token` in its config file", base_url)))
+    }
+    if !response.status().is_success() {
+        return Err(Box::from(format!("'{}' refused to report its switches: {} -- {}", base_url, response.status(), response.text().await.unwrap_or_default())))
+    }
+    let body = response.text().await
+        .map_err(|err| format!("Could not read the switches of '{}': {}", base_url, err))?;
+    let report: SwitchesReport = json::from_str(&body)
+        .map_err(|err| format!("Unparseable switches from '{}': {}", base_url, err))?;
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    println!("maintenance:            {}", on_off(report.maintenance));
+    println!("read-only:              {}", on_off(report.read_only));
+    println!("telegram-notifications: {}", on_off(report.telegram_notifications));
+    Ok(())
+}
+
+/// the admin token in `config` -- for when `console switch` runs on the same host (& with the same config file) as the instance
+fn configured_admin_token(config: &Config) -> Option<String> {
+    let ExtendedOption::Enabled(services) = &config.services else { return None };
+    let ExtendedOption::Enabled(web) = &services.web else { return None };
+    web.admin_token.clone()
+}
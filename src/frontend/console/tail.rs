@@ -0,0 +1,98 @@
+//! The `console tail` job: follows the logs of a running instance -- through the Server-Sent Events of its `/api/logs/tail` route,
+//! enabled by [crate::config::WebConfig::logs_following_routes] -- printing them, colored by level, until interrupted.
+
+use crate::{
+    config::{Config, ExtendedOption, RocketConfigOptions, TailOptions},
+    logging::log_tail::{TailFilter, TailedRecord},
+};
+use crossterm::style::Stylize;
+use rocket::serde::json;
+
+
+/// Rocket's port when it is configured through `Rocket.toml` -- unless that file says otherwise
+const ROCKET_DEFAULT_PORT: u16 = 8000;
+
+
+pub async fn tail(options: &TailOptions, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    // validated here as well, so mistakes are reported without bothering the running instance
+    TailFilter::new(&options.level, options.target.as_deref())?;
+    let base_url = options.url.clone()
+        .unwrap_or_else(|| configured_url(config));
+    let mut request = reqwest::Client::new()
+        .get(format!("{}/api/logs/tail", base_url.trim_end_matches('/')))
+        .query(&[("level", options.level.as_str())]);
+    if let Some(target) = &options.target {
+        request = request.query(&[("target", target.as_str())]);
+    }
+    if let Some(recent) = options.recent {
+        request = request.query(&[("recent", recent)]);
+    }
+    if let Some(api_key) = &options.api_key {
+        request = request.header("X-API-Key", api_key);
+    }
+    let mut response = request.send().await
+        .map_err(|err| format!("Could not connect to '{}': {}", base_url, err))?;
+    if !response.status().is_success() {
+        return Err(Box::from(format!("'{}' refused to stream its logs: {} -- {}", base_url, response.status(), response.text().await.unwrap_or_default())))
+    }
+    println!("Following the logs of '{}' -- press Ctrl-C to stop", base_url);
+    // Server-Sent Events are line based -- chunks, though, may end anywhere
+    let mut pending = Vec::new();
+    let mut event_name = None;
+    while let Some(chunk) = response.chunk().await
+                                    .map_err(|err| format!("Lost the logs stream of '{}': {}", base_url, err))? {
+        pending.extend_from_slice(&chunk);
+        while let Some(line_end) = pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = pending.drain(..=line_end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(name) = line.strip_prefix("event:") {
+                event_name = Some(name.trim().to_string());
+            } else if let Some(data) = line.strip_prefix("data:") {
+                print_event(event_name.as_deref(), data.trim_start(), options.no_color);
+            } else if line.is_empty() {
+                event_name = None;
+            }
+        }
+    }
+    println!("'{}' ended the logs stream -- was it shutdown?", base_url);
+    Ok(())
+}
+
+/// the web server's URL, as in `config` -- for when `console tail` (or `schedule`) runs on the same host (& with the same config file) as the instance.\
+/// With TLS, "localhost" is used: certificates are rarely issued for "127.0.0.1"
+pub(super) fn configured_url(config: &Config) -> String {
+    let default_url = format!("http://127.0.0.1:{}", ROCKET_DEFAULT_PORT);
+    let ExtendedOption::Enabled(services) = &config.services else { return default_url };
+    match &services.web {
+        ExtendedOption::Enabled(web) => match &web.rocket_config {
+            RocketConfigOptions::Provided { http_port, tls: ExtendedOption::Enabled(_), .. } => format!("https://localhost:{}", http_port),
+            RocketConfigOptions::Provided { http_port, .. } => format!("http://127.0.0.1:{}", http_port),
+            RocketConfigOptions::StandardRocketTomlFile => default_url,
+        },
+        _ => default_url,
+    }
+}
+
+fn print_event(event_name: Option<&str>, data: &str, no_color: bool) {
+    match event_name {
+        Some("log") => match json::from_str::<TailedRecord>(data) {
+            Ok(record) => {
+                let line = format!("{} {:8} {}: {}", record.timestamp, record.level, record.target, record.message);
+                if no_color {
+                    println!("{}", line);
+                } else {
+                    match record.level.as_str() {
+                        "CRITICAL" | "ERROR" => println!("{}", line.red()),
+                        "WARN"               => println!("{}", line.yellow()),
+                        "INFO"               => println!("{}", line),
+                        _                    => println!("{}", line.dark_grey()),
+                    }
+                }
+            },
+            Err(err) => eprintln!("Unparseable log record '{}': {}", data, err),
+        },
+        Some("lagged") => eprintln!("... {} records were missed, for they came faster than they could be printed ...", data),
+        _ => (),
+    }
+}
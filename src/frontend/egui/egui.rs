@@ -2,6 +2,9 @@
 
 use super::lottie_anim_facade::{LOTTIE_ANIMATIONS, LottieAnimation, LottieAnimationFacade};
 use super::fractal_clock::{self,FractalClock};
+use super::settings::Settings;
+use super::runtime_panel::RuntimePanel;
+use super::ui_storage::UiStorage;
 use std::{
     default::Default,
 };
@@ -18,10 +21,19 @@ pub struct Egui {
     hello_value:               f32,
     show_hello_window:         bool,
     show_fractal_clock_window: bool,
+    show_settings_window:      bool,
+    show_runtime_window:       bool,
     fractal_clock:             FractalClock,
     play_lottie_animation:     bool,
     #[serde(skip)]
     lottie_animations:         Vec<LottieAnimationData>,
+    #[serde(skip)]
+    settings:                  Settings,
+    #[serde(skip)]
+    runtime_panel:             RuntimePanel,
+    /// where this struct is persisted -- see [UiStorage]
+    #[serde(skip)]
+    ui_storage:                Option<UiStorage>,
 }
 
 struct LottieAnimationData {
@@ -38,6 +50,8 @@ impl Egui {
             hello_value:               value,
             show_hello_window:         false,
             show_fractal_clock_window: false,
+            show_settings_window:      false,
+            show_runtime_window:       false,
             play_lottie_animation:     true,
             fractal_clock:             FractalClock::default(),
             lottie_animations:         LOTTIE_ANIMATIONS.into_iter()
@@ -47,6 +61,9 @@ impl Egui {
                     animation_data: anim_data.to_string(),
                     animation: None,
                 }).collect(),
+            settings:                  Settings::new(),
+            runtime_panel:             RuntimePanel::default(),
+            ui_storage:                None,
         }
     }
 
@@ -62,7 +79,7 @@ impl Egui {
                 .start(
                     "the_canvas_id", // hardcode it
                     web_options,
-                    Box::new(|cc| Box::new(Self::app_creator(cc, "Web Dom", 4.4))),
+                    Box::new(|cc| Box::new(Self::app_creator(cc, "Web Dom", 4.4, Settings::new(), web_runtime_panel()))),
                 )
                 .await
                 .expect("Running a web eframe");
@@ -70,8 +87,9 @@ impl Egui {
         Ok(())
     }
 
+    /// `settings` are the ones the user may change through the "Settings" window & `runtime_panel` is what the "Runtime" window shows
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn run_egui_native_app() -> Result<(), Box<dyn std::error::Error>> {
+    pub fn run_egui_native_app(settings: Settings, runtime_panel: RuntimePanel) -> Result<(), Box<dyn std::error::Error>> {
         // Log to stdout (if you run with `RUST_LOG=debug`). -- if you'd ever want it, add to Cargo.toml: tracing-subscriber = "0.3"
         //tracing_subscriber::fmt::init();
 
@@ -82,11 +100,11 @@ impl Egui {
         eframe::run_native(
             "kickass-egui-web-app-template",
             options,
-            Box::new(|cc| Box::new(Self::app_creator(cc, "Native Dom", 4.4))),
+            Box::new(|cc| Box::new(Self::app_creator(cc, "Native Dom", 4.4, settings, runtime_panel))),
         ).map_err(|err| Box::from(format!("Error running a native eframe: {err}")))
     }
 
-    fn app_creator<IntoString: Into<String>>(cc: &eframe::CreationContext<'_>, default_label: IntoString, default_value: f32) -> Self {
+    fn app_creator<IntoString: Into<String>>(cc: &eframe::CreationContext<'_>, default_label: IntoString, default_value: f32, settings: Settings, runtime_panel: RuntimePanel) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
         cc.egui_ctx.set_visuals(egui::Visuals {
@@ -94,11 +112,15 @@ impl Egui {
             ..Default::default()
         });
 
-        // Load any previous app state or create one from the given parameters -- depends on the `persistence` feature on eframe
-        match cc.storage {
-            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
-            None => Self::new(default_label.into(), default_value),
-        }
+        // Load any previous app state -- kept along with the application, rather than in eframe's storage -- or create one from the given parameters
+        let ui_storage = UiStorage::open();
+        let mut app: Self = eframe::get_value(&ui_storage, eframe::APP_KEY)
+            .unwrap_or_else(|| Self::new(default_label.into(), default_value));
+        // not persisted: they are bound to the running application
+        app.settings = settings;
+        app.runtime_panel = runtime_panel;
+        app.ui_storage = Some(ui_storage);
+        app
     }
 }
 
@@ -118,6 +140,8 @@ impl eframe::App for Egui {
             hello_value: value,
             show_hello_window,
             show_fractal_clock_window,
+            show_settings_window,
+            show_runtime_window,
             ..
         } = self;
 
@@ -157,6 +181,8 @@ impl eframe::App for Egui {
 
             ui.add(egui::Checkbox::new(show_hello_window, "Show 'hello' window"));
             ui.add(egui::Checkbox::new(show_fractal_clock_window, "Show 'fractal clock' window"));
+            ui.add(egui::Checkbox::new(show_settings_window, "Show 'settings' window"));
+            ui.add(egui::Checkbox::new(show_runtime_window, "Show 'runtime' window"));
 
             ui.add(egui::Label::new(RichText::new("Lottie Animations:").size(20.0).underline()));
             for mut animation_data in &mut self.lottie_animations {
@@ -216,10 +242,43 @@ impl eframe::App for Egui {
                 self.fractal_clock.show(ui, Some(seconds));
             });
         }
+
+        if *show_settings_window {
+            egui::Window::new("Settings").show(ctx, |ui| {
+                self.settings.show(ui);
+            });
+        }
+
+        if *show_runtime_window {
+            egui::Window::new("Runtime").show(ctx, |ui| {
+                self.runtime_panel.show(ui);
+            });
+        }
     }
 
-    /// Called by the frame work to save state before shutdown.
-    fn save<'a, 'b>(&'a mut self, storage: &'b mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    /// Called by the frame work to save state periodically & before shutdown -- into our [UiStorage], rather than into eframe's `storage`
+    fn save<'a, 'b>(&'a mut self, _storage: &'b mut dyn eframe::Storage) {
+        let Some(mut ui_storage) = self.ui_storage.take() else { return };
+        eframe::set_value(&mut ui_storage, eframe::APP_KEY, self);
+        eframe::Storage::flush(&mut ui_storage);
+        self.ui_storage = Some(ui_storage);
     }
 }
+
+/// The "Runtime" window of the web app: fed by the `/api/stats/runtime` route of the web server serving it
+#[cfg(target_arch = "wasm32")]
+fn web_runtime_panel() -> RuntimePanel {
+    RuntimePanel::new(|feed| {
+        ehttp::fetch(ehttp::Request::get("/api/stats/runtime"), move |response| {
+            let status = response
+                .and_then(|response| if response.ok {
+                    Ok(response)
+                } else {
+                    Err(format!("HTTP {} {}", response.status, response.status_text))
+                })
+                .and_then(|response| serde_json::from_slice(&response.bytes)
+                    .map_err(|err| format!("Unexpected answer: {err}")));
+            feed.publish(status.map_err(|err| format!("Could not retrieve the runtime status: {err}")));
+        });
+    })
+}
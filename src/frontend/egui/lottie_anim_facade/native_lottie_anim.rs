@@ -1,7 +1,29 @@
 //! Implements the native version of [super::LottieAnimation]
 //! as of 2022-05-12, egui is still zero-copy unfriendly, so we circumvent this performance hit by
 //! "pre-loading" all frames of the animation into bitmap buffers (textures), at the cost of increased (video) RAM usage
-//! (done in the UI thread...)
+//! (done in the UI thread...)\
+//! NOTE: it depends on the `rlottie` system library -- so it is commented out, along with its dependency in Cargo.toml, being replaced
+//!       by a placeholder. Enable both to see the animations natively.
+
+use eframe::egui::{self, Ui, RichText};
+
+
+pub struct LottieAnimation {
+    animation_name: String,
+}
+
+impl super::types::LottieAnimationFacade for LottieAnimation {
+    fn from_data(animation_name: String, _animation_data: String) -> Self {
+        Self {
+            animation_name
+        }
+    }
+
+    fn show(&mut self, ui: &mut Ui, _seconds: f64) {
+        ui.add(egui::Label::new(RichText::new(format!("Lottie animation '{}' needs `rlottie` to be shown natively -- see Cargo.toml", self.animation_name)).size(15.0)));
+    }
+}
+
 /*
 
 use eframe::egui::{self, ColorImage, TextureHandle, TextureOptions, Ui};
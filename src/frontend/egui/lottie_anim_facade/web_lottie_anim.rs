@@ -1,21 +1,142 @@
-//! When in web, lottie animations could be used without the need of the rlottie library,
-//! as browsers natively supports these animations -- it is not implemented here, 'thought
+//! Implements the web version of [super::LottieAnimation]: browsers play lottie animations without the need of the rlottie library,
+//! through the `lottie-web` JavaScript player (loaded by `web-egui/index.html`) -- which renders each frame into an off-screen canvas,
+//! whose pixels are then uploaded to an egui texture.\
+//! Unlike the native version, frames are not pre-loaded: only the one due is rendered, following the animation's own frame rate
 
-use eframe::egui::{self, Ui, RichText};
+use eframe::egui::{self, ColorImage, RichText, TextureHandle, TextureOptions, Ui};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use js_sys::{Object, Reflect, JSON};
+
+
+#[wasm_bindgen]
+extern "C" {
+    /// `lottie-web`'s animation -- see https://github.com/airbnb/lottie-web#usage
+    type AnimationItem;
+
+    /// fails if `lottie-web` was not loaded
+    #[wasm_bindgen(catch, js_namespace = lottie, js_name = loadAnimation)]
+    fn load_animation(params: &JsValue) -> Result<AnimationItem, JsValue>;
+
+    #[wasm_bindgen(method, js_name = goToAndStop)]
+    fn go_to_and_stop(this: &AnimationItem, frame: f64, is_frame: bool);
+
+    #[wasm_bindgen(method, getter, js_name = totalFrames)]
+    fn total_frames(this: &AnimationItem) -> f64;
+
+    #[wasm_bindgen(method, getter, js_name = frameRate)]
+    fn frame_rate(this: &AnimationItem) -> f64;
+
+    #[wasm_bindgen(method)]
+    fn destroy(this: &AnimationItem);
+}
 
 
 pub struct LottieAnimation {
     animation_name: String,
+    /// the reason, if the animation couldn't be loaded -- shown in its place
+    player:         Result<Player, String>,
+}
+
+/// An animation loaded into `lottie-web`, drawing on its own canvas
+struct Player {
+    animation:   AnimationItem,
+    canvas:      HtmlCanvasElement,
+    context:     CanvasRenderingContext2d,
+    texture:     Option<TextureHandle>,
+    /// the frame currently in [Self::texture]
+    shown_frame: Option<u32>,
+}
+
+impl Player {
+
+    fn load(animation_data: &str) -> Result<Self, JsValue> {
+        let animation_data = JSON::parse(animation_data)?;
+        // the canvas is sized as the animation was designed -- egui scales it when painting
+        let dimension = |name: &str| Reflect::get(&animation_data, &JsValue::from_str(name))
+            .ok()
+            .and_then(|dimension| dimension.as_f64())
+            .map_or(512, |dimension| dimension as u32);
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("no `document` to create the canvas in")?;
+        let canvas: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+        canvas.set_width(dimension("w"));
+        canvas.set_height(dimension("h"));
+        let context: CanvasRenderingContext2d = canvas.get_context("2d")?
+            .ok_or("the canvas has no 2d context")?
+            .dyn_into()?;
+
+        let renderer_settings = Object::new();
+        Reflect::set(&renderer_settings, &"context".into(), &context)?;
+        Reflect::set(&renderer_settings, &"clearCanvas".into(), &true.into())?;
+        let params = Object::new();
+        Reflect::set(&params, &"renderer".into(), &"canvas".into())?;
+        Reflect::set(&params, &"loop".into(), &false.into())?;
+        Reflect::set(&params, &"autoplay".into(), &false.into())?;
+        Reflect::set(&params, &"animationData".into(), &animation_data)?;
+        Reflect::set(&params, &"rendererSettings".into(), &renderer_settings)?;
+        let animation = load_animation(&params)?;
+
+        Ok(Self { animation, canvas, context, texture: None, shown_frame: None })
+    }
+
+    /// renders `frame` into [Self::texture], if it is not already there
+    fn render(&mut self, ui: &Ui, animation_name: &str, frame: u32) -> Result<(), JsValue> {
+        if self.shown_frame == Some(frame) {
+            return Ok(())
+        }
+        self.animation.go_to_and_stop(frame as f64, true);
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let image_data = self.context.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+        let image = ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &image_data.data().0);
+        match &mut self.texture {
+            Some(texture) => texture.set(image, TextureOptions::LINEAR),
+            None => self.texture = Some(ui.ctx().load_texture(format!("Lottie Animation '{}'", animation_name), image, TextureOptions::LINEAR)),
+        }
+        self.shown_frame = Some(frame);
+        Ok(())
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.animation.destroy();
+    }
 }
 
 impl super::types::LottieAnimationFacade for LottieAnimation {
     fn from_data(animation_name: String, animation_data: String) -> Self {
+        let player = Player::load(&animation_data)
+            .map_err(|err| format!("{:?}", err));
         Self {
-            animation_name
+            animation_name,
+            player,
         }
     }
 
-    fn show(&mut self, ui: &mut Ui, _seconds: f64) {
-        ui.add(egui::Label::new(RichText::new(format!("Here I'd show lottie animation '{}' by asking the browser to download that file from the server and showing it here...", self.animation_name)).size(15.0)));
+    fn show(&mut self, ui: &mut Ui, seconds: f64) {
+        let player = match &mut self.player {
+            Ok(player) => player,
+            Err(err) => {
+                ui.add(egui::Label::new(RichText::new(format!("Could not load lottie animation '{}' into the browser's player: {}", self.animation_name, err)).size(15.0)));
+                return
+            },
+        };
+        let total_frames = player.animation.total_frames().max(1.0);
+        let frame = ((seconds * player.animation.frame_rate()) % total_frames) as u32;
+        if let Err(err) = player.render(ui, &self.animation_name, frame) {
+            self.player = Err(format!("{:?}", err));
+            return
+        }
+
+        // paint the texture -- keeping the animation's aspect ratio -- and request a repaint for the next frame
+        if let Some(texture) = &player.texture {
+            let (width, height) = (player.canvas.width() as f32, player.canvas.height() as f32);
+            let max_size = ui.available_size();
+            let scale = (max_size.x / width).min(max_size.y / height);
+            ui.image(texture.id(), egui::vec2(width * scale, height * scale));
+        }
+        ui.ctx().request_repaint();
     }
-}
\ No newline at end of file
+}
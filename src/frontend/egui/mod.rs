@@ -1,5 +1,10 @@
 mod egui;
 mod fractal_clock;
 mod lottie_anim_facade;
+mod runtime_panel;
+mod settings;
+mod ui_storage;
 
-pub use self::egui::*;
\ No newline at end of file
+pub use self::egui::*;
+pub use settings::Settings;
+pub use runtime_panel::{RuntimePanel, RuntimeStatus, ServiceStatus, MetricStatus, MetricHistoryStatus, SocketClientStatus, LogStatus};
\ No newline at end of file
@@ -0,0 +1,217 @@
+//! The "Runtime" window: a live view of the running application -- the services' states, the metrics (charting the ones with a history),
+//! the connected socket clients & the most recent logs -- refreshed every [REFRESH_INTERVAL] while shown.\
+//! Whoever runs this UI provides the refreshing (see [RuntimePanel::new()]): natively, it is read straight out of the shared runtime;
+//! on the web, out of the `/api/stats/runtime` route -- in both cases, publishing a [RuntimeStatus] to a [RuntimeStatusFeed].\
+//! Notice this module is also compiled by `web-egui/`, so it may not depend on the rest of the application.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use eframe::egui::{
+    self, Color32, RichText,
+    plot::{Plot, Line, PlotPoints},
+};
+use serde::{Serialize, Deserialize};
+
+
+/// How often the status is refreshed, while the window is shown
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+
+/// A snapshot of the running application -- as shown in the "Runtime" window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeStatus {
+    pub uptime_seconds: u64,
+    pub services:       Vec<ServiceStatus>,
+    /// the counters & gauges -- with their labeled values summed up
+    pub metrics:        Vec<MetricStatus>,
+    /// the recent trends of the metrics whose history is kept -- see `crate::runtime::MetricsHistory`
+    #[serde(default)]
+    pub history:        Vec<MetricHistoryStatus>,
+    /// only the connected ones
+    pub socket_clients: Vec<SocketClientStatus>,
+    /// the most recent first -- only kept if following the logs is enabled
+    pub recent_logs:    Vec<LogStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub name:  String,
+    /// "starting", "running", "shutting down" or "not running"
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricStatus {
+    pub name:  String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricHistoryStatus {
+    pub name:    String,
+    /// (seconds since the Unix epoch, value) -- the oldest first
+    pub samples: Vec<(i64, f64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketClientStatus {
+    pub address:      String,
+    pub messages_in:  u64,
+    pub messages_out: u64,
+    pub errors:       u64,
+    pub idle_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogStatus {
+    pub timestamp: String,
+    /// as in `slog`: "CRITICAL", "ERROR", "WARN", "INFO", "DEBUG" or "TRACE"
+    pub level:     String,
+    pub target:    String,
+    pub message:   String,
+}
+
+/// Where refreshes publish the status to -- possibly from other threads or, on the web, from the fetch callbacks
+#[derive(Clone, Default)]
+pub struct RuntimeStatusFeed(Arc<Mutex<Option<Result<RuntimeStatus, String>>>>);
+
+impl RuntimeStatusFeed {
+
+    /// replaces the shown status by `status` -- or by the reason it couldn't be retrieved
+    pub fn publish(&self, status: Result<RuntimeStatus, String>) {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(status);
+    }
+
+    fn latest(&self) -> Option<Result<RuntimeStatus, String>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+/// The contents of the "Runtime" window
+#[derive(Default)]
+pub struct RuntimePanel {
+    /// asks for a new status to be published to the given feed -- `None` if this UI is not bound to a running application
+    refresh:           Option<Box<dyn Fn(RuntimeStatusFeed) + Send>>,
+    feed:              RuntimeStatusFeed,
+    /// in egui's time, in seconds
+    last_refresh_time: Option<f64>,
+}
+
+impl RuntimePanel {
+
+    /// `refresh` is called every [REFRESH_INTERVAL] while the window is shown -- it should not block: the status may be published later
+    pub fn new(refresh: impl Fn(RuntimeStatusFeed) + Send + 'static) -> Self {
+        Self {
+            refresh: Some(Box::new(refresh)),
+            ..Self::default()
+        }
+    }
+
+    /// renders the latest status -- asking for a new one if it is due
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let Some(refresh) = &self.refresh else {
+            ui.label("Not bound to a running application");
+            return
+        };
+        let now = ui.input(|input| input.time);
+        if self.last_refresh_time.map_or(true, |last_refresh_time| now - last_refresh_time >= REFRESH_INTERVAL.as_secs_f64()) {
+            self.last_refresh_time = Some(now);
+            refresh(self.feed.clone());
+        }
+        // repaints would otherwise only happen on user input
+        ui.ctx().request_repaint_after(REFRESH_INTERVAL);
+        match self.feed.latest() {
+            None => { ui.label("Retrieving the status..."); },
+            Some(Err(err)) => { ui.colored_label(Color32::RED, err); },
+            Some(Ok(status)) => show_status(ui, &status),
+        }
+    }
+}
+
+fn show_status(ui: &mut egui::Ui, status: &RuntimeStatus) {
+    let seconds = status.uptime_seconds;
+    ui.label(format!("Uptime: {}d {:02}:{:02}:{:02}", seconds / 86400, (seconds % 86400) / 3600, (seconds % 3600) / 60, seconds % 60));
+
+    egui::CollapsingHeader::new("Services").default_open(true).show(ui, |ui| {
+        egui::Grid::new("runtime_services").striped(true).show(ui, |ui| {
+            for service in &status.services {
+                ui.label(&service.name);
+                let color = if service.state == "running" { Color32::GREEN } else { Color32::YELLOW };
+                ui.colored_label(color, &service.state);
+                ui.end_row();
+            }
+        });
+    });
+
+    egui::CollapsingHeader::new(format!("Metrics ({})", status.metrics.len())).id_source("runtime_metrics").show(ui, |ui| {
+        egui::ScrollArea::vertical().id_source("runtime_metrics_scroll").max_height(200.0).show(ui, |ui| {
+            egui::Grid::new("runtime_metrics_grid").striped(true).show(ui, |ui| {
+                for metric in &status.metrics {
+                    ui.label(&metric.name);
+                    ui.monospace(format!("{}", metric.value));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+
+    if !status.history.is_empty() {
+        egui::CollapsingHeader::new(format!("Charts ({})", status.history.len())).id_source("runtime_charts").show(ui, |ui| {
+            for metric in &status.history {
+                let last_timestamp = metric.samples.last().map_or(0, |(timestamp, _value)| *timestamp);
+                let last_value = metric.samples.last().map_or(String::from("-"), |(_timestamp, value)| value.to_string());
+                ui.label(format!("{} -- {}", metric.name, last_value));
+                // seconds before the last sample on the x axis
+                let points: PlotPoints = metric.samples.iter()
+                    .map(|(timestamp, value)| [(timestamp - last_timestamp) as f64, *value])
+                    .collect();
+                Plot::new(format!("runtime_chart_{}", metric.name))
+                    .height(80.0)
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .show(ui, |plot_ui| plot_ui.line(Line::new(points)));
+            }
+        });
+    }
+
+    egui::CollapsingHeader::new(format!("Socket clients ({})", status.socket_clients.len())).id_source("runtime_socket_clients").show(ui, |ui| {
+        if status.socket_clients.is_empty() {
+            ui.label("No socket clients are connected");
+            return
+        }
+        egui::Grid::new("runtime_socket_clients_grid").striped(true).show(ui, |ui| {
+            for header in ["address", "in", "out", "errors", "idle"] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+            for client in &status.socket_clients {
+                ui.monospace(&client.address);
+                ui.label(client.messages_in.to_string());
+                ui.label(client.messages_out.to_string());
+                ui.label(client.errors.to_string());
+                ui.label(format!("{:.1}s", client.idle_seconds));
+                ui.end_row();
+            }
+        });
+    });
+
+    egui::CollapsingHeader::new("Recent logs").id_source("runtime_recent_logs").show(ui, |ui| {
+        if status.recent_logs.is_empty() {
+            ui.label("No recent logs -- they are only kept if following the logs is enabled");
+            return
+        }
+        egui::ScrollArea::vertical().id_source("runtime_recent_logs_scroll").max_height(300.0).show(ui, |ui| {
+            for log in &status.recent_logs {
+                let color = match log.level.as_str() {
+                    "CRITICAL" | "ERROR" => Color32::RED,
+                    "WARN"               => Color32::YELLOW,
+                    _                    => ui.visuals().text_color(),
+                };
+                ui.label(RichText::new(format!("{} {:5} {}: {}", log.timestamp, log.level, log.target, log.message)).monospace().color(color));
+            }
+        });
+    });
+}
@@ -0,0 +1,126 @@
+//! The "Settings" window: editable widgets for the settings that may change while the application runs -- each one bound, by whoever runs
+//! this UI, to the code reading & applying it (see [Settings::toggle()] & [Settings::text()]). Applying may fail -- like when a value doesn't
+//! pass validation -- in which case the error is shown next to the widget.\
+//! The application binds the degradation switches & the hot reloadable config settings -- the latter applied through the same validated
+//! path as the admin API's config edits (see `crate::runtime::ConfigReload::edit()`).\
+//! Notice this module is also compiled by `web-egui/`, so it may not depend on the rest of the application.
+
+use eframe::egui::{self, Color32};
+
+
+/// The settings shown in the "Settings" window
+#[derive(Default)]
+pub struct Settings {
+    toggles: Vec<Toggle>,
+    texts:   Vec<Text>,
+}
+
+/// An on/off setting
+struct Toggle {
+    name:  String,
+    hint:  String,
+    /// tells the current value -- which may have been changed elsewhere
+    read:  Box<dyn Fn() -> bool + Send>,
+    apply: Box<dyn Fn(bool) -> Result<(), String> + Send>,
+    /// the error of the last [Self::apply] -- shown until the next attempt
+    error: Option<String>,
+}
+
+/// A setting edited as text -- applied when the user says so
+struct Text {
+    name:  String,
+    hint:  String,
+    /// tells the current value -- which may have been changed elsewhere
+    read:  Box<dyn Fn() -> String + Send>,
+    apply: Box<dyn Fn(&str) -> Result<(), String> + Send>,
+    /// what the user typed, while not applied
+    draft: Option<String>,
+    /// the error of the last [Self::apply] -- shown until the next attempt
+    error: Option<String>,
+}
+
+impl Settings {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds an on/off setting named `name` (with `hint` shown on hover), whose value is given by `read` & changed by `apply`
+    pub fn toggle(mut self,
+                  name:  &str,
+                  hint:  &str,
+                  read:  impl Fn() -> bool + Send + 'static,
+                  apply: impl Fn(bool) -> Result<(), String> + Send + 'static)
+                 -> Self {
+        self.toggles.push(Toggle {
+            name:  name.to_string(),
+            hint:  hint.to_string(),
+            read:  Box::new(read),
+            apply: Box::new(apply),
+            error: None,
+        });
+        self
+    }
+
+    /// adds a text setting named `name` (with `hint` shown on hover), whose value is given by `read` & changed by `apply` -- when the user
+    /// clicks its "Apply" button
+    pub fn text(mut self,
+                name:  &str,
+                hint:  &str,
+                read:  impl Fn() -> String + Send + 'static,
+                apply: impl Fn(&str) -> Result<(), String> + Send + 'static)
+               -> Self {
+        self.texts.push(Text {
+            name:  name.to_string(),
+            hint:  hint.to_string(),
+            read:  Box::new(read),
+            apply: Box::new(apply),
+            draft: None,
+            error: None,
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toggles.is_empty() && self.texts.is_empty()
+    }
+
+    /// renders the settings -- applying the ones changed by the user
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if self.is_empty() {
+            ui.label("No settings may be changed while running here");
+            return
+        }
+        egui::Grid::new("settings").striped(true).show(ui, |ui| {
+            for toggle in &mut self.toggles {
+                let mut value = (toggle.read)();
+                if ui.checkbox(&mut value, &toggle.name).on_hover_text(&toggle.hint).changed() {
+                    toggle.error = (toggle.apply)(value).err();
+                }
+                if let Some(error) = &toggle.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.end_row();
+            }
+            for text in &mut self.texts {
+                ui.label(&text.name).on_hover_text(&text.hint);
+                let mut value = text.draft.clone().unwrap_or_else(|| (text.read)());
+                if ui.text_edit_singleline(&mut value).changed() {
+                    text.draft = Some(value);
+                }
+                if ui.add_enabled(text.draft.is_some(), egui::Button::new("Apply")).clicked() {
+                    if let Some(draft) = text.draft.clone() {
+                        match (text.apply)(&draft) {
+                            Ok(()) => (text.draft, text.error) = (None, None),
+                            Err(err) => text.error = Some(err),
+                        }
+                    }
+                }
+                if let Some(error) = &text.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
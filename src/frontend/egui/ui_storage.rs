@@ -0,0 +1,99 @@
+//! Persistence façade for the UI state -- the [super::Egui] struct & any future panels -- so it follows the application's install,
+//! rather than living in eframe's per-user storage:
+//!   * native: in a RON file next to the config file -- `${0}.egui.ron`
+//!   * web: in the browser's `localStorage`
+//!
+//! Values are kept by key, through the [eframe::Storage] API, so each panel may keep its own state:
+//! ```no_compile
+//!     eframe::set_value(&mut ui_storage, "my_panel", &my_panel_state);
+//!     let my_panel_state: MyPanelState = eframe::get_value(&ui_storage, "my_panel").unwrap_or_default();
+//! ```
+//! eframe still keeps its own state (window positions & sizes, egui's memory, ...) where it always did.
+
+use std::collections::BTreeMap;
+use log::warn;
+
+
+/// The `localStorage` item holding the UI state, on web
+#[cfg(target_arch = "wasm32")]
+const LOCAL_STORAGE_KEY: &str = "kickass-app-template.egui";
+
+
+/// The persisted UI state -- see [super]. Changes are only persisted on [eframe::Storage::flush()]
+pub struct UiStorage {
+    /// the values, by key, RON serialized
+    values: BTreeMap<String, String>,
+}
+
+impl UiStorage {
+
+    /// loads the persisted UI state -- starting empty if there is none (or if it can't be read)
+    pub fn open() -> Self {
+        let values = Self::read()
+            .map(|persisted| ron::from_str(&persisted).unwrap_or_else(|err| {
+                warn!("Egui: the persisted UI state is unreadable -- starting afresh: {}", err);
+                BTreeMap::new()
+            }))
+            .unwrap_or_default();
+        Self {
+            values,
+        }
+    }
+
+    /// where the UI state is kept -- next to the config file: `${0}.egui.ron`
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_path() -> String {
+        let program_name = std::env::args().next().unwrap_or_else(|| String::from("kickass-app-template"));
+        format!("{}.egui.ron", program_name)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read() -> Option<String> {
+        std::fs::read_to_string(Self::file_path()).ok()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(persisted: &str) {
+        if let Err(err) = std::fs::write(Self::file_path(), persisted) {
+            warn!("Egui: could not persist the UI state into '{}': {}", Self::file_path(), err);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read() -> Option<String> {
+        Self::local_storage().and_then(|local_storage| local_storage.get_item(LOCAL_STORAGE_KEY).ok().flatten())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(persisted: &str) {
+        match Self::local_storage() {
+            Some(local_storage) => if let Err(err) = local_storage.set_item(LOCAL_STORAGE_KEY, persisted) {
+                warn!("Egui: could not persist the UI state into the browser's `localStorage`: {:?}", err);
+            },
+            None => warn!("Egui: the browser has no `localStorage` -- the UI state won't be persisted"),
+        }
+    }
+}
+
+impl eframe::Storage for UiStorage {
+
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {
+        match ron::ser::to_string_pretty(&self.values, ron::ser::PrettyConfig::default()) {
+            Ok(persisted) => Self::write(&persisted),
+            Err(err) => warn!("Egui: could not serialize the UI state: {}", err),
+        }
+    }
+}
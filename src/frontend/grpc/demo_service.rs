@@ -0,0 +1,89 @@
+//! Implements `proto/demo.proto` -- the gRPC counterpart of the socket server's processors
+
+use super::proto::{
+    demo_server::Demo,
+    PingRequest, PongReply, PangRequest, PungReply, SpeechlessRequest, SpeechlessReply,
+};
+use crate::runtime::DegradeSwitches;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+use tonic::{Request, Response, Status};
+
+
+/// The `Demo` service.\
+/// Unlike the socket server's per-client counters, these are shared by all clients -- gRPC calls are not bound to connections
+pub struct DemoService {
+    /// while in maintenance mode, calls are refused with `UNAVAILABLE` (along with the maintenance message)
+    degrade: Arc<DegradeSwitches>,
+    pings:   AtomicU64,
+    pangs:   AtomicU64,
+}
+
+impl DemoService {
+
+    pub fn new(degrade: Arc<DegradeSwitches>) -> Self {
+        Self {
+            degrade,
+            pings: AtomicU64::new(0),
+            pangs: AtomicU64::new(0),
+        }
+    }
+
+    fn refuse_if_in_maintenance(&self) -> Result<(), Status> {
+        if self.degrade.maintenance() {
+            Err(Status::unavailable(self.degrade.maintenance_message()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Demo for DemoService {
+
+    async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PongReply>, Status> {
+        self.refuse_if_in_maintenance()?;
+        let count = self.pings.fetch_add(1, Ordering::Relaxed) + 1;
+        Ok(Response::new(PongReply { count }))
+    }
+
+    async fn pang(&self, request: Request<PangRequest>) -> Result<Response<PungReply>, Status> {
+        self.refuse_if_in_maintenance()?;
+        let count = self.pangs.fetch_add(1, Ordering::Relaxed) + 1;
+        let peer = request.remote_addr().map_or_else(|| String::from("an unknown peer"), |address| address.to_string());
+        Ok(Response::new(PungReply { message: format!("`Pang` from {}, {} times", peer, count) }))
+    }
+
+    async fn speechless(&self, _request: Request<SpeechlessRequest>) -> Result<Response<SpeechlessReply>, Status> {
+        self.refuse_if_in_maintenance()?;
+        Ok(Response::new(SpeechlessReply {}))
+    }
+}
+
+
+/// Unit tests the [demo_service](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::config::DegradeConfig;
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn counters_and_maintenance() {
+        let degrade = Arc::new(DegradeSwitches::new(&DegradeConfig { maintenance_message: String::from("be right back"), ..DegradeConfig::default() }));
+        let service = DemoService::new(Arc::clone(&degrade));
+        for expected_count in 1..=3 {
+            let reply = service.ping(Request::new(PingRequest {})).await.expect("`Ping` failed");
+            assert_eq!(reply.into_inner().count, expected_count, "Wrong `Ping` counter");
+        }
+        let reply = service.pang(Request::new(PangRequest {})).await.expect("`Pang` failed");
+        assert_eq!(reply.into_inner().message, "`Pang` from an unknown peer, 1 times", "Wrong `Pang` answer");
+
+        degrade.set_maintenance(true);
+        let status = service.ping(Request::new(PingRequest {})).await.expect_err("Calls should be refused while in maintenance");
+        assert_eq!(status.code(), tonic::Code::Unavailable, "Wrong status while in maintenance");
+        assert_eq!(status.message(), "be right back", "The maintenance message should be sent along");
+    }
+}
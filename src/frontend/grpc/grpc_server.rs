@@ -0,0 +1,116 @@
+//! see [super]
+
+use super::{
+    DemoService,
+    proto::{FILE_DESCRIPTOR_SET, demo_server::DemoServer},
+};
+use crate::{
+    config::{Config, GrpcConfig},
+    runtime::{Runtime, DegradeSwitches},
+    frontend::{Service, ServiceHealth, Runner},
+};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use owning_ref::OwningRef;
+use futures::future::BoxFuture;
+use tokio::{
+    net::TcpListener,
+    sync::{watch, RwLock},
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use log::info;
+
+
+/// The handle to start & shutdown the gRPC service
+pub struct GrpcServer {
+    config:             OwningRef<Arc<Config>, GrpcConfig>,
+    degrade:            Arc<DegradeSwitches>,
+    /// set once the port is bound
+    listening:          Arc<AtomicBool>,
+    /// `true` once [Service::shutdown()] is called -- see [Service::health()]
+    shutdown_requested: watch::Sender<bool>,
+}
+
+impl GrpcServer {
+
+    pub fn new(grpc_config: OwningRef<Arc<Config>, GrpcConfig>, degrade: Arc<DegradeSwitches>) -> Self {
+        Self {
+            config:             grpc_config,
+            degrade,
+            listening:          Arc::new(AtomicBool::new(false)),
+            shutdown_requested: watch::channel(false).0,
+        }
+    }
+}
+
+impl Service for GrpcServer {
+
+    fn name(&self) -> &'static str {
+        "gRPC"
+    }
+
+    /// binds the port -- so failing to do so is reported here -- & returns the runner serving the calls
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>> {
+        Box::pin(async move {
+            let address = format!("{}:{}", self.config.interface, self.config.port);
+            let listener = TcpListener::bind(&address).await
+                .map_err(|err| format!("gRPC: could not listen to {}: {}", address, err))?;
+            let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+            health_reporter.set_serving::<DemoServer<DemoService>>().await;
+            let reflection_service = if self.config.reflection {
+                Some(tonic_reflection::server::Builder::configure()
+                         .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                         .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+                         .build()
+                         .map_err(|err| format!("gRPC: could not build the reflection service: {}", err))?)
+            } else {
+                None
+            };
+            let router = Server::builder()
+                .add_service(health_service)
+                .add_optional_service(reflection_service)
+                .add_service(DemoServer::new(DemoService::new(Arc::clone(&self.degrade))));
+            self.listening.store(true, Ordering::Relaxed);
+            info!("gRPC: listening to {}", address);
+            let mut shutdown = self.shutdown_requested.subscribe();
+            let runner: Runner = Box::pin(async move {
+                router.serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown_requested(&mut shutdown)).await
+                    .map_err(|err| Box::from(format!("gRPC: error serving the calls: {}", err)) as Box<dyn std::error::Error + Send + Sync>)
+            });
+            Ok(runner)
+        })
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.shutdown_requested.send_replace(true);
+        })
+    }
+
+    fn health(&self) -> ServiceHealth {
+        if *self.shutdown_requested.borrow() {
+            ServiceHealth::ShuttingDown
+        } else if self.listening.load(Ordering::Relaxed) {
+            ServiceHealth::Running
+        } else {
+            ServiceHealth::Starting
+        }
+    }
+
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()> {
+        Box::pin(Runtime::register_grpc_server(runtime, self))
+    }
+}
+
+/// resolves once a shutdown is requested
+async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) {
+    // the sender is kept by the server until the runner ends -- so `changed()` only fails if the server was dropped
+    while !*shutdown.borrow_and_update() {
+        if shutdown.changed().await.is_err() {
+            break
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! The gRPC service: offers the socket server's demo protocol (see [super::socket_server::ClientMessages]) through `tonic` -- as
+//! defined in `proto/demo.proto` -- along with the standard health & reflection services. See [crate::config::GrpcConfig] -- disabled by default.\
+//! Example, enabled on port 50051 with reflection:
+//! ```no_compile
+//!     grpcurl -plaintext localhost:50051 list
+//!     grpcurl -plaintext localhost:50051 kickass.demo.Demo/Ping
+//!     grpcurl -plaintext localhost:50051 grpc.health.v1.Health/Check
+//! ```
+//! Add your own services to `proto/` & register them in [GrpcServer] -- `build.rs` generates their code.
+//!
+//! TODO 2026-10-16: TLS & authentication (through the `AuthProvider`, as the socket server's `Login`) -- for now, only plaintext & anonymous calls are served.
+
+mod grpc_server;
+pub use grpc_server::*;
+
+mod demo_service;
+pub use demo_service::DemoService;
+
+/// The code generated by `build.rs` out of `proto/demo.proto`
+pub mod proto {
+    tonic::include_proto!("kickass.demo");
+
+    /// describes the services above, for the reflection service
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("grpc_descriptor");
+}
@@ -6,34 +6,54 @@ pub mod egui;
 pub mod telegram;
 pub mod web;
 pub mod socket_server;
+pub mod socket_client;
+pub mod grpc;
+mod service;
+pub use service::{Service, ServiceHealth, ServiceResult, Runner};
 
 use crate::{
-    runtime::Runtime,
+    runtime::{Runtime, DegradeSwitches, ConfigReload, ConfigEdit, metrics_report},
     config::{Config, ExtendedOption, UiOptions},
-    frontend::egui::Egui,
+    frontend::egui::{Egui, Settings, RuntimePanel, RuntimeStatus, ServiceStatus, MetricStatus, MetricHistoryStatus, SocketClientStatus, LogStatus},
+    logging::log_tail,
 };
+use futures::future::BoxFuture;
 use tokio::sync::RwLock;
+use std::{
+    sync::Arc,
+    time::Duration,
+};
 use log::{debug,error};
 
 
+/// How often [wait_for_services()] checks if the services are started
+const SERVICES_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many of the most recent log records [runtime_status()] includes
+const RUNTIME_STATUS_LOGS: usize = 64;
+
+/// How the Telegram bots are named among the services -- followed by their names. See [telegram_healths()]
+const TELEGRAM_SERVICE_PREFIX: &str = "telegram:";
+
+
 pub async fn async_run(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    match config.ui {
+    match &config.ui {
         ExtendedOption::Enabled(ui) => match ui {
-            UiOptions::Console(job) => console::async_run(&job, runtime, &config).await,
-            UiOptions::Terminal => Ok(()),//terminal::async_run(config, result).await,
+            UiOptions::Console(job) => console::async_run(job, runtime, config).await,
+            UiOptions::Terminal(_) => Ok(()),//terminal::async_run(config, result).await,
             UiOptions::Egui => Ok(()),
         }
         _ => panic!("BUG! empty `config.ui`"),
     }
 }
 
-pub fn run(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    match config.ui {
+pub fn run(runtime: &Arc<RwLock<Runtime>>, config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match &config.ui {
         ExtendedOption::Enabled(ui) => match ui {
-            UiOptions::Console(job) => console::run(&job, runtime, &config),
-            UiOptions::Terminal => terminal::run(runtime, &config),
+            UiOptions::Console(job) => console::run(job, runtime, config),
+            UiOptions::Terminal(options) => terminal::run(options, runtime, config),
             UiOptions::Egui => {
-                Egui::run_egui_native_app()
+                Egui::run_egui_native_app(egui_settings(runtime), egui_runtime_panel(runtime))
                     .unwrap_or_else(|err| error!("Error running egui: {:?}", err));
                 sync_shutdown_tokio_services(runtime)
             },
@@ -42,39 +62,240 @@ pub fn run(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std
     }
 }
 
-/// signals background (async Tokio) tasks that a graceful shutdown was requested
-pub async fn shutdown_tokio_services(runtime: &RwLock<Runtime>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Binds the settings the egui "Settings" window may change to the runtime: the degradation switches &, if the config hot reload is
+/// enabled, the config settings it may apply -- edited through [ConfigReload::edit()], like the admin API does
+fn egui_settings(runtime: &RwLock<Runtime>) -> Settings {
+    let (degrade, config_reload) = {
+        let runtime = runtime.blocking_read();
+        (Arc::clone(&runtime.degrade), runtime.config_reload.clone())
+    };
+    let switch = |read: fn(&DegradeSwitches) -> bool, set: fn(&DegradeSwitches, bool)| {
+        let (degrade_to_read, degrade_to_set) = (Arc::clone(&degrade), Arc::clone(&degrade));
+        (move || read(&degrade_to_read),
+         move |value| -> Result<(), String> { set(&degrade_to_set, value); Ok(()) })
+    };
+    let (maintenance, set_maintenance) = switch(DegradeSwitches::maintenance, DegradeSwitches::set_maintenance);
+    let (read_only_api, set_read_only_api) = switch(DegradeSwitches::read_only_api, DegradeSwitches::set_read_only_api);
+    let (disable_notifications, set_disable_notifications) = switch(DegradeSwitches::disable_telegram_notifications, DegradeSwitches::set_disable_telegram_notifications);
+    let settings = Settings::new()
+        .toggle("Maintenance mode", "Frontends refuse to serve users, answering the maintenance message instead", maintenance, set_maintenance)
+        .toggle("Read-only web API", "The web API refuses requests that would change the application's state", read_only_api, set_read_only_api)
+        .toggle("Disable Telegram notifications", "Telegram push notifications are discarded", disable_notifications, set_disable_notifications);
+    let Some(config_reload) = config_reload else {
+        return settings
+    };
+    // each config setting is shown & typed in RON -- `None` for the ones of disabled services
+    fn config_setting<T: serde::Serialize + serde::de::DeserializeOwned + 'static>(config_reload:   &Arc<ConfigReload>,
+                                                                                 field:           fn(&mut ConfigEdit) -> &mut Option<T>)
+                                                                                -> (impl Fn() -> String + Send + 'static, impl Fn(&str) -> Result<(), String> + Send + 'static) {
+        let (config_reload_to_read, config_reload_to_edit) = (Arc::clone(config_reload), Arc::clone(config_reload));
+        (move || {
+             let mut current = ConfigEdit::from_config(&config_reload_to_read.current());
+             field(&mut current).as_ref()
+                 .map_or_else(|| String::from("None"), |value| ron::to_string(value).unwrap_or_else(|err| format!("<{}>", err)))
+         },
+         move |typed: &str| {
+             let value = ron::from_str(typed)
+                 .map_err(|err| format!("Invalid value: {}", err))?;
+             let mut edit = ConfigEdit::default();
+             *field(&mut edit) = Some(value);
+             config_reload_to_edit.edit(&edit, "egui")
+                 .map(|_changes| ())
+                 .map_err(|err| err.to_string())
+         })
+    }
+    let (log_filters, set_log_filters) = config_setting(&config_reload, |edit| &mut edit.log_filters);
+    let (max_message_length, set_max_message_length) = config_setting(&config_reload, |edit| &mut edit.socket_server_max_message_length);
+    let (max_malformed_messages, set_max_malformed_messages) = config_setting(&config_reload, |edit| &mut edit.socket_server_max_malformed_messages);
+    let (ban_seconds, set_ban_seconds) = config_setting(&config_reload, |edit| &mut edit.socket_server_ban_seconds);
+    settings
+        .text("Log filters", "The log level of each target -- \"*\" for all the others. Like {\"*\":info,\"socket_server\":debug}", log_filters, set_log_filters)
+        .text("Socket server: max message length", "Longer messages get their socket clients disconnected & banned", max_message_length, set_max_message_length)
+        .text("Socket server: max malformed messages", "Socket clients sending this many malformed messages are disconnected & banned", max_malformed_messages, set_max_malformed_messages)
+        .text("Socket server: ban seconds", "For how long abusive socket clients are banned", ban_seconds, set_ban_seconds)
+}
 
-    debug!("Program logic is asking for a graceful shutdown...");
+/// Binds the egui "Runtime" window to the runtime: each refresh builds a [runtime_status()] in Tokio
+fn egui_runtime_panel(runtime: &Arc<RwLock<Runtime>>) -> RuntimePanel {
+    let runtime = Arc::clone(runtime);
+    RuntimePanel::new(move |feed| {
+        // Tokio is started along with the UI, so it may not be there yet -- and the UI thread should not wait for it
+        let tokio_runtime = runtime.try_read().ok()
+            .and_then(|runtime| runtime.tokio_runtime.clone());
+        let Some(tokio_runtime) = tokio_runtime else {
+            feed.publish(Err(String::from("The runtime is still starting")));
+            return
+        };
+        let runtime = Arc::clone(&runtime);
+        tokio_runtime.spawn(async move {
+            feed.publish(Ok(runtime_status(&runtime).await));
+        });
+    })
+}
 
-    tokio::join!(
+/// The snapshot of the running application shown by the egui "Runtime" window -- also served by the `/api/stats/runtime` route
+pub async fn runtime_status(runtime: &RwLock<Runtime>) -> RuntimeStatus {
+    let mut services = telegram_healths(runtime).await;
+    services.extend([
+        (String::from("web"),           Runtime::do_if_web_server_is_present(runtime, |web_server| Box::pin(async move { web_server.health() })).await),
+        (String::from("socket-server"), Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.health() })).await),
+        (String::from("socket-client"), Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.health() })).await),
+        (String::from("grpc"),          Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| Box::pin(async move { grpc_server.health() })).await),
+    ]);
+    let services = services.into_iter()
+        .map(|(name, health)| ServiceStatus {
+            name,
+            state: match health {
+                Some(ServiceHealth::Starting)     => "starting",
+                Some(ServiceHealth::Running)      => "running",
+                Some(ServiceHealth::ShuttingDown) => "shutting down",
+                None                              => "not running",
+            }.to_string(),
+        })
+        .collect();
+    let runtime = runtime.read().await;
+    let metrics = runtime.metrics.as_ref()
+        .map(|metrics| metrics_report(metrics).into_iter()
+            .filter(|family| family.kind == "counter" || family.kind == "gauge")
+            .map(|family| MetricStatus {
+                value: family.samples.iter().map(|sample| sample.value).sum(),
+                name:  family.name,
+            })
+            .collect())
+        .unwrap_or_default();
+    let history = runtime.metrics_history.as_ref()
+        .map(|metrics_history| metrics_history.names().into_iter()
+            .filter_map(|name| metrics_history.history(&name, None)
+                .map(|samples| MetricHistoryStatus {
+                    samples: samples.into_iter().map(|sample| (sample.timestamp, sample.value)).collect(),
+                    name,
+                }))
+            .collect())
+        .unwrap_or_default();
+    let socket_clients = runtime.socket_server_stats.report().endpoints.into_iter()
+        .filter(|endpoint| endpoint.connected)
+        .map(|endpoint| SocketClientStatus {
+            address:      endpoint.address,
+            messages_in:  endpoint.messages_in,
+            messages_out: endpoint.messages_out,
+            errors:       endpoint.errors,
+            idle_seconds: endpoint.idle_seconds,
+        })
+        .collect();
+    let (recent_records, _followers) = log_tail::subscribe();
+    let recent_logs = recent_records.into_iter().rev()
+        .take(RUNTIME_STATUS_LOGS)
+        .map(|record| LogStatus {
+            timestamp: record.timestamp,
+            level:     record.level,
+            target:    record.target,
+            message:   record.message,
+        })
+        .collect();
+    RuntimeStatus {
+        uptime_seconds: runtime.started_at.elapsed().as_secs(),
+        services,
+        metrics,
+        history,
+        socket_clients,
+        recent_logs,
+    }
+}
 
-        // shutdown telegram
-        Runtime::do_for_telegram_ui(runtime, |telegram_ui| Box::pin(async move {
-            if let Some(shutdown_token) = telegram_ui.shutdown_token.clone() {
-                shutdown_token.shutdown()
-                    .expect("Could not shutdown Telegram")
-                    .await;
-            }
-        })),
+/// The health of each Telegram bot -- named as `telegram:<bot name>` -- or a single, "not running", `telegram` if there are none
+pub async fn telegram_healths(runtime: &RwLock<Runtime>) -> Vec<(String, Option<ServiceHealth>)> {
+    let bot_names = runtime.read().await.telegram_bot_names();
+    if bot_names.is_empty() {
+        return vec![(String::from("telegram"), None)]
+    }
+    let mut healths = Vec::with_capacity(bot_names.len());
+    for bot_name in bot_names {
+        let health = Runtime::do_if_telegram_ui_is_present(runtime, &bot_name, |telegram_ui| Box::pin(async move { telegram_ui.health() })).await;
+        healths.push((format!("{}{}", TELEGRAM_SERVICE_PREFIX, bot_name), health));
+    }
+    healths
+}
 
-        // shutdown the web server
-        Runtime::do_for_web_server(runtime, |web_server| Box::pin(async move {
-            if let Some(shutdown_token) = web_server.shutdown_token.clone() {
-                shutdown_token.notify();
+/// asks the service named `service_name` -- telegram (all the bots), telegram:<bot name>, web, socket-server, socket-client or grpc --
+/// to shut down, for good. Returns what should be shown to the operator
+pub async fn stop_service(runtime: &RwLock<Runtime>, service_name: &str) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    let stopped = match service_name {
+        "telegram"      => {
+            let (bot_names, mut stopped) = (runtime.read().await.telegram_bot_names(), None);
+            for bot_name in bot_names {
+                stopped = Runtime::do_if_telegram_ui_is_present(runtime, &bot_name, |telegram_ui| Service::shutdown(telegram_ui)).await.or(stopped);
             }
-        })),
+            stopped
+        },
+        telegram_bot if telegram_bot.starts_with(TELEGRAM_SERVICE_PREFIX)
+                        => Runtime::do_if_telegram_ui_is_present(runtime, &telegram_bot[TELEGRAM_SERVICE_PREFIX.len()..], |telegram_ui| Service::shutdown(telegram_ui)).await,
+        "web"           => Runtime::do_if_web_server_is_present(runtime, |web_server| Service::shutdown(web_server)).await,
+        "socket-server" => Runtime::do_if_socket_server_is_present(runtime, |socket_server| Service::shutdown(socket_server)).await,
+        "socket-client" => Runtime::do_if_socket_client_is_present(runtime, |socket_client| Service::shutdown(socket_client)).await,
+        "grpc"          => Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| Service::shutdown(grpc_server)).await,
+        unknown => return Err(Box::from(format!("Unknown service '{}' -- known ones are: telegram (or telegram:<bot name>), web, socket-server, socket-client & grpc", unknown))),
+    };
+    match stopped {
+        Some(()) => Ok(format!("{} was asked to shut down", service_name)),
+        None => Err(Box::from(format!("{} is not running", service_name))),
+    }
+}
 
-        // shutdown socket server
-        Runtime::do_for_socket_server(runtime, |socket_server| Box::pin(async move {
-            socket_server.shutdown();
-        })),
+/// Starts `service`, registers it in the `runtime` & runs it -- only returning when it is over
+pub async fn run_service(runtime: &RwLock<Runtime>, mut service: impl Service) -> ServiceResult {
+    debug!("    starting the {} service...", service.name());
+    let runner = service.start().await?;
+    service.register(runtime).await;
+    runner.await
+}
 
+/// Waits until all services enabled in `config` are started -- as told by them being registered in the `runtime` (see [run_service()])
+/// & reporting [ServiceHealth::Running]: so the network services are already bound to their ports
+pub async fn wait_for_services(runtime: &RwLock<Runtime>, config: &Config) {
+    let ExtendedOption::Enabled(services) = &config.services else { return };
+    loop {
+        let telegram_started = !services.telegram.is_enabled() ||
+                               telegram_healths(runtime).await.iter().all(|(_name, health)| *health == Some(ServiceHealth::Running));
+        let web_started = !services.web.is_enabled() ||
+                          Runtime::do_if_web_server_is_present(runtime, |web_server| Box::pin(async move { web_server.health() })).await == Some(ServiceHealth::Running);
+        let socket_server_started = !services.socket_server.is_enabled() ||
+                                    Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.health() })).await == Some(ServiceHealth::Running);
+        let socket_client_started = !services.socket_client.is_enabled() ||
+                                    Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.health() })).await == Some(ServiceHealth::Running);
+        let grpc_started = !services.grpc.is_enabled() ||
+                           Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| Box::pin(async move { grpc_server.health() })).await == Some(ServiceHealth::Running);
+        if telegram_started && web_started && socket_server_started && socket_client_started && grpc_started {
+            break
+        }
+        tokio::time::sleep(SERVICES_POLL_INTERVAL).await;
+    }
+}
+
+/// signals background (async Tokio) tasks that a graceful shutdown was requested
+pub async fn shutdown_tokio_services(runtime: &RwLock<Runtime>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+
+    debug!("Program logic is asking for a graceful shutdown...");
+
+    // so the services are not restarted by the supervisor -- see [crate::runtime::Supervisor]
+    runtime.read().await.shutdown.request();
+
+    tokio::join!(
+        Runtime::do_for_each_telegram_ui(runtime, |telegram_ui| shutdown_service(telegram_ui)),
+        Runtime::do_for_web_server(runtime, |web_server| shutdown_service(web_server)),
+        Runtime::do_for_socket_server(runtime, |socket_server| shutdown_service(socket_server)),
+        // not waited for: they might be disabled
+        Runtime::do_if_socket_client_is_present(runtime, |socket_client| shutdown_service(socket_client)),
+        Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| shutdown_service(grpc_server)),
     );
 
     Ok(())
 }
 
+fn shutdown_service(service: &mut impl Service) -> BoxFuture<'_, ()> {
+    debug!("  shutting down the {} service -- health: {:?}", service.name(), service.health());
+    service.shutdown()
+}
+
 pub fn sync_shutdown_tokio_services(runtime: &RwLock<Runtime>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     runtime.blocking_read().tokio_runtime.as_ref().unwrap()
         .block_on(shutdown_tokio_services(runtime))
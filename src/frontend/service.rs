@@ -0,0 +1,46 @@
+//! The common interface of our long-running services -- [super::web::WebServer], [super::telegram::TelegramUI], [super::socket_server::SocketServer],
+//! [super::socket_client::SocketClient] & [super::grpc::GrpcServer] -- through which `main.rs` starts, registers, runs & shuts them down without caring about which service they are.\
+//! Example:
+//! ```no_compile
+//!     frontend::run_service(&runtime, WebServer::new(web_config, &runtime).await).await?;
+
+use crate::runtime::Runtime;
+use futures::future::BoxFuture;
+use tokio::sync::RwLock;
+
+
+/// What a service's runner results in
+pub type ServiceResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Runs a started service, only resolving when it is over -- see [Service::start()]
+pub type Runner = BoxFuture<'static, ServiceResult>;
+
+/// The lifecycle stage of a [Service]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceHealth {
+    /// instantiated, but not yet started -- or, for the network services, not yet listening
+    Starting,
+    /// started -- its [Runner] is executing & its ports, if any, are bound
+    Running,
+    /// a shutdown was requested -- its [Runner] is about to resolve
+    ShuttingDown,
+}
+
+pub trait Service: Send + Sync {
+
+    /// how this service is referred to in the logs
+    fn name(&self) -> &'static str;
+
+    /// Prepares the service to run, returning the [Runner] that executes it -- this special semantics allows the service to be
+    /// registered in the [Runtime] (so others may reach it & request it to shutdown) before running
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>>;
+
+    /// requests the service to cease running -- causing its [Runner] to resolve
+    fn shutdown(&self) -> BoxFuture<'_, ()>;
+
+    fn health(&self) -> ServiceHealth;
+
+    /// moves the service into its place in the [Runtime] -- see `Runtime::register_*()`
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()>
+                                                  where Self: Sized;
+}
@@ -0,0 +1,14 @@
+//! The socket client: the counterpart of [super::socket_server], connecting to the socket server of another instance (of an app built
+//! from this template) -- see [crate::config::SocketClientConfig].
+//!
+//! It speaks the very same protocol (see [super::socket_server::ClientMessages] & [super::socket_server::ServerMessages]) & follows
+//! the same processor-stream design, mirrored: the messages received from the server are fed to a processor stream, whose items (if any)
+//! are sent back to the server -- see [processor]. Requests may also be sent by the logic, at any time, through [SocketClient::sender()].
+//!
+//! TODO 2026-10-16: only the RON wire format is spoken -- the other formats of [crate::config::SocketWireFormat] require the client to be
+//!                  configured with the server's format &, for `Bincode`, to implement `message-io`'s `FramedTcp` framing.
+
+mod socket_client;
+pub use socket_client::*;
+
+mod processor;
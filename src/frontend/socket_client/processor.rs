@@ -0,0 +1,38 @@
+//! The socket client's processor: maps the stream of [ServerMessages] received from the server into the stream of [ClientMessages]
+//! to send back to it -- the mirror of the socket server's processors.\
+//! Customize [client_processor()] with whatever your app should do with the other instance's messages. It is called once per connection,
+//! so any states kept in it are reset on reconnections.
+
+use crate::frontend::socket_server::{ClientMessages, ServerMessages};
+use std::sync::Arc;
+use futures::{
+    future,
+    stream::{BoxStream, StreamExt},
+};
+use log::{debug, warn};
+
+
+/// Builds, for each connection, the stream of requests answering the stream of messages received from the server
+pub type ClientProcessor = Arc<dyn Fn(BoxStream<'static, ServerMessages>) -> BoxStream<'static, ClientMessages> + Send + Sync>;
+
+
+/// The demo processor: logs what the server sends, answering nothing but its keepalives -- the pings are sent by the client itself
+/// (see [crate::config::SocketClientConfig::ping_interval_millis])
+pub fn client_processor(server_messages: BoxStream<'static, ServerMessages>) -> BoxStream<'static, ClientMessages> {
+    server_messages
+        .filter_map(|server_message| {
+            match server_message {
+                ServerMessages::Welcome { protocol_version, features } => debug!("Socket Client: the server speaks the protocol v{} with us, with features {:?}", protocol_version, features),
+                ServerMessages::IncompatibleProtocol { min_version, max_version } => warn!("Socket Client: the server only speaks the protocol from v{} to v{} -- it closed our connection", min_version, max_version),
+                ServerMessages::Pong(count) => debug!("Socket Client: the server answered our ping #{}", count),
+                ServerMessages::KeepAlive(nonce) => return future::ready(Some(ClientMessages::KeepAliveAck(nonce))),
+                ServerMessages::Maintenance(message) => warn!("Socket Client: the server is in maintenance: {}", message),
+                ServerMessages::TooBusy => warn!("Socket Client: the server is too busy -- our last request was dropped"),
+                ServerMessages::TooManyClients(limit) => warn!("Socket Client: the server is full, with {} clients -- it closed our connection", limit),
+                ServerMessages::Unauthenticated(reason) => warn!("Socket Client: the server demands a login, which this client doesn't do: {}", reason),
+                other => debug!("Socket Client: received {:?}", other),
+            }
+            future::ready(None)
+        })
+        .boxed()
+}
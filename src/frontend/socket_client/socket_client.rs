@@ -0,0 +1,291 @@
+//! see [super]
+
+use super::processor::{ClientProcessor, client_processor};
+use crate::{
+    config::{Config, SocketClientConfig},
+    runtime::Runtime,
+    frontend::{
+        Service, ServiceHealth, Runner,
+        socket_server::{ClientMessages, PROTOCOL_VERSION, ron_client_serializer, ron_client_deserializer},
+    },
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use owning_ref::OwningRef;
+use futures::{
+    StreamExt,
+    future::BoxFuture,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::{mpsc, watch, RwLock},
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use log::{info, warn};
+
+
+/// The handle to start, use & shutdown the Socket Client
+pub struct SocketClient {
+    config:             OwningRef<Arc<Config>, SocketClientConfig>,
+    /// see [super::processor]
+    processor:          ClientProcessor,
+    /// through which the logic sends requests -- see [Self::sender()]
+    requests_sender:    mpsc::UnboundedSender<ClientMessages>,
+    /// taken by the runner
+    requests_receiver:  Option<mpsc::UnboundedReceiver<ClientMessages>>,
+    /// set while connected to the server
+    connected:          Arc<AtomicBool>,
+    /// `true` once [Service::shutdown()] is called -- see [Service::health()]
+    shutdown_requested: watch::Sender<bool>,
+}
+
+impl SocketClient {
+
+    pub fn new(client_config: OwningRef<Arc<Config>, SocketClientConfig>) -> Self {
+        let (requests_sender, requests_receiver) = mpsc::unbounded_channel();
+        Self {
+            config:             client_config,
+            processor:          Arc::new(client_processor),
+            requests_sender,
+            requests_receiver:  Some(requests_receiver),
+            connected:          Arc::new(AtomicBool::new(false)),
+            shutdown_requested: watch::channel(false).0,
+        }
+    }
+
+    /// Through which requests are sent to the server -- they wait, in order, while the client is (re)connecting. Example:
+    /// ```no_compile
+    ///     Runtime::do_for_socket_client(&runtime, |socket_client| Box::pin(async move { socket_client.sender().send(ClientMessages::Ping) })).await?;
+    /// ```
+    pub fn sender(&self) -> mpsc::UnboundedSender<ClientMessages> {
+        self.requests_sender.clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+}
+
+impl Service for SocketClient {
+
+    fn name(&self) -> &'static str {
+        "Socket Client"
+    }
+
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>> {
+        Box::pin(async move {
+            let requests = self.requests_receiver.take()
+                .ok_or("`requests_receiver` is not present. Was this client already executed?")?;
+            let runner: Runner = Box::pin(run((*self.config).clone(), Arc::clone(&self.processor), requests, Arc::clone(&self.connected), self.shutdown_requested.subscribe()));
+            Ok(runner)
+        })
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.shutdown_requested.send_replace(true);
+        })
+    }
+
+    /// [ServiceHealth::Running] as soon as started -- even if not yet connected, as the other instance may be down for a while
+    fn health(&self) -> ServiceHealth {
+        if *self.shutdown_requested.borrow() {
+            ServiceHealth::ShuttingDown
+        } else if self.requests_receiver.is_some() {
+            ServiceHealth::Starting
+        } else {
+            ServiceHealth::Running
+        }
+    }
+
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()> {
+        Box::pin(Runtime::register_socket_client(runtime, self))
+    }
+}
+
+/// Connects to the server -- reconnecting whenever the connection fails or is lost -- until a shutdown is requested
+async fn run(config:        SocketClientConfig,
+             processor:     ClientProcessor,
+             mut requests:  mpsc::UnboundedReceiver<ClientMessages>,
+             connected:     Arc<AtomicBool>,
+             mut shutdown:  watch::Receiver<bool>)
+            -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let connect_timeout = Duration::from_millis(config.connect_timeout_millis);
+    let reconnect_delay = Duration::from_millis(config.reconnect_delay_millis);
+    let ping_interval = (config.ping_interval_millis > 0).then(|| Duration::from_millis(config.ping_interval_millis));
+    loop {
+        let connection = tokio::select! {
+            () = shutdown_requested(&mut shutdown) => return Ok(()),
+            connection = tokio::time::timeout(connect_timeout, TcpStream::connect(&config.server_address)) => connection,
+        };
+        match connection {
+            Ok(Ok(stream)) => {
+                info!("Socket Client: connected to {}", config.server_address);
+                connected.store(true, Ordering::Relaxed);
+                let outcome = serve(stream, &processor, &mut requests, ping_interval, &mut shutdown).await;
+                connected.store(false, Ordering::Relaxed);
+                match outcome {
+                    Ok(Disconnection::ShutdownRequested) => return Ok(()),
+                    Ok(Disconnection::ByTheServer) => warn!("Socket Client: {} closed the connection -- reconnecting in {:?}", config.server_address, reconnect_delay),
+                    Err(err) => warn!("Socket Client: connection to {} failed: {} -- reconnecting in {:?}", config.server_address, err, reconnect_delay),
+                }
+            },
+            Ok(Err(err)) => warn!("Socket Client: could not connect to {}: {} -- retrying in {:?}", config.server_address, err, reconnect_delay),
+            Err(_elapsed) => warn!("Socket Client: connecting to {} timed out after {:?} -- retrying in {:?}", config.server_address, connect_timeout, reconnect_delay),
+        }
+        tokio::select! {
+            () = shutdown_requested(&mut shutdown) => return Ok(()),
+            () = tokio::time::sleep(reconnect_delay) => (),
+        }
+    }
+}
+
+/// How a connection ended
+enum Disconnection {
+    ShutdownRequested,
+    ByTheServer,
+}
+
+/// Exchanges messages through the connected `stream` until it ends: after saying `Hello`, the server's messages go through the `processor`,
+/// whose answers, the `requests` & the pings are sent to the server
+async fn serve(stream:        TcpStream,
+               processor:     &ClientProcessor,
+               requests:      &mut mpsc::UnboundedReceiver<ClientMessages>,
+               ping_interval: Option<Duration>,
+               shutdown:      &mut watch::Receiver<bool>)
+              -> Result<Disconnection, Box<dyn std::error::Error + Send + Sync>> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let (server_messages_sender, server_messages_receiver) = mpsc::unbounded_channel();
+    let mut answers = processor(UnboundedReceiverStream::new(server_messages_receiver).boxed());
+    let mut pings = ping_interval.map(|ping_interval| tokio::time::interval_at(tokio::time::Instant::now() + ping_interval, ping_interval));
+    // the server measures our round trips, if it sends keepalives -- answered by the `processor`
+    let hello = ClientMessages::Hello { protocol_version: PROTOCOL_VERSION, features: vec![String::from("keepalive")] };
+    writer.write_all(ron_client_serializer(&hello).as_bytes()).await?;
+    loop {
+        let outgoing = tokio::select! {
+            () = shutdown_requested(shutdown) => return Ok(Disconnection::ShutdownRequested),
+            line = lines.next_line() => {
+                let Some(line) = line? else { return Ok(Disconnection::ByTheServer) };
+                match ron_client_deserializer(line.as_bytes()) {
+                    Ok(server_message) => {
+                        server_messages_sender.send(server_message).ok();
+                    },
+                    Err(err) => warn!("Socket Client: could not understand the server's message -- dropping it: {}", err),
+                }
+                continue
+            },
+            Some(answer) = answers.next() => answer,
+            Some(request) = requests.recv() => request,
+            () = tick(&mut pings) => ClientMessages::Ping,
+        };
+        writer.write_all(ron_client_serializer(&outgoing).as_bytes()).await?;
+    }
+}
+
+/// resolves once `shutdown` is `true`
+async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) {
+    // the sender is kept by the client until the runner ends -- so `changed()` only fails if the client was dropped
+    while !*shutdown.borrow_and_update() {
+        if shutdown.changed().await.is_err() {
+            break
+        }
+    }
+}
+
+/// the next tick of `interval` -- never resolving if there is none
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        },
+        None => std::future::pending().await,
+    }
+}
+
+
+/// Unit tests the [socket_client](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ExtendedOption, DegradeConfig},
+        runtime::{DegradeSwitches, EventBus, PrivilegesGate},
+        frontend::socket_server::{SocketServer, SocketServerStats, BridgedPeers, ServerMessages},
+    };
+    use futures::{future, stream::BoxStream};
+
+
+    /// assures the requests of the logic reach the socket server of another instance -- whose answers are fed to the processor
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn round_trip() {
+        // the socket server's processor is given the Tokio runtime it runs on
+        let tokio_runtime = Arc::new(tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Building the Tokio runtime"));
+        tokio_runtime.block_on(async {
+            let port = std::net::TcpListener::bind("127.0.0.1:0")
+                .and_then(|listener| listener.local_addr())
+                .expect("Finding a free port")
+                .port();
+            let mut config = Config::default();
+            config.services.socket_server.interface = String::from("127.0.0.1");
+            config.services.socket_server.port = port;
+            config.services.socket_client = ExtendedOption::Enabled(SocketClientConfig {
+                server_address:         format!("127.0.0.1:{}", port),
+                connect_timeout_millis: 1000,
+                reconnect_delay_millis: 100,
+                ping_interval_millis:   0,
+            });
+            let config = Arc::new(config);
+
+            let mut socket_server = SocketServer::new(OwningRef::new(Arc::clone(&config)).map(|config| &*config.services.socket_server),
+                                                      Arc::new(SocketServerStats::new(16)), Arc::new(BridgedPeers::new()), Arc::new(DegradeSwitches::new(&DegradeConfig::default())), None, Arc::new(EventBus::new()));
+            let privileges = Arc::new(PrivilegesGate::new());
+            privileges.open();
+            socket_server.attach_processor(Arc::clone(&tokio_runtime), privileges).await;
+            let server_runner = tokio::spawn(socket_server.start().await.expect("Starting the socket server"));
+
+            let mut socket_client = SocketClient::new(OwningRef::new(Arc::clone(&config)).map(|config| &*config.services.socket_client));
+            // records what the server sends, answering nothing
+            let (server_messages_sender, mut server_messages) = mpsc::unbounded_channel();
+            socket_client.processor = Arc::new(move |messages: BoxStream<'static, ServerMessages>| {
+                let server_messages_sender = server_messages_sender.clone();
+                messages
+                    .filter_map(move |message| {
+                        server_messages_sender.send(message).ok();
+                        future::ready(None::<ClientMessages>)
+                    })
+                    .boxed()
+            });
+            // requests wait while the client is connecting -- which it retries until the server is up
+            socket_client.sender().send(ClientMessages::Ping).expect("Queuing the request");
+            let client_runner = tokio::spawn(socket_client.start().await.expect("Starting the socket client"));
+
+            let mut received = vec![];
+            while received.len() < 2 {
+                let message = tokio::time::timeout(Duration::from_secs(5), server_messages.recv()).await
+                    .expect("Nothing was received from the server in time")
+                    .expect("The processor should outlive the connection");
+                received.push(message);
+            }
+            assert!(matches!(&received[0], ServerMessages::Welcome { protocol_version: PROTOCOL_VERSION, .. }), "The server should have welcomed our `Hello`: {:?}", received);
+            assert_eq!(received[1], ServerMessages::Pong(1), "The server should have answered our `Ping`: {:?}", received);
+            assert!(socket_client.is_connected(), "The client should be connected");
+
+            Service::shutdown(&socket_client).await;
+            client_runner.await.expect("Joining the socket client")
+                .expect("The socket client should have ended gracefully");
+            Service::shutdown(&socket_server).await;
+            server_runner.await.expect("Joining the socket server")
+                .expect("The socket server should have ended gracefully");
+        });
+    }
+}
@@ -0,0 +1,118 @@
+//! The local hops made to the socket server on behalf of other clients: the ones of the transports `message-io` doesn't speak -- see
+//! [super::tls] & [super::unix_socket] -- & the ones of the web gateways -- see [crate::frontend::web::socket_proxy].
+//!
+//! Each hop is registered -- in [BridgedPeers] -- before connecting, so [super::socket_server] knows the client it serves: limits, bans &
+//! statistics apply to that client, rather than to the local address.\
+//! The TLS & Unix socket transports have `message-io` listening on an ephemeral loopback port. As any local process may connect to
+//! that port, the connections not registered are refused as soon as they are accepted -- so the plain listener is only ever reached
+//! through its bridge.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use parking_lot::RwLock;
+use tokio::net::{TcpSocket, TcpStream};
+use message_io::network::Endpoint;
+
+
+/// For how long, after a connection ends, its mapping is kept -- so its late events (like the disconnection) are still attributed to the client
+const MAPPING_GRACE: Duration = Duration::from_secs(5);
+
+
+/// The local hops made to the socket server, mapped to the addresses of the clients they serve -- shared through
+/// [crate::runtime::Runtime::socket_server_peers]
+pub struct BridgedPeers {
+    /// (hop address -> client address)
+    peers: RwLock<HashMap<SocketAddr, SocketAddr>>,
+}
+
+impl BridgedPeers {
+
+    pub fn new() -> Self {
+        Self {
+            peers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// the address of the client behind `endpoint` -- which is its own, unless it is a hop
+    pub fn addr_of(&self, endpoint: Endpoint) -> SocketAddr {
+        self.client_addr(endpoint.addr())
+    }
+
+    /// tells if `endpoint` is a registered hop -- the only connections served through the TLS & Unix socket transports
+    pub fn is_hop(&self, endpoint: Endpoint) -> bool {
+        self.is_hop_addr(endpoint.addr())
+    }
+
+    fn client_addr(&self, addr: SocketAddr) -> SocketAddr {
+        self.peers.read().get(&addr).copied().unwrap_or(addr)
+    }
+
+    fn is_hop_addr(&self, addr: SocketAddr) -> bool {
+        self.peers.read().contains_key(&addr)
+    }
+
+    /// Connects a hop, serving `client_addr`, to the socket server listening at `server_addr` -- clients with no address (like the
+    /// Unix socket ones) being known by the hop's. The returned guard keeps the hop registered -- see [HopGuard::release()]
+    pub async fn connect(self: &Arc<Self>, server_addr: SocketAddr, client_addr: Option<SocketAddr>) -> Result<(TcpStream, HopGuard), std::io::Error> {
+        let hop = if server_addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+        // from the same interface, so the hop is local
+        hop.bind(SocketAddr::new(server_addr.ip(), 0))?;
+        let hop_addr = hop.local_addr()?;
+        // the mapping must be known before `message-io` accepts the hop's connection
+        self.peers.write().insert(hop_addr, client_addr.unwrap_or(hop_addr));
+        let guard = HopGuard { peers: Arc::clone(self), hop_addr };
+        let plain_stream = hop.connect(server_addr).await?;
+        Ok((plain_stream, guard))
+    }
+}
+
+/// Keeps a hop registered in [BridgedPeers] -- unregistering it when dropped
+pub struct HopGuard {
+    peers:    Arc<BridgedPeers>,
+    hop_addr: SocketAddr,
+}
+
+impl HopGuard {
+    /// unregisters the hop after [MAPPING_GRACE] -- for its connection has ended
+    pub async fn release(self) {
+        tokio::time::sleep(MAPPING_GRACE).await;
+    }
+}
+
+impl Drop for HopGuard {
+    fn drop(&mut self) {
+        self.peers.peers.write().remove(&self.hop_addr);
+    }
+}
+
+
+/// Unit tests the [bridge](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures only the registered hops are mapped to their clients -- & only while they last
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn only_hops_are_mapped() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Binding the plain listener");
+        let internal_addr = listener.local_addr().expect("Getting the plain listener's address");
+        let peers = Arc::new(BridgedPeers::new());
+        let client_addr = "10.0.0.7:41234".parse().unwrap();
+        let (plain_stream, hop) = peers.connect(internal_addr, Some(client_addr)).await.expect("Connecting the hop");
+        let (_accepted, hop_addr) = listener.accept().await.expect("Accepting the hop");
+        assert_eq!(plain_stream.local_addr().unwrap(), hop_addr, "Sanity check");
+        assert!(peers.is_hop_addr(hop_addr), "The hop should have been registered");
+        assert_eq!(peers.client_addr(hop_addr), client_addr, "The hop should have been mapped to its client");
+        let intruder = tokio::net::TcpStream::connect(internal_addr).await.expect("Connecting directly");
+        let intruder_addr = intruder.local_addr().unwrap();
+        assert!(!peers.is_hop_addr(intruder_addr), "Connections not made through `connect()` should not have been taken as hops");
+        assert_eq!(peers.client_addr(intruder_addr), intruder_addr, "Connections other than hops should have been known by their own addresses");
+        drop(hop);
+        assert!(!peers.is_hop_addr(hop_addr), "Ended hops should have been unregistered");
+    }
+}
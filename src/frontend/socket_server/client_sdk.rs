@@ -0,0 +1,166 @@
+//! Generates a small, standalone Rust client crate for the socket protocol -- so external teams may integrate with
+//! this server without copying its internal sources. See [crate::config::Jobs::GenerateClientSdk].
+//!
+//! The generated crate contains `ClientMessages` & `ServerMessages` -- extracted from [super::protocol]'s sources,
+//! so they never drift apart -- along with the serializers & a blocking client honoring this server's framing.
+//!
+//! TODO 2026-10-16: also emit a TypeScript client, for the JSON wire format (RON has no good TS support).
+//!                  The generated client speaks RON only: keep its framing in sync with [crate::config::SocketWireFormat::Ron] in [super::socket_server].
+
+use crate::config::APP_NAME;
+use std::fs;
+use log::info;
+
+
+/// The sources the messages are extracted from
+const PROTOCOL_SOURCE: &str = include_str!("protocol.rs");
+
+const CARGO_TOML_TEMPLATE: &str = r#"[package]
+name = "{CRATE_NAME}"
+version = "{VERSION}"
+edition = "2021"
+description = "Client for the {APP_NAME} socket protocol -- GENERATED: do not edit"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+ron   = "0.8"
+"#;
+
+const LIB_RS_TEMPLATE: &str = r#"//! Client for the {APP_NAME} socket protocol (version {VERSION}).
+//!
+//! GENERATED by `{APP_NAME} console generate-client-sdk` -- do not edit: regenerate it instead.
+//!
+//! Framing: messages are serialized in RON, one per line (terminated by '\n'), over plain TCP. Usage:
+//! ```no_run
+//!     let mut client = {CRATE_IDENT}::Client::connect("127.0.0.1:9758")?;
+//!     let answer = client.request(&{CRATE_IDENT}::ClientMessages::Ping)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+use serde::{Serialize, Deserialize};
+
+
+/// The version of the messages below -- to be told to the server through `ClientMessages::Hello`
+pub const PROTOCOL_VERSION: u32 = {PROTOCOL_VERSION};
+
+{CLIENT_MESSAGES}
+
+{SERVER_MESSAGES}
+
+
+/// serializes `message` as expected by the server -- including the line terminator
+pub fn serialize(message: &ClientMessages) -> Result<String, ron::Error> {
+    let mut line = ron::ser::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// deserializes a `line` sent by the server
+pub fn deserialize(line: &str) -> Result<ServerMessages, ron::error::SpannedError> {
+    ron::from_str(line.trim_end())
+}
+
+/// A blocking client, for the simplest integrations
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self { reader, writer })
+    }
+
+    pub fn send(&mut self, message: &ClientMessages) -> io::Result<()> {
+        let line = serialize(message)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidInput, format!("could not serialize {:?}: {}", message, err)))?;
+        self.writer.write_all(line.as_bytes())
+    }
+
+    /// waits for the next message from the server
+    pub fn receive(&mut self) -> io::Result<ServerMessages> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "the server closed the connection"))
+        }
+        deserialize(&line)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, format!("could not deserialize {:?}: {}", line, err)))
+    }
+
+    /// sends `message` & waits for the answer -- not to be used for messages the server doesn't answer to
+    pub fn request(&mut self, message: &ClientMessages) -> io::Result<ServerMessages> {
+        self.send(message)?;
+        self.receive()
+    }
+}
+"#;
+
+
+/// Writes the client crate into `output_dir` -- which is created, if needed
+pub fn generate_client_sdk(output_dir: &str) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let crate_name = format!("{}-client", APP_NAME);
+    let replace_placeholders = |template: &str| -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(template
+            .replace("{CRATE_NAME}",       &crate_name)
+            .replace("{CRATE_IDENT}",      &crate_name.replace('-', "_"))
+            .replace("{APP_NAME}",         APP_NAME)
+            .replace("{VERSION}",          env!("CARGO_PKG_VERSION"))
+            .replace("{PROTOCOL_VERSION}", &super::protocol::PROTOCOL_VERSION.to_string())
+            .replace("{CLIENT_MESSAGES}",  extract_enum(PROTOCOL_SOURCE, "ClientMessages")?)
+            .replace("{SERVER_MESSAGES}",  extract_enum(PROTOCOL_SOURCE, "ServerMessages")?))
+    };
+    let src_dir = format!("{}/src", output_dir);
+    fs::create_dir_all(&src_dir)
+        .map_err(|err| format!("Could not create the client SDK directory '{}': {}", src_dir, err))?;
+    for (file, template) in [(format!("{}/Cargo.toml", output_dir), CARGO_TOML_TEMPLATE),
+                             (format!("{}/lib.rs", src_dir),        LIB_RS_TEMPLATE)] {
+        fs::write(&file, replace_placeholders(template)?)
+            .map_err(|err| format!("Could not write the client SDK file '{}': {}", file, err))?;
+    }
+    info!("Client SDK crate '{}' generated at '{}'", crate_name, output_dir);
+    Ok(())
+}
+
+/// returns the source of the `enum_name` enum -- along with its doc comments & attributes
+fn extract_enum<'a>(source: &'a str, enum_name: &str) -> Result<&'a str, Box<dyn std::error::Error + Sync + Send>> {
+    let declaration = format!("\npub enum {} {{", enum_name);
+    let declaration_start = source.find(&declaration)
+        .ok_or_else(|| format!("Could not find `pub enum {}` in the protocol sources", enum_name))? + 1;
+    // walks back over the doc comments & attributes
+    let mut start = declaration_start;
+    for line in source[..declaration_start].lines().rev() {
+        if !line.starts_with("///") && !line.starts_with("#[") {
+            break
+        }
+        start = source[..start - 1].rfind('\n').map_or(0, |newline| newline + 1);
+    }
+    let end = source[declaration_start..].find("\n}")
+        .ok_or_else(|| format!("Could not find the end of `pub enum {}` in the protocol sources", enum_name))?;
+    Ok(&source[start..declaration_start + end + 2])
+}
+
+
+/// Unit tests the [client_sdk](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn enums_are_extracted_with_their_docs() {
+        let extracted = extract_enum(PROTOCOL_SOURCE, "ClientMessages")
+            .expect("`ClientMessages` should have been extracted");
+        assert!(extracted.starts_with("/// Messages coming from the clients"), "Doc comments are missing from {:?}", extracted);
+        assert!(extracted.contains("#[derive(Debug, PartialEq, Serialize, Deserialize)]"), "Derives are missing from {:?}", extracted);
+        assert!(extracted.contains("    Ping,"), "Variants are missing from {:?}", extracted);
+        assert!(extracted.ends_with("\n}"), "The enum should end with its closing brace: {:?}", extracted);
+        assert!(!extracted.contains("ServerMessages {"), "Only `ClientMessages` should have been extracted: {:?}", extracted);
+    }
+}
@@ -0,0 +1,170 @@
+//! Bandwidth throttling for the socket server's answers -- see [crate::config::SocketServerConfig::egress_limit].
+//!
+//! Token buckets -- one for each client & a global one -- are drained by the bytes about to be sent: when they go short,
+//! the answer is delayed until they are refilled, at the configured rates. Idle buckets fill up to the configured bursts.\
+//! Only the processor's answers are throttled: the few bytes of the control messages (`TooBusy`, `Maintenance`, `Banned`, ...) are not.
+//!
+//! Notice the delays are applied in the sender stream: with the `Serial`, `Concurrent` & `Adaptive` processor strategies, answers are sent one at a time,
+//! so a client held by its own limit also holds the answers for the others. Use the `Parallel` strategy for the per-connection limit to be fair.
+//!
+//! TODO 2026-10-16: hold the answers in per-client queues, so a throttled client doesn't delay the others regardless of the processor strategy.
+//!                  Requires `message-io` to be replaced by our Tokio version, which will have a sender task for each connection
+
+use crate::config::{ExtendedOption, EgressLimitConfig};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use metrics::{counter, histogram, describe_counter, describe_histogram, Unit};
+
+
+/// Counter of the bytes of the answers sent to the clients
+pub const BYTES_METRIC: &str = "socket_egress_bytes_total";
+/// Counter of the answers delayed by the limits, labeled by the `limit` (`connection` or `global`) that delayed them the most
+pub const THROTTLED_METRIC: &str = "socket_egress_throttled_total";
+/// Histogram of how long the throttled answers were delayed
+pub const DELAY_METRIC: &str = "socket_egress_delay_seconds";
+
+/// Above this many clients, the buckets of the ones that didn't receive anything for a while are forgotten
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+
+/// registers the descriptions of the egress metrics -- to be called once the recorder is installed
+pub fn describe_metrics() {
+    describe_counter!(BYTES_METRIC, Unit::Bytes, "Bytes of the answers sent by the socket server");
+    describe_counter!(THROTTLED_METRIC, Unit::Count, "Socket server answers delayed by the egress limits, by the limit that delayed them the most");
+    describe_histogram!(DELAY_METRIC, Unit::Seconds, "How long the socket server answers delayed by the egress limits waited to be sent");
+}
+
+/// Tells how long each answer should wait before being sent, so the configured egress limits are honored
+pub struct EgressThrottle {
+    /// `None` if throttling was not configured
+    config:  Option<EgressLimitConfig>,
+    global:  Mutex<Bucket>,
+    clients: Mutex<HashMap<SocketAddr, Bucket>>,
+}
+
+impl EgressThrottle {
+
+    pub fn new(egress_limit_config: &ExtendedOption<EgressLimitConfig>) -> Self {
+        let config = match egress_limit_config {
+            ExtendedOption::Enabled(config) => Some(config.clone()),
+            _ => None,
+        };
+        let now = Instant::now();
+        Self {
+            global:  Mutex::new(Bucket::new(config.as_ref().map_or(0, |config| config.global_burst_bytes), now)),
+            clients: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    /// accounts for `bytes` about to be sent to `addr`, returning for how long they should wait -- `Duration::ZERO` if they may go right away
+    pub fn reserve(&self, addr: SocketAddr, bytes: usize) -> Duration {
+        counter!(BYTES_METRIC).increment(bytes as u64);
+        let Some(config) = &self.config else { return Duration::ZERO };
+        let now = Instant::now();
+        let connection_delay = if config.per_connection_bytes_per_second > 0 {
+            let mut clients = self.clients.lock();
+            if clients.len() > MAX_IDLE_BUCKETS {
+                clients.retain(|_addr, bucket| !bucket.is_full(config.per_connection_bytes_per_second, config.per_connection_burst_bytes, now));
+            }
+            clients.entry(addr)
+                .or_insert_with(|| Bucket::new(config.per_connection_burst_bytes, now))
+                .reserve(bytes, config.per_connection_bytes_per_second, config.per_connection_burst_bytes, now)
+        } else {
+            Duration::ZERO
+        };
+        let global_delay = if config.global_bytes_per_second > 0 {
+            self.global.lock().reserve(bytes, config.global_bytes_per_second, config.global_burst_bytes, now)
+        } else {
+            Duration::ZERO
+        };
+        let delay = connection_delay.max(global_delay);
+        if delay > Duration::ZERO {
+            let limit = if connection_delay >= global_delay { "connection" } else { "global" };
+            counter!(THROTTLED_METRIC, "limit" => limit).increment(1);
+            histogram!(DELAY_METRIC).record(delay.as_secs_f64());
+        }
+        delay
+    }
+}
+
+/// A token bucket, where each token allows a byte to be sent.\
+/// Reservations are always accepted, taking the bucket into debt -- which tells how long to wait for them
+struct Bucket {
+    tokens:      f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+
+    fn new(burst_bytes: u64, now: Instant) -> Self {
+        Self { tokens: burst_bytes as f64, last_refill: now }
+    }
+
+    fn refill(&mut self, bytes_per_second: u64, burst_bytes: u64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * bytes_per_second as f64).min(burst_bytes as f64);
+        self.last_refill = now;
+    }
+
+    fn reserve(&mut self, bytes: usize, bytes_per_second: u64, burst_bytes: u64, now: Instant) -> Duration {
+        self.refill(bytes_per_second, burst_bytes, now);
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / bytes_per_second as f64)
+        }
+    }
+
+    fn is_full(&mut self, bytes_per_second: u64, burst_bytes: u64, now: Instant) -> bool {
+        self.refill(bytes_per_second, burst_bytes, now);
+        self.tokens >= burst_bytes as f64
+    }
+}
+
+
+/// Unit tests the [egress_throttle](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    fn config(per_connection_bytes_per_second: u64, global_bytes_per_second: u64) -> ExtendedOption<EgressLimitConfig> {
+        ExtendedOption::Enabled(EgressLimitConfig {
+            per_connection_bytes_per_second,
+            per_connection_burst_bytes: 1000,
+            global_bytes_per_second,
+            global_burst_bytes:         1000,
+        })
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn bursts_go_right_away_and_the_excess_waits() {
+        let throttle = EgressThrottle::new(&config(1000, 0));
+        let client = "127.0.0.1:4321".parse().unwrap();
+        assert_eq!(throttle.reserve(client, 1000), Duration::ZERO, "The burst should have been sent right away");
+        let delay = throttle.reserve(client, 500);
+        assert!(delay > Duration::from_millis(400) && delay <= Duration::from_millis(500), "500 bytes over the burst, at 1000 bytes/s, should wait ~500ms -- not {:?}", delay);
+        let other_client = "127.0.0.1:4322".parse().unwrap();
+        assert_eq!(throttle.reserve(other_client, 1000), Duration::ZERO, "Each client should have its own burst");
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn global_limit_is_shared() {
+        let throttle = EgressThrottle::new(&config(0, 1000));
+        assert_eq!(throttle.reserve("127.0.0.1:4321".parse().unwrap(), 1000), Duration::ZERO, "The global burst should have been sent right away");
+        let delay = throttle.reserve("127.0.0.1:4322".parse().unwrap(), 1000);
+        assert!(delay > Duration::from_millis(900), "Another client shouldn't escape the global limit -- it waited only {:?}", delay);
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn disabled_never_waits() {
+        let throttle = EgressThrottle::new(&ExtendedOption::Disabled);
+        assert_eq!(throttle.reserve("127.0.0.1:4321".parse().unwrap(), 1 << 30), Duration::ZERO, "Unconfigured throttling shouldn't delay anything");
+    }
+}
@@ -11,7 +11,7 @@ use super::{
 };
 use std::{
     sync::Arc,
-    time::Duration,
+    task::Poll,
 };
 use std::future::Future;
 use futures::{stream, Stream, StreamExt, SinkExt};
@@ -30,15 +30,15 @@ use tokio::sync::mpsc::error::TrySendError;
 /// for the producer Channel
 pub const SENDER_BUFFER: usize  = 8192;
 
-/// for the concurrent executor
-pub const CONCURRENCY: usize = 16;
-
-/// for the parallel executor
-pub const PAR_PARAMS: ParParamsConfig =
-    //ParParamsConfig::Default;
+/// for the parallel executor -- `workers` being 0 means one per CPU
+pub fn par_params(workers: usize) -> ParParamsConfig {
+    //ParParamsConfig::Default
     //ParParamsConfig::ScaleOfCpus { scale: 1.0 }
-    ParParamsConfig::Manual { num_workers: NumWorkers::Default, buf_size: BufSize::Fixed(8192) }
-;
+    ParParamsConfig::Manual {
+        num_workers: if workers == 0 { NumWorkers::Default } else { NumWorkers::Fixed(workers) },
+        buf_size:    BufSize::Fixed(8192),
+    }
+}
 
 
 /// creates a tuple of (stream, producer, closer) tied together using `futures::channel::mpsc::channel`\
@@ -68,28 +68,44 @@ pub fn sync_futures_stream(_tokio_runtime: Arc<tokio::runtime::Runtime>)
 
 /// creates creates a tuple of  (stream, producer, closer) tied together using `tokio::sync::mpsc::channel`\
 /// tokio channel -- through `.try_send()` is ~ 15% faster than using `futures`'s\
-/// producer function is able to tell if the channel is full (so the server answers TooBusy),
-/// but the close function is horrible
+/// producer function is able to tell if the channel is full (so the server answers TooBusy).\
+/// The closer marks the end of the stream: events already queued are still yielded, but no new ones are accepted
+/// -- the stream ends as soon as they are drained
 pub fn sync_tokio_stream(_tokio_runtime: Arc<tokio::runtime::Runtime>)
                         -> (impl Stream<Item = SocketEvent<ClientMessages>>,     // stream of client requests
                             impl FnMut(SocketEvent<ClientMessages>) -> bool,     // producer of client requests (adds to the stream)
                             impl FnMut()) {                                      // closer (closes the stream)
 
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<SocketEvent<ClientMessages>>(SENDER_BUFFER);
-    let stream = stream::poll_fn(move |cx| rx.poll_recv(cx));
+    // `None` is the end-of-stream mark sent by the closer
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Option<SocketEvent<ClientMessages>>>(SENDER_BUFFER);
+    let tx_for_close = tx.clone();
+    let stream = stream::poll_fn(move |cx| loop {
+        match rx.poll_recv(cx) {
+            Poll::Ready(Some(Some(socket_event))) => return Poll::Ready(Some(socket_event)),
+            // refuses new events, while still draining the queued ones -- after which `poll_recv()` yields `None`
+            Poll::Ready(Some(None)) => rx.close(),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        }
+    });
 
     (
         stream,
         // blocking producer
-        move |incoming| match tx.try_send(incoming) {
+        move |incoming| match tx.try_send(Some(incoming)) {
             Ok(_) => true,
             Err(err) => match err {
                 TrySendError::Full(_) => false,
-                TrySendError::Closed(err) => panic!("Could not send Socket Server network event. The `Stream` upgraded by `processor::processor` closed: {:?}", err),
+                TrySendError::Closed(err) => {
+                    debug!("Socket Server network event discarded, as the `Stream` upgraded by `processor::processor` was closed: {:?}", err);
+                    false
+                },
             }
         },
-        // stupid "close" function, as tokio channels don't provide a way of syncing or even closing a channel before they are dropped
-        move || std::thread::sleep(Duration::from_secs(5)),
+        // sends the end-of-stream mark -- waiting for room in the channel, if needed (to be called outside of async contexts)
+        move || if tx_for_close.blocking_send(None).is_err() {
+            debug!("Socket Server processor's `Stream` was already closed");
+        },
     )
 }
 
@@ -109,10 +125,10 @@ pub async fn spawn_stream_executor(stream: impl Stream<Item = (Endpoint, SendSta
 }
 
 /// dummy stream executor allowing parallel execution -- In use while Mutiny library is not released
-pub async fn spawn_parallel_stream_executor(stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static) -> tokio::task::JoinHandle<()> {
+pub async fn spawn_parallel_stream_executor(stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static, workers: usize) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         debug!("Experimental Parallel Stream Executor started!");
-        stream.par_for_each(PAR_PARAMS, |(endpoint, send_status)| async move {
+        stream.par_for_each(par_params(workers), |(endpoint, send_status)| async move {
             if let SendStatus::Sent = send_status {
                 // sending was OK
             } else {
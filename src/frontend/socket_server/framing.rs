@@ -0,0 +1,111 @@
+//! Our framing for [crate::config::SocketTransport::Framed] & for the binary wire formats: each message is prefixed by its length, as an
+//! unsigned varint -- the very wire format of `message-io`'s `FramedTcp`, which clients may keep using.
+//!
+//! We don't receive through `FramedTcp`, as its decoder buffers frames of whatever length the clients announce: connections are plain `Tcp`
+//! & their frames are reassembled by [FrameDecoder] -- which refuses the frames above the limit as soon as their length is known.
+
+/// The most bytes a varint-encoded `u64` takes
+const MAX_VARINT_LEN: u32 = 10;
+
+
+/// prefixes `payload` with its length
+pub fn frame(payload: Vec<u8>) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(MAX_VARINT_LEN as usize + payload.len());
+    let mut length = payload.len();
+    while length >= 0x80 {
+        framed.push((length as u8 & 0x7f) | 0x80);
+        length >>= 7;
+    }
+    framed.push(length as u8);
+    framed.extend_from_slice(&payload);
+    framed
+}
+
+/// Reassembles the frames of a connection from the bytes it delivers, as they come
+#[derive(Default)]
+pub struct FrameDecoder {
+    /// the length prefix being read -- & how many bits of it were read
+    length:   usize,
+    shift:    u32,
+    /// the length of the frame being received -- once its prefix was read
+    expected: Option<usize>,
+    payload:  Vec<u8>,
+}
+
+impl FrameDecoder {
+
+    /// the payloads of the frames completed by `data` -- or `Err(<announced length>)` as soon as a frame longer than `max_length` is
+    /// announced, before any of it is buffered
+    pub fn decode(&mut self, mut data: &[u8], max_length: usize) -> Result<Vec<Vec<u8>>, usize> {
+        let mut frames = vec![];
+        while let Some((&byte, rest)) = data.split_first() {
+            match self.expected {
+                None => {
+                    data = rest;
+                    let bits = (byte & 0x7f) as usize;
+                    // overlong prefixes
+                    if self.shift >= usize::BITS || (bits << self.shift) >> self.shift != bits {
+                        return Err(usize::MAX)
+                    }
+                    self.length |= bits << self.shift;
+                    self.shift += 7;
+                    if byte & 0x80 != 0 {
+                        continue
+                    }
+                    let length = std::mem::take(&mut self.length);
+                    self.shift = 0;
+                    if length > max_length {
+                        return Err(length)
+                    }
+                    if length > 0 {
+                        self.expected = Some(length);
+                        self.payload.reserve(length);
+                    }
+                },
+                Some(expected) => {
+                    let missing = (expected - self.payload.len()).min(data.len());
+                    let (chunk, rest) = data.split_at(missing);
+                    self.payload.extend_from_slice(chunk);
+                    data = rest;
+                    if self.payload.len() == expected {
+                        self.expected = None;
+                        frames.push(std::mem::take(&mut self.payload));
+                    }
+                },
+            }
+        }
+        Ok(frames)
+    }
+}
+
+
+/// Unit tests the [framing](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures frames are reassembled however their bytes are delivered -- & that the ones above the limit are refused by their length prefix
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn reassembling() {
+        let messages = [vec![1u8; 3], vec![2u8; 200], vec![3u8; 1]];
+        let stream: Vec<u8> = messages.iter().cloned().flat_map(frame).collect();
+        assert_eq!(&stream[4..7], &[0xc8, 0x01, 2], "Sanity check: the 200 bytes message should have a 2 bytes prefix");
+
+        let mut decoder = FrameDecoder::default();
+        assert_eq!(decoder.decode(&stream, 1024), Ok(messages.to_vec()), "All frames delivered at once should have been decoded");
+
+        let mut decoder = FrameDecoder::default();
+        let decoded: Vec<Vec<u8>> = stream.chunks(1)
+            .flat_map(|byte| decoder.decode(byte, 1024).expect("Decoding byte by byte"))
+            .collect();
+        assert_eq!(decoded, messages.to_vec(), "Frames delivered byte by byte should have been decoded");
+
+        let mut decoder = FrameDecoder::default();
+        assert_eq!(decoder.decode(&stream[..5], 100), Ok(vec![messages[0].clone()]), "The frames within the limit should have been accepted");
+        assert_eq!(decoder.decode(&stream[5..6], 100), Err(200), "The frame above the limit should have been refused as soon as its length was known");
+
+        let mut decoder = FrameDecoder::default();
+        assert_eq!(decoder.decode(&[0xff; 11], usize::MAX), Err(usize::MAX), "Overlong length prefixes should have been refused");
+    }
+}
@@ -1,50 +1,68 @@
 //! Here you'll see a demonstration of how to create an async processor that uses a single thread to perform several async tasks.\
-//! A similar performance to [serial_processor] was achieved using the `.then()` version (see bellow).
+//! A similar performance to [serial_processor] was achieved using the `.then()` version -- equivalent to a `buffer` of 1 (see bellow).
 
 //! Analysis:
 //!   - Possibly `.then()` implies no buffering of futures -- therefore, no concurrency.
+//!
+//! It also backs [crate::config::SocketProcessorStrategy::Adaptive] -- see [adaptive_sync_processors()].
 
 use super::{
     types::*,
     socket_server::SocketEvent,
     protocol::{ClientMessages, ServerMessages},
+    processor_metrics,
+    panic_isolation,
+};
+use crate::{
+    config::SocketPanicPolicy,
+    runtime::{Alert, Topic},
 };
 use std::{
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
     collections::HashMap,
     future::Future,
 };
-use futures::{Stream, StreamExt, FutureExt};
+use futures::{Stream, StreamExt, future::Either};
 use par_stream::prelude::*;
-use message_io::network::{Endpoint, SendStatus};
-use tokio::sync::{RwLock};
+use message_io::network::Endpoint;
+use tokio::sync::{RwLock, Semaphore};
+use tracing::Instrument;
+use log::debug;
 
 
 /// customize this to hold the states you want for each client
 #[derive(Debug)]
 struct ClientStates {
-    count: usize,
+    count:    usize,
+    /// the optional features negotiated by the client's `Hello` -- see [super::protocol::SERVER_FEATURES]
+    features: Vec<String>,
 }
 
 /// Here is where the main "protocol" processor logic lies: returns a Stream pipeline able to
 /// transform client inputs ([ClientMessages] requests) into server outputs ([ServerMessages] answers)
-fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
+/// -- running up to `buffer` requests at once or, if `gate` is given, as many as it lets through
+fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, buffer: usize, gate: Option<Arc<AdaptiveGate>>)
             -> impl Stream<Item = Result<(Endpoint, ServerMessages),
                                          (Endpoint, Box<dyn std::error::Error + Sync + Send>)> > {
 
     let client_states = Arc::new(RwLock::new(HashMap::<Endpoint, ClientStates>::new()));
 
     stream
-        .map(|socket_event: SocketEvent<ClientMessages>| async { socket_event })
-
-        // using .then() (without the .buffered_unordered() call) proved to be faster for this workload
-        .then(move |socket_event| {
+        // using .then() (without the .buffered_unordered() call) proved to be faster for this workload -- configure a `buffer` of 1 for a similar behavior
+        .map(move |socket_event: SocketEvent<ClientMessages>| {
             let client_states = Arc::clone(&client_states);
-            async move {
+            let span = processor_metrics::span(&socket_event);
+            // a panic in the handlers below becomes an error answer (& an alert) -- if `panic_policy` says so
+            let request = panic_isolation::isolated_async(panic_policy.clone(), alerts.clone(), socket_event.endpoint(), async move {
                 let client_states = Arc::clone(&client_states);
-                match socket_event.await {
+                match socket_event {
 
                     SocketEvent::Incoming { endpoint, client_message } => {
+                        let (message_type, started) = (processor_metrics::message_type(&client_message), Instant::now());
                         let server_message = match client_message {
 
                             ClientMessages::Ping => {
@@ -59,11 +77,15 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                                 let client_state = writeable_client_states.get_mut(&endpoint).expect("unknown client");
                                 let msg_count = client_state.count + 1;
                                 client_state.count = msg_count;
+                                let with_timestamp = client_state.features.iter().any(|feature| feature == "pung-timestamp");
                                 drop(client_state);
                                 drop(writeable_client_states);
                                 // some async operations goes here...
                                 // (like an http get or something)
-                                let param = format!("`Pang` from {}, {} times", endpoint.addr(), msg_count);
+                                let mut param = format!("`Pang` from {}, {} times", endpoint.addr(), msg_count);
+                                if with_timestamp {
+                                    param = format!("{} -- at {}", param, chrono::Local::now().to_rfc3339());
+                                }
                                 Ok(ServerMessages::Pung(param))
                             }
 
@@ -77,7 +99,14 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                                 //  so the client will be notified their message wasn't processed correctly)
                                 Err(Box::from(format!("This is an example of a fallible processor failing :)")))
                             },
+
+                            // answered by the socket server itself
+                            ClientMessages::Login { .. } | ClientMessages::Hello { .. } | ClientMessages::KeepAliveAck(_) |
+                            ClientMessages::Subscribe { .. } | ClientMessages::Resume { .. } => {
+                                Ok(ServerMessages::None)
+                            },
                         };
+                        processor_metrics::on_processed(message_type, started);
                         // Ok / Err wrapper
                         match server_message {
                             Ok(server_message) => Ok((endpoint, server_message)),
@@ -87,7 +116,14 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
 
                     SocketEvent::Connected { endpoint } => {
                         client_states.write().await
-                            .insert(endpoint, ClientStates { count: 0 });
+                            .insert(endpoint, ClientStates { count: 0, features: vec![] });
+                        Ok((endpoint, ServerMessages::None))
+                    },
+
+                    SocketEvent::Negotiated { endpoint, features } => {
+                        if let Some(client_state) = client_states.write().await.get_mut(&endpoint) {
+                            client_state.features = features;
+                        }
                         Ok((endpoint, ServerMessages::None))
                     },
 
@@ -98,11 +134,90 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                     },
 
                 }
+            }).instrument(span);
+            match &gate {
+                Some(gate) => {
+                    gate.adapt();
+                    Either::Left(Arc::clone(gate).run(request))
+                },
+                None => Either::Right(request),
             }
         })
+        .buffer_unordered(buffer.max(1))   // we'll execute up to this many futures concurrently -- in the same thread / CPU core
+}
+
+/// Lets the requests of the [adaptive_sync_processors()] run one at a time -- as cheap as the [super::serial_processor] -- while the
+/// processor's queue occupancy (the events dispatched to it still waiting for their answers) stays low; up to `buffer` at once when it
+/// reaches `high_occupancy`, going back to one at a time after it drains to half of that.\
+/// The client states are the same on both modes, so nothing needs to be handed over when switching
+struct AdaptiveGate {
+    permits:           Semaphore,
+    /// events dispatched to the processor still waiting for their answers
+    occupancy:         AtomicUsize,
+    buffer:            usize,
+    high_occupancy:    usize,
+    state:             parking_lot::Mutex<AdaptiveState>,
+}
+
+struct AdaptiveState {
+    concurrent:        bool,
+    /// permits to be forgotten as the requests holding them end -- for going back to one at a time
+    permits_to_forget: usize,
+}
 
-        // if you decide to use only .map() -- without .then() above -- the call bellow is needed to resolve the Futures
-        //.buffer_unordered(super::executor::CONCURRENCY)   // we'll execute up to this many futures concurrently -- in the same thread / CPU core
+impl AdaptiveGate {
+
+    fn new(buffer: usize, high_occupancy: usize) -> Self {
+        Self {
+            permits:        Semaphore::new(1),
+            occupancy:      AtomicUsize::new(0),
+            buffer:         buffer.max(1),
+            high_occupancy: high_occupancy.max(1),
+            state:          parking_lot::Mutex::new(AdaptiveState { concurrent: false, permits_to_forget: 0 }),
+        }
+    }
+
+    /// an event is about to be dispatched to the processor -- to be matched by [Self::run()] or, if it couldn't be, [Self::on_rejected()]
+    fn on_dispatched(&self) {
+        self.occupancy.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the last event given to [Self::on_dispatched()] was, definitely, not dispatched
+    fn on_rejected(&self) {
+        self.occupancy.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// switches between one & `buffer` requests at a time, as the occupancy crosses the thresholds -- called as the processor takes each event
+    fn adapt(&self) {
+        let occupancy = self.occupancy.load(Ordering::Relaxed);
+        let extra_permits = self.buffer - 1;
+        let mut state = self.state.lock();
+        if !state.concurrent && occupancy >= self.high_occupancy {
+            state.concurrent = true;
+            let not_yet_forgotten = state.permits_to_forget.min(extra_permits);
+            state.permits_to_forget -= not_yet_forgotten;
+            self.permits.add_permits(extra_permits - not_yet_forgotten);
+            debug!("Socket Server: the adaptive processor now runs up to {} requests at once -- {} are waiting for their answers", self.buffer, occupancy);
+        } else if state.concurrent && occupancy <= self.high_occupancy / 2 {
+            state.concurrent = false;
+            state.permits_to_forget += extra_permits - self.permits.forget_permits(extra_permits);
+            debug!("Socket Server: the adaptive processor now runs one request at a time -- {} are waiting for their answers", occupancy);
+        }
+    }
+
+    /// runs `request` once the gate lets it through
+    async fn run<Output>(self: Arc<Self>, request: impl Future<Output = Output>) -> Output {
+        let permit = self.permits.acquire().await
+            .expect("BUG: futures_processor.rs: the adaptive gate's semaphore is never closed");
+        let output = request.await;
+        self.occupancy.fetch_sub(1, Ordering::Relaxed);
+        let mut state = self.state.lock();
+        if state.permits_to_forget > 0 {
+            state.permits_to_forget -= 1;
+            permit.forget();
+        }
+        output
+    }
 }
 
 /// Returns a tied-together `(stream, producer, closer)` tuple which [socket_server] uses to transform [ClientMessages] into [ServerMessages].\
@@ -110,15 +225,69 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
 ///   - The `Stream` of (`Endpoint`, [ServerMessages]) -- [socket_server] will, then, apply operations at the end of it to deliver the messages
 ///   - The producer to send `SocketEvent<ClientMessages>` to that stream
 ///   - The closer of the stream
-pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>) -> (impl Stream<Item = Result<(Endpoint, ServerMessages),
+pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, buffer: usize) -> (impl Stream<Item = Result<(Endpoint, ServerMessages),
                                                                                                   (Endpoint, Box<dyn std::error::Error + Sync + Send>)> >,
                                                                         impl FnMut(SocketEvent<ClientMessages>) -> bool,
                                                                         impl FnMut()) {
     let (stream, producer, closer) = super::executor::sync_tokio_stream(tokio_runtime);
-    (processor(stream), producer, closer)
+    (processor(stream, panic_policy, alerts, buffer, None), producer, closer)
+}
+
+/// Like [sync_processors()], but running the requests one at a time while the processor's queue occupancy is below `high_occupancy`
+/// -- see [AdaptiveGate]
+pub fn adaptive_sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, buffer: usize, high_occupancy: usize)
+                               -> (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)> >,
+                                   impl FnMut(SocketEvent<ClientMessages>) -> bool,
+                                   impl FnMut()) {
+    let (stream, mut producer, closer) = super::executor::sync_tokio_stream(tokio_runtime);
+    let gate = Arc::new(AdaptiveGate::new(buffer, high_occupancy));
+    let gate_for_producer = Arc::clone(&gate);
+    let producer = move |socket_event| {
+        gate_for_producer.on_dispatched();
+        let dispatched = producer(socket_event);
+        if !dispatched {
+            gate_for_producer.on_rejected();
+        }
+        dispatched
+    };
+    (processor(stream, panic_policy, alerts, buffer, Some(gate)), producer, closer)
 }
 
-/// see [super::executor::spawn_parallel_stream_executor()]
-pub async fn spawn_stream_executor(stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static) -> tokio::task::JoinHandle<()> {
-    super::executor::spawn_stream_executor(stream).await
-}
\ No newline at end of file
+
+/// Unit tests the [futures_processor](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures the adaptive gate lets several requests through only while the occupancy is high -- going back to one when it drains
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn adaptive_gate() {
+        let gate = Arc::new(AdaptiveGate::new(4, 8));
+        (0..7).for_each(|_| gate.on_dispatched());
+        gate.adapt();
+        assert_eq!(gate.permits.available_permits(), 1, "Below `high_occupancy`, requests should run one at a time");
+
+        gate.on_dispatched();
+        gate.adapt();
+        assert_eq!(gate.permits.available_permits(), 4, "At `high_occupancy`, up to `buffer` requests should run at once");
+
+        for _ in 0..4 {
+            Arc::clone(&gate).run(async {}).await;
+        }
+        gate.adapt();
+        assert_eq!(gate.permits.available_permits(), 1, "Drained to half of `high_occupancy`, requests should run one at a time again");
+
+        // the permits held when going back to one at a time are forgotten as their requests end
+        (0..4).for_each(|_| gate.on_dispatched());
+        gate.adapt();
+        let held_permits = gate.permits.acquire_many(2).await.expect("Acquiring the permits");
+        (0..5).for_each(|_| gate.on_rejected());
+        gate.adapt();
+        assert_eq!(gate.permits.available_permits(), 0, "The available permits should have been forgotten");
+        drop(held_permits);
+        assert_eq!(gate.permits.available_permits(), 2, "The held permits should have been released");
+        Arc::clone(&gate).run(async {}).await;
+        assert_eq!(gate.permits.available_permits(), 1, "The permit left to be forgotten should have been, as a request ended");
+    }
+}
@@ -0,0 +1,56 @@
+//! The socket server's authentication handshake -- see [crate::config::SocketServerConfig::login]: clients send
+//! `Login { token }` & are answered `LoggedIn(<identity name>)` or `Unauthenticated(<reason>)`.
+//! Until logged in, their other messages are answered with `Unauthenticated` -- never reaching the processors.
+
+use crate::{
+    config::{ExtendedOption, SocketLogin},
+    runtime::{AuthProvider, constant_time_eq},
+    logic,
+};
+use std::sync::Arc;
+
+
+/// Verifies the tokens of the `Login` handshake, as configured
+pub struct LoginVerifier {
+    /// `None` if logging in is not required
+    login: Option<SocketLogin>,
+    /// see [SocketLogin::AuthProvider]
+    auth:  Option<Arc<dyn AuthProvider>>,
+}
+
+impl LoginVerifier {
+
+    pub fn new(login: &ExtendedOption<SocketLogin>, auth: Option<Arc<dyn AuthProvider>>) -> Self {
+        let login = match login {
+            ExtendedOption::Enabled(login) => Some(login.clone()),
+            _ => None,
+        };
+        Self { login, auth }
+    }
+
+    /// if clients must log in before sending other messages
+    pub fn is_required(&self) -> bool {
+        self.login.is_some()
+    }
+
+    /// the name of the identity owning `token` -- `Ok(None)` if it was refused; `Err` if it couldn't be verified.\
+    /// If logging in is not required, everyone is "anonymous"
+    pub async fn verify(&self, token: &str) -> Result<Option<String>, Box<dyn std::error::Error + Sync + Send>> {
+        match &self.login {
+            None => Ok(Some(String::from("anonymous"))),
+            Some(SocketLogin::StaticTokens(tokens)) => Ok(tokens.iter()
+                .position(|static_token| constant_time_eq(static_token.as_bytes(), token.as_bytes()))
+                .map(|position| format!("socket-token#{}", position+1))),
+            Some(SocketLogin::AuthProvider) => {
+                let auth = self.auth.as_ref()
+                    .ok_or("The socket server's login is set to use the auth provider, but `auth` is not enabled in the config")?;
+                let principal = match auth.verify_api_key(token).await? {
+                    Some(principal) => Some(principal),
+                    None => auth.verify_token(token).await?,
+                };
+                Ok(principal.map(|principal| principal.name))
+            },
+            Some(SocketLogin::Logic) => logic::verify_socket_token(token).await,
+        }
+    }
+}
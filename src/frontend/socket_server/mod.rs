@@ -4,15 +4,41 @@ mod socket_server;
 pub use socket_server::*;
 
 mod protocol;
+pub use protocol::{ClientMessages, ServerMessages, PROTOCOL_VERSION, ron_client_serializer, ron_client_deserializer};
+
+mod stats;
+pub use stats::{SocketServerStats, SocketServerStatsReport, describe_metrics as describe_stats_metrics};
+
+mod wire_dump;
+pub use wire_dump::WireDump;
+
+mod egress_throttle;
+pub use egress_throttle::describe_metrics as describe_egress_metrics;
+
+mod processor_metrics;
+pub use processor_metrics::describe_metrics as describe_processor_metrics;
+
+mod panic_isolation;
+
+mod framing;
+
+mod tls;
+
+mod bridge;
+pub use bridge::{BridgedPeers, HopGuard};
+
+mod unix_socket;
+
+mod login;
+
+mod subscriptions;
+
+mod client_sdk;
+pub use client_sdk::generate_client_sdk;
 
 mod serial_processor;
 mod parallel_processor;
 mod futures_processor;
-/////////////////////////////////////////////////////////////
-// uncomment one of the processors bellow to activate them //
-/////////////////////////////////////////////////////////////
-pub use serial_processor::{sync_processors, spawn_stream_executor};
-//pub use futures_processor::{sync_processors, spawn_stream_executor};
-//pub use parallel_processor::{sync_processors, spawn_stream_executor};
+mod processors;
 
 mod executor;
\ No newline at end of file
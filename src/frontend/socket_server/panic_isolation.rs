@@ -0,0 +1,65 @@
+//! Keeps a panic while processing a request from bringing the whole socket server down -- see [crate::config::SocketServerConfig::panic_policy].
+//!
+//! The processors wrap each of their handlers with [isolated()] (or [isolated_async()]): caught panics become errors attached to the
+//! request's endpoint -- answered to the client as [ServerMessages::ProcessorError] -- and the pipeline goes on with the other requests.\
+//! Notice the client states a handler was changing when it panicked are kept as they were left.\
+//! Each caught panic is logged, counted (as `socket_processor_panics_total`) on `/api/metrics` & published as an [Alert] of kind
+//! [PANIC_ALERT_KIND] -- see [crate::runtime::alerts_topic()].
+
+use super::{
+    protocol::ServerMessages,
+    processor_metrics,
+};
+use crate::{
+    config::SocketPanicPolicy,
+    runtime::{Alert, Topic},
+};
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+};
+use futures::FutureExt;
+use message_io::network::Endpoint;
+use log::error;
+
+
+/// The [Alert::kind] of the caught panics
+pub const PANIC_ALERT_KIND: &str = "processor-panic";
+
+
+/// What the processors' handlers yield
+pub type ProcessorAnswer = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>;
+
+
+/// runs `handler` -- processing a request from `endpoint` -- catching its panics if `panic_policy` says so, alerting them on `alerts`
+pub fn isolated(panic_policy: &SocketPanicPolicy, alerts: &Topic<Alert>, endpoint: Endpoint, handler: impl FnOnce() -> ProcessorAnswer) -> ProcessorAnswer {
+    match panic_policy {
+        SocketPanicPolicy::Crash   => handler(),
+        SocketPanicPolicy::Isolate => panic::catch_unwind(AssertUnwindSafe(handler))
+                                          .unwrap_or_else(|panic_payload| Err(on_panic(alerts, endpoint, panic_payload))),
+    }
+}
+
+/// async version of [isolated()] -- taking `panic_policy` & `alerts` by value, so the returned future may be spawned
+pub async fn isolated_async(panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, endpoint: Endpoint, handler: impl Future<Output = ProcessorAnswer>) -> ProcessorAnswer {
+    match panic_policy {
+        SocketPanicPolicy::Crash   => handler.await,
+        SocketPanicPolicy::Isolate => AssertUnwindSafe(handler).catch_unwind().await
+                                          .unwrap_or_else(|panic_payload| Err(on_panic(&alerts, endpoint, panic_payload))),
+    }
+}
+
+fn on_panic(alerts: &Topic<Alert>, endpoint: Endpoint, panic_payload: Box<dyn Any + Send>) -> (Endpoint, Box<dyn std::error::Error + Sync + Send>) {
+    let reason = panic_payload.downcast_ref::<&str>().map(|reason| reason.to_string())
+        .or_else(|| panic_payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("<non-string panic payload>"));
+    error!("Socket Server's processor panicked while processing a request from {}: {} -- answering `ProcessorError` & going on", endpoint.addr(), reason);
+    processor_metrics::on_panic();
+    alerts.publish(Alert {
+        source:  "socket-server",
+        kind:    PANIC_ALERT_KIND,
+        message: format!("The processor panicked while processing a request from {}: {}", endpoint.addr(), reason),
+    });
+    (endpoint, Box::from(format!("The processor panicked: {}", reason)))
+}
@@ -15,39 +15,52 @@ use super::{
     types::*,
     socket_server::SocketEvent,
     protocol::{ClientMessages, ServerMessages},
+    processor_metrics,
+    panic_isolation,
+};
+use crate::{
+    config::SocketPanicPolicy,
+    runtime::{Alert, Topic},
 };
 use std::{
     sync::Arc,
+    time::Instant,
     collections::HashMap
 };
 use futures::{Stream, FutureExt};
 use par_stream::prelude::*;
-use message_io::network::{Endpoint, SendStatus};
+use message_io::network::Endpoint;
 use tokio::sync::{RwLock};
+use tracing::Instrument;
 
 
 /// customize this to hold the states you want for each client
 #[derive(Debug)]
 struct ClientStates {
-    count: usize,
+    count:    usize,
+    /// the optional features negotiated by the client's `Hello` -- see [super::protocol::SERVER_FEATURES]
+    features: Vec<String>,
 }
 
 /// Here is where the main "protocol" processor logic lies: returns a Stream pipeline able to
 /// transform client inputs ([ClientMessages] requests) into server outputs ([ServerMessages] answers)
-fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 'static)
+fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 'static, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, workers: usize)
             -> impl Stream<Item = Result<(Endpoint, ServerMessages),
                                          (Endpoint, Box<dyn std::error::Error + Sync + Send>)>> {
 
     let client_states = Arc::new(RwLock::new(HashMap::<Endpoint, ClientStates>::new()));
 
     stream
-        .par_then_unordered(super::executor::PAR_PARAMS, move |socket_event: SocketEvent<ClientMessages>| {
+        .par_then_unordered(super::executor::par_params(workers), move |socket_event: SocketEvent<ClientMessages>| {
             let client_states = Arc::clone(&client_states);
-            async move {
+            let span = processor_metrics::span(&socket_event);
+            // a panic in the handlers below becomes an error answer (& an alert) -- if `panic_policy` says so
+            panic_isolation::isolated_async(panic_policy.clone(), alerts.clone(), socket_event.endpoint(), async move {
                 let client_states = Arc::clone(&client_states);
                 match socket_event {
 
                     SocketEvent::Incoming { endpoint, client_message } => {
+                        let (message_type, started) = (processor_metrics::message_type(&client_message), Instant::now());
                         let server_message = match client_message {
 
                             ClientMessages::Ping => {
@@ -62,6 +75,7 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
                                 let client_state = writeable_client_states.get_mut(&endpoint).expect("unknown client");
                                 let msg_count = client_state.count + 1;
                                 client_state.count = msg_count;
+                                let with_timestamp = client_state.features.iter().any(|feature| feature == "pung-timestamp");
                                 drop(client_state);
                                 drop(writeable_client_states);
                                 ///* CPU Intensive
@@ -69,9 +83,12 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
                                 for i in 1..(1<<24) {
                                     r ^= r % i;
                                 }
-                                let param = format!("`Pang` from {}, {} times -- r={r} -- THREAD {:?}", endpoint.addr(), msg_count, std::thread::current()); // */
+                                let mut param = format!("`Pang` from {}, {} times -- r={r} -- THREAD {:?}", endpoint.addr(), msg_count, std::thread::current()); // */
                                 /* Uncomment this if comparing the performance to [concurrent_processor]
-                                let param = format!("`Pang` from {}, {} times", endpoint.addr(), msg_count); // */
+                                let mut param = format!("`Pang` from {}, {} times", endpoint.addr(), msg_count); // */
+                                if with_timestamp {
+                                    param = format!("{} -- at {}", param, chrono::Local::now().to_rfc3339());
+                                }
                                 Ok(ServerMessages::Pung(param))
                             }
 
@@ -85,7 +102,14 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
                                 //  so the client will be notified their message wasn't processed correctly)
                                 Err(Box::from(format!("This is an example of a fallible processor failing :)")))
                             },
+
+                            // answered by the socket server itself
+                            ClientMessages::Login { .. } | ClientMessages::Hello { .. } | ClientMessages::KeepAliveAck(_) |
+                            ClientMessages::Subscribe { .. } | ClientMessages::Resume { .. } => {
+                                Ok(ServerMessages::None)
+                            },
                         };
+                        processor_metrics::on_processed(message_type, started);
                         // Ok / Err wrapper
                         match server_message {
                             Ok(server_message) => Ok((endpoint, server_message)),
@@ -95,7 +119,14 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
 
                     SocketEvent::Connected { endpoint } => {
                         client_states.write().await
-                            .insert(endpoint, ClientStates { count: 0 });
+                            .insert(endpoint, ClientStates { count: 0, features: vec![] });
+                        Ok((endpoint, ServerMessages::None))
+                    },
+
+                    SocketEvent::Negotiated { endpoint, features } => {
+                        if let Some(client_state) = client_states.write().await.get_mut(&endpoint) {
+                            client_state.features = features;
+                        }
                         Ok((endpoint, ServerMessages::None))
                     },
 
@@ -106,7 +137,7 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
                     },
 
                 }
-            }
+            }).instrument(span)
         })
 }
 
@@ -115,14 +146,9 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>> + Send + 's
 ///   - The `Stream` of (`Endpoint`, [ServerMessages]) -- [socket_server] will, then, apply operations at the end of it to deliver the messages
 ///   - The producer to send `SocketEvent<ClientMessages>` to that stream
 ///   - The closer of the stream
-pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>) -> (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>,
+pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>, workers: usize) -> (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>,
                                                                         impl FnMut(SocketEvent<ClientMessages>) -> bool,
                                                                         impl FnMut()) {
     let (stream, producer, closer) = super::executor::sync_tokio_stream(tokio_runtime);
-    (processor(stream), producer, closer)
+    (processor(stream, panic_policy, alerts, workers), producer, closer)
 }
-
-/// see [super::executor::spawn_parallel_stream_executor()]
-pub async fn spawn_stream_executor(stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static) -> tokio::task::JoinHandle<()> {
-    super::executor::spawn_parallel_stream_executor(stream).await
-}
\ No newline at end of file
@@ -0,0 +1,99 @@
+//! Metrics for the protocol processors -- messages by type, processing latencies, queue occupancy & overflows.
+//!
+//! They are published through the `metrics` facade, so any installed recorder may export them: the application installs
+//! an OpenMetrics one, rendered at `/api/metrics` -- see [crate::runtime::install_metrics_recorder()].\
+//! Each event is also processed within a tracing span -- exported if [crate::config::Config::telemetry] is enabled.
+
+use super::{
+    protocol::ClientMessages,
+    socket_server::SocketEvent,
+};
+use crate::config::SocketOverflowPolicy;
+use std::time::Instant;
+use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Unit};
+use tracing::{info_span, Span};
+
+
+/// Counter of the messages processed, labeled by `type`
+pub const MESSAGES_METRIC: &str = "socket_processor_messages_total";
+/// Histogram of the time each message took to be processed, labeled by `type`
+pub const LATENCY_METRIC: &str = "socket_processor_latency_seconds";
+/// Gauge of the events dispatched to the processor still waiting for their answers
+pub const QUEUE_METRIC: &str = "socket_processor_queue_occupancy";
+/// Counter of the messages arriving while the processor's queue was full, labeled by the `policy` applied -- see [SocketOverflowPolicy]
+pub const OVERFLOWS_METRIC: &str = "socket_processor_overflows_total";
+/// Counter of the panics caught while processing messages -- see [crate::config::SocketPanicPolicy::Isolate]
+pub const PANICS_METRIC: &str = "socket_processor_panics_total";
+
+
+/// registers the descriptions of the processors' metrics -- to be called once the recorder is installed
+pub fn describe_metrics() {
+    describe_counter!(MESSAGES_METRIC, Unit::Count, "Messages processed by the socket server's processor, by type");
+    describe_histogram!(LATENCY_METRIC, Unit::Seconds, "Time the socket server's processor took to process each message, by type");
+    describe_gauge!(QUEUE_METRIC, Unit::Count, "Events dispatched to the socket server's processor still waiting for their answers");
+    describe_counter!(OVERFLOWS_METRIC, Unit::Count, "Messages arriving while the socket server's processor queue was full, by the overflow policy applied");
+    describe_counter!(PANICS_METRIC, Unit::Count, "Panics caught while the socket server's processor processed messages");
+}
+
+/// the label identifying `message` on the metrics
+pub fn message_type(message: &ClientMessages) -> &'static str {
+    match message {
+        ClientMessages::Ping       => "Ping",
+        ClientMessages::Pang       => "Pang",
+        ClientMessages::Speechless => "Speechless",
+        ClientMessages::Error      => "Error",
+        ClientMessages::Login {..} => "Login",
+        ClientMessages::Hello {..} => "Hello",
+        ClientMessages::KeepAliveAck(_) => "KeepAliveAck",
+        ClientMessages::Subscribe {..} => "Subscribe",
+        ClientMessages::Resume {..} => "Resume",
+    }
+}
+
+/// the tracing span for the processing of `socket_event` -- named after its message type, for the incoming messages
+pub fn span(socket_event: &SocketEvent<ClientMessages>) -> Span {
+    let event = match socket_event {
+        SocketEvent::Incoming { client_message, .. } => message_type(client_message),
+        SocketEvent::Connected { .. }                => "Connected",
+        SocketEvent::Negotiated { .. }               => "Negotiated",
+        SocketEvent::Disconnected { .. }             => "Disconnected",
+    };
+    info_span!("socket_event", event, endpoint = ?socket_event.endpoint())
+}
+
+/// the processor finished processing a message of `message_type`, which it started at `started`
+pub fn on_processed(message_type: &'static str, started: Instant) {
+    counter!(MESSAGES_METRIC, "type" => message_type).increment(1);
+    histogram!(LATENCY_METRIC, "type" => message_type).record(started.elapsed().as_secs_f64());
+}
+
+/// an event was dispatched to the processor -- to be matched by [on_answered()] or [on_rejected()]
+pub fn on_dispatched() {
+    gauge!(QUEUE_METRIC).increment(1.0);
+}
+
+/// the processor yielded the answer for one of the events dispatched to it
+pub fn on_answered() {
+    gauge!(QUEUE_METRIC).decrement(1.0);
+}
+
+/// an event couldn't be dispatched, as the processor's queue was full -- `overflow_policy` is about to be applied
+pub fn on_overflow(overflow_policy: &SocketOverflowPolicy) {
+    let policy = match overflow_policy {
+        SocketOverflowPolicy::ReplyTooBusy => "ReplyTooBusy",
+        SocketOverflowPolicy::Drop         => "Drop",
+        SocketOverflowPolicy::Block { .. } => "Block",
+        SocketOverflowPolicy::Disconnect   => "Disconnect",
+    };
+    counter!(OVERFLOWS_METRIC, "policy" => policy).increment(1);
+}
+
+/// the last event given to [on_dispatched()] was, definitely, not dispatched
+pub fn on_rejected() {
+    gauge!(QUEUE_METRIC).decrement(1.0);
+}
+
+/// the processor panicked while processing a message -- and the panic was caught
+pub fn on_panic() {
+    counter!(PANICS_METRIC).increment(1);
+}
@@ -0,0 +1,62 @@
+//! Resolves, at startup, which processor handles the socket server's requests -- see [crate::config::SocketServerConfig::processor].
+//!
+//! All processors share the same protocol logic, differing only on how requests are executed:
+//!   - [super::serial_processor]: one request at a time, in a single async task;
+//!   - [super::futures_processor]: several requests concurrently, in a single async task;
+//!   - [super::parallel_processor]: several requests in parallel, using several threads;
+//!   - the adaptive one -- also in [super::futures_processor]: one request at a time, going concurrent as the queue fills up.
+
+use super::{
+    socket_server::SocketEvent,
+    protocol::{ClientMessages, ServerMessages},
+    serial_processor, futures_processor, parallel_processor, executor,
+};
+use crate::{
+    config::{SocketProcessorStrategy, SocketPanicPolicy},
+    runtime::{Alert, Topic},
+};
+use std::{
+    sync::Arc,
+    pin::Pin,
+};
+use futures::Stream;
+use message_io::network::{Endpoint, SendStatus};
+
+
+/// The stream of answers yielded by any of the processors
+pub type ProcessorStream = Pin<Box<dyn Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>> + Send + Sync>>;
+/// Feeds requests to a [ProcessorStream] -- returning `false` if it is too busy
+pub type ProcessorProducer = Box<dyn FnMut(SocketEvent<ClientMessages>) -> bool + Send + Sync>;
+/// Closes a [ProcessorStream]
+pub type ProcessorCloser = Box<dyn FnMut() + Send + Sync>;
+
+
+/// Returns the tied-together `(stream, producer, closer)` tuple of the processor selected by `strategy`, handling its panics according
+/// to `panic_policy` -- the caught ones being published on `alerts`. See `sync_processors()` on [serial_processor] for the details
+pub fn sync_processors(strategy: &SocketProcessorStrategy, panic_policy: &SocketPanicPolicy, alerts: &Topic<Alert>, tokio_runtime: Arc<tokio::runtime::Runtime>) -> (ProcessorStream, ProcessorProducer, ProcessorCloser) {
+    let (panic_policy, alerts) = (panic_policy.clone(), alerts.clone());
+    match *strategy {
+        SocketProcessorStrategy::Serial                 => boxed(serial_processor::sync_processors(tokio_runtime, panic_policy, alerts)),
+        SocketProcessorStrategy::Concurrent { buffer }  => boxed(futures_processor::sync_processors(tokio_runtime, panic_policy, alerts, buffer)),
+        SocketProcessorStrategy::Parallel   { workers } => boxed(parallel_processor::sync_processors(tokio_runtime, panic_policy, alerts, workers)),
+        SocketProcessorStrategy::Adaptive   { buffer, high_occupancy } => boxed(futures_processor::adaptive_sync_processors(tokio_runtime, panic_policy, alerts, buffer, high_occupancy)),
+    }
+}
+
+/// Spawns the executor for the answers stream (as returned by [super::SocketServer::set_processor()]) suitable for `strategy`
+pub async fn spawn_stream_executor(strategy: &SocketProcessorStrategy, stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static) -> tokio::task::JoinHandle<()> {
+    match *strategy {
+        SocketProcessorStrategy::Serial |
+        SocketProcessorStrategy::Concurrent { .. } |
+        SocketProcessorStrategy::Adaptive   { .. }      => executor::spawn_stream_executor(stream).await,
+        SocketProcessorStrategy::Parallel   { workers } => executor::spawn_parallel_stream_executor(stream, workers).await,
+    }
+}
+
+fn boxed(processors: (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>> + Send + Sync + 'static,
+                      impl FnMut(SocketEvent<ClientMessages>) -> bool + Send + Sync + 'static,
+                      impl FnMut() + Send + Sync + 'static))
+        -> (ProcessorStream, ProcessorProducer, ProcessorCloser) {
+    let (stream, producer, closer) = processors;
+    (Box::pin(stream), Box::new(producer), Box::new(closer))
+}
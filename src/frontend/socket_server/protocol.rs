@@ -10,6 +10,24 @@ use ron::{
 use serde::{Serialize, Deserialize};
 
 
+/// The version of the messages below, as seen on the wire -- to be increased on any change not understood by the deployed clients
+/// (like renaming, reordering or removing variants & fields), keeping the previous version's golden files (see the tests) around
+pub const PROTOCOL_VERSION: u32 = 6;
+
+/// The oldest version clients may speak -- those saying [ClientMessages::Hello] with older ones are refused.
+/// To be increased once this server no longer understands them (so far, each version only added messages)
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// The optional features this server may enable for each client -- negotiated through [ClientMessages::Hello] & made available to the
+/// processors by [super::SocketEvent::Negotiated]
+pub const SERVER_FEATURES: &[&str] = &[
+    // `Pung` answers tell when they were produced -- see the processors
+    "pung-timestamp",
+    // the server sends `KeepAlive`s -- to be answered with `KeepAliveAck`s -- measuring the client's round trips. See the server's `keepalive` config
+    "keepalive",
+    // APP's features go here
+];
+
 /// Messages coming from the clients, suitable to be deserialized by this server
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum ClientMessages {
@@ -25,6 +43,28 @@ pub enum ClientMessages {
 
     /// demo request causing the processor to result in `Err`
     Error,
+
+    /// Authenticates the client -- answered by [ServerMessages::LoggedIn] or [ServerMessages::Unauthenticated].
+    /// Required before any other message by servers demanding it -- see the server's `login` config
+    Login { token: String },
+
+    /// Tells the protocol version spoken by the client & the optional features it wants -- answered by [ServerMessages::Welcome] or,
+    /// if the version is older than [MIN_PROTOCOL_VERSION], by [ServerMessages::IncompatibleProtocol].
+    /// Optional, but advised as the first message: clients not saying it get no optional features
+    Hello { protocol_version: u32, features: Vec<String> },
+
+    /// Answers [ServerMessages::KeepAlive], echoing its nonce -- for the server to measure the round-trip time. Expected as soon as
+    /// possible from the clients that negotiated the `keepalive` feature
+    KeepAliveAck(u64),
+
+    /// Subscribes to the `topic` of the server's event bus -- answered by [ServerMessages::Subscribed] (whose id identifies the subscription)
+    /// or [ServerMessages::SubscriptionRefused]. From then on, its events are pushed as [ServerMessages::Pushed]
+    Subscribe { topic: String },
+
+    /// Resumes, after reconnecting, the subscription `subscription_id` -- answered by [ServerMessages::Subscribed], followed by the events
+    /// pushed after `last_seq` (the sequence number of the last one received -- 0 for none) the client missed; or by
+    /// [ServerMessages::SubscriptionLost] if they can no longer be replayed
+    Resume { subscription_id: u64, last_seq: u64 },
 }
 
 /// Messages generated by this server, suitable to be serialized here
@@ -57,8 +97,74 @@ pub enum ServerMessages {
     /// If the processor results in `Err`, this will be sent along with the error description
     ProcessorError(String),
 
+    /// While in maintenance mode, this is answered (along with the maintenance message) instead of processing the requests
+    /// -- new connections receive it & are, then, closed
+    Maintenance(String),
+
+    /// Answered to messages longer than the allowed length (sent along) -- the client is, then, disconnected & temporarily banned
+    MessageTooLong(usize),
+
+    /// Answered once the client's count of malformed messages reaches the allowed limit (sent along) -- the client is, then,
+    /// disconnected & temporarily banned
+    TooManyMalformedMessages(u32),
+
+    /// Answered to the new connections of banned clients -- along with how many seconds remain until the ban is lifted -- which are, then, closed
+    Banned(u64),
+
     /// Server sends this to connected clients once it has decided it is time to quit
     ShuttingDown,
+
+    /// Answer of a successful [ClientMessages::Login] -- along with the name of the authenticated identity
+    LoggedIn(String),
+
+    /// Answer of a failed [ClientMessages::Login] -- along with the reason -- and of the messages sent before a successful one, when logging in is required
+    Unauthenticated(String),
+
+    /// Answered to the new connections above the server's limit of connected clients (sent along) -- which are, then, closed.
+    /// Clients are advised to try again later
+    TooManyClients(usize),
+
+    /// Answer of [ClientMessages::Hello]: the protocol version to be spoken -- the client's, or [PROTOCOL_VERSION], if the client is newer --
+    /// along with the requested features this server enabled (see [SERVER_FEATURES])
+    Welcome { protocol_version: u32, features: Vec<String> },
+
+    /// Answered to a [ClientMessages::Hello] with a protocol version this server doesn't speak -- along with the versions it does.
+    /// The client is, then, disconnected
+    IncompatibleProtocol { min_version: u32, max_version: u32 },
+
+    /// Sent, every once in a while, to the clients that negotiated the `keepalive` feature -- which should echo the nonce back in a
+    /// [ClientMessages::KeepAliveAck]
+    KeepAlive(u64),
+
+    /// Answer of [ClientMessages::Subscribe] & of a successful [ClientMessages::Resume] -- along with the subscription's id, to be kept by
+    /// the client in order to resume it
+    Subscribed { subscription_id: u64, topic: String },
+
+    /// An event pushed to the subscription `subscription_id` -- `seq` counts them, per subscription, from 1 & `payload` is the event in JSON
+    Pushed { subscription_id: u64, seq: u64, payload: String },
+
+    /// Answered to a [ClientMessages::Resume] whose subscription expired (or is unknown) or whose missed events are no longer kept --
+    /// the client should, then, `Subscribe` again & resynchronize by other means
+    SubscriptionLost(u64),
+
+    /// Answered to a [ClientMessages::Subscribe] that can't be fulfilled -- along with the reason
+    SubscriptionRefused(String),
+}
+
+
+/// Negotiates the protocol with a client saying [ClientMessages::Hello]: returns the version to be spoken & the requested features enabled
+/// -- or `None` if the client's version is no longer spoken here
+pub fn negotiate(protocol_version: u32, features: &[String]) -> Option<(u32, Vec<String>)> {
+    if protocol_version < MIN_PROTOCOL_VERSION {
+        return None
+    }
+    let mut enabled_features: Vec<String> = vec![];
+    for feature in features {
+        if SERVER_FEATURES.contains(&feature.as_str()) && !enabled_features.contains(feature) {
+            enabled_features.push(feature.clone());
+        }
+    }
+    Some((protocol_version.min(PROTOCOL_VERSION), enabled_features))
 }
 
 
@@ -97,12 +203,84 @@ pub fn ron_deserializer(message: &[u8]) -> Result<ClientMessages, Box<dyn std::e
         .map_err(|err| Box::from(format!("RON deserialization error for message '{:?}': {}", std::str::from_utf8(message), err)))
 }
 
+/// RON serializer for client messages -- for when we are the client
+pub fn ron_client_serializer(message: &ClientMessages) -> String {
+    let mut output_data = ron::ser::to_string(message).unwrap();
+    write!(output_data, "\n").unwrap();
+    output_data
+}
+
+/// RON deserializer for server messages -- for when we are the client
+pub fn ron_client_deserializer(message: &[u8]) -> Result<ServerMessages, Box<dyn std::error::Error>> {
+    RON_DESERIALIZER_CONFIG.from_bytes(message)
+        .map_err(|err| Box::from(format!("RON deserialization error for server message '{:?}': {}", std::str::from_utf8(message), err)))
+}
+
+
+// JSON SERDE
+/////////////
+
+/// JSON serializer for server messages -- one message per line, as in RON
+pub fn json_serializer(message: ServerMessages) -> String {
+    let mut output_data = serde_json::to_string(&message).unwrap();
+    write!(output_data, "\n").unwrap();
+    output_data
+}
+
+/// JSON deserializer for client messages
+pub fn json_deserializer(message: &[u8]) -> Result<ClientMessages, Box<dyn std::error::Error>> {
+    serde_json::from_slice(message)
+        .map_err(|err| Box::from(format!("JSON deserialization error for message '{:?}': {}", std::str::from_utf8(message), err)))
+}
+
+
+// BINCODE SERDE
+////////////////
+// no delimiters are added: binary messages must travel in a framed transport (like `message-io`'s `FramedTcp`)
+
+/// Bincode serializer for server messages
+pub fn bincode_serializer(message: ServerMessages) -> Vec<u8> {
+    bincode::serialize(&message).unwrap()
+}
+
+/// Bincode deserializer for client messages
+pub fn bincode_deserializer(message: &[u8]) -> Result<ClientMessages, Box<dyn std::error::Error>> {
+    bincode::deserialize(message)
+        .map_err(|err| Box::from(format!("Bincode deserialization error for message {:?}: {}", message, err)))
+}
+
 
 /// Unit tests the [protocol](self) module
 #[cfg(any(test, feature = "dox"))]
 mod tests {
     use super::*;
 
+
+    /// JSON serializer for client messages -- as the clients do
+    fn json_client_serializer(message: &ClientMessages) -> String {
+        let mut output_data = serde_json::to_string(message).unwrap();
+        write!(output_data, "\n").unwrap();
+        output_data
+    }
+
+    /// JSON deserializer for server messages -- as the clients do
+    fn json_client_deserializer(message: &[u8]) -> Result<ServerMessages, Box<dyn std::error::Error>> {
+        serde_json::from_slice(message)
+            .map_err(|err| Box::from(format!("JSON deserialization error for server message '{:?}': {}", std::str::from_utf8(message), err)))
+    }
+
+    /// Bincode serializer for client messages -- as the clients do
+    fn bincode_client_serializer(message: &ClientMessages) -> Vec<u8> {
+        bincode::serialize(message).unwrap()
+    }
+
+    /// Bincode deserializer for server messages -- as the clients do
+    fn bincode_client_deserializer(message: &[u8]) -> Result<ServerMessages, Box<dyn std::error::Error>> {
+        bincode::deserialize(message)
+            .map_err(|err| Box::from(format!("Bincode deserialization error for server message {:?}: {}", message, err)))
+    }
+
+
     /// assures RON serialization / deserialization works for the server & produces good human readable/writeable text
     #[test]
     fn ron_serde_for_server_only() {
@@ -117,4 +295,146 @@ mod tests {
             .expect("RON deserialization failed");
         assert_eq!(observed, expected, "RON deserialization is not good");
     }
+
+    /// assures only the known features are enabled & newer clients are downgraded to our version
+    #[test]
+    fn negotiation() {
+        let features = vec![String::from("compression"), String::from("pung-timestamp"), String::from("pung-timestamp")];
+        assert_eq!(negotiate(PROTOCOL_VERSION, &features), Some((PROTOCOL_VERSION, vec![String::from("pung-timestamp")])), "Unknown & repeated features should be left out");
+        assert_eq!(negotiate(PROTOCOL_VERSION + 1, &[]), Some((PROTOCOL_VERSION, vec![])), "Newer clients should be told to speak our version");
+        assert_eq!(negotiate(MIN_PROTOCOL_VERSION, &[]), Some((MIN_PROTOCOL_VERSION, vec![])), "Older, but still spoken, versions should be kept");
+        assert_eq!(negotiate(MIN_PROTOCOL_VERSION - 1, &features), None, "Versions older than the minimum should be refused");
+    }
+
+    /// Where the golden files of [golden_messages()] live
+    const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/socket_protocol");
+
+    /// Set this env var to (re)generate the golden files -- only do it for intended wire changes, which
+    /// require a new [PROTOCOL_VERSION]
+    const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN_MESSAGES";
+
+    /// A sample of every [ClientMessages] variant, in declaration order
+    fn client_samples() -> Vec<ClientMessages> {
+        let samples = vec![
+            ClientMessages::Ping,
+            ClientMessages::Pang,
+            ClientMessages::Speechless,
+            ClientMessages::Error,
+            ClientMessages::Login { token: String::from("s3cr3t") },
+            ClientMessages::Hello { protocol_version: 6, features: vec![String::from("pung-timestamp"), String::from("compression")] },
+            ClientMessages::KeepAliveAck(7),
+            ClientMessages::Subscribe { topic: String::from("orders") },
+            ClientMessages::Resume { subscription_id: 12345, last_seq: 41 },
+        ];
+        // this exhaustive match won't compile for new variants, reminding they need a sample above
+        samples.iter().for_each(|sample| match sample {
+            ClientMessages::Ping | ClientMessages::Pang | ClientMessages::Speechless | ClientMessages::Error | ClientMessages::Login { .. } |
+            ClientMessages::Hello { .. } | ClientMessages::KeepAliveAck(_) | ClientMessages::Subscribe { .. } | ClientMessages::Resume { .. } => (),
+        });
+        samples
+    }
+
+    /// A sample of every [ServerMessages] variant, in declaration order
+    fn server_samples() -> Vec<ServerMessages> {
+        let samples = vec![
+            ServerMessages::Pong(42),
+            ServerMessages::Pung(String::from("42")),
+            ServerMessages::None,
+            ServerMessages::UnknownMessage(String::from("Pinga")),
+            ServerMessages::TooBusy,
+            ServerMessages::ProcessorError(String::from("This is a processor error")),
+            ServerMessages::Maintenance(String::from("Back in 5 minutes")),
+            ServerMessages::MessageTooLong(8193),
+            ServerMessages::TooManyMalformedMessages(16),
+            ServerMessages::Banned(60),
+            ServerMessages::ShuttingDown,
+            ServerMessages::LoggedIn(String::from("alice")),
+            ServerMessages::Unauthenticated(String::from("Invalid token")),
+            ServerMessages::TooManyClients(1024),
+            ServerMessages::Welcome { protocol_version: 6, features: vec![String::from("pung-timestamp")] },
+            ServerMessages::IncompatibleProtocol { min_version: 1, max_version: 6 },
+            ServerMessages::KeepAlive(7),
+            ServerMessages::Subscribed { subscription_id: 12345, topic: String::from("orders") },
+            ServerMessages::Pushed { subscription_id: 12345, seq: 42, payload: String::from("{\"id\":7}") },
+            ServerMessages::SubscriptionLost(12345),
+            ServerMessages::SubscriptionRefused(String::from("Unknown topic")),
+        ];
+        // this exhaustive match won't compile for new variants, reminding they need a sample above
+        samples.iter().for_each(|sample| match sample {
+            ServerMessages::Pong(_) | ServerMessages::Pung(_) | ServerMessages::None | ServerMessages::UnknownMessage(_) |
+            ServerMessages::TooBusy | ServerMessages::ProcessorError(_) | ServerMessages::Maintenance(_) |
+            ServerMessages::MessageTooLong(_) | ServerMessages::TooManyMalformedMessages(_) | ServerMessages::Banned(_) |
+            ServerMessages::ShuttingDown | ServerMessages::LoggedIn(_) | ServerMessages::Unauthenticated(_) | ServerMessages::TooManyClients(_) |
+            ServerMessages::Welcome { .. } | ServerMessages::IncompatibleProtocol { .. } | ServerMessages::KeepAlive(_) |
+            ServerMessages::Subscribed { .. } | ServerMessages::Pushed { .. } | ServerMessages::SubscriptionLost(_) | ServerMessages::SubscriptionRefused(_) => (),
+        });
+        samples
+    }
+
+    /// Compares `serialized` against the contents of the golden `file_name` (for the given `format` of the current [PROTOCOL_VERSION]),
+    /// (re)writing it instead if [UPDATE_GOLDEN_ENV] is set
+    fn assert_golden(format: &str, file_name: &str, serialized: &str) {
+        let dir = format!("{}/{}/v{}", GOLDEN_DIR, format, PROTOCOL_VERSION);
+        let path = format!("{}/{}", dir, file_name);
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            std::fs::create_dir_all(&dir).expect("Couldn't create the golden files' directory");
+            std::fs::write(&path, serialized).expect("Couldn't write the golden file");
+            return
+        }
+        let golden = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Couldn't read the golden file '{}' ({}) -- generate it with `{}=1 cargo test golden`", path, err, UPDATE_GOLDEN_ENV));
+        assert_eq!(serialized, golden, "Wire-breaking change for the {} format: the messages no longer serialize as in '{}' -- if this was intended, increase PROTOCOL_VERSION and generate the new golden files", format, path);
+    }
+
+    /// assures the messages of all variants are serialized & deserialized as recorded in the golden files -- preventing accidental wire-breaking
+    /// changes (renamed or reordered variants & fields, changed types) from reaching the deployed clients
+    #[test]
+    fn golden_messages() {
+        // RON
+        let client_golden: String = client_samples().iter().map(ron_client_serializer).collect();
+        assert_golden("ron", "client_messages.ron", &client_golden);
+        for (line, expected) in client_golden.lines().zip(client_samples()) {
+            let observed = ron_deserializer(line.as_bytes())
+                .unwrap_or_else(|err| panic!("RON deserialization of the golden client message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "RON deserialization of golden client messages is not good");
+        }
+        let server_golden: String = server_samples().into_iter().map(ron_serializer).collect();
+        assert_golden("ron", "server_messages.ron", &server_golden);
+        for (line, expected) in server_golden.lines().zip(server_samples()) {
+            let observed = ron_client_deserializer(line.as_bytes())
+                .unwrap_or_else(|err| panic!("RON deserialization of the golden server message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "RON deserialization of golden server messages is not good");
+        }
+        // JSON
+        let client_golden: String = client_samples().iter().map(json_client_serializer).collect();
+        assert_golden("json", "client_messages.json", &client_golden);
+        for (line, expected) in client_golden.lines().zip(client_samples()) {
+            let observed = json_deserializer(line.as_bytes())
+                .unwrap_or_else(|err| panic!("JSON deserialization of the golden client message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "JSON deserialization of golden client messages is not good");
+        }
+        let server_golden: String = server_samples().into_iter().map(json_serializer).collect();
+        assert_golden("json", "server_messages.json", &server_golden);
+        for (line, expected) in server_golden.lines().zip(server_samples()) {
+            let observed = json_client_deserializer(line.as_bytes())
+                .unwrap_or_else(|err| panic!("JSON deserialization of the golden server message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "JSON deserialization of golden server messages is not good");
+        }
+        // Bincode -- recorded in hex, one message per line. Its enum numbering & field order are even more prone to silently break the
+        // clients than the textual formats' variant names
+        let client_golden: String = client_samples().iter().map(|message| format!("{}\n", hex::encode(bincode_client_serializer(message)))).collect();
+        assert_golden("bincode", "client_messages.hex", &client_golden);
+        for (line, expected) in client_golden.lines().zip(client_samples()) {
+            let observed = bincode_deserializer(&hex::decode(line).expect("Golden files are hex encoded"))
+                .unwrap_or_else(|err| panic!("Bincode deserialization of the golden client message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "Bincode deserialization of golden client messages is not good");
+        }
+        let server_golden: String = server_samples().into_iter().map(|message| format!("{}\n", hex::encode(bincode_serializer(message)))).collect();
+        assert_golden("bincode", "server_messages.hex", &server_golden);
+        for (line, expected) in server_golden.lines().zip(server_samples()) {
+            let observed = bincode_client_deserializer(&hex::decode(line).expect("Golden files are hex encoded"))
+                .unwrap_or_else(|err| panic!("Bincode deserialization of the golden server message '{}' failed: {}", line, err));
+            assert_eq!(observed, expected, "Bincode deserialization of golden server messages is not good");
+        }
+    }
 }
\ No newline at end of file
@@ -20,24 +20,33 @@ use super::{
     types::*,
     socket_server::SocketEvent,
     protocol::{ClientMessages, ServerMessages},
+    processor_metrics,
+    panic_isolation,
+};
+use crate::{
+    config::SocketPanicPolicy,
+    runtime::{Alert, Topic},
 };
 use std::{
     sync::Arc,
+    time::Instant,
     collections::HashMap
 };
 use futures::{Stream, StreamExt};
-use message_io::network::{Endpoint, SendStatus};
+use message_io::network::Endpoint;
 
 
 /// customize this to hold the states you want for each client
 #[derive(Debug)]
 struct ClientStates {
-    count: usize,
+    count:    usize,
+    /// the optional features negotiated by the client's `Hello` -- see [super::protocol::SERVER_FEATURES]
+    features: Vec<String>,
 }
 
 /// Here is where the main "protocol" processor logic lies: returns a Stream pipeline able to
 /// transform client inputs ([ClientMessages] requests) into server outputs ([ServerMessages] answers)
-fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
+fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>)
             -> impl Stream<Item = Result<(Endpoint, ServerMessages),
                                          (Endpoint, Box<dyn std::error::Error + Sync + Send>)>> {
 
@@ -45,9 +54,12 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
 
     stream
         .map(move |socket_event: SocketEvent<ClientMessages>| {
-            match socket_event {
+            let _span = processor_metrics::span(&socket_event).entered();
+            // a panic in the handlers below becomes an error answer (& an alert) -- if `panic_policy` says so
+            panic_isolation::isolated(&panic_policy, &alerts, socket_event.endpoint(), || match socket_event {
 
                 SocketEvent::Incoming { endpoint, client_message } => {
+                    let (message_type, started) = (processor_metrics::message_type(&client_message), Instant::now());
                     let server_message = match client_message {
 
                         ClientMessages::Ping => {
@@ -59,7 +71,10 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                         ClientMessages::Pang => {
                             let client_state = client_states.get_mut(&endpoint).expect("unknown client");
                             client_state.count += 1;
-                            let param = format!("`Pang` from {}, {} times", endpoint.addr(), client_state.count);
+                            let mut param = format!("`Pang` from {}, {} times", endpoint.addr(), client_state.count);
+                            if client_state.features.iter().any(|feature| feature == "pung-timestamp") {
+                                param = format!("{} -- at {}", param, chrono::Local::now().to_rfc3339());
+                            }
                             ServerMessages::Pung(param)
                         }
 
@@ -70,12 +85,26 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                         ClientMessages::Error => {
                             ServerMessages::ProcessorError("This processor handles all its errors internally...".to_string())
                         }
+
+                        // answered by the socket server itself
+                        ClientMessages::Login { .. } | ClientMessages::Hello { .. } | ClientMessages::KeepAliveAck(_) |
+                        ClientMessages::Subscribe { .. } | ClientMessages::Resume { .. } => {
+                            ServerMessages::None
+                        },
                     };
+                    processor_metrics::on_processed(message_type, started);
                     Ok((endpoint, server_message))
                 },
 
                 SocketEvent::Connected { endpoint } => {
-                    client_states.insert(endpoint, ClientStates { count: 0 });
+                    client_states.insert(endpoint, ClientStates { count: 0, features: vec![] });
+                    Ok((endpoint, ServerMessages::None))
+                },
+
+                SocketEvent::Negotiated { endpoint, features } => {
+                    if let Some(client_state) = client_states.get_mut(&endpoint) {
+                        client_state.features = features;
+                    }
                     Ok((endpoint, ServerMessages::None))
                 },
 
@@ -84,7 +113,7 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
                     Ok((endpoint, ServerMessages::None))
                 },
 
-            }
+            })
         })
 }
 
@@ -93,14 +122,50 @@ fn processor(stream: impl Stream<Item = SocketEvent<ClientMessages>>)
 ///   - The `Stream` of (`Endpoint`, [ServerMessages]) -- [socket_server] will, then, apply operations at the end of it to deliver the messages
 ///   - The producer to send `SocketEvent<ClientMessages>` to that stream
 ///   - The closer of the stream
-pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>) -> (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>,
+pub fn sync_processors(tokio_runtime: Arc<tokio::runtime::Runtime>, panic_policy: SocketPanicPolicy, alerts: Topic<Alert>) -> (impl Stream<Item = Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>,
                                                                         impl FnMut(SocketEvent<ClientMessages>) -> bool,
                                                                         impl FnMut()) {
     let (stream, producer, closer) = super::executor::sync_tokio_stream(tokio_runtime);
-    (processor(stream), producer, closer)
+    (processor(stream, panic_policy, alerts), producer, closer)
 }
 
-/// see [super::executor::spawn_concurrent_stream_executor()]
-pub async fn spawn_stream_executor(stream: impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static) -> tokio::task::JoinHandle<()> {
-    super::executor::spawn_stream_executor(stream).await
-}
\ No newline at end of file
+
+/// Unit tests the [serial_processor](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::runtime::{EventBus, alerts_topic};
+    use super::super::panic_isolation::PANIC_ALERT_KIND;
+    use message_io::{node, network::Transport};
+
+
+    /// assures a panicking handler is answered as an error -- & alerted -- while the processor goes on serving the same connection
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn panics_are_isolated() {
+        let (handler, _listener) = node::split::<()>();
+        let (_listener_id, addr) = handler.network().listen(Transport::Tcp, "127.0.0.1:0").expect("Listening");
+        let (endpoint, _local_addr) = handler.network().connect(Transport::Tcp, addr).expect("Connecting");
+        let alerts = alerts_topic(&Arc::new(EventBus::new()));
+        let mut alerts_receiver = alerts.subscribe();
+        let events = vec![
+            // the client states are only created on `Connected` -- so this `Ping` makes the handler panic
+            SocketEvent::Incoming { endpoint, client_message: ClientMessages::Ping },
+            SocketEvent::Connected { endpoint },
+            SocketEvent::Incoming { endpoint, client_message: ClientMessages::Ping },
+        ];
+        let answers: Vec<_> = futures::executor::block_on(processor(futures::stream::iter(events), SocketPanicPolicy::Isolate, alerts).collect());
+        handler.stop();
+
+        match &answers[0] {
+            Err((errored_endpoint, err)) => {
+                assert_eq!(*errored_endpoint, endpoint, "The error should be attached to the request's endpoint");
+                assert!(err.to_string().contains("unknown client"), "The error should tell the panic reason: '{}'", err);
+            },
+            other => panic!("The panic should have been answered as an error, but `{:?}` was yielded", other),
+        }
+        assert!(matches!(answers[2], Ok((_, ServerMessages::Pong(1)))), "The connection should have been served after the panic: {:?}", answers[2]);
+        let alert = alerts_receiver.try_recv().expect("The panic should have been alerted").event;
+        assert_eq!(alert.kind, PANIC_ALERT_KIND, "Wrong alert kind");
+        assert!(alert.message.contains("unknown client"), "The alert should tell the panic reason: '{}'", alert.message);
+    }
+}
@@ -2,19 +2,41 @@
 //! TODO 20220910: `message-io` should be, eventually, replaced by my own Tokio version of this nice event's library (which is uncapable of processing more than 1 client when flooded)
 
 
-use crate::config::config::{Config, SocketServerConfig};
+use crate::{
+    config::config::{Config, SocketServerConfig, IngressLimitConfig, SendQueueLimitConfig, KeepAliveConfig, ExtendedOption, SocketOverflowPolicy, SocketTransport, SocketWireFormat},
+    runtime::{Runtime, DegradeSwitches, AuthProvider, Alert, Topic, EventBus, PublishedEvent, PrivilegesGate, alerts_topic},
+    frontend::{Service, ServiceHealth, Runner},
+    logging::access_log::log_socket_access,
+};
 use super::{
     types::*,
     protocol::{self, ServerMessages, ClientMessages},
+    stats::{SocketServerStats, RoundTrips},
+    wire_dump::{WireDump, Direction},
+    egress_throttle::EgressThrottle,
+    framing::{self, FrameDecoder},
+    tls,
+    bridge::BridgedPeers,
+    unix_socket,
+    login::LoginVerifier,
+    subscriptions::Subscriptions,
+    processor_metrics,
+    processors::{sync_processors, spawn_stream_executor},
 };
 use std::{
-    sync::Arc,
-    net::{ToSocketAddrs,SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    },
+    net::{ToSocketAddrs,SocketAddr,IpAddr,Ipv4Addr},
+    time::{Duration, Instant},
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, VecDeque};
+use std::borrow::Cow;
 use owning_ref::OwningRef;
 use futures::future::BoxFuture;
 use futures::{Stream, stream, StreamExt};
+use tokio::sync::{broadcast, oneshot, RwLock};
 use message_io::{
     network::{NetEvent, Transport, Endpoint, SendStatus},
     node::{self, NodeHandler, NodeListener},
@@ -24,16 +46,200 @@ use log::{trace, debug, info, warn, error};
 
 
 type DeserializerFn = fn(&[u8]) -> Result<ClientMessages, Box<dyn std::error::Error>>;
-type SerializerFn   = fn(ServerMessages) -> String;
+type SerializerFn   = fn(ServerMessages) -> Vec<u8>;
+
+/// How often to retry dispatching the held requests to the processor, when the [SocketOverflowPolicy::Block] policy is in use
+const OVERFLOW_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Clients are only checked against [KeepAliveConfig::max_p95_round_trip_millis] once this many of their round trips were measured
+const MIN_ROUND_TRIPS_FOR_ALERTS: usize = 20;
+
+
+/// The serde functions & the `message-io` transport for the configured [SocketWireFormat] & [SocketTransport]
+#[derive(Clone, Copy)]
+struct WireCodec {
+    serializer:   SerializerFn,
+    deserializer: DeserializerFn,
+    transport:    Transport,
+    /// if each message is prefixed by its length -- see [framing]
+    framed:       bool,
+    /// if each delivery from `message-io` may carry several messages, each ending in '\n'
+    line_delimited: bool,
+}
+
+impl WireCodec {
+
+    fn new(wire_format: &SocketWireFormat, socket_transport: &SocketTransport) -> Self {
+        let (serializer, deserializer, textual): (SerializerFn, DeserializerFn, bool) = match wire_format {
+            SocketWireFormat::Ron     => (|message| protocol::ron_serializer(message).into_bytes(),  protocol::ron_deserializer,     true),
+            SocketWireFormat::Json    => (|message| protocol::json_serializer(message).into_bytes(), protocol::json_deserializer,    true),
+            SocketWireFormat::Bincode => (protocol::bincode_serializer,                              protocol::bincode_deserializer, false),
+        };
+        let (transport, framed) = match socket_transport {
+            // each datagram is delivered whole -- and may carry several textual messages
+            SocketTransport::Udp { .. } => (Transport::Udp, false),
+            // our framing puts the message length at the beginning of each message (as `FramedTcp` does), so this is suitable for binary formats
+            SocketTransport::Framed => (Transport::Tcp, true),
+            // Tcp allows plain text messages and seems to work fine for small messages (provided length < MTU size?)
+            SocketTransport::Plain | SocketTransport::Tls { .. } | SocketTransport::Unix { .. } if textual => (Transport::Tcp, false),
+            SocketTransport::Plain | SocketTransport::Tls { .. } | SocketTransport::Unix { .. } => (Transport::Tcp, true),
+        };
+        Self {
+            serializer,
+            deserializer,
+            transport,
+            framed,
+            line_delimited: textual && !framed,
+        }
+    }
+
+    /// serializes `message` -- framing it, if needed
+    fn serialize(&self, message: ServerMessages) -> Vec<u8> {
+        let output_data = (self.serializer)(message);
+        if self.framed { framing::frame(output_data) } else { output_data }
+    }
+
+    /// the messages in the `input_data` delivered by `message-io`: when framed, the frames it completes -- the incomplete one being kept in
+    /// the client's `frames` -- while, for the textual formats over `Tcp` & `Udp`, it may have several of them, each ending in '\n'.\
+    /// Fails with the announced length of a frame above `max_length`, before buffering it -- see [FrameDecoder]
+    fn messages<'a>(&self, input_data: &'a [u8], frames: &mut FrameDecoder, max_length: usize) -> Result<Vec<Cow<'a, [u8]>>, usize> {
+        if self.framed {
+            return frames.decode(input_data, max_length)
+                .map(|frames| frames.into_iter().map(Cow::Owned).collect())
+        }
+        let is_delimiter: fn(&u8) -> bool = if self.line_delimited {
+            |byte| *byte == b'\n'
+        } else {
+            |_byte| false
+        };
+        Ok(input_data.split(is_delimiter).filter(|&msg| msg.len() > 0).map(Cow::Borrowed).collect())
+    }
+
+    /// if clients are identified by their datagrams, rather than by connections -- see [SocketTransport::Udp]
+    fn is_datagram(&self) -> bool {
+        matches!(self.transport, Transport::Udp)
+    }
+}
+
+/// The signals this server sends itself through `message-io`
+enum ServerSignal {
+    /// see [SocketServer::shutdown()]
+    Shutdown,
+    /// ends the idle UDP sessions -- see [SocketTransport::Udp]
+    ExpireSessions,
+    /// the outcome of verifying the `Login` of `endpoint` -- done out of `message-io`'s thread. See [LoginVerifier::verify()]
+    LoginVerified { endpoint: Endpoint, principal: Result<Option<String>, String> },
+    /// see [SocketServer::disconnect()]
+    Disconnect { endpoint: Endpoint },
+    /// sends the keepalives -- see [SocketServerConfig::keepalive]
+    KeepAlive,
+    /// pushes an event of the `topic` clients may subscribe to -- see [forward_events()]
+    Publish { topic: &'static str, payload: String },
+    /// retries dispatching the requests of `endpoint` held for room in the processor's queue -- see [SocketOverflowPolicy::Block]
+    RetryBlocked { endpoint: Endpoint },
+}
+
+/// The connected clients' endpoints, by address -- so messages may be pushed to them. See [SocketServer::push()]
+type ConnectedClients = parking_lot::Mutex<HashMap<SocketAddr, Endpoint>>;
+
+/// How many answers each client has pending -- the events dispatched to the processor whose answers were not yet sent. Fed by [run()]
+/// & drained by [to_sender_stream()] -- see [SocketServerConfig::per_client_send_queue]
+#[derive(Default)]
+struct SendQueues(parking_lot::Mutex<HashMap<Endpoint, usize>>);
+
+impl SendQueues {
+
+    /// an event of `endpoint` was dispatched to the processor -- which answers each of them, even if with [ServerMessages::None]
+    fn on_dispatched(&self, endpoint: Endpoint) {
+        *self.0.lock().entry(endpoint).or_insert(0) += 1;
+    }
+
+    /// one of the answers for `endpoint` was sent -- or skipped
+    fn on_answered(&self, endpoint: Endpoint) {
+        if let Some(pending) = self.0.lock().get_mut(&endpoint) {
+            *pending = pending.saturating_sub(1);
+        }
+    }
+
+    /// how many answers `endpoint` has pending
+    fn pending(&self, endpoint: Endpoint) -> usize {
+        self.0.lock().get(&endpoint).copied().unwrap_or(0)
+    }
 
-// RON serde
-const DESERIALIZER: DeserializerFn = protocol::ron_deserializer;
-const SERIALIZER:   SerializerFn   = protocol::ron_serializer;
-const TRANSPORT:    Transport      = Transport::Tcp;   // Tcp allows plain text messages and seems to work fine for small messages (provided length < MTU size?)
+    /// `endpoint` is gone -- the answers still pending for it won't be accounted for
+    fn forget(&self, endpoint: Endpoint) {
+        self.0.lock().remove(&endpoint);
+    }
+}
 
-// BinCode serde
-// const TRANSPORT:    Transport = Transport::Tcp;   // FramedTcp puts the message length at the beginning of each message, so this is suitable for binary formats
-// ...
+/// What the server keeps for each connected client -- or UDP session
+struct ClientState {
+    malformed_messages:  u32,
+    /// when the client's last datagram arrived -- for expiring UDP sessions
+    last_seen:           Instant,
+    /// how many messages the client may still send at once -- see [IngressLimitConfig]
+    rate_tokens:         f64,
+    rate_refilled_at:    Instant,
+    /// messages in a row exceeding the rate
+    rate_violations:     u32,
+    /// the identity the client logged in as -- see [LoginVerifier]
+    principal:           Option<String>,
+    /// if the client is above its limit of pending answers -- see [SendQueueLimitConfig]
+    send_queue_overflow: bool,
+    /// if the client negotiated the `keepalive` feature -- see [KeepAliveConfig]
+    keepalive:           bool,
+    /// the nonce & sending time of the last keepalive, while not answered
+    pending_keepalive:   Option<(u64, Instant)>,
+    keepalives_sent:     u64,
+    /// the round trips of this connection -- checked against the SLA
+    round_trips:         RoundTrips,
+    /// if the client's p95 round trip is above the SLA -- alerted when it got there
+    above_sla:           bool,
+    /// the incomplete frame received from the client -- see [framing]
+    frames:              FrameDecoder,
+    /// the requests waiting for room in the processor's queue -- the oldest first -- along with when to give up on them.
+    /// See [SocketOverflowPolicy::Block]
+    blocked:             VecDeque<(Vec<u8>, Instant)>,
+}
+
+impl ClientState {
+
+    fn new(now: Instant) -> Self {
+        Self {
+            malformed_messages:  0,
+            last_seen:           now,
+            // full -- whatever the burst turns out to be
+            rate_tokens:         f64::INFINITY,
+            rate_refilled_at:    now,
+            rate_violations:     0,
+            principal:           None,
+            send_queue_overflow: false,
+            keepalive:           false,
+            pending_keepalive:   None,
+            keepalives_sent:     0,
+            round_trips:         RoundTrips::default(),
+            above_sla:           false,
+            frames:              FrameDecoder::default(),
+            blocked:             VecDeque::new(),
+        }
+    }
+
+    /// accounts for a message received from this client, telling if it is within the `ingress_limit` -- a token bucket refilled at
+    /// `messages_per_second`, up to `burst_messages`
+    fn within_rate(&mut self, ingress_limit: &IngressLimitConfig, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.rate_refilled_at).as_secs_f64();
+        self.rate_tokens = (self.rate_tokens + elapsed * ingress_limit.messages_per_second as f64).min(ingress_limit.burst_messages.max(1) as f64);
+        self.rate_refilled_at = now;
+        if self.rate_tokens >= 1.0 {
+            self.rate_tokens -= 1.0;
+            self.rate_violations = 0;
+            true
+        } else {
+            self.rate_violations += 1;
+            false
+        }
+    }
+}
 
 
 /// The internal events this server shares with the protocol processors
@@ -43,28 +249,100 @@ const TRANSPORT:    Transport      = Transport::Tcp;   // Tcp allows plain text
 pub enum SocketEvent<ClientMessages> {
     Connected    {endpoint: Endpoint},
     Incoming     {endpoint: Endpoint, client_message: ClientMessages},
+    /// the client said `Hello` & was welcomed: from now on, it has the given `features` enabled -- see [protocol::negotiate()]
+    Negotiated   {endpoint: Endpoint, features: Vec<String>},
     Disconnected {endpoint: Endpoint},
 }
 
+impl<ClientMessages> SocketEvent<ClientMessages> {
+    /// the client this event refers to
+    pub fn endpoint(&self) -> Endpoint {
+        match self {
+            SocketEvent::Connected    {endpoint}     |
+            SocketEvent::Incoming     {endpoint, ..} |
+            SocketEvent::Negotiated   {endpoint, ..} |
+            SocketEvent::Disconnected {endpoint}     => *endpoint,
+        }
+    }
+}
+
 /// The handle to define, start and shutdown a Socket Server
 pub struct SocketServer<'a> {
     config:                            OwningRef<Arc<Config>, SocketServerConfig>,
-    handler:                           NodeHandler<()>,
-    listener:                          Option<NodeListener<()>>,
+    /// per-endpoint statistics -- see [SocketServerStats]
+    stats:                             Arc<SocketServerStats>,
+    /// raw frames dumping -- see [WireDump]
+    wire_dump:                         Arc<WireDump>,
+    /// delays the answers exceeding the egress limits -- see [EgressThrottle]
+    egress_throttle:                   Arc<EgressThrottle>,
+    /// the hops made on behalf of other clients -- by the TLS terminator, the Unix socket bridge or the web gateways -- & the clients'
+    /// addresses behind them -- see [BridgedPeers]
+    bridged_peers:                     Arc<BridgedPeers>,
+    /// how messages are serialized & framed -- see [SocketServerConfig::wire_format]
+    codec:                             WireCodec,
+    /// see [Self::connected_clients()]
+    connected:                         Arc<ConnectedClients>,
+    /// see [SocketServerConfig::per_client_send_queue]
+    send_queues:                       Arc<SendQueues>,
+    /// may be updated while running -- see [AbuseLimits]
+    abuse_limits:                      Arc<AbuseLimits>,
+    /// checked for the maintenance mode -- see [DegradeSwitches::maintenance()]
+    degrade:                           Arc<DegradeSwitches>,
+    /// see [SocketServerConfig::login]
+    login_verifier:                    Arc<LoginVerifier>,
+    /// where the clients above the round-trip SLA are alerted to -- see [SocketServerConfig::keepalive]
+    alerts:                            Topic<Alert>,
+    /// whose events clients may subscribe to -- see [SocketServerConfig::subscriptions]
+    events:                            Arc<EventBus>,
+    handler:                           NodeHandler<ServerSignal>,
+    listener:                          Option<NodeListener<ServerSignal>>,
     request_processor_stream_producer: Option<Box<dyn FnMut(SocketEvent<ClientMessages>) -> bool + Send + Sync + 'a>>,
     request_processor_stream_closer:   Option<Box<dyn FnMut() + Send + Sync + 'a>>,
+    /// signaled when the stream returned by [Self::set_processor()] ends -- after the processor's stream was closed & all its answers sent
+    answers_flushed:                   Option<oneshot::Receiver<()>>,
+    /// the task sending the processor's answers -- see [Self::attach_processor()]
+    stream_executor:                   Option<tokio::task::JoinHandle<()>>,
+    /// set once the server is bound to its port -- see [Service::health()]
+    listening:                         Arc<AtomicBool>,
+    /// set once [Service::shutdown()] is called -- see [Service::health()]
+    shutdown_requested:                AtomicBool,
 }
 
 impl SocketServer<'static> {
 
-    pub fn new(server_config: OwningRef<Arc<Config>, SocketServerConfig>) -> Self {
-        let (handler, listener) = node::split::<()>();
+    /// `bridged_peers` maps the hops made on behalf of other clients to them -- see [BridgedPeers]; `auth` is needed if [SocketServerConfig::login]
+    /// delegates to the [AuthProvider]; `events` receives the clients above the round-trip SLA (see [alerts_topic()]) & has its events pushed
+    /// to the subscribed clients
+    pub fn new(server_config: OwningRef<Arc<Config>, SocketServerConfig>, stats: Arc<SocketServerStats>, bridged_peers: Arc<BridgedPeers>, degrade: Arc<DegradeSwitches>, auth: Option<Arc<dyn AuthProvider>>, events: Arc<EventBus>) -> Self {
+        let (handler, listener) = node::split::<ServerSignal>();
+        let wire_dump = Arc::new(WireDump::new(&server_config.wire_dump));
+        let egress_throttle = Arc::new(EgressThrottle::new(&server_config.egress_limit));
+        let abuse_limits = Arc::new(AbuseLimits::new(&server_config));
+        let codec = WireCodec::new(&server_config.wire_format, &server_config.transport);
+        let login_verifier = Arc::new(LoginVerifier::new(&server_config.login, auth));
+        let alerts = alerts_topic(&events);
         Self {
             config:                            server_config,
+            stats,
+            wire_dump,
+            egress_throttle,
+            bridged_peers,
+            codec,
+            connected:                         Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            send_queues:                       Arc::new(SendQueues::default()),
+            abuse_limits,
+            degrade,
+            login_verifier,
+            alerts,
+            events,
             handler,
             listener:                          Some(listener),
             request_processor_stream_producer: None,
             request_processor_stream_closer:   None,
+            answers_flushed:                   None,
+            stream_executor:                   None,
+            listening:                         Arc::new(AtomicBool::new(false)),
+            shutdown_requested:                AtomicBool::new(false),
         }
     }
 
@@ -78,7 +356,70 @@ impl SocketServer<'static> {
                          request_processor_stream_closer:   impl FnMut() + Send + Sync + 'static) -> impl Stream<Item = (Endpoint, SendStatus)> + Send + Sync + 'static {
         self.request_processor_stream_producer = Some(Box::new(request_processor_stream_producer));
         self.request_processor_stream_closer   = Some(Box::new(request_processor_stream_closer));
-        to_sender_stream(self.handler.clone(), request_processor_stream)
+        let (answers_flushed_sender, answers_flushed_receiver) = oneshot::channel();
+        self.answers_flushed = Some(answers_flushed_receiver);
+        to_sender_stream(self.handler.clone(), Arc::clone(&self.stats), Arc::clone(&self.wire_dump), Arc::clone(&self.egress_throttle), Arc::clone(&self.bridged_peers), Arc::clone(&self.send_queues), self.codec, request_processor_stream, answers_flushed_sender)
+    }
+
+    /// Attaches the request processor for the configured [SocketServerConfig::processor] strategy -- see [Self::set_processor()] --
+    /// spawning the executor that sends its answers back to the clients.\
+    /// Requests are only processed once `privileges` opens -- the ones arriving before that wait, as if the processor was busy
+    pub async fn attach_processor(&mut self, tokio_runtime: Arc<tokio::runtime::Runtime>, privileges: Arc<PrivilegesGate>) {
+        let processor_strategy = self.config.processor.clone();
+        let (processor_stream, stream_producer, stream_closer) = sync_processors(&processor_strategy, &self.config.panic_policy, &self.alerts, tokio_runtime);
+        let processor = self.set_processor(processor_stream, stream_producer, stream_closer);
+        let processor = stream::once(async move {
+                if privileges.wait().await {
+                    processor.left_stream()
+                } else {
+                    stream::empty().right_stream()
+                }
+            })
+            .flatten();
+        self.stream_executor = Some(spawn_stream_executor(&processor_strategy, processor).await);
+    }
+
+    /// the wire dumping facility of this server -- through which it may be paused, resumed or targeted to other endpoints
+    pub fn wire_dump(&self) -> Arc<WireDump> {
+        Arc::clone(&self.wire_dump)
+    }
+
+    /// the limits on what clients may send -- through which they may be updated while the server runs
+    pub fn abuse_limits(&self) -> Arc<AbuseLimits> {
+        Arc::clone(&self.abuse_limits)
+    }
+
+    /// the addresses of the clients currently connected -- or with open UDP sessions
+    pub fn connected_clients(&self) -> Vec<SocketAddr> {
+        self.connected.lock().keys().copied().collect()
+    }
+
+    /// sends `message` to the connected client at `address`, out of the request/answer flow -- like operators' test messages.\
+    /// Pushes are dumped & accounted for in the stats, but are not subject to the egress limits
+    pub fn push(&self, address: SocketAddr, message: ServerMessages) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let endpoint = self.connected.lock().get(&address).copied()
+            .ok_or_else(|| format!("{} is not connected", address))?;
+        let output_data = self.codec.serialize(message);
+        self.wire_dump.dump(Direction::Outbound, address, &output_data);
+        match self.handler.network().send(endpoint, &output_data) {
+            SendStatus::Sent => {
+                self.stats.on_message_out(address, output_data.len());
+                Ok(())
+            },
+            status => {
+                self.stats.on_error(address);
+                Err(Box::from(format!("Could not push to {}: {:?}", address, status)))
+            },
+        }
+    }
+
+    /// closes the connection with the client at `address` (or ends its UDP session) -- like operators do to misbehaving clients.
+    /// Done by `message-io`'s thread, so the processor is notified as in any other disconnection
+    pub fn disconnect(&self, address: SocketAddr) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let endpoint = self.connected.lock().get(&address).copied()
+            .ok_or_else(|| format!("{} is not connected", address))?;
+        self.handler.signals().send(ServerSignal::Disconnect { endpoint });
+        Ok(())
     }
 
     /// returns a runner, which you may call to run `Server` and that will only return when
@@ -91,31 +432,103 @@ impl SocketServer<'static> {
                                                                                         Box<dyn std::error::Error + Sync + Send>>> + Sync + Send + 'static,
                                              Box<dyn std::error::Error + Sync + Send>> {
 
-        let handler = self.handler.clone();
         let listener = self.listener.take();
         let interface = self.config.interface.clone();
         let port        = self.config.port;
+        let events = Arc::clone(&self.events);
+        let listening = Arc::clone(&self.listening);
+        let context = ServerContext {
+            handler:          self.handler.clone(),
+            codec:            self.codec,
+            bridged_peers:    Arc::clone(&self.bridged_peers),
+            hops_only:        matches!(self.config.transport, SocketTransport::Tls { .. } | SocketTransport::Unix { .. }),
+            connected:        Arc::clone(&self.connected),
+            stats:            Arc::clone(&self.stats),
+            wire_dump:        Arc::clone(&self.wire_dump),
+            degrade:          Arc::clone(&self.degrade),
+            login_verifier:   Arc::clone(&self.login_verifier),
+            abuse_limits:     Arc::clone(&self.abuse_limits),
+            send_queues:      Arc::clone(&self.send_queues),
+            alerts:           self.alerts.clone(),
+            session_timeout:  match &self.config.transport {
+                                  SocketTransport::Udp { session_timeout_millis } => Some(Duration::from_millis(*session_timeout_millis)),
+                                  _ => None,
+                              },
+            flush_timeout:    Duration::from_millis(self.config.flush_timeout_millis),
+            overflow_policy:  self.config.overflow_policy.clone(),
+            max_connections:  self.config.max_connections,
+            send_queue_limit: match &self.config.per_client_send_queue {
+                                  ExtendedOption::Enabled(send_queue_limit) => Some(send_queue_limit.clone()),
+                                  _ => None,
+                              },
+            keepalive:        match &self.config.keepalive {
+                                  ExtendedOption::Enabled(keepalive) => Some(keepalive.clone()),
+                                  _ => None,
+                              },
+        };
+        let subscriptions = match &self.config.subscriptions {
+            ExtendedOption::Enabled(subscriptions) => Some(subscriptions.clone()),
+            _ => None,
+        };
+        let tls_acceptor = match &self.config.transport {
+            SocketTransport::Plain | SocketTransport::Framed | SocketTransport::Udp { .. } | SocketTransport::Unix { .. } => None,
+            SocketTransport::Tls { cert_file, key_file } => Some(tls::acceptor(cert_file, key_file)?),
+        };
+        let unix_socket_file = match &self.config.transport {
+            SocketTransport::Unix { socket_file } => Some(socket_file.clone()),
+            _ => None,
+        };
         let request_processor_stream_producer = self.request_processor_stream_producer.take();
         let request_processor_stream_closer = self.request_processor_stream_closer.take();
+        let answers_flushed = self.answers_flushed.take();
 
         if listener.is_none() {
             return Err(Box::from(format!("`listener` is not present. Was this server already executed?")))
         }
-        if request_processor_stream_producer.is_none() || request_processor_stream_closer.is_none() {
+        if request_processor_stream_producer.is_none() || request_processor_stream_closer.is_none() || answers_flushed.is_none() {
             return Err(Box::from(format!("Request processor fields are not present. Was `set_processor()` called? ... or was this server already executed?")));
         }
 
         let request_processor_stream_producer = request_processor_stream_producer.unwrap();
         let request_processor_stream_closer = request_processor_stream_closer.unwrap();
+        let answers_flushed = answers_flushed.unwrap();
 
         let runner = move || -> BoxFuture<'_, Result<(), Box<dyn std::error::Error + Send + Sync>>> {
             Box::pin(async move {
                 let addr = (interface, port).to_socket_addrs()?.next().expect("Addr Iterator ended prematurely");
-                tokio::task::spawn_blocking(move || {
-                    run(handler, listener.unwrap(), addr, request_processor_stream_producer, request_processor_stream_closer)
-                }).await?;
-
-                Ok(())
+                // with TLS (& Unix sockets), the configured address (or file) is listened to by the terminator (or bridge) -- `message-io`
+                // listening on an ephemeral loopback port
+                let (addr, bound_addr, terminator) = match (tls_acceptor, &unix_socket_file) {
+                    (Some(tls_acceptor), _) => {
+                        let tls_listener = tokio::net::TcpListener::bind(addr).await
+                            .map_err(|err| format!("Cannot listening at {} by TLS: {}", addr, err))?;
+                        let (bound_addr_sender, bound_addr_receiver) = oneshot::channel();
+                        let tls_terminator = tokio::spawn(tls::terminate(tls_listener, tls_acceptor, bound_addr_receiver, Arc::clone(&context.bridged_peers)));
+                        (SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0), Some(bound_addr_sender), Some(tls_terminator))
+                    },
+                    (None, Some(socket_file)) => {
+                        let (bound_addr_sender, bound_addr_receiver) = oneshot::channel();
+                        let unix_bridge = unix_socket::spawn_bridge(socket_file, bound_addr_receiver, Arc::clone(&context.bridged_peers))?;
+                        (SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0), Some(bound_addr_sender), Some(unix_bridge))
+                    },
+                    (None, None) => (addr, None, None),
+                };
+                let forwarder = subscriptions.as_ref()
+                    .map(|subscriptions| tokio::spawn(forward_events(events.follow(), subscriptions.topics.clone(), context.handler.clone())));
+                let subscriptions = Subscriptions::new(subscriptions);
+                let result = tokio::task::spawn_blocking(move || {
+                    run(context, listener.unwrap(), addr, bound_addr, listening, request_processor_stream_producer, request_processor_stream_closer, answers_flushed, subscriptions)
+                }).await;
+                if let Some(terminator) = terminator {
+                    terminator.abort();
+                }
+                if let Some(forwarder) = forwarder {
+                    forwarder.abort();
+                }
+                if let Some(socket_file) = &unix_socket_file {
+                    unix_socket::remove_socket_file(socket_file);
+                }
+                result?
             })
         };
 
@@ -126,106 +539,1192 @@ impl SocketServer<'static> {
         warn!("Socket Server: Shutdown asked & initiated");
         self.handler
             .signals()
-            .send(());
+            .send(ServerSignal::Shutdown);
+    }
+
+}
+
+impl Service for SocketServer<'static> {
+
+    fn name(&self) -> &'static str {
+        "Socket Server"
+    }
+
+    /// requires [SocketServer::attach_processor()] to have been called
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>> {
+        Box::pin(async move {
+            let stream_executor = self.stream_executor.take()
+                .ok_or("Stream executor is not present. Was `attach_processor()` called? ... or was this server already executed?")?;
+            let runner_closure = self.runner().await?;
+            let runner: Runner = Box::pin(async move {
+                let (service_runner_result, stream_executor_result) = tokio::join!(runner_closure(), stream_executor);
+                service_runner_result.map_err(|err| format!("service runner failed: {}", err))?;
+                stream_executor_result.map_err(|err| format!("stream executor failed: {}", err))?;
+                Ok(())
+            });
+            Ok(runner)
+        })
     }
 
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.shutdown_requested.store(true, Ordering::Relaxed);
+            SocketServer::shutdown(self);
+        })
+    }
+
+    fn health(&self) -> ServiceHealth {
+        if self.shutdown_requested.load(Ordering::Relaxed) {
+            ServiceHealth::ShuttingDown
+        } else if self.listener.is_some() || !self.listening.load(Ordering::Relaxed) {
+            ServiceHealth::Starting
+        } else {
+            ServiceHealth::Running
+        }
+    }
+
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()> {
+        Box::pin(Runtime::register_socket_server(runtime, self))
+    }
 }
 
 /// upgrades the `request_processor_stream` to a `Stream` able to either process requests & send back answers to the clients
-fn to_sender_stream(handler: NodeHandler<()>, request_processor_stream: impl Stream<Item = Result<(Endpoint, ServerMessages),
-                                                                                                  (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>)
+/// -- delaying the answers exceeding the limits of `egress_throttle` & draining the clients' `send_queues`.\
+/// `answers_flushed` is signaled when it ends -- after all answers were sent
+fn to_sender_stream(handler: NodeHandler<ServerSignal>,
+                    stats: Arc<SocketServerStats>,
+                    wire_dump: Arc<WireDump>,
+                    egress_throttle: Arc<EgressThrottle>,
+                    bridged_peers: Arc<BridgedPeers>,
+                    send_queues: Arc<SendQueues>,
+                    codec: WireCodec,
+                    request_processor_stream: impl Stream<Item = Result<(Endpoint, ServerMessages),
+                                                                        (Endpoint, Box<dyn std::error::Error + Sync + Send>)>>,
+                    answers_flushed: oneshot::Sender<()>)
                    -> impl Stream<Item = (Endpoint, SendStatus)> {
 
     request_processor_stream
-        .map(move |processor_response| {
-            let (endpoint, outgoing) = match processor_response {
-                Ok((endpoint, outgoing)) => {
-                    trace!("Sending `{:?}` to {}", outgoing, endpoint.addr());
-                    (endpoint, outgoing)
-                },
-                Err((endpoint, err)) => {
-                    let err_string = format!("{:?}", err);
-                    error!("Socket Server's processor yielded an error: {}", err_string);
-                    (endpoint, ServerMessages::ProcessorError(err_string))
-                },
-            };
-            // send the message, skipping messages that are programmed not to generate any response
-            if outgoing != ServerMessages::None {
-                let output_data = SERIALIZER(outgoing);
-                let result = handler.network().send(endpoint, &output_data.as_bytes());
-                Some((endpoint, result))
-            } else {
-                None
+        .then(move |processor_response| {
+            let handler = handler.clone();
+            let stats = Arc::clone(&stats);
+            let wire_dump = Arc::clone(&wire_dump);
+            let egress_throttle = Arc::clone(&egress_throttle);
+            let bridged_peers = Arc::clone(&bridged_peers);
+            let send_queues = Arc::clone(&send_queues);
+            async move {
+                let (endpoint, outgoing) = match processor_response {
+                    Ok((endpoint, outgoing)) => {
+                        trace!("Sending `{:?}` to {}", outgoing, bridged_peers.addr_of(endpoint));
+                        (endpoint, outgoing)
+                    },
+                    Err((endpoint, err)) => {
+                        let err_string = format!("{:?}", err);
+                        error!("Socket Server's processor yielded an error: {}", err_string);
+                        stats.on_error(bridged_peers.addr_of(endpoint));
+                        (endpoint, ServerMessages::ProcessorError(err_string))
+                    },
+                };
+                stats.on_processed(bridged_peers.addr_of(endpoint));
+                processor_metrics::on_answered();
+                // send the message, skipping messages that are programmed not to generate any response
+                if outgoing != ServerMessages::None {
+                    let output_data = codec.serialize(outgoing);
+                    let delay = egress_throttle.reserve(bridged_peers.addr_of(endpoint), output_data.len());
+                    if delay > Duration::ZERO {
+                        trace!("Holding {} bytes to {} for {:?}, due to the egress limits", output_data.len(), bridged_peers.addr_of(endpoint), delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                    wire_dump.dump(Direction::Outbound, bridged_peers.addr_of(endpoint), &output_data);
+                    let result = handler.network().send(endpoint, &output_data);
+                    send_queues.on_answered(endpoint);
+                    if let SendStatus::Sent = result {
+                        stats.on_message_out(bridged_peers.addr_of(endpoint), output_data.len());
+                    } else {
+                        stats.on_error(bridged_peers.addr_of(endpoint));
+                    }
+                    Some((endpoint, result))
+                } else {
+                    send_queues.on_answered(endpoint);
+                    None
+                }
             }
         })
         .flat_map(|into_iter| stream::iter(into_iter))
+        .chain(stream::once(async move { answers_flushed.send(()).ok(); })
+            .filter_map(|()| async { None }))
+}
+
+/// forwards the events of the `topics` clients may subscribe to -- see [SocketServerConfig::subscriptions] -- to [run()], which pushes them
+async fn forward_events(mut follower: broadcast::Receiver<PublishedEvent>, topics: Vec<String>, handler: NodeHandler<ServerSignal>) {
+    loop {
+        match follower.recv().await {
+            Ok(event) if topics.iter().any(|topic| topic == event.topic) => {
+                handler.signals().send(ServerSignal::Publish { topic: event.topic, payload: event.payload.to_string() });
+            },
+            Ok(_event) => (),
+            Err(broadcast::error::RecvError::Lagged(missed)) => warn!("Socket Server: {} events weren't pushed to the subscribed clients, for being published too fast", missed),
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Limits on what clients may send -- clients exceeding them are disconnected & have their IPs banned for a while.\
+/// See [SocketServerConfig::max_message_length] & friends. May be updated while the server runs -- see [Self::update()]
+pub struct AbuseLimits {
+    max_message_length:     AtomicUsize,
+    /// 0 for no limit
+    max_malformed_messages: AtomicU32,
+    ban_seconds:            AtomicU64,
+    /// see [SocketServerConfig::ingress_limit]
+    ingress_limit:          parking_lot::RwLock<Option<IngressLimitConfig>>,
+}
+
+impl AbuseLimits {
+
+    fn new(server_config: &SocketServerConfig) -> Self {
+        Self {
+            max_message_length:     AtomicUsize::new(server_config.max_message_length),
+            max_malformed_messages: AtomicU32::new(server_config.max_malformed_messages),
+            ban_seconds:            AtomicU64::new(Self::effective_ban_seconds(server_config)),
+            ingress_limit:          parking_lot::RwLock::new(Self::enabled_ingress_limit(server_config)),
+        }
+    }
+
+    /// applies the limits in `server_config` -- effective for the next messages received. Used by the config hot reload
+    pub fn update(&self, server_config: &SocketServerConfig) {
+        self.max_message_length.store(server_config.max_message_length, Ordering::Relaxed);
+        self.max_malformed_messages.store(server_config.max_malformed_messages, Ordering::Relaxed);
+        self.ban_seconds.store(Self::effective_ban_seconds(server_config), Ordering::Relaxed);
+        *self.ingress_limit.write() = Self::enabled_ingress_limit(server_config);
+    }
+
+    /// Unix socket clients are never banned -- just disconnected: they all share the IP of their loopback hops, so banning one would ban them all.
+    /// Only the users allowed by the socket file's permissions reach them, anyway -- see [unix_socket]
+    fn effective_ban_seconds(server_config: &SocketServerConfig) -> u64 {
+        if matches!(server_config.transport, SocketTransport::Unix { .. }) {
+            0
+        } else {
+            server_config.ban_seconds
+        }
+    }
+
+    fn enabled_ingress_limit(server_config: &SocketServerConfig) -> Option<IngressLimitConfig> {
+        match &server_config.ingress_limit {
+            ExtendedOption::Enabled(ingress_limit) => Some(ingress_limit.clone()),
+            _ => None,
+        }
+    }
+
+    fn max_message_length(&self) -> usize {
+        self.max_message_length.load(Ordering::Relaxed)
+    }
+
+    fn max_malformed_messages(&self) -> u32 {
+        self.max_malformed_messages.load(Ordering::Relaxed)
+    }
+
+    fn ban_duration(&self) -> Duration {
+        Duration::from_secs(self.ban_seconds.load(Ordering::Relaxed))
+    }
+
+    fn ingress_limit(&self) -> Option<IngressLimitConfig> {
+        self.ingress_limit.read().clone()
+    }
+}
+
+/// What [run()] -- & the [Connections] bookkeeping -- needs from the [SocketServer] being run: its handles & the settings taken from its config
+struct ServerContext {
+    handler:          NodeHandler<ServerSignal>,
+    codec:            WireCodec,
+    /// tells the clients' addresses behind the hops -- see [BridgedPeers]
+    bridged_peers:    Arc<BridgedPeers>,
+    /// if only the hops are served -- for the TLS & Unix socket transports, whose plain listener is reached through a bridge
+    hops_only:        bool,
+    connected:        Arc<ConnectedClients>,
+    stats:            Arc<SocketServerStats>,
+    wire_dump:        Arc<WireDump>,
+    degrade:          Arc<DegradeSwitches>,
+    login_verifier:   Arc<LoginVerifier>,
+    abuse_limits:     Arc<AbuseLimits>,
+    send_queues:      Arc<SendQueues>,
+    /// where the clients above the round-trip SLA are alerted to
+    alerts:           Topic<Alert>,
+    /// UDP only: for how long sessions last without datagrams
+    session_timeout:  Option<Duration>,
+    flush_timeout:    Duration,
+    overflow_policy:  SocketOverflowPolicy,
+    /// 0 for no limit
+    max_connections:  usize,
+    send_queue_limit: Option<SendQueueLimitConfig>,
+    keepalive:        Option<KeepAliveConfig>,
+}
+
+/// The state [run()] keeps about its clients
+struct Connections {
+    /// connected clients (or UDP sessions)
+    clients:       HashMap<Endpoint, ClientState>,
+    banned_until:  HashMap<IpAddr, Instant>,
+    subscriptions: Subscriptions<Endpoint>,
 }
 
 /// Runs the server until a shutdown is requested.\
 /// Incoming requests are feed through `send_to_request_processor()` -- which was generated along with a stream that transforms [ClientMessages] into [ServerMessages];\
-/// Once the server is shutdown, `close_request_processor_stream()` is called and waited on.
-fn run(handler:                               NodeHandler<()>,
-       listener:                              NodeListener<()>,
-       addr:                                  SocketAddr,
-       mut send_to_request_processor:         impl FnMut(SocketEvent<ClientMessages>) -> bool,
-       mut close_request_processor_stream:    impl FnMut()) {
+/// Once the server is shutdown, `close_request_processor_stream()` is called and `answers_flushed` is waited on for up to [ServerContext::flush_timeout]
+/// -- so the answers for the requests already dispatched are sent before the clients are disconnected.\
+/// Requests arriving while the processor is too busy are handled according to [ServerContext::overflow_policy].\
+/// While in maintenance mode (see [ServerContext::degrade]), requests are answered with [ServerMessages::Maintenance] & new connections are refused.\
+/// Clients exceeding the [ServerContext::abuse_limits] are answered with the corresponding error, disconnected & banned.\
+/// `Login`s are answered here, once verified by the [ServerContext::login_verifier] -- which, if logging in is required, also holds the other
+/// messages of the clients not logged in.\
+/// `Hello`s are also answered here -- the negotiated protocol being told to the processor (see [SocketEvent::Negotiated]) & the clients
+/// speaking versions no longer supported being disconnected.
+/// Notice `message-io` delivers the textual formats' bytes as they are read, so a line without '\n' is never accumulated: a line longer than
+/// what is read at once is processed in pieces -- and is, then, caught by one of the limits. Frames are accumulated (see [framing]), but only
+/// up to the limit: longer ones are refused by their length prefix.\
+/// `bound_addr`, if given, receives the address actually listened to -- see [tls::terminate()] & [unix_socket::spawn_bridge()] -- connections
+/// not made by the terminator (or bridge) being refused (see [ServerContext::hops_only]).\
+/// With UDP, there are no connections: a client's first datagram is taken as its connection -- being refused, in maintenance or when banned,
+/// by dropping it -- & its session ends after [ServerContext::session_timeout] without datagrams from it.\
+/// New clients above [ServerContext::max_connections] are answered with [ServerMessages::TooManyClients] & refused; while the clients with more
+/// answers pending in the send queues than [ServerContext::send_queue_limit] allows are flagged -- having their requests answered with `TooBusy` --
+/// or disconnected.\
+/// If [ServerContext::keepalive] is given, the clients that negotiated it are sent [ServerMessages::KeepAlive]s -- their answers measuring their
+/// round trips, which are fed to the stats & alerted when above the SLA.\
+/// `Subscribe`s & `Resume`s are answered here, by `subscriptions` -- which get the events to push through [ServerSignal::Publish].
+fn run(context:                            ServerContext,
+       listener:                           NodeListener<ServerSignal>,
+       addr:                               SocketAddr,
+       bound_addr:                         Option<oneshot::Sender<SocketAddr>>,
+       listening:                          Arc<AtomicBool>,
+       mut send_to_request_processor:      impl FnMut(SocketEvent<ClientMessages>) -> bool,
+       mut close_request_processor_stream: impl FnMut(),
+       answers_flushed:                    oneshot::Receiver<()>,
+       subscriptions:                      Subscriptions<Endpoint>)
+      -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
 
-    let mut clients: HashSet<Endpoint> = HashSet::new();
+    let mut connections = Connections {
+        clients:       HashMap::new(),
+        banned_until:  HashMap::new(),
+        subscriptions,
+    };
+    // UDP sessions are checked for expiration twice per `session_timeout`
+    let expiration_interval = context.session_timeout.map(|session_timeout| (session_timeout / 2).max(Duration::from_millis(1)));
+    let mut answers_flushed = Some(answers_flushed);
+    let flushed_in_time = Arc::new(AtomicBool::new(true));
+    let flushed_in_time_for_listener = Arc::clone(&flushed_in_time);
+    let flush_timeout = context.flush_timeout;
+    // `message-io` calls us back from its own threads -- out of the Tokio runtime we are running on
+    let tokio_runtime = tokio::runtime::Handle::current();
 
-    match handler.network().listen(TRANSPORT, addr) {
-        Ok((_id, real_addr)) => info!("Socket Server running at {} by {}", real_addr, TRANSPORT),
-        Err(_) => return error!("Cannot listening at {} by {}", addr, TRANSPORT),
+    match context.handler.network().listen(context.codec.transport, addr) {
+        Ok((_id, real_addr)) => {
+            info!("Socket Server running at {} by {}", real_addr, context.codec.transport);
+            if let Some(bound_addr) = bound_addr {
+                bound_addr.send(real_addr).ok();
+            }
+            listening.store(true, Ordering::Relaxed);
+            if let Some(expiration_interval) = expiration_interval {
+                context.handler.signals().send_with_timer(ServerSignal::ExpireSessions, expiration_interval);
+            }
+            if let Some(keepalive) = &context.keepalive {
+                context.handler.signals().send_with_timer(ServerSignal::KeepAlive, Duration::from_millis(keepalive.interval_millis));
+            }
+        },
+        Err(err) => return Err(Box::from(format!("Cannot listening at {} by {}: {}", addr, context.codec.transport, err))),
     }
 
     listener.for_each(move |event| match event {
         NodeEvent::Network(net_event) => match net_event {
             NetEvent::Message(endpoint, input_data) => {
-                for input_message in input_data.split(|c| *c == '\n' as u8).filter(|&msg| msg.len() > 0) {
-                    match DESERIALIZER(input_message) {
+                context.stats.on_bytes_in(context.bridged_peers.addr_of(endpoint), input_data.len());
+                context.wire_dump.dump(Direction::Inbound, context.bridged_peers.addr_of(endpoint), input_data);
+                if context.codec.is_datagram() && !connections.clients.contains_key(&endpoint) {
+                    // UDP has no connections: this client's first datagram is accepted or refused as if it were one
+                    if context.degrade.maintenance() {
+                        info!("Refusing UDP session from '{}': in maintenance mode", context.bridged_peers.addr_of(endpoint));
+                        log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                        let output_data = context.codec.serialize(ServerMessages::Maintenance(context.degrade.maintenance_message().to_string()));
+                        context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                        context.handler.network().send(endpoint, &output_data);
+                        return
+                    }
+                    if let Some(until) = connections.banned_until.get(&context.bridged_peers.addr_of(endpoint).ip()).filter(|until| **until > Instant::now()) {
+                        let remaining = until.saturating_duration_since(Instant::now());
+                        info!("Refusing UDP session from '{}': banned for {:?} more", context.bridged_peers.addr_of(endpoint), remaining);
+                        log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                        let output_data = context.codec.serialize(ServerMessages::Banned(remaining.as_secs() + 1));
+                        context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                        context.handler.network().send(endpoint, &output_data);
+                        return
+                    }
+                    if context.max_connections > 0 && connections.clients.len() >= context.max_connections {
+                        info!("Refusing UDP session from '{}': already at the limit of {} clients", context.bridged_peers.addr_of(endpoint), context.max_connections);
+                        log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                        context.stats.on_refused_connection();
+                        let output_data = context.codec.serialize(ServerMessages::TooManyClients(context.max_connections));
+                        context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                        context.handler.network().send(endpoint, &output_data);
+                        return
+                    }
+                    connections.connect(endpoint, &context, &mut send_to_request_processor);
+                    info!("Started UDP session with '{}' -- client count: {}", context.bridged_peers.addr_of(endpoint), connections.clients.len());
+                }
+                if let Some(client) = connections.clients.get_mut(&endpoint) {
+                    client.last_seen = Instant::now();
+                }
+                let mut unknown_client_frames = FrameDecoder::default();
+                let frames = connections.clients.get_mut(&endpoint).map_or(&mut unknown_client_frames, |client| &mut client.frames);
+                let input_messages = match context.codec.messages(input_data, frames, context.abuse_limits.max_message_length()) {
+                    Ok(input_messages) => input_messages,
+                    Err(announced_length) => {
+                        connections.refuse_too_long(endpoint, announced_length, &context, &mut send_to_request_processor);
+                        return
+                    },
+                };
+                for input_message in &input_messages {
+                    let input_message: &[u8] = input_message;
+                    if input_message.len() > context.abuse_limits.max_message_length() {
+                        connections.refuse_too_long(endpoint, input_message.len(), &context, &mut send_to_request_processor);
+                        break
+                    }
+                    if let (Some(ingress_limit), Some(client)) = (context.abuse_limits.ingress_limit(), connections.clients.get_mut(&endpoint)) {
+                        if !client.within_rate(&ingress_limit, Instant::now()) {
+                            let rate_violations = client.rate_violations;
+                            context.stats.on_rate_limited(context.bridged_peers.addr_of(endpoint));
+                            let output_data = context.codec.serialize(ServerMessages::TooBusy);
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            context.handler.network().send(endpoint, &output_data);
+                            if ingress_limit.max_violations > 0 && rate_violations >= ingress_limit.max_violations {
+                                warn!("{} sent {} messages in a row above the rate of {} messages/s: disconnecting & banning the client for {:?}",
+                                      context.bridged_peers.addr_of(endpoint), rate_violations, ingress_limit.messages_per_second, context.abuse_limits.ban_duration());
+                                connections.banned_until.insert(context.bridged_peers.addr_of(endpoint).ip(), Instant::now() + context.abuse_limits.ban_duration());
+                                connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+                                break
+                            }
+                            trace!("{} exceeded the rate of {} messages/s: answered `TooBusy`", context.bridged_peers.addr_of(endpoint), ingress_limit.messages_per_second);
+                            continue
+                        }
+                    }
+                    if let (Some(send_queue_limit), Some(client)) = (&context.send_queue_limit, connections.clients.get_mut(&endpoint)) {
+                        let pending_answers = context.send_queues.pending(endpoint);
+                        let overflow = pending_answers >= send_queue_limit.max_pending_answers;
+                        if overflow && send_queue_limit.disconnect {
+                            warn!("{} has {} answers pending, reaching the limit of {}: disconnecting the client",
+                                  context.bridged_peers.addr_of(endpoint), pending_answers, send_queue_limit.max_pending_answers);
+                            context.stats.on_send_queue_overflow(context.bridged_peers.addr_of(endpoint));
+                            connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+                            break
+                        }
+                        if overflow != client.send_queue_overflow {
+                            client.send_queue_overflow = overflow;
+                            if overflow {
+                                warn!("{} has {} answers pending, reaching the limit of {}: flagged -- its requests are answered `TooBusy` until they are sent",
+                                      context.bridged_peers.addr_of(endpoint), pending_answers, send_queue_limit.max_pending_answers);
+                                context.stats.on_send_queue_overflow(context.bridged_peers.addr_of(endpoint));
+                            } else {
+                                info!("{} is no longer above its limit of pending answers: unflagged", context.bridged_peers.addr_of(endpoint));
+                            }
+                        }
+                        if overflow {
+                            let output_data = context.codec.serialize(ServerMessages::TooBusy);
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            context.handler.network().send(endpoint, &output_data);
+                            continue
+                        }
+                    }
+                    match (context.codec.deserializer)(input_message) {
+                        Ok(ClientMessages::KeepAliveAck(nonce)) => {
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            let Some(client) = connections.clients.get_mut(&endpoint) else { continue };
+                            let Some((_nonce, sent)) = client.pending_keepalive.filter(|(pending_nonce, _sent)| *pending_nonce == nonce) else {
+                                debug!("{} answered the unknown (or late) keepalive #{}: ignoring it", context.bridged_peers.addr_of(endpoint), nonce);
+                                continue
+                            };
+                            client.pending_keepalive = None;
+                            let round_trip = sent.elapsed();
+                            trace!("{} answered the keepalive #{} in {:?}", context.bridged_peers.addr_of(endpoint), nonce, round_trip);
+                            context.stats.on_round_trip(context.bridged_peers.addr_of(endpoint), round_trip);
+                            client.round_trips.record(round_trip);
+                            let max_p95_round_trip_millis = context.keepalive.as_ref().map_or(0, |keepalive| keepalive.max_p95_round_trip_millis);
+                            if max_p95_round_trip_millis == 0 || client.round_trips.len() < MIN_ROUND_TRIPS_FOR_ALERTS {
+                                continue
+                            }
+                            let p95 = client.round_trips.percentile(95.0).unwrap_or_default();
+                            let above_sla = p95 > Duration::from_millis(max_p95_round_trip_millis);
+                            if above_sla && !client.above_sla {
+                                let message = format!("{} has its p95 round trip at {:?} -- above the SLA of {}ms", context.bridged_peers.addr_of(endpoint), p95, max_p95_round_trip_millis);
+                                warn!("{}", message);
+                                context.alerts.publish(Alert { source: "socket-server", kind: "round-trip", message });
+                            } else if !above_sla && client.above_sla {
+                                info!("{} has its p95 round trip back within the SLA of {}ms, at {:?}", context.bridged_peers.addr_of(endpoint), max_p95_round_trip_millis, p95);
+                            }
+                            client.above_sla = above_sla;
+                        },
+                        Ok(incoming) if context.degrade.maintenance() => {
+                            debug!("Received `{:?}` from {} while in maintenance mode: answering `Maintenance`", incoming, context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            let output_data = context.codec.serialize(ServerMessages::Maintenance(context.degrade.maintenance_message().to_string()));
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            context.handler.network().send(endpoint, &output_data);
+                        },
+                        Ok(ClientMessages::Hello { protocol_version, features }) => {
+                            trace!("Received `Hello` from {}: protocol v{}, features {:?}", context.bridged_peers.addr_of(endpoint), protocol_version, features);
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            let Some((protocol_version, features)) = protocol::negotiate(protocol_version, &features) else {
+                                warn!("{} speaks the protocol v{}, older than the minimum of v{}: disconnecting the client",
+                                      context.bridged_peers.addr_of(endpoint), protocol_version, protocol::MIN_PROTOCOL_VERSION);
+                                log_socket_access(context.bridged_peers.addr_of(endpoint), "HELLO-REFUSE");
+                                context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                                let output_data = context.codec.serialize(ServerMessages::IncompatibleProtocol { min_version: protocol::MIN_PROTOCOL_VERSION, max_version: protocol::PROTOCOL_VERSION });
+                                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                                context.handler.network().send(endpoint, &output_data);
+                                connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+                                break
+                            };
+                            debug!("{} speaks the protocol v{}, with features {:?}", context.bridged_peers.addr_of(endpoint), protocol_version, features);
+                            if let Some(client) = connections.clients.get_mut(&endpoint) {
+                                client.keepalive = context.keepalive.is_some() && features.iter().any(|feature| feature == "keepalive");
+                            }
+                            let output_data = context.codec.serialize(ServerMessages::Welcome { protocol_version, features: features.clone() });
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            if let SendStatus::Sent = context.handler.network().send(endpoint, &output_data) {
+                                context.stats.on_message_out(context.bridged_peers.addr_of(endpoint), output_data.len());
+                            }
+                            context.stats.on_dispatched(context.bridged_peers.addr_of(endpoint));
+                            processor_metrics::on_dispatched();
+                            if send_to_request_processor(SocketEvent::Negotiated { endpoint, features }) {
+                                context.send_queues.on_dispatched(endpoint);
+                            } else {
+                                error!("Server was too busy to tell the processor the protocol negotiated with {}: its features won't be enabled", context.bridged_peers.addr_of(endpoint));
+                                context.stats.on_rejected(context.bridged_peers.addr_of(endpoint));
+                                processor_metrics::on_rejected();
+                            }
+                        },
+                        Ok(ClientMessages::Login { token }) => {
+                            trace!("Received `Login` from {}", context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            // verifying may take a while (like when asking an OIDC provider) -- so it is done out of this thread
+                            let handler = context.handler.clone();
+                            let login_verifier = Arc::clone(&context.login_verifier);
+                            tokio_runtime.spawn(async move {
+                                let principal = login_verifier.verify(&token).await
+                                    .map_err(|err| err.to_string());
+                                handler.signals().send(ServerSignal::LoginVerified { endpoint, principal });
+                            });
+                        },
+                        Ok(incoming) if context.login_verifier.is_required() && connections.clients.get(&endpoint).is_some_and(|client| client.principal.is_none()) => {
+                            debug!("Received `{:?}` from {}, who didn't log in: answering `Unauthenticated`", incoming, context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                            let output_data = context.codec.serialize(ServerMessages::Unauthenticated(String::from("Login first")));
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            context.handler.network().send(endpoint, &output_data);
+                        },
+                        Ok(ClientMessages::Subscribe { topic }) => {
+                            trace!("Received `Subscribe` to '{}' from {}", topic, context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            let principal = connections.clients.get(&endpoint).and_then(|client| client.principal.clone());
+                            let answer = connections.subscriptions.subscribe(endpoint, principal, topic, Instant::now());
+                            debug!("Answering the `Subscribe` of {} with `{:?}`", context.bridged_peers.addr_of(endpoint), answer);
+                            let output_data = context.codec.serialize(answer);
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            if let SendStatus::Sent = context.handler.network().send(endpoint, &output_data) {
+                                context.stats.on_message_out(context.bridged_peers.addr_of(endpoint), output_data.len());
+                            }
+                        },
+                        Ok(ClientMessages::Resume { subscription_id, last_seq }) => {
+                            trace!("Received `Resume` of the subscription #{} after #{} from {}", subscription_id, last_seq, context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            let principal = connections.clients.get(&endpoint).and_then(|client| client.principal.clone());
+                            let answers = connections.subscriptions.resume(endpoint, principal.as_deref(), subscription_id, last_seq, Instant::now());
+                            debug!("{} resumed the subscription #{}, having {} messages replayed", context.bridged_peers.addr_of(endpoint), subscription_id, answers.len().saturating_sub(1));
+                            for answer in answers {
+                                let output_data = context.codec.serialize(answer);
+                                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                                if let SendStatus::Sent = context.handler.network().send(endpoint, &output_data) {
+                                    context.stats.on_message_out(context.bridged_peers.addr_of(endpoint), output_data.len());
+                                }
+                            }
+                        },
                         Ok(incoming) => {
-                            trace!("Received `{:?}` from {}", incoming, endpoint.addr());
+                            trace!("Received `{:?}` from {}", incoming, context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_message_in(context.bridged_peers.addr_of(endpoint));
+                            context.stats.on_dispatched(context.bridged_peers.addr_of(endpoint));
+                            processor_metrics::on_dispatched();
+                            // requests arriving while others of the client are held (see `SocketOverflowPolicy::Block` below) are held as well -- keeping their order
+                            if let (SocketOverflowPolicy::Block { timeout_millis }, Some(client)) = (&context.overflow_policy, connections.clients.get_mut(&endpoint)) {
+                                if !client.blocked.is_empty() {
+                                    client.blocked.push_back((input_message.to_vec(), Instant::now() + Duration::from_millis(*timeout_millis)));
+                                    continue
+                                }
+                            }
                             let sent = send_to_request_processor(SocketEvent::Incoming { endpoint, client_message: incoming });
-                            if !sent {
-                                error!("Server was too busy to process message '{:?}' for {}", std::str::from_utf8(input_message), endpoint.addr());
-                                let output_data = SERIALIZER(ServerMessages::TooBusy);
-                                handler.network().send(endpoint, &output_data.as_bytes());
+                            if sent {
+                                context.send_queues.on_dispatched(endpoint);
+                                continue
                             }
+                            processor_metrics::on_overflow(&context.overflow_policy);
+                            match &context.overflow_policy {
+                                SocketOverflowPolicy::ReplyTooBusy => {
+                                    error!("Server was too busy to process message '{:?}' for {}: answering `TooBusy`", std::str::from_utf8(input_message), context.bridged_peers.addr_of(endpoint));
+                                    let output_data = context.codec.serialize(ServerMessages::TooBusy);
+                                    context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                                    context.handler.network().send(endpoint, &output_data);
+                                },
+                                SocketOverflowPolicy::Drop => {
+                                    error!("Server was too busy to process message '{:?}' for {}: dropping it", std::str::from_utf8(input_message), context.bridged_peers.addr_of(endpoint));
+                                },
+                                SocketOverflowPolicy::Block { timeout_millis } => {
+                                    // the wait doesn't happen here, on `message-io`'s thread -- which would stall all clients: the request is held & retried on timer signals
+                                    if let Some(client) = connections.clients.get_mut(&endpoint) {
+                                        debug!("Server was too busy to process message '{:?}' for {}: holding it for up to {}ms", std::str::from_utf8(input_message), context.bridged_peers.addr_of(endpoint), timeout_millis);
+                                        client.blocked.push_back((input_message.to_vec(), Instant::now() + Duration::from_millis(*timeout_millis)));
+                                        context.handler.signals().send_with_timer(ServerSignal::RetryBlocked { endpoint }, OVERFLOW_RETRY_INTERVAL);
+                                        continue
+                                    }
+                                    error!("Server was too busy to process message '{:?}' for {}, who is no longer connected: dropping it", std::str::from_utf8(input_message), context.bridged_peers.addr_of(endpoint));
+                                },
+                                SocketOverflowPolicy::Disconnect => {
+                                    error!("Server was too busy to process message '{:?}' for {}: disconnecting the client", std::str::from_utf8(input_message), context.bridged_peers.addr_of(endpoint));
+                                    context.stats.on_rejected(context.bridged_peers.addr_of(endpoint));
+                                    processor_metrics::on_rejected();
+                                    connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+                                    break
+                                },
+                            }
+                            context.stats.on_rejected(context.bridged_peers.addr_of(endpoint));
+                            processor_metrics::on_rejected();
                         },
                         Err(err) => {
-                            debug!("Unknown command received from {}: String: {:?}. Bytes: {:?}", endpoint.addr(), std::str::from_utf8(input_message), input_message);
-                            let output_data = SERIALIZER(ServerMessages::UnknownMessage(err.to_string()));
-                            handler.network().send(endpoint, &output_data.as_bytes());
+                            debug!("Unknown command received from {}: String: {:?}. Bytes: {:?}", context.bridged_peers.addr_of(endpoint), std::str::from_utf8(input_message), input_message);
+                            context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                            let output_data = context.codec.serialize(ServerMessages::UnknownMessage(err.to_string()));
+                            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                            context.handler.network().send(endpoint, &output_data);
+                            let malformed_messages = connections.clients.get_mut(&endpoint)
+                                .map(|client| { client.malformed_messages += 1; client.malformed_messages })
+                                .unwrap_or(0);
+                            if context.abuse_limits.max_malformed_messages() > 0 && malformed_messages >= context.abuse_limits.max_malformed_messages() {
+                                warn!("{} sent {} malformed messages: disconnecting & banning the client for {:?}", context.bridged_peers.addr_of(endpoint), malformed_messages, context.abuse_limits.ban_duration());
+                                let output_data = context.codec.serialize(ServerMessages::TooManyMalformedMessages(context.abuse_limits.max_malformed_messages()));
+                                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                                context.handler.network().send(endpoint, &output_data);
+                                connections.banned_until.insert(context.bridged_peers.addr_of(endpoint).ip(), Instant::now() + context.abuse_limits.ban_duration());
+                                connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+                                break
+                            }
                         },
                     }
                 }
             },
-            NetEvent::Connected(endpoint, handshake) => {
-                debug!("Unknown connection attempted from '{endpoint}': handshake: {handshake} -- UDP?");
+            // only reported for the connections we start -- which this server never does (UDP clients start their sessions in `Message`)
+            NetEvent::Connected(endpoint, established) => {
+                debug!("Unexpected outgoing connection event for '{endpoint}': established: {established}");
+            },
+            NetEvent::Accepted(endpoint, _listener_id) if context.hops_only && !context.bridged_peers.is_hop(endpoint) => {
+                warn!("Refusing TCP connection from '{}': it didn't come through the TLS terminator (or Unix socket bridge)", endpoint.addr());
+                log_socket_access(endpoint.addr(), "REFUSE");
+                context.handler.network().remove(endpoint.resource_id());
+            },
+            NetEvent::Accepted(endpoint, _listener_id) if context.degrade.maintenance() => {
+                info!("Refusing TCP connection from '{}': in maintenance mode", context.bridged_peers.addr_of(endpoint));
+                log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                let output_data = context.codec.serialize(ServerMessages::Maintenance(context.degrade.maintenance_message().to_string()));
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                context.handler.network().send(endpoint, &output_data);
+                // `message-io` won't report this disconnection -- neither the stats nor the processor ever knew about this client
+                context.handler.network().remove(endpoint.resource_id());
+            },
+            NetEvent::Accepted(endpoint, _listener_id) if connections.banned_until.get(&context.bridged_peers.addr_of(endpoint).ip()).is_some_and(|until| *until > Instant::now()) => {
+                let remaining = connections.banned_until[&context.bridged_peers.addr_of(endpoint).ip()].saturating_duration_since(Instant::now());
+                info!("Refusing TCP connection from '{}': banned for {:?} more", context.bridged_peers.addr_of(endpoint), remaining);
+                log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                let output_data = context.codec.serialize(ServerMessages::Banned(remaining.as_secs() + 1));
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                context.handler.network().send(endpoint, &output_data);
+                context.handler.network().remove(endpoint.resource_id());
+            },
+            NetEvent::Accepted(endpoint, _listener_id) if context.max_connections > 0 && connections.clients.len() >= context.max_connections => {
+                info!("Refusing TCP connection from '{}': already at the limit of {} clients", context.bridged_peers.addr_of(endpoint), context.max_connections);
+                log_socket_access(context.bridged_peers.addr_of(endpoint), "REFUSE");
+                context.stats.on_refused_connection();
+                let output_data = context.codec.serialize(ServerMessages::TooManyClients(context.max_connections));
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                context.handler.network().send(endpoint, &output_data);
+                context.handler.network().remove(endpoint.resource_id());
             },
             NetEvent::Accepted(endpoint, listener_id) => {
-                clients.insert(endpoint);
-                info!("Accepted TCP connection from '{}': listener_id: {} -- client count: {}", endpoint.addr(), listener_id, clients.len());
-                send_to_request_processor(SocketEvent::Connected { endpoint });
+                connections.connect(endpoint, &context, &mut send_to_request_processor);
+                info!("Accepted TCP connection from '{}': listener_id: {} -- client count: {}", context.bridged_peers.addr_of(endpoint), listener_id, connections.clients.len());
             },
             NetEvent::Disconnected(endpoint) => {
-                clients.remove(&endpoint);
-                info!("TCP Disconnected from '{}': -- client count: {}", endpoint.addr(), clients.len());
+                connections.clients.remove(&endpoint);
+                connections.subscriptions.detach(endpoint, Instant::now());
+                context.connected.lock().remove(&context.bridged_peers.addr_of(endpoint));
+                context.send_queues.forget(endpoint);
+                info!("TCP Disconnected from '{}': -- client count: {}", context.bridged_peers.addr_of(endpoint), connections.clients.len());
+                log_socket_access(context.bridged_peers.addr_of(endpoint), "DISCONNECT");
+                context.stats.on_disconnected(context.bridged_peers.addr_of(endpoint));
+                context.stats.on_dispatched(context.bridged_peers.addr_of(endpoint));
+                processor_metrics::on_dispatched();
                 send_to_request_processor(SocketEvent::Disconnected { endpoint });
             },
         },
-        // shutdown event
-        NodeEvent::Signal(_) => {
+        NodeEvent::Signal(ServerSignal::ExpireSessions) => {
+            let (Some(session_timeout), Some(expiration_interval)) = (context.session_timeout, expiration_interval) else { return };
+            let now = Instant::now();
+            let expired: Vec<Endpoint> = connections.clients.iter()
+                .filter(|(_endpoint, client)| now.duration_since(client.last_seen) >= session_timeout)
+                .map(|(endpoint, _client)| *endpoint)
+                .collect();
+            for endpoint in expired {
+                info!("UDP session with '{}' expired: no datagrams for {:?}", context.bridged_peers.addr_of(endpoint), session_timeout);
+                connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+            }
+            context.handler.signals().send_with_timer(ServerSignal::ExpireSessions, expiration_interval);
+        },
+        NodeEvent::Signal(ServerSignal::LoginVerified { endpoint, principal }) => {
+            // the client may have gone away while its login was being verified
+            let Some(client) = connections.clients.get_mut(&endpoint) else { return };
+            let answer = match principal {
+                Ok(Some(principal)) => {
+                    info!("{} logged in as '{}'", context.bridged_peers.addr_of(endpoint), principal);
+                    log_socket_access(context.bridged_peers.addr_of(endpoint), "LOGIN");
+                    client.principal = Some(principal.clone());
+                    ServerMessages::LoggedIn(principal)
+                },
+                Ok(None) => {
+                    warn!("{} failed to log in: invalid token", context.bridged_peers.addr_of(endpoint));
+                    log_socket_access(context.bridged_peers.addr_of(endpoint), "LOGIN-REFUSE");
+                    context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                    ServerMessages::Unauthenticated(String::from("Invalid token"))
+                },
+                Err(err) => {
+                    error!("Could not verify the login of {}: {}", context.bridged_peers.addr_of(endpoint), err);
+                    log_socket_access(context.bridged_peers.addr_of(endpoint), "LOGIN-REFUSE");
+                    context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                    ServerMessages::Unauthenticated(String::from("The token could not be verified -- try again later"))
+                },
+            };
+            let output_data = context.codec.serialize(answer);
+            context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+            if let SendStatus::Sent = context.handler.network().send(endpoint, &output_data) {
+                context.stats.on_message_out(context.bridged_peers.addr_of(endpoint), output_data.len());
+            }
+        },
+        NodeEvent::Signal(ServerSignal::Disconnect { endpoint }) => {
+            // the client may have gone away in the meantime
+            if connections.clients.contains_key(&endpoint) {
+                info!("Disconnecting {}, as asked", context.bridged_peers.addr_of(endpoint));
+                connections.disconnect(endpoint, &context, &mut send_to_request_processor);
+            }
+        },
+        NodeEvent::Signal(ServerSignal::KeepAlive) => {
+            let Some(keepalive) = &context.keepalive else { return };
+            let now = Instant::now();
+            for (endpoint, client) in connections.clients.iter_mut().filter(|(_endpoint, client)| client.keepalive) {
+                if let Some((nonce, _sent)) = client.pending_keepalive {
+                    debug!("{} didn't answer the keepalive #{} within {}ms", context.bridged_peers.addr_of(*endpoint), nonce, keepalive.interval_millis);
+                }
+                client.keepalives_sent += 1;
+                client.pending_keepalive = Some((client.keepalives_sent, now));
+                let output_data = context.codec.serialize(ServerMessages::KeepAlive(client.keepalives_sent));
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(*endpoint), &output_data);
+                if let SendStatus::Sent = context.handler.network().send(*endpoint, &output_data) {
+                    context.stats.on_message_out(context.bridged_peers.addr_of(*endpoint), output_data.len());
+                }
+            }
+            context.handler.signals().send_with_timer(ServerSignal::KeepAlive, Duration::from_millis(keepalive.interval_millis));
+        },
+        NodeEvent::Signal(ServerSignal::Publish { topic, payload }) => {
+            for (endpoint, push) in connections.subscriptions.publish(topic, &payload, Instant::now()) {
+                let output_data = context.codec.serialize(push);
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                if let SendStatus::Sent = context.handler.network().send(endpoint, &output_data) {
+                    context.stats.on_message_out(context.bridged_peers.addr_of(endpoint), output_data.len());
+                } else {
+                    context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+                }
+            }
+        },
+        NodeEvent::Signal(ServerSignal::RetryBlocked { endpoint }) => {
+            // the client may have gone away in the meantime
+            let Some(client) = connections.clients.get_mut(&endpoint) else { return };
+            while let Some((input_message, deadline)) = client.blocked.pop_front() {
+                // the message was consumed by the failed attempt: it is deserialized again for each retry
+                let Ok(incoming) = (context.codec.deserializer)(&input_message) else { continue };
+                if send_to_request_processor(SocketEvent::Incoming { endpoint, client_message: incoming }) {
+                    context.send_queues.on_dispatched(endpoint);
+                } else if Instant::now() >= deadline {
+                    error!("Server was too busy to process message '{:?}' for {} even after holding it: dropping it", std::str::from_utf8(&input_message), context.bridged_peers.addr_of(endpoint));
+                    context.stats.on_rejected(context.bridged_peers.addr_of(endpoint));
+                    processor_metrics::on_rejected();
+                } else {
+                    client.blocked.push_front((input_message, deadline));
+                    context.handler.signals().send_with_timer(ServerSignal::RetryBlocked { endpoint }, OVERFLOW_RETRY_INTERVAL);
+                    break
+                }
+            }
+        },
+        NodeEvent::Signal(ServerSignal::Shutdown) => {
             // send the shutdown notification to all clients
             warn!("Sending any pending messages");
             close_request_processor_stream();
-            //drop(request_processor_stream_producer);
-            warn!("Socket Server: Notifying {} client{}", clients.len(), if clients.len() != 1 {"s"} else {""});
-            let output_data = SERIALIZER(ServerMessages::ShuttingDown);
-            for endpoint in clients.drain() {
-                handler.network().send(endpoint, &output_data.as_bytes());
+            if let Some(answers_flushed) = answers_flushed.take() {
+                let flushed = tokio_runtime
+                    .block_on(async { tokio::time::timeout(flush_timeout, answers_flushed).await });
+                if !matches!(flushed, Ok(Ok(()))) {
+                    error!("Socket Server: the processor didn't flush its answers within {:?} -- answers for requests still being processed were dropped", flush_timeout);
+                    flushed_in_time_for_listener.store(false, Ordering::Relaxed);
+                }
+            }
+            warn!("Socket Server: Notifying {} client{}", connections.clients.len(), if connections.clients.len() != 1 {"s"} else {""});
+            let output_data = context.codec.serialize(ServerMessages::ShuttingDown);
+            context.connected.lock().clear();
+            for (endpoint, _client) in connections.clients.drain() {
+                context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+                context.handler.network().send(endpoint, &output_data);
             }
             warn!("Socket Server: telling `message-io` its services are no longer needed");
-            handler.stop();
+            context.handler.stop();
         },
     });
+
+    if flushed_in_time.load(Ordering::Relaxed) {
+        Ok(())
+    } else {
+        Err(Box::from(format!("Socket Server: answers were dropped on shutdown, as the processor couldn't flush them within {:?}", flush_timeout)))
+    }
+}
+
+impl Connections {
+
+    /// does the bookkeeping for a new client -- whose connection was accepted or, with UDP, whose first datagram arrived
+    fn connect(&mut self, endpoint: Endpoint, context: &ServerContext, send_to_request_processor: &mut impl FnMut(SocketEvent<ClientMessages>) -> bool) {
+        // forget the expired bans
+        let now = Instant::now();
+        self.banned_until.retain(|_ip, until| *until > now);
+        self.clients.insert(endpoint, ClientState::new(now));
+        context.connected.lock().insert(context.bridged_peers.addr_of(endpoint), endpoint);
+        log_socket_access(context.bridged_peers.addr_of(endpoint), "CONNECT");
+        context.stats.on_connected(context.bridged_peers.addr_of(endpoint));
+        context.stats.on_dispatched(context.bridged_peers.addr_of(endpoint));
+        processor_metrics::on_dispatched();
+        if send_to_request_processor(SocketEvent::Connected { endpoint }) {
+            context.send_queues.on_dispatched(endpoint);
+        }
+    }
+
+    /// answers `endpoint`, who sent a message of `length` bytes -- above [AbuseLimits::max_message_length()] -- with [ServerMessages::MessageTooLong],
+    /// disconnecting & banning it
+    fn refuse_too_long(&mut self, endpoint: Endpoint, length: usize, context: &ServerContext, send_to_request_processor: &mut impl FnMut(SocketEvent<ClientMessages>) -> bool) {
+        warn!("Message of {} bytes received from {} exceeds the limit of {}: disconnecting & banning the client for {:?}",
+              length, context.bridged_peers.addr_of(endpoint), context.abuse_limits.max_message_length(), context.abuse_limits.ban_duration());
+        context.stats.on_error(context.bridged_peers.addr_of(endpoint));
+        let output_data = context.codec.serialize(ServerMessages::MessageTooLong(context.abuse_limits.max_message_length()));
+        context.wire_dump.dump(Direction::Outbound, context.bridged_peers.addr_of(endpoint), &output_data);
+        context.handler.network().send(endpoint, &output_data);
+        self.banned_until.insert(context.bridged_peers.addr_of(endpoint).ip(), Instant::now() + context.abuse_limits.ban_duration());
+        self.disconnect(endpoint, context, send_to_request_processor);
+    }
+
+    /// closes the connection with `endpoint` (or ends its UDP session), doing the bookkeeping for it
+    /// -- as `message-io` doesn't report disconnections it was asked to do
+    fn disconnect(&mut self, endpoint: Endpoint, context: &ServerContext, send_to_request_processor: &mut impl FnMut(SocketEvent<ClientMessages>) -> bool) {
+        log_socket_access(context.bridged_peers.addr_of(endpoint), "DISCONNECT");
+        // UDP clients share the listener's resource -- removing it would stop the server
+        if !context.codec.is_datagram() {
+            context.handler.network().remove(endpoint.resource_id());
+        }
+        self.clients.remove(&endpoint);
+        self.subscriptions.detach(endpoint, Instant::now());
+        context.connected.lock().remove(&context.bridged_peers.addr_of(endpoint));
+        context.send_queues.forget(endpoint);
+        context.stats.on_disconnected(context.bridged_peers.addr_of(endpoint));
+        context.stats.on_dispatched(context.bridged_peers.addr_of(endpoint));
+        processor_metrics::on_dispatched();
+        if !send_to_request_processor(SocketEvent::Disconnected { endpoint }) {
+            error!("Server was too busy to be notified that {} was disconnected: its processor states will linger", context.bridged_peers.addr_of(endpoint));
+            context.stats.on_rejected(context.bridged_peers.addr_of(endpoint));
+            processor_metrics::on_rejected();
+        }
+    }
+}
+
+
+/// Unit tests the [socket_server](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::config::config::{DegradeConfig, EgressLimitConfig};
+    use futures::future;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+        net::{TcpStream, tcp::{OwnedReadHalf, OwnedWriteHalf}},
+    };
+
+
+    /// For how long [TestClient::receive()] waits for the server's messages
+    const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+    /// A socket server running on a free loopback port -- see [start_server()]
+    struct TestServer {
+        server:   SocketServer<'static>,
+        address:  SocketAddr,
+        runner:   tokio::task::JoinHandle<Result<(), Box<dyn std::error::Error + Sync + Send>>>,
+        executor: tokio::task::JoinHandle<()>,
+    }
+
+    impl TestServer {
+
+        /// shuts the server down, waiting for it to end
+        async fn stop(self) {
+            self.server.shutdown();
+            self.runner.await.expect("Joining the server")
+                .expect("The server should have ended gracefully");
+            self.executor.await.expect("Joining the answers executor");
+        }
+    }
+
+    /// starts a socket server for the default config -- adjusted by `configure()` -- whose processor answers each request with `Pong(0)`,
+    /// but for the ones `accepts()` refuses: as if its queue was full, they are left to the [SocketOverflowPolicy]
+    async fn start_server(configure: impl FnOnce(&mut SocketServerConfig), mut accepts: impl FnMut() -> bool + Send + Sync + 'static) -> TestServer {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Finding a free port")
+            .port();
+        let mut config = Config::default();
+        let server_config = &mut *config.services.socket_server;
+        server_config.interface = String::from("127.0.0.1");
+        server_config.port = port;
+        configure(server_config);
+        let server_config = OwningRef::new(Arc::new(config))
+            .map(|config| &*config.services.socket_server);
+        let mut server = SocketServer::new(server_config, Arc::new(SocketServerStats::new(16)), Arc::new(BridgedPeers::new()), Arc::new(DegradeSwitches::new(&DegradeConfig::default())), None, Arc::new(EventBus::new()));
+        // `None` is the end-of-stream mark sent by the closer
+        let (requests_sender, mut requests_receiver) = tokio::sync::mpsc::unbounded_channel::<Option<SocketEvent<ClientMessages>>>();
+        let end_sender = requests_sender.clone();
+        let processor_stream = stream::poll_fn(move |cx| requests_receiver.poll_recv(cx).map(Option::flatten))
+            .map(|socket_event| -> Result<(Endpoint, ServerMessages), (Endpoint, Box<dyn std::error::Error + Sync + Send>)> {
+                let answer = match socket_event {
+                    SocketEvent::Incoming { .. } => ServerMessages::Pong(0),
+                    _ => ServerMessages::None,
+                };
+                Ok((socket_event.endpoint(), answer))
+            });
+        let producer = move |socket_event: SocketEvent<ClientMessages>| match socket_event {
+            SocketEvent::Incoming { .. } if !accepts() => false,
+            socket_event => requests_sender.send(Some(socket_event)).is_ok(),
+        };
+        let closer = move || {
+            end_sender.send(None).ok();
+        };
+        let answers = server.set_processor(processor_stream, producer, closer);
+        let executor = tokio::spawn(answers.for_each(|_sent| future::ready(())));
+        let runner = server.runner().await.expect("Preparing the server");
+        let runner = tokio::spawn(runner());
+        while !matches!(Service::health(&server), ServiceHealth::Running) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        TestServer { server, address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), runner, executor }
+    }
+
+    /// A client of the [TestServer], speaking RON
+    struct TestClient {
+        lines:  Lines<BufReader<OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl TestClient {
+
+        async fn connect(address: SocketAddr) -> Self {
+            let (reader, writer) = TcpStream::connect(address).await
+                .expect("Connecting to the server")
+                .into_split();
+            Self { lines: BufReader::new(reader).lines(), writer }
+        }
+
+        /// sends all `messages` at once
+        async fn send(&mut self, messages: &[ClientMessages]) {
+            let output_data: String = messages.iter()
+                .map(protocol::ron_client_serializer)
+                .collect();
+            self.writer.write_all(output_data.as_bytes()).await
+                .expect("Sending to the server");
+        }
+
+        /// the next message from the server -- `None` if the connection was closed
+        async fn receive(&mut self) -> Option<ServerMessages> {
+            let line = tokio::time::timeout(RECEIVE_TIMEOUT, self.lines.next_line()).await
+                .expect("Nothing was received from the server in time")
+                .ok()??;
+            Some(protocol::ron_client_deserializer(line.as_bytes()).expect("Deserializing the server's message"))
+        }
+
+        /// tells if nothing is received from the server for `duration`
+        async fn receives_nothing_for(&mut self, duration: Duration) -> bool {
+            tokio::time::timeout(duration, self.lines.next_line()).await.is_err()
+        }
+    }
+
+
+    /// assures requests refused by a busy processor are silently dropped with [SocketOverflowPolicy::Drop] -- the connection being kept
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn dropped_overflows() {
+        let mut requests = 0;
+        let server = start_server(|config| config.overflow_policy = SocketOverflowPolicy::Drop, move || { requests += 1; requests > 1 }).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert!(client.receives_nothing_for(Duration::from_millis(300)).await, "The request refused by the processor should have been dropped, unanswered");
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "The connection should have been kept -- & the next request, processed");
+        server.stop().await;
+    }
+
+    /// assures requests refused by a busy processor wait for room with [SocketOverflowPolicy::Block] -- being dropped if it doesn't come in time -- without stalling the other clients
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn blocked_overflows() {
+        // room is made after 10 retries -- within the timeout
+        let mut attempts = 0;
+        let server = start_server(|config| config.overflow_policy = SocketOverflowPolicy::Block { timeout_millis: 2000 }, move || { attempts += 1; attempts > 10 }).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "The request should have waited for room in the processor's queue");
+        server.stop().await;
+
+        // room never comes
+        let server = start_server(|config| config.overflow_policy = SocketOverflowPolicy::Block { timeout_millis: 50 }, || false).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert!(client.receives_nothing_for(Duration::from_millis(300)).await, "The request should have been dropped once the timeout elapsed");
+        assert_eq!(server.server.connected_clients().len(), 1, "The client should have been kept connected");
+        server.stop().await;
+
+        // the held requests don't stall the other clients
+        let server = start_server(|config| config.overflow_policy = SocketOverflowPolicy::Block { timeout_millis: 2000 }, || false).await;
+        let mut blocked_client = TestClient::connect(server.address).await;
+        let mut other_client = TestClient::connect(server.address).await;
+        blocked_client.send(&[ClientMessages::Ping]).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        other_client.send(&[ClientMessages::Subscribe { topic: String::from("anything") }]).await;
+        let answer = tokio::time::timeout(Duration::from_millis(500), other_client.receive()).await
+            .expect("Other clients should have been answered while the request was held");
+        assert!(matches!(answer, Some(ServerMessages::SubscriptionRefused(_))), "Unexpected answer: {:?}", answer);
+        server.stop().await;
+    }
+
+    /// assures clients whose requests are refused by a busy processor are disconnected with [SocketOverflowPolicy::Disconnect]
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn disconnected_overflows() {
+        let server = start_server(|config| config.overflow_policy = SocketOverflowPolicy::Disconnect, || false).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, None, "The client should have been disconnected, unanswered");
+        assert!(server.server.connected_clients().is_empty(), "The server shouldn't count the client as connected");
+        server.stop().await;
+    }
+
+    /// assures the answers above the client's egress limit are held -- each `Pong(0)\n` being 8 bytes
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn egress_is_throttled() {
+        let server = start_server(|config| config.egress_limit = ExtendedOption::Enabled(EgressLimitConfig {
+            per_connection_bytes_per_second: 100,
+            per_connection_burst_bytes:      8,
+            global_bytes_per_second:         0,
+            global_burst_bytes:              0,
+        }), || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        let start = Instant::now();
+        client.send(&[ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping]).await;
+        for _ in 0..3 {
+            assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "All answers should have been sent, eventually");
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(120), "Only the 1st answer fits the burst: the other 16 bytes, at 100 bytes/s, should have waited ~160ms -- not {:?}", elapsed);
+        server.stop().await;
+    }
+
+    /// assures the messages above the client's ingress limit are answered with `TooBusy` -- & that, after too many in a row, the client
+    /// is disconnected & banned
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn ingress_is_limited() {
+        let ingress_limit = |max_violations| ExtendedOption::Enabled(IngressLimitConfig {
+            messages_per_second: 1,
+            burst_messages:      2,
+            max_violations,
+        });
+
+        // flooding clients are answered `TooBusy`, but kept
+        let server = start_server(|config| config.ingress_limit = ingress_limit(0), || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping]).await;
+        let mut answers = vec![];
+        for _ in 0..4 {
+            answers.push(client.receive().await.expect("The client should have been kept connected"));
+        }
+        // `TooBusy`s are answered by the network thread, possibly ahead of the processor's answers
+        assert_eq!(answers.iter().filter(|answer| **answer == ServerMessages::Pong(0)).count(), 2, "Only the burst should have been processed: {:?}", answers);
+        assert_eq!(answers.iter().filter(|answer| **answer == ServerMessages::TooBusy).count(), 2, "The messages above the burst should have been answered `TooBusy`: {:?}", answers);
+        server.stop().await;
+
+        // abusive ones are disconnected & banned
+        let server = start_server(|config| config.ingress_limit = ingress_limit(2), || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping]).await;
+        let mut answers = vec![];
+        while let Some(answer) = client.receive().await {
+            answers.push(answer);
+        }
+        assert!(answers.contains(&ServerMessages::TooBusy), "The messages above the burst should have been answered `TooBusy` before disconnecting: {:?}", answers);
+        let mut banned_client = TestClient::connect(server.address).await;
+        assert!(matches!(banned_client.receive().await, Some(ServerMessages::Banned(_))), "New connections from the abusive client's IP should have been refused with `Banned`");
+        assert_eq!(banned_client.receive().await, None, "The banned client's connection should have been closed");
+        server.stop().await;
+    }
+
+    /// assures new clients above [SocketServerConfig::max_connections] are answered with `TooManyClients` & refused -- room being made as others leave
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn max_connections() {
+        let server = start_server(|config| config.max_connections = 1, || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "The client within the limit should have been served");
+        let mut refused_client = TestClient::connect(server.address).await;
+        assert_eq!(refused_client.receive().await, Some(ServerMessages::TooManyClients(1)), "The client above the limit should have been told so");
+        assert_eq!(refused_client.receive().await, None, "The client above the limit should have been disconnected");
+        drop(client);
+        while !server.server.connected_clients().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mut new_client = TestClient::connect(server.address).await;
+        new_client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(new_client.receive().await, Some(ServerMessages::Pong(0)), "Clients should have been accepted again once there was room");
+        server.stop().await;
+    }
+
+    /// assures clients reaching [SocketServerConfig::per_client_send_queue] have their requests answered with `TooBusy` until their answers
+    /// are sent -- or are disconnected. Answers are held by an egress limit of 100 bytes/s with no burst: ~80ms for each `Pong(0)\n`
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn send_queue_overflow() {
+        let configure = |disconnect| move |config: &mut SocketServerConfig| {
+            config.per_client_send_queue = ExtendedOption::Enabled(SendQueueLimitConfig { max_pending_answers: 2, disconnect });
+            config.egress_limit = ExtendedOption::Enabled(EgressLimitConfig {
+                per_connection_bytes_per_second: 100,
+                per_connection_burst_bytes:      0,
+                global_bytes_per_second:         0,
+                global_burst_bytes:              0,
+            });
+        };
+
+        // flagged clients are answered `TooBusy`, but kept
+        let server = start_server(configure(false), || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        // lets the (unanswered) `Connected` event leave the queue
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        client.send(&[ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping]).await;
+        let mut answers = vec![];
+        for _ in 0..4 {
+            answers.push(client.receive().await.expect("The client should have been kept connected"));
+        }
+        assert_eq!(answers.iter().filter(|answer| **answer == ServerMessages::Pong(0)).count(), 2, "Only the requests within the limit should have been processed: {:?}", answers);
+        assert_eq!(answers.iter().filter(|answer| **answer == ServerMessages::TooBusy).count(), 2, "The requests above the limit should have been answered `TooBusy`: {:?}", answers);
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "Once its answers were sent, the client should have been unflagged");
+        server.stop().await;
+
+        // or disconnected
+        let server = start_server(configure(true), || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        client.send(&[ClientMessages::Ping, ClientMessages::Ping, ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, None, "The client reaching the limit should have been disconnected");
+        assert!(server.server.connected_clients().is_empty(), "The server shouldn't count the client as connected");
+        server.stop().await;
+    }
+
+    /// assures messages above [SocketServerConfig::max_message_length] are answered with `MessageTooLong` -- their clients being disconnected & banned
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn too_long_messages() {
+        let server = start_server(|config| config.max_message_length = 16, || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "Messages within the limit should have been processed");
+        client.send(&[ClientMessages::Login { token: "t".repeat(16) }]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::MessageTooLong(16)), "The message above the limit should have been refused");
+        assert_eq!(client.receive().await, None, "The client should have been disconnected");
+        let mut banned_client = TestClient::connect(server.address).await;
+        assert!(matches!(banned_client.receive().await, Some(ServerMessages::Banned(_))), "New connections from the client's IP should have been refused with `Banned`");
+        server.stop().await;
+    }
+
+    /// assures length prefixed messages above [SocketServerConfig::max_message_length] are refused by their prefix -- before being received
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn too_long_frames() {
+        let server = start_server(|config| { config.transport = SocketTransport::Framed; config.max_message_length = 16 }, || true).await;
+        let mut stream = TcpStream::connect(server.address).await.expect("Connecting to the server");
+        // a 1 MiB frame is announced -- but never sent
+        stream.write_all(&[0x80, 0x80, 0x40]).await.expect("Sending the length prefix");
+        let mut frames = FrameDecoder::default();
+        let mut buffer = [0u8; 64];
+        let answer = loop {
+            let read = tokio::time::timeout(RECEIVE_TIMEOUT, tokio::io::AsyncReadExt::read(&mut stream, &mut buffer)).await
+                .expect("Nothing was received from the server in time")
+                .expect("Receiving from the server");
+            assert!(read > 0, "The server should have answered before disconnecting");
+            if let Some(frame) = frames.decode(&buffer[..read], usize::MAX).expect("Decoding the server's frames").pop() {
+                break frame
+            }
+        };
+        assert_eq!(protocol::ron_client_deserializer(answer.trim_ascii_end()).expect("Deserializing the server's message"), ServerMessages::MessageTooLong(16),
+                   "The frame above the limit should have been refused by its length prefix");
+        let read = tokio::time::timeout(RECEIVE_TIMEOUT, tokio::io::AsyncReadExt::read(&mut stream, &mut buffer)).await
+            .expect("The connection should have been closed in time");
+        assert!(matches!(read, Ok(0) | Err(_)), "The client should have been disconnected");
+        server.stop().await;
+    }
+
+    /// assures clients sending [SocketServerConfig::max_malformed_messages] are answered with `TooManyMalformedMessages`, disconnected & banned
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn malformed_messages() {
+        let server = start_server(|config| { config.max_malformed_messages = 3; config.ban_seconds = 60 }, || true).await;
+        let mut client = TestClient::connect(server.address).await;
+        client.writer.write_all(b"Pinga\nPinga\n").await.expect("Sending to the server");
+        for _ in 0..2 {
+            assert!(matches!(client.receive().await, Some(ServerMessages::UnknownMessage(_))), "Malformed messages below the limit should have been answered `UnknownMessage`");
+        }
+        client.send(&[ClientMessages::Ping]).await;
+        assert_eq!(client.receive().await, Some(ServerMessages::Pong(0)), "The client should have been kept connected while below the limit");
+        client.writer.write_all(b"Pinga\n").await.expect("Sending to the server");
+        assert!(matches!(client.receive().await, Some(ServerMessages::UnknownMessage(_))), "The malformed message reaching the limit should have been answered `UnknownMessage`");
+        assert_eq!(client.receive().await, Some(ServerMessages::TooManyMalformedMessages(3)), "Reaching the limit should have been told to the client");
+        assert_eq!(client.receive().await, None, "The client should have been disconnected");
+        let mut banned_client = TestClient::connect(server.address).await;
+        assert!(matches!(banned_client.receive().await, Some(ServerMessages::Banned(seconds)) if seconds <= 60), "New connections from the client's IP should have been refused with `Banned`");
+        assert_eq!(banned_client.receive().await, None, "The banned client's connection should have been closed");
+        server.stop().await;
+    }
+
+    /// assures Unix socket clients are served through the bridge -- the socket file being removed on shutdown
+    #[cfg(unix)]
+    #[cfg_attr(not(feature = "dox"), tokio::test(flavor = "multi_thread"))]
+    async fn unix_socket_clients() {
+        let socket_file = std::env::temp_dir()
+            .join(format!("kickass-socket-server-test-{}.sock", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let server = start_server(|config| config.transport = SocketTransport::Unix { socket_file: socket_file.clone() }, || true).await;
+        let (reader, mut writer) = tokio::net::UnixStream::connect(&socket_file).await
+            .expect("Connecting to the socket file")
+            .into_split();
+        writer.write_all(protocol::ron_client_serializer(&ClientMessages::Ping).as_bytes()).await
+            .expect("Sending to the server");
+        let line = tokio::time::timeout(RECEIVE_TIMEOUT, BufReader::new(reader).lines().next_line()).await
+            .expect("Nothing was received from the server in time")
+            .expect("Receiving from the server")
+            .expect("The connection was closed");
+        assert_eq!(protocol::ron_client_deserializer(line.as_bytes()).expect("Deserializing the server's message"), ServerMessages::Pong(0), "The Unix socket client should have been answered");
+        server.stop().await;
+        assert!(!std::path::Path::new(&socket_file).exists(), "The socket file should have been removed on shutdown");
+    }
 }
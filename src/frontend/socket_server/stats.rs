@@ -0,0 +1,336 @@
+//! Per-endpoint statistics for the socket server -- exposed through the web API (`/api/stats/socket`) and the terminal dashboard.
+//! Their server-wide totals (connections & messages) are also published through the `metrics` facade -- see [describe_metrics()].
+//!
+//! Memory is bounded by [crate::config::SocketServerConfig::max_tracked_endpoints]: when full, room is made by forgetting the
+//! disconnected endpoint inactive for the longest time -- if all tracked endpoints are connected, new ones are simply not tracked.\
+//! Round-trip times are measured for the clients answering the server's keepalives -- see [crate::config::SocketServerConfig::keepalive] --
+//! & reported as percentiles of their most recent [ROUND_TRIP_SAMPLES].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Unit};
+
+
+/// Gauge of the clients currently connected (or with open UDP sessions)
+pub const CONNECTED_CLIENTS_METRIC: &str = "socket_server_connected_clients";
+/// Counter of the connections accepted (or UDP sessions started)
+pub const CONNECTIONS_METRIC: &str = "socket_server_connections_total";
+/// Counter of the messages received & sent, labeled by `direction` (`in` or `out`)
+pub const MESSAGES_METRIC: &str = "socket_server_messages_total";
+/// Counter of the bytes received & sent, labeled by `direction` (`in` or `out`)
+pub const BYTES_METRIC: &str = "socket_server_bytes_total";
+/// Counter of the messages exceeding their clients' rate -- see [crate::config::SocketServerConfig::ingress_limit]
+pub const RATE_LIMITED_METRIC: &str = "socket_server_rate_limited_total";
+/// Counter of the times clients had too many answers pending -- see [crate::config::SocketServerConfig::per_client_send_queue]
+pub const SEND_QUEUE_OVERFLOWS_METRIC: &str = "socket_server_send_queue_overflows_total";
+/// Counter of the connections refused for being above the limit -- see [crate::config::SocketServerConfig::max_connections]
+pub const REFUSED_CONNECTIONS_METRIC: &str = "socket_server_refused_connections_total";
+/// Histogram of the clients' round-trip times, measured through keepalives -- see [crate::config::SocketServerConfig::keepalive]
+pub const ROUND_TRIP_METRIC: &str = "socket_server_round_trip_seconds";
+
+/// How many of the most recent round trips of each client are kept for computing their percentiles
+pub const ROUND_TRIP_SAMPLES: usize = 128;
+
+
+/// registers the descriptions of the socket server's metrics -- to be called once the recorder is installed
+pub fn describe_metrics() {
+    describe_gauge!(CONNECTED_CLIENTS_METRIC, Unit::Count, "Clients currently connected to the socket server");
+    describe_counter!(CONNECTIONS_METRIC, Unit::Count, "Connections accepted by the socket server");
+    describe_counter!(MESSAGES_METRIC, Unit::Count, "Messages received & sent by the socket server, by direction");
+    describe_counter!(BYTES_METRIC, Unit::Bytes, "Bytes received & sent by the socket server, by direction");
+    describe_counter!(RATE_LIMITED_METRIC, Unit::Count, "Messages refused by the socket server for exceeding their clients' rate");
+    describe_counter!(SEND_QUEUE_OVERFLOWS_METRIC, Unit::Count, "Times the socket server's clients had too many answers pending -- being flagged or disconnected");
+    describe_counter!(REFUSED_CONNECTIONS_METRIC, Unit::Count, "Connections refused by the socket server for being above its limit of clients");
+    describe_histogram!(ROUND_TRIP_METRIC, Unit::Seconds, "Round-trip times of the socket server's clients, measured through keepalives");
+}
+
+
+/// Shared between the socket server (which feeds it) and the UIs (which report it)
+pub struct SocketServerStats {
+    max_tracked_endpoints: usize,
+    endpoints:             Mutex<HashMap<SocketAddr, EndpointStats>>,
+    /// events that happened to endpoints that couldn't be tracked
+    untracked_events:      AtomicU64,
+}
+
+struct EndpointStats {
+    connected:       bool,
+    messages_in:     u64,
+    messages_out:    u64,
+    bytes_in:        u64,
+    bytes_out:       u64,
+    errors:          u64,
+    last_activity:   Instant,
+    /// when each of the events still being processed were dispatched to the processor
+    processing:      VecDeque<Instant>,
+    processed:       u64,
+    processing_time: Duration,
+    round_trips:     RoundTrips,
+}
+
+/// The most recent round-trip times of a client -- up to [ROUND_TRIP_SAMPLES] of them
+#[derive(Debug, Default)]
+pub struct RoundTrips(VecDeque<Duration>);
+
+impl RoundTrips {
+
+    pub fn record(&mut self, round_trip: Duration) {
+        if self.0.len() >= ROUND_TRIP_SAMPLES {
+            self.0.pop_front();
+        }
+        self.0.push_back(round_trip);
+    }
+
+    /// how many round trips are kept
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// the `percentile` (from 0 to 100) of the kept round trips, by the nearest-rank method -- `None` if none were measured
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.0.is_empty() {
+            return None
+        }
+        let mut sorted: Vec<Duration> = self.0.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+
+    /// `percentile` in microseconds -- 0 if none were measured
+    fn percentile_micros(&self, percentile: f64) -> f64 {
+        self.percentile(percentile).map_or(0.0, |round_trip| round_trip.as_secs_f64() * 1e6)
+    }
+}
+
+/// Snapshot of [SocketServerStats], as reported to the UIs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SocketServerStatsReport {
+    pub max_tracked_endpoints: usize,
+    pub untracked_events:      u64,
+    pub endpoints:             Vec<EndpointStatsReport>,
+}
+
+/// Snapshot of the statistics for a single endpoint -- see [SocketServerStatsReport]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EndpointStatsReport {
+    pub address:                        String,
+    pub connected:                      bool,
+    pub messages_in:                    u64,
+    pub messages_out:                   u64,
+    pub bytes_in:                       u64,
+    pub bytes_out:                      u64,
+    pub errors:                         u64,
+    /// seconds since the last activity
+    pub idle_seconds:                   f64,
+    /// mean time the processor took, from the event dispatching to the answer
+    pub mean_processing_latency_micros: f64,
+    /// percentiles of the most recent round trips -- 0 if none were measured (see [crate::config::SocketServerConfig::keepalive])
+    pub round_trip_p50_micros:          f64,
+    pub round_trip_p95_micros:          f64,
+    pub round_trip_p99_micros:          f64,
+}
+
+impl SocketServerStats {
+
+    pub fn new(max_tracked_endpoints: usize) -> Self {
+        Self {
+            max_tracked_endpoints,
+            endpoints:        Mutex::new(HashMap::new()),
+            untracked_events: AtomicU64::new(0),
+        }
+    }
+
+    /// starts tracking `address` -- if there is room for it
+    pub fn on_connected(&self, address: SocketAddr) {
+        counter!(CONNECTIONS_METRIC).increment(1);
+        gauge!(CONNECTED_CLIENTS_METRIC).increment(1.0);
+        if self.max_tracked_endpoints == 0 {
+            return
+        }
+        let mut endpoints = self.endpoints.lock();
+        if !endpoints.contains_key(&address) && !make_room(&mut endpoints, self.max_tracked_endpoints) {
+            self.untracked_events.fetch_add(1, Ordering::Relaxed);
+            return
+        }
+        endpoints.insert(address, EndpointStats {
+            connected:       true,
+            messages_in:     0,
+            messages_out:    0,
+            bytes_in:        0,
+            bytes_out:       0,
+            errors:          0,
+            last_activity:   Instant::now(),
+            processing:      VecDeque::new(),
+            processed:       0,
+            processing_time: Duration::ZERO,
+            round_trips:     RoundTrips::default(),
+        });
+    }
+
+    pub fn on_disconnected(&self, address: SocketAddr) {
+        gauge!(CONNECTED_CLIENTS_METRIC).decrement(1.0);
+        self.update(address, |stats| stats.connected = false);
+    }
+
+    /// `bytes` were received -- possibly containing several messages
+    pub fn on_bytes_in(&self, address: SocketAddr, bytes: usize) {
+        counter!(BYTES_METRIC, "direction" => "in").increment(bytes as u64);
+        self.update(address, |stats| stats.bytes_in += bytes as u64);
+    }
+
+    /// a message was received and dispatched to the processor
+    pub fn on_message_in(&self, address: SocketAddr) {
+        counter!(MESSAGES_METRIC, "direction" => "in").increment(1);
+        self.update(address, |stats| stats.messages_in += 1);
+    }
+
+    /// an event (a message, a connection or a disconnection) was dispatched to the processor -- to be matched by [Self::on_processed()]
+    pub fn on_dispatched(&self, address: SocketAddr) {
+        self.update(address, |stats| stats.processing.push_back(Instant::now()));
+    }
+
+    /// the processor yielded the answer for one of the events dispatched to it
+    pub fn on_processed(&self, address: SocketAddr) {
+        self.update(address, |stats| if let Some(dispatched) = stats.processing.pop_front() {
+            stats.processed += 1;
+            stats.processing_time += dispatched.elapsed();
+        });
+    }
+
+    /// the last event given to [Self::on_dispatched()] couldn't be dispatched after all -- the server is too busy
+    pub fn on_rejected(&self, address: SocketAddr) {
+        self.update(address, |stats| {
+            stats.processing.pop_back();
+            stats.errors += 1;
+        });
+    }
+
+    pub fn on_message_out(&self, address: SocketAddr, bytes: usize) {
+        counter!(MESSAGES_METRIC, "direction" => "out").increment(1);
+        counter!(BYTES_METRIC, "direction" => "out").increment(bytes as u64);
+        self.update(address, |stats| {
+            stats.messages_out += 1;
+            stats.bytes_out += bytes as u64;
+        });
+    }
+
+    /// a message exceeding the client's rate was refused -- also counted as an error
+    pub fn on_rate_limited(&self, address: SocketAddr) {
+        counter!(RATE_LIMITED_METRIC).increment(1);
+        self.on_error(address);
+    }
+
+    /// the client at `address` was found with too many answers pending -- also counted as an error
+    pub fn on_send_queue_overflow(&self, address: SocketAddr) {
+        counter!(SEND_QUEUE_OVERFLOWS_METRIC).increment(1);
+        self.on_error(address);
+    }
+
+    /// the client at `address` answered a keepalive, `round_trip` after it was sent
+    pub fn on_round_trip(&self, address: SocketAddr, round_trip: Duration) {
+        histogram!(ROUND_TRIP_METRIC).record(round_trip.as_secs_f64());
+        self.update(address, |stats| stats.round_trips.record(round_trip));
+    }
+
+    /// a connection was refused for being above the limit of clients -- not tracked, as it never got to be connected
+    pub fn on_refused_connection(&self) {
+        counter!(REFUSED_CONNECTIONS_METRIC).increment(1);
+    }
+
+    /// unknown messages, processor errors, sending errors or when the server is too busy
+    pub fn on_error(&self, address: SocketAddr) {
+        self.update(address, |stats| stats.errors += 1);
+    }
+
+    pub fn report(&self) -> SocketServerStatsReport {
+        let mut endpoints: Vec<EndpointStatsReport> = self.endpoints.lock().iter()
+            .map(|(address, stats)| EndpointStatsReport {
+                address:                        address.to_string(),
+                connected:                      stats.connected,
+                messages_in:                    stats.messages_in,
+                messages_out:                   stats.messages_out,
+                bytes_in:                       stats.bytes_in,
+                bytes_out:                      stats.bytes_out,
+                errors:                         stats.errors,
+                idle_seconds:                   stats.last_activity.elapsed().as_secs_f64(),
+                mean_processing_latency_micros: if stats.processed == 0 { 0.0 } else { stats.processing_time.as_secs_f64() * 1e6 / stats.processed as f64 },
+                round_trip_p50_micros:          stats.round_trips.percentile_micros(50.0),
+                round_trip_p95_micros:          stats.round_trips.percentile_micros(95.0),
+                round_trip_p99_micros:          stats.round_trips.percentile_micros(99.0),
+            })
+            .collect();
+        endpoints.sort_by(|a, b| a.address.cmp(&b.address));
+        SocketServerStatsReport {
+            max_tracked_endpoints: self.max_tracked_endpoints,
+            untracked_events:      self.untracked_events.load(Ordering::Relaxed),
+            endpoints,
+        }
+    }
+
+    /// applies `operation` to the stats of `address` -- if it is being tracked
+    fn update(&self, address: SocketAddr, operation: impl FnOnce(&mut EndpointStats)) {
+        if self.max_tracked_endpoints == 0 {
+            return
+        }
+        match self.endpoints.lock().get_mut(&address) {
+            Some(stats) => {
+                stats.last_activity = Instant::now();
+                operation(stats);
+            },
+            None => {
+                self.untracked_events.fetch_add(1, Ordering::Relaxed);
+            },
+        }
+    }
+}
+
+/// assures there is room for one more endpoint in `endpoints`, forgetting the disconnected one inactive for the longest time, if needed.
+/// Returns `false` if no room could be made
+fn make_room(endpoints: &mut HashMap<SocketAddr, EndpointStats>, max_tracked_endpoints: usize) -> bool {
+    if endpoints.len() < max_tracked_endpoints {
+        return true
+    }
+    let oldest_disconnected = endpoints.iter()
+        .filter(|(_address, stats)| !stats.connected)
+        .min_by_key(|(_address, stats)| stats.last_activity)
+        .map(|(address, _stats)| *address);
+    match oldest_disconnected {
+        Some(address) => {
+            endpoints.remove(&address);
+            true
+        },
+        None => false,
+    }
+}
+
+
+/// Unit tests the [stats](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn round_trip_percentiles() {
+        let mut round_trips = RoundTrips::default();
+        assert_eq!(round_trips.percentile(50.0), None, "No percentiles should be reported before any round trips are measured");
+        for millis in 1..=100 {
+            round_trips.record(Duration::from_millis(millis));
+        }
+        assert_eq!(round_trips.percentile(50.0), Some(Duration::from_millis(50)), "Wrong p50");
+        assert_eq!(round_trips.percentile(95.0), Some(Duration::from_millis(95)), "Wrong p95");
+        assert_eq!(round_trips.percentile(99.0), Some(Duration::from_millis(99)), "Wrong p99");
+        for _ in 0..ROUND_TRIP_SAMPLES {
+            round_trips.record(Duration::from_millis(1000));
+        }
+        assert_eq!(round_trips.len(), ROUND_TRIP_SAMPLES, "Only the most recent round trips should be kept");
+        assert_eq!(round_trips.percentile(50.0), Some(Duration::from_millis(1000)), "The oldest round trips should have been forgotten");
+    }
+}
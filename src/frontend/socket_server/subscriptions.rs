@@ -0,0 +1,204 @@
+//! Resumable push subscriptions -- see [SubscriptionsConfig]: clients `Subscribe` to topics of the event bus, having their events pushed
+//! as [ServerMessages::Pushed], numbered per subscription. The last ones are kept, so clients reconnecting within the resume window may
+//! `Resume { subscription_id, last_seq }` -- having what they missed replayed -- instead of resynchronizing by other means.\
+//! Kept by the socket server's `message-io` thread, which feeds them with the events of the subscribable topics & sends the pushes they return
+
+use crate::config::config::SubscriptionsConfig;
+use super::protocol::ServerMessages;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+
+/// A client's subscription to a topic
+struct Subscription<Client> {
+    topic:       String,
+    /// who subscribed -- only them may resume it. See [super::login]
+    principal:   Option<String>,
+    /// the client receiving the pushes -- `None` while it is disconnected
+    client:      Option<Client>,
+    /// when the client disconnected -- for expiring the subscription after the resume window
+    detached_at: Option<Instant>,
+    /// the sequence number of the last push
+    last_seq:    u64,
+    /// the last pushes -- their sequence numbers & payloads, the oldest first. See [SubscriptionsConfig::buffer_size]
+    buffer:      VecDeque<(u64, String)>,
+}
+
+/// The subscriptions of the socket server's clients -- identified by `Client` (their `message-io` endpoints)
+pub struct Subscriptions<Client> {
+    /// `None` if subscriptions are disabled -- see [crate::config::SocketServerConfig::subscriptions]
+    config:        Option<SubscriptionsConfig>,
+    subscriptions: HashMap<u64, Subscription<Client>>,
+}
+
+impl<Client: Copy + PartialEq> Subscriptions<Client> {
+
+    pub fn new(config: Option<SubscriptionsConfig>) -> Self {
+        Self {
+            config,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// answers the `Subscribe` of `client` (logged in as `principal`) to `topic`
+    pub fn subscribe(&mut self, client: Client, principal: Option<String>, topic: String, now: Instant) -> ServerMessages {
+        let Some(config) = &self.config else {
+            return ServerMessages::SubscriptionRefused(String::from("Subscriptions are disabled in this server"))
+        };
+        if !config.topics.contains(&topic) {
+            return ServerMessages::SubscriptionRefused(format!("'{}' may not be subscribed to", topic))
+        }
+        self.expire(now);
+        // random ids, so they can't be guessed by other clients trying to resume them
+        let subscription_id = loop {
+            let subscription_id = rand::random::<u64>();
+            if !self.subscriptions.contains_key(&subscription_id) {
+                break subscription_id
+            }
+        };
+        self.subscriptions.insert(subscription_id, Subscription {
+            topic:       topic.clone(),
+            principal,
+            client:      Some(client),
+            detached_at: None,
+            last_seq:    0,
+            buffer:      VecDeque::new(),
+        });
+        ServerMessages::Subscribed { subscription_id, topic }
+    }
+
+    /// answers the `Resume` of `client` (logged in as `principal`): [ServerMessages::Subscribed], followed by the pushes after `last_seq`
+    /// -- or [ServerMessages::SubscriptionLost], if some of them are no longer kept. Resuming a subscription whose previous connection
+    /// wasn't noticed as closed yet moves it to the new one
+    pub fn resume(&mut self, client: Client, principal: Option<&str>, subscription_id: u64, last_seq: u64, now: Instant) -> Vec<ServerMessages> {
+        if self.config.is_none() {
+            return vec![ServerMessages::SubscriptionRefused(String::from("Subscriptions are disabled in this server"))]
+        }
+        self.expire(now);
+        let Some(subscription) = self.subscriptions.get_mut(&subscription_id)
+            .filter(|subscription| subscription.principal.as_deref() == principal) else {
+            return vec![ServerMessages::SubscriptionLost(subscription_id)]
+        };
+        let oldest_kept_seq = subscription.buffer.front().map_or(subscription.last_seq + 1, |(seq, _payload)| *seq);
+        if last_seq > subscription.last_seq || last_seq + 1 < oldest_kept_seq {
+            self.subscriptions.remove(&subscription_id);
+            return vec![ServerMessages::SubscriptionLost(subscription_id)]
+        }
+        subscription.client = Some(client);
+        subscription.detached_at = None;
+        let mut answers = vec![ServerMessages::Subscribed { subscription_id, topic: subscription.topic.clone() }];
+        answers.extend(subscription.buffer.iter()
+            .filter(|(seq, _payload)| *seq > last_seq)
+            .map(|(seq, payload)| ServerMessages::Pushed { subscription_id, seq: *seq, payload: payload.clone() }));
+        answers
+    }
+
+    /// pushes `payload` -- an event of `topic` -- to its subscriptions, returning the messages to be sent to their connected clients
+    pub fn publish(&mut self, topic: &str, payload: &str, now: Instant) -> Vec<(Client, ServerMessages)> {
+        let Some(buffer_size) = self.config.as_ref().map(|config| config.buffer_size) else {
+            return vec![]
+        };
+        self.expire(now);
+        let mut pushes = vec![];
+        for (subscription_id, subscription) in self.subscriptions.iter_mut().filter(|(_subscription_id, subscription)| subscription.topic == topic) {
+            subscription.last_seq += 1;
+            if buffer_size > 0 {
+                if subscription.buffer.len() >= buffer_size {
+                    subscription.buffer.pop_front();
+                }
+                subscription.buffer.push_back((subscription.last_seq, payload.to_string()));
+            }
+            if let Some(client) = subscription.client {
+                pushes.push((client, ServerMessages::Pushed { subscription_id: *subscription_id, seq: subscription.last_seq, payload: payload.to_string() }));
+            }
+        }
+        pushes
+    }
+
+    /// `client` disconnected: its subscriptions are kept for the resume window
+    pub fn detach(&mut self, client: Client, now: Instant) {
+        for subscription in self.subscriptions.values_mut().filter(|subscription| subscription.client == Some(client)) {
+            subscription.client = None;
+            subscription.detached_at = Some(now);
+        }
+    }
+
+    /// forgets the subscriptions whose clients didn't resume them within the window
+    fn expire(&mut self, now: Instant) {
+        let Some(config) = &self.config else { return };
+        let resume_window = Duration::from_millis(config.resume_window_millis);
+        self.subscriptions.retain(|_subscription_id, subscription| subscription.detached_at
+            .map_or(true, |detached_at| now.saturating_duration_since(detached_at) < resume_window));
+    }
+}
+
+
+/// Unit tests the [subscriptions](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    fn config(buffer_size: usize) -> Option<SubscriptionsConfig> {
+        Some(SubscriptionsConfig {
+            topics:               vec![String::from("orders")],
+            buffer_size,
+            resume_window_millis: 60_000,
+        })
+    }
+
+    fn subscribed_id(answer: &ServerMessages) -> u64 {
+        match answer {
+            ServerMessages::Subscribed { subscription_id, .. } => *subscription_id,
+            other => panic!("`Subscribed` was expected, but `{:?}` was answered", other),
+        }
+    }
+
+    /// assures events are pushed, numbered, to the connected subscribers -- & replayed to the ones resuming, from where they stopped
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn push_and_resume() {
+        let now = Instant::now();
+        let mut subscriptions = Subscriptions::<u32>::new(config(16));
+        let subscription_id = subscribed_id(&subscriptions.subscribe(1, None, String::from("orders"), now));
+        assert_eq!(subscriptions.publish("payments", "{}", now), vec![], "Other topics shouldn't be pushed");
+        let pushes: Vec<_> = (1..=3).flat_map(|event| subscriptions.publish("orders", &event.to_string(), now)).collect();
+        assert_eq!(pushes, (1..=3).map(|seq| (1, ServerMessages::Pushed { subscription_id, seq, payload: seq.to_string() })).collect::<Vec<_>>(),
+                   "Each event should have been pushed, in sequence");
+
+        subscriptions.detach(1, now);
+        assert_eq!(subscriptions.publish("orders", "4", now), vec![], "Disconnected clients shouldn't be pushed to");
+        subscriptions.publish("orders", "5", now);
+        let answers = subscriptions.resume(2, None, subscription_id, 3, now + Duration::from_secs(30));
+        assert_eq!(answers, vec![
+            ServerMessages::Subscribed { subscription_id, topic: String::from("orders") },
+            ServerMessages::Pushed { subscription_id, seq: 4, payload: String::from("4") },
+            ServerMessages::Pushed { subscription_id, seq: 5, payload: String::from("5") },
+        ], "The missed events should have been replayed after resuming");
+        assert_eq!(subscriptions.publish("orders", "6", now), vec![(2, ServerMessages::Pushed { subscription_id, seq: 6, payload: String::from("6") })],
+                   "The pushes should go to the new connection, continuing the sequence");
+    }
+
+    /// assures subscriptions can't be resumed when what was missed is no longer kept, after the window or by others
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn lost_subscriptions() {
+        let now = Instant::now();
+        let mut subscriptions = Subscriptions::<u32>::new(config(2));
+        assert!(matches!(subscriptions.subscribe(1, None, String::from("alerts"), now), ServerMessages::SubscriptionRefused(_)), "Topics not configured should be refused");
+        assert!(matches!(Subscriptions::<u32>::new(None).subscribe(1, None, String::from("orders"), now), ServerMessages::SubscriptionRefused(_)), "Disabled subscriptions should be refused");
+
+        let subscription_id = subscribed_id(&subscriptions.subscribe(1, Some(String::from("alice")), String::from("orders"), now));
+        subscriptions.detach(1, now);
+        (1..=3).for_each(|event| { subscriptions.publish("orders", &event.to_string(), now); });
+        assert_eq!(subscriptions.resume(2, Some("mallory"), subscription_id, 3, now), vec![ServerMessages::SubscriptionLost(subscription_id)], "Only the subscriber may resume it");
+        assert_eq!(subscriptions.resume(2, Some("alice"), subscription_id, 0, now), vec![ServerMessages::SubscriptionLost(subscription_id)],
+                   "Pushes no longer kept (the 1st) can't be replayed");
+        assert_eq!(subscriptions.resume(2, Some("alice"), subscription_id, 3, now), vec![ServerMessages::SubscriptionLost(subscription_id)], "Lost subscriptions should be forgotten");
+
+        let subscription_id = subscribed_id(&subscriptions.subscribe(3, None, String::from("orders"), now));
+        subscriptions.detach(3, now);
+        assert_eq!(subscriptions.resume(4, None, subscription_id, 0, now + Duration::from_secs(60)), vec![ServerMessages::SubscriptionLost(subscription_id)],
+                   "Subscriptions should expire after the resume window");
+    }
+}
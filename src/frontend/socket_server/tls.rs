@@ -0,0 +1,79 @@
+//! TLS termination for the socket server -- see [crate::config::SocketTransport::Tls].
+//!
+//! As `message-io` only speaks plain TCP, TLS is terminated in-process: the configured interface & port are listened to by [terminate()],
+//! which decrypts each connection & hands it to `message-io` -- listening on an ephemeral loopback port -- as plain text.
+//! So the protocol & processors are the very same for both transports.\
+//! That plain listener is reachable by any local process, so only the hops made by [terminate()] are served: connections not registered
+//! in [BridgedPeers] are refused as soon as they are accepted -- so local users can't skip TLS. The loopback connections' addresses are
+//! mapped back to the real clients' as well, so statistics, bans, egress limits & logs refer to the clients -- not to `127.0.0.1`.
+//!
+//! TODO 2026-10-16: terminate TLS directly on the connections, dropping the loopback hop, once `message-io` is replaced by our Tokio version.
+
+use super::bridge::BridgedPeers;
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    sync::oneshot,
+};
+use tokio_native_tls::{native_tls, TlsAcceptor};
+use log::{debug, info, warn};
+
+
+/// How long clients have to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// builds the TLS acceptor for the PEM `cert_file` & `key_file`
+pub fn acceptor(cert_file: &str, key_file: &str) -> Result<TlsAcceptor, Box<dyn std::error::Error + Send + Sync>> {
+    let cert = std::fs::read(cert_file)
+        .map_err(|err| format!("Could not read the socket server's TLS certificate '{}': {}", cert_file, err))?;
+    let key = std::fs::read(key_file)
+        .map_err(|err| format!("Could not read the socket server's TLS key '{}': {}", key_file, err))?;
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .map_err(|err| format!("Invalid socket server's TLS certificate '{}' or key '{}': {}", cert_file, key_file, err))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity)
+        .map_err(|err| format!("Could not build the socket server's TLS acceptor: {}", err))?;
+    Ok(TlsAcceptor::from(acceptor))
+}
+
+/// Accepts TLS connections on `listener`, forwarding them -- decrypted -- to the plain `message-io` listener, whose address is
+/// sent through `internal_addr` once it is bound. Runs until aborted.
+pub async fn terminate(listener: TcpListener, acceptor: TlsAcceptor, internal_addr: oneshot::Receiver<SocketAddr>, peers: Arc<BridgedPeers>) {
+    let Ok(internal_addr) = internal_addr.await else {
+        warn!("Socket Server: the plain listener was not started -- TLS connections won't be accepted");
+        return
+    };
+    info!("Socket Server: terminating TLS at {}, forwarding to {}", listener.local_addr().map_or_else(|err| err.to_string(), |addr| addr.to_string()), internal_addr);
+    let acceptor = Arc::new(acceptor);
+    loop {
+        let (tcp_stream, client_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                warn!("Socket Server: error accepting a TLS connection: {}", err);
+                continue
+            },
+        };
+        let acceptor = Arc::clone(&acceptor);
+        let peers = Arc::clone(&peers);
+        tokio::spawn(async move {
+            let mut tls_stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp_stream)).await {
+                Ok(Ok(tls_stream)) => tls_stream,
+                Ok(Err(err)) => return debug!("Socket Server: TLS handshake with {} failed: {}", client_addr, err),
+                Err(_elapsed) => return debug!("Socket Server: TLS handshake with {} timed out", client_addr),
+            };
+            match peers.connect(internal_addr, Some(client_addr)).await {
+                Ok((mut plain_stream, hop)) => {
+                    if let Err(err) = tokio::io::copy_bidirectional(&mut tls_stream, &mut plain_stream).await {
+                        debug!("Socket Server: TLS connection with {} ended: {}", client_addr, err);
+                    }
+                    hop.release().await;
+                },
+                Err(err) => warn!("Socket Server: could not forward the TLS client {} to {}: {}", client_addr, internal_addr, err),
+            }
+        });
+    }
+}
@@ -0,0 +1,89 @@
+//! Unix domain sockets for the socket server -- see [crate::config::SocketTransport::Unix] -- for IPC with clients on the same host.
+//!
+//! As `message-io` only speaks TCP & UDP, the socket file is listened to by [spawn_bridge()], which hands each connection to `message-io`
+//! -- listening on an ephemeral loopback port -- just like the TLS terminator does (see [super::tls]). So the protocol & processors are
+//! the very same for all transports.\
+//! That plain listener is reachable by any local process, so only the hops made by the bridge are served: connections not registered
+//! in [BridgedPeers] are refused as soon as they are accepted -- so access is, indeed, controlled by the socket file's permissions.\
+//! Clients are known by the addresses of their loopback hops -- one per connection. As they all share its IP, they are never banned:
+//! the abusive ones are just disconnected.
+//!
+//! TODO 2026-10-16: listen to the socket file directly, dropping the loopback hop, once `message-io` is replaced by our Tokio version.
+
+use super::bridge::BridgedPeers;
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+};
+use tokio::{
+    sync::oneshot,
+    task::JoinHandle,
+};
+use log::warn;
+
+
+/// Listens to `socket_file` -- replacing a stale one, left by an instance that didn't shut down gracefully -- forwarding its connections to
+/// the plain `message-io` listener, whose address is sent through `internal_addr` once it is bound -- registering the hops in `peers`.
+/// The returned task runs until aborted
+#[cfg(unix)]
+pub fn spawn_bridge(socket_file: &str, internal_addr: oneshot::Receiver<SocketAddr>, peers: Arc<BridgedPeers>) -> Result<JoinHandle<()>, Box<dyn std::error::Error + Send + Sync>> {
+    use std::os::unix::fs::FileTypeExt;
+    if let Ok(metadata) = std::fs::symlink_metadata(socket_file) {
+        if !metadata.file_type().is_socket() {
+            return Err(Box::from(format!("Socket Server: '{}' exists & is not a socket -- refusing to replace it", socket_file)))
+        }
+        if std::os::unix::net::UnixStream::connect(socket_file).is_ok() {
+            return Err(Box::from(format!("Socket Server: '{}' is being listened to -- is another instance running?", socket_file)))
+        }
+        warn!("Socket Server: replacing the stale socket file '{}'", socket_file);
+        remove_socket_file(socket_file);
+    }
+    let listener = tokio::net::UnixListener::bind(socket_file)
+        .map_err(|err| format!("Socket Server: cannot listen to the Unix socket '{}': {}", socket_file, err))?;
+    Ok(tokio::spawn(bridge(listener, socket_file.to_string(), internal_addr, peers)))
+}
+
+/// Unix sockets are, well, Unix only
+#[cfg(not(unix))]
+pub fn spawn_bridge(socket_file: &str, _internal_addr: oneshot::Receiver<SocketAddr>, _peers: Arc<BridgedPeers>) -> Result<JoinHandle<()>, Box<dyn std::error::Error + Send + Sync>> {
+    Err(Box::from(format!("Socket Server: cannot listen to '{}' -- Unix sockets are only available on Unix", socket_file)))
+}
+
+/// removes `socket_file` -- once the server is no longer listening to it
+pub fn remove_socket_file(socket_file: &str) {
+    if let Err(err) = std::fs::remove_file(socket_file) {
+        warn!("Socket Server: could not remove the socket file '{}': {}", socket_file, err);
+    }
+}
+
+/// see [spawn_bridge()]
+#[cfg(unix)]
+async fn bridge(listener: tokio::net::UnixListener, socket_file: String, internal_addr: oneshot::Receiver<SocketAddr>, peers: Arc<BridgedPeers>) {
+    use log::{debug, info};
+    let Ok(internal_addr) = internal_addr.await else {
+        warn!("Socket Server: the plain listener was not started -- Unix socket connections won't be accepted");
+        return
+    };
+    info!("Socket Server: listening to the Unix socket '{}', forwarding to {}", socket_file, internal_addr);
+    loop {
+        let mut unix_stream = match listener.accept().await {
+            Ok((unix_stream, _unnamed_addr)) => unix_stream,
+            Err(err) => {
+                warn!("Socket Server: error accepting a Unix socket connection: {}", err);
+                continue
+            },
+        };
+        let peers = Arc::clone(&peers);
+        tokio::spawn(async move {
+            match peers.connect(internal_addr, None).await {
+                Ok((mut plain_stream, hop)) => {
+                    if let Err(err) = tokio::io::copy_bidirectional(&mut unix_stream, &mut plain_stream).await {
+                        debug!("Socket Server: Unix socket connection ended: {}", err);
+                    }
+                    hop.release().await;
+                },
+                Err(err) => warn!("Socket Server: could not forward a Unix socket client to {}: {}", internal_addr, err),
+            }
+        });
+    }
+}
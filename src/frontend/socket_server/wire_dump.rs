@@ -0,0 +1,228 @@
+//! Wire-level dumping of the socket server's traffic -- see [crate::config::SocketServerConfig::wire_dump].
+//!
+//! Each frame is written, as received from (or sent to) the network, both escaped and as an hex dump -- so
+//! framing & serialization issues with the RON/bincode formats may be spotted without a packet sniffer.
+//!
+//! Operators pause, resume & retarget it through the admin API: `GET /api/admin/socket/wire-dump`, `PUT /api/admin/socket/wire-dump/active`
+//! & `PUT /api/admin/socket/wire-dump/endpoints` -- see [crate::frontend::web::admin].
+
+use crate::config::{ExtendedOption, WireDumpConfig};
+use std::{
+    fmt::Write as _,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    net::SocketAddr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use parking_lot::{Mutex, RwLock};
+use log::{info, warn, error};
+
+
+/// How many bytes to show in each line of the hex dump
+const BYTES_PER_LINE: usize = 16;
+
+
+/// The direction of a dumped frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// Dumps the frames exchanged with the clients selected in [WireDumpConfig] to a rotating capture file
+pub struct WireDump {
+    /// `None` if dumping was not configured -- in which case it may not be activated
+    config:    Option<WireDumpConfig>,
+    active:    AtomicBool,
+    /// which client addresses to dump -- empty for all
+    endpoints: RwLock<Vec<String>>,
+    /// the opened capture file & its current size
+    file:      Mutex<Option<(File, u64)>>,
+}
+
+impl WireDump {
+
+    pub fn new(wire_dump_config: &ExtendedOption<WireDumpConfig>) -> Self {
+        let config = match wire_dump_config {
+            ExtendedOption::Enabled(config) => Some(config.clone()),
+            _ => None,
+        };
+        let instance = Self {
+            active:    AtomicBool::new(false),
+            endpoints: RwLock::new(config.as_ref().map_or(vec![], |config| config.endpoints.clone())),
+            file:      Mutex::new(None),
+            config,
+        };
+        if let Some(config) = &instance.config {
+            instance.set_active(config.active);
+        }
+        instance
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// if dumping was configured -- otherwise, it may not be activated
+    pub fn is_configured(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// the client addresses being dumped -- empty for all
+    pub fn endpoints(&self) -> Vec<String> {
+        self.endpoints.read().clone()
+    }
+
+    /// pauses or resumes dumping -- which may only be resumed if it was configured
+    pub fn set_active(&self, active: bool) {
+        let Some(config) = &self.config else {
+            if active {
+                warn!("Socket Server: wire dumping was asked to be activated, but it is not configured -- see `wire_dump` in the config file");
+            }
+            return
+        };
+        if self.active.swap(active, Ordering::Relaxed) != active {
+            if active {
+                info!("Socket Server: wire dumping is now ACTIVE, writing to '{}' -- endpoints: {:?}", config.capture_file, self.endpoints.read());
+            } else {
+                info!("Socket Server: wire dumping is now paused");
+                // closes the capture file, so it may be inspected or removed
+                self.file.lock().take();
+            }
+        }
+    }
+
+    /// restricts dumping to the given client addresses -- either IPs or IPs & ports. Empty for all traffic
+    pub fn set_endpoints(&self, endpoints: Vec<String>) {
+        info!("Socket Server: wire dumping endpoints set to {:?}", endpoints);
+        *self.endpoints.write() = endpoints;
+    }
+
+    /// dumps `frame` if dumping is active & `address` is one of the selected endpoints
+    pub fn dump(&self, direction: Direction, address: SocketAddr, frame: &[u8]) {
+        if !self.is_active() || !self.is_selected(address) {
+            return
+        }
+        let entry = format_entry(direction, address, frame);
+        if let Err(err) = self.write(entry.as_bytes()) {
+            error!("Socket Server: wire dumping failed -- pausing it: {}", err);
+            self.set_active(false);
+        }
+    }
+
+    fn is_selected(&self, address: SocketAddr) -> bool {
+        let endpoints = self.endpoints.read();
+        endpoints.is_empty() || endpoints.iter()
+            .any(|endpoint| *endpoint == address.to_string() || *endpoint == address.ip().to_string())
+    }
+
+    /// appends `entry` to the capture file, rotating it if it would exceed the configured size
+    fn write(&self, entry: &[u8]) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let config = self.config.as_ref().expect("BUG! wire dumping was activated without being configured");
+        let mut file = self.file.lock();
+        if let Some((_file, size)) = &*file {
+            if *size > 0 && size + entry.len() as u64 > config.max_file_size {
+                file.take();
+                rotate(config)?;
+            }
+        }
+        if file.is_none() {
+            let opened = OpenOptions::new().create(true).append(true).open(&config.capture_file)
+                .map_err(|err| format!("could not open the capture file '{}': {}", config.capture_file, err))?;
+            let size = opened.metadata()
+                .map_err(|err| format!("could not stat the capture file '{}': {}", config.capture_file, err))?
+                .len();
+            *file = Some((opened, size));
+        }
+        let (opened, size) = file.as_mut().expect("BUG! the capture file was just opened");
+        opened.write_all(entry)
+            .map_err(|err| format!("could not write to the capture file '{}': {}", config.capture_file, err))?;
+        *size += entry.len() as u64;
+        Ok(())
+    }
+}
+
+/// shifts the capture files -- `file.1` becomes `file.2`, ... -- deleting the oldest one
+fn rotate(config: &WireDumpConfig) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let rotated = |n: u32| format!("{}.{}", config.capture_file, n);
+    if config.max_rotated_files == 0 {
+        return fs::remove_file(&config.capture_file)
+            .map_err(|err| Box::from(format!("could not remove the full capture file '{}': {}", config.capture_file, err)))
+    }
+    let _ = fs::remove_file(rotated(config.max_rotated_files));
+    for n in (1..config.max_rotated_files).rev() {
+        let _ = fs::rename(rotated(n), rotated(n+1));
+    }
+    fs::rename(&config.capture_file, rotated(1))
+        .map_err(|err| Box::from(format!("could not rotate the capture file '{}': {}", config.capture_file, err)))
+}
+
+/// renders `frame` as a header line, followed by its escaped contents & hex dump
+fn format_entry(direction: Direction, address: SocketAddr, frame: &[u8]) -> String {
+    let mut entry = format!("{} {} {} {} bytes\n",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.6f"),
+                            match direction { Direction::Inbound => "<<< from", Direction::Outbound => ">>> to  " },
+                            address,
+                            frame.len());
+    let _ = writeln!(entry, "  \"{}\"", frame.escape_ascii());
+    for (line, chunk) in frame.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(entry, "  {:08x} ", line * BYTES_PER_LINE);
+        for i in 0..BYTES_PER_LINE {
+            match chunk.get(i) {
+                Some(byte) => { let _ = write!(entry, " {:02x}", byte); },
+                None => entry.push_str("   "),
+            }
+        }
+        entry.push_str("  |");
+        entry.extend(chunk.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }));
+        entry.push_str("|\n");
+    }
+    entry.push('\n');
+    entry
+}
+
+
+/// Unit tests the [wire_dump](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    fn config(capture_file: &str, endpoints: Vec<String>) -> ExtendedOption<WireDumpConfig> {
+        ExtendedOption::Enabled(WireDumpConfig {
+            active: true,
+            endpoints,
+            capture_file: capture_file.to_string(),
+            max_file_size: 1024,
+            max_rotated_files: 2,
+        })
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn frames_are_escaped_and_hex_dumped() {
+        let entry = format_entry(Direction::Inbound, "127.0.0.1:4321".parse().unwrap(), b"Ping\n\x01");
+        assert!(entry.contains("<<< from 127.0.0.1:4321 6 bytes\n"), "Unexpected header in {:?}", entry);
+        assert!(entry.contains("  \"Ping\\n\\x01\"\n"), "Unexpected escaping in {:?}", entry);
+        assert!(entry.contains("  00000000  50 69 6e 67 0a 01 "), "Unexpected hex dump in {:?}", entry);
+        assert!(entry.contains("|Ping..|\n"), "Unexpected ascii column in {:?}", entry);
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn only_selected_endpoints_are_dumped_and_files_are_rotated() {
+        let capture_file = format!("/tmp/kickass-wire-dump-test-{}.txt", std::process::id());
+        let wire_dump = WireDump::new(&config(&capture_file, vec!["10.0.0.7".to_string()]));
+        for _ in 0..100 {
+            wire_dump.dump(Direction::Inbound,  "10.0.0.7:1000".parse().unwrap(), b"Selected");
+            wire_dump.dump(Direction::Outbound, "10.0.0.8:1000".parse().unwrap(), b"Ignored");
+        }
+        wire_dump.set_active(false);
+        let current = fs::read_to_string(&capture_file).expect("the capture file should exist");
+        assert!(current.contains("Selected") && !current.contains("Ignored"), "Only the selected endpoint should have been dumped");
+        assert!(current.len() <= 1024, "The capture file should have been rotated before exceeding its max size");
+        assert!(fs::metadata(format!("{}.2", capture_file)).is_ok(), "Rotated files should have been kept");
+        assert!(fs::metadata(format!("{}.3", capture_file)).is_err(), "Older rotated files should have been deleted");
+        for file in [capture_file.clone(), format!("{}.1", capture_file), format!("{}.2", capture_file)] {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
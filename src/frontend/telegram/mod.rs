@@ -5,4 +5,12 @@
 //!     -- please refer to [crate::command_line] for info on the `Query` command
 
 mod telegram;
-pub use telegram::*;
\ No newline at end of file
+pub use telegram::*;
+mod throttling;
+pub use throttling::CommandThrottleReport;
+mod splitting;
+pub use splitting::adjust_message;
+mod notifier;
+pub use notifier::TelegramNotifier;
+mod status_report;
+pub use status_report::{status_report, STATUS_REPORT_JOB};
@@ -0,0 +1,126 @@
+//! Push messages (MTs) from the business logic -- always available through [crate::runtime::Runtime::telegram_notifier], even while
+//! the Telegram service is still starting (or if it never does).
+//!
+//! Messages are queued & flushed -- through the primary bot, see [crate::config::ServicesConfig::telegram] -- once its [super::TelegramUI] starts, respecting Telegram's limits -- see [TelegramMtLimits]:
+//! ```no_compile
+//!     if let Some(telegram_notifier) = &runtime.telegram_notifier {
+//!         telegram_notifier.notify("<b>Disk almost full</b>", true);
+//!     }
+//! ```
+
+use super::{MtSender, adjust_message};
+use crate::config::TelegramMtLimits;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    time::Instant,
+};
+use teloxide::RequestError;
+use log::{debug, warn};
+
+
+/// A queued push message -- to all the notification chats, as of the time it is sent. See [crate::config::TelegramConfig::notification_chat_ids]
+struct Notification {
+    message: String,
+    html:    bool,
+}
+
+/// Queues the business logic's push messages until the Telegram service starts, then sends them -- paced by [TelegramMtLimits]
+pub struct TelegramNotifier {
+    limits:   TelegramMtLimits,
+    queue:    parking_lot::Mutex<VecDeque<Notification>>,
+    /// wakes the flusher when a message is queued
+    enqueued: Notify,
+    /// set once the flusher is running -- see [Self::attach()]
+    attached: AtomicBool,
+}
+
+impl TelegramNotifier {
+
+    pub fn new(limits: &TelegramMtLimits) -> Self {
+        Self {
+            limits:   limits.clone(),
+            queue:    parking_lot::Mutex::new(VecDeque::new()),
+            enqueued: Notify::new(),
+            attached: AtomicBool::new(false),
+        }
+    }
+
+    /// queues `message` to all the notification chats -- dropping the oldest queued message if [TelegramMtLimits::max_queued_messages] is reached
+    pub fn notify(&self, message: impl Into<String>, html: bool) {
+        let notification = Notification { message: message.into(), html };
+        {
+            let mut queue = self.queue.lock();
+            if queue.len() >= self.limits.max_queued_messages.max(1) {
+                if let Some(dropped) = queue.pop_front() {
+                    warn!("Telegram Notifier: queue is full ({} messages) -- dropping the oldest one: '{}'", queue.len()+1, dropped.message);
+                }
+            }
+            queue.push_back(notification);
+        }
+        self.enqueued.notify_one();
+    }
+
+    /// starts flushing the queued messages through `mt_sender` -- to be called once the Telegram service is running.
+    /// Subsequent calls are ignored
+    pub fn attach(self: &Arc<Self>, mt_sender: MtSender) {
+        if self.attached.swap(true, Ordering::Relaxed) {
+            return
+        }
+        let queued = self.queue.lock().len();
+        debug!("Telegram Notifier: attached to the Telegram service -- {} message(s) were waiting", queued);
+        tokio::spawn(Arc::clone(self).flush(mt_sender));
+    }
+
+    /// sends the queued messages, forever, respecting [TelegramMtLimits] & Telegram's "retry after" answers.\
+    /// Messages are sent in order: one waiting for its chat's interval holds the ones behind it.
+    async fn flush(self: Arc<Self>, mt_sender: MtSender) {
+        let min_message_interval = Duration::from_secs(1) / self.limits.max_messages_per_second.max(1);
+        let min_chat_interval = Duration::from_millis(self.limits.min_chat_interval_millis);
+        let mut last_sent: Option<Instant> = None;
+        let mut last_sent_per_chat: HashMap<i64, Instant> = HashMap::new();
+        loop {
+            let notification = self.queue.lock().pop_front();
+            let Some(notification) = notification else {
+                self.enqueued.notified().await;
+                continue
+            };
+            let parts = adjust_message(&notification.message, notification.html);
+            let deliveries: Vec<_> = mt_sender.notification_chat_ids().into_iter()
+                .flat_map(|chat_id| parts.iter().map(move |part| (chat_id, part)))
+                .collect();
+            for (chat_id, part) in deliveries {
+                let ready_at = [
+                    last_sent.map(|last_sent| last_sent + min_message_interval),
+                    last_sent_per_chat.get(&chat_id).map(|last_sent| *last_sent + min_chat_interval),
+                ].into_iter().flatten().max();
+                if let Some(ready_at) = ready_at {
+                    tokio::time::sleep_until(ready_at).await;
+                }
+                loop {
+                    match mt_sender.send_part(chat_id, part, notification.html).await {
+                        Err(RequestError::RetryAfter(retry_after)) => {
+                            warn!("Telegram Notifier: Telegram asked to slow down -- retrying the message to #{} in {:?}", chat_id, retry_after);
+                            tokio::time::sleep(retry_after).await;
+                        },
+                        Err(err) => {
+                            warn!("Telegram Notifier: error sending push message '{}' to #{} -- dropping it: {}", part, chat_id, err);
+                            break
+                        },
+                        Ok(()) => break,
+                    }
+                }
+                let now = Instant::now();
+                last_sent = Some(now);
+                last_sent_per_chat.insert(chat_id, now);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Splits messages exceeding Telegram's size limit into several, well-formed, ones -- see [adjust_message()]
+
+use std::borrow::Cow;
+
+
+/// Telegram refuses messages larger than this -- in characters, after the HTML is parsed. As we measure in bytes, we are on the safe side
+pub const TELEGRAM_MAX_MESSAGE_SIZE: usize = 4096;
+
+/// Longest HTML entity we recognize (like `&quot;`) -- `&`s not followed by `;` within it are taken as text
+const MAX_ENTITY_SIZE: usize = 10;
+
+
+/// Adjusts `message` to Telegram's limits, returning the parts to be sent -- in order. Messages within the limits are returned as they are.\
+/// Parts are cut, preferably, after line breaks or spaces. For `html` messages, tags & entities are never cut and the tags open at
+/// each cut are closed at the end of the part & reopened at the beginning of the next one -- so each part is well-formed on its own
+pub fn adjust_message(message: &str, html: bool) -> Vec<Cow<'_, str>> {
+    if message.len() <= TELEGRAM_MAX_MESSAGE_SIZE {
+        vec![Cow::Borrowed(message)]
+    } else if html {
+        split_html(message, TELEGRAM_MAX_MESSAGE_SIZE).into_iter().map(Cow::Owned).collect()
+    } else {
+        split_plain(message, TELEGRAM_MAX_MESSAGE_SIZE).into_iter().map(Cow::Borrowed).collect()
+    }
+}
+
+fn split_plain(mut text: &str, max_size: usize) -> Vec<&str> {
+    let mut parts = vec![];
+    while text.len() > max_size {
+        let cut = cut_position(text, max_size).max(first_char_size(text));
+        parts.push(&text[..cut]);
+        text = &text[cut..];
+    }
+    parts.push(text);
+    parts
+}
+
+fn split_html(message: &str, max_size: usize) -> Vec<String> {
+    let mut parts = vec![];
+    let mut part = String::new();
+    // the tags open at this point, as (opening tag, tag name)
+    let mut open_tags: Vec<(&str, &str)> = vec![];
+    // size of `part` without contents -- just with the reopened tags
+    let mut empty_part_size = 0;
+    let mut rest = message;
+    while !rest.is_empty() {
+        let closing_tags_size: usize = open_tags.iter().map(|(_opening_tag, name)| name.len() + 3).sum();
+        let budget = max_size.saturating_sub(part.len() + closing_tags_size);
+        let token_size = if rest.starts_with('<') {
+            rest.find('>').map_or(rest.len(), |end| end + 1)
+        } else if rest.starts_with('&') {
+            rest[..char_boundary(rest, MAX_ENTITY_SIZE)].find(';').map_or(1, |end| end + 1)
+        } else {
+            rest.find(|c| c == '<' || c == '&').unwrap_or(rest.len())
+        };
+        let (token, remaining) = rest.split_at(token_size);
+        if token.starts_with("</") {
+            if let Some(position) = open_tags.iter().rposition(|(_opening_tag, name)| *name == tag_name(token)) {
+                open_tags.truncate(position);
+            }
+            // closing tags were already accounted for in the budget
+            part.push_str(token);
+        } else if token.starts_with('<') {
+            let self_closing = token.ends_with("/>");
+            let needed_size = token.len() + if self_closing { 0 } else { tag_name(token).len() + 3 };
+            if needed_size > budget && part.len() > empty_part_size {
+                close_part(&mut parts, &mut part, &open_tags);
+                empty_part_size = part.len();
+                continue
+            }
+            part.push_str(token);
+            if !self_closing {
+                open_tags.push((token, tag_name(token)));
+            }
+        } else if token.starts_with('&') || token.len() <= budget {
+            if token.len() > budget && part.len() > empty_part_size {
+                close_part(&mut parts, &mut part, &open_tags);
+                empty_part_size = part.len();
+                continue
+            }
+            part.push_str(token);
+        } else {
+            // text too big for what is left of this part: fill it & go on with the rest of the text in the next one
+            let cut = match separator_cut(token, budget) {
+                Some(cut) => cut,
+                None if part.len() > empty_part_size => {
+                    close_part(&mut parts, &mut part, &open_tags);
+                    empty_part_size = part.len();
+                    continue
+                },
+                None => char_boundary(token, budget).max(first_char_size(token)),
+            };
+            part.push_str(&token[..cut]);
+            close_part(&mut parts, &mut part, &open_tags);
+            empty_part_size = part.len();
+            rest = &rest[cut..];
+            continue
+        }
+        rest = remaining;
+    }
+    if part.len() > empty_part_size || parts.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// closes the `open_tags` in `part`, moving it to `parts` -- then reopens them in the new `part`
+fn close_part(parts: &mut Vec<String>, part: &mut String, open_tags: &[(&str, &str)]) {
+    for (_opening_tag, name) in open_tags.iter().rev() {
+        part.push_str("</");
+        part.push_str(name);
+        part.push('>');
+    }
+    parts.push(std::mem::take(part));
+    for (opening_tag, _name) in open_tags {
+        part.push_str(opening_tag);
+    }
+}
+
+/// `b` for `<b>`, `</b>` & `a` for `<a href="...">`
+fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches('<').trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next().unwrap_or("")
+}
+
+/// where to cut `text` so its first part is, at most, `max_size` bytes long -- preferably after a line break or a space, if that
+/// doesn't make the part too short
+fn cut_position(text: &str, max_size: usize) -> usize {
+    let limit = char_boundary(text, max_size);
+    separator_cut(text, max_size)
+        .filter(|&cut| cut > limit / 2)
+        .unwrap_or(limit)
+}
+
+/// the position just after the last line break (or, if none, space) within the first `max_size` bytes of `text`
+fn separator_cut(text: &str, max_size: usize) -> Option<usize> {
+    let window = &text[..char_boundary(text, max_size)];
+    window.rfind('\n')
+        .or_else(|| window.rfind(' '))
+        .map(|separator| separator + 1)
+}
+
+/// the greatest character boundary of `text` up to `max_size`
+fn char_boundary(text: &str, max_size: usize) -> usize {
+    let mut boundary = max_size.min(text.len());
+    while !text.is_char_boundary(boundary) {
+        boundary -= 1;
+    }
+    boundary
+}
+
+fn first_char_size(text: &str) -> usize {
+    text.chars().next().map_or(0, char::len_utf8)
+}
+
+
+/// Unit tests the [splitting](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn long_messages_are_split() {
+        assert_eq!(split_plain("first line\nsecond line", 16), vec!["first line\n", "second line"], "Plain text should be cut after the line break");
+        assert_eq!(split_plain("çççç", 3), vec!["ç", "ç", "ç", "ç"], "UTF-8 characters shouldn't be cut");
+
+        let parts = split_html("<b>bold &amp; <i>italic words</i></b> plain", 24);
+        assert_eq!(parts, vec!["<b>bold &amp; </b>",
+                               "<b><i>italic </i></b>",
+                               "<b><i>words</i></b> ",
+                               "plain"], "Open tags should have been closed & reopened on each part");
+        assert!(parts.iter().all(|part| part.len() <= 24), "No part should exceed the limit");
+
+        let short = "<b>short</b>";
+        assert_eq!(adjust_message(short, true), vec![Cow::Borrowed(short)], "Messages within the limit should be kept as they are");
+        let long = "word ".repeat(2000);
+        let parts = adjust_message(&long, false);
+        assert_eq!(parts.len(), 3, "A 10000 bytes message should have been split in 3");
+        assert_eq!(parts.concat(), long, "No contents should have been lost");
+    }
+
+    /// assures a `&` followed by non-ASCII text (not an entity) doesn't get the HTML splitting to cut a character
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn ampersands_before_multibyte_characters() {
+        let message = format!("{}&ééééé tail", "x".repeat(5000));
+        let parts = adjust_message(&message, true);
+        assert_eq!(parts.concat(), message, "No contents should have been lost");
+        assert!(parts.iter().all(|part| part.len() <= TELEGRAM_MAX_MESSAGE_SIZE), "No part should exceed the limit");
+    }
+}
@@ -0,0 +1,57 @@
+//! Periodic status reports, sent to the notification chats -- see [crate::config::TelegramConfig::status_report_cron]: a summary of the
+//! runtime (uptime, switches, message counters & scheduled jobs) built out of the metrics registry & the scheduler.
+//!
+//! The reports are sent by the [crate::scheduler::Scheduler] job named [STATUS_REPORT_JOB] -- so, with leader election, only the leader sends them.
+
+use crate::runtime::{Runtime, metrics_report};
+use tokio::sync::RwLock;
+
+
+/// The name under which the reports are scheduled -- see `/api/stats/scheduler`
+pub const STATUS_REPORT_JOB: &str = "telegram-status-report";
+
+
+/// the status report, in HTML
+pub async fn status_report(runtime: &RwLock<Runtime>) -> String {
+    let runtime = runtime.read().await;
+    let mut lines = vec![format!("<b>{} status</b>", env!("CARGO_PKG_NAME"))];
+    lines.push(format!("Uptime: {}", format_uptime(runtime.started_at.elapsed().as_secs())));
+    lines.push(format!("Maintenance: {}", if runtime.degrade.maintenance() { "ON" } else { "off" }));
+    if let Some(metrics) = &runtime.metrics {
+        let message_counters: Vec<String> = metrics_report(metrics).into_iter()
+            .filter(|family| family.kind == "counter" && family.name.contains("messages"))
+            .map(|family| format!("  {}: {}", escape_html(&family.name), family.samples.iter().map(|sample| sample.value).sum::<f64>()))
+            .collect();
+        if !message_counters.is_empty() {
+            lines.push(String::from("<b>Messages</b>"));
+            lines.extend(message_counters);
+        }
+    }
+    if let Some(scheduler) = &runtime.scheduler {
+        lines.push(String::from("<b>Scheduled jobs</b>"));
+        for job in scheduler.report() {
+            let mut line = format!("  {}: {} runs, {} failures", escape_html(&job.name), job.runs, job.failures);
+            if let Some(last_error) = &job.last_error {
+                line.push_str(&format!(" -- last error: {}", escape_html(last_error)));
+            }
+            if let Some(next_run) = &job.next_run {
+                line.push_str(&format!(" -- next at {}", next_run));
+            }
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// like "2d 03:04:05"
+fn format_uptime(seconds: u64) -> String {
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    format!("{}d {:02}:{:02}:{:02}", days, seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+/// escapes the characters Telegram's HTML parse mode would take as markup
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
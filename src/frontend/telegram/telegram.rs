@@ -1,15 +1,27 @@
 //! see [super]
 
-use crate::config::{Config, TelegramConfig, TelegramBotOptions};
-use std::{
-    sync::Arc,
-    borrow::{Borrow, Cow},
+use super::{
+    throttling::{CommandThrottle, CommandThrottleReport},
+    notifier::TelegramNotifier,
+    adjust_message,
+};
+use crate::{
+    config::{Config, TelegramConfig, TelegramBotOptions, TelegramDialogueStorage},
+    runtime::{Runtime, DegradeSwitches, AuthProvider, OPERATOR_ROLE, correlated, new_correlation_id},
+    frontend::{Service, ServiceHealth, Runner},
+    logging::access_log::log_telegram_access,
+    logic::{UnitsConversion, ConversionDirection, dto::Exposed},
+};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
 };
 use owning_ref::OwningRef;
 use futures::{
     SinkExt,
     future::BoxFuture
 };
+use tokio::sync::RwLock;
 use teloxide::{
     prelude::*,
     utils::command::BotCommands,
@@ -18,10 +30,11 @@ use teloxide::{
     },
     dispatching::{
         DefaultKey,
-        dialogue::InMemStorage,
+        dialogue::{ErasedStorage, InMemStorage, RedisStorage, Storage, serializer::Json},
     },
 };
-use log::debug;
+use serde::{Serialize, Deserialize};
+use log::{debug, warn, error};
 
 
 /// prefix to all debug log messages, so to better contextualize them
@@ -36,83 +49,145 @@ const DEBUG_IDENT: &str = "      ";
 pub struct TelegramUI {
     /// runtime configs for our UI service
     telegram_config: OwningRef<Arc<Config>, TelegramConfig>,
+    /// starts as [TelegramConfig::notification_chat_ids] -- but may be replaced while running. See [Self::set_notification_chat_ids()]
+    notification_chat_ids: Arc<parking_lot::RwLock<Vec<i64>>>,
+    /// checked before sending push notifications -- see [DegradeSwitches::disable_telegram_notifications()]
+    degrade: Arc<DegradeSwitches>,
+    /// enforces the per-user [TelegramConfig::command_limits]
+    command_throttle: Arc<CommandThrottle>,
+    /// if present, tells who the operators are -- see [Operators]
+    auth: Option<Arc<dyn AuthProvider>>,
     /// Teloxide's bot
     bot: AutoSend<Bot>,
     /// Teloxide's dispatcher associated with [bot]
     dispatcher: Option<Dispatcher<AutoSend<Bot>, Box<dyn std::error::Error + Sync + Send>, DefaultKey>>,
     /// if present, exposes the Teloxide's `shutdown_token` through which one may request the service to cease running
     pub shutdown_token: Option<ShutdownToken>,
-    /// if set, may be used to send MTs to the Telegram Bot
-    _mt_hande: Option<bool>,
+    /// set once [Service::shutdown()] is called -- see [Service::health()]
+    shutdown_requested: AtomicBool,
+    /// sends the MTs -- handed to the [TelegramNotifier] once the service starts
+    mt_sender: MtSender,
+    /// if present, the queued MTs of the business logic will be sent once the service starts -- see [TelegramNotifier::attach()]
+    notifier: Option<Arc<TelegramNotifier>>,
+}
+
+/// Sends the MTs (Mobile Terminated messages) through the Telegram Bot -- cheaply cloneable, so the [TelegramNotifier] may use it
+/// while the [TelegramUI] is kept in the [Runtime]
+#[derive(Clone)]
+pub struct MtSender {
+    bot: AutoSend<Bot>,
+    /// checked before sending push notifications -- see [DegradeSwitches::disable_telegram_notifications()]
+    degrade: Arc<DegradeSwitches>,
+    /// shared with [TelegramUI] -- so changes are seen when broadcasting
+    notification_chat_ids: Arc<parking_lot::RwLock<Vec<i64>>>,
+}
+
+impl MtSender {
+
+    /// the chats currently receiving the notifications
+    pub fn notification_chat_ids(&self) -> Vec<i64> {
+        self.notification_chat_ids.read().clone()
+    }
+
+    /// sends a `part` of a message -- as returned by [adjust_message()] -- to the single `chat_id`,
+    /// unless notifications are disabled by the degradation switches
+    pub async fn send_part(&self, chat_id: i64, part: &str, html: bool) -> Result<(), teloxide::RequestError> {
+        if self.degrade.disable_telegram_notifications() {
+            debug!("{}Telegram notifications are disabled: discarding push message to #{}", DEBUG_IDENT, chat_id);
+            return Ok(())
+        }
+        let sender = self.bot.send_message::<ChatId, &str>(teloxide::types::ChatId(chat_id), part);
+        if html {
+            sender.parse_mode(teloxide::types::ParseMode::Html)
+                .send().await?;
+        } else {
+            sender.send().await?;
+        }
+        Ok(())
+    }
 }
 
 impl TelegramUI {
 
     /// call this foe with:
     /// ```no_compile
-    ///     if let ExtendedOption::Enabled(telegram_config) = &config.telegram {
-    ///         let shareable_telegram_controller = TelegramUI::new(telegram_config);
+    ///     for telegram_config in &config.telegram {
+    ///         let shareable_telegram_controller = TelegramUI::new(telegram_config, degrade_switches, auth_provider, telegram_notifier);
     ///         // this hypothetical function holds an Arc reference to the controller, from which any thread may request shutdown at any time
     ///         report_telegram_controller(shareable_telegram_controller.clone());
     ///         // the next call will run the service and block until shutdown is requested from elsewhere
     ///         shareable_telegram_controller.run_service().await?;
     ///         info!("Telegram service is DONE");
     ///     }
-    pub async fn new(telegram_config: OwningRef<Arc<Config>, TelegramConfig>) -> Self {
-        debug!("{}Instantiating 'teloxide' for bot token '{}'", DEBUG_IDENT, telegram_config.token);
+    pub async fn new(telegram_config: OwningRef<Arc<Config>, TelegramConfig>,
+                     degrade:         Arc<DegradeSwitches>,
+                     auth:            Option<Arc<dyn AuthProvider>>,
+                     notifier:        Option<Arc<TelegramNotifier>>)
+                    -> Self {
+        debug!("{}Instantiating 'teloxide' for the '{}' bot {:?}", DEBUG_IDENT, telegram_config.name, telegram_config.bot);
         let bot = Bot::new(&telegram_config.token).auto_send();
+        let command_throttle = Arc::new(CommandThrottle::new(&telegram_config.command_limits));
+        let notification_chat_ids = Arc::new(parking_lot::RwLock::new(telegram_config.notification_chat_ids.clone()));
+        let mt_sender = MtSender {
+            bot:                   bot.clone(),
+            degrade:               Arc::clone(&degrade),
+            notification_chat_ids: Arc::clone(&notification_chat_ids),
+        };
         let mut instance = Self {
             telegram_config,
+            notification_chat_ids,
+            degrade,
+            command_throttle,
+            auth,
             bot,
             dispatcher:     None,
             shutdown_token: None,
-            _mt_hande:       None,
+            shutdown_requested: AtomicBool::new(false),
+            mt_sender,
+            notifier,
         };
         instance.setup_bot().await;
         instance
     }
 
-    /// sends the `message` to all registered "chat ids"
-    pub async fn broadcast_message(&self, message: &str, html: bool) -> Result<(), Box<dyn std::error::Error>> {
-        for chat_id in &self.telegram_config.notification_chat_ids {
-            self.send_message(*chat_id, message, html).await?;
+    /// the name this bot is registered under, in the [Runtime] -- see [TelegramConfig::name]
+    pub fn bot_name(&self) -> &str {
+        &self.telegram_config.name
+    }
+
+    /// sends the `message` to all registered "chat ids" -- best-effort: messages that must not be lost should go through the
+    /// [crate::runtime::Outbox] instead
+    pub async fn broadcast_message(&self, message: &str, html: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let parts = adjust_message(message, html);
+        for chat_id in self.mt_sender.notification_chat_ids() {
+            for part in &parts {
+                self.mt_sender.send_part(chat_id, part, html).await
+                    .map_err(|err| format!("TelegramUI: error sending push message '{}' to #{}: {}", part, chat_id, err))?;
+            }
         }
         Ok(())
     }
 
-    /// sends the `message` to the single `chat_id`
-    pub async fn send_message(&self, chat_id: i64, message: &str, html: bool) -> Result<(), Box<dyn std::error::Error>> {
-        // TODO 2022-11-20 Maybe an API redesign should be done for the sake of efficiency: 'adjust_message(&str) -> &[Cow<&str>]' might be introduced
-        //                 to avoid the need of doing the following every time, in which case, this method should be reverted back to just sending
-        //                 the message. PS: `broadcast_message()` might be one example of a function calling adjust_message() and then send_message()
-        //                 as many times as needed. Note the bellow version only cuts the message and discards the rest of it, while on the proposed
-        //                 'adjust_message()', we'd split it into several parts. HTML would still be a challenge...
-        // adjust the message to telegram limits
-        const TELEGRAM_MAX_MESSAGE_SIZE: usize = 4096;
-        let mut message = Cow::Borrowed(message);
-        if message.len() > TELEGRAM_MAX_MESSAGE_SIZE {
-            // if the message is too big, cuts it down for sending, adding the '...' suffix to indicate there was a cut:
-            // for plain text, just add it; for HTML, preserve the last closing HTML tag as well, in order not to defecate formatting
-            let cutting_suffix = if !html {
-                format!("...")
-            }  else {
-                let last_closing_tag_pos = message.rfind("</").unwrap_or(message.len());
-                format!("...{}", &message[last_closing_tag_pos..])
-            };
-            message = Cow::Owned(format!("{}{}", &message[0..TELEGRAM_MAX_MESSAGE_SIZE -cutting_suffix.len()], cutting_suffix));
-        }
+    /// replaces the chats receiving the notifications (& acting as operators, when there is no [AuthProvider]) -- used by the config hot reload
+    pub fn set_notification_chat_ids(&self, chat_ids: Vec<i64>) {
+        *self.notification_chat_ids.write() = chat_ids;
+    }
 
-        let sender = self.bot.send_message::<ChatId, &str>(teloxide::types::ChatId(chat_id), message.borrow());
-        let result = if html {
-            sender.parse_mode(teloxide::types::ParseMode::Html)
-                .send().await
-        } else {
-            sender.send().await
-        };
-        result.map_err(|err| format!("TelegramUI: error sending push message '{}' to #{}: {}", message, chat_id, err))?;
+    /// sends the `message` to the single `chat_id`, in as many parts as needed (see [adjust_message()]) -- unless notifications are disabled
+    /// by the degradation switches
+    pub async fn send_message(&self, chat_id: i64, message: &str, html: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for part in adjust_message(message, html) {
+            self.mt_sender.send_part(chat_id, &part, html).await
+                .map_err(|err| format!("TelegramUI: error sending push message '{}' to #{}: {}", part, chat_id, err))?;
+        }
         Ok(())
     }
 
+    /// metrics on the commands accepted & refused due to [TelegramConfig::command_limits]
+    pub fn command_throttle_report(&self) -> CommandThrottleReport {
+        self.command_throttle.report()
+    }
+
     /// returns a runner, which you may call to run the telegram UI and that will only return when
     /// the service is over -- this special semantics allows holding the mutable reference to `self`
     /// as little as possible.\
@@ -139,7 +214,7 @@ impl TelegramUI {
         match self.telegram_config.bot {
             TelegramBotOptions::Dice       => (),//self.dice().await,             // returns with a Controller telling the service is not running but we're ready to send any MTs -- consider renaming this enum variant to 'SinkService'
             TelegramBotOptions::Stateless  => self.setup_query_ui_bot().await,    // starts the service able to perform query commands
-            TelegramBotOptions::Stateful   => ()//self.stateful_commands().await,
+            TelegramBotOptions::Stateful   => self.setup_stateful_bot().await,    // starts the dialogue bot, which keeps its dialogues in the configured storage
         }
     }
 
@@ -148,6 +223,9 @@ impl TelegramUI {
         let _listener = teloxide::dispatching::update_listeners::polling_default(self.bot.clone()).await;
 
         let dispatcher = Dispatcher::builder(self.bot.clone(), Update::filter_message().filter_command::<Commands>().chain(dptree::endpoint(handler)))
+            .dependencies(dptree::deps![Arc::clone(&self.command_throttle),
+                                        Arc::clone(&self.degrade),
+                                        Arc::new(Operators { chat_ids: Arc::clone(&self.notification_chat_ids), auth: self.auth.clone() })])
             .default_handler(ignore_update)
             .build();
         let shutdown_token = dispatcher.shutdown_token();
@@ -155,6 +233,68 @@ impl TelegramUI {
         self.shutdown_token = Some(shutdown_token);
     }
 
+    async fn setup_stateful_bot(&mut self) {
+        debug!("{}Opening the dialogue storage {:?}", DEBUG_IDENT, self.telegram_config.dialogue_storage);
+        let storage = open_dialogue_storage(&self.telegram_config.dialogue_storage).await;
+        let dispatcher = Dispatcher::builder(self.bot.clone(), Update::filter_message().enter_dialogue::<Message, ErasedStorage<State>, State>()
+            .branch(dptree::case![State::Start].endpoint(start))
+            .branch(dptree::case![State::ReceiveFullName].endpoint(receive_full_name))
+            .branch(dptree::case![State::ReceiveAge { full_name }].endpoint(receive_age))
+            .branch(dptree::case![State::ReceiveLocation { full_name, age }].endpoint(receive_location))
+        )
+            .dependencies(dptree::deps![storage])
+            .build();
+        let shutdown_token = dispatcher.shutdown_token();
+        self.dispatcher = Some(dispatcher);
+        self.shutdown_token = Some(shutdown_token);
+    }
+
+}
+
+impl Service for TelegramUI {
+
+    fn name(&self) -> &'static str {
+        "Telegram UI"
+    }
+
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>> {
+        let run_closure = self.runner();
+        Box::pin(async move {
+            if let Some(notifier) = &self.notifier {
+                notifier.attach(self.mt_sender.clone());
+            }
+            let runner: Runner = Box::pin(async move {
+                run_closure().await;
+                Ok(())
+            });
+            Ok(runner)
+        })
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.shutdown_requested.store(true, Ordering::Relaxed);
+            if let Some(shutdown_token) = self.shutdown_token.clone() {
+                shutdown_token.shutdown()
+                    .expect("Could not shutdown Telegram")
+                    .await;
+            }
+        })
+    }
+
+    fn health(&self) -> ServiceHealth {
+        if self.shutdown_requested.load(Ordering::Relaxed) {
+            ServiceHealth::ShuttingDown
+        } else if self.dispatcher.is_some() {
+            ServiceHealth::Starting
+        } else {
+            ServiceHealth::Running
+        }
+    }
+
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()> {
+        Box::pin(Runtime::register_telegram_ui(runtime, self))
+    }
 }
 
 // UI Business Rules
@@ -171,10 +311,53 @@ enum Commands {
     Username(String),
     #[command(description = "informs your desired username and and age", parse_with = "split")]
     UsernameAndAge { username: String, age: u8 },
+    #[command(description = "converts a temperature (°C) and a length (m) to the imperial system", parse_with = "split")]
+    ToImperial { celsius: f64, meters: f64 },
+    #[command(description = "(operators only) turns the maintenance mode `on` or `off`")]
+    Maintenance(String),
 }
 
-/// handler for the bot messages
-async fn handler(bot: AutoSend<Bot>, message: Message, command: Commands) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Who may issue operator commands: the users having the [OPERATOR_ROLE] (as "telegram:<user id>"), if [crate::config::Config::auth]
+/// is enabled -- or the chats receiving the notifications (see [TelegramConfig::notification_chat_ids]), otherwise
+struct Operators {
+    chat_ids: Arc<parking_lot::RwLock<Vec<i64>>>,
+    auth:     Option<Arc<dyn AuthProvider>>,
+}
+
+impl Operators {
+    async fn is_operator(&self, chat_id: i64, user_id: u64) -> bool {
+        match &self.auth {
+            Some(auth) => auth.lookup_roles(&format!("telegram:{}", user_id)).await
+                              .iter().any(|role| role == OPERATOR_ROLE),
+            None => self.chat_ids.read().contains(&chat_id),
+        }
+    }
+}
+
+/// handler for the bot messages -- each one traced by its own span & handled with a new correlation id in scope (see [correlated()])
+#[tracing::instrument(name = "telegram_update", skip_all, fields(chat_id = message.chat.id.0))]
+async fn handler(bot: AutoSend<Bot>, message: Message, command: Commands, command_throttle: Arc<CommandThrottle>, degrade: Arc<DegradeSwitches>, operators: Arc<Operators>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    correlated(Some(new_correlation_id()), handle_command(bot, message, command, command_throttle, degrade, operators)).await
+}
+
+/// see [handler()]
+async fn handle_command(bot: AutoSend<Bot>, message: Message, command: Commands, command_throttle: Arc<CommandThrottle>, degrade: Arc<DegradeSwitches>, operators: Arc<Operators>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let user_id = message.from().map_or(message.chat.id.0 as u64, |user| user.id.0);
+    let access = |outcome| log_telegram_access(message.chat.id.0, user_id, message.text().unwrap_or(""), outcome);
+    let verdict = command_throttle.check(user_id);
+    if let Some(reply) = verdict.reply() {
+        warn!("TelegramUI: refusing a command from user #{} (chat #{}): {:?}", user_id, message.chat.id, verdict);
+        access("throttled");
+        bot.send_message(message.chat.id, reply).await?;
+        return Ok(())
+    }
+    let is_operator = operators.is_operator(message.chat.id.0, user_id).await;
+    if degrade.maintenance() && !is_operator {
+        access("maintenance");
+        bot.send_message(message.chat.id, degrade.maintenance_message()).await?;
+        return Ok(())
+    }
+    access("ok");
     match command {
         Commands::Help => {
             bot.send_message(message.chat.id, Commands::descriptions().to_string()).await?;
@@ -185,6 +368,22 @@ async fn handler(bot: AutoSend<Bot>, message: Message, command: Commands) -> Res
         Commands::UsernameAndAge { username, age } => {
             bot.send_message(message.chat.id, format!("Your username is @{username} and age is {age}.")).await?;
         }
+        Commands::ToImperial { celsius, meters } => {
+            let conversion = UnitsConversion::compute(ConversionDirection::MetricToImperial, celsius, meters);
+            bot.send_message(message.chat.id, conversion.to_telegram_text()).await?;
+        }
+        Commands::Maintenance(_) if !is_operator => {
+            warn!("TelegramUI: refusing the `maintenance` command from user #{} (chat #{}): not an operator", user_id, message.chat.id);
+            bot.send_message(message.chat.id, "Only operators may do that").await?;
+        }
+        Commands::Maintenance(switch) => {
+            match switch.trim() {
+                "on"  => degrade.set_maintenance(true),
+                "off" => degrade.set_maintenance(false),
+                _     => (),
+            }
+            bot.send_message(message.chat.id, format!("Maintenance mode is {}", if degrade.maintenance() {"ON"} else {"off"})).await?;
+        }
     }
     Ok(())
 }
@@ -221,102 +420,99 @@ async fn dice_bot(token: &str) -> ShutdownToken {
     dispatcher.shutdown_token()
 }
 
-async fn stateful_commands(token: &str) -> ShutdownToken {
-    type MyDialogue = Dialogue<State, InMemStorage<State>>;
-    type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+// Stateful bot
+///////////////
 
-    debug!("Starting dialogue bot...");
+type MyDialogue = Dialogue<State, ErasedStorage<State>>;
+type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-    let bot = Bot::new(token).auto_send();
+/// The dialogue states -- persisted by the storage selected in [TelegramDialogueStorage]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub enum State {
+    #[default]
+    Start,
+    ReceiveFullName,
+    ReceiveAge { full_name: String },
+    ReceiveLocation { full_name: String, age: u8 },
+}
 
-    let mut dispatcher = Dispatcher::builder(bot.clone(), Update::filter_message().enter_dialogue::<Message, InMemStorage<State>, State>()
-        .branch(dptree::case![State::Start].endpoint(start))
-        .branch(dptree::case![State::ReceiveFullName].endpoint(receive_full_name))
-        .branch(dptree::case![State::ReceiveAge { full_name }].endpoint(receive_age))
-        .branch(dptree::case![State::ReceiveLocation { full_name, age }].endpoint(receive_location))
-    )
-        .dependencies(dptree::deps![InMemStorage::<State>::new()])
-        .build();
-    dispatcher
-        .setup_ctrlc_handler()
-        .dispatch().await;
-
-    #[derive(Clone)]
-    pub enum State {
-        Start,
-        ReceiveFullName,
-        ReceiveAge { full_name: String },
-        ReceiveLocation { full_name: String, age: u8 },
+/// opens the dialogue storage selected in the config -- falling back to the in-memory one if it can't be opened
+async fn open_dialogue_storage(dialogue_storage: &TelegramDialogueStorage) -> Arc<ErasedStorage<State>> {
+    match dialogue_storage {
+        TelegramDialogueStorage::InMemory => InMemStorage::<State>::new().erase(),
+        TelegramDialogueStorage::Redis { url } => match RedisStorage::open(url.as_str(), Json).await {
+            Ok(redis_storage) => redis_storage.erase(),
+            Err(err) => {
+                error!("TelegramUI: could not open the Redis dialogue storage at '{}' -- falling back to the in-memory one, which won't survive restarts: {:?}", url, err);
+                InMemStorage::<State>::new().erase()
+            },
+        },
     }
+}
 
-    impl Default for State {
-        fn default() -> Self {
-            Self::Start
+#[tracing::instrument(name = "telegram_update", skip_all, fields(chat_id = msg.chat.id.0, state = "Start"))]
+async fn start(bot: AutoSend<Bot>, msg: Message, dialogue: MyDialogue) -> HandlerResult {
+    bot.send_message(msg.chat.id, "Let's start! What's your full name?").await?;
+    dialogue.update(State::ReceiveFullName).await?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "telegram_update", skip_all, fields(chat_id = msg.chat.id.0, state = "ReceiveFullName"))]
+async fn receive_full_name(
+    bot: AutoSend<Bot>,
+    msg: Message,
+    dialogue: MyDialogue,
+) -> HandlerResult {
+    match msg.text() {
+        Some(text) => {
+            bot.send_message(msg.chat.id, "How old are you?").await?;
+            dialogue.update(State::ReceiveAge { full_name: text.into() }).await?;
+        }
+        None => {
+            bot.send_message(msg.chat.id, "Send me plain text.").await?;
         }
     }
 
-    async fn start(bot: AutoSend<Bot>, msg: Message, dialogue: MyDialogue) -> HandlerResult {
-        bot.send_message(msg.chat.id, "Let's start! What's your full name?").await?;
-        dialogue.update(State::ReceiveFullName).await?;
-        Ok(())
-    }
+    Ok(())
+}
 
-    async fn receive_full_name(
-        bot: AutoSend<Bot>,
-        msg: Message,
-        dialogue: MyDialogue,
-    ) -> HandlerResult {
-        match msg.text() {
-            Some(text) => {
-                bot.send_message(msg.chat.id, "How old are you?").await?;
-                dialogue.update(State::ReceiveAge { full_name: text.into() }).await?;
-            }
-            None => {
-                bot.send_message(msg.chat.id, "Send me plain text.").await?;
-            }
+#[tracing::instrument(name = "telegram_update", skip_all, fields(chat_id = msg.chat.id.0, state = "ReceiveAge"))]
+async fn receive_age(
+    bot: AutoSend<Bot>,
+    msg: Message,
+    dialogue: MyDialogue,
+    full_name: String, // Available from `State::ReceiveAge`.
+) -> HandlerResult {
+    match msg.text().map(|text| text.parse::<u8>()) {
+        Some(Ok(age)) => {
+            bot.send_message(msg.chat.id, "What's your location?").await?;
+            dialogue.update(State::ReceiveLocation { full_name, age }).await?;
         }
-
-        Ok(())
-    }
-
-    async fn receive_age(
-        bot: AutoSend<Bot>,
-        msg: Message,
-        dialogue: MyDialogue,
-        full_name: String, // Available from `State::ReceiveAge`.
-    ) -> HandlerResult {
-        match msg.text().map(|text| text.parse::<u8>()) {
-            Some(Ok(age)) => {
-                bot.send_message(msg.chat.id, "What's your location?").await?;
-                dialogue.update(State::ReceiveLocation { full_name, age }).await?;
-            }
-            _ => {
-                bot.send_message(msg.chat.id, "Send me a number.").await?;
-            }
+        _ => {
+            bot.send_message(msg.chat.id, "Send me a number.").await?;
         }
-
-        Ok(())
     }
 
-    async fn receive_location(
-        bot: AutoSend<Bot>,
-        msg: Message,
-        dialogue: MyDialogue,
-        (full_name, age): (String, u8), // Available from `State::ReceiveLocation`.
-    ) -> HandlerResult {
-        match msg.text() {
-            Some(location) => {
-                let message = format!("Full name: {full_name}\nAge: {age}\nLocation: {location}");
-                bot.send_message(msg.chat.id, message).await?;
-                dialogue.exit().await?;
-            }
-            None => {
-                bot.send_message(msg.chat.id, "Send me plain text.").await?;
-            }
-        }
+    Ok(())
+}
 
-        Ok(())
+#[tracing::instrument(name = "telegram_update", skip_all, fields(chat_id = msg.chat.id.0, state = "ReceiveLocation"))]
+async fn receive_location(
+    bot: AutoSend<Bot>,
+    msg: Message,
+    dialogue: MyDialogue,
+    (full_name, age): (String, u8), // Available from `State::ReceiveLocation`.
+) -> HandlerResult {
+    match msg.text() {
+        Some(location) => {
+            let message = format!("Full name: {full_name}\nAge: {age}\nLocation: {location}");
+            bot.send_message(msg.chat.id, message).await?;
+            dialogue.exit().await?;
+        }
+        None => {
+            bot.send_message(msg.chat.id, "Send me plain text.").await?;
+        }
     }
 
-    dispatcher.shutdown_token()
+    Ok(())
 }
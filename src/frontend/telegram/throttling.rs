@@ -0,0 +1,149 @@
+//! Per-user command rate limits & daily quotas for the Telegram bots -- see [crate::config::TelegramConfig::command_limits].
+//!
+//! Stops a single user from driving expensive backend commands in a loop: commands above the limits are not executed,
+//! being answered with a friendly "slow down" message instead.
+
+use crate::config::TelegramCommandLimits;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+
+/// The window for measuring the rate of commands, per user
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Above this many tracked users, the ones that went quiet are forgotten -- so memory doesn't grow unbounded
+const MAX_IDLE_USERS: usize = 4096;
+
+
+/// Counts the commands of each user, telling if they are within the configured limits
+pub struct CommandThrottle {
+    limits:         TelegramCommandLimits,
+    users:          Mutex<HashMap<u64, UserCounters>>,
+    accepted:       AtomicU64,
+    rate_limited:   AtomicU64,
+    quota_exceeded: AtomicU64,
+}
+
+struct UserCounters {
+    window_start: Instant,
+    window_count: u32,
+    day:          NaiveDate,
+    day_count:    u32,
+}
+
+/// The verdict for a user's command -- see [CommandThrottle::check()]
+#[derive(Debug, PartialEq)]
+pub enum ThrottleVerdict {
+    Allowed,
+    /// above the per-minute rate: the user may retry after the given duration
+    RateLimited(Duration),
+    /// the daily quota is over: the user may retry tomorrow
+    QuotaExceeded,
+}
+
+impl ThrottleVerdict {
+    /// the friendly message to answer the user with, if the command was refused
+    pub fn reply(&self) -> Option<String> {
+        match self {
+            ThrottleVerdict::Allowed => None,
+            ThrottleVerdict::RateLimited(retry_after) => Some(format!("Easy there! You are sending commands too fast -- please, slow down and try again in {} seconds.", retry_after.as_secs().max(1))),
+            ThrottleVerdict::QuotaExceeded => Some(String::from("You have reached your daily quota of commands -- please, come back tomorrow.")),
+        }
+    }
+}
+
+/// Snapshot of the [CommandThrottle] metrics
+#[derive(Debug, Serialize)]
+pub struct CommandThrottleReport {
+    pub tracked_users:  usize,
+    pub accepted:       u64,
+    pub rate_limited:   u64,
+    pub quota_exceeded: u64,
+}
+
+impl CommandThrottle {
+
+    pub fn new(limits: &TelegramCommandLimits) -> Self {
+        Self {
+            limits:         limits.clone(),
+            users:          Mutex::new(HashMap::new()),
+            accepted:       AtomicU64::new(0),
+            rate_limited:   AtomicU64::new(0),
+            quota_exceeded: AtomicU64::new(0),
+        }
+    }
+
+    /// counts a command from `user_id`, telling if it should be executed
+    pub fn check(&self, user_id: u64) -> ThrottleVerdict {
+        self.check_at(user_id, Instant::now(), chrono::Local::now().date_naive())
+    }
+
+    fn check_at(&self, user_id: u64, now: Instant, today: NaiveDate) -> ThrottleVerdict {
+        let mut users = self.users.lock();
+        if users.len() > MAX_IDLE_USERS {
+            users.retain(|_user_id, counters| counters.day == today && now.duration_since(counters.window_start) < RATE_WINDOW);
+        }
+        let counters = users.entry(user_id)
+            .or_insert_with(|| UserCounters { window_start: now, window_count: 0, day: today, day_count: 0 });
+        if now.duration_since(counters.window_start) >= RATE_WINDOW {
+            counters.window_start = now;
+            counters.window_count = 0;
+        }
+        if counters.day != today {
+            counters.day = today;
+            counters.day_count = 0;
+        }
+        if self.limits.max_commands_per_day > 0 && counters.day_count >= self.limits.max_commands_per_day {
+            self.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+            ThrottleVerdict::QuotaExceeded
+        } else if self.limits.max_commands_per_minute > 0 && counters.window_count >= self.limits.max_commands_per_minute {
+            self.rate_limited.fetch_add(1, Ordering::Relaxed);
+            ThrottleVerdict::RateLimited(RATE_WINDOW.saturating_sub(now.duration_since(counters.window_start)))
+        } else {
+            counters.window_count += 1;
+            counters.day_count += 1;
+            self.accepted.fetch_add(1, Ordering::Relaxed);
+            ThrottleVerdict::Allowed
+        }
+    }
+
+    pub fn report(&self) -> CommandThrottleReport {
+        CommandThrottleReport {
+            tracked_users:  self.users.lock().len(),
+            accepted:       self.accepted.load(Ordering::Relaxed),
+            rate_limited:   self.rate_limited.load(Ordering::Relaxed),
+            quota_exceeded: self.quota_exceeded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+
+/// Unit tests the [throttling](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn rates_and_quotas_are_enforced_per_user() {
+        let throttle = CommandThrottle::new(&TelegramCommandLimits { max_commands_per_minute: 2, max_commands_per_day: 3 });
+        let start = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert_eq!(throttle.check_at(1, start, today), ThrottleVerdict::Allowed);
+        assert_eq!(throttle.check_at(1, start, today), ThrottleVerdict::Allowed);
+        assert!(matches!(throttle.check_at(1, start, today), ThrottleVerdict::RateLimited(_)), "The 3rd command in the same minute should be refused");
+        assert_eq!(throttle.check_at(2, start, today), ThrottleVerdict::Allowed, "Limits should be per user");
+        let next_minute = start + RATE_WINDOW;
+        assert_eq!(throttle.check_at(1, next_minute, today), ThrottleVerdict::Allowed, "The rate should be reset on the next minute");
+        assert_eq!(throttle.check_at(1, next_minute, today), ThrottleVerdict::QuotaExceeded, "The daily quota should have been reached");
+        assert_eq!(throttle.check_at(1, next_minute, today.succ_opt().unwrap()), ThrottleVerdict::Allowed, "The quota should be reset on the next day");
+        let report = throttle.report();
+        assert_eq!((report.accepted, report.rate_limited, report.quota_exceeded), (5, 1, 1), "Unexpected metrics");
+    }
+}
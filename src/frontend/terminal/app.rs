@@ -0,0 +1,182 @@
+//! The Terminal UI's state: the data shown -- refreshed out of the [Runtime], local or remote -- & what the keys do to it
+
+use super::remote::{self, ConsoleSnapshot, ConsoleCommand, RemoteConsole};
+use crate::{
+    runtime::{Runtime, JobRun},
+    frontend::{
+        egui::RuntimeStatus,
+        socket_server::SocketServerStatsReport,
+    },
+    scheduler::ScheduledJobStatus,
+};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tokio::sync::RwLock;
+
+
+pub const TABS: [&str; 5] = ["Services", "Logs", "Metrics", "Socket Clients", "Jobs"];
+
+pub const KEYBINDINGS: &str = "q: quit (shutting down) | ←/→: tabs | ↑/↓: select / scroll | s: stop the selected service | \
+                               m: maintenance | r: read-only API | n: Telegram notifications";
+
+/// [KEYBINDINGS], when attached to a running instance
+pub const REMOTE_KEYBINDINGS: &str = "q: quit (leaving it running) | ←/→: tabs | ↑/↓: select / scroll | s: stop the selected service | \
+                                      m: maintenance | r: read-only API | n: Telegram notifications";
+
+
+/// The degradation switches, as last refreshed
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Switches {
+    pub maintenance:            bool,
+    pub read_only_api:          bool,
+    pub telegram_notifications: bool,
+}
+
+/// Where the data shown comes from -- & where the commands go to
+pub enum Source<'a> {
+    /// this very process
+    Local {
+        runtime:       &'a RwLock<Runtime>,
+        /// through which the data is retrieved -- this UI runs out of Tokio
+        tokio_runtime: Arc<tokio::runtime::Runtime>,
+    },
+    /// a running instance -- see [super::remote]
+    Remote(RemoteConsole),
+}
+
+pub struct App<'a> {
+    source:               Source<'a>,
+    pub should_quit:      bool,
+    /// index into [TABS]
+    pub tab:              usize,
+    /// index into `status.services`
+    pub selected_service: usize,
+    /// how many of the most recent logs are scrolled past
+    pub logs_scroll:      usize,
+    /// the outcome of the last command
+    pub feedback:         Option<String>,
+    pub status:           RuntimeStatus,
+    pub switches:         Switches,
+    pub socket_clients:   Option<SocketServerStatsReport>,
+    /// `None` if the scheduler is not enabled
+    pub scheduled_jobs:   Option<Vec<ScheduledJobStatus>>,
+    pub job_runs:         Vec<JobRun>,
+}
+
+impl<'a> App<'a> {
+
+    pub fn new(source: Source<'a>) -> Self {
+        Self {
+            source,
+            should_quit:      false,
+            tab:              0,
+            selected_service: 0,
+            logs_scroll:      0,
+            feedback:         None,
+            status:           RuntimeStatus::default(),
+            switches:         Switches::default(),
+            socket_clients:   None,
+            scheduled_jobs:   None,
+            job_runs:         vec![],
+        }
+    }
+
+    /// the running instance this UI is attached to -- `None` if it shows this very process
+    pub fn remote_url(&self) -> Option<&str> {
+        match &self.source {
+            Source::Local { .. } => None,
+            Source::Remote(remote_console) => Some(remote_console.url()),
+        }
+    }
+
+    /// retrieves the data shown -- quitting if a shutdown was requested elsewhere (unless attached to a running instance)
+    pub fn refresh(&mut self) {
+        let snapshot = match &self.source {
+            Source::Local { runtime, tokio_runtime } => Some(tokio_runtime.block_on(remote::snapshot(runtime))),
+            Source::Remote(remote_console) => {
+                if let Some(feedback) = remote_console.take_feedback() {
+                    self.feedback = Some(feedback);
+                }
+                remote_console.take_snapshot()
+            },
+        };
+        let Some(ConsoleSnapshot { status, switches, socket_clients, scheduled_jobs, job_runs, shutting_down }) = snapshot else {
+            return
+        };
+        self.status = status;
+        self.switches = switches;
+        self.socket_clients = socket_clients;
+        self.scheduled_jobs = scheduled_jobs;
+        self.job_runs = job_runs;
+        if shutting_down && self.remote_url().is_none() {
+            self.should_quit = true;
+        }
+    }
+
+    pub fn on_right(&mut self) {
+        self.tab = (self.tab + 1) % TABS.len();
+    }
+
+    pub fn on_left(&mut self) {
+        self.tab = (self.tab + TABS.len() - 1) % TABS.len();
+    }
+
+    pub fn on_up(&mut self) {
+        match TABS[self.tab] {
+            "Services" => self.selected_service = self.selected_service.saturating_sub(1),
+            "Logs"     => self.logs_scroll = self.logs_scroll.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    pub fn on_down(&mut self) {
+        match TABS[self.tab] {
+            "Services" => self.selected_service = (self.selected_service + 1).min(self.status.services.len().saturating_sub(1)),
+            "Logs"     => self.logs_scroll = (self.logs_scroll + 1).min(self.status.recent_logs.len().saturating_sub(1)),
+            _ => {}
+        }
+    }
+
+    pub fn on_escape(&mut self) {
+        self.feedback = None;
+    }
+
+    pub fn on_key(&mut self, c: char) {
+        match c {
+            'q' => self.should_quit = true,
+            '1'..='5' => self.tab = c as usize - '1' as usize,
+            's' => self.stop_selected_service(),
+            'm' | 'r' | 'n' => self.flip_switch(c),
+            _ => {}
+        }
+    }
+
+    // TODO 2026-10-16: also (re)start stopped services -- once `Service`s may be started again after their `Runner` resolves
+    fn stop_selected_service(&mut self) {
+        let Some(service) = self.status.services.get(self.selected_service) else {
+            return
+        };
+        self.execute(ConsoleCommand::StopService(service.name.clone()));
+    }
+
+    fn flip_switch(&mut self, key: char) {
+        let command = match key {
+            'm' => ConsoleCommand::SetMaintenance(!self.switches.maintenance),
+            'r' => ConsoleCommand::SetReadOnlyApi(!self.switches.read_only_api),
+            _   => ConsoleCommand::SetTelegramNotifications(!self.switches.telegram_notifications),
+        };
+        self.execute(command);
+    }
+
+    fn execute(&mut self, command: ConsoleCommand) {
+        match &self.source {
+            Source::Local { runtime, tokio_runtime } => {
+                let feedback = tokio_runtime.block_on(remote::execute(runtime, command));
+                self.feedback = Some(feedback);
+                self.refresh();
+            },
+            // the feedback -- & the refreshed data -- are received along with the next snapshots
+            Source::Remote(remote_console) => remote_console.send(command),
+        }
+    }
+}
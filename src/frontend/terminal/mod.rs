@@ -1,18 +1,109 @@
-mod demo;
+//! The Terminal UI: a dashboard of the running application, driven by the [Runtime] -- with tabs for the services (& the degradation
+//! switches), the logs, the metrics, the socket clients & the jobs -- with the keybindings shown at its bottom.
+//!
+//! Logs sent to the console would garble the screen: consider logging to a file while using it -- the "Logs" tab shows the most
+//! recent records either way.
+//!
+//! With `--connect`, it attaches to a running instance instead -- see [remote] -- so it may be inspected without a shell on its host.
+
+mod app;
+mod ui;
+pub mod remote;
 
 use crate::{
-    config::{Config},
+    config::{Config, TerminalOptions},
     runtime::Runtime,
     frontend
 };
+use app::{App, Source};
+use remote::RemoteConsole;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
 use tokio::sync::RwLock;
 
 
-pub fn run(runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    std::thread::sleep(std::time::Duration::from_secs(5));
-    demo::run_demo(demo::Config {
-        enhanced_graphics: false,
-        ..Default::default()
-    }).map_err(|err| format!("Error running Terminal UI: {:?}", err))?;
+/// How often the shown data is refreshed
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often [run()] checks if Tokio -- through which the data is retrieved -- is started
+const TOKIO_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+
+/// runs the Terminal UI until the user quits it (or a shutdown is requested) -- then shuts the services down.
+/// If attached to a running instance -- see [TerminalOptions::connect] -- it is left running: only this process' Tokio is shut down
+pub fn run(options: &TerminalOptions, runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Tokio is started along with this UI
+    let tokio_runtime = loop {
+        if let Some(tokio_runtime) = &runtime.blocking_read().tokio_runtime {
+            break std::sync::Arc::clone(tokio_runtime)
+        }
+        std::thread::sleep(TOKIO_POLL_INTERVAL);
+    };
+    let source = match &options.connect {
+        Some(url) => {
+            let admin_token = options.admin_token.as_deref()
+                .ok_or_else(|| format!("Terminal UI: `--admin-token` is required to attach to '{}'", url))?;
+            Source::Remote(tokio_runtime.block_on(RemoteConsole::connect(url, admin_token))?)
+        },
+        None => Source::Local { runtime, tokio_runtime },
+    };
+
+    enable_raw_mode()
+        .map_err(|err| format!("Terminal UI: could not enable the terminal's raw mode: {:?}", err))?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
+        .map_err(|err| format!("Terminal UI: could not setup the terminal: {:?}", err))?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))
+        .map_err(|err| format!("Terminal UI: could not setup the terminal: {:?}", err))?;
+
+    let result = run_app(&mut terminal, App::new(source));
+
+    // restores the terminal, even if the UI failed
+    disable_raw_mode().ok();
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture).ok();
+    terminal.show_cursor().ok();
+
+    result.map_err(|err| format!("Error running Terminal UI: {:?}", err))?;
     frontend::sync_shutdown_tokio_services(runtime)
 }
+
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+    let mut last_refresh = Instant::now();
+    app.refresh();
+    loop {
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+
+        let timeout = REFRESH_INTERVAL.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char(c) => app.on_key(c),
+                    KeyCode::Tab | KeyCode::Right => app.on_right(),
+                    KeyCode::BackTab | KeyCode::Left => app.on_left(),
+                    KeyCode::Up => app.on_up(),
+                    KeyCode::Down => app.on_down(),
+                    KeyCode::Esc => app.on_escape(),
+                    _ => {}
+                }
+            }
+        }
+        if last_refresh.elapsed() >= REFRESH_INTERVAL {
+            app.refresh();
+            last_refresh = Instant::now();
+        }
+        if app.should_quit {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Attaching the Terminal UI to a running instance -- see [crate::config::TerminalOptions::connect]: the instance pushes
+//! [ConsoleEvent::Snapshot]s of its state through its `/api/admin/console` WebSocket (see `frontend/web/remote_console.rs`) & runs the
+//! [ConsoleCommand]s it receives, answering them with [ConsoleEvent::Feedback]. Text frames carry them as JSON:
+//! ```no_compile
+//!     → {"StopService":"web"}
+//!     ← {"Feedback":"web was asked to shut down"}
+//! ```
+//! The local Terminal UI goes through the very same [snapshot()] & [execute()] -- just without the WebSocket in between.
+
+use super::app::Switches;
+use crate::{
+    runtime::{Runtime, JobRun},
+    frontend::{
+        self,
+        egui::RuntimeStatus,
+        socket_server::SocketServerStatsReport,
+    },
+    scheduler::ScheduledJobStatus,
+};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use futures::{SinkExt, StreamExt};
+use tokio::{
+    net::TcpStream,
+    sync::{RwLock, mpsc},
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream,
+    tungstenite::{Message, client::IntoClientRequest, http::HeaderValue},
+};
+use parking_lot::Mutex;
+use log::warn;
+
+
+/// Where the running instances offer their remote console -- see [RemoteConsole::connect()]
+pub const REMOTE_CONSOLE_PATH: &str = "/api/admin/console";
+
+
+/// The state of the running application, as shown by the Terminal UI
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConsoleSnapshot {
+    pub status:         RuntimeStatus,
+    pub switches:       Switches,
+    pub socket_clients: Option<SocketServerStatsReport>,
+    /// `None` if the scheduler is not enabled
+    pub scheduled_jobs: Option<Vec<ScheduledJobStatus>>,
+    pub job_runs:       Vec<JobRun>,
+    /// if the application is shutting down
+    pub shutting_down:  bool,
+}
+
+/// What the Terminal UI may ask the running application to do -- see [execute()]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ConsoleCommand {
+    /// see [frontend::stop_service()]
+    StopService(String),
+    SetMaintenance(bool),
+    SetReadOnlyApi(bool),
+    SetTelegramNotifications(bool),
+}
+
+/// What the running application sends to the remote Terminal UIs
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ConsoleEvent {
+    Snapshot(Box<ConsoleSnapshot>),
+    /// the outcome of a [ConsoleCommand] -- followed by a fresh snapshot
+    Feedback(String),
+}
+
+
+/// the state of the running application, as of now
+pub async fn snapshot(runtime: &RwLock<Runtime>) -> ConsoleSnapshot {
+    let status = frontend::runtime_status(runtime).await;
+    let runtime = runtime.read().await;
+    ConsoleSnapshot {
+        status,
+        switches:       Switches {
+            maintenance:            runtime.degrade.maintenance(),
+            read_only_api:          runtime.degrade.read_only_api(),
+            telegram_notifications: !runtime.degrade.disable_telegram_notifications(),
+        },
+        socket_clients: Some(runtime.socket_server_stats.report()),
+        scheduled_jobs: runtime.scheduler.as_ref().map(|scheduler| scheduler.report()),
+        job_runs:       runtime.job_accounting.recent_runs(),
+        shutting_down:  runtime.shutdown.is_requested(),
+    }
+}
+
+/// runs `command` -- returning what should be shown to the operator
+pub async fn execute(runtime: &RwLock<Runtime>, command: ConsoleCommand) -> String {
+    let degrade = Arc::clone(&runtime.read().await.degrade);
+    let (name, on) = match command {
+        ConsoleCommand::StopService(service_name) => return frontend::stop_service(runtime, &service_name).await
+                                                                .unwrap_or_else(|err| format!("ERROR: {}", err)),
+        ConsoleCommand::SetMaintenance(on)           => { degrade.set_maintenance(on); ("maintenance", on) },
+        ConsoleCommand::SetReadOnlyApi(on)           => { degrade.set_read_only_api(on); ("read-only", on) },
+        ConsoleCommand::SetTelegramNotifications(on) => { degrade.set_disable_telegram_notifications(!on); ("telegram-notifications", on) },
+    };
+    format!("{} is now {}", name, if on { "on" } else { "off" })
+}
+
+
+/// What was received from the running instance -- kept by [RemoteConsole::run()], taken by the Terminal UI
+#[derive(Default)]
+struct Received {
+    /// the newest snapshot not yet taken
+    snapshot: Option<ConsoleSnapshot>,
+    /// the newest feedback not yet taken -- including the reason the connection was lost
+    feedback: Option<String>,
+}
+
+/// The Terminal UI's end of a running instance's remote console
+pub struct RemoteConsole {
+    url:      String,
+    received: Arc<Mutex<Received>>,
+    commands: mpsc::UnboundedSender<ConsoleCommand>,
+}
+
+impl RemoteConsole {
+
+    /// attaches to the instance whose web server is at `url` -- like "ws://10.0.0.1:8000" -- presenting its `admin_token`.
+    /// Must be called from within the Tokio runtime, which keeps receiving the snapshots until this is dropped
+    pub async fn connect(url: &str, admin_token: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut request = format!("{}{}", url.trim_end_matches('/'), REMOTE_CONSOLE_PATH).into_client_request()
+            .map_err(|err| format!("Terminal UI: invalid instance address '{}': {}", url, err))?;
+        let authorization = HeaderValue::from_str(&format!("Bearer {}", admin_token))
+            .map_err(|err| format!("Terminal UI: invalid admin token: {}", err))?;
+        request.headers_mut().insert("Authorization", authorization);
+        let (web_socket, _response) = tokio_tungstenite::connect_async(request).await
+            .map_err(|err| format!("Terminal UI: could not attach to '{}': {}", url, err))?;
+        let received = Arc::new(Mutex::new(Received::default()));
+        let (commands, commands_receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Self::run(web_socket, Arc::clone(&received), commands_receiver));
+        Ok(Self {
+            url: url.to_string(),
+            received,
+            commands,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// the newest snapshot received since the last call -- if any
+    pub fn take_snapshot(&self) -> Option<ConsoleSnapshot> {
+        self.received.lock().snapshot.take()
+    }
+
+    /// the newest feedback received since the last call -- if any
+    pub fn take_feedback(&self) -> Option<String> {
+        self.received.lock().feedback.take()
+    }
+
+    /// sends `command` to the running instance -- its feedback is to be taken with [Self::take_feedback()]
+    pub fn send(&self, command: ConsoleCommand) {
+        if self.commands.send(command).is_err() {
+            self.received.lock().feedback = Some(format!("ERROR: no longer attached to '{}'", self.url));
+        }
+    }
+
+    /// keeps the [Received] data up to date & sends the commands, until either side closes the WebSocket
+    async fn run(web_socket:            WebSocketStream<MaybeTlsStream<TcpStream>>,
+                 received:              Arc<Mutex<Received>>,
+                 mut commands_receiver: mpsc::UnboundedReceiver<ConsoleCommand>) {
+        let (mut ws_sink, mut ws_stream) = web_socket.split();
+        let lost = |reason: String| received.lock().feedback = Some(format!("ERROR: detached from the instance -- {}", reason));
+        loop {
+            tokio::select! {
+                ws_message = ws_stream.next() => match ws_message {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<ConsoleEvent>(&text) {
+                        Ok(ConsoleEvent::Snapshot(snapshot)) => received.lock().snapshot = Some(*snapshot),
+                        Ok(ConsoleEvent::Feedback(feedback)) => received.lock().feedback = Some(feedback),
+                        Err(err) => warn!("Terminal UI: could not understand the instance's message -- dropping it: {}", err),
+                    },
+                    Some(Ok(Message::Close(_))) | None => {
+                        lost(String::from("it closed the connection: was it shut down?"));
+                        break
+                    },
+                    // pings are answered by the WebSocket layer itself
+                    Some(Ok(_other_frame)) => (),
+                    Some(Err(err)) => {
+                        lost(format!("connection error: {}", err));
+                        break
+                    },
+                },
+                command = commands_receiver.recv() => match command {
+                    Some(command) => {
+                        let frame = Message::Text(serde_json::to_string(&command).expect("BUG! `ConsoleCommand`s are serializable to JSON"));
+                        if let Err(err) = ws_sink.send(frame).await {
+                            lost(format!("could not send {:?}: {}", command, err));
+                            break
+                        }
+                    },
+                    // the UI quit
+                    None => {
+                        ws_sink.close().await.ok();
+                        break
+                    },
+                },
+            }
+        }
+    }
+}
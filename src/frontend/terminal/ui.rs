@@ -0,0 +1,313 @@
+//! Draws the Terminal UI's tabs out of the [App]'s data
+
+use super::app::{App, TABS, KEYBINDINGS, REMOTE_KEYBINDINGS};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Row, Sparkline, Table, Tabs, Wrap},
+    Frame,
+};
+
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .split(f.size());
+    let titles = TABS
+        .iter()
+        .enumerate()
+        .map(|(i, t)| Spans::from(Span::styled(format!("{} {}", i + 1, t), Style::default().fg(Color::Green))))
+        .collect();
+    let uptime = app.status.uptime_seconds;
+    let title = format!("{}{} -- up for {}d {:02}:{:02}:{:02}", env!("CARGO_PKG_NAME"),
+                        app.remote_url().map_or_else(String::new, |url| format!(" @ {}", url)),
+                        uptime / 86400, (uptime % 86400) / 3600, (uptime % 3600) / 60, uptime % 60);
+    let tabs = Tabs::new(titles)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().fg(Color::Yellow))
+        .select(app.tab);
+    f.render_widget(tabs, chunks[0]);
+    match TABS[app.tab] {
+        "Services"       => draw_services_tab(f, app, chunks[1]),
+        "Logs"           => draw_logs_tab(f, app, chunks[1]),
+        "Metrics"        => draw_metrics_tab(f, app, chunks[1]),
+        "Socket Clients" => draw_socket_clients_tab(f, app, chunks[1]),
+        "Jobs"           => draw_jobs_tab(f, app, chunks[1]),
+        _ => {}
+    };
+    let footer = match &app.feedback {
+        Some(feedback) => Spans::from(vec![
+            Span::styled(feedback.as_str(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::raw(" (Esc to dismiss)"),
+        ]),
+        None => Spans::from(Span::styled(if app.remote_url().is_some() { REMOTE_KEYBINDINGS } else { KEYBINDINGS },
+                                         Style::default().fg(Color::DarkGray))),
+    };
+    f.render_widget(Paragraph::new(footer).block(Block::default().borders(Borders::ALL)), chunks[2]);
+}
+
+/// the services' states -- the selected one may be stopped -- & the degradation switches
+fn draw_services_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    let rows = app.status.services.iter().enumerate().map(|(i, service)| {
+        let mut style = match service.state.as_str() {
+            "running"     => Style::default().fg(Color::Green),
+            "not running" => Style::default().fg(Color::DarkGray),
+            _             => Style::default().fg(Color::Yellow),
+        };
+        if i == app.selected_service {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        Row::new(vec![service.name.clone(), service.state.clone()]).style(style)
+    });
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Service", "State"]).style(Style::default().fg(Color::Yellow)).bottom_margin(1))
+        .block(Block::default().title("Services").borders(Borders::ALL))
+        .widths(&[Constraint::Length(16), Constraint::Length(16)]);
+    f.render_widget(table, chunks[0]);
+
+    let switch = |name: &'static str, key: &'static str, on: bool| Spans::from(vec![
+        Span::raw(format!("[{}] {:24}", key, name)),
+        if on {
+            Span::styled("on", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        } else {
+            Span::styled("off", Style::default().fg(Color::Green))
+        },
+    ]);
+    let switches = vec![
+        switch("maintenance", "m", app.switches.maintenance),
+        switch("read-only API", "r", app.switches.read_only_api),
+        switch("telegram-notifications", "n", app.switches.telegram_notifications),
+    ];
+    let paragraph = Paragraph::new(switches)
+        .block(Block::default().title("Degradation switches").borders(Borders::ALL));
+    f.render_widget(paragraph, chunks[1]);
+}
+
+/// the most recent log records, the newest first -- see [crate::logging::log_tail]
+fn draw_logs_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let items: Vec<ListItem> = app.status.recent_logs.iter()
+        .skip(app.logs_scroll)
+        .map(|log| {
+            let style = match log.level.as_str() {
+                "CRITICAL" => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                "ERROR"    => Style::default().fg(Color::Red),
+                "WARN"     => Style::default().fg(Color::Yellow),
+                "INFO"     => Style::default().fg(Color::Blue),
+                _          => Style::default().fg(Color::DarkGray),
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{} {:<8}", log.timestamp, log.level), style),
+                Span::raw(format!(" {}: {}", log.target, log.message)),
+            ]))
+        })
+        .collect();
+    let title = format!("Logs ({} most recent{})", app.status.recent_logs.len(),
+                        if app.logs_scroll > 0 { format!(" -- scrolled past {}", app.logs_scroll) } else { String::new() });
+    f.render_widget(List::new(items).block(Block::default().title(title).borders(Borders::ALL)), area);
+}
+
+/// the counters & gauges -- see [crate::runtime::metrics_report] -- below the charts of the ones whose history is kept
+/// (see [crate::runtime::MetricsHistory])
+fn draw_metrics_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let area = if app.status.history.is_empty() {
+        area
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area);
+        draw_metrics_history(f, app, chunks[0]);
+        chunks[1]
+    };
+    if app.status.metrics.is_empty() {
+        let paragraph = Paragraph::new("No metrics -- is the metrics recorder enabled in the config?")
+            .block(Block::default().title("Metrics").borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+        f.render_widget(paragraph, area);
+        return
+    }
+    let rows = app.status.metrics.iter()
+        .map(|metric| Row::new(vec![metric.name.clone(), format!("{}", metric.value)]));
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Metric", "Value"]).style(Style::default().fg(Color::Yellow)).bottom_margin(1))
+        .block(Block::default().title(format!("Metrics ({})", app.status.metrics.len())).borders(Borders::ALL))
+        .widths(&[Constraint::Percentage(70), Constraint::Percentage(30)]);
+    f.render_widget(table, area);
+}
+
+/// a sparkline for each metric with a history -- the newest samples that fit, scaled to the highest of them
+fn draw_metrics_history<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let history = &app.status.history;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(history.iter().map(|_| Constraint::Length(3)).chain([Constraint::Min(0)]).collect::<Vec<_>>())
+        .split(area);
+    for (metric, chunk) in history.iter().zip(chunks) {
+        let visible = metric.samples.len().saturating_sub(chunk.width.saturating_sub(2) as usize);
+        let values: Vec<f64> = metric.samples[visible..].iter().map(|(_timestamp, value)| value.max(0.0)).collect();
+        let highest = values.iter().copied().fold(0.0, f64::max);
+        // sparklines take integers: scaling keeps the resolution of fractional values
+        let data: Vec<u64> = values.iter()
+            .map(|value| if highest > 0.0 { (value / highest * 1000.0) as u64 } else { 0 })
+            .collect();
+        let title = format!("{} -- {}", metric.name, metric.samples.last().map_or(String::from("-"), |(_timestamp, value)| value.to_string()));
+        let sparkline = Sparkline::default()
+            .block(Block::default().title(title).borders(Borders::LEFT | Borders::RIGHT | Borders::TOP))
+            .data(&data)
+            .max(1000)
+            .style(Style::default().fg(Color::Green));
+        f.render_widget(sparkline, chunk);
+    }
+}
+
+/// per-endpoint statistics of the socket server -- see [crate::frontend::socket_server::SocketServerStats]
+fn draw_socket_clients_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let Some(report) = &app.socket_clients else {
+        return
+    };
+    let header_style = Style::default().fg(Color::Yellow);
+    let header = Row::new(vec!["Address", "Connected", "Msgs In", "Msgs Out", "Bytes In", "Bytes Out", "Errors", "Idle (s)", "Latency (µs)", "RTT p95 (µs)"])
+        .style(header_style)
+        .bottom_margin(1);
+    let rows = report.endpoints.iter().map(|endpoint| {
+        let style = if endpoint.connected {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        Row::new(vec![
+            endpoint.address.clone(),
+            if endpoint.connected { "yes" } else { "no" }.to_string(),
+            endpoint.messages_in.to_string(),
+            endpoint.messages_out.to_string(),
+            endpoint.bytes_in.to_string(),
+            endpoint.bytes_out.to_string(),
+            endpoint.errors.to_string(),
+            format!("{:.1}", endpoint.idle_seconds),
+            format!("{:.1}", endpoint.mean_processing_latency_micros),
+            format!("{:.1}", endpoint.round_trip_p95_micros),
+        ])
+        .style(style)
+    });
+    let title = format!("Socket Clients ({} of up to {} tracked; {} untracked events)",
+                        report.endpoints.len(), report.max_tracked_endpoints, report.untracked_events);
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(22),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(13),
+            Constraint::Length(13),
+        ]);
+    f.render_widget(table, area);
+}
+
+/// the scheduled jobs -- see [crate::scheduler::Scheduler] -- & the most recent job runs -- see [crate::runtime::JobAccounting]
+fn draw_jobs_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    match &app.scheduled_jobs {
+        None => {
+            let paragraph = Paragraph::new("The scheduler is not enabled in the config")
+                .block(Block::default().title("Scheduled jobs").borders(Borders::ALL));
+            f.render_widget(paragraph, chunks[0]);
+        },
+        Some(scheduled_jobs) => {
+            let rows = scheduled_jobs.iter().map(|job| {
+                let style = if job.running {
+                    Style::default().fg(Color::Green)
+                } else if job.last_error.is_some() {
+                    Style::default().fg(Color::Red)
+                } else if !job.registered || !job.enabled {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    job.name.clone(),
+                    job.schedule.clone(),
+                    job.runs.to_string(),
+                    job.failures.to_string(),
+                    job.last_run.clone().unwrap_or_default(),
+                    job.next_run.clone().unwrap_or_default(),
+                    job.last_error.clone().unwrap_or_default(),
+                ])
+                .style(style)
+            });
+            let table = Table::new(rows)
+                .header(Row::new(vec!["Job", "Schedule", "Runs", "Failures", "Last run", "Next run", "Last error"])
+                            .style(Style::default().fg(Color::Yellow))
+                            .bottom_margin(1))
+                .block(Block::default().title("Scheduled jobs").borders(Borders::ALL))
+                .widths(&[
+                    Constraint::Length(24),
+                    Constraint::Length(16),
+                    Constraint::Length(6),
+                    Constraint::Length(8),
+                    Constraint::Length(26),
+                    Constraint::Length(26),
+                    Constraint::Min(10),
+                ]);
+            f.render_widget(table, chunks[0]);
+        },
+    }
+
+    let rows = app.job_runs.iter().rev().map(|run| {
+        let style = if run.succeeded { Style::default().fg(Color::Green) } else { Style::default().fg(Color::Red) };
+        Row::new(vec![
+            run.job.clone(),
+            run.timestamp.clone(),
+            format!("{:.3}", run.wall_time_micros as f64 / 1e6),
+            run.cpu_time_micros.map_or_else(String::new, |cpu_time_micros| format!("{:.3}", cpu_time_micros as f64 / 1e6)),
+            run.peak_memory_delta_kib.map_or_else(String::new, |peak_memory_delta_kib| peak_memory_delta_kib.to_string()),
+            if run.succeeded { "yes" } else { "no" }.to_string(),
+        ])
+        .style(style)
+    });
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Job", "Started at", "Wall (s)", "CPU (s)", "Memory Δ (KiB)", "Succeeded"])
+                    .style(Style::default().fg(Color::Yellow))
+                    .bottom_margin(1))
+        .block(Block::default().title(format!("Recent job runs ({})", app.job_runs.len())).borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(24),
+            Constraint::Length(26),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(15),
+            Constraint::Length(10),
+        ]);
+    f.render_widget(table, chunks[1]);
+}
@@ -0,0 +1,37 @@
+//! Feeds the access log (see [crate::logging::access_log]) with the HTTP requests -- only attached if it is enabled.
+//!
+//! The request line is taken before the other fairings have the chance of rerouting the request (like [super::maintenance] does),
+//! so this fairing should be attached first.
+
+use crate::logging::access_log::log_http_access;
+use rocket::{
+    Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+};
+
+
+/// The fairing logging each request to the access log
+pub struct AccessLog;
+
+/// The request line, as received -- kept in the request's local cache
+struct RequestLine(String);
+
+#[rocket::async_trait]
+impl Fairing for AccessLog {
+    fn info(&self) -> Info {
+        Info {
+            name: "Access log",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        request.local_cache(|| RequestLine(request.uri().to_string()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestLine(uri) = request.local_cache(|| RequestLine(request.uri().to_string()));
+        log_http_access(request.client_ip(), request.method(), uri, response.status().code,
+                        response.body().preset_size(), request.headers().get_one("Referer"), request.headers().get_one("User-Agent"));
+    }
+}
@@ -0,0 +1,399 @@
+//! The admin API: lets operators drive the running application -- shutting it down, reloading & editing its config, changing the log filters,
+//! managing the socket server's clients & wire dumping, the scheduled jobs, the job queue's dead letters, the outbox's undelivered intents & the degradation switches -- through the [Runtime]'s controllers,
+//! so no access to the host (nor to its signals) is needed. It is served even in maintenance mode -- so it may be turned off through it.\
+//! Only mounted if [crate::config::WebConfig::admin_token] is set -- requests not presenting it as `Authorization: Bearer <token>`
+//! are refused with "401 Unauthorized"
+
+use crate::{
+    runtime::{Runtime, ConfigEdit, EditableField, EDITABLE_FIELDS, OutboxEntry, DegradeSwitches, SwitchesReport, SWITCHES, constant_time_eq},
+    frontend::{self, socket_server::WireDump},
+    logging,
+};
+use std::{
+    sync::Arc,
+    collections::BTreeMap,
+    net::SocketAddr,
+};
+use rocket::{
+    get, post, put, patch, delete, State,
+    http::{ContentType, Status},
+    request::{FromRequest, Outcome, Request},
+    serde::{json::Json, Serialize},
+};
+use sloggers::types::Severity;
+use tokio::sync::RwLock;
+use log::{warn, error};
+
+
+pub const BASE_PATH: &str = "/api/admin";
+
+/// The page to edit the config -- see [config_page()]
+const CONFIG_PAGE: &str = include_str!("admin_config.html");
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        shutdown,
+        reload_config,
+        config_page,
+        editable_config,
+        edit_config,
+        set_log_filters,
+        socket_clients,
+        disconnect_socket_client,
+        wire_dump,
+        activate_wire_dump,
+        set_wire_dump_endpoints,
+        enable_scheduled_job,
+        requeue_dead_letter,
+        discard_dead_letter,
+        outbox_entries,
+        requeue_outbox_entry,
+        discard_outbox_entry,
+        switches,
+        set_switch,
+    ]
+}
+
+/// The token the [Admin] requests must present -- managed by Rocket when the admin API is enabled
+pub struct AdminToken(pub String);
+
+/// Request guard for the admin routes: fails with "401 Unauthorized" if the [AdminToken] is not presented as `Authorization: Bearer <token>`
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(AdminToken(admin_token)) = request.rocket().state::<AdminToken>() else {
+            return Outcome::Error((Status::NotFound, "The admin API is disabled"))
+        };
+        let presented = request.headers().get_one("Authorization")
+            .and_then(|authorization| authorization.strip_prefix("Bearer "));
+        match presented {
+            Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => Outcome::Success(Admin),
+            _ => {
+                warn!("Web: refused admin request for {} from {:?}: missing or invalid token", request.uri(), request.client_ip());
+                Outcome::Error((Status::Unauthorized, "Missing or invalid admin token"))
+            },
+        }
+    }
+}
+
+
+/// Gracefully shuts the services down -- as `Ctrl-C` would. Answered before the shutdown starts, with "202 Accepted"
+#[post("/shutdown")]
+fn shutdown(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> Status {
+    warn!("Admin API: graceful shutdown requested");
+    let runtime = Arc::clone(runtime.inner());
+    tokio::spawn(async move {
+        if let Err(err) = frontend::shutdown_tokio_services(&runtime).await {
+            error!("Admin API: could not shut the services down: {}", err);
+        }
+    });
+    Status::Accepted
+}
+
+/// Reloads the config file right away -- see [crate::runtime::ConfigReload]. The outcome is logged, as the reload happens after this
+/// is answered with "202 Accepted". "409 Conflict" if the config hot reload is disabled
+#[post("/config/reload")]
+async fn reload_config(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, &'static str) {
+    match &runtime.read().await.config_reload {
+        Some(config_reload) => {
+            warn!("Admin API: config reload requested");
+            config_reload.request_reload();
+            (Status::Accepted, "The config file is being reloaded -- see the logs for the outcome")
+        },
+        None => (Status::Conflict, "The config hot reload is disabled -- see `hot_reload` in the config file"),
+    }
+}
+
+/// The admin page to edit the config -- a form for the [editable_config()], saved through [edit_config()]. It asks for the admin token
+#[get("/config/page")]
+fn config_page() -> (ContentType, &'static str) {
+    (ContentType::HTML, CONFIG_PAGE)
+}
+
+/// The editable config -- see [editable_config()]
+#[derive(Serialize)]
+struct EditableConfig {
+    values: ConfigEdit,
+    schema: &'static [EditableField],
+}
+
+/// The running values of the config settings that may be edited -- along with their descriptions.
+/// "409 Conflict" if the config hot reload is disabled
+#[get("/config")]
+async fn editable_config(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> Result<Json<EditableConfig>, (Status, &'static str)> {
+    match &runtime.read().await.config_reload {
+        Some(config_reload) => Ok(Json(EditableConfig { values: ConfigEdit::from_config(&config_reload.current()), schema: EDITABLE_FIELDS })),
+        None => Err((Status::Conflict, "The config hot reload is disabled -- see `hot_reload` in the config file")),
+    }
+}
+
+/// Edits the given config settings -- absent ones are kept -- persisting them to the config file, which is then reloaded.
+/// Answers the changes made, each of them also leaving an audit log entry. "422 Unprocessable Entity" if the edit is invalid;
+/// "409 Conflict" if the config hot reload is disabled
+#[patch("/config", format = "json", data = "<config_edit>")]
+async fn edit_config(_admin: Admin, config_edit: Json<ConfigEdit>, runtime: &State<Arc<RwLock<Runtime>>>) -> Result<Json<Vec<String>>, (Status, String)> {
+    let config_reload = runtime.read().await.config_reload.clone()
+        .ok_or_else(|| (Status::Conflict, String::from("The config hot reload is disabled -- see `hot_reload` in the config file")))?;
+    tokio::task::spawn_blocking(move || config_reload.edit(&config_edit, "the admin API"))
+        .await
+        .map_err(|err| (Status::InternalServerError, format!("The config edit panicked: {}", err)))?
+        .map(Json)
+        .map_err(|err| (Status::UnprocessableEntity, err.to_string()))
+}
+
+/// Replaces the log filters -- like `{"*": "info", "socket_server": "debug"}` -- until the next config reload or restart.
+/// See [crate::config::Config::log_filters]
+#[put("/log-filters", format = "json", data = "<log_filters>")]
+fn set_log_filters(_admin: Admin, log_filters: Json<BTreeMap<String, Severity>>) -> Status {
+    warn!("Admin API: log filters set to {:?}", log_filters.0);
+    logging::apply_log_filters(&log_filters);
+    Status::NoContent
+}
+
+/// The addresses of the socket server's connected clients. "404 Not Found" if the socket server is not running
+#[get("/socket/clients")]
+async fn socket_clients(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> Option<Json<Vec<SocketAddr>>> {
+    Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.connected_clients() })).await
+        .map(Json)
+}
+
+/// Disconnects the socket server's client at `address` -- like `127.0.0.1:54321`. "404 Not Found" if it is not connected
+#[delete("/socket/clients/<address>")]
+async fn disconnect_socket_client(_admin: Admin, address: SocketAddr, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let disconnected = Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.disconnect(address) })).await;
+    match disconnected {
+        Some(Ok(())) => {
+            warn!("Admin API: disconnecting socket client {}", address);
+            (Status::Ok, format!("{} is being disconnected", address))
+        },
+        Some(Err(err)) => (Status::NotFound, err.to_string()),
+        None => (Status::NotFound, String::from("The socket server is not running")),
+    }
+}
+
+/// The state of the socket server's wire dumping -- see [WireDump]
+#[derive(Serialize)]
+struct WireDumpReport {
+    configured: bool,
+    active:     bool,
+    /// the client addresses being dumped -- empty for all
+    endpoints:  Vec<String>,
+}
+
+/// The state of the socket server's wire dumping. "404 Not Found" if the socket server is not running
+#[get("/socket/wire-dump")]
+async fn wire_dump(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> Option<Json<WireDumpReport>> {
+    let wire_dump = socket_wire_dump(runtime).await?;
+    Some(Json(WireDumpReport {
+        configured: wire_dump.is_configured(),
+        active:     wire_dump.is_active(),
+        endpoints:  wire_dump.endpoints(),
+    }))
+}
+
+/// Resumes (`true`) or pauses (`false`) the socket server's wire dumping.
+/// "404 Not Found" if the socket server is not running; "409 Conflict" if dumping is not configured -- see `wire_dump` in the config file
+#[put("/socket/wire-dump/active", format = "json", data = "<active>")]
+async fn activate_wire_dump(_admin: Admin, active: Json<bool>, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(wire_dump) = socket_wire_dump(runtime).await else {
+        return (Status::NotFound, String::from("The socket server is not running"))
+    };
+    if !wire_dump.is_configured() {
+        return (Status::Conflict, String::from("Wire dumping is not configured -- see `wire_dump` in the config file"))
+    }
+    warn!("Admin API: {} the socket server's wire dumping", if active.0 { "resuming" } else { "pausing" });
+    wire_dump.set_active(active.0);
+    (Status::Ok, format!("Wire dumping is {}", if active.0 { "active" } else { "paused" }))
+}
+
+/// Restricts the socket server's wire dumping to the given client addresses -- like `["10.0.0.7", "10.0.0.8:54321"]` -- or to
+/// all of them, if empty. "404 Not Found" if the socket server is not running
+#[put("/socket/wire-dump/endpoints", format = "json", data = "<endpoints>")]
+async fn set_wire_dump_endpoints(_admin: Admin, endpoints: Json<Vec<String>>, runtime: &State<Arc<RwLock<Runtime>>>) -> Status {
+    let Some(wire_dump) = socket_wire_dump(runtime).await else {
+        return Status::NotFound
+    };
+    wire_dump.set_endpoints(endpoints.0);
+    Status::NoContent
+}
+
+/// the socket server's [WireDump] -- `None` if it is not running
+async fn socket_wire_dump(runtime: &RwLock<Runtime>) -> Option<Arc<WireDump>> {
+    Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.wire_dump() })).await
+}
+
+/// Disables (`false`) or re-enables (`true`) the scheduled job `name` -- see [crate::scheduler::Scheduler::set_enabled()].
+/// "404 Not Found" if there is no such job; "409 Conflict" if the scheduler is disabled
+#[put("/scheduler/jobs/<name>/enabled", format = "json", data = "<enabled>")]
+async fn enable_scheduled_job(_admin: Admin, name: &str, enabled: Json<bool>, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(scheduler) = runtime.read().await.scheduler.clone() else {
+        return (Status::Conflict, String::from("The scheduler is disabled -- see `scheduler` in the config file"))
+    };
+    if scheduler.set_enabled(name, enabled.0) {
+        warn!("Admin API: scheduled job '{}' {}", name, if enabled.0 { "enabled" } else { "disabled" });
+        (Status::Ok, format!("Job '{}' is {}", name, if enabled.0 { "enabled" } else { "disabled" }))
+    } else {
+        (Status::NotFound, format!("There is no scheduled job named '{}'", name))
+    }
+}
+
+/// Runs the job queue's dead letter `id` again -- with its attempts reset. Dead letters are listed on `/api/stats/job-queue`.
+/// "404 Not Found" if it is no longer dead-lettered; "409 Conflict" if the job queue is disabled
+#[post("/job-queue/dead-letters/<id>/requeue")]
+async fn requeue_dead_letter(_admin: Admin, id: u64, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(job_queue) = runtime.read().await.job_queue.clone() else {
+        return (Status::Conflict, String::from("The job queue is disabled -- see `job_queue` in the config file"))
+    };
+    match job_queue.requeue_dead_letter(id) {
+        Ok(true) => {
+            warn!("Admin API: dead letter #{} requeued", id);
+            (Status::Ok, format!("Job #{} is queued again", id))
+        },
+        Ok(false) => (Status::NotFound, format!("Job #{} is not in the dead letters", id)),
+        Err(err) => (Status::InternalServerError, err.to_string()),
+    }
+}
+
+/// Purges the job queue's dead letter `id`. "404 Not Found" if it is no longer dead-lettered; "409 Conflict" if the job queue is disabled
+#[delete("/job-queue/dead-letters/<id>")]
+async fn discard_dead_letter(_admin: Admin, id: u64, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(job_queue) = runtime.read().await.job_queue.clone() else {
+        return (Status::Conflict, String::from("The job queue is disabled -- see `job_queue` in the config file"))
+    };
+    match job_queue.discard_dead_letter(id) {
+        Ok(true) => {
+            warn!("Admin API: dead letter #{} discarded", id);
+            (Status::Ok, format!("Job #{} was discarded", id))
+        },
+        Ok(false) => (Status::NotFound, format!("Job #{} is not in the dead letters", id)),
+        Err(err) => (Status::InternalServerError, err.to_string()),
+    }
+}
+
+/// The outbox's undelivered intents -- including the failed ones -- the oldest first. See [crate::runtime::Outbox].
+/// "409 Conflict" if the outbox is disabled
+#[get("/outbox")]
+async fn outbox_entries(_admin: Admin, runtime: &State<Arc<RwLock<Runtime>>>) -> Result<Json<Vec<OutboxEntry>>, (Status, String)> {
+    let outbox = runtime.read().await.outbox.clone()
+        .ok_or_else(|| (Status::Conflict, String::from("The outbox is disabled -- see `outbox` in the config file")))?;
+    outbox.entries()
+        .map(Json)
+        .map_err(|err| (Status::InternalServerError, err.to_string()))
+}
+
+/// Tries delivering the outbox's intent `id` again -- right away & with its attempts reset. "404 Not Found" if it is no longer in the outbox
+#[post("/outbox/<id>/requeue")]
+async fn requeue_outbox_entry(_admin: Admin, id: u64, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(outbox) = runtime.read().await.outbox.clone() else {
+        return (Status::Conflict, String::from("The outbox is disabled -- see `outbox` in the config file"))
+    };
+    match outbox.requeue(id) {
+        Ok(true) => {
+            warn!("Admin API: outbox intent #{} requeued", id);
+            (Status::Ok, format!("Intent #{} is being delivered again", id))
+        },
+        Ok(false) => (Status::NotFound, format!("Intent #{} is not in the outbox", id)),
+        Err(err) => (Status::InternalServerError, err.to_string()),
+    }
+}
+
+/// Gives up on delivering the outbox's intent `id`. "404 Not Found" if it is no longer in the outbox
+#[delete("/outbox/<id>")]
+async fn discard_outbox_entry(_admin: Admin, id: u64, runtime: &State<Arc<RwLock<Runtime>>>) -> (Status, String) {
+    let Some(outbox) = runtime.read().await.outbox.clone() else {
+        return (Status::Conflict, String::from("The outbox is disabled -- see `outbox` in the config file"))
+    };
+    match outbox.discard(id) {
+        Ok(true) => {
+            warn!("Admin API: outbox intent #{} discarded", id);
+            (Status::Ok, format!("Intent #{} was discarded", id))
+        },
+        Ok(false) => (Status::NotFound, format!("Intent #{} is not in the outbox", id)),
+        Err(err) => (Status::InternalServerError, err.to_string()),
+    }
+}
+
+/// The state of the degradation switches -- see [DegradeSwitches]
+#[get("/switches")]
+fn switches(_admin: Admin, degrade: &State<Arc<DegradeSwitches>>) -> Json<SwitchesReport> {
+    Json(degrade.report())
+}
+
+/// Turns on (`true`) or off (`false`) the degradation switch `name` -- one of [SWITCHES]. "404 Not Found" if there is no such switch
+#[put("/switches/<name>", format = "json", data = "<on>")]
+fn set_switch(_admin: Admin, name: &str, on: Json<bool>, degrade: &State<Arc<DegradeSwitches>>) -> (Status, String) {
+    if degrade.set(name, on.0) {
+        warn!("Admin API: switch '{}' turned {}", name, if on.0 { "on" } else { "off" });
+        (Status::Ok, format!("{} is now {}", name, if on.0 { "on" } else { "off" }))
+    } else {
+        (Status::NotFound, format!("There is no switch named '{}' -- known ones are: {}", name, SWITCHES.join(", ")))
+    }
+}
+
+
+/// Unit tests the [admin](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::config::DegradeConfig;
+    use rocket::{
+        http::Header,
+        local::asynchronous::Client,
+    };
+
+
+    const ADMIN_TOKEN: &str = "the-admin-token";
+
+
+    /// a client for the degradation switches routes -- requiring `admin_token`, if given. If not, the admin API is disabled
+    async fn admin_client(admin_token: Option<&str>, degrade: &Arc<DegradeSwitches>) -> Client {
+        let mut rocket = rocket::build()
+            .manage(Arc::clone(degrade))
+            .mount(BASE_PATH, rocket::routes![switches, set_switch]);
+        if let Some(admin_token) = admin_token {
+            rocket = rocket.manage(AdminToken(String::from(admin_token)));
+        }
+        Client::tracked(rocket).await
+            .expect("Igniting Rocket")
+    }
+
+    /// assures the admin routes are refused to the requests not presenting the admin token -- & not served at all if it is not set
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn admin_token_is_required() {
+        let degrade = Arc::new(DegradeSwitches::new(&DegradeConfig::default()));
+        let switches_path = format!("{}/switches", BASE_PATH);
+        let maintenance_path = format!("{}/switches/maintenance", BASE_PATH);
+        let client = admin_client(Some(ADMIN_TOKEN), &degrade).await;
+
+        let response = client.get(switches_path.as_str()).dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized, "Requests without the token should have been refused");
+        for authorization in [format!("Bearer {}x", ADMIN_TOKEN), String::from(ADMIN_TOKEN), format!("Basic {}", ADMIN_TOKEN)] {
+            let response = client.put(maintenance_path.as_str())
+                .header(ContentType::JSON)
+                .header(Header::new("Authorization", authorization.clone()))
+                .body("true")
+                .dispatch().await;
+            assert_eq!(response.status(), Status::Unauthorized, "'Authorization: {}' should have been refused", authorization);
+        }
+        assert!(!degrade.maintenance(), "Refused requests shouldn't have flipped the switch");
+
+        let response = client.put(maintenance_path.as_str())
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {}", ADMIN_TOKEN)))
+            .body("true")
+            .dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "Requests presenting the token should have been served");
+        assert!(degrade.maintenance(), "The switch should have been flipped");
+
+        let client = admin_client(None, &degrade).await;
+        let response = client.get(switches_path.as_str())
+            .header(Header::new("Authorization", format!("Bearer {}", ADMIN_TOKEN)))
+            .dispatch().await;
+        assert_eq!(response.status(), Status::NotFound, "The admin API should be disabled without a configured token");
+    }
+}
@@ -1,25 +1,323 @@
 //! Place here any APIs your program shares with external services
 
+use crate::{
+    runtime::{Runtime, DegradeSwitches, Webhooks, Delivery, JobAccounting, JobRun, JobQueue, JobQueueReport, MetricsHistory, MetricSample, SelfMonitor, ResourcesSample, Warmup, WarmupReport, AuthProvider, AuthResult, Principal, SessionTokens, SessionToken},
+    frontend::{self, egui::RuntimeStatus, socket_server::{SocketServerStats, SocketServerStatsReport}, telegram::CommandThrottleReport},
+    scheduler::{Scheduler, ScheduledJobStatus},
+    logic::{UnitsConversion, ConversionDirection, dto::Exposed},
+    logging::log_tail::{self, TailFilter, TailedRecord},
+};
+use super::response_cache::{ResponseCache, ResponseCacheReport, Cacheable, CachedJson};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::Duration,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
 use rocket::{
-    get, post,
-    response::Responder,
+    get, post, delete, State, Shutdown,
+    http::{ContentType, Status},
+    request::{FromRequest, Outcome, Request},
+    response::{Responder, stream::{Event, EventStream}},
     FromFormField,
     FromForm,
     serde::{json::Json, Serialize, Deserialize},
 };
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use tokio::sync::{RwLock, broadcast::error::RecvError};
+use log::warn;
 
 
 pub const BASE_PATH: &str = "/api";
 
+/// How many days ahead `/schedule.ics` goes, at most -- see [schedule_ics()]
+const MAX_SCHEDULE_DAYS: u32 = 31;
+/// How many runs of each job `/schedule.ics` lists, at most -- so jobs on short intervals don't flood the calendars
+const MAX_SCHEDULED_RUNS_PER_JOB: usize = 100;
+
 /// all methods exported by this module
 pub fn routes() -> Vec<rocket::Route> {
     rocket::routes![
         rest_service,
         get_service,
         post_service,
+        socket_stats,
+        runtime_stats,
+        cache_stats,
+        telegram_stats,
+        jobs_stats,
+        ready,
+    ]
+}
+
+/// methods exported by this module only when the metrics recorder is installed -- see [crate::runtime::Runtime::metrics]
+pub fn metrics_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        metrics,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::WebConfig::logs_following_routes] is enabled
+pub fn logs_following_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        logs_tail,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::Config::job_queue] is enabled
+pub fn job_queue_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        job_queue_stats,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::Config::self_monitor] is enabled
+pub fn self_monitor_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        self_monitor_stats,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::Config::scheduler] is enabled
+pub fn scheduler_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        scheduler_stats,
+        schedule_ics,
     ]
 }
 
+/// methods exported by this module only when [crate::config::Config::metrics_history] is enabled
+pub fn metrics_history_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        metrics_history_names,
+        metrics_history,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::Config::auth] is enabled
+pub fn session_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        issue_socket_token,
+        revoke_socket_token,
+        revoke_socket_tokens,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::Config::webhooks] is enabled
+pub fn webhooks_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        webhook_deliveries,
+    ]
+}
+
+
+/// Request guard for the routes changing the application's state: fails with "503 Service Unavailable"
+/// while the API is degraded to read-only -- see [DegradeSwitches::read_only_api()]
+pub struct WritableApi;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WritableApi {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.rocket().state::<Arc<DegradeSwitches>>() {
+            Some(degrade) if degrade.read_only_api() => Outcome::Error((Status::ServiceUnavailable, "The API is, temporarily, read-only")),
+            _ => Outcome::Success(WritableApi),
+        }
+    }
+}
+
+/// Request guard for the routes requiring an identity -- verified by the [AuthProvider] -- presented either as an API key (in the `X-API-Key` header),
+/// as a bearer token (`Authorization: Bearer <token>`) or as a name & password (`Authorization: Basic <base64 of name:password>`).\
+/// Fails with "401 Unauthorized" if no valid credentials are presented -- but, if [crate::config::Config::auth] is disabled, everyone passes as
+/// [Principal::anonymous()]
+pub struct Authenticated(pub Principal);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authenticated {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(auth) = request.rocket().state::<Arc<dyn AuthProvider>>() else {
+            return Outcome::Success(Authenticated(Principal::anonymous()))
+        };
+        let authorization = request.headers().get_one("Authorization");
+        let result: AuthResult = if let Some(api_key) = request.headers().get_one("X-API-Key") {
+            auth.verify_api_key(api_key).await
+        } else if let Some(token) = authorization.and_then(|authorization| authorization.strip_prefix("Bearer ")) {
+            auth.verify_token(token).await
+        } else if let Some(credentials) = authorization.and_then(|authorization| authorization.strip_prefix("Basic "))
+                                                       .and_then(|encoded| BASE64.decode(encoded.trim()).ok())
+                                                       .and_then(|decoded| String::from_utf8(decoded).ok()) {
+            match credentials.split_once(':') {
+                Some((name, password)) => auth.verify_password(name, password).await,
+                None => Ok(None),
+            }
+        } else {
+            Ok(None)
+        };
+        match result {
+            Ok(Some(principal)) => Outcome::Success(Authenticated(principal)),
+            Ok(None) => Outcome::Error((Status::Unauthorized, "Missing or invalid credentials")),
+            Err(err) => {
+                warn!("Web: could not verify the credentials for {}: {}", request.uri(), err);
+                Outcome::Error((Status::ServiceUnavailable, "Credentials could not be verified"))
+            },
+        }
+    }
+}
+
+
+/// All metrics published through the `metrics` facade, in the OpenMetrics text format -- to be scraped by Prometheus & friends
+#[get("/metrics")]
+fn metrics(metrics: &State<PrometheusHandle>) -> (ContentType, String) {
+    (ContentType::new("application", "openmetrics-text").with_params([("version", "1.0.0"), ("charset", "utf-8")]),
+     metrics.render())
+}
+
+/// Per-endpoint statistics of the socket server -- see [SocketServerStats]
+#[get("/stats/socket")]
+fn socket_stats(socket_server_stats: &State<Arc<SocketServerStats>>) -> Json<SocketServerStatsReport> {
+    Json(socket_server_stats.report())
+}
+
+/// The services' states, the metrics, the connected socket clients & the recent logs -- as shown by the egui "Runtime" window, which
+/// the web app feeds from here
+#[get("/stats/runtime")]
+async fn runtime_stats(runtime: &State<Arc<RwLock<Runtime>>>) -> Json<RuntimeStatus> {
+    Json(frontend::runtime_status(runtime).await)
+}
+
+/// Metrics for the [ResponseCache] -- hits, misses, ...
+#[get("/stats/cache")]
+fn cache_stats(response_cache: &State<Arc<ResponseCache>>) -> Json<ResponseCacheReport> {
+    Json(response_cache.report())
+}
+
+/// How the command limits of each running Telegram bot are holding -- see [crate::config::TelegramConfig::command_limits]
+#[get("/stats/telegram")]
+async fn telegram_stats(runtime: &State<Arc<RwLock<Runtime>>>) -> Json<BTreeMap<String, CommandThrottleReport>> {
+    let bot_names = runtime.read().await.telegram_bot_names();
+    let mut reports = BTreeMap::new();
+    for bot_name in bot_names {
+        let report = Runtime::do_if_telegram_ui_is_present(runtime, &bot_name, |telegram_ui| Box::pin(std::future::ready(telegram_ui.command_throttle_report()))).await;
+        if let Some(report) = report {
+            reports.insert(bot_name, report);
+        }
+    }
+    Json(reports)
+}
+
+/// Resources used by the most recent job runs -- the newest first -- see [JobAccounting]
+#[get("/stats/jobs")]
+fn jobs_stats(job_accounting: &State<Arc<JobAccounting>>) -> Json<Vec<JobRun>> {
+    Json(job_accounting.recent_runs())
+}
+
+/// Counters & dead letters of the [JobQueue]
+#[get("/stats/job-queue")]
+fn job_queue_stats(job_queue: &State<Arc<JobQueue>>) -> Json<JobQueueReport> {
+    Json(job_queue.report())
+}
+
+/// The latest sample of the process' resources -- `null` until the first one is taken. See [SelfMonitor]
+#[get("/stats/self-monitor")]
+fn self_monitor_stats(self_monitor: &State<Arc<SelfMonitor>>) -> Json<Option<ResourcesSample>> {
+    Json(self_monitor.latest())
+}
+
+/// The state of the scheduled jobs -- their last & next runs, ... -- see [Scheduler]
+#[get("/stats/scheduler")]
+fn scheduler_stats(scheduler: &State<Arc<Scheduler>>) -> Json<Vec<ScheduledJobStatus>> {
+    Json(scheduler.report())
+}
+
+/// The upcoming runs of the scheduled jobs, for the next `days` (7, if not given -- up to [MAX_SCHEDULE_DAYS]) -- as an iCalendar feed,
+/// so operators may subscribe to it from their calendar apps. See [Scheduler::upcoming_runs()]
+#[get("/schedule.ics?<days>")]
+fn schedule_ics(days: Option<u32>, scheduler: &State<Arc<Scheduler>>) -> (ContentType, String) {
+    let until = chrono::Local::now() + chrono::Duration::days(days.unwrap_or(7).min(MAX_SCHEDULE_DAYS) as i64);
+    (ContentType::Calendar, scheduler.icalendar(until, MAX_SCHEDULED_RUNS_PER_JOB))
+}
+
+/// The names of the metrics whose history is kept -- see [MetricsHistory]
+#[get("/stats/history")]
+fn metrics_history_names(metrics_history: &State<Arc<MetricsHistory>>) -> Json<Vec<String>> {
+    Json(metrics_history.names())
+}
+
+/// The recent history of `metric` -- the oldest samples first -- from `since` (seconds since the Unix epoch), if given.
+/// "404 Not Found" if its history is not kept
+#[get("/stats/history/<metric>?<since>")]
+fn metrics_history(metric: &str, since: Option<i64>, metrics_history: &State<Arc<MetricsHistory>>) -> Option<Json<Vec<MetricSample>>> {
+    metrics_history.history(metric, since).map(Json)
+}
+
+/// Issues, to the authenticated identity, a short-lived session token to be presented in the socket server's `Login` handshake
+/// (when its login is set to use the auth provider) -- so both frontends share the same identity. See [SessionTokens]
+#[post("/session/socket-token")]
+fn issue_socket_token(authenticated: Authenticated, session_tokens: &State<Arc<SessionTokens>>) -> Json<SessionToken> {
+    Json(session_tokens.issue(&authenticated.0))
+}
+
+/// Revokes one of the authenticated identity's session tokens -- "404 Not Found" if it is not among them
+#[delete("/session/socket-token/<token>")]
+fn revoke_socket_token(authenticated: Authenticated, token: &str, session_tokens: &State<Arc<SessionTokens>>) -> Status {
+    if session_tokens.revoke(token, &authenticated.0) { Status::NoContent } else { Status::NotFound }
+}
+
+/// Revokes all of the authenticated identity's session tokens -- answering how many were revoked
+#[delete("/session/socket-tokens")]
+fn revoke_socket_tokens(authenticated: Authenticated, session_tokens: &State<Arc<SessionTokens>>) -> Json<usize> {
+    Json(session_tokens.revoke_all(&authenticated.0.name))
+}
+
+/// Readiness probe: "200 OK" once the services are started & warmed up -- "503 Service Unavailable" before that. See [Warmup]
+#[get("/ready")]
+fn ready(warmup: &State<Arc<Warmup>>) -> (Status, Json<WarmupReport>) {
+    let status = if warmup.is_ready() { Status::Ok } else { Status::ServiceUnavailable };
+    (status, Json(warmup.report()))
+}
+
+/// The most recent outbound webhook deliveries -- the newest first -- see [Webhooks]. Requires authentication
+#[get("/webhooks/deliveries")]
+fn webhook_deliveries(_authenticated: Authenticated, webhooks: &State<Arc<Webhooks>>) -> Json<Vec<Delivery>> {
+    Json(webhooks.recent_deliveries())
+}
+
+/// Server-Sent Events streaming the log records (as "log" events) -- at least as severe as `level` ("info", if absent) &, if given, from `target`
+/// (in whole `::` segments: "socket_server", "rocket", ...). Starts with up to `recent` of the latest records already logged (all the
+/// ones kept, if absent -- see [log_tail::RECENT_RECORDS]), then follows the ones logged from now on. Records missed by slow followers
+/// are reported through "lagged" events. Requires authentication -- this is what `console tail` follows
+#[get("/logs/tail?<level>&<target>&<recent>")]
+fn logs_tail(_authenticated: Authenticated, level: Option<&str>, target: Option<&str>, recent: Option<usize>, mut shutdown: Shutdown) -> Result<EventStream![], (Status, String)> {
+    let filter = TailFilter::new(level.unwrap_or("info"), target)
+        .map_err(|err| (Status::BadRequest, err.to_string()))?;
+    let (recent_records, mut records) = log_tail::subscribe();
+    let mut recent_records: Vec<TailedRecord> = recent_records.into_iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+    if let Some(recent) = recent {
+        recent_records = recent_records.split_off(recent_records.len().saturating_sub(recent));
+    }
+    Ok(EventStream! {
+        for record in recent_records {
+            yield Event::json(&record).event("log");
+        }
+        loop {
+            let record = tokio::select! {
+                record = records.recv() => record,
+                _ = &mut shutdown => break,
+            };
+            match record {
+                Ok(record) if filter.matches(&record) => yield Event::json(&record).event("log"),
+                Ok(_) => (),
+                Err(RecvError::Lagged(missed)) => yield Event::data(missed.to_string()).event("lagged"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
 
 /// A simple rest service demo, returning a JSON built out of a string
 #[get("/rest-service/<world>")]
@@ -28,34 +326,37 @@ fn rest_service(world: &str) -> RawJson {
 }
 
 /// A simple get service demo using native types and a custom enum, returning a JSON built out of a string
+/// -- pretending to be expensive, its answers are cached -- see [ResponseCache]
 #[get("/get-service?<from_temperature>&<from_length>&<conversion>")]
-fn get_service(from_temperature: f64, from_length: f64, conversion: Conversions) -> RawJson {
-    let (from_temperature_unit, from_length_unit,
-        to_temperature, to_length,
-        to_temperature_unit, to_length_unit) = match conversion {
-        Conversions::MetricToImperial => ("°C", "m",  (from_temperature * 9.0/5.0) + 32.0, from_length * 3.2808398950132, "°F", "ft"),
-        Conversions::ImperialToMetric => ("°F", "ft", (from_temperature - 32.0) * 5.0/9.0, from_length / 3.2808398950132, "°C", "m")
-    };
-    RawJson { json: format!("{{\
-                                \"from_temperature\": \"{:.2}{}\",
-                                \"from_length\":      \"{:.2}{}\",
-                                \"to_temperature\":   \"{:.2}{}\",
-                                \"to_length\":        \"{:.2}{}\"
-                            }}",
-                            from_temperature, from_temperature_unit,
-                            from_length,      from_length_unit,
-                            to_temperature,   to_temperature_unit,
-                            to_length,        to_length_unit) }
+async fn get_service(from_temperature: f64, from_length: f64, conversion: Conversions,
+                     cacheable: Cacheable, response_cache: &State<Arc<ResponseCache>>) -> CachedJson {
+    response_cache.get_or_compute(cacheable, Duration::from_secs(60), || async move {
+        convert(from_temperature, from_length, conversion)
+    }).await
+}
+fn convert(from_temperature: f64, from_length: f64, conversion: Conversions) -> String {
+    let conversion = UnitsConversion::compute(conversion.into(), from_temperature, from_length);
+    rocket::serde::json::to_string(&conversion.to_dto())
+        .unwrap_or_else(|err| format!(r#"{{"error":"could not serialize the conversion: {}"}}"#, err))
 }
 #[derive(Debug, PartialEq, FromFormField)]
 enum Conversions {
     MetricToImperial,
     ImperialToMetric,
 }
+impl From<Conversions> for ConversionDirection {
+    fn from(conversion: Conversions) -> Self {
+        match conversion {
+            Conversions::MetricToImperial => ConversionDirection::MetricToImperial,
+            Conversions::ImperialToMetric => ConversionDirection::ImperialToMetric,
+        }
+    }
+}
 
 /// A simple post service demo receiving & sending a JSON made out of a struct
+/// -- considered to change the application's state, so it is refused when the API is read-only
 #[post("/post-service", format = "json", data = "<shipping_info_json>")]
-fn post_service(shipping_info_json: Json<ShippingInfo>) -> Json<ShippingInfo> {
+fn post_service(_writable: WritableApi, shipping_info_json: Json<ShippingInfo>) -> Json<ShippingInfo> {
     let shipping_info = shipping_info_json.into_inner();
     Json(shipping_info)
 }
@@ -77,4 +378,53 @@ struct ShippingInfo {
 #[response(status = 200, content_type = "json")]
 struct RawJson {
     json: String,
-}
\ No newline at end of file
+}
+
+/// Unit tests the [api](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AuthConfig, IdentityConfig},
+        runtime::{new_auth_provider, SESSION_TOKEN_TTL},
+    };
+    use rocket::{
+        http::Header,
+        local::asynchronous::Client,
+    };
+
+
+    const API_KEY: &str = "alice-api-key";
+
+
+    /// assures the web guards refuse the socket session tokens -- which are only for the socket server's login: otherwise, they
+    /// would grant the whole API & could be renewed, forever, through `/session/socket-token`
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn session_tokens_are_refused() {
+        let auth = new_auth_provider(&AuthConfig::Static { identities: vec![IdentityConfig {
+            name:          String::from("alice"),
+            password_hash: String::new(),
+            api_keys:      vec![String::from(API_KEY)],
+            roles:         vec![],
+        }] }).expect("Instantiating the auth provider");
+        let session_tokens = Arc::new(SessionTokens::new(SESSION_TOKEN_TTL));
+        let rocket = rocket::build()
+            .manage(auth)
+            .manage(Arc::clone(&session_tokens))
+            .mount(BASE_PATH, session_routes());
+        let client = Client::tracked(rocket).await
+            .expect("Igniting Rocket");
+        let path = format!("{}/session/socket-token", BASE_PATH);
+
+        let response = client.post(path.as_str())
+            .header(Header::new("X-API-Key", API_KEY))
+            .dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "Web-authenticated identities should have been issued a session token");
+
+        let session_token = session_tokens.issue(&Principal { name: String::from("alice"), roles: vec![] });
+        let response = client.post(path.as_str())
+            .header(Header::new("Authorization", format!("Bearer {}", session_token.token)))
+            .dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized, "Session tokens should have been refused by the web guards");
+    }
+}
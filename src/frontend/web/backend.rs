@@ -1,14 +1,42 @@
 //! Place here any methods your web UI uses
+//!
+//! The `/stats/*` methods are the data source for the dashboard widgets: they are shaped for them -- summarizing what `/api/stats/*`
+//! tells in detail -- and are, also, pushed periodically through the Server-Sent Events of `/stats/updates`.\
+//! Notice the `web-stats` app is, still, the stock `coreui-free-angular-admin-template` (a git submodule): its widgets are yet to be
+//! wired to these methods -- which, meanwhile, may feed any other dashboard.
 
-use rocket::get;
+use crate::runtime::{DegradeSwitches, Warmup, WarmupStatus, JobAccounting, Webhooks, procfs};
+use crate::frontend::socket_server::SocketServerStats;
+use super::response_cache::{ResponseCache, ResponseCacheReport};
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+use rocket::{
+    get, State, Shutdown,
+    response::stream::{Event, EventStream},
+    serde::{json::Json, Serialize},
+};
 
 
 pub const BASE_PATH: &str = "/backend";
 
+/// How often `/stats/updates` pushes the stats
+const UPDATES_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Up to how many of the most recent events `/stats/events` lists
+const MAX_EVENTS: usize = 50;
+
+
 /// all methods exported by this module
 pub fn routes() -> Vec<rocket::Route> {
     rocket::routes![
         info,
+        system_stats,
+        health_stats,
+        counters_stats,
+        events_stats,
+        stats_updates,
     ]
 }
 
@@ -16,4 +44,163 @@ pub fn routes() -> Vec<rocket::Route> {
 #[get("/info")]
 fn info() -> &'static str {
     "Backend written in Rust!"
-}
\ No newline at end of file
+}
+
+/// For the "system" widgets -- the fields are absent if they couldn't be read from `/proc` (like when not running on Linux)
+#[derive(Debug, Serialize)]
+pub struct SystemStats {
+    pub uptime_seconds:      Option<f64>,
+    pub resident_memory_kib: Option<u64>,
+    pub threads:             Option<u64>,
+    /// the system's 1, 5 & 15 minutes load averages
+    pub load_average:        Option<[f64; 3]>,
+}
+
+/// For the "health" widgets
+#[derive(Debug, Serialize)]
+pub struct HealthStats {
+    /// `Done` when ready to serve -- see `/api/ready`
+    pub warmup:                          WarmupStatus,
+    pub maintenance:                     bool,
+    pub read_only_api:                   bool,
+    pub telegram_notifications_disabled: bool,
+}
+
+/// For the "counters" widgets
+#[derive(Debug, Serialize)]
+pub struct CountersStats {
+    pub socket:    SocketCounters,
+    pub web_cache: ResponseCacheReport,
+}
+
+/// Totals of the socket server's per-client statistics -- for the tracked clients only. See `/api/stats/socket`
+#[derive(Debug, Serialize)]
+pub struct SocketCounters {
+    pub connected_clients: usize,
+    pub tracked_clients:   usize,
+    pub messages_in:       u64,
+    pub messages_out:      u64,
+    pub bytes_in:          u64,
+    pub bytes_out:         u64,
+    pub errors:            u64,
+}
+
+/// For the "recent events" widget: job runs & webhook deliveries
+#[derive(Debug, Serialize)]
+pub struct StatsEvent {
+    pub timestamp:   String,
+    /// "job" or "webhook"
+    pub kind:        &'static str,
+    pub description: String,
+    pub succeeded:   bool,
+}
+
+#[get("/stats/system")]
+fn system_stats() -> Json<SystemStats> {
+    Json(system())
+}
+
+#[get("/stats/health")]
+fn health_stats(warmup: &State<Arc<Warmup>>, degrade: &State<Arc<DegradeSwitches>>) -> Json<HealthStats> {
+    Json(health(warmup, degrade))
+}
+
+#[get("/stats/counters")]
+fn counters_stats(socket_server_stats: &State<Arc<SocketServerStats>>, response_cache: &State<Arc<ResponseCache>>) -> Json<CountersStats> {
+    Json(counters(socket_server_stats, response_cache))
+}
+
+/// the newest first
+#[get("/stats/events")]
+fn events_stats(job_accounting: &State<Arc<JobAccounting>>, webhooks: Option<&State<Arc<Webhooks>>>) -> Json<Vec<StatsEvent>> {
+    Json(events(job_accounting, webhooks.map(|webhooks| webhooks.inner().as_ref())))
+}
+
+/// Server-Sent Events pushing the "system", "health", "counters" & "events" stats (named after them) every [UPDATES_INTERVAL]
+#[get("/stats/updates")]
+fn stats_updates(warmup:              &State<Arc<Warmup>>,
+                 degrade:             &State<Arc<DegradeSwitches>>,
+                 socket_server_stats: &State<Arc<SocketServerStats>>,
+                 response_cache:      &State<Arc<ResponseCache>>,
+                 job_accounting:      &State<Arc<JobAccounting>>,
+                 webhooks:            Option<&State<Arc<Webhooks>>>,
+                 mut shutdown:        Shutdown)
+                -> EventStream![] {
+    let warmup = Arc::clone(warmup);
+    let degrade = Arc::clone(degrade);
+    let socket_server_stats = Arc::clone(socket_server_stats);
+    let response_cache = Arc::clone(response_cache);
+    let job_accounting = Arc::clone(job_accounting);
+    let webhooks = webhooks.map(|webhooks| Arc::clone(webhooks));
+    EventStream! {
+        let mut interval = tokio::time::interval(UPDATES_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => (),
+                _ = &mut shutdown => break,
+            }
+            yield Event::json(&system()).event("system");
+            yield Event::json(&health(&warmup, &degrade)).event("health");
+            yield Event::json(&counters(&socket_server_stats, &response_cache)).event("counters");
+            yield Event::json(&events(&job_accounting, webhooks.as_deref())).event("events");
+        }
+    }
+}
+
+
+fn system() -> SystemStats {
+    SystemStats {
+        uptime_seconds:      procfs::uptime_seconds(),
+        resident_memory_kib: procfs::status_value("VmRSS:"),
+        threads:             procfs::status_value("Threads:"),
+        load_average:        procfs::load_average(),
+    }
+}
+
+fn health(warmup: &Warmup, degrade: &DegradeSwitches) -> HealthStats {
+    HealthStats {
+        warmup:                          warmup.report().status,
+        maintenance:                     degrade.maintenance(),
+        read_only_api:                   degrade.read_only_api(),
+        telegram_notifications_disabled: degrade.disable_telegram_notifications(),
+    }
+}
+
+fn counters(socket_server_stats: &SocketServerStats, response_cache: &ResponseCache) -> CountersStats {
+    let socket_report = socket_server_stats.report();
+    let endpoints = &socket_report.endpoints;
+    CountersStats {
+        socket: SocketCounters {
+            connected_clients: endpoints.iter().filter(|endpoint| endpoint.connected).count(),
+            tracked_clients:   endpoints.len(),
+            messages_in:       endpoints.iter().map(|endpoint| endpoint.messages_in).sum(),
+            messages_out:      endpoints.iter().map(|endpoint| endpoint.messages_out).sum(),
+            bytes_in:          endpoints.iter().map(|endpoint| endpoint.bytes_in).sum(),
+            bytes_out:         endpoints.iter().map(|endpoint| endpoint.bytes_out).sum(),
+            errors:            endpoints.iter().map(|endpoint| endpoint.errors).sum(),
+        },
+        web_cache: response_cache.report(),
+    }
+}
+
+fn events(job_accounting: &JobAccounting, webhooks: Option<&Webhooks>) -> Vec<StatsEvent> {
+    let job_events = job_accounting.recent_runs().into_iter()
+        .map(|run| StatsEvent {
+            timestamp:   run.timestamp,
+            kind:        "job",
+            description: format!("{} -- {:.3}s", run.job, run.wall_time_micros as f64 / 1e6),
+            succeeded:   run.succeeded,
+        });
+    let webhook_events = webhooks.map_or_else(Vec::new, |webhooks| webhooks.recent_deliveries()).into_iter()
+        .map(|delivery| StatsEvent {
+            timestamp:   delivery.timestamp,
+            kind:        "webhook",
+            description: format!("'{}' to {} -- {}", delivery.event, delivery.target, delivery.outcome),
+            succeeded:   delivery.delivered,
+        });
+    let mut events: Vec<StatsEvent> = job_events.chain(webhook_events).collect();
+    // timestamps are RFC 3339, in the local timezone: they sort chronologically as strings
+    events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    events.truncate(MAX_EVENTS);
+    events
+}
@@ -0,0 +1,57 @@
+//! Correlation ids for the HTTP requests -- see [crate::runtime::correlated()]: taken from the request's `X-Correlation-Id` header
+//! (if sane) or newly generated & echoed back in the answer's. Routes reaching the business logic get it through the [Correlation]
+//! request guard, running the logic [Correlation::scope()]d with it
+
+use crate::runtime::{correlated, sanitized_correlation_id, CORRELATION_ID_HEADER};
+use std::future::Future;
+use rocket::{
+    Request, Response,
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    request::{FromRequest, Outcome},
+};
+
+
+/// The fairing echoing the correlation id of each request in its answer
+pub struct CorrelationHeader;
+
+/// The request's correlation id -- kept in the request's local cache, so the routes & the answer share it
+struct RequestCorrelationId(String);
+
+#[rocket::async_trait]
+impl Fairing for CorrelationHeader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Correlation ids",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_header(Header::new(CORRELATION_ID_HEADER, request_correlation_id(request).to_string()));
+    }
+}
+
+/// Request guard giving the routes the request's correlation id
+pub struct Correlation(pub String);
+
+impl Correlation {
+    /// runs `future` with this request's correlation id in scope
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        correlated(Some(self.0), future).await
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Correlation {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Correlation(request_correlation_id(request).to_string()))
+    }
+}
+
+/// the correlation id of `request` -- the same for the whole request's lifetime
+fn request_correlation_id<'r>(request: &'r Request<'_>) -> &'r str {
+    &request.local_cache(|| RequestCorrelationId(sanitized_correlation_id(request.headers().get_one(CORRELATION_ID_HEADER)))).0
+}
@@ -0,0 +1,124 @@
+//! The files every browser & crawler asks for -- `/favicon.ico`, `/robots.txt` & `/.well-known/security.txt` -- served even if the
+//! web app is disabled, so services derived from this template don't answer them with 404s.\
+//! Each one may be the built-in default, a file on disk, contents given in the config or disabled -- see [DefaultFilesConfig].
+
+use crate::config::{DefaultFilesConfig, DefaultFileSource};
+use std::{
+    borrow::Cow,
+    io::Cursor,
+};
+use rocket::{
+    get, State,
+    Request, Response,
+    http::{ContentType, uri::Host},
+    response::{self, Responder},
+};
+
+
+pub const BASE_PATH: &str = "/";
+
+/// How long clients may cache the default files
+const CACHE_CONTROL: &str = "public, max-age=86400";
+
+const BUILT_IN_FAVICON: &[u8] = include_bytes!("../../../web-app/src/favicon.ico");
+const BUILT_IN_ROBOTS_TXT: &str = "User-agent: *\nDisallow: /api/\n";
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        favicon,
+        robots_txt,
+        security_txt,
+    ]
+}
+
+/// The contents of the default files -- to be managed by Rocket
+pub struct DefaultFiles {
+    favicon:      Contents,
+    robots_txt:   Contents,
+    security_txt: Contents,
+}
+
+/// [DefaultFileSource], resolved
+enum Contents {
+    /// computed when served, as the built-in `security.txt` depends on the request
+    BuiltIn,
+    Given(Vec<u8>),
+    Disabled,
+}
+
+impl DefaultFiles {
+
+    /// reads the files on disk pointed to by `config` -- failing if any of them can't be read
+    pub fn new(config: &DefaultFilesConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self {
+            favicon:      Contents::new(&config.favicon)?,
+            robots_txt:   Contents::new(&config.robots_txt)?,
+            security_txt: Contents::new(&config.security_txt)?,
+        })
+    }
+}
+
+impl Contents {
+
+    fn new(source: &DefaultFileSource) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        match source {
+            DefaultFileSource::BuiltIn          => Ok(Self::BuiltIn),
+            DefaultFileSource::File(file_path)  => std::fs::read(file_path)
+                .map(Self::Given)
+                .map_err(|err| Box::from(format!("Could not read the default file '{}': {}", file_path, err))),
+            DefaultFileSource::Content(content) => Ok(Self::Given(content.as_bytes().to_vec())),
+            DefaultFileSource::Disabled         => Ok(Self::Disabled),
+        }
+    }
+
+    /// these contents -- or `built_in`'s, if they are the built-in ones. `None` if disabled
+    fn or_built_in<'r>(&'r self, built_in: impl FnOnce() -> Cow<'r, [u8]>) -> Option<Cow<'r, [u8]>> {
+        match self {
+            Self::BuiltIn         => Some(built_in()),
+            Self::Given(contents) => Some(Cow::Borrowed(contents)),
+            Self::Disabled        => None,
+        }
+    }
+}
+
+/// the built-in `security.txt`, for `host` -- see RFC 9116
+fn built_in_security_txt(host: &str) -> String {
+    let expires = chrono::Utc::now() + chrono::Duration::days(365);
+    format!("Contact: mailto:security@{}\nExpires: {}\nPreferred-Languages: en\n", host, expires.format("%Y-%m-%dT%H:%M:%S%.3fZ"))
+}
+
+#[get("/favicon.ico")]
+fn favicon(default_files: &State<DefaultFiles>) -> Option<DefaultFile<'_>> {
+    default_files.favicon.or_built_in(|| Cow::Borrowed(BUILT_IN_FAVICON))
+        .map(|contents| DefaultFile { content_type: ContentType::Icon, contents })
+}
+
+#[get("/robots.txt")]
+fn robots_txt(default_files: &State<DefaultFiles>) -> Option<DefaultFile<'_>> {
+    default_files.robots_txt.or_built_in(|| Cow::Borrowed(BUILT_IN_ROBOTS_TXT.as_bytes()))
+        .map(|contents| DefaultFile { content_type: ContentType::Plain, contents })
+}
+
+#[get("/.well-known/security.txt")]
+fn security_txt<'r>(default_files: &'r State<DefaultFiles>, host: Option<&Host<'_>>) -> Option<DefaultFile<'r>> {
+    let host = host.map_or("localhost", |host| host.domain().as_str());
+    default_files.security_txt.or_built_in(|| Cow::Owned(built_in_security_txt(host).into_bytes()))
+        .map(|contents| DefaultFile { content_type: ContentType::Plain, contents })
+}
+
+struct DefaultFile<'r> {
+    content_type: ContentType,
+    contents:     Cow<'r, [u8]>,
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for DefaultFile<'o> {
+    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'o> {
+        Response::build()
+            .header(self.content_type)
+            .raw_header("Cache-Control", CACHE_CONTROL)
+            .sized_body(self.contents.len(), Cursor::new(self.contents))
+            .ok()
+    }
+}
@@ -2,4 +2,14 @@
 //! includes the file generated by `build.rs` containing the internal plain & compressed static files to be served
 
 
+/// An embedded file, along with its compressed variants -- which are only present if worth it
+pub struct EmbeddedFileData {
+    /// the digest of the plain contents -- from which the `ETag`s of all variants are built
+    pub digest:   &'static str,
+    /// the plain contents
+    pub identity: &'static [u8],
+    pub gzip:     Option<&'static [u8]>,
+    pub brotli:   Option<&'static [u8]>,
+}
+
 include!(concat!(env!("OUT_DIR"), "/embedded_files.rs"));
\ No newline at end of file
@@ -0,0 +1,123 @@
+//! Streams the events of the internal event bus -- see [crate::runtime::EventBus] -- as Server-Sent Events named after their topics,
+//! with their sequence numbers as ids & their payloads as JSON data:
+//!   * `/api/events/follow` -- the events published from now on, for dashboards & the like.
+//!     See [crate::config::WebConfig::ogre_events_following_routes]
+//!   * `/api/events/queue` -- for external services consuming the events: the recent events after the given one (by the `after` parameter
+//!     or by the standard `Last-Event-ID` header, sent by reconnecting `EventSource`s) are replayed before following the new ones.
+//!     See [crate::config::WebConfig::ogre_events_queue_routes]
+//!
+//! Both accept `topics` -- a comma separated list of the topics of interest -- and send a "lagged" event, with how many events were missed,
+//! whenever the consumer falls behind.
+
+use super::api::Authenticated;
+use crate::runtime::{EventBus, PublishedEvent};
+use std::sync::Arc;
+use rocket::{
+    get, State, Shutdown,
+    request::{FromRequest, Outcome, Request},
+    response::stream::{Event, EventStream},
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+
+pub const BASE_PATH: &str = "/api/events";
+
+
+/// methods exported by this module only when [crate::config::WebConfig::ogre_events_following_routes] is enabled
+pub fn following_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        follow,
+    ]
+}
+
+/// methods exported by this module only when [crate::config::WebConfig::ogre_events_queue_routes] is enabled
+pub fn queue_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        queue,
+    ]
+}
+
+
+/// The `Last-Event-ID` header -- the last event seen by a reconnecting consumer
+pub struct LastEventId(Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(LastEventId(request.headers().get_one("Last-Event-ID").and_then(|seq| seq.trim().parse().ok())))
+    }
+}
+
+/// the events published from now on
+#[get("/follow?<topics>")]
+fn follow(_authenticated: Authenticated, topics: Option<&str>, events: &State<Arc<EventBus>>, shutdown: Shutdown) -> EventStream![] {
+    stream(TopicsFilter::new(topics), vec![], 0, events.follow(), shutdown)
+}
+
+/// the recent events after the `after`-th -- or after the one in the `Last-Event-ID` header -- followed by the ones published from now on.
+/// Without either, only the events published from now on are sent
+#[get("/queue?<after>&<topics>")]
+fn queue(_authenticated: Authenticated, after: Option<u64>, last_event_id: LastEventId, topics: Option<&str>,
+         events: &State<Arc<EventBus>>, shutdown: Shutdown) -> EventStream![] {
+    let filter = TopicsFilter::new(topics);
+    match after.or(last_event_id.0) {
+        Some(after) => {
+            let (recent_events, missed, receiver) = events.follow_after(after);
+            stream(filter, recent_events, missed, receiver, shutdown)
+        },
+        None => stream(filter, vec![], 0, events.follow(), shutdown),
+    }
+}
+
+
+/// The topics a consumer is interested in -- all, if none are given
+struct TopicsFilter(Vec<String>);
+
+impl TopicsFilter {
+
+    fn new(topics: Option<&str>) -> Self {
+        Self(topics.map_or_else(Vec::new, |topics| topics.split(',')
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty())
+            .collect()))
+    }
+
+    fn matches(&self, event: &PublishedEvent) -> bool {
+        self.0.is_empty() || self.0.iter().any(|topic| topic == event.topic)
+    }
+}
+
+/// sends the "lagged" event, if any events were `missed`, then the `recent_events` & the ones published from now on -- until `shutdown`
+fn stream(filter: TopicsFilter, recent_events: Vec<PublishedEvent>, missed: u64,
+          mut receiver: broadcast::Receiver<PublishedEvent>, mut shutdown: Shutdown) -> EventStream![] {
+    EventStream! {
+        if missed > 0 {
+            yield Event::data(missed.to_string()).event("lagged");
+        }
+        for event in recent_events {
+            if filter.matches(&event) {
+                yield to_sse(&event);
+            }
+        }
+        loop {
+            let event = tokio::select! {
+                event = receiver.recv() => event,
+                _ = &mut shutdown => break,
+            };
+            match event {
+                Ok(event) if filter.matches(&event) => yield to_sse(&event),
+                Ok(_) => (),
+                Err(RecvError::Lagged(missed)) => yield Event::data(missed.to_string()).event("lagged"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn to_sse(event: &PublishedEvent) -> Event {
+    Event::json(&event.payload)
+        .event(event.topic)
+        .id(event.seq.to_string())
+}
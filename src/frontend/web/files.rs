@@ -1,14 +1,20 @@
-//! Exposes methods that allow serving files
+//! Exposes methods that allow serving files.\
+//! Embedded files are sent brotli or gzip compressed -- as negotiated through the `Accept-Encoding` header, falling back to the plain
+//! contents for clients accepting neither (or for files not worth compressing). Each variant is validated by its build-time `ETag` -- answering `304 Not Modified` to matching `If-None-Match` requests --
+//! and may be partially retrieved through single `Range`s (`If-Range` aware). `HEAD` requests are answered by Rocket's automatic handling
+//! of the `GET` route, which keeps all headers -- `Content-Length` included -- but strips the body.\
+//! For development, the static files may be served straight from the disk instead -- see [crate::config::WebConfig::static_files_dir]
 
-use super::embedded_files;
+use super::embedded_files::{self, EmbeddedFileData};
 use std::{
     io::Cursor,
     path::PathBuf,
 };
 use rocket::{
-    get,
+    get, State,
     Request,
     Response,
+    fs::NamedFile,
     response::{self, Responder},
     http::{
         ContentType,
@@ -26,42 +32,266 @@ pub fn routes() -> Vec<rocket::Route> {
     ]
 }
 
+/// methods exported by this module -- instead of [routes()] -- when [crate::config::WebConfig::static_files_dir] is set
+pub fn disk_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        get_disk_file,
+    ]
+}
+
+/// The directory the static files are served from -- see [crate::config::WebConfig::static_files_dir]
+pub struct StaticFilesDir(pub PathBuf);
+
 /// serves statically linked files (to the executable) for blazing-fast speeds
 /// (no context switches nor cache additions/evictions)
-/// -- for more details, see `build.rs`
+/// -- for more details, see `build.rs`.\
+/// `HEAD` requests are also served here -- see [self]
 #[get("/<file..>")]
 fn get_embedded_file(file: PathBuf) -> EmbeddedFile {
     let internal_file_name = format!("/{}", file.to_string_lossy().to_string());
     EmbeddedFile {file_name: internal_file_name}
 }
 
+/// serves the static files straight from the disk, for development -- with caching disabled, so rebuilt apps are seen on reload.
+/// Directories -- like the root -- are served by their `index.html`
+#[get("/<file..>")]
+async fn get_disk_file(file: PathBuf, static_files_dir: &State<StaticFilesDir>) -> Option<DiskFile> {
+    let mut path = static_files_dir.0.join(file);
+    if tokio::fs::metadata(&path).await.map_or(false, |metadata| metadata.is_dir()) {
+        path.push("index.html");
+    }
+    NamedFile::open(path).await.ok().map(DiskFile)
+}
+
+/// A file on disk -- sent with caching disabled
+struct DiskFile(NamedFile);
+
+impl<'r> Responder<'r, 'static> for DiskFile {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        Response::build_from(self.0.respond_to(req)?)
+            .raw_header("Cache-Control", "no-store")
+            .ok()
+    }
+}
+
 struct EmbeddedFile {
     file_name: String,
 }
 
 impl<'r> Responder<'r, 'r> for EmbeddedFile {
-    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'r> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
         let file_name = self.file_name;
-        let (compressed, file_contents) = match embedded_files::STATIC_FILES.get(file_name.as_str()) {
-            Some(tuple) => tuple,
+        let file = match embedded_files::STATIC_FILES.get(file_name.as_str()) {
+            Some(file) => file,
             None => return Result::Err(Status{code:404}),
         };
         let file_extension = match file_name.rsplit_once(".") {
             Some((_file_name_before_last_dot, file_extension)) => file_extension,
             None => "html",
         };
+        let encoding = Encoding::negotiate(req.headers().get_one("Accept-Encoding"), file);
+        let file_contents = encoding.contents(file);
+        // each variant has its own ETag, as required for strong validators
+        let etag = format!("\"{}{}\"", file.digest, encoding.etag_suffix());
         let mut response_builder = Response::build();
-        response_builder.header(ContentType::from_extension(file_extension).unwrap());
-        if *compressed {
-            // informs the client the content is compressed
-            response_builder.raw_header("Content-Encoding", embedded_files::CONTENT_ENCODING);
-        }
         response_builder
+            .raw_header("ETag", etag.clone())
             // enforce caching on the client
             .raw_header("Cache-Control", embedded_files::CACHE_CONTROL)
             .raw_header("expires",       embedded_files::EXPIRATION_DATE)
-            .raw_header("last-modified", embedded_files::GENERATION_DATE)
-            .sized_body(file_contents.len(), Cursor::new(file_contents))
-            .ok()
+            .raw_header("last-modified", embedded_files::GENERATION_DATE);
+        if file.gzip.is_some() || file.brotli.is_some() {
+            // caches must keep one response per accepted encoding
+            response_builder.raw_header("Vary", "Accept-Encoding");
+        }
+        // the client's cached copy is still good
+        if req.headers().get_one("If-None-Match").map_or(false, |if_none_match| etag_matches(if_none_match, &etag)) {
+            return response_builder
+                .status(Status::NotModified)
+                .ok()
+        }
+        response_builder
+            .header(ContentType::from_extension(file_extension).unwrap())
+            .raw_header("Accept-Ranges", "bytes");
+        if let Some(content_encoding) = encoding.content_encoding() {
+            // informs the client the content is compressed
+            response_builder.raw_header("Content-Encoding", content_encoding);
+        }
+        // ranges refer to the contents as sent -- compressed or not -- & are only honored if the client's partial copy is still good
+        let range = req.headers().get_one("Range")
+            .filter(|_| req.headers().get_one("If-Range").map_or(true, |if_range| etag_matches(if_range, &etag)));
+        match range.map(|range| parse_range(range, file_contents.len())) {
+            Some(ByteRange::Satisfiable(start, end)) => {
+                let part = &file_contents[start..=end];
+                response_builder
+                    .status(Status::PartialContent)
+                    .raw_header("Content-Range", format!("bytes {}-{}/{}", start, end, file_contents.len()))
+                    .sized_body(part.len(), Cursor::new(part))
+                    .ok()
+            },
+            Some(ByteRange::Unsatisfiable) => response_builder
+                .status(Status::RangeNotSatisfiable)
+                .raw_header("Content-Range", format!("bytes */{}", file_contents.len()))
+                .ok(),
+            Some(ByteRange::Ignored) | None => response_builder
+                .sized_body(file_contents.len(), Cursor::new(file_contents))
+                .ok(),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// The variants an embedded file may be sent as
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Brotli,
+    GZip,
+    Identity,
+}
+
+impl Encoding {
+
+    /// the best variant of `file` for a client sending the `accept_encoding` header: the compressed one with the highest quality
+    /// (brotli winning ties, for its better ratios) -- or the plain contents, if the client accepts none of the available ones
+    fn negotiate(accept_encoding: Option<&str>, file: &EmbeddedFileData) -> Self {
+        let Some(accept_encoding) = accept_encoding else {
+            return Self::Identity
+        };
+        [Self::Brotli, Self::GZip].into_iter()
+            .filter(|encoding| encoding.available_contents(file).is_some())
+            .map(|encoding| (encoding, quality(accept_encoding, encoding.content_encoding().unwrap_or("identity"))))
+            .filter(|(_encoding, quality)| *quality > 0.0)
+            .fold(None, |best: Option<(Self, f32)>, (encoding, quality)| match best {
+                Some((_best_encoding, best_quality)) if best_quality >= quality => best,
+                _ => Some((encoding, quality)),
+            })
+            .map_or(Self::Identity, |(encoding, _quality)| encoding)
+    }
+
+    /// the `Content-Encoding` header value for this variant -- `None` for the plain contents
+    fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Self::Brotli   => Some("br"),
+            Self::GZip     => Some("gzip"),
+            Self::Identity => None,
+        }
+    }
+
+    fn etag_suffix(self) -> &'static str {
+        match self {
+            Self::Brotli   => "-br",
+            Self::GZip     => "-gzip",
+            Self::Identity => "",
+        }
+    }
+
+    fn available_contents(self, file: &EmbeddedFileData) -> Option<&'static [u8]> {
+        match self {
+            Self::Brotli   => file.brotli,
+            Self::GZip     => file.gzip,
+            Self::Identity => Some(file.identity),
+        }
+    }
+
+    /// the contents of this variant of `file` -- the plain ones, if it was not worth compressing
+    fn contents(self, file: &EmbeddedFileData) -> &'static [u8] {
+        self.available_contents(file).unwrap_or(file.identity)
+    }
+}
+
+/// the quality (`q`) the `Accept-Encoding` header value gives to `coding` -- or to `*`, if `coding` is not listed. 0 if not acceptable
+fn quality(accept_encoding: &str, coding: &str) -> f32 {
+    let mut wildcard_quality = 0.0;
+    for item in accept_encoding.split(',') {
+        let mut parameters = item.split(';');
+        let name = parameters.next().unwrap_or("").trim();
+        let quality = parameters
+            .find_map(|parameter| parameter.trim().strip_prefix("q="))
+            .and_then(|quality| quality.trim().parse().ok())
+            .unwrap_or(1.0);
+        if name.eq_ignore_ascii_case(coding) {
+            return quality
+        } else if name == "*" {
+            wildcard_quality = quality;
+        }
+    }
+    wildcard_quality
+}
+
+/// tells if `etag` is listed in the `If-None-Match` or `If-Range` header value -- `*` & weak validators (`W/"..."`) included
+fn etag_matches(header_value: &str, etag: &str) -> bool {
+    header_value.split(',')
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// The outcome of parsing a `Range` header -- see [parse_range()]
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// the inclusive `(start, end)` positions of the requested bytes
+    Satisfiable(usize, usize),
+    /// the range starts beyond the contents -- to be answered with `416 Range Not Satisfiable`
+    Unsatisfiable,
+    /// malformed, multiple or in units other than bytes -- the whole contents are to be sent, as the standard allows
+    Ignored,
+}
+
+/// parses a single range `Range` header -- `bytes=first-last`, `bytes=first-` or `bytes=-suffix_length` -- for contents of `len` bytes
+fn parse_range(range: &str, len: usize) -> ByteRange {
+    let Some((first, last)) = range.trim().strip_prefix("bytes=")
+                                         .filter(|spec| !spec.contains(','))
+                                         .and_then(|spec| spec.split_once('-')) else {
+        return ByteRange::Ignored
+    };
+    let (first, last) = (first.trim(), last.trim());
+    let (start, end) = match (first.parse::<usize>(), last.parse::<usize>()) {
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        (Ok(start), Err(_)) if last.is_empty() => (start, len.saturating_sub(1)),
+        (Err(_), Ok(suffix_length)) if first.is_empty() => {
+            if suffix_length == 0 {
+                return ByteRange::Unsatisfiable
+            }
+            (len.saturating_sub(suffix_length), len.saturating_sub(1))
+        },
+        _ => return ByteRange::Ignored,
+    };
+    if start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Satisfiable(start, end)
+    }
+}
+
+
+/// Unit tests the [files](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn encoding_negotiation() {
+        let file = EmbeddedFileData { digest: "0", identity: b"plain", gzip: Some(b"gzip"), brotli: Some(b"brotli") };
+        let plain_file = EmbeddedFileData { digest: "0", identity: b"plain", gzip: None, brotli: None };
+        assert_eq!(Encoding::negotiate(Some("gzip, deflate, br"), &file),  Encoding::Brotli,   "Brotli should win ties");
+        assert_eq!(Encoding::negotiate(Some("gzip, br;q=0.5"), &file),     Encoding::GZip,     "Qualities should be honored");
+        assert_eq!(Encoding::negotiate(Some("gzip;q=0, *"), &file),        Encoding::Brotli,   "The wildcard should cover unlisted codings");
+        assert_eq!(Encoding::negotiate(Some("deflate"), &file),            Encoding::Identity, "Unsupported codings should fall back to identity");
+        assert_eq!(Encoding::negotiate(None, &file),                       Encoding::Identity, "Clients not sending Accept-Encoding should get the plain contents");
+        assert_eq!(Encoding::negotiate(Some("gzip, br"), &plain_file),     Encoding::Identity, "Files not worth compressing should be sent plain");
+        assert_eq!(Encoding::GZip.contents(&file), b"gzip", "The contents should match the negotiated encoding");
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn ranges_and_etags() {
+        assert_eq!(parse_range("bytes=0-9",    100), ByteRange::Satisfiable(0, 9),   "Explicit ranges should be honored");
+        assert_eq!(parse_range("bytes=90-",    100), ByteRange::Satisfiable(90, 99), "Open ranges should go up to the last byte");
+        assert_eq!(parse_range("bytes=-10",    100), ByteRange::Satisfiable(90, 99), "Suffix ranges should refer to the last bytes");
+        assert_eq!(parse_range("bytes=50-500", 100), ByteRange::Satisfiable(50, 99), "Ranges should be capped to the contents");
+        assert_eq!(parse_range("bytes=100-",   100), ByteRange::Unsatisfiable,       "Ranges starting beyond the contents can't be satisfied");
+        assert_eq!(parse_range("bytes=0-1,5-6",100), ByteRange::Ignored,             "Multiple ranges are not supported");
+        assert_eq!(parse_range("items=0-9",    100), ByteRange::Ignored,             "Only byte ranges are supported");
+        assert!(etag_matches(r#""abc", W/"def""#, r#""def""#), "Weak validators should match");
+        assert!(etag_matches("*", r#""abc""#), "The wildcard should match any ETag");
+        assert!(!etag_matches(r#""abc""#, r#""def""#), "Different ETags should not match");
+    }
+}
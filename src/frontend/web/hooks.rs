@@ -0,0 +1,216 @@
+//! Generic receiver for inbound webhooks (GitHub, Stripe, ... or our own outbound webhooks) -- see [crate::config::WebConfig::inbound_hooks].
+//!
+//! Requests are only accepted if properly signed (HMAC-SHA256) & within the configured rate -- only the properly signed ones count
+//! towards it, so forged requests can't exhaust it -- being then published as [InboundHook]s on the [INBOUND_HOOKS_TOPIC] of the event bus,
+//! with the request's correlation id (see [super::correlation]). There, they reach the logic (see [crate::logic::process_inbound_hooks()])
+//! and the bus' followers.
+
+use super::correlation::Correlation;
+use crate::{
+    config::InboundHookConfig,
+    runtime::{verify_hmac_sha256_hex, EventBus, Topic},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use rocket::{
+    post, State,
+    data::{Data, ToByteUnit},
+    http::{HeaderMap, Status},
+    request::{FromRequest, Outcome, Request},
+};
+use log::{debug, warn};
+
+
+pub const BASE_PATH: &str = "/api/hooks";
+
+/// Payloads bigger than this are refused with "413 Payload Too Large"
+const MAX_PAYLOAD_MIB: u64 = 1;
+
+/// The window for measuring the rate of requests, per hook
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The event bus topic carrying the [InboundHook]s
+pub const INBOUND_HOOKS_TOPIC: &str = "hooks.inbound";
+
+
+/// A validated payload received by an inbound webhook -- published on the [INBOUND_HOOKS_TOPIC]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InboundHook {
+    /// the name of the hook, as in [crate::config::WebConfig::inbound_hooks]
+    pub hook:    String,
+    /// the payload, as received -- invalid UTF-8 sequences replaced
+    pub payload: String,
+}
+
+/// the [INBOUND_HOOKS_TOPIC] of `events`
+pub fn inbound_hooks_topic(events: &Arc<EventBus>) -> Topic<InboundHook> {
+    events.topic(INBOUND_HOOKS_TOPIC)
+        .expect("BUG: hooks.rs: the inbound hooks topic was created for another event type")
+}
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        receive_hook,
+    ]
+}
+
+/// The configured hooks, their rate counters & where they are published -- Rocket's managed state for this module
+pub struct InboundHooks {
+    hooks:        BTreeMap<String, InboundHookConfig>,
+    /// `(window_start, count)`, by hook name
+    rate_windows: Mutex<HashMap<String, (Instant, u32)>>,
+    received:     Topic<InboundHook>,
+}
+
+impl InboundHooks {
+
+    pub fn new(hooks: &BTreeMap<String, InboundHookConfig>, events: &Arc<EventBus>) -> Self {
+        Self {
+            hooks:        hooks.clone(),
+            rate_windows: Mutex::new(HashMap::new()),
+            received:     inbound_hooks_topic(events),
+        }
+    }
+
+    /// counts a (properly signed) request for `hook_name`, telling if it is within its configured rate
+    fn within_rate(&self, hook_name: &str, max_requests_per_minute: u32) -> bool {
+        if max_requests_per_minute == 0 {
+            return true
+        }
+        let mut rate_windows = self.rate_windows.lock();
+        let (window_start, count) = rate_windows.entry(hook_name.to_string())
+            .or_insert_with(|| (Instant::now(), 0));
+        if window_start.elapsed() >= RATE_WINDOW {
+            *window_start = Instant::now();
+            *count = 0;
+        }
+        *count += 1;
+        *count <= max_requests_per_minute
+    }
+}
+
+/// Request guard exposing all request headers -- as the signature header is only known after the hook is identified
+pub struct RequestHeaders<'r>(&'r HeaderMap<'r>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestHeaders<'r> {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestHeaders(request.headers()))
+    }
+}
+
+/// Receives the payload for the `hook_name` inbound webhook, answering "202 Accepted" if it was valid & published
+#[post("/<hook_name>", data = "<payload>")]
+async fn receive_hook(hook_name: &str, headers: RequestHeaders<'_>, correlation: Correlation, payload: Data<'_>, inbound_hooks: &State<InboundHooks>) -> Status {
+    let Some(hook) = inbound_hooks.hooks.get(hook_name) else {
+        debug!("Inbound hooks: request for the unknown hook '{}'", hook_name);
+        return Status::NotFound
+    };
+    let payload = match payload.open(MAX_PAYLOAD_MIB.mebibytes()).into_bytes().await {
+        Ok(payload) if payload.is_complete() => payload.into_inner(),
+        Ok(_) => return Status::PayloadTooLarge,
+        Err(err) => {
+            warn!("Inbound hooks: could not read the payload for '{}': {}", hook_name, err);
+            return Status::BadRequest
+        },
+    };
+    let signature = headers.0.get_one(&hook.signature_header)
+        .and_then(|signature| signature.strip_prefix(hook.signature_prefix.as_str()));
+    match signature {
+        Some(signature) if verify_hmac_sha256_hex(&hook.secret, &payload, signature) => (),
+        _ => {
+            warn!("Inbound hooks: refusing a request for '{}' with a missing or invalid signature", hook_name);
+            return Status::Unauthorized
+        },
+    }
+    if !inbound_hooks.within_rate(hook_name, hook.max_requests_per_minute) {
+        warn!("Inbound hooks: '{}' is above its rate of {} requests per minute", hook_name, hook.max_requests_per_minute);
+        return Status::TooManyRequests
+    }
+    let inbound_hook = InboundHook { hook: hook_name.to_string(), payload: String::from_utf8_lossy(&payload).into_owned() };
+    correlation.scope(async { inbound_hooks.received.publish(inbound_hook) }).await;
+    Status::Accepted
+}
+
+
+/// Unit tests the [hooks](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use rocket::{
+        http::Header,
+        local::asynchronous::Client,
+    };
+
+
+    /// HMAC-SHA256 test vector from RFC 4231 (test case 2)
+    const SECRET: &str = "Jefe";
+    const PAYLOAD: &str = "what do ya want for nothing?";
+    const SIGNATURE: &str = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+
+
+    /// assures only properly signed requests are accepted & published -- missing or invalid signatures being refused
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn signatures() {
+        let (client, events) = client(0).await;
+        let mut received = inbound_hooks_topic(&events).subscribe();
+
+        assert_eq!(post(&client, Some(SIGNATURE)).await, Status::Accepted, "Properly signed requests should have been accepted");
+        assert_eq!(received.try_recv().expect("The hook should have been published").event,
+                   InboundHook { hook: String::from("github"), payload: String::from(PAYLOAD) },
+                   "The payload should have been published, as received");
+
+        let forged_signature = SIGNATURE.replace('5', "6");
+        assert_eq!(post(&client, Some(&forged_signature)).await, Status::Unauthorized, "Invalid signatures should have been refused");
+        assert_eq!(post(&client, Some("not hex")).await, Status::Unauthorized, "Malformed signatures should have been refused");
+        assert_eq!(post(&client, None).await, Status::Unauthorized, "Requests without a signature should have been refused");
+        assert!(received.try_recv().is_err(), "Refused requests shouldn't have been published");
+    }
+
+    /// assures requests above the configured rate are refused -- & that forged ones don't count towards it
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn rate_limit() {
+        let (client, _events) = client(2).await;
+        assert_eq!(post(&client, None).await, Status::Unauthorized, "Requests without a signature should have been refused");
+        for _ in 0..2 {
+            assert_eq!(post(&client, Some(SIGNATURE)).await, Status::Accepted, "Requests within the rate should have been accepted");
+        }
+        assert_eq!(post(&client, Some(SIGNATURE)).await, Status::TooManyRequests, "Requests above the rate should have been refused");
+    }
+
+    /// a client for the `github` hook, limited to `max_requests_per_minute`, along with the event bus it publishes to
+    async fn client(max_requests_per_minute: u32) -> (Client, Arc<EventBus>) {
+        let hooks = BTreeMap::from([(String::from("github"), InboundHookConfig {
+            secret:                  String::from(SECRET),
+            signature_header:        String::from("X-Hub-Signature-256"),
+            signature_prefix:        String::from("sha256="),
+            max_requests_per_minute,
+        })]);
+        let events = Arc::new(EventBus::new());
+        let rocket = rocket::build()
+            .manage(InboundHooks::new(&hooks, &events))
+            .mount(BASE_PATH, routes());
+        let client = Client::tracked(rocket).await
+            .expect("Igniting Rocket");
+        (client, events)
+    }
+
+    /// POSTs [PAYLOAD] to the `github` hook, with the given hex `signature`
+    async fn post(client: &Client, signature: Option<&str>) -> Status {
+        let mut request = client.post(format!("{}/github", BASE_PATH))
+            .body(PAYLOAD);
+        if let Some(signature) = signature {
+            request = request.header(Header::new("X-Hub-Signature-256", format!("sha256={}", signature)));
+        }
+        request.dispatch().await.status()
+    }
+}
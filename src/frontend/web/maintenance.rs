@@ -0,0 +1,91 @@
+//! Maintenance mode for the web server -- see [DegradeSwitches::maintenance()].
+//!
+//! While on, a fairing reroutes every request -- but for the monitoring routes & the admin API, in [EXEMPT_PATHS] -- to this module's routes,
+//! which answer "503 Service Unavailable" along with [DegradeSwitches::maintenance_message()]: as JSON, for the API, or as
+//! HTML, for everything else. Rerouting (instead of rewriting the responses) assures the original handlers are not executed.
+
+use super::admin;
+use crate::runtime::DegradeSwitches;
+use std::sync::Arc;
+use rocket::{
+    get, State,
+    Data, Request,
+    fairing::{Fairing, Info, Kind},
+    http::{Method, Status, uri::Origin},
+    response::content::RawHtml,
+    serde::json::{Json, Value, json},
+};
+
+
+pub const BASE_PATH: &str = "/maintenance";
+
+/// Requests to these path prefixes are served normally, even in maintenance mode -- so the application may still be monitored &
+/// administered (including turning the maintenance mode off)
+pub const EXEMPT_PATHS: &[&str] = &["/api/metrics", "/api/stats/", "/backend/stats/", "/healthz", "/readyz", admin::BASE_PATH];
+
+/// Requests to paths starting with this are answered with JSON -- HTML otherwise
+const API_PATH: &str = "/api";
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        api_maintenance,
+        page_maintenance,
+    ]
+}
+
+/// The fairing rerouting requests to this module's routes while in maintenance mode -- to be attached to Rocket
+pub struct Maintenance {
+    degrade: Arc<DegradeSwitches>,
+}
+
+impl Maintenance {
+    pub fn new(degrade: Arc<DegradeSwitches>) -> Self {
+        Self { degrade }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for Maintenance {
+    fn info(&self) -> Info {
+        Info {
+            name: "Maintenance mode",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        if !self.degrade.maintenance() {
+            return
+        }
+        let path = request.uri().path();
+        if path.starts_with(BASE_PATH) || EXEMPT_PATHS.iter().any(|exempt_path| path.starts_with(exempt_path)) {
+            return
+        }
+        let maintenance_uri = if path.starts_with(API_PATH) { "/maintenance/api" } else { "/maintenance/page" };
+        request.set_method(Method::Get);
+        request.set_uri(Origin::parse(maintenance_uri).expect("BUG! maintenance URIs are valid"));
+    }
+}
+
+/// Where API requests are rerouted to while in maintenance mode -- "404 Not Found" otherwise
+#[get("/api")]
+fn api_maintenance(degrade: &State<Arc<DegradeSwitches>>) -> (Status, Json<Value>) {
+    if degrade.maintenance() {
+        (Status::ServiceUnavailable, Json(json!({"maintenance": degrade.maintenance_message()})))
+    } else {
+        (Status::NotFound, Json(json!({})))
+    }
+}
+
+/// Where requests for the web app are rerouted to while in maintenance mode -- "404 Not Found" otherwise
+#[get("/page")]
+fn page_maintenance(degrade: &State<Arc<DegradeSwitches>>) -> (Status, RawHtml<String>) {
+    if degrade.maintenance() {
+        (Status::ServiceUnavailable, RawHtml(format!("<!DOCTYPE html><html><head><title>Maintenance</title></head><body><h1>{}</h1></body></html>",
+                                                     degrade.maintenance_message())))
+    } else {
+        (Status::NotFound, RawHtml(String::new()))
+    }
+}
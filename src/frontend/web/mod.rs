@@ -15,16 +15,43 @@
 mod files;
 mod embedded_files;
 mod api;
+mod stats;
 mod backend;
+mod hooks;
+pub use hooks::{InboundHook, inbound_hooks_topic};
+mod admin;
+mod remote_console;
+mod events;
+mod socket_proxy;
+mod websocket;
+mod maintenance;
+mod redirects;
+mod default_files;
+mod sanity_check;
+mod access_log;
+mod request_tracing;
+mod correlation;
+mod response_cache;
+pub use response_cache::ResponseCache;
 
-use crate::config::config::{Config, WebConfig, RocketConfigOptions, RocketProfiles};
+use crate::{
+    config::config::{Config, WebConfig, WebTlsConfig, SocketServerConfig, SocketTransport, SocketWireFormat, SocketLogin, RocketConfigOptions, RocketProfiles, ExtendedOption},
+    runtime::Runtime,
+    frontend::{Service, ServiceHealth, Runner},
+    logging::access_log::is_access_log_enabled,
+};
 use std::{
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     net::Ipv4Addr,
 };
 use owning_ref::OwningRef;
 use futures::future::BoxFuture;
+use tokio::sync::RwLock;
 use rocket;
+use log::{warn, error};
 
 
 /// Returned by this module when the Rocket server starts -- see [runner()].\
@@ -36,30 +63,190 @@ pub struct WebServer {
     web_config: OwningRef<Arc<Config>, WebConfig>,
     /// tells if the service is fully started & working
     started: bool,
+    /// set once Rocket is bound to its port -- see [Service::health()]
+    listening: Arc<AtomicBool>,
     /// contains the builder for Rocket -- which exists between [new()] and [runner()] calls
     rocket_builder: Option<rocket::Rocket<rocket::Build>>,
     /// if present, exposes the Rocket's `shutdown_token`, through which one may request the service to cease running
     pub shutdown_token: Option<rocket::Shutdown>,
+    /// set once [Service::shutdown()] is called -- see [Service::health()]
+    shutdown_requested: AtomicBool,
 }
 
 impl WebServer {
 
-    pub fn new(web_config: OwningRef<Arc<Config>, WebConfig>) -> WebServer {
-        let mut rocket_builder = match web_config.rocket_config {
+    /// Shared parts of `shared_runtime` (the degradation switches, statistics, ...) are made available to the routes as Rocket's managed state --
+    /// as well as `shared_runtime` itself, for the probes of [sanity_check] to inspect the other services
+    pub async fn new(web_config: OwningRef<Arc<Config>, WebConfig>, shared_runtime: &Arc<RwLock<Runtime>>) -> WebServer {
+        let runtime = shared_runtime.read().await;
+        let mut rocket_builder = match &web_config.rocket_config {
             RocketConfigOptions::StandardRocketTomlFile => rocket::build(),
-            RocketConfigOptions::Provided {http_port, workers} =>
-                rocket::custom(build_rocket_config(&web_config.profile, http_port, workers))
+            RocketConfigOptions::Provided {http_port, workers, tls} =>
+                rocket::custom(build_rocket_config(&web_config.profile, *http_port, *workers, tls))
         };
-        if web_config.web_app {
+        if is_access_log_enabled() {
+            // before any other fairings, so requests are logged as received
+            rocket_builder = rocket_builder.attach(access_log::AccessLog);
+        }
+        if web_config.as_owner().telemetry.is_enabled() {
+            rocket_builder = rocket_builder.attach(request_tracing::RequestTracing);
+        }
+        rocket_builder = rocket_builder.attach(correlation::CorrelationHeader);
+        let redirects = redirects::Redirects::new(&web_config)
+            .unwrap_or_else(|err| panic!("Invalid `redirects` / `rewrites` in the web config: {}", err));
+        if !redirects.is_empty() {
+            // before the maintenance mode, so it sees the rewritten paths
+            rocket_builder = rocket_builder
+                .attach(redirects)
+                .mount(redirects::BASE_PATH, redirects::routes());
+        }
+        rocket_builder = rocket_builder
+            .manage(Arc::clone(&runtime.degrade))
+            .manage(Arc::clone(&runtime.socket_server_stats))
+            .manage(Arc::clone(&runtime.response_cache))
+            .manage(Arc::clone(&runtime.job_accounting))
+            .manage(Arc::clone(&runtime.warmup))
+            .manage(Arc::clone(shared_runtime))
+            .attach(maintenance::Maintenance::new(Arc::clone(&runtime.degrade)))
+            .mount(maintenance::BASE_PATH, maintenance::routes())
+            .manage(default_files::DefaultFiles::new(&web_config.default_files)
+                .unwrap_or_else(|err| panic!("Invalid `default_files` in the web config: {}", err)))
+            .mount(default_files::BASE_PATH, default_files::routes());
+        if let Some(metrics) = &runtime.metrics {
             rocket_builder = rocket_builder
-                .mount(files::BASE_PATH,   files::routes())
-                .mount(backend::BASE_PATH, backend::routes());
+                .manage(metrics.clone())
+                .mount(api::BASE_PATH, api::metrics_routes());
+            if web_config.stats_routes {
+                rocket_builder = rocket_builder.mount(stats::BASE_PATH, stats::routes());
+            }
+        }
+        if web_config.sanity_check_routes {
+            rocket_builder = rocket_builder
+                .manage(sanity_check::SanityCheck::new(Arc::clone(shared_runtime), &web_config.as_owner().services))
+                .mount(sanity_check::BASE_PATH, sanity_check::routes());
+        }
+        if let Some(auth) = &runtime.auth {
+            rocket_builder = rocket_builder.manage(Arc::clone(auth));
+        }
+        if let Some(session_tokens) = &runtime.session_tokens {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(session_tokens))
+                .mount(api::BASE_PATH, api::session_routes());
+        }
+        if let Some(job_queue) = &runtime.job_queue {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(job_queue))
+                .mount(api::BASE_PATH, api::job_queue_routes());
+        }
+        if let Some(self_monitor) = &runtime.self_monitor {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(self_monitor))
+                .mount(api::BASE_PATH, api::self_monitor_routes());
+        }
+        if let Some(scheduler) = &runtime.scheduler {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(scheduler))
+                .mount(api::BASE_PATH, api::scheduler_routes());
+        }
+        if let Some(metrics_history) = &runtime.metrics_history {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(metrics_history))
+                .mount(api::BASE_PATH, api::metrics_history_routes());
+        }
+        if let Some(webhooks) = &runtime.webhooks {
+            rocket_builder = rocket_builder
+                .manage(Arc::clone(webhooks))
+                .mount(api::BASE_PATH, api::webhooks_routes());
+        }
+        if web_config.ogre_events_following_routes || web_config.ogre_events_queue_routes {
+            rocket_builder = rocket_builder.manage(Arc::clone(&runtime.events));
+        }
+        if web_config.ogre_events_following_routes {
+            rocket_builder = rocket_builder.mount(events::BASE_PATH, events::following_routes());
         }
+        if web_config.ogre_events_queue_routes {
+            rocket_builder = rocket_builder.mount(events::BASE_PATH, events::queue_routes());
+        }
+        if web_config.logs_following_routes {
+            rocket_builder = rocket_builder.mount(api::BASE_PATH, api::logs_following_routes());
+        }
+        if !web_config.inbound_hooks.is_empty() {
+            rocket_builder = rocket_builder
+                .manage(hooks::InboundHooks::new(&web_config.inbound_hooks, &runtime.events))
+                .mount(hooks::BASE_PATH, hooks::routes());
+        }
+        if let Some(admin_token) = &web_config.admin_token {
+            rocket_builder = rocket_builder
+                .manage(admin::AdminToken(admin_token.clone()))
+                .mount(admin::BASE_PATH, admin::routes())
+                .mount(remote_console::BASE_PATH, remote_console::routes());
+        }
+        if web_config.socket_proxy_routes || web_config.websocket_routes {
+            let socket_server = match &web_config.as_owner().services {
+                ExtendedOption::Enabled(services) => Some(&services.socket_server),
+                _ => None,
+            };
+            let unavailability = match socket_server {
+                // (through TLS, the socket server may not be reached as a plain client -- and the bridges speak RON only)
+                // TODO 2026-10-16: connect to the TLS terminator's plain side -- which requires its ephemeral port to be shared through the runtime
+                Some(ExtendedOption::Enabled(socket_server_config @ SocketServerConfig { transport: SocketTransport::Plain, wire_format: SocketWireFormat::Ron, .. })) => {
+                    // logging in, if required, may only be done on behalf of the web clients with session tokens issued to their identities
+                    let session_tokens = match &socket_server_config.login {
+                        ExtendedOption::Enabled(SocketLogin::AuthProvider) => runtime.session_tokens.as_ref().map(Arc::clone),
+                        _ => None,
+                    };
+                    let may_log_in = !socket_server_config.login.is_enabled() || session_tokens.is_some();
+                    match socket_proxy::SocketProxy::new(&socket_server_config.interface, socket_server_config.port, Arc::clone(&runtime.socket_server_peers), session_tokens) {
+                        Ok(socket_proxy) => {
+                            rocket_builder = rocket_builder.manage(socket_proxy);
+                            if web_config.socket_proxy_routes && may_log_in {
+                                rocket_builder = rocket_builder.mount(socket_proxy::BASE_PATH, socket_proxy::routes());
+                            } else if web_config.socket_proxy_routes {
+                                error!("The `/api/socket-proxy` route won't be available: it may only log its clients in to the socket server if its login uses the auth provider -- with `auth` enabled");
+                            }
+                            if web_config.websocket_routes {
+                                rocket_builder = rocket_builder.mount(websocket::BASE_PATH, websocket::routes());
+                            }
+                        },
+                        Err(err) => error!("The socket proxy & WebSocket routes won't be available: {}", err),
+                    }
+                    None
+                },
+                Some(ExtendedOption::Enabled(socket_server_config)) =>
+                    Some(format!("only socket servers on the `Plain` transport, speaking `Ron`, may be reached -- not on `{:?}`, speaking `{:?}`",
+                                 socket_server_config.transport, socket_server_config.wire_format)),
+                _ => Some(String::from("the socket server is disabled")),
+            };
+            if let Some(reason) = unavailability {
+                if web_config.socket_proxy_routes {
+                    warn!("The `/api/socket-proxy` route won't be available: {}", reason);
+                }
+                if web_config.websocket_routes {
+                    warn!("The `/api/ws` WebSocket route won't be available: {}", reason);
+                }
+            }
+        }
+        if web_config.web_app {
+            rocket_builder = match &web_config.static_files_dir {
+                Some(static_files_dir) => rocket_builder
+                    .manage(files::StaticFilesDir(static_files_dir.into()))
+                    .mount(files::BASE_PATH, files::disk_routes()),
+                None => rocket_builder.mount(files::BASE_PATH, files::routes()),
+            };
+            rocket_builder = rocket_builder.mount(backend::BASE_PATH, backend::routes());
+        }
+        let listening = Arc::new(AtomicBool::new(false));
+        let listening_for_liftoff = Arc::clone(&listening);
+        rocket_builder = rocket_builder.attach(rocket::fairing::AdHoc::on_liftoff("Listening", move |_rocket| Box::pin(async move {
+            listening_for_liftoff.store(true, Ordering::Relaxed);
+        })));
         Self {
             web_config,
             started: false,
+            listening,
             rocket_builder: Some(rocket_builder),
             shutdown_token: None,
+            shutdown_requested: AtomicBool::new(false),
         }
     }
 
@@ -79,6 +266,7 @@ impl WebServer {
             .map_err(|err| format!("Error 'Ignite'ing rocket: {:?}", err))?;
 
         self.shutdown_token = Some(ignited_rocket.shutdown());
+        self.started = true;
 
         let runner = move || -> BoxFuture<'_, Result<(), Box<dyn std::error::Error + Send + Sync>>> {
             Box::pin(async move {
@@ -94,14 +282,58 @@ impl WebServer {
 
 }
 
-fn build_rocket_config(profile: &RocketProfiles, http_port: u16, workers: u16) -> rocket::Config {
+impl Service for WebServer {
+
+    fn name(&self) -> &'static str {
+        "Web"
+    }
+
+    fn start(&mut self) -> BoxFuture<'_, Result<Runner, Box<dyn std::error::Error + Send + Sync>>> {
+        Box::pin(async move {
+            let runner_closure = self.runner().await?;
+            let runner: Runner = runner_closure();
+            Ok(runner)
+        })
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.shutdown_requested.store(true, Ordering::Relaxed);
+            if let Some(shutdown_token) = self.shutdown_token.clone() {
+                shutdown_token.notify();
+            }
+        })
+    }
+
+    fn health(&self) -> ServiceHealth {
+        if self.shutdown_requested.load(Ordering::Relaxed) {
+            ServiceHealth::ShuttingDown
+        } else if self.started && self.listening.load(Ordering::Relaxed) {
+            ServiceHealth::Running
+        } else {
+            ServiceHealth::Starting
+        }
+    }
+
+    fn register(self, runtime: &RwLock<Runtime>) -> BoxFuture<'_, ()> {
+        Box::pin(Runtime::register_web_server(runtime, self))
+    }
+}
+
+/// Rocket's config for `profile` -- serving HTTPS if `tls` is enabled. Missing or invalid certificate & key files are reported by Rocket, when ignited
+fn build_rocket_config(profile: &RocketProfiles, http_port: u16, workers: u16, tls: &ExtendedOption<WebTlsConfig>) -> rocket::Config {
     let address = Ipv4Addr::new(0, 0, 0, 0).into();
+    let tls = match tls {
+        ExtendedOption::Enabled(WebTlsConfig { cert_file, key_file }) => Some(rocket::config::TlsConfig::from_paths(cert_file, key_file)),
+        _ => None,
+    };
     match profile {
         RocketProfiles::Debug => rocket::Config {
             profile: rocket::Config::DEBUG_PROFILE,
             address,
             port: http_port,
             workers: workers as usize,
+            tls,
             ..rocket::Config::debug_default()
         },
         RocketProfiles::Production => rocket::Config {
@@ -110,6 +342,7 @@ fn build_rocket_config(profile: &RocketProfiles, http_port: u16, workers: u16) -
             address,
             port: http_port,
             workers: workers as usize,
+            tls,
             ..rocket::Config::release_default()
         },
     }
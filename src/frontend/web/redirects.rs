@@ -0,0 +1,203 @@
+//! Config-defined redirects & rewrites -- see [WebConfig::redirects] & [WebConfig::rewrites]: so deployments may handle moved URLs,
+//! enforce canonical hosts or map legacy paths without recompiling the embedded frontends.
+//!
+//! A fairing, attached before the others (but for the access log), checks each request against the rules -- in the configured order:
+//!   - matching redirects reroute the request to this module's route, which answers the redirect. As in the maintenance mode,
+//!     rerouting (instead of rewriting the responses) assures the original handlers are not executed;
+//!   - matching rewrites just change the request's path (keeping its method) -- to be matched against the routes as if it were requested.
+
+use crate::config::{WebConfig, PathPattern, RedirectRule, RewriteRule};
+use rocket::{
+    get,
+    Data, Request,
+    fairing::{Fairing, Info, Kind},
+    http::{Method, uri::Origin},
+    request::{FromRequest, Outcome},
+    response::Redirect,
+};
+use regex::Regex;
+use log::warn;
+
+
+pub const BASE_PATH: &str = "/redirect";
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        redirect,
+    ]
+}
+
+/// [PathPattern], compiled
+enum CompiledPattern {
+    Exact(String),
+    Regex(Regex),
+}
+
+impl CompiledPattern {
+
+    fn new(pattern: &PathPattern) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        match pattern {
+            PathPattern::Exact(path) => Ok(Self::Exact(path.clone())),
+            PathPattern::Regex(regex) => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|err| Box::from(format!("Invalid regular expression '{}': {}", regex, err))),
+        }
+    }
+
+    /// if `path` matches, returns `target` -- with the capture groups expanded, for regexes
+    fn apply(&self, path: &str, target: &str) -> Option<String> {
+        match self {
+            Self::Exact(exact_path) => (exact_path == path).then(|| target.to_string()),
+            Self::Regex(regex) => regex.captures(path).map(|captures| {
+                let mut expanded = String::new();
+                captures.expand(target, &mut expanded);
+                expanded
+            }),
+        }
+    }
+}
+
+struct CompiledRedirect {
+    host:   Option<String>,
+    from:   CompiledPattern,
+    to:     String,
+    status: u16,
+}
+
+struct CompiledRewrite {
+    from: CompiledPattern,
+    to:   String,
+}
+
+/// Where the matched redirect is kept -- in the request's local cache -- for the [redirect()] route
+struct PendingRedirect(Option<(u16, String)>);
+
+/// The fairing applying the redirects & rewrites -- to be attached to Rocket
+pub struct Redirects {
+    redirects: Vec<CompiledRedirect>,
+    rewrites:  Vec<CompiledRewrite>,
+}
+
+impl Redirects {
+
+    /// fails if any of the `redirects` or `rewrites` in `web_config` is invalid
+    pub fn new(web_config: &WebConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let redirects = web_config.redirects.iter()
+            .map(|RedirectRule { host, from, to, status }| {
+                if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+                    return Err(Box::from(format!("Redirect to '{}' has an invalid status code {} -- 301, 302, 303, 307 or 308 are expected", to, status)))
+                }
+                Ok(CompiledRedirect { host: host.clone(), from: CompiledPattern::new(from)?, to: to.clone(), status: *status })
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+        let rewrites = web_config.rewrites.iter()
+            .map(|RewriteRule { from, to }| Ok(CompiledRewrite { from: CompiledPattern::new(from)?, to: to.clone() }))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+        Ok(Self { redirects, rewrites })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.redirects.is_empty() && self.rewrites.is_empty()
+    }
+
+    /// the first redirect matching `host` & `path`, as (status, target)
+    fn redirect_for(&self, host: Option<&str>, path: &str) -> Option<(u16, String)> {
+        self.redirects.iter()
+            .filter(|redirect| redirect.host.as_ref().is_none_or(|redirect_host| host.is_some_and(|host| host.eq_ignore_ascii_case(redirect_host))))
+            .find_map(|redirect| redirect.from.apply(path, &redirect.to).map(|target| (redirect.status, target)))
+    }
+
+    /// the path of the first rewrite matching `path`
+    fn rewrite_for(&self, path: &str) -> Option<String> {
+        self.rewrites.iter()
+            .find_map(|rewrite| rewrite.from.apply(path, &rewrite.to))
+    }
+}
+
+/// appends `query` to `target`, unless it already has one
+fn with_query(target: String, query: Option<&str>) -> String {
+    match query {
+        Some(query) if !target.contains('?') => format!("{}?{}", target, query),
+        _ => target,
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for Redirects {
+    fn info(&self) -> Info {
+        Info {
+            name: "Redirects & rewrites",
+            kind: Kind::Request,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().map(|query| query.to_string());
+        if let Some((status, target)) = self.redirect_for(request.host().map(|host| host.domain().as_str()), &path) {
+            request.local_cache(|| PendingRedirect(Some((status, with_query(target, query.as_deref())))));
+            request.set_method(Method::Get);
+            request.set_uri(Origin::parse(BASE_PATH).expect("BUG! the redirect URI is valid"));
+        } else if let Some(rewritten_path) = self.rewrite_for(&path) {
+            match Origin::parse_owned(with_query(rewritten_path, query.as_deref())) {
+                Ok(rewritten_uri) => request.set_uri(rewritten_uri),
+                Err(err) => warn!("Rewriting '{}' led to an invalid URI -- serving it as it is: {}", path, err),
+            }
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r PendingRedirect {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(request.local_cache(|| PendingRedirect(None)))
+    }
+}
+
+/// Where redirected requests are rerouted to -- "404 Not Found" otherwise
+#[get("/")]
+fn redirect(pending_redirect: &PendingRedirect) -> Option<Redirect> {
+    let (status, target) = pending_redirect.0.clone()?;
+    Some(match status {
+        301 => Redirect::moved(target),
+        302 => Redirect::found(target),
+        303 => Redirect::to(target),
+        307 => Redirect::temporary(target),
+        _   => Redirect::permanent(target),
+    })
+}
+
+
+/// Unit tests the [redirects](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn rules_are_matched_in_order() {
+        let mut web_config = (*Config::default().services.web).clone();
+        web_config.redirects = vec![
+            RedirectRule { host: None,                              from: PathPattern::Exact(String::from("/old-page")), to: String::from("/new-page"),                 status: 308 },
+            RedirectRule { host: Some(String::from("example.com")), from: PathPattern::Regex(String::from("^(/.*)$")),   to: String::from("https://www.example.com$1"), status: 301 },
+        ];
+        web_config.rewrites = vec![
+            RewriteRule { from: PathPattern::Regex(String::from("^/legacy/(.*)$")), to: String::from("/api/$1") },
+        ];
+        let redirects = Redirects::new(&web_config).expect("Valid rules were refused");
+        assert_eq!(redirects.redirect_for(Some("www.example.com"), "/old-page"), Some((308, String::from("/new-page"))), "Exact paths should redirect regardless of the host");
+        assert_eq!(redirects.redirect_for(Some("EXAMPLE.com"), "/some/page"), Some((301, String::from("https://www.example.com/some/page"))), "The canonical host redirect should apply");
+        assert_eq!(redirects.redirect_for(Some("www.example.com"), "/some/page"), None, "The canonical host shouldn't be redirected");
+        assert_eq!(redirects.rewrite_for("/legacy/stats"), Some(String::from("/api/stats")), "Capture groups should have been expanded");
+        assert_eq!(redirects.rewrite_for("/api/stats"), None, "Unmatched paths shouldn't be rewritten");
+        assert_eq!(with_query(String::from("/new-page"), Some("a=1")), "/new-page?a=1", "The query should have been carried over");
+
+        web_config.redirects[0].status = 200;
+        assert!(Redirects::new(&web_config).is_err(), "Non-redirect status codes should have been refused");
+    }
+}
@@ -0,0 +1,82 @@
+//! The remote console: lets Terminal UIs attach to this running instance -- see [crate::config::TerminalOptions::connect] -- pushing
+//! them snapshots of its state & running their commands, through the `/api/admin/console` WebSocket. See [crate::frontend::terminal::remote]
+//! for the protocol.\
+//! Mounted along with the admin API -- so only for requests presenting the [crate::config::WebConfig::admin_token]
+
+use super::admin::Admin;
+use crate::{
+    runtime::Runtime,
+    frontend::terminal::remote::{self, ConsoleCommand, ConsoleEvent},
+};
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+use rocket::{
+    get, State,
+    serde::json,
+};
+use rocket_ws::{WebSocket, Channel, Message};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::RwLock;
+use log::{debug, info};
+
+
+pub const BASE_PATH: &str = "/api/admin";
+
+/// How often the snapshots are pushed -- besides right after each command
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        console,
+    ]
+}
+
+/// Upgrades the connection to a WebSocket, serving the remote console until either side closes it
+#[get("/console")]
+fn console(_admin: Admin, ws: WebSocket, runtime: &State<Arc<RwLock<Runtime>>>) -> Channel<'static> {
+    let runtime = Arc::clone(runtime.inner());
+    ws.channel(move |duplex| Box::pin(async move {
+        info!("Remote console: a Terminal UI attached");
+        let (mut ws_sink, mut ws_stream) = duplex.split();
+        let mut snapshot_interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = snapshot_interval.tick() => {
+                    ws_sink.send(to_frame(&ConsoleEvent::Snapshot(Box::new(remote::snapshot(&runtime).await)))).await?;
+                },
+                ws_message = ws_stream.next() => match ws_message {
+                    Some(Ok(Message::Text(text))) => {
+                        let feedback = match json::from_str::<ConsoleCommand>(&text) {
+                            Ok(command) => {
+                                info!("Remote console: executing {:?}", command);
+                                remote::execute(&runtime, command).await
+                            },
+                            Err(err) => format!("ERROR: unknown command '{}': {}", text, err),
+                        };
+                        ws_sink.send(to_frame(&ConsoleEvent::Feedback(feedback))).await?;
+                        // so the outcome is seen right away
+                        ws_sink.send(to_frame(&ConsoleEvent::Snapshot(Box::new(remote::snapshot(&runtime).await)))).await?;
+                        snapshot_interval.reset();
+                    },
+                    Some(Ok(Message::Close(_))) | None => break,
+                    // pings are answered by the WebSocket layer itself
+                    Some(Ok(_other_frame)) => (),
+                    Some(Err(err)) => {
+                        debug!("Remote console: receive error -- closing: {}", err);
+                        break
+                    },
+                },
+            }
+        }
+        info!("Remote console: a Terminal UI detached");
+        ws_sink.close().await
+    }))
+}
+
+fn to_frame(event: &ConsoleEvent) -> Message {
+    Message::Text(json::to_string(event).expect("BUG! `ConsoleEvent`s are serializable to JSON"))
+}
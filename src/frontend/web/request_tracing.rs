@@ -0,0 +1,42 @@
+//! Opens a tracing span for each HTTP request -- closed once the request is done with -- for the requests to show up in the
+//! distributed traces. Only attached if [crate::config::Config::telemetry] is enabled -- see [crate::logging::telemetry]
+
+use rocket::{
+    Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+};
+use tracing::{info_span, field, Span};
+
+
+/// The fairing tracing each request
+pub struct RequestTracing;
+
+/// The request's span -- kept in the request's local cache, so it lasts as long as the request does
+struct RequestSpan(Span);
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        request.local_cache(|| RequestSpan(new_span(request)));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestSpan(span) = request.local_cache(|| RequestSpan(new_span(request)));
+        span.record("http.status_code", response.status().code);
+    }
+}
+
+/// the span for `request` -- whose status code is only known by the time it is answered
+fn new_span(request: &Request<'_>) -> Span {
+    info_span!("http_request",
+               http.method      = %request.method(),
+               http.target      = %request.uri(),
+               http.status_code = field::Empty)
+}
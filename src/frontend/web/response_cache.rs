@@ -0,0 +1,247 @@
+//! Server-side cache for the JSON answers of expensive API endpoints -- keyed by route & parameters, with TTLs & e-tags.
+//!
+//! Usage -- see `get_service()` in [super::api] for a working example:
+//! ```no_compile
+//!     #[get("/expensive?<param>")]
+//!     async fn expensive(param: u32, cacheable: Cacheable, cache: &State<Arc<ResponseCache>>) -> CachedJson {
+//!         cache.get_or_compute(cacheable, Duration::from_secs(60), || async move {
+//!             expensive_business_query(param).await
+//!         }).await
+//!     }
+//! ```
+//! Clients presenting the e-tag they already have (in the `If-None-Match` header) get a body-less "304 Not Modified".
+//!
+//! Answers are kept up to date by invalidating them whenever their data changes -- either directly, through [ResponseCache::invalidate()],
+//! or by subscribing it to the event bus topics the changes are published on:
+//! ```no_compile
+//!     runtime.response_cache.invalidate_on(&events.topic::<OrderChanged>("order.changed")?, "/api/orders");
+//! ```
+//! Out of the box, all answers are invalidated by the [super::InboundHook]s -- telling the external data changed. See `main.rs`.
+
+use crate::runtime::Topic;
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    future::Future,
+    hash::{Hash, Hasher},
+    io::Cursor,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+use rocket::{
+    http::{ContentType, Header, Status},
+    request::{self, FromRequest, Request},
+    response::{self, Responder, Response},
+    serde::Serialize,
+};
+use log::{debug, warn};
+
+
+/// Caches JSON answers of expensive handlers & counts the hits & misses
+#[derive(Default)]
+pub struct ResponseCache {
+    entries:      Mutex<HashMap<String, CachedEntry>>,
+    hits:         AtomicU64,
+    misses:       AtomicU64,
+    not_modified: AtomicU64,
+}
+
+struct CachedEntry {
+    json:       Arc<String>,
+    etag:       Arc<String>,
+    expires_at: Instant,
+}
+
+/// Snapshot of the [ResponseCache] metrics
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ResponseCacheReport {
+    pub entries:      usize,
+    pub hits:         u64,
+    /// how many of the hits were answered with "304 Not Modified"
+    pub not_modified: u64,
+    pub misses:       u64,
+}
+
+impl ResponseCache {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns the cached answer for `cacheable`'s route & parameters -- calling `compute` (which should yield a JSON)
+    /// to produce it if it is not cached or if it expired. Freshly computed answers are kept for `ttl`
+    pub async fn get_or_compute<ComputeFn:  FnOnce() -> ComputeFut,
+                                ComputeFut: Future<Output=String>>
+                               (&self, cacheable: Cacheable, ttl: Duration, compute: ComputeFn) -> CachedJson {
+        let cached = self.entries.lock().get(&cacheable.key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| (Arc::clone(&entry.json), Arc::clone(&entry.etag)));
+        let (json, etag) = match cached {
+            Some(cached) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                cached
+            },
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                // the lock is not held while computing: concurrent misses for the same key compute it more than once
+                let json = Arc::new(compute().await);
+                let etag = Arc::new(etag_for(&json));
+                let now = Instant::now();
+                let mut entries = self.entries.lock();
+                entries.retain(|_key, entry| entry.expires_at > now);
+                entries.insert(cacheable.key, CachedEntry {
+                    json:       Arc::clone(&json),
+                    etag:       Arc::clone(&etag),
+                    expires_at: now + ttl,
+                });
+                (json, etag)
+            },
+        };
+        let not_modified = cacheable.if_none_match.as_deref() == Some(etag.as_str());
+        if not_modified {
+            self.not_modified.fetch_add(1, Ordering::Relaxed);
+        }
+        CachedJson { json, etag, not_modified }
+    }
+
+    /// forgets all answers whose route starts with `route_prefix` -- like "/api/get-service" -- so they get recomputed on the next requests
+    pub fn invalidate(&self, route_prefix: &str) {
+        let mut entries = self.entries.lock();
+        let before = entries.len();
+        entries.retain(|key, _entry| !key.starts_with(route_prefix));
+        debug!("Response Cache: {} entries invalidated for routes starting with '{}'", before - entries.len(), route_prefix);
+    }
+
+    /// [invalidate()](Self::invalidate)s the answers whose route starts with `route_prefix` whenever an event is published on `topic`
+    /// -- telling their data changed. Spawns a Tokio task for that, lasting as long as the event bus does
+    pub fn invalidate_on<EventType: Serialize + Clone + Send + Sync + 'static>(self: &Arc<Self>, topic: &Topic<EventType>, route_prefix: &'static str) {
+        let mut changes = topic.subscribe();
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(_change) => cache.invalidate(route_prefix),
+                    // some changes were missed, but invalidating once covers them all
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Response Cache: fell behind the changes for routes starting with '{}' -- {} events were missed", route_prefix, missed);
+                        cache.invalidate(route_prefix);
+                    },
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    pub fn report(&self) -> ResponseCacheReport {
+        ResponseCacheReport {
+            entries:      self.entries.lock().len(),
+            hits:         self.hits.load(Ordering::Relaxed),
+            not_modified: self.not_modified.load(Ordering::Relaxed),
+            misses:       self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// a strong e-tag for the given `json`
+fn etag_for(json: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+
+/// Request guard identifying the request for the [ResponseCache] -- by its route & parameters -- along with the e-tag the client already has, if any
+pub struct Cacheable {
+    key:           String,
+    if_none_match: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Cacheable {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(Cacheable {
+            key:           request.uri().to_string(),
+            if_none_match: request.headers().get_one("If-None-Match").map(|etag| etag.to_string()),
+        })
+    }
+}
+
+/// The answer from the [ResponseCache] -- either the JSON or "304 Not Modified", along with the e-tag
+pub struct CachedJson {
+    json:         Arc<String>,
+    etag:         Arc<String>,
+    not_modified: bool,
+}
+
+impl<'r> Responder<'r, 'static> for CachedJson {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response.header(Header::new("ETag", self.etag.to_string()));
+        if self.not_modified {
+            response.status(Status::NotModified);
+        } else {
+            response
+                .header(ContentType::JSON)
+                .sized_body(self.json.len(), Cursor::new(self.json.to_string()));
+        }
+        response.ok()
+    }
+}
+
+
+/// Unit tests the [response_cache](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    fn cacheable(key: &str, if_none_match: Option<&str>) -> Cacheable {
+        Cacheable { key: key.to_string(), if_none_match: if_none_match.map(|etag| etag.to_string()) }
+    }
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn answers_are_cached_until_invalidated() {
+        let cache = ResponseCache::new();
+        let ttl = Duration::from_secs(60);
+        let first = cache.get_or_compute(cacheable("/api/x?a=1", None), ttl, || async { String::from(r#"{"n":1}"#) }).await;
+        let second = cache.get_or_compute(cacheable("/api/x?a=1", None), ttl, || async { String::from(r#"{"n":2}"#) }).await;
+        assert_eq!(second.json, first.json, "The second call should have been answered from the cache");
+        let other = cache.get_or_compute(cacheable("/api/x?a=2", None), ttl, || async { String::from(r#"{"n":3}"#) }).await;
+        assert_eq!(*other.json, r#"{"n":3}"#, "Different parameters should be cached separately");
+        let not_modified = cache.get_or_compute(cacheable("/api/x?a=1", Some(&first.etag)), ttl, || async { String::new() }).await;
+        assert!(not_modified.not_modified, "Presenting the current e-tag should yield '304 Not Modified'");
+        cache.invalidate("/api/x");
+        let recomputed = cache.get_or_compute(cacheable("/api/x?a=1", Some(&first.etag)), ttl, || async { String::from(r#"{"n":4}"#) }).await;
+        assert_eq!(*recomputed.json, r#"{"n":4}"#, "Invalidated answers should be recomputed");
+        assert!(!recomputed.not_modified, "An outdated e-tag should yield the new answer");
+        let report = cache.report();
+        assert_eq!((report.hits, report.not_modified, report.misses), (2, 1, 3), "Unexpected metrics");
+    }
+
+    /// assures answers are invalidated by the events telling their data changed -- & only by those
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn invalidation_by_events() {
+        let cache = Arc::new(ResponseCache::new());
+        let events = Arc::new(crate::runtime::EventBus::new());
+        let changes = events.topic::<u32>("x.changed").expect("Creating the topic");
+        cache.invalidate_on(&changes, "/api/x");
+        let ttl = Duration::from_secs(60);
+        cache.get_or_compute(cacheable("/api/x?a=1", None), ttl, || async { String::from(r#"{"n":1}"#) }).await;
+        cache.get_or_compute(cacheable("/api/y", None), ttl, || async { String::from(r#"{"n":2}"#) }).await;
+        changes.publish(1);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let recomputed = cache.get_or_compute(cacheable("/api/x?a=1", None), ttl, || async { String::from(r#"{"n":3}"#) }).await;
+        assert_eq!(*recomputed.json, r#"{"n":3}"#, "The answers of the changed data should have been invalidated");
+        let kept = cache.get_or_compute(cacheable("/api/y", None), ttl, || async { String::from(r#"{"n":4}"#) }).await;
+        assert_eq!(*kept.json, r#"{"n":2}"#, "Other answers should have been kept");
+    }
+}
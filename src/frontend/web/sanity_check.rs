@@ -0,0 +1,162 @@
+//! Liveness & readiness probes -- see [crate::config::WebConfig::sanity_check_routes]: for orchestrators (Kubernetes, load balancers, ...)
+//! to tell if this executable should be restarted (`/healthz`) or if it may be sent traffic (`/readyz`).
+//!
+//! Both answer "200 OK" if all their components are healthy -- "503 Service Unavailable" otherwise -- with the status of each one:
+//! ```no_compile
+//!     {"healthy":false,"components":[{"name":"tokio","healthy":true,"status":"scheduling latency: 41.2µs"},
+//!                                    {"name":"socket_server","healthy":false,"status":"starting"}, ...]}
+//! ```
+//! Both are served even in maintenance mode -- during which `/readyz` reports not being ready.
+
+use crate::{
+    config::{ExtendedOption, ServicesConfig},
+    runtime::Runtime,
+    frontend::{self, Service, ServiceHealth},
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+    future::Future,
+};
+use rocket::{
+    get, State,
+    http::Status,
+    serde::{json::Json, Serialize},
+};
+use tokio::sync::RwLock;
+
+
+pub const BASE_PATH: &str = "/";
+
+/// For how long each component is waited on before being considered unhealthy
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Above this, Tokio is considered overloaded (or blocked) -- tasks are taking too long to be scheduled
+const MAX_SCHEDULING_LATENCY: Duration = Duration::from_millis(500);
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        healthz,
+        readyz,
+    ]
+}
+
+/// What the probes inspect -- to be managed by Rocket
+pub struct SanityCheck {
+    /// where the services are registered -- see [crate::frontend::run_service()]
+    runtime:               Arc<RwLock<Runtime>>,
+    telegram_enabled:      bool,
+    socket_server_enabled: bool,
+    socket_client_enabled: bool,
+    grpc_enabled:          bool,
+}
+
+impl SanityCheck {
+
+    /// only the services enabled in the config are probed
+    pub fn new(runtime: Arc<RwLock<Runtime>>, services: &ExtendedOption<ServicesConfig>) -> Self {
+        let (telegram_enabled, socket_server_enabled, socket_client_enabled, grpc_enabled) = match services {
+            ExtendedOption::Enabled(services) => (services.telegram.is_enabled(), services.socket_server.is_enabled(), services.socket_client.is_enabled(), services.grpc.is_enabled()),
+            _ => (false, false, false, false),
+        };
+        Self { runtime, telegram_enabled, socket_server_enabled, socket_client_enabled, grpc_enabled }
+    }
+}
+
+/// The answer of the probes
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct SanityReport {
+    healthy:    bool,
+    components: Vec<ComponentStatus>,
+}
+
+impl SanityReport {
+    fn new(components: Vec<ComponentStatus>) -> (Status, Json<Self>) {
+        let healthy = components.iter().all(|component| component.healthy);
+        (if healthy { Status::Ok } else { Status::ServiceUnavailable }, Json(Self { healthy, components }))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct ComponentStatus {
+    name:    &'static str,
+    healthy: bool,
+    status:  String,
+}
+
+impl ComponentStatus {
+    fn new(name: &'static str, healthy: bool, status: impl Into<String>) -> Self {
+        Self { name, healthy, status: status.into() }
+    }
+}
+
+/// Liveness probe: "200 OK" while Tokio is able to run our tasks timely -- "503 Service Unavailable" means the process should be restarted
+#[get("/healthz")]
+async fn healthz() -> (Status, Json<SanityReport>) {
+    SanityReport::new(vec![tokio_status().await])
+}
+
+/// Readiness probe: "200 OK" once the enabled services are running, the warmup is done & the maintenance mode is off
+#[get("/readyz")]
+async fn readyz(sanity_check: &State<SanityCheck>) -> (Status, Json<SanityReport>) {
+    let runtime = &sanity_check.runtime;
+    let mut components = vec![tokio_status().await];
+    if sanity_check.socket_server_enabled {
+        components.push(service_status("socket_server", Runtime::do_if_socket_server_is_present(runtime, |socket_server| Box::pin(async move { socket_server.health() }))).await);
+    }
+    if sanity_check.socket_client_enabled {
+        components.push(service_status("socket_client", Runtime::do_if_socket_client_is_present(runtime, |socket_client| Box::pin(async move { socket_client.health() }))).await);
+    }
+    if sanity_check.grpc_enabled {
+        components.push(service_status("grpc", Runtime::do_if_grpc_server_is_present(runtime, |grpc_server| Box::pin(async move { grpc_server.health() }))).await);
+    }
+    if sanity_check.telegram_enabled {
+        components.push(service_status("telegram", telegram_health(runtime)).await);
+    }
+    match tokio::time::timeout(PROBE_TIMEOUT, runtime.read()).await {
+        Ok(runtime) => {
+            let warmup_report = runtime.warmup.report();
+            components.push(ComponentStatus::new("warmup", runtime.warmup.is_ready(), format!("{:?}", warmup_report.status).to_lowercase()));
+            let maintenance = runtime.degrade.maintenance();
+            components.push(ComponentStatus::new("maintenance", !maintenance, if maintenance { "on" } else { "off" }));
+        },
+        Err(_elapsed) => components.push(ComponentStatus::new("runtime", false, format!("locked for more than {:?}", PROBE_TIMEOUT))),
+    }
+    SanityReport::new(components)
+}
+
+/// measures how long Tokio takes to start running a new task
+async fn tokio_status() -> ComponentStatus {
+    let spawned_at = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::spawn(async move { spawned_at.elapsed() })).await {
+        Ok(Ok(latency)) => ComponentStatus::new("tokio", latency <= MAX_SCHEDULING_LATENCY, format!("scheduling latency: {:?}", latency)),
+        Ok(Err(err)) => ComponentStatus::new("tokio", false, format!("probing task failed: {}", err)),
+        Err(_elapsed) => ComponentStatus::new("tokio", false, format!("probing task not ran in {:?}", PROBE_TIMEOUT)),
+    }
+}
+
+/// the status of the service `name`, as told by `health` -- the future resolving to [crate::frontend::Service::health()]
+/// the health of the least healthy Telegram bot -- see [crate::config::ServicesConfig::telegram]
+async fn telegram_health(runtime: &RwLock<Runtime>) -> Option<ServiceHealth> {
+    let healths: Vec<Option<ServiceHealth>> = frontend::telegram_healths(runtime).await.into_iter()
+        .map(|(_bot, health)| health)
+        .collect();
+    [None, Some(ServiceHealth::ShuttingDown), Some(ServiceHealth::Starting)].into_iter()
+        .find(|health| healths.contains(health))
+        .unwrap_or(Some(ServiceHealth::Running))
+}
+
+async fn service_status(name: &'static str, health: impl Future<Output=Option<ServiceHealth>>) -> ComponentStatus {
+    match tokio::time::timeout(PROBE_TIMEOUT, health).await {
+        Ok(Some(ServiceHealth::Running))      => ComponentStatus::new(name, true,  "running"),
+        Ok(Some(ServiceHealth::Starting))     => ComponentStatus::new(name, false, "starting"),
+        Ok(Some(ServiceHealth::ShuttingDown)) => ComponentStatus::new(name, false, "shutting down"),
+        // not registered in the runtime until started -- see `run_service()`
+        Ok(None)                              => ComponentStatus::new(name, false, "starting"),
+        Err(_elapsed)                         => ComponentStatus::new(name, false, format!("no answer in {:?}", PROBE_TIMEOUT)),
+    }
+}
@@ -0,0 +1,189 @@
+//! Example of frontends composing: a gateway bridging the web API to the socket server's protocol -- see [crate::config::WebConfig::socket_proxy_routes].
+//!
+//! `POST /api/socket-proxy` receives a [ClientMessages] (as JSON), forwards it through a connection to our own socket server
+//! -- so it is handled by the very same processor pipeline serving the socket clients -- & answers the [ServerMessages] reply:
+//! ```no_compile
+//!     curl -X POST -H "Content-Type: application/json" -H "X-API-Key: <key>" -d '"Ping"' http://localhost:8000/api/socket-proxy
+//!     {"Pong":1}
+//! ```
+//! Messages the processor doesn't answer to (like `Speechless`) get "204 No Content" once [ANSWER_TIMEOUT] elapses.\
+//! Requests must pass the [Authenticated] guard. Their connections are made on behalf of the web clients: they are registered as hops in
+//! [BridgedPeers] -- so the socket server's limits, bans & statistics apply to each web client's address, rather than to ours -- & do
+//! the `Hello` handshake & (if required, when the socket login uses the auth provider) the `Login` one, with a session token issued to the
+//! request's identity just for that.\
+//! Only mounted if the socket server is enabled -- through plain TCP & the RON wire format -- & its login, if required, may be done on the
+//! web clients' behalf.
+
+use super::api::Authenticated;
+use crate::{
+    runtime::{Principal, SessionTokens},
+    frontend::socket_server::{ClientMessages, ServerMessages, PROTOCOL_VERSION, BridgedPeers, HopGuard, ron_client_serializer, ron_client_deserializer},
+};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+use rocket::{
+    post, State, Request,
+    http::Status,
+    request::{FromRequest, Outcome},
+    serde::json::Json,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    net::tcp::{OwnedReadHalf, OwnedWriteHalf},
+};
+use log::warn;
+
+
+pub const BASE_PATH: &str = "/api";
+
+/// How long to wait for the socket server to answer
+pub const ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        socket_proxy,
+    ]
+}
+
+/// How to reach our socket server on behalf of the web clients -- Rocket's managed state for this module & for [super::websocket]
+#[derive(Clone)]
+pub struct SocketProxy {
+    address:        SocketAddr,
+    /// where our connections are registered as hops, so the socket server knows which web client they serve
+    peers:          Arc<BridgedPeers>,
+    /// if set, the socket server requires logging in -- which is done on behalf of the web clients, with session tokens issued to them
+    session_tokens: Option<Arc<SessionTokens>>,
+}
+
+impl SocketProxy {
+
+    /// `interface` & `port` are the ones our socket server listens to -- `peers` being the ones it consults (see [crate::runtime::Runtime::socket_server_peers]).\
+    /// `session_tokens` must be given if the socket server's login is required -- which must, then, use the auth provider
+    pub fn new(interface: &str, port: u16, peers: Arc<BridgedPeers>, session_tokens: Option<Arc<SessionTokens>>) -> Result<Self, String> {
+        let host = if interface == "0.0.0.0" { "127.0.0.1" } else { interface };
+        let address = format!("{}:{}", host, port).parse()
+            .map_err(|err| format!("the socket server's interface '{}' is not an IP address: {}", interface, err))?;
+        Ok(Self { address, peers, session_tokens })
+    }
+
+    /// Connects to the socket server on behalf of the web client at `client_addr`, logging `principal` in -- if required & known
+    pub async fn connect(&self, principal: Option<&Principal>, client_addr: SocketAddr) -> Result<GatewayConnection, String> {
+        let (stream, hop) = self.peers.connect(self.address, Some(client_addr)).await
+            .map_err(|err| format!("could not connect to the socket server at {}: {}", self.address, err))?;
+        let (reader, writer) = stream.into_split();
+        let mut connection = GatewayConnection { lines: BufReader::new(reader).lines(), writer, hop };
+        if let (Some(session_tokens), Some(principal)) = (&self.session_tokens, principal) {
+            let session_token = session_tokens.issue(principal);
+            let logged_in = connection.request(&ClientMessages::Login { token: session_token.token.clone() }).await;
+            // once logged in, the connection keeps its identity -- so the token is no longer needed
+            session_tokens.revoke(&session_token.token, principal);
+            let refused = match logged_in {
+                Ok(ServerMessages::LoggedIn(_name)) => None,
+                Ok(ServerMessages::Unauthenticated(reason)) => Some(format!("the socket server refused to log '{}' in: {}", principal.name, reason)),
+                Ok(unexpected) => Some(format!("the socket server answered the `Login` with {:?}", unexpected)),
+                Err(err) => Some(err),
+            };
+            if let Some(err) = refused {
+                connection.close();
+                return Err(err)
+            }
+        }
+        Ok(connection)
+    }
+
+    /// sends `message` to the socket server, on behalf of `principal` at `client_addr`, returning its answer -- or `None` if it didn't answer within [ANSWER_TIMEOUT]
+    async fn forward(&self, principal: &Principal, client_addr: SocketAddr, message: &ClientMessages) -> Result<Option<ServerMessages>, String> {
+        let mut connection = self.connect(Some(principal), client_addr).await?;
+        let answer = async {
+            match connection.request(&ClientMessages::Hello { protocol_version: PROTOCOL_VERSION, features: vec![] }).await? {
+                ServerMessages::Welcome { .. } => (),
+                unexpected => return Err(format!("the socket server answered the `Hello` with {:?}", unexpected)),
+            }
+            connection.send(message).await?;
+            connection.receive().await
+        }.await;
+        connection.close();
+        answer
+    }
+}
+
+/// A connection to the socket server made on behalf of a web client -- see [SocketProxy::connect()]
+pub struct GatewayConnection {
+    lines:  Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    /// keeps the connection mapped to the web client
+    hop:    HopGuard,
+}
+
+impl GatewayConnection {
+
+    /// the next line sent by the socket server, as it comes -- `None` once it closes the connection
+    pub async fn next_line(&mut self) -> Result<Option<String>, std::io::Error> {
+        self.lines.next_line().await
+    }
+
+    pub async fn send(&mut self, message: &ClientMessages) -> Result<(), String> {
+        self.writer.write_all(ron_client_serializer(message).as_bytes()).await
+            .map_err(|err| format!("could not send {:?} to the socket server: {}", message, err))
+    }
+
+    /// the next message from the socket server -- `None` if it didn't come within [ANSWER_TIMEOUT]
+    pub async fn receive(&mut self) -> Result<Option<ServerMessages>, String> {
+        match tokio::time::timeout(ANSWER_TIMEOUT, self.lines.next_line()).await {
+            Err(_elapsed) => Ok(None),
+            Ok(Err(err)) => Err(format!("could not receive from the socket server: {}", err)),
+            Ok(Ok(None)) => Err(String::from("the socket server closed the connection")),
+            Ok(Ok(Some(line))) => ron_client_deserializer(line.as_bytes())
+                .map(Some)
+                .map_err(|err| err.to_string()),
+        }
+    }
+
+    /// sends `message`, demanding an answer within [ANSWER_TIMEOUT]
+    async fn request(&mut self, message: &ClientMessages) -> Result<ServerMessages, String> {
+        self.send(message).await?;
+        self.receive().await?
+            .ok_or_else(|| format!("the socket server didn't answer {:?} within {:?}", message, ANSWER_TIMEOUT))
+    }
+
+    /// Disconnects, keeping the mapping to the web client for a while -- so the disconnection is still attributed to it
+    pub fn close(self) {
+        let Self { lines, writer, hop } = self;
+        drop((lines, writer));
+        tokio::spawn(hop.release());
+    }
+}
+
+/// Request guard for the address of the web client -- as told by [Request::client_ip()] (so honoring Rocket's `ip_header`), with the port of
+/// the connection it came from
+pub struct ClientAddr(pub SocketAddr);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientAddr {
+    type Error = &'static str;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.client_ip() {
+            Some(client_ip) => Outcome::Success(ClientAddr(SocketAddr::new(client_ip, request.remote().map_or(0, |remote| remote.port())))),
+            None => Outcome::Error((Status::BadRequest, "The client's address is unknown")),
+        }
+    }
+}
+
+/// Forwards the `message` to the socket server, on behalf of the authenticated web client, answering its reply
+#[post("/socket-proxy", format = "json", data = "<message>")]
+async fn socket_proxy(authenticated: Authenticated, client_addr: ClientAddr, message: Json<ClientMessages>, socket_proxy: &State<SocketProxy>) -> Result<Json<ServerMessages>, (Status, String)> {
+    match socket_proxy.forward(&authenticated.0, client_addr.0, &message).await {
+        Ok(Some(answer)) => Ok(Json(answer)),
+        Ok(None) => Err((Status::NoContent, String::new())),
+        Err(err) => {
+            warn!("Socket Proxy: {} (for '{}' at {})", err, authenticated.0.name, client_addr.0);
+            Err((Status::BadGateway, err))
+        },
+    }
+}
@@ -0,0 +1,43 @@
+//! Exposes the metrics registry -- everything published through the `metrics` facade (see [crate::runtime::install_metrics_recorder()]),
+//! by the processors, the services & the logic alike -- see [crate::config::WebConfig::stats_routes]:
+//!   - `/api/stats/metrics`: as JSON -- for dashboards & scripts;
+//!   - `/api/stats/metrics/prometheus`: in the Prometheus text format -- for scrapers.
+//!
+//! To publish your own metrics, from anywhere:
+//! ```no_compile
+//!     metrics::describe_counter!("orders_total", Unit::Count, "Orders placed, by channel");
+//!     metrics::counter!("orders_total", "channel" => "telegram").increment(1);
+//!     metrics::histogram!("order_processing_seconds").record(elapsed.as_secs_f64());
+
+use crate::runtime::{metrics_report, MetricFamily};
+use metrics_exporter_prometheus::PrometheusHandle;
+use rocket::{
+    get, State,
+    http::ContentType,
+    serde::json::Json,
+};
+
+
+pub const BASE_PATH: &str = "/api/stats";
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        metrics_json,
+        metrics_prometheus,
+    ]
+}
+
+/// All registered metrics, with their kinds, descriptions & labeled values
+#[get("/metrics")]
+fn metrics_json(metrics: &State<PrometheusHandle>) -> Json<Vec<MetricFamily>> {
+    Json(metrics_report(metrics))
+}
+
+/// All registered metrics, in the Prometheus text exposition format
+#[get("/metrics/prometheus")]
+fn metrics_prometheus(metrics: &State<PrometheusHandle>) -> (ContentType, String) {
+    (ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")]),
+     metrics.render())
+}
@@ -0,0 +1,96 @@
+//! WebSocket bridge to the socket server -- see [crate::config::WebConfig::websocket_routes]: so browsers (like our Angular web app)
+//! may speak the socket server's protocol & receive whatever it sends, as it is sent, without polling.
+//!
+//! Each WebSocket connected to `/api/ws` gets its own connection to our socket server -- being, then, a regular client of its processor
+//! pipeline, with its own per-client states & statistics. Text frames carry the messages as JSON:
+//! ```no_compile
+//!     const ws = new WebSocket("ws://localhost:8000/api/ws");
+//!     ws.onmessage = (event) => console.log(JSON.parse(event.data));  // {"Pong":1}
+//!     ws.onopen = () => ws.send(JSON.stringify("Ping"));
+//! ```
+//! Frames not understood as [ClientMessages] are answered with [ServerMessages::UnknownMessage].\
+//! The connections are made on behalf of the browsers, as in [super::socket_proxy]: the socket server's limits, bans & statistics apply
+//! to each browser's address. If the upgrade request passes the [Authenticated] guard (& the socket login uses the auth provider), the
+//! identity is logged in on the browser's behalf -- otherwise, as browsers may not set headers on WebSockets, they do the `Login` handshake
+//! themselves, with a token issued by `/api/session/socket-token`. The `Hello` handshake is always left to them.\
+//! Only mounted if the socket server is enabled through plain TCP & the RON wire format -- as in [super::socket_proxy].
+
+use super::{
+    api::Authenticated,
+    socket_proxy::{SocketProxy, ClientAddr},
+};
+use crate::frontend::socket_server::{ClientMessages, ServerMessages, ron_client_deserializer};
+use rocket::{
+    get, State,
+    serde::json,
+};
+use rocket_ws::{WebSocket, Channel, Message};
+use futures::{SinkExt, StreamExt};
+use log::{debug, warn};
+
+
+pub const BASE_PATH: &str = "/api";
+
+
+/// all methods exported by this module
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        websocket,
+    ]
+}
+
+/// Upgrades the connection to a WebSocket, bridging it to the socket server -- on behalf of the browser -- until either side closes it
+#[get("/ws")]
+fn websocket(ws: WebSocket, authenticated: Option<Authenticated>, client_addr: ClientAddr, socket_proxy: &State<SocketProxy>) -> Channel<'static> {
+    let socket_proxy = socket_proxy.inner().clone();
+    ws.channel(move |duplex| Box::pin(async move {
+        let principal = authenticated.map(|authenticated| authenticated.0);
+        let mut connection = socket_proxy.connect(principal.as_ref(), client_addr.0).await
+            .map_err(|err| {
+                warn!("WebSocket: {} (for {})", err, client_addr.0);
+                gateway_error(err)
+            })?;
+        let (mut ws_sink, mut ws_stream) = duplex.split();
+        // the hop is released whatever ends the relay -- so the disconnection is attributed to the browser
+        let relayed: Result<(), rocket_ws::result::Error> = async {
+            loop {
+                tokio::select! {
+                    ws_message = ws_stream.next() => match ws_message {
+                        Some(Ok(Message::Text(text))) => match json::from_str::<ClientMessages>(&text) {
+                            Ok(client_message) => connection.send(&client_message).await.map_err(gateway_error)?,
+                            Err(_err) => ws_sink.send(to_frame(&ServerMessages::UnknownMessage(text))).await?,
+                        },
+                        Some(Ok(Message::Close(_))) | None => break,
+                        // pings are answered by the WebSocket layer itself
+                        Some(Ok(_other_frame)) => (),
+                        Some(Err(err)) => {
+                            debug!("WebSocket: receive error -- closing: {}", err);
+                            break
+                        },
+                    },
+                    socket_line = connection.next_line() => match socket_line? {
+                        // the deserialization error is not `Send` -- so it may not be held across the `.await`
+                        Some(line) => match ron_client_deserializer(line.as_bytes()).map_err(|err| err.to_string()) {
+                            Ok(server_message) => ws_sink.send(to_frame(&server_message)).await?,
+                            Err(err) => warn!("WebSocket: could not understand the socket server's message -- dropping it: {}", err),
+                        },
+                        // the socket server closed the connection (like when shutting down)
+                        None => break,
+                    },
+                }
+            }
+            Ok(())
+        }.await;
+        connection.close();
+        relayed?;
+        ws_sink.close().await
+    }))
+}
+
+fn gateway_error(err: String) -> rocket_ws::result::Error {
+    rocket_ws::result::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, err))
+}
+
+fn to_frame(server_message: &ServerMessages) -> Message {
+    Message::Text(json::to_string(server_message).expect("BUG! `ServerMessages` are serializable to JSON"))
+}
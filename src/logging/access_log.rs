@@ -0,0 +1,76 @@
+//! The access log -- see [crate::config::Config::access_log]: one line per access to our services, apart from the application's log.
+//!
+//! Lines follow the spirit of the "combined log format" -- `<client> - <user> [<time>] "<request>" <status> <bytes> "<referer>" "<user agent>"`
+//! -- with `-` for the unknown fields:
+//! ```no_compile
+//!     127.0.0.1 - - [16/Oct/2026:10:42:00 -0300] "GET /api/stats/socket" 200 1234 "-" "curl/8.0.1"
+//!     127.0.0.1:50422 - - [16/Oct/2026:10:42:01 -0300] "CONNECT socket" - - "-" "-"
+//!     chat:-1001234 - 123456789 [16/Oct/2026:10:42:02 -0300] "/toimperial 36.6 1.8" ok - "-" "telegram"
+//! ```
+//! each prefixed by the timestamp & level added by the `slog` drain -- which also rotates the file. Log redactions apply.
+
+use super::{
+    redaction::{RedactionFilter, SharedRedactions},
+    rotating_file::build_rotating_file_logger,
+};
+use crate::config::{AccessLogConfig, ExtendedOption};
+use std::fmt::Display;
+use once_cell::sync::OnceCell;
+use slog::Drain;
+use sloggers::types::Severity;
+
+
+/// Present if the access log is enabled
+static ACCESS_LOGGER: OnceCell<slog::Logger> = OnceCell::new();
+
+
+/// starts the access logger, if enabled in `access_log_config` -- masking the given `redactions` out of the lines
+pub fn setup_access_log(access_log_config: &ExtendedOption<AccessLogConfig>, redactions: SharedRedactions) {
+    let ExtendedOption::Enabled(access_log_config) = access_log_config else { return };
+    let logger = build_rotating_file_logger(&access_log_config.file_path,
+                                            access_log_config.rotation_size,
+                                            access_log_config.rotations_kept,
+                                            access_log_config.compress_rotated,
+                                            Severity::Info)
+        .unwrap_or_else(|err| panic!("Could not create the access log '{}': {}", access_log_config.file_path, err));
+    let logger = slog::Logger::root(RedactionFilter::new(logger, redactions).fuse(), slog::o!());
+    ACCESS_LOGGER.set(logger).ok();
+}
+
+/// tells if accesses are being logged -- so callers may skip gathering their data otherwise
+pub fn is_access_log_enabled() -> bool {
+    ACCESS_LOGGER.get().is_some()
+}
+
+/// logs an HTTP request, answered with `status` & a body of `bytes` (if known)
+pub fn log_http_access(client: Option<impl Display>, method: impl Display, uri: impl Display, status: u16, bytes: Option<usize>, referer: Option<&str>, user_agent: Option<&str>) {
+    log_access(client.map(|client| client.to_string()).as_deref(), None, &format!("{} {}", method, uri), &status.to_string(), bytes, referer, user_agent);
+}
+
+/// logs a `event` (like "CONNECT" or "DISCONNECT") on a socket connection
+pub fn log_socket_access(client: impl Display, event: &str) {
+    log_access(Some(&client.to_string()), None, &format!("{} socket", event), "-", None, None, None);
+}
+
+/// logs a Telegram `command` (the message text) sent by `user_id` in `chat_id`, with the given `outcome` (like "ok" or "throttled")
+pub fn log_telegram_access(chat_id: i64, user_id: u64, command: &str, outcome: &str) {
+    log_access(Some(&format!("chat:{}", chat_id)), Some(&user_id.to_string()), command, outcome, None, None, Some("telegram"));
+}
+
+fn log_access(client: Option<&str>, user: Option<&str>, request: &str, status: &str, bytes: Option<usize>, referer: Option<&str>, user_agent: Option<&str>) {
+    let Some(logger) = ACCESS_LOGGER.get() else { return };
+    slog::info!(logger, "{} - {} [{}] \"{}\" {} {} \"{}\" \"{}\"",
+                client.unwrap_or("-"),
+                user.unwrap_or("-"),
+                chrono::Local::now().format("%d/%b/%Y:%H:%M:%S %z"),
+                escape(request),
+                status,
+                bytes.map_or(String::from("-"), |bytes| bytes.to_string()),
+                escape(referer.unwrap_or("-")),
+                escape(user_agent.unwrap_or("-")));
+}
+
+/// so quoted fields may not be broken by their contents
+fn escape(field: &str) -> String {
+    field.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,59 @@
+//! Tags the log records with the correlation id in scope -- see [crate::runtime::correlation_id()] -- so all the lines caused by a single
+//! user action, across the subsystems, may be found at once: grep for `correlation_id=<id>`
+
+use crate::runtime::correlation_id;
+use slog::{Drain, OwnedKVList, Record, RecordStatic};
+
+
+/// `slog` drain wrapper appending ` [correlation_id=<id>]` to the messages logged while a correlation id is in scope.\
+/// The id is taken from the logging task, so this drain must run before any asynchronous ones
+pub struct CorrelationTagger<D: Drain> {
+    drain: D,
+}
+
+impl<D: Drain> CorrelationTagger<D> {
+
+    pub fn new(drain: D) -> Self {
+        Self { drain }
+    }
+}
+
+impl<D: Drain> Drain for CorrelationTagger<D> {
+    type Ok  = D::Ok;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let Some(correlation_id) = correlation_id() else {
+            return self.drain.log(record, values)
+        };
+        let record_static = RecordStatic {
+            location: record.location(),
+            tag:      record.tag(),
+            level:    record.level(),
+        };
+        self.drain.log(&Record::new(&record_static, &format_args!("{} [correlation_id={}]", record.msg(), correlation_id), record.kv()), values)
+    }
+}
+
+
+/// Unit tests the [correlation](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        runtime::correlated,
+        logging::test_utils::CollectorDrain,
+    };
+    use std::sync::{Arc, Mutex};
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn messages_are_tagged() {
+        let messages = Arc::new(Mutex::new(vec![]));
+        let logger = slog::Logger::root(CorrelationTagger::new(CollectorDrain(Arc::clone(&messages))), slog::o!());
+        slog::info!(logger, "uncorrelated");
+        correlated(Some(String::from("abc")), async { slog::info!(logger, "correlated") }).await;
+        assert_eq!(*messages.lock().unwrap(), vec!["uncorrelated".to_string(), "correlated [correlation_id=abc]".to_string()],
+                   "Only the messages logged with a correlation id in scope should be tagged");
+    }
+}
@@ -0,0 +1,121 @@
+//! Live log following: records are published -- already redacted -- to the subscribers of [subscribe()], like the `/api/logs/tail` route
+//! (see [crate::config::WebConfig::logs_following_routes]) followed by `console tail`. Records are only rendered while someone is following
+//! -- or while the most recent ones are kept for new followers to catch up with. See [keep_recent()].
+
+use super::target_filter::matches_segments;
+use std::{
+    collections::VecDeque,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use once_cell::sync::Lazy;
+use serde::{Serialize, Deserialize};
+use slog::{Drain, Level, OwnedKVList, Record};
+use tokio::sync::broadcast;
+
+
+/// How many records may be waiting for slow followers -- beyond that, they will miss the oldest ones
+const FOLLOWERS_BUFFER: usize = 1024;
+
+/// How many of the most recent records are kept, when following the logs is enabled -- see [keep_recent()]
+pub const RECENT_RECORDS: usize = 256;
+
+static FOLLOWERS: Lazy<broadcast::Sender<TailedRecord>> = Lazy::new(|| broadcast::channel(FOLLOWERS_BUFFER).0);
+
+/// The ring buffer of the most recent records -- also held while publishing them, so followers neither miss nor repeat records
+/// between the ones they catch up with & the ones they follow
+static RECENT: Lazy<parking_lot::Mutex<VecDeque<TailedRecord>>> = Lazy::new(|| parking_lot::Mutex::new(VecDeque::new()));
+
+/// The capacity of [RECENT] -- 0 if the recent records are not to be kept
+static RECENT_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+
+
+/// A log record, as sent to the followers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailedRecord {
+    pub timestamp: String,
+    /// as in `slog`: "CRITICAL", "ERROR", "WARN", "INFO", "DEBUG" or "TRACE"
+    pub level:     String,
+    pub target:    String,
+    pub message:   String,
+}
+
+/// Selects the records a follower is interested in
+pub struct TailFilter {
+    min_level: Level,
+    target:    Option<String>,
+}
+
+impl TailFilter {
+
+    /// `level` is the least severe level to follow -- "info", "debug", ...; `target` matches (in whole `::` segments) the records' targets
+    pub fn new(level: &str, target: Option<&str>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let min_level = Level::from_str(level)
+            .map_err(|_| format!("Unknown log level '{}' -- known ones are: critical, error, warn, info, debug & trace", level))?;
+        Ok(Self { min_level, target: target.map(|target| target.to_string()) })
+    }
+
+    pub fn matches(&self, record: &TailedRecord) -> bool {
+        let level_matches = Level::from_str(&record.level).is_ok_and(|level| level.is_at_least(self.min_level));
+        let target_matches = match &self.target {
+            Some(target) => matches_segments(&record.target, target),
+            None => true,
+        };
+        level_matches && target_matches
+    }
+}
+
+/// keeps up to `capacity` of the most recent records (0 to keep none) -- for new followers to catch up with. See [subscribe()]
+pub fn keep_recent(capacity: usize) {
+    RECENT_CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut recent = RECENT.lock();
+    while recent.len() > capacity {
+        recent.pop_front();
+    }
+}
+
+/// Follows the log records logged from now on -- returning, as well, the most recent ones already logged (the oldest first), if they are kept.
+/// See [keep_recent()]
+pub fn subscribe() -> (Vec<TailedRecord>, broadcast::Receiver<TailedRecord>) {
+    let recent = RECENT.lock();
+    (recent.iter().cloned().collect(), FOLLOWERS.subscribe())
+}
+
+/// `slog` drain wrapper publishing the records to the followers -- see [subscribe()].\
+/// Should be placed after the redaction drain, so secrets don't leak through it
+pub struct LogTail<D: Drain> {
+    drain: D,
+}
+
+impl<D: Drain> LogTail<D> {
+    pub fn new(drain: D) -> Self {
+        Self { drain }
+    }
+}
+
+impl<D: Drain> Drain for LogTail<D> {
+    type Ok  = D::Ok;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let recent_capacity = RECENT_CAPACITY.load(Ordering::Relaxed);
+        if recent_capacity > 0 || FOLLOWERS.receiver_count() > 0 {
+            let tailed_record = TailedRecord {
+                timestamp: chrono::Local::now().to_rfc3339(),
+                level:     record.level().as_str().to_string(),
+                target:    if record.tag().is_empty() { record.module() } else { record.tag() }.to_string(),
+                message:   record.msg().to_string(),
+            };
+            let mut recent = RECENT.lock();
+            if recent_capacity > 0 {
+                if recent.len() >= recent_capacity {
+                    recent.pop_front();
+                }
+                recent.push_back(tailed_record.clone());
+            }
+            // sending only fails if there are no followers
+            let _ = FOLLOWERS.send(tailed_record);
+        }
+        self.drain.log(record, values)
+    }
+}
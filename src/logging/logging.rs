@@ -0,0 +1,148 @@
+//! see [super]
+
+use super::{
+    target_filter::{self, TargetLevelFilter},
+    sampling::SamplingFilter,
+    correlation::CorrelationTagger,
+    redaction::{RedactionFilter, SharedRedactions},
+    access_log::setup_access_log,
+    log_tail::{self, LogTail},
+    rotating_file::{build_rotating_file_logger, request_reopen},
+    pretty_console::PrettyConsole,
+};
+use crate::config::{DEBUG, Config, ExtendedOption, LoggingOptions, LogCompression, UiOptions, TerminalOptions};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+};
+use once_cell::sync::Lazy;
+use slog::Drain;
+use slog_scope::GlobalLoggerGuard;
+use sloggers::{Build, types::Severity};
+use log::{info, warn, error};
+
+
+/// Keep those levels in sync with Cargo.toml's `log` crate levels defined in features.
+/// Example: features = ["max_level_debug", "release_max_level_info"]
+const LOG_LEVEL: Severity = if DEBUG {
+    Severity::Debug
+} else {
+    Severity::Info
+};
+
+/// The redactions of the application's & access logs -- see [apply_log_redactions()]
+static REDACTIONS: Lazy<SharedRedactions> = Lazy::new(SharedRedactions::default);
+
+/// starts a global logger according to `config` specifications -- along with the access log, if enabled
+/// -- the returned value should not be dropped until the program ends
+pub fn setup_logging(config: &Config) -> GlobalLoggerGuard {
+    let logger = match &config.log {
+        LoggingOptions::Quiet => build_quiet_logger(),
+        LoggingOptions::ToConsole => build_console_logger(),
+        LoggingOptions::ToConsolePretty {shorten_modules, align_key_values} => build_pretty_console_logger(*shorten_modules, *align_key_values),
+        LoggingOptions::ToFile {file_path, rotation_size, rotations_kept, compress_rotated} => build_file_logger(&file_path, *rotation_size, *rotations_kept, *compress_rotated)
+    };
+    *REDACTIONS.write().expect("BUG! the log redactions' lock is poisoned") = RedactionFilter::<slog::Logger>::redactions_from_config(config)
+        .unwrap_or_else(|err| panic!("Could not setup log redactions: {}", err));
+    setup_access_log(&config.access_log, Arc::clone(&REDACTIONS));
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(web) = &services.web {
+            if web.logs_following_routes || web.admin_token.is_some() {
+                // so followers may catch up with what was logged before they came -- as do the "Logs" tab of remote Terminal UIs
+                log_tail::keep_recent(log_tail::RECENT_RECORDS);
+            }
+        }
+    }
+    if let ExtendedOption::Enabled(UiOptions::Terminal(TerminalOptions { connect: None, .. })) = &config.ui {
+        // for the Terminal UI's "Logs" tab
+        log_tail::keep_recent(log_tail::RECENT_RECORDS);
+    }
+    let logger = RedactionFilter::new(LogTail::new(logger), Arc::clone(&REDACTIONS));
+    let logger = SamplingFilter::new(CorrelationTagger::new(logger), &config.log_sampling);
+    let logger = slog::Logger::root(TargetLevelFilter::new(logger, LOG_LEVEL.as_level(), &config.log_filters).fuse(), slog::o!());
+    let log_guard = slog_scope::set_global_logger(logger);
+    slog_stdlog::init().unwrap();
+    warn_of_uncompiled_levels(&config.log_filters);
+    log_guard
+}
+
+/// replaces the per-target level overrides set up by [setup_logging()] -- see [crate::config::Config::log_filters].
+/// Used by the config hot reload
+pub fn apply_log_filters(log_filters: &BTreeMap<String, Severity>) {
+    target_filter::set_filters(log_filters);
+    warn_of_uncompiled_levels(log_filters);
+}
+
+/// rebuilds the redactions set up by [setup_logging()] from `config` -- so changed secrets (like the Telegram notification chat ids) are
+/// masked as well. Used by the config hot reload
+pub fn apply_log_redactions(config: &Config) {
+    match RedactionFilter::<slog::Logger>::redactions_from_config(config) {
+        Ok(redactions) => *REDACTIONS.write().expect("BUG! the log redactions' lock is poisoned") = redactions,
+        Err(err) => error!("Could not rebuild the log redactions -- keeping the previous ones: {}", err),
+    }
+}
+
+/// Rotates & reopens the log files -- see [super::rotating_file] -- whenever a SIGHUP is received, as `logrotate` (& operators) expect.
+/// Resolves right away if `config` logs to no files
+pub async fn reopen_files_on_sighup(config: &Config) {
+    if !matches!(config.log, LoggingOptions::ToFile { .. }) && !config.access_log.is_enabled() {
+        return
+    }
+    #[cfg(unix)]
+    {
+        let mut sighups = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(sighups) => sighups,
+            Err(err) => return error!("SIGHUP won't reopen the log files: {}", err),
+        };
+        while sighups.recv().await.is_some() {
+            request_reopen();
+            // files reopen themselves before writing their next records -- this one's included
+            info!("SIGHUP received: rotating & reopening the log files");
+        }
+    }
+}
+
+fn warn_of_uncompiled_levels(log_filters: &BTreeMap<String, Severity>) {
+    for (target, severity) in log_filters {
+        if severity_as_level_filter(*severity) > log::STATIC_MAX_LEVEL {
+            warn!("Log filter for '{}' asks for {:?} messages, but only up to {} are compiled in -- tweak the `log` features in Cargo.toml if you really need them",
+                  target, severity, log::STATIC_MAX_LEVEL);
+        }
+    }
+}
+
+/// Inner drains let all levels through -- [TargetLevelFilter] is the one deciding what gets logged
+fn build_quiet_logger() -> slog::Logger {
+    sloggers::null::NullLoggerBuilder {}
+        .build()
+        .expect("Could not create a 'quiet' logger")
+}
+
+fn build_console_logger() -> slog::Logger {
+    let mut builder = sloggers::terminal::TerminalLoggerBuilder::new();
+    builder.level(Severity::Trace);
+    builder.destination(sloggers::terminal::Destination::Stdout);
+    builder.build().expect("Could not create a 'console' logger")
+}
+
+fn build_pretty_console_logger(shorten_modules: bool, align_key_values: bool) -> slog::Logger {
+    // write errors (like a closed stdout) are not worth panicking for
+    slog::Logger::root(PrettyConsole::new(shorten_modules, align_key_values).ignore_res(), slog::o!())
+}
+
+fn build_file_logger(log_file: &str, rotate_size: usize, rotate_keep: usize, rotate_compress: LogCompression) -> slog::Logger {
+    build_rotating_file_logger(log_file, rotate_size, rotate_keep, rotate_compress, Severity::Trace)
+        .unwrap_or_else(|err| panic!("Could not create a file logger: {}", err))
+}
+
+/// maps `slog`'s severities into the `log` crate's equivalents
+fn severity_as_level_filter(severity: Severity) -> log::LevelFilter {
+    match severity {
+        Severity::Trace    => log::LevelFilter::Trace,
+        Severity::Debug    => log::LevelFilter::Debug,
+        Severity::Info     => log::LevelFilter::Info,
+        Severity::Warning  => log::LevelFilter::Warn,
+        Severity::Error    => log::LevelFilter::Error,
+        Severity::Critical => log::LevelFilter::Error,
+    }
+}
@@ -0,0 +1,30 @@
+//! Facade for the `slog` crate to behave just like the `log` API
+//! (currently we use `slog-scope` & `slog-stdlog` crates for the heavy lifting).
+//!
+//! Besides building the drains specified in [crate::config::LoggingOptions], this module provides
+//! `slog` drain wrappers applied to all of them:
+//!   * [target_filter]: per-target level overrides -- see [crate::config::Config::log_filters]
+//!   * [sampling]: burst suppression & sampling of trace/debug messages -- see [crate::config::Config::log_sampling]
+//!   * [correlation]: tags the messages with the correlation id in scope -- see [crate::runtime::correlated()]
+//!   * [redaction]: masks sensitive data out of the messages -- see [crate::config::Config::log_redactions]
+//!
+//! File logs are rotated & compressed by [rotating_file], while [pretty_console] renders the colored console logs for local development.\
+//! Apart from them, [access_log] keeps the accesses to our services -- see [crate::config::Config::access_log] -- and
+//! [log_tail] streams the (redacted) records to whoever is following them -- see [crate::config::WebConfig::logs_following_routes].\
+//! Tracing spans -- for distributed tracing -- are exported by [telemetry], apart from the logs
+
+mod logging;
+pub use logging::*;
+
+mod target_filter;
+mod sampling;
+mod correlation;
+mod redaction;
+mod rotating_file;
+mod pretty_console;
+#[cfg(any(test, feature = "dox"))]
+mod test_utils;
+
+pub mod access_log;
+pub mod log_tail;
+pub mod telemetry;
@@ -0,0 +1,125 @@
+//! Human-friendly console logging, for local development -- see [crate::config::LoggingOptions::ToConsolePretty]: compact timestamps,
+//! colored levels, shortened module paths & (optionally) aligned key-values, one record per line:
+//! ```no_compile
+//!     10:42:00.123 INFO  k::f::w::api      GET /api/stats/runtime answered          status=200 elapsed_ms=3
+//! ```
+//! Colors are only used if stdout is a terminal -- & if the `NO_COLOR` environment variable is not set.
+
+use std::{
+    fmt::{self, Write as _},
+    io::{self, IsTerminal, Write},
+};
+use slog::{Drain, Key, Level, OwnedKVList, Record, KV};
+
+
+/// The column where the key-values start, when aligned -- shorter messages are padded up to it
+const KEY_VALUES_COLUMN: usize = 48;
+
+/// Targets are padded up to this width, so the messages start in the same column
+const TARGET_WIDTH: usize = 16;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+
+
+/// The `slog` drain writing the pretty records to stdout
+pub struct PrettyConsole {
+    colored:          bool,
+    shorten_modules:  bool,
+    align_key_values: bool,
+}
+
+impl PrettyConsole {
+
+    pub fn new(shorten_modules: bool, align_key_values: bool) -> Self {
+        Self {
+            colored: io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            shorten_modules,
+            align_key_values,
+        }
+    }
+
+    /// the line for `record` -- without the line break
+    fn format(&self, record: &Record<'_>, values: &OwnedKVList) -> String {
+        let target = if record.tag().is_empty() { record.module() } else { record.tag() };
+        let target = if self.shorten_modules { shorten_module(target) } else { target.to_string() };
+        let message = record.msg().to_string();
+        let mut key_values = KeyValues::default();
+        // errors only mean some values could not be rendered -- they are logged without them
+        let _ = record.kv().serialize(record, &mut key_values);
+        let _ = values.serialize(record, &mut key_values);
+        let mut line = String::with_capacity(128);
+        let (dim, cyan, level_color, reset) = if self.colored { (DIM, CYAN, level_color(record.level()), RESET) } else { ("", "", "", "") };
+        let _ = write!(line, "{dim}{}{reset} {level_color}{:<5}{reset} {cyan}{:<TARGET_WIDTH$}{reset} {}",
+                       chrono::Local::now().format("%H:%M:%S%.3f"), record.level().as_short_str(), target, message);
+        if !key_values.0.is_empty() {
+            let padding = if self.align_key_values { KEY_VALUES_COLUMN.saturating_sub(message.chars().count()) } else { 0 };
+            let _ = write!(line, "{:padding$} {dim}{}{reset}", "", key_values.0.trim_start());
+        }
+        line
+    }
+}
+
+impl Drain for PrettyConsole {
+    type Ok  = ();
+    type Err = io::Error;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let mut line = self.format(record, values);
+        line.push('\n');
+        // a single write, so records of concurrent threads are never interleaved
+        io::stdout().lock().write_all(line.as_bytes())
+    }
+}
+
+/// Renders the key-values as `key=value` pairs, separated by spaces
+#[derive(Default)]
+struct KeyValues(String);
+
+impl slog::Serializer for KeyValues {
+    fn emit_arguments(&mut self, key: Key, value: &fmt::Arguments<'_>) -> slog::Result {
+        let _ = write!(self.0, " {}={}", key, value);
+        Ok(())
+    }
+}
+
+/// `"kickass_app_template::frontend::web::api"` becomes `"k::f::w::api"` -- all segments, but the last, are reduced to their initials
+fn shorten_module(module: &str) -> String {
+    match module.rsplit_once("::") {
+        Some((parents, last)) => {
+            let mut shortened: String = parents.split("::")
+                .filter_map(|segment| segment.chars().next())
+                .flat_map(|initial| [initial, ':', ':'])
+                .collect();
+            shortened.push_str(last);
+            shortened
+        },
+        None => module.to_string(),
+    }
+}
+
+fn level_color(level: Level) -> &'static str {
+    match level {
+        Level::Critical => "\x1b[1;91m",
+        Level::Error    => "\x1b[91m",
+        Level::Warning  => "\x1b[93m",
+        Level::Info     => "\x1b[92m",
+        Level::Debug    => "\x1b[94m",
+        Level::Trace    => "\x1b[95m",
+    }
+}
+
+
+/// Unit tests the [pretty_console](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn module_paths_are_shortened() {
+        assert_eq!(shorten_module("kickass_app_template::frontend::web::api"), "k::f::w::api", "Parent segments should be reduced to their initials");
+        assert_eq!(shorten_module("rocket"), "rocket", "Single segment targets should be kept as they are");
+    }
+}
@@ -0,0 +1,240 @@
+//! Sensitive data redaction for `slog` -- so tokens, chat ids, API keys, e-mails, ... never reach the console nor the log files.\
+//! The redactions may be replaced at runtime -- see [SharedRedactions] -- as done by the config hot reload.
+
+use crate::config::{Config, ExtendedOption, SocketLogin, SocketServerConfig};
+use std::{
+    borrow::Cow,
+    sync::{Arc, RwLock},
+};
+use slog::{Drain, OwnedKVList, Record, RecordStatic};
+use regex::{Captures, Regex};
+
+
+/// What sensitive data is replaced with
+pub const REDACTED: &str = "<redacted>";
+
+/// The redaction expressions of [RedactionFilter]s -- shared so they may be replaced (for the next record) by whoever holds them.\
+/// (`std`'s lock, as `slog` requires drains to be unwind safe)
+pub type SharedRedactions = Arc<RwLock<Vec<Regex>>>;
+
+/// `slog` drain wrapper masking, from every record's message, the sensitive data matched by any of its `redactions`.\
+/// Only the message is inspected -- key-value pairs are passed through untouched, as the `log` facade we use doesn't produce them.
+/// Notice the message needs to be rendered in order to be inspected, so this drain should be placed after any filtering ones.
+pub struct RedactionFilter<D: Drain> {
+    drain:      D,
+    redactions: SharedRedactions,
+}
+
+impl<D: Drain> RedactionFilter<D> {
+
+    pub fn new(drain: D, redactions: SharedRedactions) -> Self {
+        Self {
+            drain,
+            redactions,
+        }
+    }
+
+    /// builds the redaction expressions from [Config::log_redactions], adding the literal secrets
+    /// present elsewhere in `config` -- the Telegram token & notification chat ids, the webhook secrets & the socket login tokens.\
+    /// Literals only match as whole words, so short ones (like chat ids) don't mask parts of unrelated numbers -- as ports & counters
+    pub fn redactions_from_config(config: &Config) -> Result<Vec<Regex>, Box<dyn std::error::Error + Sync + Send>> {
+        let mut redactions = config.log_redactions.iter()
+            .map(|pattern| Regex::new(pattern)
+                .map_err(|err| format!("Invalid `log_redactions` expression '{}': {}", pattern, err)))
+            .collect::<Result<Vec<Regex>, String>>()?;
+        let mut secrets = vec![];
+        if let ExtendedOption::Enabled(services) = &config.services {
+            if let ExtendedOption::Enabled(telegram_bots) = &services.telegram {
+                for telegram in telegram_bots {
+                    secrets.push(telegram.token.clone());
+                    secrets.extend(telegram.notification_chat_ids.iter().map(|chat_id| chat_id.to_string()));
+                }
+            }
+            if let ExtendedOption::Enabled(SocketServerConfig { login: ExtendedOption::Enabled(SocketLogin::StaticTokens(tokens)), .. }) = &services.socket_server {
+                secrets.extend(tokens.iter().cloned());
+            }
+        }
+        if let ExtendedOption::Enabled(webhooks) = &config.webhooks {
+            secrets.extend(webhooks.targets.values().map(|target| target.secret.clone()));
+        }
+        secrets.sort();
+        secrets.dedup();
+        redactions.extend(secrets.iter()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| literal_redaction(secret)));
+        Ok(redactions)
+    }
+
+    /// applies all `redactions` to `message` -- returning it borrowed if nothing had to be masked
+    fn redact<'a>(redactions: &[Regex], message: &'a str) -> Cow<'a, str> {
+        redactions.iter()
+            .fold(Cow::Borrowed(message), |message, redaction| {
+                if !redaction.is_match(&message) {
+                    return message
+                }
+                let redacted = redaction.replace_all(&message, |captures: &Captures| mask(captures)).into_owned();
+                Cow::Owned(redacted)
+            })
+    }
+}
+
+impl<D: Drain> Drain for RedactionFilter<D> {
+    type Ok  = D::Ok;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let redactions = self.redactions.read().expect("BUG! the log redactions' lock is poisoned");
+        if redactions.is_empty() {
+            return self.drain.log(record, values)
+        }
+        let message = record.msg().to_string();
+        match Self::redact(&redactions, &message) {
+            Cow::Borrowed(_) => self.drain.log(record, values),
+            Cow::Owned(redacted) => {
+                let record_static = RecordStatic {
+                    location: record.location(),
+                    tag:      record.tag(),
+                    level:    record.level(),
+                };
+                self.drain.log(&Record::new(&record_static, &format_args!("{}", redacted), record.kv()), values)
+            },
+        }
+    }
+}
+
+/// the expression matching the literal `secret` -- only as a whole word: not preceded nor followed by word characters
+fn literal_redaction(secret: &str) -> Regex {
+    let is_word = |character: Option<char>| character.map_or(false, |character| character.is_alphanumeric() || character == '_');
+    let pattern = format!("{}{}{}",
+                          if is_word(secret.chars().next()) { r"\b" } else { "" },
+                          regex::escape(secret),
+                          if is_word(secret.chars().last()) { r"\b" } else { "" });
+    Regex::new(&pattern).expect("BUG! escaped literals should always be valid regexes")
+}
+
+/// the replacement for a match: the `secret` group masked, if present -- otherwise, the whole match
+fn mask(captures: &Captures) -> String {
+    let whole = captures.get(0).expect("BUG! regex captures always have the whole match");
+    match captures.name("secret") {
+        Some(secret) => format!("{}{}{}",
+                                &whole.as_str()[..secret.start() - whole.start()],
+                                REDACTED,
+                                &whole.as_str()[secret.end() - whole.start()..]),
+        None => REDACTED.to_string(),
+    }
+}
+
+
+/// Unit tests the [redaction](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{ServicesConfig, TelegramConfig, TelegramBotOptions, TelegramDialogueStorage, TelegramCommandLimits, TelegramMtLimits},
+        logging::test_utils::CollectorDrain,
+    };
+    use std::sync::{Arc, Mutex};
+
+
+    /// a real-looking (but fake) bot token
+    const TELEGRAM_TOKEN: &str = "5213457890:AAHxq3Yp0L-9z_kq2Lm4Rt7uVw8xYz1AbCd";
+    const CHAT_ID: i64 = 1234567890;
+
+    /// the default config with our test telegram secrets -- `chat_ids` being the notification ones
+    fn config(chat_ids: Vec<i64>) -> Config {
+        let mut config = Config::default();
+        config.services = ExtendedOption::Enabled(ServicesConfig {
+            telegram: ExtendedOption::Enabled(vec![TelegramConfig {
+                name:                  String::from("main"),
+                token:                 String::from("<<my-custom-token>>"),
+                bot:                   TelegramBotOptions::Stateless,
+                notification_chat_ids: chat_ids,
+                dialogue_storage:      TelegramDialogueStorage::InMemory,
+                command_limits:        TelegramCommandLimits { max_commands_per_minute: 0, max_commands_per_day: 0 },
+                mt_limits:             TelegramMtLimits { max_queued_messages: 0, max_messages_per_second: 0, min_chat_interval_millis: 0 },
+                status_report_cron:    None,
+            }]),
+            web:           ExtendedOption::Unset,
+            socket_server: ExtendedOption::Unset,
+            socket_client: ExtendedOption::Unset,
+            grpc:          ExtendedOption::Unset,
+        });
+        config
+    }
+
+    /// builds the redactions for `config`
+    fn redactions(config: &Config) -> Vec<Regex> {
+        RedactionFilter::<slog::Discard>::redactions_from_config(config)
+            .expect("Default redactions should be valid")
+    }
+
+    /// returns a logger over [RedactionFilter] for `redactions`, as well as the collected messages
+    fn logger_with(redactions: &SharedRedactions) -> (slog::Logger, Arc<Mutex<Vec<String>>>) {
+        let messages = Arc::new(Mutex::new(vec![]));
+        let logger = slog::Logger::root(RedactionFilter::new(CollectorDrain(Arc::clone(&messages)), Arc::clone(redactions)).fuse(), slog::o!());
+        (logger, messages)
+    }
+
+    /// returns a logger over [RedactionFilter] for the default config with our test telegram secrets, as well as the collected messages
+    fn logger() -> (slog::Logger, Arc<Mutex<Vec<String>>>) {
+        logger_with(&Arc::new(RwLock::new(redactions(&config(vec![CHAT_ID])))))
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn telegram_secrets_never_appear() {
+        let (logger, messages) = logger();
+        slog::info!(logger, "Instantiating 'teloxide' for bot token '{}'", TELEGRAM_TOKEN);
+        slog::info!(logger, "Instantiating 'teloxide' for bot token '{}'", "<<my-custom-token>>");
+        slog::warn!(logger, "Could not notify chat #{}: blocked by the user", CHAT_ID);
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 3, "All messages should have been logged");
+        for message in messages.iter() {
+            assert!(!message.contains(TELEGRAM_TOKEN), "Telegram token leaked into the logs: '{}'", message);
+            assert!(!message.contains("<<my-custom-token>>"), "Configured Telegram token leaked into the logs: '{}'", message);
+            assert!(!message.contains(&CHAT_ID.to_string()), "Notification chat id leaked into the logs: '{}'", message);
+            assert!(message.contains(REDACTED), "Redacted message is missing the '{}' mark: '{}'", REDACTED, message);
+        }
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn emails_and_keys() {
+        let (logger, messages) = logger();
+        slog::info!(logger, "Sending report to john.doe@example.com");
+        slog::info!(logger, "Calling the API with api_key=s3cr3t-K3y; PASSWORD: \"hunter2\"");
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages[0], format!("Sending report to {}", REDACTED), "e-mails should be masked entirely");
+        assert_eq!(messages[1], format!("Calling the API with api_key={}; PASSWORD: \"{}\"", REDACTED, REDACTED), "only the `secret` group should be masked");
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn clean_messages_are_untouched() {
+        let (logger, messages) = logger();
+        slog::info!(logger, "Web server listening on {}:{}", "127.0.0.1", 8000);
+        assert_eq!(messages.lock().unwrap()[0], "Web server listening on 127.0.0.1:8000");
+    }
+
+    /// assures literal secrets only match as whole words -- so short ones, like chat ids, don't mask parts of unrelated numbers
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn literals_match_whole_words() {
+        let (logger, messages) = logger();
+        slog::info!(logger, "Processed {}1 records on port {}", CHAT_ID, 81234);
+        slog::info!(logger, "Notified #{}, then chat_{}", CHAT_ID, CHAT_ID);
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages[0], format!("Processed {}1 records on port 81234", CHAT_ID), "Numbers merely containing a chat id should be untouched");
+        assert_eq!(messages[1], format!("Notified #{}, then chat_{}", REDACTED, CHAT_ID), "Only whole chat ids should have been masked");
+    }
+
+    /// assures replaced redactions -- as done by the config hot reload -- apply to the next records
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn redactions_are_replaceable() {
+        const RELOADED_CHAT_ID: i64 = 987654321;
+        let shared_redactions = Arc::new(RwLock::new(redactions(&config(vec![CHAT_ID]))));
+        let (logger, messages) = logger_with(&shared_redactions);
+        slog::info!(logger, "Notified #{}", RELOADED_CHAT_ID);
+        *shared_redactions.write().unwrap() = redactions(&config(vec![CHAT_ID, RELOADED_CHAT_ID]));
+        slog::info!(logger, "Notified #{}", RELOADED_CHAT_ID);
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages[0], format!("Notified #{}", RELOADED_CHAT_ID), "Sanity check: the chat id was not yet a secret");
+        assert_eq!(messages[1], format!("Notified #{}", REDACTED), "The reloaded chat id should have been masked");
+    }
+}
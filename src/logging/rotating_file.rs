@@ -0,0 +1,294 @@
+//! Size-rotated log files, whose rotations are compressed (see [LogCompression]) by a background thread -- so rotating multi-GB files
+//! never holds the logging drain. Used both by the application's log & by the access log.
+//!
+//! Rotated files are named after the moment they were rotated -- like `app.log.20261016-104200.123.zst` -- so they sort chronologically &
+//! don't have to be renamed when newer ones come in. Only the newest `rotations_kept` are kept.
+//!
+//! Files may also be rotated on demand -- see [request_reopen()], called on SIGHUP -- playing well with `logrotate`: if it already moved
+//! the file away, a new one is simply opened in its place.
+
+use crate::config::LogCompression;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{mpsc, atomic::{AtomicU64, Ordering}},
+    thread,
+};
+use slog::Drain;
+use sloggers::types::Severity;
+use log::error;
+
+
+/// Suffix of the compressed files while they are being written
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Incremented by [request_reopen()] -- files reopen themselves before their next write if it moved past the value they last saw
+static REOPEN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+
+/// asks all log files to flush, rotate & reopen themselves -- which they do right before their next records are written
+pub fn request_reopen() {
+    REOPEN_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+
+/// builds a `slog` logger writing, asynchronously, records at least as severe as `level` to `file_path` -- rotated when it reaches
+/// `rotation_size` bytes (0 for never) & compressed according to `compression`
+pub fn build_rotating_file_logger(file_path:      &str,
+                                  rotation_size:  usize,
+                                  rotations_kept: usize,
+                                  compression:    LogCompression,
+                                  level:          Severity)
+                                 -> Result<slog::Logger, Box<dyn std::error::Error + Send + Sync>> {
+    let rotating_file = RotatingFile::open(Path::new(file_path), rotation_size as u64, rotations_kept, compression)?;
+    let drain = slog_term::FullFormat::new(slog_term::PlainDecorator::new(rotating_file)).build().fuse();
+    let drain = slog_async::Async::new(drain)
+        .overflow_strategy(slog_async::OverflowStrategy::Block)
+        .build()
+        .filter_level(level.as_level())
+        .fuse();
+    Ok(slog::Logger::root(drain, slog::o!()))
+}
+
+/// A log file rotated when it grows beyond `rotation_size` -- checked only on flushes, so records are never split between files
+struct RotatingFile {
+    path:           PathBuf,
+    file:           BufWriter<File>,
+    size:           u64,
+    rotation_size:  u64,
+    /// to where the just rotated files are sent, to be compressed & to have the old rotations removed
+    compressor:     mpsc::Sender<PathBuf>,
+    /// the last [REOPEN_GENERATION] seen
+    reopen_generation: u64,
+}
+
+impl RotatingFile {
+
+    fn open(path: &Path, rotation_size: u64, rotations_kept: usize, compression: LogCompression) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let file = open_for_appending(path)?;
+        let size = file.metadata()
+            .map_err(|err| format!("Could not get the size of the log file '{}': {}", path.display(), err))?
+            .len();
+        let compressor = spawn_compressor(path.to_path_buf(), rotations_kept, compression)?;
+        let reopen_generation = REOPEN_GENERATION.load(Ordering::Relaxed);
+        Ok(Self { path: path.to_path_buf(), file: BufWriter::new(file), size, rotation_size, compressor, reopen_generation })
+    }
+
+    /// done on [request_reopen()]: rotates the file -- as if it had reached `rotation_size` -- unless it was moved away (by `logrotate`,
+    /// for instance), in which case a new one is just opened in its place. Empty files (like the ones `copytruncate`d) are left as they are
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if is_open_at(self.file.get_ref(), &self.path) {
+            self.size = self.file.get_ref().metadata()?.len();
+            if self.size > 0 {
+                self.rotate()?;
+            }
+        } else {
+            self.file = BufWriter::new(open_for_appending(&self.path).map_err(io::Error::other)?);
+            self.size = self.file.get_ref().metadata()?.len();
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let rotated_path = PathBuf::from(format!("{}.{}", self.path.display(), chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")));
+        fs::rename(&self.path, &rotated_path)?;
+        self.file = BufWriter::new(open_for_appending(&self.path).map_err(io::Error::other)?);
+        self.size = 0;
+        // (this runs in the logging thread: logging from here could deadlock it)
+        if self.compressor.send(rotated_path.clone()).is_err() {
+            eprintln!("The log compressor for '{}' is gone: '{}' will be kept uncompressed", self.path.display(), rotated_path.display());
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let reopen_generation = REOPEN_GENERATION.load(Ordering::Relaxed);
+        if reopen_generation != self.reopen_generation {
+            self.reopen_generation = reopen_generation;
+            self.reopen()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.rotation_size > 0 && self.size >= self.rotation_size {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+}
+
+/// tells if `file` is still the one at `path`
+#[cfg(unix)]
+fn is_open_at(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(at_path)) => open.dev() == at_path.dev() && open.ino() == at_path.ino(),
+        _ => false,
+    }
+}
+#[cfg(not(unix))]
+fn is_open_at(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+fn open_for_appending(path: &Path) -> Result<File, Box<dyn std::error::Error + Send + Sync>> {
+    OpenOptions::new().create(true).append(true).open(path)
+        .map_err(|err| Box::from(format!("Could not open the log file '{}': {}", path.display(), err)))
+}
+
+/// spawns the thread compressing, one by one, the rotated files of `path` sent to the returned channel -- then removing the old rotations
+fn spawn_compressor(path: PathBuf, rotations_kept: usize, compression: LogCompression) -> Result<mpsc::Sender<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
+    let (sender, receiver) = mpsc::channel::<PathBuf>();
+    let log_file = path.display().to_string();
+    thread::Builder::new()
+        .name(String::from("log-compressor"))
+        .spawn(move || {
+            for rotated_path in receiver {
+                if let Err(err) = compress(&rotated_path, compression) {
+                    error!("Could not compress the rotated log file '{}' -- it will be kept as it is: {}", rotated_path.display(), err);
+                }
+                remove_old_rotations(&path, rotations_kept);
+            }
+        })
+        .map_err(|err| format!("Could not spawn the log compressor thread for '{}': {}", log_file, err))?;
+    Ok(sender)
+}
+
+/// compresses `rotated_path` into a file with the suffix of `compression`, removing it afterwards
+fn compress(rotated_path: &Path, compression: LogCompression) -> io::Result<()> {
+    let suffix = match compression {
+        LogCompression::None        => return Ok(()),
+        LogCompression::Gzip        => "gz",
+        LogCompression::Zstd { .. } => "zst",
+    };
+    let compressed_path = PathBuf::from(format!("{}.{}", rotated_path.display(), suffix));
+    let temp_path = PathBuf::from(format!("{}{}", compressed_path.display(), TEMP_SUFFIX));
+    let mut source = File::open(rotated_path)?;
+    let mut destination = BufWriter::new(File::create(&temp_path)?);
+    match compression {
+        LogCompression::None => (),
+        LogCompression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(&mut destination, flate2::Compression::default());
+            io::copy(&mut source, &mut encoder)?;
+            encoder.finish()?;
+        },
+        LogCompression::Zstd { level } => zstd::stream::copy_encode(&mut source, &mut destination, level)?,
+    }
+    destination.flush()?;
+    fs::rename(&temp_path, &compressed_path)?;
+    fs::remove_file(rotated_path)
+}
+
+/// removes all but the newest `rotations_kept` rotated files of `path` -- compressed or not
+fn remove_old_rotations(path: &Path, rotations_kept: usize) {
+    let (Some(directory), Some(file_name)) = (path.parent(), path.file_name().and_then(|file_name| file_name.to_str())) else { return };
+    let directory = if directory.as_os_str().is_empty() { Path::new(".") } else { directory };
+    let prefix = format!("{}.", file_name);
+    let mut rotations: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|rotation| rotation.file_name()
+                .and_then(|rotation_name| rotation_name.to_str())
+                .is_some_and(|rotation_name| rotation_name.starts_with(&prefix) && !rotation_name.ends_with(TEMP_SUFFIX)))
+            .collect(),
+        Err(err) => {
+            error!("Could not list the rotated log files of '{}': {}", path.display(), err);
+            return
+        },
+    };
+    // the timestamps in the names sort them chronologically
+    rotations.sort();
+    let excess = rotations.len().saturating_sub(rotations_kept);
+    for old_rotation in rotations.into_iter().take(excess) {
+        if let Err(err) = fs::remove_file(&old_rotation) {
+            error!("Could not remove the old rotated log file '{}': {}", old_rotation.display(), err);
+        }
+    }
+}
+
+
+/// Unit tests the [rotating_file](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn rotations_are_compressed_and_pruned() {
+        let directory = std::env::temp_dir().join(format!("kickass-rotating-file-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("Could not create the test directory");
+        let path = directory.join("test.log");
+        let mut rotating_file = RotatingFile::open(&path, 100, 2, LogCompression::Gzip).expect("Could not open the log file");
+        for rotation in 0..4 {
+            writeln!(rotating_file, "{}", "x".repeat(120)).unwrap();
+            rotating_file.flush().unwrap();
+            // distinct timestamps for the rotated files
+            thread::sleep(Duration::from_millis(2 + rotation));
+        }
+        let rotated_names = || -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(&directory).unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .filter(|name| name != "test.log")
+                .collect();
+            names.sort();
+            names
+        };
+        // compression happens in the background
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (rotated_names().len() != 2 || !rotated_names().iter().all(|name| name.ends_with(".gz"))) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let names = rotated_names();
+        fs::remove_dir_all(&directory).ok();
+        assert_eq!(names.len(), 2, "Only the 2 newest rotations should have been kept: {:?}", names);
+        assert!(names.iter().all(|name| name.starts_with("test.log.") && name.ends_with(".gz")), "Rotations should have been gzipped: {:?}", names);
+        assert_eq!(rotating_file.size, 0, "The log file should have been rotated on the last flush");
+    }
+
+    /// assures reopen requests rotate the file -- or just reopen it, if it was moved away, as `logrotate` does
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn reopen_requests() {
+        let directory = std::env::temp_dir().join(format!("kickass-rotating-file-reopen-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("Could not create the test directory");
+        let path = directory.join("test.log");
+        let file_names = || -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(&directory).unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        let mut rotating_file = RotatingFile::open(&path, 0, 10, LogCompression::None).expect("Could not open the log file");
+        writeln!(rotating_file, "before the request").unwrap();
+        rotating_file.flush().unwrap();
+
+        request_reopen();
+        writeln!(rotating_file, "after the request").unwrap();
+        rotating_file.flush().unwrap();
+        let names = file_names();
+        assert_eq!(names.len(), 2, "The file should have been rotated: {:?}", names);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after the request\n", "Records after the request should go to the new file");
+
+        // as `logrotate` would do
+        fs::rename(&path, directory.join("moved.log")).unwrap();
+        request_reopen();
+        writeln!(rotating_file, "after being moved").unwrap();
+        rotating_file.flush().unwrap();
+        let names = file_names();
+        fs::remove_dir_all(&directory).ok();
+        assert_eq!(names.len(), 3, "A new file should have been opened, with no rotations: {:?}", names);
+        assert!(names.contains(&String::from("test.log")), "The file should have been reopened: {:?}", names);
+    }
+}
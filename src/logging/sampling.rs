@@ -0,0 +1,276 @@
+//! Log sampling & burst suppression for `slog` -- so floods (like a misbehaving socket client may cause)
+//! won't fill up the disk nor bury the relevant messages.
+
+use super::target_filter::matches_segments;
+use crate::config::{ExtendedOption, LogSamplingConfig};
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use slog::{Drain, Level, OwnedKVList, Record, RecordLocation, RecordStatic};
+
+
+/// The window for measuring the rate of trace & debug messages, per target
+const SAMPLING_WINDOW: Duration = Duration::from_secs(1);
+
+/// Where the summary records ("repeated N times", "N messages sampled out") are said to come from
+static SUMMARY_LOCATION: RecordLocation = RecordLocation {
+    file:     file!(),
+    line:     line!(),
+    column:   column!(),
+    function: "",
+    module:   module_path!(),
+};
+
+/// `slog` drain wrapper implementing [crate::config::Config::log_sampling]:
+///   * consecutive identical messages are collapsed into a single "Last message repeated N times" one;
+///   * trace & debug messages above the configured rate (per target) are sampled -- with the number of sampled out messages reported.
+/// Summaries are only logged when a subsequent message comes in (or when this drain is dropped).
+pub struct SamplingFilter<D: Drain> {
+    drain:              D,
+    repeat_window:      Duration,
+    max_debug_rate:     u32,
+    sample_one_in:      u32,
+    /// `(key, max_debug_rate)` pairs, sorted from the most specific (longest) key to the least specific one
+    target_rates:       Vec<(String, u32)>,
+    /// `false` if all of the above are disabled -- in which case records are passed through without locking
+    enabled:            bool,
+    state:              Mutex<SamplingState>,
+}
+
+#[derive(Default)]
+struct SamplingState {
+    last_message:   Option<LastMessage>,
+    /// trace & debug counters, by target
+    debug_counters: HashMap<String, DebugCounter>,
+}
+
+/// Identifies the last message logged, to detect repetitions
+struct LastMessage {
+    fingerprint: u64,
+    level:       Level,
+    tag:         String,
+    first_seen:  Instant,
+    repeats:     u32,
+}
+
+struct DebugCounter {
+    window_start: Instant,
+    count:        u32,
+    dropped:      u32,
+}
+
+impl<D: Drain> SamplingFilter<D> {
+
+    pub fn new(drain: D, log_sampling: &ExtendedOption<LogSamplingConfig>) -> Self {
+        let (repeat_window, max_debug_rate, sample_one_in, mut target_rates) = match log_sampling {
+            ExtendedOption::Enabled(config) => (
+                Duration::from_millis(config.repeat_window_millis),
+                config.max_debug_rate,
+                config.sample_one_in,
+                config.target_rates.iter()
+                    .map(|(key, rate)| (key.to_string(), *rate))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => (Duration::ZERO, 0, 0, vec![]),
+        };
+        target_rates.sort_by(|(key_a, _), (key_b, _)| key_b.len().cmp(&key_a.len()));
+        let enabled = !repeat_window.is_zero() || max_debug_rate > 0 || target_rates.iter().any(|(_key, rate)| *rate > 0);
+        Self {
+            drain,
+            repeat_window,
+            max_debug_rate,
+            sample_one_in,
+            target_rates,
+            enabled,
+            state: Mutex::new(SamplingState::default()),
+        }
+    }
+
+    /// the maximum number of trace & debug messages per second for the given `target` & `module` -- 0 meaning "unlimited"
+    fn max_debug_rate_for(&self, target: &str, module: &str) -> u32 {
+        self.target_rates.iter()
+            .find(|(key, _rate)| matches_segments(target, key) || matches_segments(module, key))
+            .map(|(_key, rate)| *rate)
+            .unwrap_or(self.max_debug_rate)
+    }
+
+    /// tells if the trace or debug `record` should be logged, updating the rate counters for its target
+    fn sample(&self, state: &mut SamplingState, record: &Record<'_>, values: &OwnedKVList) -> Result<bool, D::Err> {
+        let max_rate = self.max_debug_rate_for(record.tag(), record.module());
+        if max_rate == 0 {
+            return Ok(true)
+        }
+        if !state.debug_counters.contains_key(record.tag()) {
+            state.debug_counters.insert(record.tag().to_string(), DebugCounter { window_start: Instant::now(), count: 0, dropped: 0 });
+        }
+        let counter = state.debug_counters.get_mut(record.tag()).expect("BUG! counter was just inserted");
+        let elapsed = counter.window_start.elapsed();
+        if elapsed >= SAMPLING_WINDOW {
+            if counter.dropped > 0 {
+                self.log_summary(Level::Info, record.tag(), format_args!("{} trace/debug messages were sampled out in the last {:?} -- above the limit of {}/s",
+                                                                         counter.dropped, elapsed, max_rate), values)?;
+            }
+            *counter = DebugCounter { window_start: Instant::now(), count: 0, dropped: 0 };
+        }
+        counter.count = counter.count.saturating_add(1);
+        if counter.count <= max_rate || (self.sample_one_in > 0 && (counter.count - max_rate) % self.sample_one_in == 0) {
+            Ok(true)
+        } else {
+            counter.dropped += 1;
+            Ok(false)
+        }
+    }
+
+    /// tells if `record` is a repetition of the last message -- which should, then, not be logged
+    fn is_repetition(&self, state: &mut SamplingState, record: &Record<'_>, values: &OwnedKVList) -> Result<bool, D::Err> {
+        let fingerprint = fingerprint(record);
+        if let Some(last_message) = &mut state.last_message {
+            if last_message.fingerprint == fingerprint && last_message.first_seen.elapsed() < self.repeat_window {
+                last_message.repeats += 1;
+                return Ok(true)
+            }
+        }
+        if let Some(last_message) = state.last_message.take() {
+            self.log_repeats(&last_message, values)?;
+        }
+        state.last_message = Some(LastMessage {
+            fingerprint,
+            level:      record.level(),
+            tag:        record.tag().to_string(),
+            first_seen: Instant::now(),
+            repeats:    0,
+        });
+        Ok(false)
+    }
+
+    fn log_repeats(&self, last_message: &LastMessage, values: &OwnedKVList) -> Result<(), D::Err> {
+        if last_message.repeats > 0 {
+            self.log_summary(last_message.level, &last_message.tag, format_args!("Last message repeated {} times", last_message.repeats), values)?;
+        }
+        Ok(())
+    }
+
+    fn log_summary(&self, level: Level, tag: &str, message: fmt::Arguments, values: &OwnedKVList) -> Result<D::Ok, D::Err> {
+        let record_static = RecordStatic {
+            location: &SUMMARY_LOCATION,
+            tag,
+            level,
+        };
+        self.drain.log(&Record::new(&record_static, &message, slog::b!()), values)
+    }
+}
+
+impl<D: Drain> Drain for SamplingFilter<D> {
+    type Ok  = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if !self.enabled {
+            return self.drain.log(record, values).map(Some)
+        }
+        // the lock is held while logging, so summaries are never out of order
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !record.level().is_at_least(Level::Info) && !self.sample(&mut state, record, values)? {
+            return Ok(None)
+        }
+        if !self.repeat_window.is_zero() && self.is_repetition(&mut state, record, values)? {
+            return Ok(None)
+        }
+        self.drain.log(record, values).map(Some)
+    }
+}
+
+impl<D: Drain> Drop for SamplingFilter<D> {
+    /// reports any pending repetitions
+    fn drop(&mut self) {
+        let last_message = self.state.get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .last_message.take();
+        if let Some(last_message) = last_message {
+            let _ = self.log_repeats(&last_message, &OwnedKVList::from(slog::o!()));
+        }
+    }
+}
+
+/// identifies a record by its level, tag, location & rendered message -- without allocations
+fn fingerprint(record: &Record<'_>) -> u64 {
+    /// feeds whatever is formatted into a [Hasher]
+    struct HashWriter<H: Hasher>(H);
+    impl<H: Hasher> fmt::Write for HashWriter<H> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+    let mut hasher = HashWriter(DefaultHasher::new());
+    record.level().as_usize().hash(&mut hasher.0);
+    record.tag().hash(&mut hasher.0);
+    record.file().hash(&mut hasher.0);
+    record.line().hash(&mut hasher.0);
+    let _ = fmt::write(&mut hasher, *record.msg());
+    hasher.0.finish()
+}
+
+
+/// Unit tests the [sampling](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::logging::test_utils::CollectorDrain;
+    use std::{
+        collections::BTreeMap,
+        sync::Arc,
+    };
+
+
+    fn logger(log_sampling: LogSamplingConfig) -> (slog::Logger, Arc<Mutex<Vec<String>>>) {
+        let messages = Arc::new(Mutex::new(vec![]));
+        let drain = SamplingFilter::new(CollectorDrain(Arc::clone(&messages)), &ExtendedOption::Enabled(log_sampling));
+        (slog::Logger::root(drain.fuse(), slog::o!()), messages)
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn repeated_messages_are_collapsed() {
+        let (logger, messages) = logger(LogSamplingConfig {
+            repeat_window_millis: 60_000,
+            max_debug_rate:       0,
+            sample_one_in:        0,
+            target_rates:         BTreeMap::new(),
+        });
+        for _ in 0..10 {
+            slog::warn!(logger, "Client {} is flooding us", 42);
+        }
+        slog::warn!(logger, "Client {} is flooding us", 43);
+        drop(logger);
+        assert_eq!(*messages.lock().unwrap(), vec![
+            "Client 42 is flooding us".to_string(),
+            "Last message repeated 9 times".to_string(),
+            "Client 43 is flooding us".to_string(),
+        ]);
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn debug_messages_are_sampled() {
+        let (logger, messages) = logger(LogSamplingConfig {
+            repeat_window_millis: 0,
+            max_debug_rate:       1000,
+            sample_one_in:        10,
+            target_rates:         BTreeMap::from([(String::from("sampling"), 10)]),
+        });
+        for i in 0..110 {
+            slog::debug!(logger, "Debug #{}", i);
+        }
+        for i in 0..10 {
+            slog::info!(logger, "Info #{}", i);
+        }
+        let messages = messages.lock().unwrap();
+        let debug_messages = messages.iter().filter(|message| message.starts_with("Debug")).count();
+        let info_messages = messages.iter().filter(|message| message.starts_with("Info")).count();
+        assert_eq!(debug_messages, 10 + 10, "The first 10 debug messages (per-target rate) should pass, then only 1 of every 10");
+        assert_eq!(info_messages, 10, "Messages above `debug` should never be sampled");
+    }
+}
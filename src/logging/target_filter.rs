@@ -0,0 +1,77 @@
+//! Per-target level filtering for `slog` -- allowing noisy components to be silenced
+//! (or chatty ones to be investigated) without touching the app-wide log level.\
+//! The filters may be replaced at runtime -- see [set_filters()] -- as done by the config hot reload.
+
+use std::collections::BTreeMap;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use slog::{Drain, Level, OwnedKVList, Record};
+use sloggers::types::Severity;
+
+
+/// The key matching all targets -- overriding the `default_level` of the [TargetLevelFilter]s
+const ANY_TARGET: &str = "*";
+
+/// `(key, level)` pairs, sorted from the most specific (longest) key to the least specific one ([ANY_TARGET]) -- shared by all [TargetLevelFilter]s
+static FILTERS: Lazy<RwLock<Vec<(String, Level)>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+
+/// `slog` drain wrapper applying the levels of [crate::config::Config::log_filters] to records whose target or module
+/// matches one of its keys -- falling back to the level of the [ANY_TARGET] key, if present, or to `default_level` for all other records.\
+/// A key matches if it is equal to whole `::` segments of the target (or module): `socket_server` matches
+/// `kickass_app_template::frontend::socket_server::socket_server`, `rocket` matches `rocket::launch`, but `sock` matches none.
+pub struct TargetLevelFilter<D: Drain> {
+    drain:         D,
+    default_level: Level,
+}
+
+impl<D: Drain> TargetLevelFilter<D> {
+
+    pub fn new(drain: D, default_level: Level, log_filters: &BTreeMap<String, Severity>) -> Self {
+        set_filters(log_filters);
+        Self {
+            drain,
+            default_level,
+        }
+    }
+
+    /// the level a record with the given `target` & `module` must meet in order to be logged
+    fn level_for(&self, target: &str, module: &str) -> Level {
+        FILTERS.read().iter()
+            .find(|(key, _level)| key == ANY_TARGET || matches_segments(target, key) || matches_segments(module, key))
+            .map(|(_key, level)| *level)
+            .unwrap_or(self.default_level)
+    }
+}
+
+/// replaces the filters applied by the [TargetLevelFilter]s with `log_filters` -- effective for the next record
+pub(super) fn set_filters(log_filters: &BTreeMap<String, Severity>) {
+    let mut filters: Vec<(String, Level)> = log_filters.iter()
+        .map(|(key, severity)| (key.to_string(), severity.as_level()))
+        .collect();
+    let specificity = |key: &str| if key == ANY_TARGET { 0 } else { key.len() };
+    filters.sort_by(|(key_a, _), (key_b, _)| specificity(key_b).cmp(&specificity(key_a)));
+    *FILTERS.write() = filters;
+}
+
+impl<D: Drain> Drain for TargetLevelFilter<D> {
+    type Ok  = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if record.level().is_at_least(self.level_for(record.tag(), record.module())) {
+            self.drain.log(record, values).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// tells if `key` is present in `path` as whole `::`-separated segments -- this is done for every log record, so no allocations are allowed
+pub(super) fn matches_segments(path: &str, key: &str) -> bool {
+    path.match_indices(key)
+        .any(|(start, _)| {
+            let end = start + key.len();
+            (start == 0 || path[..start].ends_with("::")) && (end == path.len() || path[end..].starts_with("::"))
+        })
+}
@@ -0,0 +1,61 @@
+//! Distributed tracing: installs the `tracing` subscriber exporting the spans to an OpenTelemetry collector through OTLP -- see
+//! [crate::config::Config::telemetry]. The spans are opened by:
+//!   * the socket server's processors, for each event -- see [crate::frontend::socket_server]
+//!   * the web server, for each request -- see `frontend/web/request_tracing.rs`
+//!   * the Telegram bots, for each update they handle
+//!   * the scheduler, for each job run -- see [crate::scheduler::Scheduler]
+//!
+//! Your logic components may open their own, with `tracing::info_span!()` or `#[tracing::instrument]`: they become children of the
+//! ones above. If telemetry is disabled, no subscriber is installed & spans cost next to nothing.\
+//! This is independent of the logs: they keep going through `slog` -- see [super].
+
+use crate::config::TelemetryConfig;
+use std::time::Duration;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{
+    Resource,
+    runtime::Tokio,
+    trace::{self, Sampler},
+};
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use log::{info, debug};
+
+
+/// Flushes the pending spans & stops exporting them when dropped -- keep it alive while the Tokio runtime is
+pub struct TelemetryGuard;
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        debug!("Telemetry: flushing the pending spans");
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+/// Installs the global `tracing` subscriber, exporting the spans in batches as `config` says.
+/// Must be called from within the Tokio runtime -- whose tasks do the exporting
+pub fn setup_telemetry(config: &TelemetryConfig) -> Result<TelemetryGuard, Box<dyn std::error::Error + Send + Sync>> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(&config.otlp_endpoint)
+        .with_timeout(Duration::from_millis(config.export_timeout_millis));
+    let resource = Resource::new([
+        KeyValue::new("service.name",    config.service_name.clone()),
+        KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace::config()
+            // traces continued from other services follow their sampling decisions
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sampling_ratio))))
+            .with_resource(resource))
+        .install_batch(Tokio)
+        .map_err(|err| format!("Telemetry: could not build the OTLP exporter to '{}': {}", config.otlp_endpoint, err))?;
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .map_err(|err| format!("Telemetry: could not install the tracing subscriber: {}", err))?;
+    info!("Telemetry: exporting {}% of the traces to '{}' as '{}'", config.sampling_ratio * 100.0, config.otlp_endpoint, config.service_name);
+    Ok(TelemetryGuard)
+}
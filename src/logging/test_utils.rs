@@ -0,0 +1,18 @@
+//! Fixtures shared by the unit tests of the [super] drains
+
+use std::sync::{Arc, Mutex};
+use slog::{Drain, OwnedKVList, Record};
+
+
+/// drain collecting the (rendered) messages it receives
+pub struct CollectorDrain(pub Arc<Mutex<Vec<String>>>);
+
+impl Drain for CollectorDrain {
+    type Ok  = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &Record<'_>, _values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        self.0.lock().unwrap().push(record.msg().to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+//! Data Transfer Objects: the wire/API representations of our domain types -- so changes to the internal models stop
+//! rippling directly into the protocol definitions of each frontend (socket protocol enums, Rocket JSON structs & Telegram texts).
+//!
+//! The pattern:
+//!   1) domain types (like [UnitsConversion]) stay free of serialization concerns & may be refactored at will;
+//!   2) each exposed domain type implements [Exposed], telling which versioned DTO represents it on the wire -- and how to
+//!      render it as Telegram text;
+//!   3) DTOs are versioned & frozen once published: changing a representation means adding a `...V2` struct (pointing
+//!      [Exposed::Dto] to it) while keeping `...V1` -- and its `From<&DomainType>` -- for as long as old clients are served.
+//!
+//! Frontends should only ever put DTOs in their protocols -- the socket's `ServerMessages` variants included.
+
+use super::units::UnitsConversion;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+
+/// Marks the wire/API representations of domain types -- their names end with their versions, which are bumped by
+/// creating a new struct, never by changing a published one
+pub trait Dto: Serialize + DeserializeOwned {}
+
+/// Implemented by the domain types exposed through the frontends
+pub trait Exposed {
+    /// the current wire/API representation
+    type Dto: Dto + for<'a> From<&'a Self>;
+
+    /// the representation for the socket protocol & the web API
+    fn to_dto(&self) -> Self::Dto {
+        Self::Dto::from(self)
+    }
+
+    /// the (plain text) representation for the Telegram bots
+    fn to_telegram_text(&self) -> String;
+}
+
+
+// UnitsConversion
+//////////////////
+
+/// The JSON answered by `/api/get-service` since its first version: quantities as strings, with their units
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnitsConversionV1 {
+    pub from_temperature: String,
+    pub from_length:      String,
+    pub to_temperature:   String,
+    pub to_length:        String,
+}
+
+impl Dto for UnitsConversionV1 {}
+
+impl From<&UnitsConversion> for UnitsConversionV1 {
+    fn from(conversion: &UnitsConversion) -> Self {
+        let ((from_temperature_unit, from_length_unit), (to_temperature_unit, to_length_unit)) = conversion.units();
+        Self {
+            from_temperature: format!("{:.2}{}", conversion.from_temperature, from_temperature_unit),
+            from_length:      format!("{:.2}{}", conversion.from_length,      from_length_unit),
+            to_temperature:   format!("{:.2}{}", conversion.to_temperature,   to_temperature_unit),
+            to_length:        format!("{:.2}{}", conversion.to_length,        to_length_unit),
+        }
+    }
+}
+
+impl Exposed for UnitsConversion {
+    type Dto = UnitsConversionV1;
+
+    fn to_telegram_text(&self) -> String {
+        let dto = self.to_dto();
+        format!("{} is {}\n{} is {}", dto.from_temperature, dto.to_temperature, dto.from_length, dto.to_length)
+    }
+}
+
+
+/// Unit tests the [dto](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use super::super::units::ConversionDirection;
+
+
+    /// published DTOs must never change -- or old clients would break
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn units_conversion_v1_is_frozen() {
+        let conversion = UnitsConversion::compute(ConversionDirection::MetricToImperial, 100.0, 1.0);
+        let json = ron::ser::to_string(&conversion.to_dto())
+            .expect("Serialization should have worked");
+        assert_eq!(json, r#"(from_temperature:"100.00°C",from_length:"1.00m",to_temperature:"212.00°F",to_length:"3.28ft")"#,
+                   "The published representation of `UnitsConversionV1` changed");
+        assert_eq!(conversion.to_telegram_text(), "100.00°C is 212.00°F\n1.00m is 3.28ft");
+    }
+}
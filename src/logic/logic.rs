@@ -1,22 +1,77 @@
 //! see [super]
 
-use std::time::Duration;
+use super::{pipeline, UnitsConversion, ConversionDirection};
 use crate::{
-    runtime::Runtime,
+    runtime::{Runtime, Locks, JobQueue, Correlated, correlated},
     config::{Config, ExtendedOption},
+    frontend::{socket_server, web::InboundHook},
 };
-use tokio::sync::RwLock;
-use log::{info};
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{
+    RwLock,
+    broadcast::{self, error::RecvError},
+};
+use log::{info, warn, error};
+
+
+/// The kind of the [JobQueue] jobs carrying the [InboundHook]s -- see [process_inbound_hooks()]
+pub const INBOUND_HOOK_JOB: &str = "inbound-hook";
+/// The resource locked by [scheduled_demo()] while it runs
+const SCHEDULED_DEMO_LOCK: &str = "scheduled-demo";
+/// For how long [scheduled_demo()] may hold its lock -- should it crash (or hang) before releasing it
+const SCHEDULED_DEMO_LOCK_TTL: Duration = Duration::from_secs(60);
 
 
 /// Runs the service this application provides
-pub async fn long_runner(_runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
-    info!("HERE YOU WOULD START YOUR SERVICE. For now, we'll sleep for 3 min then quit");
-    tokio::time::sleep(Duration::from_secs(180)).await;
+pub async fn long_runner(runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    info!("HERE YOU WOULD START YOUR SERVICE. For now, we'll run the demo pipeline then quit");
+    // the pipeline is a singleton task: when several instances are running, only the leader may run it
+    // (for simplicity, the demo keeps on running it even if the leadership is lost in the meantime)
+    let leader_election = runtime.read().await.leader_election.clone();
+    if let Some(leader_election) = leader_election {
+        if !leader_election.is_leader() {
+            info!("Instance '{}' is not the leader: waiting for the leadership before running the demo pipeline...", leader_election.instance_id());
+            leader_election.wait_for_leadership().await;
+        }
+    }
+    let (events, storage, executable_path) = {
+        let runtime = runtime.read().await;
+        (Arc::clone(&runtime.events), runtime.storage.clone(), runtime.executable_path.clone())
+    };
+    let checkpoints = match storage {
+        Some(storage) => pipeline::Checkpoints::Storage(storage),
+        None => pipeline::Checkpoints::File(format!("{}.pipeline-checkpoint.ron", executable_path)),
+    };
+    pipeline::run(&events, checkpoints).await?;
     info!("DEMO DAEMON IS OVER. Application will now shutdown gracefully");
     Ok(())
 }
 
+/// Warms up the logic -- loading datasets, priming caches, connecting to external services, ... -- so the first requests don't pay for it.
+/// Registered as a warmup hook -- see [crate::runtime::Warmup]
+pub async fn warmup() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    info!("HERE YOU WOULD WARM UP YOUR LOGIC (load datasets, prime caches, ...). For now, we just exercise the units conversion");
+    UnitsConversion::compute(ConversionDirection::MetricToImperial, 36.6, 1.8);
+    Ok(())
+}
+
+/// A scheduled job -- registered as "demo" in the [crate::scheduler::Scheduler], to be ran as set in [crate::config::Config::scheduler].\
+/// If `locks` are enabled, its runs are serialized through them -- so they don't overlap, even across instances
+pub async fn scheduled_demo(locks: Option<Arc<Locks>>) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let lock = match &locks {
+        Some(locks) => Some(locks.acquire(SCHEDULED_DEMO_LOCK, SCHEDULED_DEMO_LOCK_TTL).await?),
+        None => None,
+    };
+    info!("HERE YOU WOULD DO YOUR PERIODIC WORK (purging expired sessions, sending digests, ...). For now, we just log this message");
+    if let (Some(locks), Some(lock)) = (locks, lock) {
+        locks.release(lock).await?;
+    }
+    Ok(())
+}
+
 /// Inspects & shows the effective configs & runtime used by the application
 pub async fn check_config(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
     println!("Effective Config:  {:#?}", config);
@@ -42,5 +97,52 @@ pub async fn check_config(runtime: &RwLock<Runtime>, config: &Config) -> Result<
         server_socket_started,
         telegram_started,
     });
+    if let Some(scheduler) = &runtime.scheduler {
+        println!("Scheduled Jobs:    {:#?}", scheduler.report());
+    }
+    if let Some(job_queue) = &runtime.job_queue {
+        // as found in the spool directory -- the counters are only kept by the running instance: see its metrics
+        println!("Job Queue:         {:#?}", job_queue.report());
+    }
+    Ok(())
+}
+/// Generates the client SDK crate for the socket protocol, so external teams may integrate with our socket server
+pub async fn generate_client_sdk(runtime: &RwLock<Runtime>, _config: &Config) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let output_dir = format!("{}.client-sdk", runtime.read().await.executable_path);
+    socket_server::generate_client_sdk(&output_dir)?;
+    println!("Client SDK crate generated at '{}'. Try it with `cd '{}' && cargo doc --open`", output_dir, output_dir);
+    Ok(())
+}
+
+/// Processes the (already validated) payloads received by the inbound webhooks, as they are published on the event bus
+/// -- see [crate::frontend::web::inbound_hooks_topic()]. Runs until the bus is dropped.\
+/// If the `job_queue` is given, the payloads are enqueued as [INBOUND_HOOK_JOB]s -- so they are processed (& retried) even across restarts
+pub async fn process_inbound_hooks(mut inbound_hooks: broadcast::Receiver<Correlated<InboundHook>>, job_queue: Option<Arc<JobQueue>>) {
+    loop {
+        match inbound_hooks.recv().await {
+            Ok(Correlated { correlation_id, event: inbound_hook }) => {
+                let result = match &job_queue {
+                    Some(job_queue) => job_queue.enqueue(INBOUND_HOOK_JOB, &inbound_hook).map(|_id| ()),
+                    None => correlated(correlation_id, on_inbound_hook(inbound_hook.clone())).await,
+                };
+                if let Err(err) = result {
+                    error!("Inbound hooks: the logic failed to process a payload for '{}': {}", inbound_hook.hook, err);
+                }
+            },
+            Err(RecvError::Lagged(missed)) => warn!("Inbound hooks: the logic fell behind -- {} payloads were missed", missed),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Processes the payload received by an inbound webhook -- see [process_inbound_hooks()]. Also the worker of the [INBOUND_HOOK_JOB]s
+pub async fn on_inbound_hook(inbound_hook: InboundHook) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    info!("HERE YOU WOULD HANDLE THE INBOUND WEBHOOKS. For now, we just log that '{}' received {} bytes", inbound_hook.hook, inbound_hook.payload.len());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Tells who owns the socket server's login `token` -- `Ok(None)` if it should be refused. See [crate::config::SocketLogin::Logic]
+pub async fn verify_socket_token(_token: &str) -> Result<Option<String>, Box<dyn std::error::Error + Sync + Send>> {
+    info!("HERE YOU WOULD VERIFY THE SOCKET CLIENTS' TOKENS. For now, we just refuse them all");
+    Ok(None)
+}
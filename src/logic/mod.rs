@@ -1,4 +1,11 @@
 //! Place here the logic for your application -- to handle jobs and services
 
 mod logic;
-pub use logic::*;
\ No newline at end of file
+pub use logic::*;
+
+mod pipeline;
+
+mod units;
+pub use units::*;
+
+pub mod dto;
\ No newline at end of file
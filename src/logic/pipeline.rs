@@ -0,0 +1,247 @@
+//! Demonstrates how a long-running job is intended to be built with this template: a multi-stage `ingest → transform → publish` pipeline.
+//!
+//! Each stage runs on its own Tokio task, linked to the next one by a bounded channel -- so a slow stage back-pressures the previous ones.
+//! CPU-bound work is offloaded to Tokio's blocking pool (keeping the async workers free), progress is reported periodically, the results
+//! are published on the [EventBus] (see [PIPELINE_TOPIC]) and the position of the last published batch is checkpointed -- to the [Storage],
+//! if enabled, or to a RON file otherwise -- allowing the pipeline to resume where it left off after a restart.
+
+use crate::runtime::{EventBus, Storage};
+use std::{
+    fs,
+    io::ErrorKind,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use serde::{Serialize, Deserialize};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use log::{debug, info, warn};
+
+
+/// How many items the demo pipeline processes before it is considered done
+const TOTAL_ITEMS: u64 = 1 << 26;
+/// Items flow through the stages in batches of this size
+const BATCH_SIZE: u64 = 4096;
+/// Simulated latency of the external source we ingest from -- per batch
+const INGEST_LATENCY: Duration = Duration::from_millis(1);
+/// Capacity (in batches) of the channels linking the stages
+const CHANNEL_CAPACITY: usize = 16;
+/// How many rounds of "heavy computation" each item goes through in the transform stage
+const TRANSFORM_ROUNDS: u32 = 64;
+/// Minimum interval between two checkpoints
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+/// Minimum interval between two progress reports
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// The event bus topic carrying the [PublishedBatch]es
+const PIPELINE_TOPIC: &str = "pipeline.results";
+/// The [Storage] key of the [Checkpoint]
+const CHECKPOINT_KEY: &str = "pipeline.checkpoint";
+
+
+/// What is persisted between runs, so the pipeline may resume from where it was
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Checkpoint {
+    /// sequence of the first item not yet published
+    next_sequence: u64,
+    /// running checksum of all published results -- the state our demo publisher accumulates
+    checksum: u64,
+}
+
+/// The unit of work flowing through the pipeline stages
+struct Batch {
+    first_sequence: u64,
+    items:          Vec<u64>,
+}
+
+/// The result of each batch -- published on the [PIPELINE_TOPIC]
+#[derive(Debug, Clone, Serialize)]
+struct PublishedBatch {
+    first_sequence: u64,
+    items:          u64,
+    /// the running checksum, including this batch
+    checksum:       u64,
+}
+
+/// Where the [Checkpoint]s are kept
+pub enum Checkpoints {
+    /// under [CHECKPOINT_KEY] -- saved along with the rest of the storage's snapshot
+    Storage(Arc<Storage>),
+    /// in a RON file of their own -- for when the [Storage] is disabled
+    File(String),
+}
+
+impl Checkpoints {
+
+    /// the last saved [Checkpoint] -- or a fresh one, if there is none
+    fn load(&self) -> Result<Checkpoint, Box<dyn std::error::Error + Sync + Send>> {
+        match self {
+            Checkpoints::Storage(storage) => storage.get(CHECKPOINT_KEY)
+                .map(Option::unwrap_or_default)
+                .map_err(|err| Box::from(format!("Pipeline: could not load the checkpoint from the storage: {}", err))),
+            Checkpoints::File(checkpoint_file) => load_checkpoint(checkpoint_file),
+        }
+    }
+
+    fn save(&self, checkpoint: &Checkpoint) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        match self {
+            Checkpoints::Storage(storage) => storage.put(CHECKPOINT_KEY, checkpoint)
+                .map_err(|err| Box::from(format!("Pipeline: could not save the checkpoint to the storage: {}", err))),
+            Checkpoints::File(checkpoint_file) => save_checkpoint(checkpoint_file, checkpoint),
+        }
+    }
+
+    /// forgets the saved [Checkpoint], so the next run starts over
+    fn clear(&self) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        match self {
+            Checkpoints::Storage(storage) => {
+                storage.remove(CHECKPOINT_KEY);
+                Ok(())
+            },
+            Checkpoints::File(checkpoint_file) => match fs::remove_file(checkpoint_file) {
+                Err(err) if err.kind() != ErrorKind::NotFound => Err(Box::from(format!("Pipeline: could not remove the checkpoint file '{}': {}", checkpoint_file, err))),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Checkpoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Checkpoints::Storage(_storage)     => write!(f, "the storage's '{}'", CHECKPOINT_KEY),
+            Checkpoints::File(checkpoint_file) => write!(f, "'{}'", checkpoint_file),
+        }
+    }
+}
+
+/// Runs the pipeline until all [TOTAL_ITEMS] are published on `events` -- resuming from the last of the `checkpoints`, if any.\
+/// Once done, the checkpoint is removed, so the next run starts over.
+pub async fn run(events: &Arc<EventBus>, checkpoints: Checkpoints) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let checkpoint = checkpoints.load()?;
+    if checkpoint.next_sequence > 0 {
+        info!("Pipeline: resuming from checkpoint {} -- {} items were already published", checkpoints, checkpoint.next_sequence);
+    } else {
+        info!("Pipeline: starting from scratch -- checkpoints will be saved to {}", checkpoints);
+    }
+
+    let (ingested_sender, ingested_receiver) = mpsc::channel::<Batch>(CHANNEL_CAPACITY);
+    let (transformed_sender, transformed_receiver) = mpsc::channel::<Batch>(CHANNEL_CAPACITY);
+    let ingest_task = tokio::spawn(ingest(checkpoint.next_sequence, ingested_sender));
+    let transform_task = tokio::spawn(transform(ingested_receiver, transformed_sender));
+    let checkpoint = publish(checkpoint, transformed_receiver, events, &checkpoints).await?;
+    ingest_task.await?;
+    transform_task.await??;
+
+    if checkpoint.next_sequence != TOTAL_ITEMS {
+        checkpoints.save(&checkpoint)?;
+        return Err(Box::from(format!("Pipeline: stages ended prematurely: only {} of {} items were published", checkpoint.next_sequence, TOTAL_ITEMS)));
+    }
+    info!("Pipeline: all {} items were published. Final checksum: {:#018x}", TOTAL_ITEMS, checkpoint.checksum);
+    checkpoints.clear()
+}
+
+/// 1st stage: reads items from the (simulated) external source, starting at `from_sequence`
+async fn ingest(from_sequence: u64, output: mpsc::Sender<Batch>) {
+    let mut sequence = from_sequence;
+    while sequence < TOTAL_ITEMS {
+        tokio::time::sleep(INGEST_LATENCY).await;
+        let next_sequence = (sequence + BATCH_SIZE).min(TOTAL_ITEMS);
+        let batch = Batch {
+            first_sequence: sequence,
+            items:          (sequence..next_sequence).collect(),
+        };
+        if output.send(batch).await.is_err() {
+            warn!("Pipeline: `ingest` stage stopped at sequence #{}: `transform` is no longer listening", sequence);
+            return
+        }
+        sequence = next_sequence;
+    }
+    debug!("Pipeline: `ingest` stage is done");
+}
+
+/// 2nd stage: applies the CPU-bound transformation, using as many CPUs as there are available
+/// -- batches are processed in parallel, but they leave this stage in the same order they came in
+async fn transform(input: mpsc::Receiver<Batch>, output: mpsc::Sender<Batch>) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let parallelism = std::thread::available_parallelism()
+        .map(|cpus| cpus.get())
+        .unwrap_or(1);
+    let mut transformed_batches = ReceiverStream::new(input)
+        .map(|batch| tokio::task::spawn_blocking(move || Batch {
+            first_sequence: batch.first_sequence,
+            items:          batch.items.into_iter().map(scramble).collect(),
+        }))
+        .buffered(parallelism);
+    while let Some(transformed_batch) = transformed_batches.next().await {
+        let transformed_batch = transformed_batch
+            .map_err(|err| format!("Pipeline: a `transform` job panicked: {}", err))?;
+        if output.send(transformed_batch).await.is_err() {
+            warn!("Pipeline: `transform` stage stopped: `publish` is no longer listening");
+            break
+        }
+    }
+    debug!("Pipeline: `transform` stage is done");
+    Ok(())
+}
+
+/// The "heavy computation" of the demo -- rounds of the `splitmix64` finalizer
+fn scramble(mut item: u64) -> u64 {
+    for _ in 0..TRANSFORM_ROUNDS {
+        item ^= item >> 30;
+        item = item.wrapping_mul(0xbf58476d1ce4e5b9);
+        item ^= item >> 27;
+        item = item.wrapping_mul(0x94d049bb133111eb);
+        item ^= item >> 31;
+    }
+    item
+}
+
+/// 3rd & last stage: publishes the results on the [PIPELINE_TOPIC] of `events`, reporting progress and saving `checkpoints` along the way.\
+/// Returns the last checkpoint -- which is only persisted here if it was time to do so.
+async fn publish(mut checkpoint: Checkpoint, mut input: mpsc::Receiver<Batch>, events: &Arc<EventBus>, checkpoints: &Checkpoints) -> Result<Checkpoint, Box<dyn std::error::Error + Sync + Send>> {
+    let results = events.topic::<PublishedBatch>(PIPELINE_TOPIC)?;
+    let start = Instant::now();
+    let start_sequence = checkpoint.next_sequence;
+    let mut last_checkpoint = Instant::now();
+    let mut last_progress = Instant::now();
+    while let Some(batch) = input.recv().await {
+        checkpoint.checksum = batch.items.iter()
+            .fold(checkpoint.checksum, |checksum, item| checksum.rotate_left(5) ^ item);
+        checkpoint.next_sequence = batch.first_sequence + batch.items.len() as u64;
+        // reaches the followers of the bus -- like the webhooks & the socket clients subscribed to it -- & the logic's subscribers
+        results.publish(PublishedBatch { first_sequence: batch.first_sequence, items: batch.items.len() as u64, checksum: checkpoint.checksum });
+        if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+            checkpoints.save(&checkpoint)?;
+            last_checkpoint = Instant::now();
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            let published = checkpoint.next_sequence - start_sequence;
+            info!("Pipeline: {:.2}% done ({}/{} items) -- {:.0} items/s",
+                  100.0 * checkpoint.next_sequence as f64 / TOTAL_ITEMS as f64, checkpoint.next_sequence, TOTAL_ITEMS,
+                  published as f64 / start.elapsed().as_secs_f64());
+            last_progress = Instant::now();
+        }
+    }
+    debug!("Pipeline: `publish` stage is done");
+    Ok(checkpoint)
+}
+
+/// loads the last saved [Checkpoint] -- or a fresh one, if `checkpoint_file` doesn't exist
+fn load_checkpoint(checkpoint_file: &str) -> Result<Checkpoint, Box<dyn std::error::Error + Sync + Send>> {
+    match fs::read_to_string(checkpoint_file) {
+        Ok(ron_contents) => ron::from_str(&ron_contents)
+            .map_err(|err| Box::from(format!("Pipeline: could not deserialize the checkpoint in '{}': {} -- HINT: delete the file to start over", checkpoint_file, err))),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Checkpoint::default()),
+        Err(err) => Err(Box::from(format!("Pipeline: could not read the checkpoint file '{}': {}", checkpoint_file, err))),
+    }
+}
+
+/// atomically saves `checkpoint` -- through a temporary file -- so a crash while writing won't ever corrupt the previous one
+fn save_checkpoint(checkpoint_file: &str, checkpoint: &Checkpoint) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let ron_contents = ron::ser::to_string_pretty(checkpoint, ron::ser::PrettyConfig::new())
+        .map_err(|err| format!("Pipeline: could not serialize checkpoint {:?}: {}", checkpoint, err))?;
+    let temporary_file = format!("{}.tmp", checkpoint_file);
+    fs::write(&temporary_file, ron_contents)
+        .and_then(|_| fs::rename(&temporary_file, checkpoint_file))
+        .map_err(|err| Box::from(format!("Pipeline: could not save the checkpoint to '{}': {}", checkpoint_file, err)))
+}
@@ -0,0 +1,42 @@
+//! Demo domain: temperature & length conversions between the metric & imperial systems.
+//!
+//! Being a domain model, it knows nothing about the frontends -- see [super::dto] for how it is exposed through them.
+
+
+/// The direction of a [UnitsConversion]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConversionDirection {
+    MetricToImperial,
+    ImperialToMetric,
+}
+
+/// A temperature & length pair converted between unit systems
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitsConversion {
+    pub direction:        ConversionDirection,
+    pub from_temperature: f64,
+    pub from_length:      f64,
+    pub to_temperature:   f64,
+    pub to_length:        f64,
+}
+
+impl UnitsConversion {
+
+    const FEET_PER_METER: f64 = 3.2808398950132;
+
+    pub fn compute(direction: ConversionDirection, from_temperature: f64, from_length: f64) -> Self {
+        let (to_temperature, to_length) = match direction {
+            ConversionDirection::MetricToImperial => ((from_temperature * 9.0/5.0) + 32.0, from_length * Self::FEET_PER_METER),
+            ConversionDirection::ImperialToMetric => ((from_temperature - 32.0) * 5.0/9.0, from_length / Self::FEET_PER_METER),
+        };
+        Self { direction, from_temperature, from_length, to_temperature, to_length }
+    }
+
+    /// `(temperature, length)` units of the source & destination systems
+    pub fn units(&self) -> ((&'static str, &'static str), (&'static str, &'static str)) {
+        match self.direction {
+            ConversionDirection::MetricToImperial => (("°C", "m"),  ("°F", "ft")),
+            ConversionDirection::ImperialToMetric => (("°F", "ft"), ("°C", "m")),
+        }
+    }
+}
@@ -7,16 +7,25 @@ mod frontend;
 mod command_line;
 mod features;
 mod logic;
+mod logging;
+mod scheduler;
 
 use crate::{
-    runtime::Runtime,
+    scheduler::Scheduler,
+    runtime::{Runtime, LeaderElection, Locks, DegradeSwitches, Webhooks, RECENT_EVENTS, JobQueue, MetricsHistory, SelfMonitor, Storage, Outbox, ConfigReload, Supervisor, new_auth_provider, SessionTokens, SESSION_TOKEN_TTL, drop_privileges, daemonize, handle_signals, monitor_clock_jumps, monitor_config_drift, watch_config_file, install_metrics_recorder, SystemClock, apply_timezone},
     config::{
         APP_NAME,
-        DEBUG,
         Config,
+        DegradeConfig,
+        SchedulerConfig,
+        ScheduledJobConfig,
+        JobSchedule,
         UiOptions,
+        TerminalOptions,
+        Jobs,
         ExtendedOption,
         config_ops,
+        config_validation,
     },
 };
 use std::{
@@ -25,7 +34,8 @@ use std::{
     thread::{self, JoinHandle},
 };
 use std::borrow::BorrowMut;
-use tokio::sync::RwLock;
+use futures::stream::{FuturesUnordered, StreamExt};
+use tokio::sync::{RwLock, watch};
 use log::{debug, error, warn};
 use owning_ref::ArcRef;
 
@@ -35,18 +45,125 @@ fn custom_sync_initialization(_runtime: &RwLock<Runtime>, _config: &Config) -> R
     Ok(())
 }
 
-fn sync_main(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+fn sync_main(runtime: &Arc<RwLock<Runtime>>, config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
     let result = frontend::run(runtime, config);
     debug!("App's sync main is done. Result: '{:?}'", result);
     result
 }
 
-async fn async_main(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
+async fn async_main(runtime: &Arc<RwLock<Runtime>>, config: &Config) -> Result<(), Box<dyn Error + Send + Sync>> {
     // debug!("    Instantiating <<your logic's injections>>...");
-    // Runtime::register_YOUR_LOGIC_COMPONENT(runtime, YourLogicComponent::new().await).await;
+    // Runtime::register_component(runtime, YourLogicComponent::new().await).await;
+    tokio::spawn(monitor_clock_jumps());
+    // (client jobs, like `console tail`, must not watch -- nor restart -- the running instance)
+    let self_monitor = runtime.read().await.self_monitor.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(self_monitor) = self_monitor {
+        debug!("    starting the self-monitor...");
+        tokio::spawn(self_monitor.run(Arc::clone(runtime)));
+    }
+    let webhooks = runtime.read().await.webhooks.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(webhooks) = webhooks {
+        let events = runtime.read().await.events.follow();
+        tokio::spawn(webhooks.forward_events(events));
+    }
+    if config.ui.runs_services() {
+        let (inbound_hooks, response_cache, job_queue) = {
+            let runtime = runtime.read().await;
+            (frontend::web::inbound_hooks_topic(&runtime.events), Arc::clone(&runtime.response_cache), runtime.job_queue.clone())
+        };
+        tokio::spawn(logic::process_inbound_hooks(inbound_hooks.subscribe(), job_queue));
+        // inbound hooks tell the external data changed -- so the answers cached out of it must be recomputed
+        response_cache.invalidate_on(&inbound_hooks, "/");
+    }
+    // runtime.read().await.response_cache.invalidate_on(&your_data_changed_topic, "/api/YOUR_ROUTE");
+    let config_file = config_file_path();
+    let load_effective_config = {
+        let config_file = config_file.clone();
+        move || config_ops::load_from_file(&config_file)
+            .map(|config_file_options| command_line::effective_config(config_file_options, &command_line::parse_from_args()))
+    };
+    let config_reload = runtime.read().await.config_reload.clone();
+    if let Some(config_reload) = config_reload {
+        debug!("    starting the config hot reload...");
+        tokio::spawn(apply_config_reloads(Arc::clone(runtime), config_reload.subscribe()));
+        tokio::spawn(watch_config_file(config_reload, config_file, load_effective_config));
+    } else {
+        let config_digest = runtime.read().await.config_digest.clone();
+        tokio::spawn(monitor_config_drift(config_file, config_digest, load_effective_config));
+    }
+    if let ExtendedOption::Enabled(leader_election_config) = &config.leader_election {
+        debug!("    starting the leader election...");
+        let leader_election = Arc::new(LeaderElection::new(leader_election_config, &runtime.read().await.events));
+        let leader_election_for_task = Arc::clone(&leader_election);
+        tokio::spawn(async move { leader_election_for_task.run().await });
+        runtime.write().await.leader_election = Some(leader_election);
+    }
+    if let ExtendedOption::Enabled(locks_backend) = &config.locks {
+        debug!("    instantiating the locks backend...");
+        runtime.write().await.locks = Some(Arc::new(Locks::new(locks_backend).await?));
+    }
+    // (client jobs, like `console tail`, must not consume the running instance's queue)
+    let job_queue = runtime.read().await.job_queue.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(job_queue) = job_queue {
+        job_queue.register_worker(logic::INBOUND_HOOK_JOB, logic::on_inbound_hook);
+        // job_queue.register_worker("YOUR_JOB_KIND", |your_job: YourJob| async move { your_logic_component.process(your_job).await });
+        job_queue.start_workers();
+    }
+    let scheduler = runtime.read().await.scheduler.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(scheduler) = scheduler {
+        debug!("    starting the scheduler...");
+        let locks = runtime.read().await.locks.clone();
+        scheduler.register("demo", move || logic::scheduled_demo(locks.clone()));
+        // scheduler.register("YOUR_JOB", || async move { your_logic_component.your_periodic_work().await });
+        let telegram_notifier = runtime.read().await.telegram_notifier.clone();
+        if let Some(telegram_notifier) = telegram_notifier.filter(|_| status_report_cron(config).is_some()) {
+            let runtime = Arc::clone(runtime);
+            scheduler.register(frontend::telegram::STATUS_REPORT_JOB, move || {
+                let (runtime, telegram_notifier) = (Arc::clone(&runtime), Arc::clone(&telegram_notifier));
+                async move {
+                    telegram_notifier.notify(frontend::telegram::status_report(&runtime).await, true);
+                    Ok(())
+                }
+            });
+        }
+        let (leader_election, degrade, shutdown) = {
+            let runtime = runtime.read().await;
+            (runtime.leader_election.clone(), Arc::clone(&runtime.degrade), Arc::clone(&runtime.shutdown))
+        };
+        scheduler.start(leader_election, Some(degrade), shutdown);
+    }
+    let metrics_history = runtime.read().await.metrics_history.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(metrics_history) = metrics_history {
+        metrics_history.start_sampling();
+    }
+    let storage = runtime.read().await.storage.clone();
+    if let Some(storage) = storage.as_ref().filter(|_| config.ui.runs_services()) {
+        storage.start_autosave();
+    }
+    let outbox = runtime.read().await.outbox.clone()
+        .filter(|_| config.ui.runs_services());
+    if let Some(outbox) = outbox {
+        debug!("    starting the outbox dispatcher...");
+        tokio::spawn(outbox.run(Arc::clone(runtime)));
+    }
     let result = frontend::async_run(runtime, config).await;
     debug!("App's async frontend::async_run() is done. Result: '{:?}'", result);
-    // Runtime::do_for_YOUR_LOGIC_COMPONENT(runtime, |your_logic_component_instance| Box::pin(async { your_logic_component_instance.shutdown().await })).await;
+    if let Some(storage) = storage {
+        debug!("    saving the storage...");
+        if let Err(err) = storage.save() {
+            error!("{}", err);
+        }
+    }
+    // Runtime::do_if_component_is_present(runtime, |your_logic_component: &mut YourLogicComponent| Box::pin(async { your_logic_component.shutdown().await })).await;
+    let leader_election = runtime.read().await.leader_election.clone();
+    if let Some(leader_election) = leader_election {
+        leader_election.resign().await;
+    }
     debug!("App's async main is done.");
     result
 }
@@ -54,10 +171,26 @@ async fn async_main(runtime: &RwLock<Runtime>, config: &Config) -> Result<(), Bo
 fn main() -> Result<(), Box<dyn Error>> {
 
     let command_line_options = command_line::parse_from_args();
+    let daemonize_requested = command_line_options.daemonize;
     let config_file_options = load_configs();
     let effective_config = Arc::new(command_line::merge_config_file_and_command_line_options(config_file_options, command_line_options));
-    let _logger_guard = setup_logging(&effective_config);
-    let runtime = Arc::new(build_runtime());
+    // (the `doctor` job reports the config problems along with its other checks)
+    let doctor_requested = matches!(&effective_config.ui, ExtendedOption::Enabled(UiOptions::Console(Jobs::Doctor(_))));
+    if !doctor_requested {
+        if let Err(problems) = config_validation::validate(&effective_config) {
+            eprintln!("{}", config_validation::problems_report(&config_file_path(), &problems));
+            return Err(Box::from(format!("{} problem(s) found in the config -- see above", problems.len())));
+        }
+    }
+    // (before any threads are started)
+    apply_timezone(effective_config.timezone.as_deref());
+    let _pid_file = if daemonize_requested {
+        Some(daemonize(&effective_config).map_err(|err| err as Box<dyn Error>)?)
+    } else {
+        None
+    };
+    let _logger_guard = logging::setup_logging(&effective_config);
+    let runtime = Arc::new(build_runtime(&effective_config));
 
     warn!("{} application started!", APP_NAME);
     debug!("Running 'custom_sync_initialization()':");
@@ -89,23 +222,159 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 }
 
+/// the cron of Telegram's status reports -- see [config::TelegramConfig::status_report_cron] -- as sent by the primary bot
+fn status_report_cron(config: &Config) -> Option<&String> {
+    let ExtendedOption::Enabled(services) = &config.services else { return None };
+    let ExtendedOption::Enabled(telegram_configs) = &services.telegram else { return None };
+    telegram_configs.first()?.status_report_cron.as_ref()
+}
+
+/// Applies the hot reloadable settings of each reloaded config -- see [ConfigReload]. Your logic components may subscribe to it as well
+async fn apply_config_reloads(runtime: Arc<RwLock<Runtime>>, mut config_updates: watch::Receiver<Arc<Config>>) {
+    while config_updates.changed().await.is_ok() {
+        let config = Arc::clone(&config_updates.borrow_and_update());
+        runtime.write().await.config_digest = config_ops::digest(&config);
+        logging::apply_log_filters(&config.log_filters);
+        logging::apply_log_redactions(&config);
+        let ExtendedOption::Enabled(services) = &config.services else { continue };
+        if let ExtendedOption::Enabled(telegram_configs) = &services.telegram {
+            for telegram_config in telegram_configs {
+                let notification_chat_ids = telegram_config.notification_chat_ids.clone();
+                Runtime::do_if_telegram_ui_is_present(&runtime, &telegram_config.name, |telegram_ui| Box::pin(async move { telegram_ui.set_notification_chat_ids(notification_chat_ids) })).await;
+            }
+        }
+        if let ExtendedOption::Enabled(socket_server_config) = &services.socket_server {
+            let abuse_limits = Runtime::do_if_socket_server_is_present(&runtime, |socket_server| Box::pin(async move { socket_server.abuse_limits() })).await;
+            if let Some(abuse_limits) = abuse_limits {
+                abuse_limits.update(socket_server_config);
+            }
+        }
+    }
+}
+
 /// Loads default configs from ${0}.config.ron file -- creating it with defaults if it doesn't exist
 fn load_configs() -> Config {
-    let program_name = std::env::args().next().expect("Program name couldn't be retrieve from args");
-    let config_file = format!("{}.config.ron", program_name);
+    let config_file = config_file_path();
     config_ops::load_or_create_default(&config_file)
         .expect(&format!("Could not load (or create) the configuration file '{config_file}'"))
 }
 
+/// The config file for this program: ${0}.config.ron
+fn config_file_path() -> String {
+    let program_name = std::env::args().next().expect("Program name couldn't be retrieve from args");
+    format!("{}.config.ron", program_name)
+}
+
 /// Builds the initial [Runtime] object, filling it with environment info & Globals.\
 /// Counters, Metrics, Reports, Controllers and even Injections will be added / updated
 /// to it as soon as they are available.
-fn build_runtime() -> RwLock<Runtime> {
-    RwLock::new(Runtime::new(
+fn build_runtime(config: &Arc<Config>) -> RwLock<Runtime> {
+    let degrade_config = match &config.degrade {
+        ExtendedOption::Enabled(degrade_config) => degrade_config.clone(),
+        _ => DegradeConfig::default(),
+    };
+    let max_tracked_socket_endpoints = match &config.services {
+        ExtendedOption::Enabled(services) => match &services.socket_server {
+            ExtendedOption::Enabled(socket_server_config) => socket_server_config.max_tracked_endpoints,
+            _ => 0,
+        },
+        _ => 0,
+    };
+    let mut runtime = Runtime::new(
         std::env::current_exe()
             .map_err(|err| format!("Could not get the executable file path: {}", err))
-            .unwrap().to_string_lossy().to_string()
-    ))
+            .unwrap().to_string_lossy().to_string(),
+        config_ops::digest(config),
+        DegradeSwitches::new(&degrade_config),
+        frontend::socket_server::SocketServerStats::new(max_tracked_socket_endpoints),
+    );
+    match install_metrics_recorder() {
+        Ok(metrics) => runtime.metrics = Some(metrics),
+        Err(err) => error!("Metrics won't be available: {}", err),
+    }
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(web_config) = &services.web {
+            if web_config.ogre_events_queue_routes {
+                runtime.events.keep_recent(RECENT_EVENTS);
+            }
+        }
+    }
+    if let ExtendedOption::Enabled(webhooks_config) = &config.webhooks {
+        runtime.webhooks = Some(Arc::new(Webhooks::new(webhooks_config)));
+    }
+    if let ExtendedOption::Enabled(auth_config) = &config.auth {
+        let auth_provider = new_auth_provider(auth_config)
+            .unwrap_or_else(|err| panic!("Could not instantiate the auth provider: {}", err));
+        runtime.auth = Some(auth_provider);
+        runtime.session_tokens = Some(Arc::new(SessionTokens::new(SESSION_TOKEN_TTL)));
+    }
+    if let ExtendedOption::Enabled(job_queue_config) = &config.job_queue {
+        runtime.job_queue = Some(Arc::new(JobQueue::new(job_queue_config)
+            .unwrap_or_else(|err| panic!("Could not instantiate the job queue: {}", err))));
+    }
+    let mut scheduler_config = match &config.scheduler {
+        ExtendedOption::Enabled(scheduler_config) => Some(scheduler_config.clone()),
+        _ => None,
+    };
+    if let Some(status_report_cron) = status_report_cron(config) {
+        scheduler_config.get_or_insert_with(|| SchedulerConfig { jobs: vec![] })
+            .jobs.push(ScheduledJobConfig { name: frontend::telegram::STATUS_REPORT_JOB.to_string(), schedule: JobSchedule::Cron(status_report_cron.clone()), critical: false });
+    }
+    if let Some(scheduler_config) = &scheduler_config {
+        runtime.scheduler = Some(Arc::new(Scheduler::new(scheduler_config, Arc::clone(&runtime.job_accounting), Arc::new(SystemClock))
+            .unwrap_or_else(|err| panic!("Could not instantiate the scheduler: {}", err))));
+    }
+    if let ExtendedOption::Enabled(storage_config) = &config.storage {
+        runtime.storage = Some(Arc::new(Storage::new(storage_config)
+            .unwrap_or_else(|err| panic!("Could not instantiate the storage: {}", err))));
+    }
+    if let ExtendedOption::Enabled(metrics_history_config) = &config.metrics_history {
+        let metrics_history = Arc::new(MetricsHistory::new(metrics_history_config));
+        track_key_metrics(&metrics_history, &runtime);
+        runtime.metrics_history = Some(metrics_history);
+    }
+    let mut primary_telegram_bot = None;
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(telegram_configs) = &services.telegram {
+            if let Some(primary_telegram_config) = telegram_configs.first() {
+                runtime.telegram_notifier = Some(Arc::new(frontend::telegram::TelegramNotifier::new(&primary_telegram_config.mt_limits)));
+                primary_telegram_bot = Some(primary_telegram_config.name.clone());
+            }
+            runtime.expect_telegram_uis(telegram_configs.iter().map(|telegram_config| telegram_config.name.clone()));
+        }
+    }
+    if let (ExtendedOption::Enabled(outbox_config), Some(storage)) = (&config.outbox, &runtime.storage) {
+        runtime.outbox = Some(Arc::new(Outbox::new(outbox_config, Arc::clone(storage), primary_telegram_bot)));
+    }
+    if let ExtendedOption::Enabled(self_monitor_config) = &config.self_monitor {
+        runtime.self_monitor = Some(Arc::new(SelfMonitor::new(self_monitor_config)));
+    }
+    if let ExtendedOption::Enabled(_hot_reload_config) = &config.hot_reload {
+        runtime.config_reload = Some(Arc::new(ConfigReload::new(Arc::clone(config), &config_file_path())));
+    }
+    runtime.warmup.register("logic", logic::warmup);
+    RwLock::new(runtime)
+}
+
+/// Starts keeping the history of the application's key metrics -- your logic components may track their own
+fn track_key_metrics(metrics_history: &MetricsHistory, runtime: &Runtime) {
+    let socket_server_stats = Arc::clone(&runtime.socket_server_stats);
+    metrics_history.track("socket_connected_clients", move || socket_server_stats.report().endpoints.iter().filter(|endpoint| endpoint.connected).count() as f64);
+    let socket_server_stats = Arc::clone(&runtime.socket_server_stats);
+    metrics_history.track("socket_messages_in", move || socket_server_stats.report().endpoints.iter().map(|endpoint| endpoint.messages_in).sum::<u64>() as f64);
+    let socket_server_stats = Arc::clone(&runtime.socket_server_stats);
+    metrics_history.track("socket_messages_out", move || socket_server_stats.report().endpoints.iter().map(|endpoint| endpoint.messages_out).sum::<u64>() as f64);
+    let socket_server_stats = Arc::clone(&runtime.socket_server_stats);
+    metrics_history.track("socket_errors", move || socket_server_stats.report().endpoints.iter().map(|endpoint| endpoint.errors).sum::<u64>() as f64);
+    let response_cache = Arc::clone(&runtime.response_cache);
+    metrics_history.track("web_cache_hits", move || response_cache.report().hits as f64);
+    let response_cache = Arc::clone(&runtime.response_cache);
+    metrics_history.track("web_cache_misses", move || response_cache.report().misses as f64);
+    if let Some(job_queue) = &runtime.job_queue {
+        let job_queue = Arc::clone(job_queue);
+        metrics_history.track("job_queue_pending", move || job_queue.pending() as f64);
+    }
+    // metrics_history.track("YOUR_METRIC", move || your_logic_component.your_metric() as f64);
 }
 
 /// starts the Tokio runtime and all related UIs,
@@ -127,100 +396,161 @@ fn start_tokio_runtime_and_apps(runtime: Arc<RwLock<Runtime>>, config: Arc<Confi
         runtime.blocking_write().tokio_runtime = Some(Arc::clone(&tokio_runtime));
         tokio_runtime
             .block_on(async {
+                // exports the tracing spans -- see `config.telemetry`. Flushed once the tasks below are over
+                let _telemetry_guard = match &config.telemetry {
+                    ExtendedOption::Enabled(telemetry_config) => logging::telemetry::setup_telemetry(telemetry_config)
+                        .map_err(|err| error!("Traces won't be exported: {}", err))
+                        .ok(),
+                    _ => None,
+                };
                 let runtime_for_async_main_task = Arc::clone(&runtime);
                 let config_for_async_main_task = Arc::clone(&config);
-                let mut async_main_task = tokio::spawn(async move {
+                let privileges = Arc::clone(&runtime.read().await.privileges);
+                // client jobs (like `console tail`) bind nothing: there are no privileges to wait for
+                if !config.ui.runs_services() {
+                    privileges.open();
+                }
+                let async_main_task = tokio::spawn(async move {
+                    if !privileges.wait().await {
+                        debug!("    not running 'async_main()': privileges could not be dropped");
+                        return Ok(())
+                    }
                     debug!("    running 'async_main()'...");
                     async_main(&runtime_for_async_main_task, &config_for_async_main_task).await
                         .map_err(|err| Box::from(format!("async_main(): Aborting due to error: {}", err)))
                 });
-                let runtime_for_telegram_task = Arc::clone(&runtime);
-                let config_for_telegram_task = Arc::clone(&config);
-                let mut telegram_task = tokio::spawn(async move {
-                    if let ExtendedOption::Enabled(_telegram_config) = &config_for_telegram_task.services.telegram {
-                        debug!("    starting Telegram UI service...");
-                        let telegram_config = ArcRef::from(config_for_telegram_task)
-                            .map(|config| &*config.services.telegram);
-                        let mut telegram_ui = frontend::telegram::TelegramUI::new(telegram_config).await;
-                        let run_closure = telegram_ui.runner();
-                        Runtime::register_telegram_ui(&runtime_for_telegram_task, telegram_ui).await;
-                        (run_closure)().await;
+                let mut tasks = FuturesUnordered::new();
+                tasks.push(named_task("async_main", async_main_task));
+                // SIGTERM & SIGINT: cancels the console jobs (see `frontend::console`) & stops the services -- not joined, as it runs until the process ends
+                let runtime_for_signals_task = Arc::clone(&runtime);
+                let runs_services = config.ui.runs_services();
+                let shutdown = Arc::clone(&runtime.read().await.shutdown);
+                tokio::spawn(handle_signals(shutdown, async move {
+                    if runs_services {
+                        frontend::shutdown_tokio_services(&runtime_for_signals_task).await
+                            .unwrap_or_else(|err| error!("Error shutting down the services: {}", err));
                     }
-                    Ok(())
-                });
-                let runtime_for_rocket_task = Arc::clone(&runtime);
-                let config_for_rocket_task = Arc::clone(&config);
-                let mut rocket_task = tokio::spawn(async move {
-                    if let ExtendedOption::Enabled(_rocket_config) = &config_for_rocket_task.services.web {
-                        debug!("    starting Web service...");
-                        let rocket_config = ArcRef::from(config_for_rocket_task)
+                }));
+                // SIGHUP: rotates & reopens the log files
+                let config_for_sighup_task = Arc::clone(&config);
+                tokio::spawn(async move { logging::reopen_files_on_sighup(&config_for_sighup_task).await });
+                // client jobs (like `console tail`) talk to an already running instance: they run no services of their own
+                if config.ui.runs_services() {
+                    // restarts the services' tasks when they end -- see `config.supervisor`
+                    let supervisor = {
+                        let runtime = runtime.read().await;
+                        let supervisor_config = if let ExtendedOption::Enabled(supervisor_config) = &config.supervisor { Some(supervisor_config.clone()) } else { None };
+                        Arc::new(Supervisor::new(supervisor_config, Arc::clone(&runtime.shutdown), runtime.telegram_notifier.clone()))
+                    };
+                    // the privilege dropping & warmup phases -- between the services binding their ports & serving (see `PrivilegesGate`).
+                    // Not joined, as they never end if one of the services fails to start
+                    let runtime_for_warmup_task = Arc::clone(&runtime);
+                    let config_for_warmup_task = Arc::clone(&config);
+                    tokio::spawn(async move {
+                        frontend::wait_for_services(&runtime_for_warmup_task, &config_for_warmup_task).await;
+                        let privileges = Arc::clone(&runtime_for_warmup_task.read().await.privileges);
+                        if let Err(err) = drop_privileges(&config_for_warmup_task.run_as) {
+                            error!("Shutting down, as privileges could not be dropped: {}", err);
+                            privileges.fail();
+                            frontend::shutdown_tokio_services(&runtime_for_warmup_task).await
+                                .unwrap_or_else(|err| error!("Error shutting down the services: {}", err));
+                            return
+                        }
+                        privileges.open();
+                        let warmup = Arc::clone(&runtime_for_warmup_task.read().await.warmup);
+                        warmup.run().await;
+                        if let Some(telegram_notifier) = &runtime_for_warmup_task.read().await.telegram_notifier {
+                            telegram_notifier.notify(format!("<b>{}</b> is up & serving", env!("CARGO_PKG_NAME")), true);
+                        }
+                    });
+                    if let ExtendedOption::Enabled(telegram_configs) = &config.services.telegram {
+                        for i in 0..telegram_configs.len() {
+                            let runtime = Arc::clone(&runtime);
+                            let telegram_config = ArcRef::from(Arc::clone(&config))
+                                .map(|config| &config.services.telegram[i]);
+                            // (leaked once per bot: task names are static)
+                            let task_name: &'static str = Box::leak(format!("telegram:{}", telegram_config.name).into_boxed_str());
+                            tasks.push(named_task(task_name, supervisor.spawn(task_name, supervisor.restart_policy("telegram"), move || {
+                                let (runtime, telegram_config) = (Arc::clone(&runtime), telegram_config.clone());
+                                async move {
+                                    let (degrade, auth, telegram_notifier) = {
+                                        let runtime = runtime.read().await;
+                                        // only the primary bot sends the notifier's push messages
+                                        (Arc::clone(&runtime.degrade), runtime.auth.clone(), runtime.telegram_notifier.clone().filter(|_| i == 0))
+                                    };
+                                    frontend::run_service(&runtime, frontend::telegram::TelegramUI::new(telegram_config, degrade, auth, telegram_notifier).await).await
+                                }
+                            })));
+                        }
+                    }
+                    if let ExtendedOption::Enabled(_rocket_config) = &config.services.web {
+                        let runtime = Arc::clone(&runtime);
+                        let rocket_config = ArcRef::from(Arc::clone(&config))
                             .map(|config| &*config.services.web);
-                        let mut rocket_handle = frontend::web::WebServer::new(rocket_config);
-                        let runner_closure = rocket_handle.runner().await?;
-                        //let shutdown_token = rocket_handle.shutdown_token.expect("shutdown should be available at this point");
-                        Runtime::register_web_server(&runtime_for_rocket_task, rocket_handle).await;
-                        runner_closure().await?;
+                        tasks.push(named_task("rocket service", supervisor.spawn("web", supervisor.restart_policy("web"), move || {
+                            let (runtime, rocket_config) = (Arc::clone(&runtime), rocket_config.clone());
+                            async move {
+                                let web_server = frontend::web::WebServer::new(rocket_config, &runtime).await;
+                                frontend::run_service(&runtime, web_server).await
+                            }
+                        })));
                     }
-                    Ok(())
-                });
-                let runtime_for_socket_server_task = Arc::clone(&runtime);
-                let config_for_socket_server_task = Arc::clone(&config);
-                let mut socket_server_task = tokio::spawn(async move {
-                    if let ExtendedOption::Enabled(_socket_server_config) = &config_for_socket_server_task.services.socket_server {
-                        debug!("    starting Socket Server service...");
-                        let socket_server_config = ArcRef::from(config_for_socket_server_task)
+                    if let ExtendedOption::Enabled(socket_server_config) = &config.services.socket_server {
+                        debug!("    the Socket Server service will use the {:?} processor", socket_server_config.processor);
+                        let runtime = Arc::clone(&runtime);
+                        let socket_server_config = ArcRef::from(Arc::clone(&config))
                             .map(|config| &*config.services.socket_server);
-                        let mut socket_server_handle = frontend::socket_server::SocketServer::new(socket_server_config);
-                        let tokio_runtime = Arc::clone(runtime.read().await.tokio_runtime.as_ref().unwrap());
-                        let (processor_stream, stream_producer, stream_closer) = frontend::socket_server::sync_processors(tokio_runtime);
-                        let processor = socket_server_handle.set_processor(processor_stream, stream_producer, stream_closer);
-                        let executor_join_handle = frontend::socket_server::spawn_stream_executor(processor).await;
-                        let runner_closure = socket_server_handle.runner().await?;
-                        Runtime::register_socket_server(&runtime_for_socket_server_task, socket_server_handle).await;
-                        let (service_runner_result, stream_executor_result) = tokio::join!(runner_closure(), async {executor_join_handle.await});
-                        service_runner_result.map_err(|err| format!("service runner failed: {}", err))?;
-                        stream_executor_result.map_err(|err| format!("stream executor failed: {}", err))?;
+                        tasks.push(named_task("socket service", supervisor.spawn("socket-server", supervisor.restart_policy("socket-server"), move || {
+                            let (runtime, socket_server_config) = (Arc::clone(&runtime), socket_server_config.clone());
+                            async move {
+                                let (socket_server_stats, socket_server_peers, degrade, auth, events, privileges, tokio_runtime) = {
+                                    let runtime = runtime.read().await;
+                                    (Arc::clone(&runtime.socket_server_stats), Arc::clone(&runtime.socket_server_peers), Arc::clone(&runtime.degrade), runtime.socket_auth(), Arc::clone(&runtime.events), Arc::clone(&runtime.privileges), Arc::clone(runtime.tokio_runtime.as_ref().unwrap()))
+                                };
+                                let mut socket_server = frontend::socket_server::SocketServer::new(socket_server_config, socket_server_stats, socket_server_peers, degrade, auth, events);
+                                socket_server.attach_processor(tokio_runtime, privileges).await;
+                                frontend::run_service(&runtime, socket_server).await
+                            }
+                        })));
                     }
-                    Ok(())
-                });
-
-                let mut all_good = true;
-                let mut join_and_log = |task_handle: Result<Result<(), Box<dyn std::error::Error + Sync + Send>>, tokio::task::JoinError>, task_name: &str| {
-                    match task_handle {
-                        Ok(join_result) => {
-                            match join_result {
-                                Ok(ok) => {
-                                    debug!("  '{}' task ended gracefully! Result: '{:?}'", task_name, ok);
-                                },
-                                Err(err) => {
-                                    error!("  '{}' ended with failure: {}", task_name, err);
-                                    all_good = false;
-                                }
+                    if let ExtendedOption::Enabled(socket_client_config) = &config.services.socket_client {
+                        debug!("    the Socket Client service will connect to {}", socket_client_config.server_address);
+                        let runtime = Arc::clone(&runtime);
+                        let socket_client_config = ArcRef::from(Arc::clone(&config))
+                            .map(|config| &*config.services.socket_client);
+                        tasks.push(named_task("socket client service", supervisor.spawn("socket-client", supervisor.restart_policy("socket-client"), move || {
+                            let (runtime, socket_client_config) = (Arc::clone(&runtime), socket_client_config.clone());
+                            async move {
+                                frontend::run_service(&runtime, frontend::socket_client::SocketClient::new(socket_client_config)).await
                             }
-                        }
-                        Err(join_err) => error!("Couldn't start/finish Tokio task '{}': {:?} -- thread panicked?", task_name, join_err)
+                        })));
                     }
-                    Some(())
-                };
+                    if let ExtendedOption::Enabled(grpc_config) = &config.services.grpc {
+                        debug!("    the gRPC service will listen to {}:{}", grpc_config.interface, grpc_config.port);
+                        let runtime = Arc::clone(&runtime);
+                        let grpc_config = ArcRef::from(Arc::clone(&config))
+                            .map(|config| &*config.services.grpc);
+                        tasks.push(named_task("grpc service", supervisor.spawn("grpc", supervisor.restart_policy("grpc"), move || {
+                            let (runtime, grpc_config) = (Arc::clone(&runtime), grpc_config.clone());
+                            async move {
+                                let degrade = Arc::clone(&runtime.read().await.degrade);
+                                frontend::run_service(&runtime, frontend::grpc::GrpcServer::new(grpc_config, degrade)).await
+                            }
+                        })));
+                    }
+                }
 
-                let mut async_main_result    = None;
-                let mut telegram_result      = None;
-                let mut rocket_result        = None;
-                let mut socket_server_result = None;
-                while async_main_result.is_none() || telegram_result.is_none() || rocket_result.is_none() || socket_server_result.is_none() {
-                    tokio::select! {
-                        result = &mut async_main_task, if async_main_result.is_none() => {
-                            async_main_result = join_and_log(result, "async_main");
-                        },
-                        result = &mut telegram_task, if telegram_result.is_none() => {
-                            telegram_result = join_and_log(result, "telegram service");
-                        },
-                        result = &mut rocket_task, if rocket_result.is_none() => {
-                            rocket_result = join_and_log(result, "rocket service");
+                let mut all_good = true;
+                while let Some((task_name, task_handle)) = tasks.next().await {
+                    match task_handle {
+                        Ok(Ok(ok)) => {
+                            debug!("  '{}' task ended gracefully! Result: '{:?}'", task_name, ok);
                         },
-                        result = &mut socket_server_task, if socket_server_result.is_none() => {
-                            socket_server_result = join_and_log(result, "socket service");
+                        Ok(Err(err)) => {
+                            error!("  '{}' ended with failure: {}", task_name, err);
+                            all_good = false;
                         },
+                        Err(join_err) => error!("Couldn't start/finish Tokio task '{}': {:?} -- thread panicked?", task_name, join_err),
                     }
                 }
                 all_good
@@ -229,6 +559,12 @@ fn start_tokio_runtime_and_apps(runtime: Arc<RwLock<Runtime>>, config: Arc<Confi
     })
 }
 
+/// pairs the outcome of the Tokio task `task_handle` with its `task_name` -- for it to be logged
+async fn named_task(task_name: &'static str, task_handle: tokio::task::JoinHandle<frontend::ServiceResult>)
+                   -> (&'static str, Result<frontend::ServiceResult, tokio::task::JoinError>) {
+    (task_name, task_handle.await)
+}
+
 /// In case no UI was provided, experimentally picks one of the available
 /// which don't require further parameters to run -- this, most of the times,
 /// filters out Console (form it may have several commands to coose from),
@@ -239,66 +575,7 @@ fn auto_select_ui(_config: &Config) -> UiOptions {
     // } else if is_tty() && config.log != Console {
     //     AvailableFrontends::Terminal
     // } else {
-    UiOptions::Terminal
+    UiOptions::Terminal(TerminalOptions::default())
     // }
 }
 
-
-// LOGGING
-//////////
-// Facade for the `slog` crate to behave just like the `log` API
-// (currently we use `slog-scope` & `slog-stdlog` crates for the heavy lifting)
-use config::config::LoggingOptions;
-use slog_scope::GlobalLoggerGuard;
-use sloggers::{Build, types::{OverflowStrategy, Severity}};
-
-
-/// Keep those levels in sync with Cargo.toml's `log` crate levels defined in features.
-/// Example: features = ["max_level_debug", "release_max_level_info"]
-const LOG_LEVEL: Severity = if DEBUG {
-    Severity::Debug
-} else {
-    Severity::Info
-};
-
-/// starts a global logger according to `config` specifications
-/// -- the returned value should not be dropped until the program ends
-fn setup_logging(config: &Config) -> GlobalLoggerGuard {
-    match &config.log {
-        LoggingOptions::Quiet => build_quiet_logger(),
-        LoggingOptions::ToConsole => build_console_logger(),
-        LoggingOptions::ToFile {file_path, rotation_size, rotations_kept, compress_rotated} => build_file_logger(&file_path, *rotation_size, *rotations_kept, *compress_rotated)
-    }
-}
-
-fn build_quiet_logger() -> GlobalLoggerGuard {
-    let logger = sloggers::null::NullLoggerBuilder {}
-        .build()
-        .expect("Could not create a 'quiet' logger");
-    let log_guard = slog_scope::set_global_logger(logger);
-    slog_stdlog::init().unwrap();
-    log_guard
-}
-
-fn build_console_logger() -> GlobalLoggerGuard{
-    let mut builder = sloggers::terminal::TerminalLoggerBuilder::new();
-    builder.level(LOG_LEVEL);
-    builder.destination(sloggers::terminal::Destination::Stdout);
-    let logger = builder.build().expect("Could not create a 'console' logger");
-    let log_guard = slog_scope::set_global_logger(logger);
-    slog_stdlog::init().unwrap();
-    log_guard
-}
-
-fn build_file_logger(log_file: &str, rotate_size: usize, rotate_keep: usize, rotate_compress: bool) -> GlobalLoggerGuard {
-    let mut builder = sloggers::file::FileLoggerBuilder::new(log_file);
-    builder.overflow_strategy(OverflowStrategy::Block);
-    builder.rotate_size(rotate_size as u64);
-    builder.rotate_keep(rotate_keep);
-    builder.rotate_compress(rotate_compress);
-    builder.level(LOG_LEVEL);
-    let logger = builder.build().expect("Could not create a file logger");
-    let log_guard = slog_scope::set_global_logger(logger);
-    slog_stdlog::init().unwrap();
-    log_guard
-}
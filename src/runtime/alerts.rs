@@ -0,0 +1,33 @@
+//! Operational alerts -- conditions operators should look into, like clients above their round-trip SLA -- published by the subsystems
+//! detecting them on the [ALERTS_TOPIC] of the [super::EventBus]. They reach its followers (like the webhooks & the `/api/events` routes)
+//! and whatever subscribes to them:
+//! ```no_compile
+//!     let alerts = alerts_topic(&runtime.read().await.events);
+//!     alerts.publish(Alert { source: "socket-server", kind: "round-trip", message: format!("...") });
+//! ```
+
+use super::{EventBus, Topic};
+use std::sync::Arc;
+use serde::Serialize;
+
+
+/// The topic carrying the [Alert]s
+pub const ALERTS_TOPIC: &str = "alerts";
+
+
+/// An operational alert -- published on the [ALERTS_TOPIC]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// the subsystem raising the alert -- like `socket-server`
+    pub source:  &'static str,
+    /// what the alert is about, for consumers to tell them apart -- like `round-trip`
+    pub kind:    &'static str,
+    /// the human readable description
+    pub message: String,
+}
+
+/// the [ALERTS_TOPIC] of `events`
+pub fn alerts_topic(events: &Arc<EventBus>) -> Topic<Alert> {
+    events.topic(ALERTS_TOPIC)
+        .expect("BUG: alerts.rs: the alerts topic was created for another event type")
+}
@@ -0,0 +1,456 @@
+//! Authentication & authorization -- identity management in one place, to which the web guards (see `frontend/web/api.rs`'s
+//! `Authenticated`) & the Telegram ACLs delegate. See [crate::config::Config::auth] for the available providers.
+//!
+//! Identities are, then, referred to by their names -- for Telegram users, "telegram:<user id>".
+//!
+//! Web-authenticated identities may also obtain short-lived, revocable session tokens -- see [SessionTokens] -- to present in the
+//! socket server's `Login` handshake: so both frontends share one identity model.
+
+use crate::config::{AuthConfig, IdentityConfig};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::Serialize;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use log::{info, warn};
+
+
+/// The role allowing operator commands -- like the Telegram `/maintenance`
+pub const OPERATOR_ROLE: &str = "operator";
+
+/// How long the OIDC provider's verdicts on tokens are trusted before asking it again
+const OIDC_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long to wait for the OIDC provider to answer
+const OIDC_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the session tokens issued by [SessionTokens] are valid for
+pub const SESSION_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+
+/// An authenticated identity
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Principal {
+    pub name:  String,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+
+    /// who everyone is when [crate::config::Config::auth] is disabled
+    pub fn anonymous() -> Self {
+        Self { name: String::from("anonymous"), roles: vec![] }
+    }
+}
+
+/// `Ok(None)` for credentials that were rejected; `Err` if they couldn't be verified (like when the provider is unreachable)
+pub type AuthResult = Result<Option<Principal>, Box<dyn std::error::Error + Send + Sync>>;
+
+/// The interface to identity management -- see [new_auth_provider()] for the built-in implementations
+pub trait AuthProvider: Send + Sync {
+
+    /// the identity owning `api_key`
+    fn verify_api_key<'a>(&'a self, api_key: &'a str) -> BoxFuture<'a, AuthResult>;
+
+    /// the identity named `name`, if `password` is its password
+    fn verify_password<'a>(&'a self, name: &'a str, password: &'a str) -> BoxFuture<'a, AuthResult>;
+
+    /// the identity a JWT / OIDC access `token` was issued to -- if it is still valid
+    fn verify_token<'a>(&'a self, token: &'a str) -> BoxFuture<'a, AuthResult>;
+
+    /// the roles of the identity named `name` -- empty if it is unknown or if the provider can't tell without credentials
+    fn lookup_roles<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Vec<String>>;
+}
+
+/// Instantiates the [AuthProvider] selected in `config`
+pub fn new_auth_provider(config: &AuthConfig) -> Result<Arc<dyn AuthProvider>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(match config {
+        AuthConfig::Static { identities }                          => Arc::new(StaticAuth::new(identities.clone())),
+        AuthConfig::File   { file_path }                           => Arc::new(FileAuth::new(file_path)?),
+        AuthConfig::Oidc   { userinfo_url, name_claim, roles_claim } => Arc::new(OidcAuth::new(userinfo_url, name_claim, roles_claim)),
+    })
+}
+
+/// tells if `password` is the one hashed in `password_hash` -- see [IdentityConfig::password_hash]. The hashes are compared in constant time
+fn verify_password_hash(password_hash: &str, password: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(password_hash) => Argon2::default().verify_password(password.as_bytes(), &password_hash).is_ok(),
+        Err(err) => {
+            warn!("Auth: refusing a password login against an invalid password hash: {}", err);
+            false
+        },
+    }
+}
+
+/// compares secrets (tokens, API keys, ...) taking the same time wherever they differ -- so they can't be guessed byte by byte
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+
+
+/// Short-lived session tokens issued to already authenticated identities -- like the ones web users get from `/api/session/socket-token`
+/// to present in the socket server's `Login` handshake. Tokens expire after their TTL & may be revoked before that
+pub struct SessionTokens {
+    ttl:    Duration,
+    /// the valid tokens, along with their expiration & owner
+    tokens: Mutex<HashMap<String, (Instant, Principal)>>,
+}
+
+/// A token issued by [SessionTokens::issue()]
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionToken {
+    pub token:              String,
+    pub expires_in_seconds: u64,
+}
+
+impl SessionTokens {
+
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// issues a new random token for `principal` -- valid until it expires or is revoked
+    pub fn issue(&self, principal: &Principal) -> SessionToken {
+        let token = hex::encode(rand::random::<[u8; 32]>());
+        let mut tokens = self.tokens.lock();
+        tokens.retain(|_token, (expires_at, _principal)| *expires_at > Instant::now());
+        tokens.insert(token.clone(), (Instant::now() + self.ttl, principal.clone()));
+        SessionToken { token, expires_in_seconds: self.ttl.as_secs() }
+    }
+
+    /// the identity `token` was issued to -- `None` if it is unknown, expired or revoked
+    pub fn verify(&self, token: &str) -> Option<Principal> {
+        let mut tokens = self.tokens.lock();
+        match tokens.get(token) {
+            Some((expires_at, principal)) if *expires_at > Instant::now() => Some(principal.clone()),
+            Some(_expired) => {
+                tokens.remove(token);
+                None
+            },
+            None => None,
+        }
+    }
+
+    /// invalidates `token` -- if it belongs to `principal`. Returns if it was revoked
+    pub fn revoke(&self, token: &str, principal: &Principal) -> bool {
+        let mut tokens = self.tokens.lock();
+        if tokens.get(token).map_or(false, |(_expires_at, owner)| owner.name == principal.name) {
+            tokens.remove(token);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// invalidates all tokens issued to the identity `name` -- returning how many were revoked
+    pub fn revoke_all(&self, name: &str) -> usize {
+        let mut tokens = self.tokens.lock();
+        let count = tokens.len();
+        tokens.retain(|_token, (_expires_at, principal)| principal.name != name);
+        count - tokens.len()
+    }
+}
+
+/// Decorates an [AuthProvider], also accepting the [SessionTokens] it issued wherever tokens are verified -- only meant for the socket
+/// server's login: were the web guards to accept them, session tokens could renew themselves forever & would grant the whole web API
+pub struct WithSessionTokens {
+    provider: Arc<dyn AuthProvider>,
+    sessions: Arc<SessionTokens>,
+}
+
+impl WithSessionTokens {
+    pub fn new(provider: Arc<dyn AuthProvider>, sessions: Arc<SessionTokens>) -> Self {
+        Self { provider, sessions }
+    }
+}
+
+impl AuthProvider for WithSessionTokens {
+
+    fn verify_api_key<'a>(&'a self, api_key: &'a str) -> BoxFuture<'a, AuthResult> {
+        self.provider.verify_api_key(api_key)
+    }
+
+    fn verify_password<'a>(&'a self, name: &'a str, password: &'a str) -> BoxFuture<'a, AuthResult> {
+        self.provider.verify_password(name, password)
+    }
+
+    fn verify_token<'a>(&'a self, token: &'a str) -> BoxFuture<'a, AuthResult> {
+        match self.sessions.verify(token) {
+            Some(principal) => Box::pin(async move { Ok(Some(principal)) }),
+            None => self.provider.verify_token(token),
+        }
+    }
+
+    fn lookup_roles<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Vec<String>> {
+        self.provider.lookup_roles(name)
+    }
+}
+
+
+/// Identities listed in the config -- see [AuthConfig::Static]
+struct StaticAuth {
+    identities: Vec<IdentityConfig>,
+}
+
+impl StaticAuth {
+
+    fn new(identities: Vec<IdentityConfig>) -> Self {
+        Self { identities }
+    }
+
+    fn principal(identity: &IdentityConfig) -> Principal {
+        Principal { name: identity.name.clone(), roles: identity.roles.clone() }
+    }
+
+    fn find_by_api_key(&self, api_key: &str) -> Option<Principal> {
+        self.identities.iter()
+            .find(|identity| identity.api_keys.iter().any(|identity_api_key| constant_time_eq(identity_api_key.as_bytes(), api_key.as_bytes())))
+            .map(Self::principal)
+    }
+
+    /// verifies the password in Tokio's blocking pool -- as argon2 is purposely slow
+    async fn find_by_password(&self, name: &str, password: &str) -> AuthResult {
+        let Some(identity) = self.identities.iter().find(|identity| identity.name == name && !identity.password_hash.is_empty()) else {
+            return Ok(None)
+        };
+        let (password_hash, password) = (identity.password_hash.clone(), password.to_string());
+        let verified = tokio::task::spawn_blocking(move || verify_password_hash(&password_hash, &password)).await?;
+        Ok(verified.then(|| Self::principal(identity)))
+    }
+
+    fn roles(&self, name: &str) -> Vec<String> {
+        self.identities.iter()
+            .find(|identity| identity.name == name)
+            .map_or_else(Vec::new, |identity| identity.roles.clone())
+    }
+}
+
+impl AuthProvider for StaticAuth {
+
+    fn verify_api_key<'a>(&'a self, api_key: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async move { Ok(self.find_by_api_key(api_key)) })
+    }
+
+    fn verify_password<'a>(&'a self, name: &'a str, password: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(self.find_by_password(name, password))
+    }
+
+    /// tokens are not issued by this provider
+    fn verify_token<'a>(&'a self, _token: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn lookup_roles<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async move { self.roles(name) })
+    }
+}
+
+
+/// Identities listed in a RON file -- see [AuthConfig::File]. The file is reloaded whenever it changes
+struct FileAuth {
+    file_path:  String,
+    /// the identities, as of the file's modification time
+    identities: Mutex<(SystemTime, Arc<StaticAuth>)>,
+}
+
+impl FileAuth {
+
+    fn new(file_path: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let (modified, identities) = Self::load(file_path)?;
+        Ok(Self {
+            file_path:  file_path.to_string(),
+            identities: Mutex::new((modified, Arc::new(identities))),
+        })
+    }
+
+    fn load(file_path: &str) -> Result<(SystemTime, StaticAuth), Box<dyn std::error::Error + Send + Sync>> {
+        let modified = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| format!("Auth: could not stat the identities file '{}': {}", file_path, err))?;
+        let contents = std::fs::read_to_string(file_path)
+            .map_err(|err| format!("Auth: could not read the identities file '{}': {}", file_path, err))?;
+        let identities: Vec<IdentityConfig> = ron::from_str(&contents)
+            .map_err(|err| format!("Auth: could not parse the identities file '{}': {}", file_path, err))?;
+        info!("Auth: loaded {} identities from '{}'", identities.len(), file_path);
+        Ok((modified, StaticAuth::new(identities)))
+    }
+
+    /// the current identities -- reloaded if the file changed. If reloading fails, the previous ones are kept
+    fn current(&self) -> Arc<StaticAuth> {
+        let mut identities = self.identities.lock();
+        let modified = std::fs::metadata(&self.file_path).and_then(|metadata| metadata.modified()).ok();
+        if modified.is_some_and(|modified| modified != identities.0) {
+            match Self::load(&self.file_path) {
+                Ok((modified, reloaded)) => *identities = (modified, Arc::new(reloaded)),
+                Err(err) => warn!("{} -- keeping the previous identities", err),
+            }
+        }
+        Arc::clone(&identities.1)
+    }
+}
+
+impl AuthProvider for FileAuth {
+
+    fn verify_api_key<'a>(&'a self, api_key: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async move { Ok(self.current().find_by_api_key(api_key)) })
+    }
+
+    fn verify_password<'a>(&'a self, name: &'a str, password: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async move { self.current().find_by_password(name, password).await })
+    }
+
+    /// tokens are not issued by this provider
+    fn verify_token<'a>(&'a self, _token: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn lookup_roles<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async move { self.current().roles(name) })
+    }
+}
+
+
+/// Access tokens verified by an OpenID Connect provider -- see [AuthConfig::Oidc]
+struct OidcAuth {
+    userinfo_url: String,
+    name_claim:   String,
+    roles_claim:  String,
+    client:       reqwest::Client,
+    /// the provider's recent verdicts, by token
+    verified:     Mutex<HashMap<String, (Instant, Option<Principal>)>>,
+}
+
+impl OidcAuth {
+
+    fn new(userinfo_url: &str, name_claim: &str, roles_claim: &str) -> Self {
+        Self {
+            userinfo_url: userinfo_url.to_string(),
+            name_claim:   name_claim.to_string(),
+            roles_claim:  roles_claim.to_string(),
+            client:       reqwest::Client::new(),
+            verified:     Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// asks the provider's userinfo endpoint who `token` was issued to
+    async fn userinfo(&self, token: &str) -> AuthResult {
+        let response = self.client.get(&self.userinfo_url)
+            .bearer_auth(token)
+            .timeout(OIDC_REQUEST_TIMEOUT)
+            .send().await
+            .map_err(|err| format!("Auth: could not reach the OIDC userinfo endpoint '{}': {}", self.userinfo_url, err))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Ok(None)
+        }
+        if !status.is_success() {
+            return Err(Box::from(format!("Auth: the OIDC userinfo endpoint '{}' answered with {}", self.userinfo_url, status)))
+        }
+        let body = response.text().await
+            .map_err(|err| format!("Auth: could not read the answer of the OIDC userinfo endpoint '{}': {}", self.userinfo_url, err))?;
+        let claims: rocket::serde::json::Value = rocket::serde::json::from_str(&body)
+            .map_err(|err| format!("Auth: the OIDC userinfo endpoint '{}' answered with an invalid JSON: {}", self.userinfo_url, err))?;
+        let Some(name) = claims.get(&self.name_claim).and_then(|name| name.as_str()) else {
+            return Err(Box::from(format!("Auth: the OIDC userinfo endpoint '{}' didn't answer the '{}' claim", self.userinfo_url, self.name_claim)))
+        };
+        let roles = claims.get(&self.roles_claim)
+            .and_then(|roles| roles.as_array())
+            .map_or_else(Vec::new, |roles| roles.iter().filter_map(|role| role.as_str()).map(String::from).collect());
+        Ok(Some(Principal { name: name.to_string(), roles }))
+    }
+}
+
+impl AuthProvider for OidcAuth {
+
+    /// API keys are not issued by this provider
+    fn verify_api_key<'a>(&'a self, _api_key: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// passwords should be given to the OIDC provider only
+    fn verify_password<'a>(&'a self, _name: &'a str, _password: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn verify_token<'a>(&'a self, token: &'a str) -> BoxFuture<'a, AuthResult> {
+        Box::pin(async move {
+            if let Some((verified_at, principal)) = self.verified.lock().get(token) {
+                if verified_at.elapsed() < OIDC_CACHE_TTL {
+                    return Ok(principal.clone())
+                }
+            }
+            let principal = self.userinfo(token).await?;
+            let mut verified = self.verified.lock();
+            verified.retain(|_token, (verified_at, _principal)| verified_at.elapsed() < OIDC_CACHE_TTL);
+            verified.insert(token.to_string(), (Instant::now(), principal.clone()));
+            Ok(principal)
+        })
+    }
+
+    /// roles are only known along with a token
+    fn lookup_roles<'a>(&'a self, _name: &'a str) -> BoxFuture<'a, Vec<String>> {
+        Box::pin(async { vec![] })
+    }
+}
+
+
+/// Unit tests the [auth](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use argon2::{PasswordHasher, password_hash::SaltString};
+
+
+    /// the argon2 hash of `password`, with a random salt -- as expected in [IdentityConfig::password_hash]
+    fn hash_password(password: &str) -> String {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+            .expect("BUG! auth.rs: 16 bytes are a valid salt");
+        Argon2::default().hash_password(password.as_bytes(), &salt)
+            .expect("BUG! auth.rs: the default argon2 params accept passwords of any size")
+            .to_string()
+    }
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn static_identities() {
+        let auth = StaticAuth::new(vec![IdentityConfig {
+            name:            String::from("alice"),
+            password_hash:   hash_password("s3cr3t"),
+            api_keys:        vec![String::from("alice-key")],
+            roles:           vec![String::from(OPERATOR_ROLE)],
+        }]);
+        let alice = Some(Principal { name: String::from("alice"), roles: vec![String::from(OPERATOR_ROLE)] });
+        assert_eq!(auth.verify_api_key("alice-key").await.unwrap(), alice, "API key should have been accepted");
+        assert_eq!(auth.verify_api_key("bob-key").await.unwrap(), None, "Unknown API key should have been rejected");
+        assert_eq!(auth.verify_password("alice", "s3cr3t").await.unwrap(), alice, "Password should have been accepted");
+        assert_eq!(auth.verify_password("alice", "wrong").await.unwrap(), None, "Wrong password should have been rejected");
+        assert_eq!(auth.verify_password("bob", "s3cr3t").await.unwrap(), None, "Unknown identities should have been rejected");
+        assert_eq!(auth.lookup_roles("alice").await, vec![String::from(OPERATOR_ROLE)], "Roles lookup is wrong");
+        assert!(auth.lookup_roles("bob").await.is_empty(), "Unknown identities should have no roles");
+    }
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn session_tokens() {
+        let sessions = Arc::new(SessionTokens::new(Duration::from_secs(60)));
+        let auth = WithSessionTokens::new(Arc::new(StaticAuth::new(vec![])), Arc::clone(&sessions));
+        let alice = Principal { name: String::from("alice"), roles: vec![] };
+        let bob = Principal { name: String::from("bob"), roles: vec![] };
+        let session_token = sessions.issue(&alice);
+        assert_eq!(auth.verify_token(&session_token.token).await.unwrap(), Some(alice.clone()), "Issued tokens should be accepted");
+        assert!(!sessions.revoke(&session_token.token, &bob), "Tokens should only be revoked by their owners");
+        assert!(sessions.revoke(&session_token.token, &alice), "The owner should be able to revoke the token");
+        assert_eq!(auth.verify_token(&session_token.token).await.unwrap(), None, "Revoked tokens should be refused");
+        sessions.issue(&alice);
+        sessions.issue(&alice);
+        assert_eq!(sessions.revoke_all("alice"), 2, "All tokens of an identity should be revoked");
+        let expiring = SessionTokens::new(Duration::ZERO);
+        assert_eq!(expiring.verify(&expiring.issue(&alice).token), None, "Expired tokens should be refused");
+    }
+}
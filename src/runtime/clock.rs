@@ -0,0 +1,76 @@
+//! The time source for the components whose behavior depends on the wall clock -- like the [crate::scheduler::Scheduler].
+//! In production, it is the [SystemClock]; in simulations, a [SimulatedClock] following Tokio's paused time -- so whole days of
+//! scheduled runs may be reproduced, deterministically, in milliseconds. See [super::simulation].\
+//! Both tell the time in the local timezone -- the configured one, after [apply_timezone()]
+
+use std::time::Duration;
+use chrono::{DateTime, Local};
+use futures::future::BoxFuture;
+
+
+/// Tells the time & sleeps -- for the components depending on the wall clock
+pub trait Clock: Send + Sync {
+
+    /// the current wall clock time
+    fn now(&self) -> DateTime<Local>;
+
+    /// sleeps for `duration`
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Makes `timezone` -- see [crate::config::Config::timezone] -- the process' local timezone, in which the logs & reports are timestamped
+/// and the cron expressions are evaluated. `None` keeps the system's.\
+/// Call it before any threads are started: it is done through the `TZ` environment variable, honored by `chrono` & the C library
+pub fn apply_timezone(timezone: Option<&str>) {
+    if let Some(timezone) = timezone {
+        std::env::set_var("TZ", timezone);
+    }
+}
+
+/// The real wall clock -- `chrono`'s & Tokio's timers
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A wall clock starting at a given time & advancing along with Tokio's time -- which, when paused, only moves when told to
+/// (`tokio::time::advance()`) or when all tasks are waiting on timers
+#[cfg(any(test, feature = "dox"))]
+pub struct SimulatedClock {
+    start:         DateTime<Local>,
+    tokio_started: tokio::time::Instant,
+}
+
+#[cfg(any(test, feature = "dox"))]
+impl SimulatedClock {
+
+    /// to be created inside the Tokio runtime whose time it follows
+    pub fn new(start: DateTime<Local>) -> Self {
+        Self {
+            start,
+            tokio_started: tokio::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "dox"))]
+impl Clock for SimulatedClock {
+
+    fn now(&self) -> DateTime<Local> {
+        let elapsed = chrono::Duration::from_std(self.tokio_started.elapsed())
+            .expect("BUG: clock.rs: simulated for longer than `chrono` can tell");
+        self.start + elapsed
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
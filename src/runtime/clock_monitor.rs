@@ -0,0 +1,39 @@
+//! Detects jumps in the system's wall clock (NTP corrections, manual changes, VM suspensions, ...) -- which affect the
+//! lease expirations of [super::LeaderElection], the timestamps in logs & reports and any timeouts based on `SystemTime`.\
+//! (timezones & DST transitions are not jumps: the wall clock is UTC -- see [super::apply_timezone()] for the local time)
+
+use std::time::{Duration, Instant, SystemTime};
+use log::warn;
+
+
+/// How often to compare the wall clock against the monotonic one
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Differences above this are reported as jumps -- below it, they are attributed to scheduling latencies & regular NTP slewing
+const JUMP_THRESHOLD: Duration = Duration::from_secs(2);
+
+
+/// Periodically compares how much the wall clock advanced against the monotonic clock, warning if they disagree.\
+/// Never returns: spawn it as a Tokio task
+pub async fn monitor_clock_jumps() {
+    let mut last_instant = Instant::now();
+    let mut last_system_time = SystemTime::now();
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let (instant, system_time) = (Instant::now(), SystemTime::now());
+        let monotonic_elapsed = instant.duration_since(last_instant);
+        match system_time.duration_since(last_system_time) {
+            Ok(wall_elapsed) if wall_elapsed > monotonic_elapsed + JUMP_THRESHOLD =>
+                warn!("Clock jump detected: the system clock moved FORWARD by {:?} -- keepalives, leases & timeouts may have been affected",
+                      wall_elapsed - monotonic_elapsed),
+            Ok(wall_elapsed) if wall_elapsed + JUMP_THRESHOLD < monotonic_elapsed =>
+                warn!("Clock jump detected: the system clock moved BACKWARDS by {:?} -- keepalives, leases & timeouts may have been affected",
+                      monotonic_elapsed - wall_elapsed),
+            Ok(_) => (),
+            Err(err) =>
+                warn!("Clock jump detected: the system clock moved BACKWARDS by {:?} -- keepalives, leases & timeouts may have been affected",
+                      err.duration() + monotonic_elapsed),
+        }
+        (last_instant, last_system_time) = (instant, system_time);
+    }
+}
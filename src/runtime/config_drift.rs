@@ -0,0 +1,58 @@
+//! Detects "edited but never applied" config files: the effective config is identified by its digest (see
+//! [crate::config::config_ops::digest()]), published in the logs & metrics at startup -- then the config file is periodically
+//! reloaded (& merged with the command line options, like it was at startup) to check if it still yields the same digest.
+//!
+//! Used when the config hot reload is disabled -- otherwise, [super::watch_config_file()] reports the drifts: the changes that were
+//! refused or that may only be applied by restarting.
+
+use crate::config::{Config, config_ops};
+use std::time::Duration;
+use metrics::{gauge, describe_gauge, Unit};
+use log::{info, warn};
+
+
+/// Gauge labeled by the `digest` of the effective config: 1 for the running one -- 0 for the ones replaced by hot reloads
+pub const CONFIG_INFO_METRIC: &str = "config_info";
+/// Gauge: 1 if the config file no longer yields the running config, 0 otherwise
+pub const CONFIG_DRIFT_METRIC: &str = "config_drifted";
+
+/// How often to reload the config file, looking for drifts
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+
+/// registers the descriptions of the config metrics -- to be called once the recorder is installed
+pub fn describe_config_metrics() {
+    describe_gauge!(CONFIG_INFO_METRIC, Unit::Count, "1, labeled by the digest of the effective (merged) config the application is running with -- 0 for the ones replaced by hot reloads");
+    describe_gauge!(CONFIG_DRIFT_METRIC, Unit::Count, "1 if the config file was edited since the application started -- and the changes were not applied");
+}
+
+/// Publishes the `running_digest` -- as computed by [config_ops::digest()] on the effective config -- then periodically
+/// reloads the config file through `load_effective_config()`, warning (once per distinct edition) if its digest no longer matches.\
+/// Never returns: spawn it as a Tokio task
+pub async fn monitor_config_drift(config_file_path: String,
+                                  running_digest:   String,
+                                  load_effective_config: impl Fn() -> Result<Config, Box<dyn std::error::Error>>) {
+    info!("Running with the config digest {} -- as loaded from '{}' & merged with the command line options", running_digest, config_file_path);
+    gauge!(CONFIG_INFO_METRIC, "digest" => running_digest.clone()).set(1.0);
+    gauge!(CONFIG_DRIFT_METRIC).set(0.0);
+    let mut last_reported_digest = running_digest.clone();
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let on_disk_digest = match load_effective_config() {
+            Ok(config) => config_ops::digest(&config),
+            Err(err) => format!("<unloadable: {}>", err),
+        };
+        if on_disk_digest == last_reported_digest {
+            continue
+        }
+        if on_disk_digest == running_digest {
+            info!("Config drift resolved: '{}' yields the running config (digest {}) again", config_file_path, running_digest);
+            gauge!(CONFIG_DRIFT_METRIC).set(0.0);
+        } else {
+            warn!("Config drift detected: '{}' was edited, but the changes were NOT applied -- running with the digest {}, while the file now yields {}. Restart to apply them",
+                  config_file_path, running_digest, on_disk_digest);
+            gauge!(CONFIG_DRIFT_METRIC).set(1.0);
+        }
+        last_reported_digest = on_disk_digest;
+    }
+}
@@ -0,0 +1,353 @@
+//! Config hot reload -- see [crate::config::Config::hot_reload]: the config file's modification time is polled & the file is reloaded
+//! (& merged with the command line options, like it was at startup) whenever it changes. Valid changes are published to the subscribers of
+//! [ConfigReload::subscribe()], which apply the settings they may change while running -- see `main.rs`.
+//!
+//! Reloads may also be requested on demand -- see [ConfigReload::request_reload()], used by the admin API.\
+//! The hot reloadable settings may also be edited through the admin API -- see [ConfigReload::edit()]: edits are validated, persisted
+//! to the config file & then reloaded, leaving an audit log entry (under the [AUDIT_LOG_TARGET]) for each change.
+//!
+//! Only some settings are hot reloadable (see [hot_reloadable_changes_only()]): changes to the others are logged as requiring a restart
+//! -- and are reported as a drift (see [super::monitor_config_drift()]) until then. Invalid files are refused, keeping the running config.
+
+use super::config_drift::{CONFIG_INFO_METRIC, CONFIG_DRIFT_METRIC};
+use crate::config::{Config, ExtendedOption, config_ops, config_validation};
+use std::{
+    sync::Arc,
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use sloggers::types::Severity;
+use metrics::gauge;
+use tokio::sync::{watch, Notify};
+use log::{info, warn};
+
+
+/// The log target of the entries telling who changed what in the config -- see [ConfigReload::edit()]
+pub const AUDIT_LOG_TARGET: &str = "audit";
+
+/// The fields of [ConfigEdit] -- for the admin page to render its form
+pub const EDITABLE_FIELDS: &[EditableField] = &[
+    EditableField { name: "log_filters",                          kind: "map of target to level",  description: "The log level of each target -- \"*\" for all the others" },
+    EditableField { name: "telegram_notification_chat_ids",       kind: "map of bot name to chat ids", description: "The chats each Telegram bot sends the notifications to" },
+    EditableField { name: "socket_server_max_message_length",     kind: "integer",                 description: "Longer messages get their socket clients disconnected & banned" },
+    EditableField { name: "socket_server_max_malformed_messages", kind: "integer",                 description: "Socket clients sending this many malformed messages are disconnected & banned" },
+    EditableField { name: "socket_server_ban_seconds",            kind: "integer",                 description: "For how long abusive socket clients are banned" },
+];
+
+
+/// Describes one of the [EDITABLE_FIELDS]
+#[derive(Debug, Serialize)]
+pub struct EditableField {
+    pub name:        &'static str,
+    pub kind:        &'static str,
+    pub description: &'static str,
+}
+
+/// The settings editable while running -- the hot reloadable ones. Absent fields are left as they are
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigEdit {
+    /// see [Config::log_filters]
+    pub log_filters:                          Option<BTreeMap<String, Severity>>,
+    /// the `notification_chat_ids` of the Telegram bots, by bot name -- see [crate::config::TelegramConfig::notification_chat_ids]
+    pub telegram_notification_chat_ids:       Option<BTreeMap<String, Vec<i64>>>,
+    /// see [crate::config::SocketServerConfig::max_message_length]
+    pub socket_server_max_message_length:     Option<usize>,
+    /// see [crate::config::SocketServerConfig::max_malformed_messages]
+    pub socket_server_max_malformed_messages: Option<u32>,
+    /// see [crate::config::SocketServerConfig::ban_seconds]
+    pub socket_server_ban_seconds:            Option<u64>,
+}
+
+impl ConfigEdit {
+
+    /// the current values of the editable settings of `config` -- the ones of disabled services are absent
+    pub fn from_config(config: &Config) -> Self {
+        let services = match &config.services {
+            ExtendedOption::Enabled(services) => Some(services),
+            _ => None,
+        };
+        let telegram_bots = services.and_then(|services| match &services.telegram {
+            ExtendedOption::Enabled(telegram_bots) => Some(telegram_bots),
+            _ => None,
+        });
+        let socket_server = services.and_then(|services| match &services.socket_server {
+            ExtendedOption::Enabled(socket_server) => Some(socket_server),
+            _ => None,
+        });
+        Self {
+            log_filters:                          Some(config.log_filters.clone()),
+            telegram_notification_chat_ids:       telegram_bots.map(|telegram_bots| telegram_bots.iter()
+                                                                       .map(|telegram| (telegram.name.clone(), telegram.notification_chat_ids.clone()))
+                                                                       .collect()),
+            socket_server_max_message_length:     socket_server.map(|socket_server| socket_server.max_message_length),
+            socket_server_max_malformed_messages: socket_server.map(|socket_server| socket_server.max_malformed_messages),
+            socket_server_ban_seconds:            socket_server.map(|socket_server| socket_server.ban_seconds),
+        }
+    }
+
+    /// applies the present fields to `config` -- returning a description of each change, for the audit log.
+    /// Fails if a field refers to a disabled service or to an unknown Telegram bot
+    fn apply(&self, config: &mut Config) -> Result<Vec<String>, String> {
+        let mut changes = Vec::new();
+        fn change<T: PartialEq + std::fmt::Debug>(changes: &mut Vec<String>, name: &str, current: &mut T, edited: &Option<T>) where T: Clone {
+            if let Some(edited) = edited {
+                if current != edited {
+                    changes.push(format!("{}: {:?} => {:?}", name, current, edited));
+                    *current = edited.clone();
+                }
+            }
+        }
+        change(&mut changes, "log_filters", &mut config.log_filters, &self.log_filters);
+        let services = match &mut config.services {
+            ExtendedOption::Enabled(services) => Some(services),
+            _ => None,
+        };
+        let (mut telegram_bots, mut socket_server) = match services {
+            Some(services) => (match &mut services.telegram {
+                                   ExtendedOption::Enabled(telegram_bots) => Some(telegram_bots),
+                                   _ => None,
+                               },
+                               match &mut services.socket_server {
+                                   ExtendedOption::Enabled(socket_server) => Some(socket_server),
+                                   _ => None,
+                               }),
+            None => (None, None),
+        };
+        if let Some(telegram_notification_chat_ids) = &self.telegram_notification_chat_ids {
+            let telegram_bots = telegram_bots.as_mut().ok_or("`telegram_notification_chat_ids` was given, but Telegram is disabled")?;
+            for (name, notification_chat_ids) in telegram_notification_chat_ids {
+                let telegram = telegram_bots.iter_mut().find(|telegram| &telegram.name == name)
+                    .ok_or_else(|| format!("`telegram_notification_chat_ids` refers to the unknown Telegram bot '{}'", name))?;
+                change(&mut changes, &format!("telegram[\"{}\"].notification_chat_ids", name), &mut telegram.notification_chat_ids, &Some(notification_chat_ids.clone()));
+            }
+        }
+        if self.socket_server_max_message_length.is_some() || self.socket_server_max_malformed_messages.is_some() || self.socket_server_ban_seconds.is_some() {
+            let socket_server = socket_server.as_mut().ok_or("socket server settings were given, but the socket server is disabled")?;
+            change(&mut changes, "socket_server.max_message_length",     &mut socket_server.max_message_length,     &self.socket_server_max_message_length);
+            change(&mut changes, "socket_server.max_malformed_messages", &mut socket_server.max_malformed_messages, &self.socket_server_max_malformed_messages);
+            change(&mut changes, "socket_server.ban_seconds",            &mut socket_server.ban_seconds,            &self.socket_server_ban_seconds);
+        }
+        Ok(changes)
+    }
+}
+
+
+/// Holds the running config -- replaced on each valid reload -- & notifies its subscribers
+pub struct ConfigReload {
+    config:           watch::Sender<Arc<Config>>,
+    /// wakes [watch_config_file()] up before its next poll -- see [Self::request_reload()]
+    reload_requested: Notify,
+    /// where [Self::edit()] persists the edits
+    config_file_path: String,
+    /// serializes the edits of the config file
+    editing:          Mutex<()>,
+}
+
+impl ConfigReload {
+
+    pub fn new(config: Arc<Config>, config_file_path: &str) -> Self {
+        Self {
+            config:           watch::channel(config).0,
+            reload_requested: Notify::new(),
+            config_file_path: config_file_path.to_string(),
+            editing:          Mutex::new(()),
+        }
+    }
+
+    /// the running config -- as loaded at startup or by the latest reload
+    pub fn current(&self) -> Arc<Config> {
+        Arc::clone(&self.config.borrow())
+    }
+
+    /// Returns a receiver whose `changed()` resolves on each applied reload. Usage:
+    /// ```no_compile
+    ///     let mut config_updates = config_reload.subscribe();
+    ///     while config_updates.changed().await.is_ok() {
+    ///         let config = Arc::clone(&config_updates.borrow_and_update());
+    ///         your_logic_component.apply(&config.your_logic_config);
+    ///     }
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Config>> {
+        self.config.subscribe()
+    }
+
+    /// asks [watch_config_file()] to reload the config file right away -- even if it wasn't modified. The outcome is logged, as usual
+    pub fn request_reload(&self) {
+        self.reload_requested.notify_one();
+    }
+
+    /// Applies `edit` to the config file -- validating the result before saving it -- then reloads it, logging an audit entry
+    /// attributing each change to `editor`. Returns the changes: none if the file already had the edited values.\
+    /// Files with `${ENV_VAR}` placeholders are refused, as the placeholders would be replaced by their values when saving
+    pub fn edit(&self, edit: &ConfigEdit, editor: &str) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let _editing = self.editing.lock();
+        let contents = std::fs::read_to_string(&self.config_file_path)
+            .map_err(|err| format!("could not read the config file '{}': {}", self.config_file_path, err))?;
+        if contents.contains("${") {
+            return Err(Box::from(format!("the config file '{}' has `${{ENV_VAR}}` placeholders, which would be lost by saving it -- edit it by hand", self.config_file_path)))
+        }
+        let mut file_config = config_ops::load_from_file(&self.config_file_path)
+            .map_err(|err| err.to_string())?;
+        let changes = edit.apply(&mut file_config)?;
+        if changes.is_empty() {
+            return Ok(changes)
+        }
+        validate(&file_config)?;
+        config_ops::save_to_file(&file_config, &self.config_file_path)
+            .map_err(|err| err.to_string())?;
+        for change in &changes {
+            warn!(target: AUDIT_LOG_TARGET, "Config edited by {}: {}", editor, change);
+        }
+        self.request_reload();
+        Ok(changes)
+    }
+
+    /// validates the `reloaded` config & publishes the changes that may be applied -- returning `true` if some of them may not
+    /// (so the running config drifted from the file)
+    fn reload(&self, reloaded: Config) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        validate(&reloaded)?;
+        let running = self.current();
+        let applicable = hot_reloadable_changes_only(&running, &reloaded);
+        let drifted = applicable != reloaded;
+        if drifted {
+            warn!("Config hot reload: some of the changes may only be applied by restarting the application (only the log filters, Telegram's notification chat ids & the socket server's abuse limits are hot reloadable)");
+        }
+        if applicable == *running {
+            return Ok(drifted)
+        }
+        let digest = config_ops::digest(&applicable);
+        info!("Config hot reload: applying the new settings -- the running config digest is now {}", digest);
+        gauge!(CONFIG_INFO_METRIC, "digest" => config_ops::digest(&running)).set(0.0);
+        gauge!(CONFIG_INFO_METRIC, "digest" => digest).set(1.0);
+        self.config.send_replace(Arc::new(applicable));
+        Ok(drifted)
+    }
+}
+
+/// Watches the config file at `config_file_path` -- reloading it through `load_effective_config()` once it changes -- at the interval
+/// given by [crate::config::Config::hot_reload]. Never returns: spawn it as a Tokio task
+pub async fn watch_config_file(config_reload:         Arc<ConfigReload>,
+                               config_file_path:      String,
+                               load_effective_config: impl Fn() -> Result<Config, Box<dyn std::error::Error>>) {
+    let poll_interval = match &config_reload.current().hot_reload {
+        ExtendedOption::Enabled(hot_reload) => Duration::from_millis(hot_reload.poll_interval_millis),
+        _ => return,
+    };
+    let running_digest = config_ops::digest(&config_reload.current());
+    info!("Running with the config digest {} -- as loaded from '{}' & merged with the command line options. Watching it for changes every {:?}",
+          running_digest, config_file_path, poll_interval);
+    gauge!(CONFIG_INFO_METRIC, "digest" => running_digest).set(1.0);
+    gauge!(CONFIG_DRIFT_METRIC).set(0.0);
+    let modified = || std::fs::metadata(&config_file_path).and_then(|metadata| metadata.modified()).ok();
+    let mut last_modified: Option<SystemTime> = modified();
+    loop {
+        let requested = tokio::select! {
+            () = tokio::time::sleep(poll_interval) => false,
+            () = config_reload.reload_requested.notified() => true,
+        };
+        let current_modified = modified();
+        if current_modified == last_modified && !requested {
+            continue
+        }
+        last_modified = current_modified;
+        let result = load_effective_config()
+            .map_err(|err| Box::from(format!("could not load it: {}", err)))
+            .and_then(|reloaded| config_reload.reload(reloaded));
+        match result {
+            Ok(drifted) => gauge!(CONFIG_DRIFT_METRIC).set(if drifted { 1.0 } else { 0.0 }),
+            Err(err) => {
+                warn!("Config hot reload: '{}' was edited, but its changes were refused -- keeping the running config: {}", config_file_path, err);
+                gauge!(CONFIG_DRIFT_METRIC).set(1.0);
+            },
+        }
+    }
+}
+
+/// the `running` config, updated with the hot reloadable settings of `reloaded`
+fn hot_reloadable_changes_only(running: &Config, reloaded: &Config) -> Config {
+    let mut applicable = running.clone();
+    applicable.log_filters = reloaded.log_filters.clone();
+    if let (ExtendedOption::Enabled(applicable_services), ExtendedOption::Enabled(reloaded_services)) = (&mut applicable.services, &reloaded.services) {
+        if let (ExtendedOption::Enabled(applicable_telegram_bots), ExtendedOption::Enabled(reloaded_telegram_bots)) = (&mut applicable_services.telegram, &reloaded_services.telegram) {
+            // bots are matched by name: added & removed ones are only applied by restarting
+            for applicable_telegram in applicable_telegram_bots.iter_mut() {
+                if let Some(reloaded_telegram) = reloaded_telegram_bots.iter().find(|reloaded_telegram| reloaded_telegram.name == applicable_telegram.name) {
+                    applicable_telegram.notification_chat_ids = reloaded_telegram.notification_chat_ids.clone();
+                }
+            }
+        }
+        if let (ExtendedOption::Enabled(applicable_socket_server), ExtendedOption::Enabled(reloaded_socket_server)) = (&mut applicable_services.socket_server, &reloaded_services.socket_server) {
+            applicable_socket_server.max_message_length     = reloaded_socket_server.max_message_length;
+            applicable_socket_server.max_malformed_messages = reloaded_socket_server.max_malformed_messages;
+            applicable_socket_server.ban_seconds            = reloaded_socket_server.ban_seconds;
+            applicable_socket_server.ingress_limit          = reloaded_socket_server.ingress_limit.clone();
+        }
+    }
+    applicable
+}
+
+/// checks `config` as it was checked at startup (see [config_validation]), then its hot reloadable settings
+fn validate(config: &Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    config_validation::validate(config)
+        .map_err(|problems| problems.iter().map(|problem| problem.to_string()).collect::<Vec<_>>().join("; "))?;
+    if let ExtendedOption::Enabled(services) = &config.services {
+        if let ExtendedOption::Enabled(socket_server) = &services.socket_server {
+            if socket_server.max_message_length == 0 {
+                return Err(Box::from("the socket server's `max_message_length` may not be 0"))
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/// Unit tests the [config_reload](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn only_hot_reloadable_changes_are_applied() {
+        let mut running = Config::default();
+        // the default one is a placeholder -- see [config_validation]
+        running.services.telegram[0].token = String::from("123456789:AAEhBP0av28uw6mhkB5-RtXmEyIMX3tTaEU");
+        let running = Arc::new(running);
+        let config_reload = ConfigReload::new(Arc::clone(&running), "unused.config.ron");
+        let mut config_updates = config_reload.subscribe();
+
+        let mut reloaded = (*running).clone();
+        reloaded.log_filters.insert(String::from("rocket"), sloggers::types::Severity::Warning);
+        reloaded.tokio_threads += 1;
+        let drifted = config_reload.reload(reloaded).expect("The reloaded config should have been valid");
+        assert!(drifted, "Changing the number of Tokio threads should be reported as a drift");
+        assert!(config_updates.has_changed().unwrap(), "Subscribers should have been notified");
+        let applied = config_updates.borrow_and_update().clone();
+        assert_eq!(applied.log_filters.len(), 1, "The log filters are hot reloadable");
+        assert_eq!(applied.tokio_threads, running.tokio_threads, "The number of Tokio threads may only be changed by restarting");
+
+        let mut unchanged = (*applied).clone();
+        unchanged.tokio_threads += 1;
+        config_reload.reload(unchanged).expect("The reloaded config should have been valid");
+        assert!(!config_updates.has_changed().unwrap(), "Subscribers shouldn't be notified when nothing may be applied");
+    }
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn edits_are_applied_and_described() {
+        let mut config = Config::default();
+        let edit = ConfigEdit {
+            log_filters: Some(BTreeMap::from([(String::from("rocket"), Severity::Warning)])),
+            ..ConfigEdit::default()
+        };
+        let changes = edit.apply(&mut config).expect("The edit should have been applied");
+        assert_eq!(changes.len(), 1, "Only the log filters were changed");
+        assert_eq!(ConfigEdit::from_config(&config).log_filters, edit.log_filters, "The edited value should be in the config");
+        assert!(edit.apply(&mut config).unwrap().is_empty(), "Applying the same edit twice should change nothing");
+        let unknown_bot = ConfigEdit {
+            telegram_notification_chat_ids: Some(BTreeMap::from([(String::from("unknown-bot"), vec![1])])),
+            ..ConfigEdit::default()
+        };
+        assert!(unknown_bot.apply(&mut config).is_err(), "Edits referring to unknown bots should be refused");
+    }
+}
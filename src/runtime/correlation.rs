@@ -0,0 +1,72 @@
+//! Correlation ids: tie together everything a single user action causes across the subsystems. The frontends start them -- for each
+//! Telegram command & each web request or inbound hook that reaches the business logic -- & run what follows [correlated()] with them:
+//! ```no_compile
+//!     correlated(Some(new_correlation_id()), async move { logic::process(request).await }).await
+//! ```
+//! While in scope, the id is:
+//!   * appended to every log line -- see [crate::logging]
+//!   * carried by the events published to the [super::EventBus] -- so subscribers may handle them in the same scope
+//!   * persisted along with the [super::Outbox] intents & sent to the webhook targets in the [CORRELATION_ID_HEADER]
+
+use std::future::Future;
+
+
+/// The HTTP header carrying correlation ids -- accepted from web clients & sent to webhook targets
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+/// Ids taken from outside (like from the [CORRELATION_ID_HEADER] of web requests) longer than this are replaced by new ones
+const MAX_CORRELATION_ID_LENGTH: usize = 64;
+
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// a new, random, correlation id -- 16 hex digits
+pub fn new_correlation_id() -> String {
+    hex::encode(rand::random::<[u8; 8]>())
+}
+
+/// `correlation_id`, if it came from outside & is reasonable to be logged & sent along -- otherwise, a new one
+pub fn sanitized_correlation_id(correlation_id: Option<&str>) -> String {
+    correlation_id
+        .filter(|correlation_id| !correlation_id.is_empty() && correlation_id.len() <= MAX_CORRELATION_ID_LENGTH)
+        .filter(|correlation_id| correlation_id.chars().all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_'))
+        .map(String::from)
+        .unwrap_or_else(new_correlation_id)
+}
+
+/// the correlation id in scope -- if the caller runs inside a [correlated()] future
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|correlation_id| correlation_id.clone()).ok()
+}
+
+/// runs `future` with `correlation_id` in scope -- or, if it is `None`, as is
+pub async fn correlated<F: Future>(correlation_id: Option<String>, future: F) -> F::Output {
+    match correlation_id {
+        Some(correlation_id) => CORRELATION_ID.scope(correlation_id, future).await,
+        None => future.await,
+    }
+}
+
+
+/// Unit tests the [correlation](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn scopes() {
+        assert_eq!(correlation_id(), None, "No correlation id should be in scope outside of `correlated()`");
+        let in_scope = correlated(Some(String::from("abc")), async {
+            tokio::task::yield_now().await;
+            correlation_id()
+        }).await;
+        assert_eq!(in_scope, Some(String::from("abc")), "The correlation id should be in scope across awaits");
+        assert_eq!(correlated(None, async { correlation_id() }).await, None, "`None` should put nothing in scope");
+        assert_eq!(sanitized_correlation_id(Some("order-42_retry")), "order-42_retry", "Sane ids should be kept");
+        assert_ne!(sanitized_correlation_id(Some("evil\nlog line")), "evil\nlog line", "Ids that could forge log lines should be replaced");
+        assert_eq!(sanitized_correlation_id(None).len(), 16, "New ids should have 16 hex digits");
+    }
+}
@@ -0,0 +1,95 @@
+//! Background mode -- see the `--daemonize` command line flag & [crate::config::Config::daemon]: `console daemon` forks into the
+//! background, detached from the terminal, with its PID written (& locked) in the configured PID file -- so other instances refuse to start.\
+//! The daemon's stdin is `/dev/null` & its stdout & stderr go to the log file (if logging to a file) or to `/dev/null`, otherwise.
+//! Its working directory is kept, so the relative paths in the config (log files, `Rocket.toml`, ...) still work.\
+//! Unix only: on other platforms, `--daemonize` is an error.
+
+use crate::config::{Config, DaemonConfig, ExtendedOption, Jobs, UiOptions};
+use std::fs::File;
+
+
+/// The PID file of the daemon -- locked while this is kept & removed when dropped
+pub struct PidFile {
+    path:  String,
+    _file: File,
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// forks the application into the background, returning only in the daemon -- the starting process exits as soon as it is forked.\
+/// Must be called before any threads are started (Tokio's & the loggers', included) -- so errors are reported to the terminal & the
+/// forked process is sound. Fails if `config` is not for `console daemon`, if [Config::daemon] is disabled or if another instance
+/// holds the PID file. The returned [PidFile] must be kept until the application ends
+pub fn daemonize(config: &Config) -> Result<PidFile, Box<dyn std::error::Error + Send + Sync>> {
+    if !matches!(&config.ui, ExtendedOption::Enabled(UiOptions::Console(Jobs::Daemon))) {
+        return Err(Box::from("`--daemonize` is only supported by the `console daemon` job"))
+    }
+    let ExtendedOption::Enabled(daemon_config) = &config.daemon else {
+        return Err(Box::from("`--daemonize` requires `daemon` to be enabled in the config file -- for the PID file"))
+    };
+    fork_into_background(daemon_config, &config.log)
+}
+
+#[cfg(unix)]
+fn fork_into_background(daemon_config: &DaemonConfig, log: &crate::config::LoggingOptions) -> Result<PidFile, Box<dyn std::error::Error + Send + Sync>> {
+    use crate::config::LoggingOptions;
+    use nix::{
+        unistd::{self, ForkResult},
+        fcntl::{flock, FlockArg},
+    };
+    use std::{
+        fs::OpenOptions,
+        io::{Read, Seek, SeekFrom, Write},
+        os::unix::io::AsRawFd,
+    };
+
+    let path = &daemon_config.pid_file;
+    let mut pid_file = OpenOptions::new().read(true).write(true).create(true).open(path)
+        .map_err(|err| format!("Could not open the PID file '{}': {}", path, err))?;
+    // the lock is shared with the forked processes -- being held until the daemon ends
+    if let Err(err) = flock(pid_file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+        let mut running_pid = String::new();
+        let _ = pid_file.read_to_string(&mut running_pid);
+        return Err(Box::from(format!("Another instance (pid {}) holds the PID file '{}' -- refusing to start: {}", running_pid.trim(), path, err)))
+    }
+    // opened before forking, so errors are still seen in the terminal
+    let stdin = File::open("/dev/null")
+        .map_err(|err| format!("Could not open /dev/null for the daemon's stdin: {}", err))?;
+    let output_path = match log {
+        LoggingOptions::ToFile { file_path, .. } => file_path.as_str(),
+        _ => "/dev/null",
+    };
+    let output = OpenOptions::new().create(true).append(true).open(output_path)
+        .map_err(|err| format!("Could not open '{}' for the daemon's stdout & stderr: {}", output_path, err))?;
+
+    // double fork: the starting process exits, returning to the shell; the first child leads a new session (detaching from the terminal)
+    // & the daemon -- not being a session leader -- may never acquire a controlling terminal again
+    let fork = || match unsafe { unistd::fork() } {
+        Ok(ForkResult::Parent { .. }) => std::process::exit(0),
+        Ok(ForkResult::Child) => Ok(()),
+        Err(err) => Err(format!("Could not fork into the background: {}", err)),
+    };
+    fork()?;
+    unistd::setsid()
+        .map_err(|err| format!("Could not detach from the terminal: {}", err))?;
+    fork()?;
+
+    pid_file.set_len(0)
+        .and_then(|()| pid_file.seek(SeekFrom::Start(0)))
+        .and_then(|_| writeln!(pid_file, "{}", std::process::id()))
+        .map_err(|err| format!("Could not write the daemon's PID to '{}': {}", path, err))?;
+    for (file, fd) in [(&stdin, 0), (&output, 1), (&output, 2)] {
+        unistd::dup2(file.as_raw_fd(), fd)
+            .map_err(|err| format!("Could not redirect the daemon's standard file descriptor #{}: {}", fd, err))?;
+    }
+    Ok(PidFile { path: path.clone(), _file: pid_file })
+}
+
+#[cfg(not(unix))]
+fn fork_into_background(_daemon_config: &DaemonConfig, _log: &crate::config::LoggingOptions) -> Result<PidFile, Box<dyn std::error::Error + Send + Sync>> {
+    Err(Box::from("`--daemonize` is only supported on Unix -- run `console daemon` through your platform's service manager instead"))
+}
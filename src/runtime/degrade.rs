@@ -0,0 +1,114 @@
+//! Graceful degradation switches -- see [crate::config::Config::degrade].
+//!
+//! Operators toggle them, by their [SWITCHES] names, through the admin API (`PUT /api/admin/switches/<name>`), its command line client
+//! (`console switch <name> on|off`), the REPL & the UIs -- and the maintenance mode through Telegram as well.\
+//! While in maintenance mode, the scheduler skips the jobs not marked as [crate::config::ScheduledJobConfig::critical].
+
+use crate::config::DegradeConfig;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Serialize, Deserialize};
+use log::warn;
+
+
+/// The names of the switches, as given to [DegradeSwitches::set()]
+pub const SWITCHES: &[&str] = &["maintenance", "read-only", "telegram-notifications"];
+
+
+/// Runtime-togglable switches -- initialized from [DegradeConfig] -- through which operators may shed load
+/// or silence integrations during incidents, without redeploys. Each frontend checks the switches relevant to it
+pub struct DegradeSwitches {
+    disable_telegram_notifications: AtomicBool,
+    read_only_api:                  AtomicBool,
+    maintenance:                    AtomicBool,
+    maintenance_message:            String,
+}
+
+/// Used when [DegradeConfig::maintenance_message] is empty
+const DEFAULT_MAINTENANCE_MESSAGE: &str = "Under maintenance";
+
+/// The state of the switches, by their [SWITCHES] names -- as answered by `GET /api/admin/switches`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SwitchesReport {
+    pub maintenance:            bool,
+    pub read_only:              bool,
+    /// `false` while they are disabled -- see [DegradeSwitches::disable_telegram_notifications()]
+    pub telegram_notifications: bool,
+}
+
+impl DegradeSwitches {
+
+    pub fn new(degrade_config: &DegradeConfig) -> Self {
+        let instance = Self {
+            disable_telegram_notifications: AtomicBool::new(false),
+            read_only_api:                  AtomicBool::new(false),
+            maintenance:                    AtomicBool::new(false),
+            maintenance_message:            if degrade_config.maintenance_message.is_empty() {
+                                                String::from(DEFAULT_MAINTENANCE_MESSAGE)
+                                            } else {
+                                                degrade_config.maintenance_message.clone()
+                                            },
+        };
+        instance.set_disable_telegram_notifications(degrade_config.disable_telegram_notifications);
+        instance.set_read_only_api(degrade_config.read_only_api);
+        instance.set_maintenance(degrade_config.maintenance);
+        instance
+    }
+
+    /// if set, Telegram push notifications are discarded
+    pub fn disable_telegram_notifications(&self) -> bool {
+        self.disable_telegram_notifications.load(Ordering::Relaxed)
+    }
+
+    pub fn set_disable_telegram_notifications(&self, disable: bool) {
+        if self.disable_telegram_notifications.swap(disable, Ordering::Relaxed) != disable {
+            warn!("Degradation switch: Telegram notifications are now {}", if disable {"DISABLED"} else {"enabled"});
+        }
+    }
+
+    /// if set, the web API refuses requests that would change the application's state
+    pub fn read_only_api(&self) -> bool {
+        self.read_only_api.load(Ordering::Relaxed)
+    }
+
+    pub fn set_read_only_api(&self, read_only: bool) {
+        if self.read_only_api.swap(read_only, Ordering::Relaxed) != read_only {
+            warn!("Degradation switch: the web API is now {}", if read_only {"READ-ONLY"} else {"read-write"});
+        }
+    }
+
+    /// if set, all frontends refuse to serve users, answering [Self::maintenance_message()] instead -- but for the monitoring routes
+    pub fn maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    pub fn set_maintenance(&self, maintenance: bool) {
+        if self.maintenance.swap(maintenance, Ordering::Relaxed) != maintenance {
+            warn!("Degradation switch: maintenance mode is now {}", if maintenance {"ON"} else {"off"});
+        }
+    }
+
+    /// what users are told while in maintenance mode
+    pub fn maintenance_message(&self) -> &str {
+        &self.maintenance_message
+    }
+
+    /// turns `on` (or off) the switch `name` -- one of [SWITCHES]. Tells if there is such a switch
+    pub fn set(&self, name: &str, on: bool) -> bool {
+        match name {
+            "maintenance"            => self.set_maintenance(on),
+            "read-only"              => self.set_read_only_api(on),
+            "telegram-notifications" => self.set_disable_telegram_notifications(!on),
+            _unknown => return false,
+        }
+        true
+    }
+
+    pub fn report(&self) -> SwitchesReport {
+        SwitchesReport {
+            maintenance:            self.maintenance(),
+            read_only:              self.read_only_api(),
+            telegram_notifications: !self.disable_telegram_notifications(),
+        }
+    }
+}
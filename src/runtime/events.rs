@@ -0,0 +1,248 @@
+//! The internal event bus: components -- processors, the business logic, the frontends -- publish typed events to named [Topic]s,
+//! each of them fanned-out to all subscribers:
+//! ```no_compile
+//!     let orders: Topic<OrderCreated> = runtime.read().await.events.topic("order.created")?;
+//!     let mut subscription = orders.subscribe();
+//!     orders.publish(OrderCreated { id: 42 });
+//!     while let Ok(Correlated { correlation_id, event: order_created }) = subscription.recv().await {
+//!         correlated(correlation_id, async { ... }).await
+//!     }
+//! ```
+//! Events carry the correlation id in scope when they were published -- see [super::correlation] -- so subscribers may keep it going.\
+//! Channels are bounded: subscribers lagging more than [TOPIC_CAPACITY] events behind miss the oldest ones -- told by
+//! `RecvError::Lagged`, so they may resync.\
+//! Events are also rendered as JSON [PublishedEvent]s -- numbered in publishing order -- for the followers of the whole bus
+//! (see [EventBus::follow()]), like the `/api/events` routes & the webhooks. Rendering only happens while someone follows
+//! or while the most recent events are kept -- see [EventBus::keep_recent()].
+
+use super::correlation_id;
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, AtomicU64, Ordering},
+    },
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use metrics::{counter, describe_counter, Unit};
+use log::warn;
+
+
+/// How many events may be waiting for slow subscribers of each topic -- beyond that, they will miss the oldest ones
+pub const TOPIC_CAPACITY: usize = 1024;
+
+/// How many [PublishedEvent]s may be waiting for slow followers of the whole bus -- beyond that, they will miss the oldest ones
+const FOLLOWERS_BUFFER: usize = 1024;
+
+/// How many of the most recent events are kept, when consuming them as a queue is enabled -- see [EventBus::keep_recent()]
+pub const RECENT_EVENTS: usize = 4096;
+
+/// Counter of the events published, labeled by `topic`
+pub const PUBLISHED_METRIC: &str = "event_bus_published_total";
+
+
+/// registers the descriptions of the event bus' metrics -- to be called once the recorder is installed
+pub fn describe_event_bus_metrics() {
+    describe_counter!(PUBLISHED_METRIC, Unit::Count, "Events published to the internal event bus, by topic");
+}
+
+
+/// An event, as sent to the followers of the whole bus -- see [EventBus::follow()]
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishedEvent {
+    /// increases by 1 for each event published to any topic -- consumers may resume after the last one they saw. See [EventBus::follow_after()]
+    pub seq:            u64,
+    pub topic:          &'static str,
+    pub timestamp:      String,
+    /// the correlation id in scope when the event was published -- see [super::correlation]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    pub payload:        serde_json::Value,
+}
+
+/// An event, as received by the subscribers of a [Topic] -- along with the correlation id in scope when it was published, if any.
+/// Handle it inside [super::correlated()] to keep the correlation going
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correlated<EventType> {
+    pub correlation_id: Option<String>,
+    pub event:          EventType,
+}
+
+/// The application's event bus -- see [super]
+pub struct EventBus {
+    /// the typed senders -- `broadcast::Sender<Correlated<EventType>>` -- by topic name, along with their event types
+    topics:          Mutex<HashMap<&'static str, (TypeId, Box<dyn Any + Send + Sync>)>>,
+    followers:       broadcast::Sender<PublishedEvent>,
+    next_seq:        AtomicU64,
+    /// the ring buffer of the most recent events -- also held while publishing them, so followers resuming from a given event neither
+    /// miss nor repeat events between the ones they catch up with & the ones they follow
+    recent:          Mutex<VecDeque<PublishedEvent>>,
+    /// the capacity of [Self::recent] -- 0 if the recent events are not to be kept
+    recent_capacity: AtomicUsize,
+}
+
+impl EventBus {
+
+    pub fn new() -> Self {
+        Self {
+            topics:          Mutex::new(HashMap::new()),
+            followers:       broadcast::channel(FOLLOWERS_BUFFER).0,
+            next_seq:        AtomicU64::new(1),
+            recent:          Mutex::new(VecDeque::new()),
+            recent_capacity: AtomicUsize::new(0),
+        }
+    }
+
+    /// the topic `name`, carrying events of type `EventType` -- created on the first call. Fails if the topic was already
+    /// created for another event type
+    pub fn topic<EventType>(self: &Arc<Self>, name: &'static str) -> Result<Topic<EventType>, Box<dyn std::error::Error + Send + Sync>>
+                           where EventType: Serialize + Clone + Send + Sync + 'static {
+        let mut topics = self.topics.lock();
+        let (type_id, sender) = topics.entry(name)
+            .or_insert_with(|| (TypeId::of::<EventType>(), Box::new(broadcast::channel::<Correlated<EventType>>(TOPIC_CAPACITY).0)));
+        let sender = sender.downcast_ref::<broadcast::Sender<Correlated<EventType>>>()
+            .filter(|_| *type_id == TypeId::of::<EventType>())
+            .ok_or_else(|| format!("Event Bus: topic '{}' already carries events of another type than `{}`", name, std::any::type_name::<EventType>()))?;
+        Ok(Topic {
+            name,
+            sender: sender.clone(),
+            bus:    Arc::clone(self),
+        })
+    }
+
+    /// keeps up to `capacity` of the most recent events (0 to keep none) -- for consumers to resume from. See [Self::follow_after()]
+    pub fn keep_recent(&self, capacity: usize) {
+        self.recent_capacity.store(capacity, Ordering::Relaxed);
+        let mut recent = self.recent.lock();
+        while recent.len() > capacity {
+            recent.pop_front();
+        }
+    }
+
+    /// Follows all events published from now on, in all topics
+    pub fn follow(&self) -> broadcast::Receiver<PublishedEvent> {
+        self.followers.subscribe()
+    }
+
+    /// Follows all events published from now on -- returning, as well, the kept ones published after the event `seq` (the oldest first)
+    /// and how many of those were missed, for not being kept anymore. See [Self::keep_recent()]
+    pub fn follow_after(&self, seq: u64) -> (Vec<PublishedEvent>, u64, broadcast::Receiver<PublishedEvent>) {
+        let recent = self.recent.lock();
+        let receiver = self.followers.subscribe();
+        let next_seq = recent.back().map_or_else(|| self.next_seq.load(Ordering::Relaxed), |newest| newest.seq + 1);
+        let oldest_kept_seq = recent.front().map_or(next_seq, |oldest| oldest.seq);
+        let missed = oldest_kept_seq.saturating_sub(seq.saturating_add(1));
+        let events = recent.iter()
+            .filter(|event| event.seq > seq)
+            .cloned()
+            .collect();
+        (events, missed, receiver)
+    }
+
+    /// renders & sends `event` to the followers -- if there are any (or if the recent events are being kept)
+    fn publish_to_followers(&self, topic: &'static str, event: &impl Serialize, correlation_id: Option<String>) {
+        let recent_capacity = self.recent_capacity.load(Ordering::Relaxed);
+        if recent_capacity == 0 && self.followers.receiver_count() == 0 {
+            return
+        }
+        let payload = match serde_json::to_value(event) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!("Event Bus: could not render an event of topic '{}' to JSON -- followers won't see it: {}", topic, err);
+                return
+            },
+        };
+        let mut recent = self.recent.lock();
+        let published_event = PublishedEvent {
+            seq:       self.next_seq.fetch_add(1, Ordering::Relaxed),
+            topic,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            correlation_id,
+            payload,
+        };
+        if recent_capacity > 0 {
+            while recent.len() >= recent_capacity {
+                recent.pop_front();
+            }
+            recent.push_back(published_event.clone());
+        }
+        // errors only mean no one is following
+        self.followers.send(published_event).ok();
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A typed topic of the [EventBus] -- cheap to clone, so each component may keep its own
+pub struct Topic<EventType> {
+    name:   &'static str,
+    sender: broadcast::Sender<Correlated<EventType>>,
+    bus:    Arc<EventBus>,
+}
+
+impl<EventType: Serialize + Clone + Send + Sync + 'static> Topic<EventType> {
+
+    /// sends `event` to all current subscribers of this topic (& to the bus followers), returning how many subscribers got it.
+    /// The correlation id in scope, if any, goes along
+    pub fn publish(&self, event: EventType) -> usize {
+        counter!(PUBLISHED_METRIC, "topic" => self.name).increment(1);
+        let correlation_id = correlation_id();
+        self.bus.publish_to_followers(self.name, &event, correlation_id.clone());
+        // errors only mean there are no subscribers
+        self.sender.send(Correlated { correlation_id, event }).unwrap_or(0)
+    }
+
+    /// receives the events published from now on -- lagging more than [TOPIC_CAPACITY] events behind means missing the oldest ones
+    pub fn subscribe(&self) -> broadcast::Receiver<Correlated<EventType>> {
+        self.sender.subscribe()
+    }
+}
+
+impl<EventType> Clone for Topic<EventType> {
+    fn clone(&self) -> Self {
+        Self {
+            name:   self.name,
+            sender: self.sender.clone(),
+            bus:    Arc::clone(&self.bus),
+        }
+    }
+}
+
+
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::runtime::correlated;
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn events_are_fanned_out_and_followed() {
+        let bus = Arc::new(EventBus::new());
+        bus.keep_recent(2);
+        let topic = bus.topic::<u32>("numbers").expect("Creating the topic");
+        let mut subscription_1 = topic.subscribe();
+        let mut subscription_2 = bus.topic::<u32>("numbers").expect("Retrieving the topic").subscribe();
+        assert_eq!(topic.publish(1), 2, "Both subscribers should have been reached");
+        assert_eq!((subscription_1.recv().await.ok().map(|received| received.event), subscription_2.recv().await.ok().map(|received| received.event)),
+                   (Some(1), Some(1)), "Events should be fanned-out");
+        topic.publish(2);
+        correlated(Some(String::from("abc")), async { topic.publish(3) }).await;
+        subscription_1.recv().await.expect("Receiving the uncorrelated event");
+        assert_eq!(subscription_1.recv().await.ok(), Some(Correlated { correlation_id: Some(String::from("abc")), event: 3 }),
+                   "Events should carry the correlation id in scope when published");
+        let (events, missed, _receiver) = bus.follow_after(0);
+        assert_eq!(events.iter().map(|event| event.payload.clone()).collect::<Vec<_>>(), vec![serde_json::json!(2), serde_json::json!(3)],
+                   "Only the most recent events should have been kept");
+        assert_eq!(missed, 1, "The first event is no longer kept");
+        assert_eq!(events.iter().map(|event| event.correlation_id.clone()).collect::<Vec<_>>(), vec![None, Some(String::from("abc"))],
+                   "Followers should see the correlation ids as well");
+        assert!(bus.topic::<String>("numbers").is_err(), "Topics should keep their event types");
+    }
+}
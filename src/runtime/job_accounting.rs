@@ -0,0 +1,113 @@
+//! Resource accounting for jobs -- a built-in version of the measurements in [crate::features]: each run has its wall time,
+//! CPU time & peak memory growth logged at completion & kept (for the most recent runs) for inspection through `/api/stats/jobs`.
+//!
+//! CPU time & memory are taken from the process-wide counters in `/proc/self` (see [super::procfs]) -- so they are only available on Linux and, if
+//! other jobs (or services) are busy at the same time, they will be accounted for as well.
+//!
+//! The scheduled jobs (see [crate::scheduler::Scheduler]) are accounted for as `scheduled: <name>`.
+//!
+//! TODO 2026-10-16: report the allocations count -- none of the allocators in `features.rs` expose it without extra dependencies
+//!                  (`jemalloc-ctl` would, for jemalloc; a counting `GlobalAlloc` wrapper would, for all).
+
+use super::procfs;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    time::Instant,
+};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use log::info;
+
+
+/// How many of the most recent runs to keep
+const RUNS_KEPT: usize = 64;
+
+
+/// The resources used by a job run -- as listed by `/api/stats/jobs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job:                   String,
+    /// when the run started
+    pub timestamp:             String,
+    pub wall_time_micros:      u64,
+    /// user + system CPU time of the whole process, while the job ran
+    pub cpu_time_micros:       Option<u64>,
+    /// how much the process' peak resident memory grew beyond the resident memory at the job's start
+    pub peak_memory_delta_kib: Option<u64>,
+    pub succeeded:             bool,
+}
+
+/// Measures job runs & keeps the most recent ones -- see [JobAccounting::account()]
+pub struct JobAccounting {
+    /// the newest at the back
+    runs: Mutex<VecDeque<JobRun>>,
+}
+
+impl JobAccounting {
+
+    pub fn new() -> Self {
+        Self {
+            runs: Mutex::new(VecDeque::with_capacity(RUNS_KEPT)),
+        }
+    }
+
+    /// Runs the `job` future, named `job_name`, accounting for the resources it used -- which are logged & recorded when it completes
+    pub async fn account<T, E>(&self, job_name: &str, job: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        let timestamp = chrono::Local::now().to_rfc3339();
+        let start = Instant::now();
+        let start_cpu_ticks = procfs::cpu_ticks();
+        let start_rss_kib = procfs::status_value("VmRSS:");
+        let result = job.await;
+        let run = JobRun {
+            job:                   job_name.to_string(),
+            timestamp,
+            wall_time_micros:      start.elapsed().as_micros() as u64,
+            cpu_time_micros:       start_cpu_ticks.zip(procfs::cpu_ticks())
+                                       .map(|(start, end)| end.saturating_sub(start) * 1_000_000 / *procfs::CLOCK_TICKS_PER_SECOND),
+            peak_memory_delta_kib: start_rss_kib.zip(procfs::status_value("VmHWM:"))
+                                       .map(|(start, peak)| peak.saturating_sub(start)),
+            succeeded:             result.is_ok(),
+        };
+        info!("Job '{}' {} in {:.3}s (wall) -- CPU: {}; peak memory growth: {}",
+              run.job, if run.succeeded {"succeeded"} else {"FAILED"}, run.wall_time_micros as f64 / 1e6,
+              run.cpu_time_micros.map_or(String::from("n/a"), |micros| format!("{:.3}s", micros as f64 / 1e6)),
+              run.peak_memory_delta_kib.map_or(String::from("n/a"), |kib| format!("{}KiB", kib)));
+        let mut runs = self.runs.lock();
+        if runs.len() >= RUNS_KEPT {
+            runs.pop_front();
+        }
+        runs.push_back(run);
+        result
+    }
+
+    /// The most recent job runs -- the newest first
+    pub fn recent_runs(&self) -> Vec<JobRun> {
+        self.runs.lock().iter().rev().cloned().collect()
+    }
+}
+
+
+/// Unit tests the [job_accounting](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn runs_are_accounted() {
+        let job_accounting = JobAccounting::new();
+        let result: Result<u64, String> = job_accounting.account("busy", async {
+            Ok((0..1_000_000u64).fold(0u64, |acc, n| acc.wrapping_add(n * n)))
+        }).await;
+        assert!(result.is_ok(), "The job's result should have been passed through");
+        let _ = job_accounting.account("failing", async { Err::<(), _>("expected failure") }).await;
+        let runs = job_accounting.recent_runs();
+        assert_eq!(runs.iter().map(|run| (run.job.as_str(), run.succeeded)).collect::<Vec<_>>(),
+                   vec![("failing", false), ("busy", true)],
+                   "Runs should be listed newest first, along with their outcomes");
+        if cfg!(target_os = "linux") {
+            assert!(runs[0].cpu_time_micros.is_some() && runs[0].peak_memory_delta_kib.is_some(), "CPU & memory should be available on Linux");
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Durable job queue: the business logic [JobQueue::enqueue()]s typed jobs -- persisted, in RON, to a spool directory, so they survive
+//! restarts -- to be consumed by a pool of workers (see [JobQueue::register_worker()]). Failing jobs are retried (with exponential backoff)
+//! & the ones still failing after the configured attempts are moved to the dead letters, for inspection through `/api/stats/job-queue` --
+//! from where operators requeue or purge them through the admin API (`/api/admin/job-queue/dead-letters/<id>`).\
+//! Statuses are also published in the metrics -- see [describe_job_queue_metrics()] -- & shown by the `check-config` console job.\
+//! Notice the spool directory belongs to a single instance: several of them may not share it.
+
+use crate::config::JobQueueConfig;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use futures::future::BoxFuture;
+use parking_lot::{Mutex, RwLock};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use tokio::sync::Notify;
+use metrics::{counter, gauge, histogram, describe_counter, describe_gauge, describe_histogram, Unit};
+use log::{debug, info, warn, error};
+
+
+/// How many of the dead letters (the newest ones) to list on [JobQueue::report()]
+const DEAD_LETTERS_REPORTED: usize = 100;
+
+/// Counter of the jobs enqueued, labeled by `kind`
+pub const ENQUEUED_METRIC: &str = "job_queue_enqueued_total";
+/// Counter of the jobs' attempts, labeled by `kind` & `outcome` -- "succeeded", "retried" or "dead_lettered"
+pub const ATTEMPTS_METRIC: &str = "job_queue_attempts_total";
+/// Gauge of the jobs either waiting for a worker, running or waiting to be retried
+pub const PENDING_METRIC: &str = "job_queue_pending";
+/// Histogram of how long the jobs' attempts took, labeled by `kind`
+pub const ATTEMPT_DURATION_METRIC: &str = "job_queue_attempt_duration_seconds";
+
+
+/// registers the descriptions of the job queue's metrics -- to be called once the recorder is installed
+pub fn describe_job_queue_metrics() {
+    describe_counter!(ENQUEUED_METRIC, Unit::Count, "Jobs enqueued in the durable job queue, by kind");
+    describe_counter!(ATTEMPTS_METRIC, Unit::Count, "Attempts to run the queued jobs, by kind & outcome (succeeded, retried or dead_lettered)");
+    describe_gauge!(PENDING_METRIC, Unit::Count, "Jobs either waiting for a worker, running or waiting to be retried");
+    describe_histogram!(ATTEMPT_DURATION_METRIC, Unit::Seconds, "How long the attempts to run the queued jobs took, by kind");
+}
+
+
+type JobHandler = Arc<dyn Fn(&str) -> BoxFuture<'static, Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + Sync>;
+
+/// A job, as persisted in the spool directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub id:         u64,
+    /// tells which worker runs it -- see [JobQueue::register_worker()]
+    pub kind:       String,
+    /// when the job was enqueued
+    pub timestamp:  String,
+    /// the job itself, in RON
+    pub payload:    String,
+    pub attempts:   u32,
+    /// present if the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// As answered by `/api/stats/job-queue`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobQueueReport {
+    pub workers:       u16,
+    /// jobs either waiting for a worker, running or waiting to be retried
+    pub pending:       usize,
+    pub enqueued:      u64,
+    pub succeeded:     u64,
+    pub retried:       u64,
+    pub dead_lettered: u64,
+    /// the newest first
+    pub dead_letters:  Vec<QueuedJob>,
+}
+
+/// The job queue & its workers. Usage:
+/// ```no_compile
+///     job_queue.register_worker("send-invoice", |invoice: Invoice| async move { billing::send(invoice).await });
+///     job_queue.start_workers();
+///     job_queue.enqueue("send-invoice", &invoice)?;
+pub struct JobQueue {
+    config:        JobQueueConfig,
+    handlers:      RwLock<HashMap<String, JobHandler>>,
+    /// jobs waiting for a worker -- the oldest at the front
+    ready:         Mutex<VecDeque<QueuedJob>>,
+    ready_notify:  Notify,
+    next_id:       AtomicU64,
+    pending:       AtomicUsize,
+    enqueued:      AtomicU64,
+    succeeded:     AtomicU64,
+    retried:       AtomicU64,
+    dead_lettered: AtomicU64,
+}
+
+impl JobQueue {
+
+    /// Prepares the spool directory, loading the jobs left pending by previous runs -- which will be ran once the workers start
+    pub fn new(config: &JobQueueConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let job_queue = Self {
+            config:        config.clone(),
+            handlers:      RwLock::new(HashMap::new()),
+            ready:         Mutex::new(VecDeque::new()),
+            ready_notify:  Notify::new(),
+            next_id:       AtomicU64::new(1),
+            pending:       AtomicUsize::new(0),
+            enqueued:      AtomicU64::new(0),
+            succeeded:     AtomicU64::new(0),
+            retried:       AtomicU64::new(0),
+            dead_lettered: AtomicU64::new(0),
+        };
+        for dir in [job_queue.pending_dir(), job_queue.dead_dir()] {
+            std::fs::create_dir_all(&dir)
+                .map_err(|err| format!("Job queue: could not create the spool directory '{}': {}", dir.display(), err))?;
+        }
+        let mut pending_jobs = read_jobs(&job_queue.pending_dir())?;
+        pending_jobs.sort_by_key(|job| job.id);
+        let max_id = pending_jobs.iter().chain(read_jobs(&job_queue.dead_dir())?.iter())
+            .map(|job| job.id)
+            .max().unwrap_or(0);
+        job_queue.next_id.store(max_id + 1, Ordering::Relaxed);
+        if !pending_jobs.is_empty() {
+            info!("Job queue: {} job(s) left pending by previous runs were loaded from '{}'", pending_jobs.len(), config.spool_dir);
+        }
+        job_queue.pending.store(pending_jobs.len(), Ordering::Relaxed);
+        gauge!(PENDING_METRIC).set(pending_jobs.len() as f64);
+        *job_queue.ready.lock() = pending_jobs.into();
+        Ok(job_queue)
+    }
+
+    /// Registers `worker` to run the jobs of the given `kind` -- which must be registered before [JobQueue::start_workers()]
+    /// or jobs of that kind may be dead-lettered for the lack of a worker
+    pub fn register_worker<Job:          DeserializeOwned,
+                           WorkerFuture: Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>
+                          (&self, kind: &str, worker: impl Fn(Job) -> WorkerFuture + Send + Sync + 'static) {
+        let kind_for_errors = kind.to_string();
+        self.handlers.write().insert(kind.to_string(), Arc::new(move |payload: &str| match ron::from_str::<Job>(payload) {
+            Ok(job) => Box::pin(worker(job)),
+            Err(err) => {
+                let err = format!("could not deserialize the '{}' job: {}", kind_for_errors, err);
+                Box::pin(async move { Err(Box::from(err)) })
+            },
+        }));
+    }
+
+    /// Spawns the configured number of workers, which will consume the queue until the Tokio runtime ends
+    pub fn start_workers(self: &Arc<Self>) {
+        debug!("Job queue: starting {} worker(s)...", self.config.workers);
+        for _ in 0..self.config.workers.max(1) {
+            let job_queue = Arc::clone(self);
+            tokio::spawn(async move { job_queue.work().await });
+        }
+    }
+
+    /// Persists `job`, of the given `kind`, to be ran by one of the workers -- returning its id
+    pub fn enqueue(&self, kind: &str, job: &impl Serialize) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let payload = ron::to_string(job)
+            .map_err(|err| format!("Job queue: could not serialize the '{}' job: {}", kind, err))?;
+        let job = QueuedJob {
+            id:         self.next_id.fetch_add(1, Ordering::Relaxed),
+            kind:       kind.to_string(),
+            timestamp:  chrono::Local::now().to_rfc3339(),
+            payload,
+            attempts:   0,
+            last_error: None,
+        };
+        self.persist(&job)?;
+        let id = job.id;
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+        counter!(ENQUEUED_METRIC, "kind" => kind.to_string()).increment(1);
+        gauge!(PENDING_METRIC).set((self.pending.fetch_add(1, Ordering::Relaxed) + 1) as f64);
+        self.push(job);
+        Ok(id)
+    }
+
+    /// how many jobs are either waiting for a worker, running or waiting to be retried -- cheaper than [Self::report()]
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&self) -> JobQueueReport {
+        let mut dead_letters = read_jobs(&self.dead_dir())
+            .unwrap_or_else(|err| {
+                warn!("{}", err);
+                Vec::new()
+            });
+        dead_letters.sort_by_key(|job| std::cmp::Reverse(job.id));
+        dead_letters.truncate(DEAD_LETTERS_REPORTED);
+        JobQueueReport {
+            workers:       self.config.workers,
+            pending:       self.pending.load(Ordering::Relaxed),
+            enqueued:      self.enqueued.load(Ordering::Relaxed),
+            succeeded:     self.succeeded.load(Ordering::Relaxed),
+            retried:       self.retried.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            dead_letters,
+        }
+    }
+
+    /// Moves the dead letter `id` back to the queue -- to be ran again, with its attempts reset -- telling if it was still dead-lettered
+    pub fn requeue_dead_letter(&self, id: u64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let dead_letter_path = self.dead_dir().join(job_file_name(id));
+        let Some(job) = read_job(&dead_letter_path)? else {
+            return Ok(false)
+        };
+        let job = QueuedJob { attempts: 0, ..job };
+        self.persist(&job)?;
+        std::fs::remove_file(&dead_letter_path)
+            .map_err(|err| format!("Job queue: could not remove the dead letter file '{}' of the requeued job #{}: {}", dead_letter_path.display(), id, err))?;
+        info!("Job queue: dead letter #{} ('{}') was requeued", job.id, job.kind);
+        gauge!(PENDING_METRIC).set((self.pending.fetch_add(1, Ordering::Relaxed) + 1) as f64);
+        self.push(job);
+        Ok(true)
+    }
+
+    /// Purges the dead letter `id` -- telling if it was still dead-lettered
+    pub fn discard_dead_letter(&self, id: u64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let dead_letter_path = self.dead_dir().join(job_file_name(id));
+        match std::fs::remove_file(&dead_letter_path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(Box::from(format!("Job queue: could not remove the dead letter file '{}': {}", dead_letter_path.display(), err))),
+        }
+    }
+
+    /// makes `job` available to the workers
+    fn push(&self, job: QueuedJob) {
+        self.ready.lock().push_back(job);
+        self.ready_notify.notify_one();
+    }
+
+    /// a worker's loop
+    async fn work(self: Arc<Self>) {
+        loop {
+            let next_job = self.ready.lock().pop_front();
+            match next_job {
+                Some(job) => self.run(job).await,
+                None => self.ready_notify.notified().await,
+            }
+        }
+    }
+
+    /// runs `job`, then either forgets it, schedules its retry or dead-letters it
+    async fn run(self: &Arc<Self>, mut job: QueuedJob) {
+        let handler = self.handlers.read().get(&job.kind).cloned();
+        job.attempts += 1;
+        let start = Instant::now();
+        let result = match handler {
+            // ran in its own task, so a panicking worker counts as a failed attempt -- rather than taking this worker down & leaving `pending` off
+            Some(handler) => {
+                let payload = job.payload.clone();
+                tokio::spawn(async move { handler(&payload).await }).await
+                    .unwrap_or_else(|err| Err(Box::from(format!("the worker panicked: {}", err))))
+            },
+            None => Err(Box::from(format!("no worker is registered for jobs of kind '{}'", job.kind))),
+        };
+        let kind = job.kind.clone();
+        histogram!(ATTEMPT_DURATION_METRIC, "kind" => kind.clone()).record(start.elapsed().as_secs_f64());
+        let outcome = match result {
+            Ok(()) => {
+                debug!("Job queue: job #{} ('{}') succeeded (attempt {})", job.id, job.kind, job.attempts);
+                self.forget(&job);
+                self.succeeded.fetch_add(1, Ordering::Relaxed);
+                "succeeded"
+            },
+            Err(err) if job.attempts < self.config.max_attempts => {
+                let retry_delay = Duration::from_millis(self.config.retry_delay_millis.saturating_mul(1u64.checked_shl(job.attempts - 1).unwrap_or(u64::MAX)));
+                warn!("Job queue: attempt {} of {} of job #{} ('{}') failed -- retrying in {:?}: {}", job.attempts, self.config.max_attempts, job.id, job.kind, retry_delay, err);
+                job.last_error = Some(err.to_string());
+                if let Err(err) = self.persist(&job) {
+                    warn!("{}", err);
+                }
+                self.retried.fetch_add(1, Ordering::Relaxed);
+                let job_queue = Arc::clone(self);
+                tokio::spawn(async move {
+                    tokio::time::sleep(retry_delay).await;
+                    job_queue.push(job);
+                });
+                "retried"
+            },
+            Err(err) => {
+                error!("Job queue: job #{} ('{}') FAILED on all {} attempts -- moving it to the dead letters: {}", job.id, job.kind, job.attempts, err);
+                job.last_error = Some(err.to_string());
+                let dead_letter_path = self.dead_dir().join(job_file_name(job.id));
+                match write_job(&job, &dead_letter_path) {
+                    Ok(()) => self.forget(&job),
+                    Err(err) => {
+                        // keeps the pending file, so the job isn't lost -- it will run again on the next start
+                        error!("{} -- keeping it as pending", err);
+                        self.release();
+                    },
+                }
+                self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+                "dead_lettered"
+            },
+        };
+        counter!(ATTEMPTS_METRIC, "kind" => kind, "outcome" => outcome).increment(1);
+    }
+
+    /// writes `job` to the pending jobs directory
+    fn persist(&self, job: &QueuedJob) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        write_job(job, &self.pending_dir().join(job_file_name(job.id)))
+    }
+
+    /// removes `job` from the pending jobs
+    fn forget(&self, job: &QueuedJob) {
+        let path = self.pending_dir().join(job_file_name(job.id));
+        if let Err(err) = std::fs::remove_file(&path) {
+            warn!("Job queue: could not remove the file '{}' of job #{} -- it will run again on the next start: {}", path.display(), job.id, err);
+        }
+        self.release();
+    }
+
+    /// stops counting a job as pending, as no worker will run it anymore (on this run)
+    fn release(&self) {
+        gauge!(PENDING_METRIC).set(self.pending.fetch_sub(1, Ordering::Relaxed).saturating_sub(1) as f64);
+    }
+
+    fn pending_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.spool_dir).join("pending")
+    }
+
+    fn dead_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.spool_dir).join("dead")
+    }
+}
+
+/// zero-padded, so listing the files sorts them in the order they were enqueued
+fn job_file_name(id: u64) -> String {
+    format!("{:020}.ron", id)
+}
+
+/// writes `job` to `path` atomically -- through a temporary file, then renamed -- so a crash never leaves a truncated job behind
+fn write_job(job: &QueuedJob, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let contents = ron::to_string(job)
+        .map_err(|err| format!("Job queue: could not serialize job #{}: {}", job.id, err))?;
+    let temporary_path = path.with_extension("tmp");
+    std::fs::write(&temporary_path, contents)
+        .and_then(|_| std::fs::rename(&temporary_path, path))
+        .map_err(|err| Box::from(format!("Job queue: could not write job #{} to '{}': {}", job.id, path.display(), err)))
+}
+
+/// the job persisted in `path` -- `None` if there is no such file
+fn read_job(path: &Path) -> Result<Option<QueuedJob>, Box<dyn std::error::Error + Send + Sync>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(Box::from(format!("Job queue: could not read the job file '{}': {}", path.display(), err))),
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(|err| Box::from(format!("Job queue: could not parse the job file '{}': {}", path.display(), err)))
+}
+
+/// the jobs persisted in `dir` -- the unreadable ones are logged & skipped
+fn read_jobs(dir: &Path) -> Result<Vec<QueuedJob>, Box<dyn std::error::Error + Send + Sync>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|err| format!("Job queue: could not list the jobs in '{}': {}", dir.display(), err))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter_map(|path| std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| ron::from_str(&contents).map_err(|err| err.to_string()))
+            .map_err(|err| warn!("Job queue: skipping the unreadable job file '{}': {}", path.display(), err))
+            .ok())
+        .collect())
+}
+
+
+/// Unit tests the [job_queue](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[derive(Serialize, Deserialize)]
+    struct Greeting {
+        name: String,
+    }
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn retries_and_dead_letters() {
+        let spool_dir = std::env::temp_dir().join(format!("kickass-job-queue-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&spool_dir);
+        let config = JobQueueConfig {
+            spool_dir:          spool_dir.to_string_lossy().to_string(),
+            workers:            2,
+            max_attempts:       2,
+            retry_delay_millis: 10,
+        };
+        let job_queue = Arc::new(JobQueue::new(&config).expect("Couldn't create the job queue"));
+        job_queue.register_worker("greet", |greeting: Greeting| async move {
+            if greeting.name.is_empty() { Err(Box::from("nobody to greet")) } else { Ok(()) }
+        });
+        job_queue.enqueue("greet", &Greeting { name: String::from("world") }).expect("Couldn't enqueue");
+        job_queue.enqueue("greet", &Greeting { name: String::new() }).expect("Couldn't enqueue");
+        job_queue.enqueue("unknown", &Greeting { name: String::from("nobody") }).expect("Couldn't enqueue");
+        assert_eq!(JobQueue::new(&config).expect("Couldn't reload the job queue").report().pending, 3,
+                   "Enqueued jobs should survive restarts");
+        job_queue.start_workers();
+        let start = tokio::time::Instant::now();
+        while job_queue.report().pending > 0 && start.elapsed() < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let report = job_queue.report();
+        assert_eq!((report.succeeded, report.retried, report.dead_lettered), (1, 2, 2), "Counters are off");
+        assert_eq!(report.dead_letters.iter().map(|job| (job.kind.as_str(), job.attempts)).collect::<Vec<_>>(),
+                   vec![("unknown", 2), ("greet", 2)],
+                   "Jobs failing on all attempts should have been dead-lettered");
+
+        let (unknown_id, empty_greeting_id) = (report.dead_letters[0].id, report.dead_letters[1].id);
+        assert!(job_queue.discard_dead_letter(unknown_id).expect("Couldn't discard"), "The dead letter should have been discarded");
+        assert!(!job_queue.discard_dead_letter(unknown_id).expect("Couldn't discard"), "Discarded dead letters should be gone");
+        assert!(job_queue.requeue_dead_letter(empty_greeting_id).expect("Couldn't requeue"), "The dead letter should have been requeued");
+        assert!(!job_queue.requeue_dead_letter(unknown_id).expect("Couldn't requeue"), "Discarded dead letters may not be requeued");
+        let start = tokio::time::Instant::now();
+        while job_queue.report().pending > 0 && start.elapsed() < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let report = job_queue.report();
+        assert_eq!((report.retried, report.dead_lettered), (3, 3), "The requeued job should have been ran on all its attempts again");
+        assert_eq!(report.dead_letters.iter().map(|job| job.id).collect::<Vec<_>>(), vec![empty_greeting_id],
+                   "Only the requeued job -- failing again -- should be dead-lettered");
+        std::fs::remove_dir_all(&spool_dir).expect("Couldn't remove the spool directory");
+    }
+
+    /// assures a panicking worker counts as a failed attempt -- neither killing the worker nor leaving the job pending forever
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn panicking_workers() {
+        let (spool_dir, job_queue) = job_queue("panics");
+        job_queue.register_worker("greet", |greeting: Greeting| async move {
+            if greeting.name.is_empty() { panic!("nobody to greet") } else { Ok(()) }
+        });
+        job_queue.enqueue("greet", &Greeting { name: String::new() }).expect("Couldn't enqueue");
+        job_queue.start_workers();
+        wait_for_pending_jobs(&job_queue).await;
+        let report = job_queue.report();
+        assert_eq!((report.pending, report.retried, report.dead_lettered), (0, 1, 1), "Panics should have been taken as failed attempts");
+        assert!(report.dead_letters[0].last_error.as_ref().is_some_and(|err| err.contains("panicked")), "The panic should be the job's error");
+
+        job_queue.enqueue("greet", &Greeting { name: String::from("world") }).expect("Couldn't enqueue");
+        wait_for_pending_jobs(&job_queue).await;
+        assert_eq!(job_queue.report().succeeded, 1, "The worker should have survived the panics");
+        std::fs::remove_dir_all(&spool_dir).expect("Couldn't remove the spool directory");
+    }
+
+    /// assures jobs that could not be dead-lettered are kept in the pending directory -- to run again on the next start
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn failed_dead_lettering() {
+        let (spool_dir, job_queue) = job_queue("dead-lettering");
+        job_queue.register_worker("greet", |_greeting: Greeting| async move { Err(Box::from("nobody to greet")) });
+        let id = job_queue.enqueue("greet", &Greeting { name: String::new() }).expect("Couldn't enqueue");
+        std::fs::remove_dir_all(job_queue.dead_dir()).expect("Couldn't remove the dead letters directory");
+        job_queue.start_workers();
+        wait_for_pending_jobs(&job_queue).await;
+        assert_eq!(job_queue.report().pending, 0, "The job shouldn't be running anymore");
+        assert!(job_queue.pending_dir().join(job_file_name(id)).exists(), "The job should have been kept as pending");
+        std::fs::remove_dir_all(&spool_dir).expect("Couldn't remove the spool directory");
+    }
+
+    /// a fresh job queue, retrying failed jobs once, in a spool directory exclusive to the `test` -- also returned
+    fn job_queue(test: &str) -> (PathBuf, Arc<JobQueue>) {
+        let spool_dir = std::env::temp_dir().join(format!("kickass-job-queue-{}-test-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&spool_dir);
+        let config = JobQueueConfig {
+            spool_dir:          spool_dir.to_string_lossy().to_string(),
+            workers:            1,
+            max_attempts:       2,
+            retry_delay_millis: 10,
+        };
+        (spool_dir, Arc::new(JobQueue::new(&config).expect("Couldn't create the job queue")))
+    }
+
+    async fn wait_for_pending_jobs(job_queue: &JobQueue) {
+        let start = tokio::time::Instant::now();
+        while job_queue.pending() > 0 && start.elapsed() < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
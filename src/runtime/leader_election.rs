@@ -0,0 +1,257 @@
+//! Leader election among several instances of this application -- see [crate::config::Config::leader_election].
+//!
+//! Leadership is a lease kept in a file on a storage shared by all instances: the leader renews it periodically
+//! and, when it expires (the leader died or lost access to the storage), any other instance may take it over.
+//! As lease expirations are compared across hosts, their clocks should be kept in sync (NTP) -- and well within
+//! the lease duration.\
+//! Leadership changes are published on the [LEADERSHIP_TOPIC] of the [super::EventBus] -- besides [LeaderElection::subscribe()].
+
+use super::{EventBus, Topic};
+use crate::config::LeaderElectionConfig;
+use std::{
+    io::ErrorKind,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use serde::{Serialize, Deserialize};
+use tokio::sync::{watch, Notify};
+use log::{info, warn};
+
+
+/// The topic carrying the [LeadershipChange]s
+pub const LEADERSHIP_TOPIC: &str = "leadership";
+
+/// After writing a lease over an expired one, waits this long before reading it back to find out which of the
+/// concurrent candidates (if any) won -- the last one to write
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+
+/// What is stored in the lease file
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Lease {
+    /// the instance id of the leader
+    holder: String,
+    /// milliseconds since the UNIX epoch
+    expires_at: u64,
+}
+
+/// This instance was elected (or stopped being) the leader -- published on the [LEADERSHIP_TOPIC]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeadershipChange {
+    pub instance_id: String,
+    pub is_leader:   bool,
+}
+
+/// the [LEADERSHIP_TOPIC] of `events`
+pub fn leadership_topic(events: &Arc<EventBus>) -> Topic<LeadershipChange> {
+    events.topic(LEADERSHIP_TOPIC)
+        .expect("BUG: leader_election.rs: the leadership topic was created for another event type")
+}
+
+/// Elects one of the running instances as the leader, so scheduled jobs & singleton tasks run on exactly one of them.\
+/// Usage: spawn [run()](LeaderElection::run()), then check [is_leader()](LeaderElection::is_leader()) before running
+/// a singleton task -- or wait for it with [wait_for_leadership()](LeaderElection::wait_for_leadership()).
+pub struct LeaderElection {
+    lease_file:     String,
+    lease_duration: Duration,
+    instance_id:    String,
+    /// publishes leadership changes
+    leadership:     watch::Sender<bool>,
+    /// ... also to the event bus
+    changes:        Topic<LeadershipChange>,
+    resigned:       AtomicBool,
+    resignation:    Notify,
+}
+
+impl LeaderElection {
+
+    pub fn new(config: &LeaderElectionConfig, events: &Arc<EventBus>) -> Self {
+        let instance_id = if config.instance_id.is_empty() {
+            let hostname = std::fs::read_to_string("/etc/hostname")
+                .map(|hostname| hostname.trim().to_string())
+                .unwrap_or_else(|_| String::from("localhost"));
+            format!("{}:{}", hostname, std::process::id())
+        } else {
+            config.instance_id.clone()
+        };
+        let (leadership, _) = watch::channel(false);
+        Self {
+            lease_file:     config.lease_file.clone(),
+            lease_duration: Duration::from_millis(config.lease_duration_millis),
+            instance_id,
+            leadership,
+            changes:        leadership_topic(events),
+            resigned:       AtomicBool::new(false),
+            resignation:    Notify::new(),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.leadership.borrow()
+    }
+
+    /// returns a receiver for leadership changes -- `true` meaning this instance was elected
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.leadership.subscribe()
+    }
+
+    /// waits until this instance is elected the leader -- which may never happen
+    pub async fn wait_for_leadership(&self) {
+        let mut leadership = self.subscribe();
+        while !*leadership.borrow_and_update() {
+            if leadership.changed().await.is_err() {
+                return
+            }
+        }
+    }
+
+    /// keeps trying to acquire (or renew) the lease until [resign()](LeaderElection::resign()) is called
+    pub async fn run(&self) {
+        info!("Leader election: instance '{}' is a candidate through lease file '{}'", self.instance_id, self.lease_file);
+        while !self.resigned.load(Ordering::Relaxed) {
+            let is_leader = self.try_acquire().await
+                .unwrap_or_else(|err| {
+                    warn!("Leader election: could not acquire (or renew) the lease -- considering this instance a follower: {}", err);
+                    false
+                });
+            if !self.resigned.load(Ordering::Relaxed) {
+                self.set_leadership(is_leader);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.lease_duration / 3) => (),
+                _ = self.resignation.notified() => (),
+            }
+        }
+    }
+
+    /// stops [run()](LeaderElection::run()) and releases the lease, if held -- so another instance may take over right away
+    pub async fn resign(&self) {
+        self.resigned.store(true, Ordering::Relaxed);
+        self.resignation.notify_one();
+        if self.is_leader() {
+            self.set_leadership(false);
+            match self.read_lease().await {
+                Ok(Some(lease)) if lease.holder == self.instance_id => {
+                    if let Err(err) = tokio::fs::remove_file(&self.lease_file).await {
+                        warn!("Leader election: could not release the lease file '{}': {}", self.lease_file, err);
+                    }
+                },
+                Ok(_) => (),
+                Err(err) => warn!("Leader election: could not release the lease: {}", err),
+            }
+        }
+    }
+
+    /// acquires the lease, if it is free or expired -- or renews it, if already held. Returns whether this instance is now the leader
+    async fn try_acquire(&self) -> Result<bool, Box<dyn std::error::Error + Sync + Send>> {
+        let now = millis_since_epoch();
+        match self.read_lease().await? {
+            Some(lease) if lease.holder != self.instance_id && lease.expires_at > now => return Ok(false),
+            _ => (),
+        }
+        self.write_lease(&Lease {
+            holder:     self.instance_id.clone(),
+            expires_at: now + self.lease_duration.as_millis() as u64,
+        }).await?;
+        // other candidates may have taken over the expired lease at the same time -- the last one to write wins. This also applies to
+        // renewals: the lease may have expired (& been taken over) between reading & writing it, if this instance was stalled
+        tokio::time::sleep(SETTLE_TIME).await;
+        Ok(self.read_lease().await?
+            .map_or(false, |lease| lease.holder == self.instance_id))
+    }
+
+    fn set_leadership(&self, is_leader: bool) {
+        let changed = self.leadership.send_if_modified(|leadership| {
+            if *leadership == is_leader {
+                return false
+            }
+            *leadership = is_leader;
+            if is_leader {
+                info!("Leader election: instance '{}' is now the LEADER", self.instance_id);
+            } else {
+                info!("Leader election: instance '{}' is now a follower", self.instance_id);
+            }
+            true
+        });
+        if changed {
+            self.changes.publish(LeadershipChange { instance_id: self.instance_id.clone(), is_leader });
+        }
+    }
+
+    /// the current lease -- `None` if there is none
+    async fn read_lease(&self) -> Result<Option<Lease>, Box<dyn std::error::Error + Sync + Send>> {
+        match tokio::fs::read_to_string(&self.lease_file).await {
+            Ok(ron_contents) => ron::from_str(&ron_contents)
+                .map(Some)
+                .or_else(|err| {
+                    // a partially written (or foreign) file: treat it as an expired lease, so it gets overwritten
+                    warn!("Leader election: ignoring unparseable lease file '{}': {}", self.lease_file, err);
+                    Ok(None)
+                }),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Box::from(format!("could not read the lease file '{}': {}", self.lease_file, err))),
+        }
+    }
+
+    /// atomically replaces the lease file -- through a temporary file exclusive to this instance
+    async fn write_lease(&self, lease: &Lease) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let ron_contents = ron::to_string(lease)
+            .map_err(|err| format!("could not serialize the lease {:?}: {}", lease, err))?;
+        let temporary_file = format!("{}.{}.tmp", self.lease_file, self.instance_id.replace(|c: char| !c.is_ascii_alphanumeric(), "_"));
+        tokio::fs::write(&temporary_file, ron_contents).await
+            .map_err(|err| format!("could not write the temporary lease file '{}': {}", temporary_file, err))?;
+        tokio::fs::rename(&temporary_file, &self.lease_file).await
+            .map_err(|err| Box::from(format!("could not replace the lease file '{}': {}", self.lease_file, err)))
+    }
+}
+
+fn millis_since_epoch() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+
+/// Unit tests the [leader_election](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures the lease is acquired when free, renewed while held, refused to others until it expires & taken over once it does
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn acquire_renew_expire_and_takeover() {
+        let lease_dir = std::env::temp_dir().join(format!("kickass-leader-election-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&lease_dir);
+        std::fs::create_dir_all(&lease_dir).expect("Couldn't create the lease directory");
+        let events = Arc::new(EventBus::new());
+        let candidate = |instance_id: &str| LeaderElection::new(&LeaderElectionConfig {
+            lease_file:            lease_dir.join("leader.lease").to_string_lossy().to_string(),
+            lease_duration_millis: 1000,
+            instance_id:           instance_id.to_string(),
+        }, &events);
+        let (first, second) = (candidate("first"), candidate("second"));
+
+        assert!(first.try_acquire().await.expect("Couldn't acquire"), "A free lease should have been acquired");
+        let acquired = first.read_lease().await.expect("Couldn't read the lease").expect("There should be a lease");
+        assert!(!second.try_acquire().await.expect("Couldn't acquire"), "A lease held by another instance shouldn't have been acquired");
+
+        assert!(first.try_acquire().await.expect("Couldn't renew"), "The held lease should have been renewed");
+        let renewed = first.read_lease().await.expect("Couldn't read the lease").expect("There should be a lease");
+        assert!(renewed.expires_at > acquired.expires_at, "Renewing should have pushed the expiration forward");
+
+        tokio::time::sleep(Duration::from_millis(renewed.expires_at.saturating_sub(millis_since_epoch()) + 10)).await;
+        assert!(second.try_acquire().await.expect("Couldn't take over"), "An expired lease should have been taken over");
+        assert!(!first.try_acquire().await.expect("Couldn't acquire"), "The former leader shouldn't get the lease back while it is held");
+        assert_eq!(first.read_lease().await.expect("Couldn't read the lease").map(|lease| lease.holder), Some(String::from("second")),
+                   "The lease should still be held by the new leader");
+        std::fs::remove_dir_all(&lease_dir).expect("Couldn't remove the lease directory");
+    }
+}
@@ -0,0 +1,175 @@
+//! Locks for business logic to serialize access to (external) resources -- possibly across several instances of this application.
+//! See [crate::config::Config::locks] for the available backends.
+
+use crate::config::LocksBackend;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use redis::aio::ConnectionManager;
+use log::debug;
+
+
+/// While waiting on [Locks::acquire()], how long to sleep between attempts
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Atomically deletes the lock key only if it still holds our token -- so we never release a lock that expired and was taken by someone else
+const REDIS_RELEASE_SCRIPT: &str = r#"
+    if redis.call("GET", KEYS[1]) == ARGV[1] then
+        return redis.call("DEL", KEYS[1])
+    else
+        return 0
+    end
+"#;
+
+
+/// A held lock, to be given back to [Locks::release()] -- if not released, it is freed when its `ttl` expires
+#[derive(Debug)]
+pub struct Lock {
+    resource: String,
+    /// distinguishes this holder from any other -- including the next ones, after our `ttl` expires
+    token:    String,
+}
+
+/// Named locks with expiration. Usage:
+/// ```no_compile
+///     let lock = locks.acquire("resource-x", Duration::from_secs(30)).await?;
+///     // ... use "resource-x" for no more than 30s ...
+///     locks.release(lock).await?;
+pub enum Locks {
+    /// Single-node locks, only serializing tasks within this process
+    Local(Mutex<HashMap<String, (String, Instant)>>),
+    /// Locks shared by all instances connected to the same Redis server
+    Redis {
+        connection: ConnectionManager,
+        key_prefix: String,
+    },
+}
+
+impl Locks {
+
+    pub async fn new(backend: &LocksBackend) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        match backend {
+            LocksBackend::Local => Ok(Self::Local(Mutex::new(HashMap::new()))),
+            LocksBackend::Redis { url, key_prefix } => {
+                let client = redis::Client::open(url.as_str())
+                    .map_err(|err| format!("Locks: invalid Redis url '{}': {}", url, err))?;
+                let connection = ConnectionManager::new(client).await
+                    .map_err(|err| format!("Locks: could not connect to Redis at '{}': {}", url, err))?;
+                Ok(Self::Redis { connection, key_prefix: key_prefix.clone() })
+            },
+        }
+    }
+
+    /// acquires the lock for `resource`, waiting for as long as it is held elsewhere.\
+    /// Once acquired, the lock lasts for `ttl` -- after which it is, automatically, released
+    pub async fn acquire(&self, resource: &str, ttl: Duration) -> Result<Lock, Box<dyn std::error::Error + Sync + Send>> {
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = self.try_acquire(resource, ttl).await? {
+                if start.elapsed() > RETRY_INTERVAL {
+                    debug!("Locks: '{}' was acquired after a {:?} wait", resource, start.elapsed());
+                }
+                break Ok(lock)
+            }
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+    }
+
+    /// acquires the lock for `resource` -- for up to `ttl` -- only if it is free: returns `None` if it is held elsewhere
+    pub async fn try_acquire(&self, resource: &str, ttl: Duration) -> Result<Option<Lock>, Box<dyn std::error::Error + Sync + Send>> {
+        if ttl.as_millis() == 0 {
+            return Err(Box::from(format!("Locks: could not acquire '{}' for {:?} -- the ttl must be, at least, 1ms", resource, ttl)))
+        }
+        let token = format!("{}:{:016x}", std::process::id(), rand::random::<u64>());
+        match self {
+            Self::Local(locks) => {
+                let now = Instant::now();
+                let mut locks = locks.lock();
+                // evicts the expired locks -- which would, otherwise, pile up for the resources never released
+                locks.retain(|_resource, (_token, expiration)| *expiration > now);
+                if locks.contains_key(resource) {
+                    return Ok(None)
+                }
+                locks.insert(resource.to_string(), (token.clone(), now + ttl));
+            },
+            Self::Redis { connection, key_prefix } => {
+                let mut connection = connection.clone();
+                let reply: Option<String> = redis::cmd("SET")
+                    .arg(format!("{}{}", key_prefix, resource))
+                    .arg(&token)
+                    .arg("NX")
+                    .arg("PX")
+                    .arg(ttl.as_millis() as u64)
+                    .query_async(&mut connection).await
+                    .map_err(|err| format!("Locks: could not acquire '{}' from Redis: {}", resource, err))?;
+                if reply.is_none() {
+                    return Ok(None)
+                }
+            },
+        }
+        Ok(Some(Lock { resource: resource.to_string(), token }))
+    }
+
+    /// releases the given `lock` -- unless it has already expired, in which case it is, possibly, held by someone else now
+    pub async fn release(&self, lock: Lock) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        match self {
+            Self::Local(locks) => {
+                let mut locks = locks.lock();
+                if matches!(locks.get(&lock.resource), Some((token, _expiration)) if token == &lock.token) {
+                    locks.remove(&lock.resource);
+                }
+            },
+            Self::Redis { connection, key_prefix } => {
+                let mut connection = connection.clone();
+                let _released: i64 = redis::Script::new(REDIS_RELEASE_SCRIPT)
+                    .key(format!("{}{}", key_prefix, lock.resource))
+                    .arg(&lock.token)
+                    .invoke_async(&mut connection).await
+                    .map_err(|err| format!("Locks: could not release '{}' on Redis: {}", lock.resource, err))?;
+            },
+        }
+        Ok(())
+    }
+}
+
+
+/// Unit tests the [locks](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures the local locks are exclusive, released only by their holders & freed -- and evicted -- once expired
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn local_locks() {
+        let locks = Locks::new(&LocksBackend::Local).await.expect("Couldn't create the locks");
+        let ttl = Duration::from_millis(100);
+
+        let lock = locks.acquire("resource-x", ttl).await.expect("Couldn't acquire");
+        assert!(locks.try_acquire("resource-x", ttl).await.expect("Couldn't try").is_none(), "A held lock shouldn't have been acquired again");
+        assert!(locks.try_acquire("resource-y", ttl).await.expect("Couldn't try").is_some(), "Other resources should be independent");
+        locks.release(lock).await.expect("Couldn't release");
+        let lock = locks.try_acquire("resource-x", ttl).await.expect("Couldn't try")
+            .expect("A released lock should have been acquired");
+
+        tokio::time::sleep(ttl).await;
+        let next_lock = locks.try_acquire("resource-x", ttl).await.expect("Couldn't try")
+            .expect("An expired lock should have been acquired");
+        let Locks::Local(entries) = &locks else { unreachable!() };
+        assert_eq!(entries.lock().len(), 1, "The expired 'resource-y' lock should have been evicted");
+        locks.release(lock).await.expect("Couldn't release");
+        assert!(locks.try_acquire("resource-x", ttl).await.expect("Couldn't try").is_none(),
+                "Releasing an expired lock shouldn't have released its next holder");
+        locks.release(next_lock).await.expect("Couldn't release");
+    }
+
+    /// assures ttls not reaching 1ms -- which Redis refuses -- are refused by any backend
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn zero_ttl() {
+        let locks = Locks::new(&LocksBackend::Local).await.expect("Couldn't create the locks");
+        assert!(locks.acquire("resource-x", Duration::ZERO).await.is_err(), "A zero ttl should have been refused");
+        assert!(locks.try_acquire("resource-x", Duration::from_micros(999)).await.is_err(), "A sub-millisecond ttl should have been refused");
+    }
+}
@@ -0,0 +1,175 @@
+//! Installs the recorder for the `metrics` facade -- through which any module may publish counters, gauges & histograms
+//! (see the socket server's processors for an example) -- rendered in the OpenMetrics text format at `/api/metrics`.\
+//! The recorder is the metrics registry: [metrics_report()] lists everything registered in it -- as served, in JSON, by the `web::stats` routes.
+
+use super::{describe_config_metrics, describe_supervisor_metrics, describe_event_bus_metrics, describe_job_queue_metrics, describe_self_monitor_metrics};
+use crate::frontend::socket_server;
+use std::collections::BTreeMap;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle, Matcher};
+use serde::Serialize;
+
+
+/// Bucket upper bounds, in seconds, for all histograms whose names end in `_seconds` -- from 10µs to 10s
+const LATENCY_BUCKETS: &[f64] = &[0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0];
+
+
+/// installs the global metrics recorder, returning the handle through which its metrics may be rendered.\
+/// Fails if a recorder was already installed
+pub fn install_metrics_recorder() -> Result<PrometheusHandle, Box<dyn std::error::Error + Sync + Send>> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix(String::from("_seconds")), LATENCY_BUCKETS)
+        .map_err(|err| format!("Could not set the latency buckets for the metrics recorder: {}", err))?
+        .install_recorder()
+        .map_err(|err| format!("Could not install the metrics recorder: {}", err))?;
+    socket_server::describe_stats_metrics();
+    socket_server::describe_processor_metrics();
+    socket_server::describe_egress_metrics();
+    describe_config_metrics();
+    describe_supervisor_metrics();
+    describe_event_bus_metrics();
+    describe_job_queue_metrics();
+    describe_self_monitor_metrics();
+    Ok(handle)
+}
+
+/// A metric registered in the recorder, with all its (labeled) values
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricFamily {
+    pub name:    String,
+    /// `counter`, `gauge`, `histogram`, `summary` or `untyped`
+    pub kind:    String,
+    pub help:    Option<String>,
+    pub samples: Vec<LabeledValue>,
+}
+
+/// One of the values of a [MetricFamily] -- histograms & summaries have several, with the `_bucket`, `_sum` & `_count` suffixes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabeledValue {
+    pub name:   String,
+    pub labels: BTreeMap<String, String>,
+    /// `NaN` & infinite values are serialized as `null`
+    pub value:  f64,
+}
+
+/// all metrics currently registered in the recorder behind `metrics`
+pub fn metrics_report(metrics: &PrometheusHandle) -> Vec<MetricFamily> {
+    parse_exposition(&metrics.render())
+}
+
+/// parses the Prometheus text exposition format, as rendered by the recorder -- lines not understood are skipped
+fn parse_exposition(text: &str) -> Vec<MetricFamily> {
+    let mut families: Vec<MetricFamily> = Vec::new();
+    let family_for = |families: &mut Vec<MetricFamily>, name: &str| -> usize {
+        families.iter().position(|family| family.name == name)
+            .unwrap_or_else(|| {
+                families.push(MetricFamily { name: name.to_string(), kind: String::from("untyped"), help: None, samples: Vec::new() });
+                families.len() - 1
+            })
+    };
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(help) = line.strip_prefix("# HELP ") {
+            let (name, help) = help.split_once(' ').unwrap_or((help, ""));
+            let index = family_for(&mut families, name);
+            families[index].help = Some(help.replace("\\n", "\n").replace("\\\\", "\\"));
+        } else if let Some(kind) = line.strip_prefix("# TYPE ") {
+            let Some((name, kind)) = kind.split_once(' ') else { continue };
+            let index = family_for(&mut families, name);
+            families[index].kind = kind.to_string();
+        } else if !line.starts_with('#') {
+            let Some(sample) = parse_sample(line) else { continue };
+            // `_bucket`, `_sum` & `_count` belong to the family without the suffix
+            let family_name = ["_bucket", "_sum", "_count"].iter()
+                .filter_map(|suffix| sample.name.strip_suffix(suffix))
+                .find(|family_name| families.iter().any(|family| &family.name == family_name))
+                .unwrap_or(&sample.name)
+                .to_string();
+            let index = family_for(&mut families, &family_name);
+            families[index].samples.push(sample);
+        }
+    }
+    families
+}
+
+/// parses `name{label="value",...} value` -- the labels being optional
+fn parse_sample(line: &str) -> Option<LabeledValue> {
+    let (name, mut rest) = match line.find(|c: char| c == '{' || c == ' ') {
+        Some(position) => (&line[..position], &line[position..]),
+        None => return None,
+    };
+    let mut labels = BTreeMap::new();
+    if let Some(labels_text) = rest.strip_prefix('{') {
+        let mut chars = labels_text.char_indices();
+        let mut label_name = String::new();
+        loop {
+            let (position, c) = chars.next()?;
+            match c {
+                '}' => {
+                    rest = &labels_text[position + 1..];
+                    break
+                },
+                ',' | ' ' => (),
+                '=' => {
+                    if chars.next()?.1 != '"' {
+                        return None
+                    }
+                    let mut label_value = String::new();
+                    loop {
+                        match chars.next()?.1 {
+                            '"'  => break,
+                            '\\' => match chars.next()?.1 {
+                                'n' => label_value.push('\n'),
+                                escaped => label_value.push(escaped),
+                            },
+                            c => label_value.push(c),
+                        }
+                    }
+                    labels.insert(std::mem::take(&mut label_name), label_value);
+                },
+                c => label_name.push(c),
+            }
+        }
+    }
+    // an optional timestamp may follow the value
+    let value = rest.split_whitespace().next()?;
+    let value = match value {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        value  => value.parse().ok()?,
+    };
+    Some(LabeledValue { name: name.to_string(), labels, value })
+}
+
+
+/// Unit tests the [metrics](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn exposition_parsing() {
+        let text = "# HELP config_info Always 1\n\
+                    # TYPE config_info gauge\n\
+                    config_info{digest=\"a\\\"b\"} 1\n\
+                    \n\
+                    # TYPE socket_egress_delay_seconds histogram\n\
+                    socket_egress_delay_seconds_bucket{limit=\"global\",le=\"0.5\"} 3\n\
+                    socket_egress_delay_seconds_bucket{limit=\"global\",le=\"+Inf\"} 4\n\
+                    socket_egress_delay_seconds_sum{limit=\"global\"} 1.25\n\
+                    socket_egress_delay_seconds_count{limit=\"global\"} 4\n\
+                    requests_total 42\n";
+        let families = parse_exposition(text);
+        assert_eq!(families.len(), 3, "Wrong number of metric families: {:?}", families);
+        assert_eq!(families[0].kind, "gauge");
+        assert_eq!(families[0].help.as_deref(), Some("Always 1"));
+        assert_eq!(families[0].samples[0].labels.get("digest").map(String::as_str), Some("a\"b"), "Escaped label values weren't unescaped");
+        assert_eq!(families[1].samples.len(), 4, "Histogram values should have been grouped in their family");
+        assert_eq!(families[1].samples[1].labels.get("le").map(String::as_str), Some("+Inf"));
+        assert_eq!(families[1].samples[1].value, 4.0);
+        assert_eq!(families[1].samples[2], LabeledValue { name: String::from("socket_egress_delay_seconds_sum"),
+                                                          labels: BTreeMap::from([(String::from("limit"), String::from("global"))]),
+                                                          value: 1.25 });
+        assert_eq!(families[2].kind, "untyped", "Metrics without `# TYPE` should be untyped");
+        assert_eq!(families[2].samples[0].value, 42.0);
+    }
+}
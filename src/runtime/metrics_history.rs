@@ -0,0 +1,135 @@
+//! In-process history of key metrics -- see [crate::config::Config::metrics_history]: each tracked metric (see [MetricsHistory::track()])
+//! is sampled at a fixed resolution into a bounded ring buffer, so the recent trends may be charted without deploying Prometheus: through
+//! `/api/stats/history` & on the metrics of the Terminal UI & of the egui "Runtime" window -- see [crate::frontend::runtime_status()].
+//! Nothing is persisted: the history starts over when the application is restarted.
+
+use crate::config::MetricsHistoryConfig;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::Duration,
+};
+use parking_lot::RwLock;
+use serde::Serialize;
+use log::debug;
+
+
+/// A sample of a metric
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricSample {
+    /// seconds since the Unix epoch
+    pub timestamp: i64,
+    pub value:     f64,
+}
+
+/// A tracked metric, along with its samples
+struct TrackedMetric {
+    name:    String,
+    probe:   Box<dyn Fn() -> f64 + Send + Sync>,
+    /// the oldest at the front
+    samples: VecDeque<MetricSample>,
+}
+
+/// The metrics history & its sampler. Usage:
+/// ```no_compile
+///     metrics_history.track("socket_connected_clients", move || socket_server_stats.report().endpoints.iter().filter(|e| e.connected).count() as f64);
+///     metrics_history.start_sampling();
+///     let samples = metrics_history.history("socket_connected_clients", None);
+pub struct MetricsHistory {
+    resolution: Duration,
+    /// how many samples to keep for each metric
+    capacity:   usize,
+    metrics:    RwLock<Vec<TrackedMetric>>,
+}
+
+impl MetricsHistory {
+
+    pub fn new(config: &MetricsHistoryConfig) -> Self {
+        let resolution_seconds = config.resolution_seconds.max(1);
+        Self {
+            resolution: Duration::from_secs(resolution_seconds),
+            capacity:   (config.retention_seconds / resolution_seconds).max(1) as usize,
+            metrics:    RwLock::new(Vec::new()),
+        }
+    }
+
+    /// starts keeping the history of the metric `name`, whose current value is told by `probe` -- replacing any previous metric with the same name.\
+    /// Counters are recorded as they are: rates are to be computed by whoever charts them
+    pub fn track(&self, name: &str, probe: impl Fn() -> f64 + Send + Sync + 'static) {
+        let mut metrics = self.metrics.write();
+        metrics.retain(|metric| metric.name != name);
+        metrics.push(TrackedMetric {
+            name:    name.to_string(),
+            probe:   Box::new(probe),
+            samples: VecDeque::with_capacity(self.capacity),
+        });
+    }
+
+    /// spawns the task sampling the tracked metrics at the configured resolution
+    pub fn start_sampling(self: &Arc<Self>) {
+        debug!("Metrics history: sampling {} metric(s) every {:?}", self.metrics.read().len(), self.resolution);
+        let metrics_history = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(metrics_history.resolution);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                metrics_history.sample(chrono::Utc::now().timestamp());
+            }
+        });
+    }
+
+    /// the names of the tracked metrics
+    pub fn names(&self) -> Vec<String> {
+        self.metrics.read().iter()
+            .map(|metric| metric.name.clone())
+            .collect()
+    }
+
+    /// the samples of metric `name` -- the oldest first -- taken from `since` (seconds since the Unix epoch), if given.\
+    /// `None` if no such metric is tracked
+    pub fn history(&self, name: &str, since: Option<i64>) -> Option<Vec<MetricSample>> {
+        self.metrics.read().iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.samples.iter()
+                .filter(|sample| since.is_none_or(|since| sample.timestamp >= since))
+                .copied()
+                .collect())
+    }
+
+    /// records, as of `timestamp`, the current value of all the tracked metrics -- forgetting the oldest samples beyond the retention
+    fn sample(&self, timestamp: i64) {
+        for metric in self.metrics.write().iter_mut() {
+            if metric.samples.len() >= self.capacity {
+                metric.samples.pop_front();
+            }
+            let value = (metric.probe)();
+            metric.samples.push_back(MetricSample { timestamp, value });
+        }
+    }
+}
+
+
+/// Unit tests the [metrics_history](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn retention_is_bounded() {
+        let metrics_history = MetricsHistory::new(&MetricsHistoryConfig { resolution_seconds: 10, retention_seconds: 30 });
+        let counter = Arc::new(AtomicU64::new(0));
+        let probed_counter = Arc::clone(&counter);
+        metrics_history.track("counter", move || probed_counter.fetch_add(1, Ordering::Relaxed) as f64);
+        for timestamp in [10, 20, 30, 40, 50] {
+            metrics_history.sample(timestamp);
+        }
+        let values = |samples: Vec<MetricSample>| samples.iter().map(|sample| (sample.timestamp, sample.value)).collect::<Vec<_>>();
+        assert_eq!(values(metrics_history.history("counter", None).unwrap()), vec![(30, 2.0), (40, 3.0), (50, 4.0)], "Only the 3 newest samples should be kept");
+        assert_eq!(values(metrics_history.history("counter", Some(40)).unwrap()), vec![(40, 3.0), (50, 4.0)], "`since` wasn't honored");
+        assert_eq!(metrics_history.history("unknown", None), None, "Untracked metrics should have no history");
+        assert_eq!(metrics_history.names(), vec!["counter".to_string()], "Wrong tracked metrics");
+    }
+}
@@ -8,6 +8,87 @@
 //!   * Counters / Metrics / Reports  -- maybe for metrics you'd better use one of the dedicated crates... but you'd define your Job reports here
 //!   * Controllers                   -- for instance, handlers for Telegram / Rocket to send push messages and request shutdown
 //!   * Injections & globals          -- if you really want it, you may place them here
+//!
+//! Tests may run the event bus, the scheduler & the logic in a deterministic simulation -- on a simulated [Clock] -- see [simulation::simulate()].
 
 mod runtime;
-pub use runtime::*;
\ No newline at end of file
+pub use runtime::*;
+
+mod leader_election;
+pub use leader_election::LeaderElection;
+
+mod locks;
+pub use locks::Locks;
+
+mod degrade;
+pub use degrade::{DegradeSwitches, SwitchesReport, SWITCHES};
+
+mod clock;
+pub use clock::{Clock, SystemClock, apply_timezone};
+#[cfg(any(test, feature = "dox"))]
+pub use clock::SimulatedClock;
+
+#[cfg(any(test, feature = "dox"))]
+mod simulation;
+
+mod clock_monitor;
+pub use clock_monitor::monitor_clock_jumps;
+
+pub mod procfs;
+
+mod self_monitor;
+pub use self_monitor::{SelfMonitor, ResourcesSample, describe_self_monitor_metrics};
+
+mod metrics;
+pub use metrics::{install_metrics_recorder, metrics_report, MetricFamily};
+
+mod metrics_history;
+pub use metrics_history::{MetricsHistory, MetricSample};
+
+mod job_accounting;
+pub use job_accounting::{JobAccounting, JobRun};
+
+mod config_drift;
+pub use config_drift::{monitor_config_drift, describe_config_metrics};
+
+mod warmup;
+pub use warmup::{Warmup, WarmupReport, WarmupStatus};
+
+mod auth;
+pub use auth::{AuthProvider, AuthResult, Principal, OPERATOR_ROLE, new_auth_provider, constant_time_eq, SessionTokens, SessionToken, WithSessionTokens, SESSION_TOKEN_TTL};
+
+mod job_queue;
+pub use job_queue::{JobQueue, JobQueueReport, describe_job_queue_metrics};
+
+mod correlation;
+pub use correlation::{correlated, correlation_id, new_correlation_id, sanitized_correlation_id, CORRELATION_ID_HEADER};
+
+mod events;
+pub use events::{EventBus, Topic, PublishedEvent, Correlated, describe_event_bus_metrics, RECENT_EVENTS};
+
+mod alerts;
+pub use alerts::{Alert, alerts_topic};
+
+mod webhooks;
+pub use webhooks::{Webhooks, Delivery, verify_hmac_sha256_hex};
+
+mod privileges;
+pub use privileges::{drop_privileges, PrivilegesGate};
+
+mod daemon;
+pub use daemon::daemonize;
+
+mod storage;
+pub use storage::Storage;
+
+mod outbox;
+pub use outbox::{Outbox, OutboxIntent, OutboxEntry};
+
+mod shutdown;
+pub use shutdown::{Shutdown, handle_signals};
+
+mod config_reload;
+pub use config_reload::{ConfigReload, ConfigEdit, EditableField, EDITABLE_FIELDS, watch_config_file};
+
+mod supervisor;
+pub use supervisor::{Supervisor, describe_supervisor_metrics};
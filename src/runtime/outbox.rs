@@ -0,0 +1,304 @@
+//! Outbox for the notifications that must not be lost: the business logic [Outbox::enqueue()]s its intents -- Telegram messages & webhook
+//! events -- which are written to the [Storage] (& saved) before anything is sent, then delivered by a background dispatcher -- see
+//! [Outbox::run()] -- retried, with exponential backoff, until they are taken: at-least-once delivery, even across crashes & restarts.\
+//! Each intent carries an idempotency key: enqueueing it again is a no-op & webhook targets receive it in the `Idempotency-Key` header, so
+//! they may discard repetitions. Intents still failing after the configured attempts are kept, as failed, to be inspected & requeued (or
+//! discarded) through the admin API -- `/api/admin/outbox`. Usage:
+//! ```no_compile
+//!     outbox.enqueue(OutboxIntent::telegram("Order #42 was paid", false), "order-42-paid")?;
+//!     outbox.enqueue(OutboxIntent::Webhook { event: String::from("order.paid"), payload: serde_json::to_string(&order)? }, "order-42-paid")?;
+//! ```
+//! The [super::SelfMonitor] sends its Telegram alerts through here -- so the ones announcing a restart aren't lost to it.
+
+use super::{Runtime, Storage, correlated, correlation_id};
+use crate::config::OutboxConfig;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::Duration,
+};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use tokio::sync::{Notify, RwLock};
+use log::{debug, info, warn, error};
+
+
+/// The intents are stored under this prefix, followed by their zero padded ids -- so they are listed in order
+const ENTRIES_PREFIX: &str = "outbox/";
+/// Storage key of the last id given to an intent
+const LAST_ID_KEY: &str = "outbox-last-id";
+/// Storage key of the idempotency keys of the most recently delivered intents -- the newest at the back
+const DELIVERED_KEYS_KEY: &str = "outbox-delivered-keys";
+
+
+/// What should be delivered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OutboxIntent {
+    /// a message to the notification chats of the Telegram `bot` -- the primary one if `None`.
+    /// See [crate::frontend::telegram::TelegramUI::broadcast_message()]
+    Telegram { bot: Option<String>, message: String, html: bool },
+    /// an event POSTed to the interested webhook targets, with the JSON `payload` -- see [super::Webhooks::deliver_once()]
+    Webhook { event: String, payload: String },
+}
+
+impl OutboxIntent {
+
+    /// a message to the notification chats of the primary Telegram bot
+    pub fn telegram(message: impl Into<String>, html: bool) -> Self {
+        Self::Telegram { bot: None, message: message.into(), html }
+    }
+}
+
+/// An intent yet to be delivered -- as stored & listed by `/api/admin/outbox`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id:                  u64,
+    pub idempotency_key:     String,
+    pub intent:              OutboxIntent,
+    /// when the intent was enqueued
+    pub timestamp:           String,
+    /// the correlation id in scope when the intent was enqueued -- back in scope while delivering it. See [super::correlation]
+    #[serde(default)]
+    pub correlation_id:      Option<String>,
+    pub attempts:            u32,
+    /// when (in milliseconds since the Unix epoch) the next attempt is due
+    pub next_attempt_millis: i64,
+    /// present if the last attempt failed
+    pub last_error:          Option<String>,
+    /// `true` once the configured attempts are exhausted -- then, it is only tried again if requeued
+    pub failed:              bool,
+}
+
+/// The outbox & its dispatcher -- see [Outbox::enqueue()] & [Outbox::run()]
+pub struct Outbox {
+    config:               OutboxConfig,
+    storage:              Arc<Storage>,
+    /// the bot sending the [OutboxIntent::Telegram] messages not addressed to a specific one
+    primary_telegram_bot: Option<String>,
+    /// serializes the enqueueing, so the same idempotency key can't sneak in twice
+    enqueueing:           Mutex<()>,
+    /// wakes the dispatcher up when intents are enqueued or requeued
+    wake_up:              Notify,
+}
+
+impl Outbox {
+
+    pub fn new(config: &OutboxConfig, storage: Arc<Storage>, primary_telegram_bot: Option<String>) -> Self {
+        let outbox = Self {
+            config: config.clone(),
+            storage,
+            primary_telegram_bot,
+            enqueueing: Mutex::new(()),
+            wake_up:    Notify::new(),
+        };
+        let pending = outbox.storage.keys(ENTRIES_PREFIX).len();
+        if pending > 0 {
+            info!("Outbox: {} intent(s) left undelivered by previous runs will be dispatched", pending);
+        }
+        outbox
+    }
+
+    /// Persists `intent` -- saving the storage -- to be delivered by the dispatcher. Returns `false` (doing nothing) if an intent with the same
+    /// `idempotency_key` is still in the outbox or was recently delivered -- see [OutboxConfig::delivered_keys_kept]
+    pub fn enqueue(&self, intent: OutboxIntent, idempotency_key: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let _enqueueing = self.enqueueing.lock();
+        let delivered_keys: VecDeque<String> = self.storage.get(DELIVERED_KEYS_KEY)?.unwrap_or_default();
+        if delivered_keys.iter().any(|delivered_key| delivered_key == idempotency_key) ||
+           self.entries()?.iter().any(|entry| entry.idempotency_key == idempotency_key) {
+            debug!("Outbox: ignoring the repeated intent '{}'", idempotency_key);
+            return Ok(false)
+        }
+        let id = self.storage.update(LAST_ID_KEY, |last_id: Option<u64>| Some(last_id.unwrap_or(0) + 1))?
+            .expect("BUG! the id updater never removes it");
+        self.storage.put(&entry_key(id), &OutboxEntry {
+            id,
+            idempotency_key:     idempotency_key.to_string(),
+            intent,
+            timestamp:           chrono::Local::now().to_rfc3339(),
+            correlation_id:      correlation_id(),
+            attempts:            0,
+            next_attempt_millis: now_millis(),
+            last_error:          None,
+            failed:              false,
+        })?;
+        self.storage.save()?;
+        self.wake_up.notify_one();
+        Ok(true)
+    }
+
+    /// The intents yet to be delivered -- including the failed ones -- the oldest first
+    pub fn entries(&self) -> Result<Vec<OutboxEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        self.storage.keys(ENTRIES_PREFIX).iter()
+            .filter_map(|key| self.storage.get::<OutboxEntry>(key).transpose())
+            .collect()
+    }
+
+    /// Makes the intent with the given `id` due right away, with its attempts reset -- telling if it is still in the outbox
+    pub fn requeue(&self, id: u64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let requeued = self.storage.update(&entry_key(id), |entry: Option<OutboxEntry>| entry.map(|entry| OutboxEntry {
+            attempts:            0,
+            next_attempt_millis: now_millis(),
+            failed:              false,
+            ..entry
+        }))?.is_some();
+        if requeued {
+            self.storage.save()?;
+            self.wake_up.notify_one();
+        }
+        Ok(requeued)
+    }
+
+    /// Gives up on delivering the intent with the given `id` -- telling if it was still in the outbox
+    pub fn discard(&self, id: u64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let discarded = self.storage.remove(&entry_key(id));
+        if discarded {
+            self.storage.save()?;
+        }
+        Ok(discarded)
+    }
+
+    /// The dispatcher: delivers the due intents -- as they are enqueued & every [OutboxConfig::poll_interval_millis] -- until the process ends
+    pub async fn run(self: Arc<Self>, runtime: Arc<RwLock<Runtime>>) {
+        let poll_interval = Duration::from_millis(self.config.poll_interval_millis);
+        loop {
+            match self.entries() {
+                Ok(entries) => {
+                    let now = now_millis();
+                    for entry in entries.into_iter().filter(|entry| !entry.failed && entry.next_attempt_millis <= now) {
+                        correlated(entry.correlation_id.clone(), self.dispatch(entry, &runtime)).await;
+                    }
+                },
+                Err(err) => error!("Outbox: could not read the intents: {}", err),
+            }
+            // woken up earlier if something is enqueued
+            tokio::time::timeout(poll_interval, self.wake_up.notified()).await.ok();
+        }
+    }
+
+    /// attempts to deliver `entry`, recording the outcome -- to be called with its correlation id in scope
+    async fn dispatch(&self, mut entry: OutboxEntry, runtime: &RwLock<Runtime>) {
+        let key = entry_key(entry.id);
+        entry.attempts += 1;
+        let recorded = match self.deliver(&entry.intent, &entry.idempotency_key, runtime).await {
+            Ok(()) => {
+                debug!("Outbox: intent #{} ('{}') delivered on attempt {}", entry.id, entry.idempotency_key, entry.attempts);
+                self.storage.remove(&key);
+                let delivered_keys_kept = self.config.delivered_keys_kept;
+                self.storage.update(DELIVERED_KEYS_KEY, |delivered_keys: Option<VecDeque<String>>| {
+                    let mut delivered_keys = delivered_keys.unwrap_or_default();
+                    delivered_keys.push_back(entry.idempotency_key.clone());
+                    while delivered_keys.len() > delivered_keys_kept {
+                        delivered_keys.pop_front();
+                    }
+                    Some(delivered_keys)
+                }).map(|_| ())
+            },
+            Err(err) => {
+                entry.last_error = Some(err.to_string());
+                if entry.attempts >= self.config.max_attempts.max(1) {
+                    entry.failed = true;
+                    error!("Outbox: intent #{} ('{}') failed after {} attempts -- requeue it through the admin API once the cause is fixed: {}",
+                           entry.id, entry.idempotency_key, entry.attempts, err);
+                } else {
+                    let retry_delay = self.config.retry_delay_millis.saturating_mul(1 << (entry.attempts - 1).min(20));
+                    entry.next_attempt_millis = now_millis().saturating_add(retry_delay as i64);
+                    warn!("Outbox: attempt {} of {} to deliver intent #{} ('{}') failed -- retrying in {}ms: {}",
+                          entry.attempts, self.config.max_attempts, entry.id, entry.idempotency_key, retry_delay, err);
+                }
+                // (unless it was discarded in the meantime)
+                self.storage.update(&key, |stored: Option<OutboxEntry>| stored.map(|_| entry)).map(|_| ())
+            },
+        };
+        if let Err(err) = recorded.and_then(|_| self.storage.save()) {
+            error!("Outbox: could not record the outcome of an attempt to deliver an intent: {}", err);
+        }
+    }
+
+    async fn deliver(&self, intent: &OutboxIntent, idempotency_key: &str, runtime: &RwLock<Runtime>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match intent {
+            OutboxIntent::Telegram { bot, message, html } => {
+                let bot = bot.as_ref().or(self.primary_telegram_bot.as_ref())
+                    .ok_or("Outbox: there is no Telegram bot to send the message through -- see `services.telegram` in the config file")?;
+                let (message, html) = (message.clone(), *html);
+                Runtime::do_if_telegram_ui_is_present(runtime, bot, |telegram_ui| Box::pin(async move { telegram_ui.broadcast_message(&message, html).await })).await
+                    .unwrap_or_else(|| Err(Box::from(format!("Outbox: the Telegram bot '{}' is not running", bot))))
+            },
+            OutboxIntent::Webhook { event, payload } => {
+                let webhooks = runtime.read().await.webhooks.clone()
+                    .ok_or("Outbox: webhooks are disabled -- see `webhooks` in the config file")?;
+                webhooks.deliver_once(event, payload, idempotency_key).await
+            },
+        }
+    }
+}
+
+fn entry_key(id: u64) -> String {
+    format!("{}{:020}", ENTRIES_PREFIX, id)
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+
+/// Unit tests the [outbox](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{StorageConfig, DegradeConfig},
+        runtime::DegradeSwitches,
+        frontend::socket_server::SocketServerStats,
+    };
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn undeliverable_intents_are_kept_for_requeueing() {
+        let storage_config = StorageConfig {
+            file_path:                 String::from("/tmp/kickass-app-template.outbox-test.ron"),
+            autosave_interval_seconds: 0,
+        };
+        std::fs::remove_file(&storage_config.file_path).ok();
+        let outbox_config = OutboxConfig {
+            poll_interval_millis: 10,
+            max_attempts:         2,
+            retry_delay_millis:   10,
+            delivered_keys_kept:  10,
+        };
+        let outbox = Arc::new(Outbox::new(&outbox_config, Arc::new(Storage::new(&storage_config).expect("Could not create the storage")), None));
+        assert!(outbox.enqueue(OutboxIntent::telegram("hello", false), "greeting").expect("Could not enqueue"), "The intent should have been enqueued");
+        assert!(!outbox.enqueue(OutboxIntent::telegram("hello again", false), "greeting").expect("Could not enqueue"), "Repeated idempotency keys should be ignored");
+
+        // intents survive restarts
+        let storage = Arc::new(Storage::new(&storage_config).expect("Could not reload the storage"));
+        let outbox = Arc::new(Outbox::new(&outbox_config, storage, None));
+        let entries = outbox.entries().expect("Could not list the intents");
+        assert_eq!(entries.len(), 1, "Wrong number of intents after the restart");
+        assert_eq!(entries[0].intent, OutboxIntent::telegram("hello", false), "Wrong intent after the restart");
+
+        // with no Telegram bot nor webhooks, deliveries fail until the attempts are exhausted
+        outbox.enqueue(OutboxIntent::Webhook { event: String::from("order.paid"), payload: String::from("42") }, "order-42-paid").expect("Could not enqueue");
+        let degrade_config = DegradeConfig { disable_telegram_notifications: false, read_only_api: false, maintenance: false, maintenance_message: String::new() };
+        let runtime = Arc::new(RwLock::new(Runtime::new(String::from("test"), String::new(), DegradeSwitches::new(&degrade_config), SocketServerStats::new(0))));
+        let dispatcher = tokio::spawn(Arc::clone(&outbox).run(Arc::clone(&runtime)));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        dispatcher.abort();
+        let entries = outbox.entries().expect("Could not list the intents");
+        assert!(entries.iter().all(|entry| entry.failed && entry.attempts == 2 && entry.last_error.is_some()),
+                "Undeliverable intents should be marked as failed after `max_attempts`: {:?}", entries);
+
+        // requeued intents are tried again
+        assert!(outbox.requeue(entries[1].id).expect("Could not requeue"), "The intent should have been requeued");
+        let requeued = &outbox.entries().expect("Could not list the intents")[1];
+        assert!(!requeued.failed && requeued.attempts == 0, "Requeued intents should have their attempts reset: {:?}", requeued);
+        let dispatcher = tokio::spawn(Arc::clone(&outbox).run(runtime));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        dispatcher.abort();
+        let requeued = &outbox.entries().expect("Could not list the intents")[1];
+        assert!(requeued.failed && requeued.attempts == 2, "The requeued intent should have been tried again: {:?}", requeued);
+
+        assert!(outbox.discard(entries[0].id).expect("Could not discard"), "The intent should have been discarded");
+        assert_eq!(outbox.entries().expect("Could not list the intents").len(), 1, "Wrong number of intents after discarding one");
+        std::fs::remove_file(&storage_config.file_path).ok();
+    }
+}
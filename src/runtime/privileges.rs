@@ -0,0 +1,136 @@
+//! Privilege dropping -- see [crate::config::Config::run_as]: the services bind their ports as root (so 80 & 443 may be used),
+//! then the process switches to the configured unprivileged user & group for the rest of its life.\
+//! Starting is, so, split in two phases around the switch: binding -- the services, up to listening -- & serving -- the socket server's
+//! processors, `async_main()` (with the scheduler, the job queue's workers, the storage & the webhooks) & the `logic::warmup` hooks,
+//! which wait for the [PrivilegesGate] to open.\
+//! Unix only: on other platforms, enabling `run_as` is an error.
+
+use crate::config::{ExtendedOption, RunAsConfig};
+use std::sync::atomic::{AtomicU8, Ordering};
+use tokio::sync::Notify;
+use log::{info, warn};
+
+
+/// [PrivilegesGate::state] values
+const CLOSED: u8 = 0;
+const OPENED: u8 = 1;
+const FAILED: u8 = 2;
+
+
+/// Holds whatever runs the business logic until the services are bound & the privileges dropped -- see [drop_privileges()]
+pub struct PrivilegesGate {
+    state:  AtomicU8,
+    notify: Notify,
+}
+
+impl PrivilegesGate {
+
+    pub fn new() -> Self {
+        Self {
+            state:  AtomicU8::new(CLOSED),
+            notify: Notify::new(),
+        }
+    }
+
+    /// the privileges were dropped (or there were none to drop): wakes up everyone in [Self::wait()]
+    pub fn open(&self) {
+        self.settle(OPENED);
+    }
+
+    /// the privileges could not be dropped & the application is shutting down: wakes up everyone in [Self::wait()], telling them not to go on
+    pub fn fail(&self) {
+        self.settle(FAILED);
+    }
+
+    /// returns once the gate is [opened](Self::open()) -- `true` -- or [failed](Self::fail()) -- `false` -- immediately, if it already was
+    pub async fn wait(&self) -> bool {
+        // created before checking, so a settlement made in between is not missed
+        let notified = self.notify.notified();
+        match self.state.load(Ordering::Relaxed) {
+            CLOSED => {
+                notified.await;
+                self.state.load(Ordering::Relaxed) == OPENED
+            },
+            state => state == OPENED,
+        }
+    }
+
+    fn settle(&self, state: u8) {
+        if self.state.compare_exchange(CLOSED, state, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+            self.notify.notify_waiters();
+        }
+    }
+}
+
+
+/// switches the process to the user & group in `run_as` -- to be called once all the services are listening.\
+/// Fails (and the application should not go on) if the switch could not be done or if the process would still be running as root
+/// without [RunAsConfig::allow_root]. Just warns if running as root without `run_as`.
+pub fn drop_privileges(run_as: &ExtendedOption<RunAsConfig>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match run_as {
+        ExtendedOption::Enabled(run_as) => switch_user(run_as),
+        _ => {
+            if is_root() {
+                warn!("Running as root: consider setting `run_as` in the config file, so the services run unprivileged once their ports are bound");
+            }
+            Ok(())
+        },
+    }
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    nix::unistd::geteuid().is_root()
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn switch_user(run_as: &RunAsConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    use nix::unistd::{self, User, Group, Uid};
+    use std::ffi::CString;
+
+    let user = User::from_name(&run_as.user)
+        .map_err(|err| format!("Could not look up the `run_as` user '{}': {}", run_as.user, err))?
+        .ok_or_else(|| format!("The `run_as` user '{}' doesn't exist", run_as.user))?;
+    let gid = match &run_as.group {
+        Some(group_name) => Group::from_name(group_name)
+            .map_err(|err| format!("Could not look up the `run_as` group '{}': {}", group_name, err))?
+            .ok_or_else(|| format!("The `run_as` group '{}' doesn't exist", group_name))?
+            .gid,
+        None => user.gid,
+    };
+
+    if unistd::geteuid().is_root() {
+        // the order matters: once the user is switched, the groups may no longer be changed
+        let user_name = CString::new(user.name.as_str())
+            .map_err(|err| format!("Invalid `run_as` user name '{}': {}", user.name, err))?;
+        unistd::initgroups(&user_name, gid)
+            .map_err(|err| format!("Could not set the supplementary groups of the `run_as` user '{}': {}", user.name, err))?;
+        unistd::setgid(gid)
+            .map_err(|err| format!("Could not switch to the `run_as` group {}: {}", gid, err))?;
+        unistd::setuid(user.uid)
+            .map_err(|err| format!("Could not switch to the `run_as` user '{}': {}", user.name, err))?;
+    } else if unistd::geteuid() != user.uid {
+        return Err(Box::from(format!("Only root may switch to the `run_as` user '{}' -- the application was started by uid {}", user.name, unistd::geteuid())));
+    }
+
+    // safety checks
+    if (unistd::getuid().is_root() || unistd::geteuid().is_root()) && !run_as.allow_root {
+        return Err(Box::from(format!("Refusing to go on as root after switching to the `run_as` user '{}' -- set `allow_root` if this is intended", user.name)));
+    }
+    if !user.uid.is_root() && unistd::setuid(Uid::from_raw(0)).is_ok() {
+        return Err(Box::from(format!("Root privileges could be regained after switching to the `run_as` user '{}'", user.name)));
+    }
+
+    info!("Privileges dropped: now running as user '{}' (uid {}), group {}", user.name, user.uid, gid);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn switch_user(run_as: &RunAsConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    Err(Box::from(format!("`run_as` (user '{}') is only supported on Unix -- disable it in the config file", run_as.user)))
+}
@@ -0,0 +1,94 @@
+//! Readers of the process' own counters in Linux's `/proc` -- shared by the [super::SelfMonitor], the [super::JobAccounting] & the
+//! web dashboard's stats. Elsewhere, they all answer `None`.
+
+use once_cell::sync::Lazy;
+
+
+/// `sysconf(_SC_CLK_TCK)` -- the unit of the CPU & start times in `/proc/self/stat`. Falls back to 100 (the `USER_HZ` of all
+/// mainstream Linux platforms) if it can't be queried
+pub static CLOCK_TICKS_PER_SECOND: Lazy<u64> = Lazy::new(|| sysconf_clock_ticks().unwrap_or(100));
+
+
+/// the `key`ed value, in KiB (or as a plain count), from `/proc/self/status` -- like "VmRSS:", "VmHWM:" or "Threads:"
+pub fn status_value(key: &str) -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status").ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// the process' resident memory -- "VmRSS:", from `/proc/self/status`
+pub fn resident_memory_bytes() -> Option<u64> {
+    status_value("VmRSS:")
+        .map(|kib| kib * 1024)
+}
+
+/// user + system CPU clock ticks used by this process so far -- fields 14 & 15 of `/proc/self/stat`. See [CLOCK_TICKS_PER_SECOND]
+pub fn cpu_ticks() -> Option<u64> {
+    let mut fields = stat_fields_after_name()?.into_iter().skip(11);
+    let user_ticks: u64 = fields.next()?.parse().ok()?;
+    let system_ticks: u64 = fields.next()?.parse().ok()?;
+    Some(user_ticks + system_ticks)
+}
+
+/// the CPU time -- user + system -- used by the process so far
+pub fn cpu_seconds() -> Option<f64> {
+    cpu_ticks()
+        .map(|cpu_ticks| cpu_ticks as f64 / *CLOCK_TICKS_PER_SECOND as f64)
+}
+
+/// how long this process has been running -- the system's uptime (from `/proc/uptime`) minus the process' start time (field 22 of `/proc/self/stat`)
+pub fn uptime_seconds() -> Option<f64> {
+    let system_uptime: f64 = std::fs::read_to_string("/proc/uptime").ok()?
+        .split_whitespace().next()?
+        .parse().ok()?;
+    let start_ticks: f64 = stat_fields_after_name()?.get(19)?.parse().ok()?;
+    Some(system_uptime - start_ticks / *CLOCK_TICKS_PER_SECOND as f64)
+}
+
+/// the system's 1, 5 & 15 minutes load averages -- from `/proc/loadavg`
+pub fn load_average() -> Option<[f64; 3]> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    let mut averages = loadavg.split_whitespace().map(|average| average.parse().ok());
+    Some([averages.next()??, averages.next()??, averages.next()??])
+}
+
+#[cfg(unix)]
+fn sysconf_clock_ticks() -> Option<u64> {
+    nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK).ok()?
+        .filter(|clock_ticks| *clock_ticks > 0)
+        .map(|clock_ticks| clock_ticks as u64)
+}
+
+#[cfg(not(unix))]
+fn sysconf_clock_ticks() -> Option<u64> {
+    None
+}
+
+/// the fields of `/proc/self/stat` from the 3rd on -- as the 2nd (the executable name) may contain spaces, but is enclosed in parenthesis
+fn stat_fields_after_name() -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    Some(stat.rsplit_once(')')?.1.split_whitespace().map(String::from).collect())
+}
+
+
+/// Unit tests the [procfs](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    /// assures the counters are read on Linux -- & are sane
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn reads_own_counters() {
+        if cfg!(not(target_os = "linux")) {
+            return
+        }
+        assert!(*CLOCK_TICKS_PER_SECOND > 0, "The clock ticks should have been queried");
+        assert!(resident_memory_bytes().is_some_and(|resident_memory_bytes| resident_memory_bytes > 0), "The resident memory should have been read");
+        assert!(status_value("Threads:").is_some_and(|threads| threads >= 1), "The threads count should have been read");
+        assert!(cpu_ticks().is_some(), "The CPU ticks should have been read");
+        assert!(uptime_seconds().is_some_and(|uptime_seconds| uptime_seconds >= 0.0), "The uptime should have been read");
+        assert!(load_average().is_some(), "The load averages should have been read");
+    }
+}
@@ -2,18 +2,25 @@
 
 use crate::{
     config::SocketServerConfig,
+    runtime::{LeaderElection, Locks, DegradeSwitches, EventBus, Webhooks, JobAccounting, JobQueue, MetricsHistory, SelfMonitor, Storage, Outbox, Warmup, Shutdown, PrivilegesGate, ConfigReload, AuthProvider, SessionTokens, WithSessionTokens},
+    scheduler::Scheduler,
     frontend::{
-        telegram::TelegramUI,
-        web::WebServer,
-        socket_server::SocketServer,
+        telegram::{TelegramUI, TelegramNotifier},
+        web::{WebServer, ResponseCache},
+        socket_server::{SocketServer, SocketServerStats, BridgedPeers},
+        socket_client::SocketClient,
+        grpc::GrpcServer,
     },
 };
 use std::{
     sync::Arc,
-    time::{SystemTime,Duration},
+    time::{SystemTime,Duration,Instant},
     ops::DerefMut,
+    any::{Any, TypeId},
+    collections::{HashMap, BTreeMap},
 };
 use futures::future::BoxFuture;
+use metrics_exporter_prometheus::PrometheusHandle;
 use tokio::sync::RwLock;
 use log::debug;
 
@@ -35,6 +42,45 @@ pub struct Runtime {
     /// decisions regarding the need for datasets & amalgamations to be regenerated
     pub executable_path: String,
 
+    /// when this process started -- for the uptime reports
+    pub started_at: Instant,
+
+    /// identifies the effective config this process is running with -- see [crate::config::config_ops::digest()]
+    pub config_digest: String,
+
+    /// graceful degradation switches, checked by the frontends -- see [DegradeSwitches]
+    pub degrade: Arc<DegradeSwitches>,
+
+    /// per-endpoint statistics fed by the socket server, for the UIs to report -- see [SocketServerStats]
+    pub socket_server_stats: Arc<SocketServerStats>,
+
+    /// the hops made to the socket server on behalf of other clients -- like the web gateways' -- so it knows who they serve. See [BridgedPeers]
+    pub socket_server_peers: Arc<BridgedPeers>,
+
+    /// cached answers of the expensive web API endpoints -- to be invalidated by the business logic when the underlying data changes.
+    /// See [ResponseCache]
+    pub response_cache: Arc<ResponseCache>,
+
+    /// resources used by the most recent job runs -- see [JobAccounting]
+    pub job_accounting: Arc<JobAccounting>,
+
+    /// the hooks to run once the services are started & the readiness they determine -- see [Warmup]
+    pub warmup: Arc<Warmup>,
+
+    /// the internal event bus, through which the processors, the business logic & the frontends publish & subscribe to the
+    /// application's events -- see [EventBus]
+    pub events: Arc<EventBus>,
+
+    /// tells if a graceful shutdown was requested (by SIGTERM or SIGINT) -- so long running jobs may stop. See [Shutdown]
+    pub shutdown: Arc<Shutdown>,
+
+    /// opened once the services are bound & the privileges dropped -- holding the processors & `async_main()` until then. See [PrivilegesGate]
+    pub privileges: Arc<PrivilegesGate>,
+
+    /// Present if the `metrics` recorder could be installed -- renders all metrics published through the facade in the OpenMetrics
+    /// text format. See [super::install_metrics_recorder()]
+    pub metrics: Option<PrometheusHandle>,
+
     /// allows calling `tokio_runtime.block_on()`, `tokio_runtime.spawn()`, etc.
     /// on this to run async tasks on sync contexts, although
     /// `futures::executor::block_on()` seems to be faster
@@ -44,15 +90,65 @@ pub struct Runtime {
     // logic
     ////////
 
-    // /// This is the user defined logic component to be injected / shared with other components
-    // your_logic_component: Option<YOUR_LOGIC_COMPONENT>,
+    /// The business logic's singletons, by type -- to be injected / shared with other components.
+    /// See [Self::register_component()] & [Self::do_for_component()]
+    components: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
 
     // internal task communication
     //////////////////////////////
 
-    /// The Telegram controller -- can be used to send push messages & request the telegram service to shutdown
-    /// -- see [TelegramUI]
-    telegram_ui: Option<TelegramUI>,
+    /// Present if [crate::config::Config::leader_election] is enabled -- tells if this instance is the leader
+    /// (the one to run scheduled jobs & singleton tasks) -- see [LeaderElection]
+    pub leader_election: Option<Arc<LeaderElection>>,
+
+    /// Present if [crate::config::Config::locks] is enabled -- allows the business logic to serialize access
+    /// to (external) resources, possibly across instances -- see [Locks]
+    pub locks: Option<Arc<Locks>>,
+
+    /// Present if [crate::config::Config::webhooks] is enabled -- through which the business logic publishes events
+    /// to external integrations -- see [Webhooks]
+    pub webhooks: Option<Arc<Webhooks>>,
+
+    /// Present if [crate::config::Config::auth] is enabled -- to which the frontends delegate authentication & authorization.
+    /// See [AuthProvider]
+    pub auth: Option<Arc<dyn AuthProvider>>,
+
+    /// Present along with [Self::auth] -- the short-lived session tokens issued to authenticated identities, only accepted by the socket
+    /// server's login (see [Self::socket_auth()]). See [SessionTokens]
+    pub session_tokens: Option<Arc<SessionTokens>>,
+
+    /// Present if [crate::config::Config::job_queue] is enabled -- to which the business logic hands durable background work.
+    /// See [JobQueue]
+    pub job_queue: Option<Arc<JobQueue>>,
+
+    /// Present if [crate::config::Config::scheduler] is enabled -- running the business logic's periodic jobs. See [Scheduler]
+    pub scheduler: Option<Arc<Scheduler>>,
+
+    /// Present if [crate::config::Config::metrics_history] is enabled -- keeping the recent history of key metrics, to which
+    /// the business logic may add its own. See [MetricsHistory]
+    pub metrics_history: Option<Arc<MetricsHistory>>,
+
+    /// Present if [crate::config::Config::self_monitor] is enabled -- holding the latest sample of the process' resources. See [SelfMonitor]
+    pub self_monitor: Option<Arc<SelfMonitor>>,
+
+    /// Present if [crate::config::Config::storage] is enabled -- the business logic's persistent state. See [Storage]
+    pub storage: Option<Arc<Storage>>,
+
+    /// Present if [crate::config::Config::outbox] is enabled -- through which the business logic sends the Telegram messages & webhook events
+    /// that must not be lost. See [Outbox]
+    pub outbox: Option<Arc<Outbox>>,
+
+    /// Present if [crate::config::Config::hot_reload] is enabled -- holding the running config, to whose changes components may subscribe.
+    /// See [ConfigReload]
+    pub config_reload: Option<Arc<ConfigReload>>,
+
+    /// Present if [crate::config::ServicesConfig::telegram] is enabled -- through which the business logic sends push messages,
+    /// queued until the Telegram service is running. See [TelegramNotifier]
+    pub telegram_notifier: Option<Arc<TelegramNotifier>>,
+
+    /// The Telegram controllers, by bot name -- can be used to send push messages & request the telegram bots to shutdown
+    /// -- see [TelegramUI]. The configured bots are known (as `None`) before they register -- see [Runtime::expect_telegram_uis()]
+    telegram_uis: BTreeMap<String, Option<TelegramUI>>,
 
     /// The Rocket controller -- can be used to inquiring the running state and to request the service to shutdown
     /// -- See [WebServer]
@@ -62,6 +158,13 @@ pub struct Runtime {
     /// -- See [SocketServer]
     socket_server: Option<SocketServer<'static>>,
 
+    /// The Socket Client controller -- can be used to send requests to the other instance's socket server & to request the service to shutdown
+    /// -- See [SocketClient]
+    socket_client: Option<SocketClient>,
+
+    /// The gRPC service controller -- can be used to inquiring the running state and to request the service to shutdown
+    /// -- See [GrpcServer]
+    grpc_server: Option<GrpcServer>,
 
 }
 
@@ -97,33 +200,7 @@ macro_rules! impl_runtime {
                                            (runtime:  &RwLock<Self>,
                                             callback: impl for<'r> FnOnce(&'r mut $field_type) -> BoxFuture<'r, ReturnType> + Send)
                                            -> ReturnType {
-                let mut start: Option<SystemTime> = None;
-                loop {
-                    if let Ok(runtime) = &mut runtime.try_write() {
-                        if let Some($field_name_ident) = runtime.deref_mut().$field_name_ident.as_mut() {
-                            if let Some(start) = start {
-                                debug!("Runtime: `{}` became available after a {:?} wait", $field_name_str, start.elapsed().unwrap());
-                            }
-                            break callback($field_name_ident).await
-                        }
-                    }
-                    if let Some(_start) = start {
-                        if _start.elapsed().unwrap() > TIMEOUT {
-                            panic!("Could not retrieve `{}` instance: {}",
-                                   $field_name_str,
-                                   if let Ok(_runtime) = &runtime.try_read() {
-                                       format!("it was not registered in `Runtime` even after {:?}", TIMEOUT)
-                                   } else {
-                                       format!("`Runtime` seems to be locked elsewhere for the past {:?}", TIMEOUT)
-                                });
-                        }
-                    } else {
-                        start = Some(SystemTime::now());
-                        debug!("Runtime: `{}` is not (yet?) available. Waiting for up to {:?} for main.rs to finish instantiating it and placing it here with `register_{}()`",
-                               $field_name_str, TIMEOUT, $field_name_str);
-                    }
-                    tokio::time::sleep(POLL_INTERVAL).await;
-                }
+                Self::wait_to_do(runtime, $field_name_str, |runtime| runtime.$field_name_ident.as_mut(), callback).await
             }
 
             /// Similar to [$get_function_name()], but only executes callback if the value is present -- not failing if it is not.
@@ -146,21 +223,240 @@ macro_rules! impl_runtime {
 
 impl Runtime {
 
-    pub fn new(executable_path: String) -> Self {
+    pub fn new(executable_path: String, config_digest: String, degrade: DegradeSwitches, socket_server_stats: SocketServerStats) -> Self {
         Self {
             executable_path,
+            started_at:          Instant::now(),
+            config_digest,
+            degrade:             Arc::new(degrade),
+            socket_server_stats: Arc::new(socket_server_stats),
+            socket_server_peers: Arc::new(BridgedPeers::new()),
+            response_cache:      Arc::new(ResponseCache::new()),
+            job_accounting:      Arc::new(JobAccounting::new()),
+            warmup:              Arc::new(Warmup::new()),
+            events:              Arc::new(EventBus::new()),
+            shutdown:            Arc::new(Shutdown::new()),
+            privileges:          Arc::new(PrivilegesGate::new()),
+            metrics:             None,
             tokio_runtime: None,
-            // your_logic_component:    None,
-            telegram_ui:   None,
+            components:          HashMap::new(),
+            leader_election: None,
+            locks:           None,
+            webhooks:        None,
+            auth:            None,
+            session_tokens:  None,
+            job_queue:       None,
+            scheduler:       None,
+            metrics_history: None,
+            self_monitor:    None,
+            storage:         None,
+            outbox:          None,
+            config_reload:   None,
+            telegram_notifier: None,
+            telegram_uis:  BTreeMap::new(),
             web_server:    None,
             socket_server: None,
+            socket_client: None,
+            grpc_server:   None,
+        }
+    }
+
+    /// The [Self::auth] provider for the socket server's login -- also accepting the [Self::session_tokens] (which the web guards must not)
+    pub fn socket_auth(&self) -> Option<Arc<dyn AuthProvider>> {
+        match (&self.auth, &self.session_tokens) {
+            (Some(auth), Some(session_tokens)) => Some(Arc::new(WithSessionTokens::new(Arc::clone(auth), Arc::clone(session_tokens)))),
+            (auth, _) => auth.clone(),
+        }
+    }
+
+    /// RW-Locks `runtime`, then registers `component` as the singleton of its type -- replacing any previous one -- so it may be
+    /// retrieved (possibly in another thread) with [Self::do_for_component()]. Any number of types may be registered.\
+    ///
+    /// Example:
+    /// ```no_compile
+    ///     Runtime::register_component(&runtime, YourLogicComponent::new().await).await;
+    // (the logic of this template has no components of its own -- see `async_main()` for where yours go)
+    #[allow(dead_code)]
+    pub async fn register_component<Component: Any + Send + Sync>(runtime: &RwLock<Self>, component: Component) {
+        runtime.write().await.components.insert(TypeId::of::<Component>(), Box::new(component));
+    }
+
+    /// Gets (or waits for up to a reasonable, hard-coded timeout) the `Component` singleton -- as registered (possibly in another thread
+    /// or task) by [Self::register_component()] -- then pass it to `callback()` to do something useful with it while `runtime` is read-locked\
+    ///
+    /// Example:
+    /// ```no_compile
+    ///     Runtime::do_for_component(&runtime, |your_logic_component: &mut YourLogicComponent| Box::pin(async move {
+    ///         your_logic_component.process(request).await
+    ///     })).await?;
+    pub async fn do_for_component<Component: Any + Send + Sync, ReturnType>
+                                 (runtime:  &RwLock<Self>,
+                                  callback: impl for<'r> FnOnce(&'r mut Component) -> BoxFuture<'r, ReturnType> + Send)
+                                 -> ReturnType {
+        Self::wait_to_do(runtime, std::any::type_name::<Component>(), Self::component_mut::<Component>, callback).await
+    }
+
+    /// Similar to [Self::do_for_component()], but only executes callback if the `Component` is registered -- not failing if it is not.
+    #[allow(dead_code)]
+    pub async fn do_if_component_is_present<Component: Any + Send + Sync, ReturnType>
+                                           (runtime:  &RwLock<Self>,
+                                            callback: impl for<'r> FnOnce(&'r mut Component) -> BoxFuture<'r, ReturnType> + Send)
+                                           -> Option<ReturnType> {
+        if !runtime.read().await.components.contains_key(&TypeId::of::<Component>()) {
+            return None
+        }
+        Some(Self::do_for_component(runtime, callback).await)
+    }
+
+    fn component_mut<Component: Any + Send + Sync>(&mut self) -> Option<&mut Component> {
+        self.components.get_mut(&TypeId::of::<Component>())
+            .and_then(|component| component.downcast_mut::<Component>())
+    }
+
+    /// declares the Telegram bots -- by their [crate::config::TelegramConfig::name]s -- that will be registered with [Self::register_telegram_ui()],
+    /// so they are waited for by [Self::do_for_each_telegram_ui()]
+    pub fn expect_telegram_uis(&mut self, bot_names: impl IntoIterator<Item = String>) {
+        for bot_name in bot_names {
+            self.telegram_uis.entry(bot_name).or_insert(None);
+        }
+    }
+
+    /// the names of the Telegram bots -- whether they are registered yet or not
+    pub fn telegram_bot_names(&self) -> Vec<String> {
+        self.telegram_uis.keys().cloned().collect()
+    }
+
+    /// RW-Locks `runtime`, then registers `telegram_ui` under its bot's name -- replacing any previous one -- so it may be retrieved
+    /// (possibly in another thread) with [Self::do_for_telegram_ui()]
+    pub async fn register_telegram_ui(runtime: &RwLock<Self>, telegram_ui: TelegramUI) {
+        runtime.write().await.telegram_uis.insert(telegram_ui.bot_name().to_string(), Some(telegram_ui));
+    }
+
+    /// Gets (or waits for up to a reasonable, hard-coded timeout) the Telegram bot named `bot_name` -- as registered (possibly in another
+    /// thread or task) by [Self::register_telegram_ui()] -- then pass it to `callback()` to do something useful with it while `runtime` is read-locked\
+    ///
+    /// Example:
+    /// ```no_compile
+    ///     Runtime::do_for_telegram_ui(&runtime, "support", |support_bot| Box::pin(async move {
+    ///         support_bot.broadcast_message(&contents_for_support, true).await
+    ///     })).await?;
+    pub async fn do_for_telegram_ui<ReturnType>
+                                   (runtime:  &RwLock<Self>,
+                                    bot_name: &str,
+                                    callback: impl for<'r> FnOnce(&'r mut TelegramUI) -> BoxFuture<'r, ReturnType> + Send)
+                                   -> ReturnType {
+        Self::wait_to_do(runtime, &format!("telegram_ui '{}'", bot_name), |runtime| runtime.telegram_uis.get_mut(bot_name).and_then(Option::as_mut), callback).await
+    }
+
+    /// Similar to [Self::do_for_telegram_ui()], but only executes callback if the bot is registered -- not failing if it is not.
+    pub async fn do_if_telegram_ui_is_present<ReturnType>
+                                             (runtime:  &RwLock<Self>,
+                                              bot_name: &str,
+                                              callback: impl for<'r> FnOnce(&'r mut TelegramUI) -> BoxFuture<'r, ReturnType> + Send)
+                                             -> Option<ReturnType> {
+        if !matches!(runtime.read().await.telegram_uis.get(bot_name), Some(Some(_))) {
+            return None
+        }
+        Some(Self::do_for_telegram_ui(runtime, bot_name, callback).await)
+    }
+
+    /// Calls [Self::do_for_telegram_ui()] for each of the expected Telegram bots -- see [Self::expect_telegram_uis()] -- returning the
+    /// results by bot name
+    pub async fn do_for_each_telegram_ui<ReturnType>
+                                        (runtime:  &RwLock<Self>,
+                                         callback: impl for<'r> Fn(&'r mut TelegramUI) -> BoxFuture<'r, ReturnType> + Send + Sync)
+                                        -> Vec<(String, ReturnType)> {
+        let bot_names = runtime.read().await.telegram_bot_names();
+        let mut results = Vec::with_capacity(bot_names.len());
+        for bot_name in bot_names {
+            let result = Self::do_for_telegram_ui(runtime, &bot_name, &callback).await;
+            results.push((bot_name, result));
+        }
+        results
+    }
+
+    /// Waits (for up to [TIMEOUT]) for the value `field()` gets out of `runtime` to be present, then calls `callback()` with it --
+    /// the waiting logic behind [Self::do_for_component()] & the getters created by `impl_runtime!()`. `field_name` is used in the logs
+    async fn wait_to_do<Field, ReturnType>(runtime:    &RwLock<Self>,
+                                           field_name: &str,
+                                           field:      impl for<'f> Fn(&'f mut Self) -> Option<&'f mut Field> + Send,
+                                           callback:   impl for<'r> FnOnce(&'r mut Field) -> BoxFuture<'r, ReturnType> + Send)
+                                          -> ReturnType {
+        let mut start: Option<SystemTime> = None;
+        loop {
+            if let Ok(runtime) = &mut runtime.try_write() {
+                if let Some(field_value) = field(runtime.deref_mut()) {
+                    if let Some(start) = start {
+                        debug!("Runtime: `{}` became available after a {:?} wait", field_name, start.elapsed().unwrap());
+                    }
+                    break callback(field_value).await
+                }
+            }
+            if let Some(_start) = start {
+                if _start.elapsed().unwrap() > TIMEOUT {
+                    panic!("Could not retrieve `{}` instance: {}",
+                           field_name,
+                           if let Ok(_runtime) = &runtime.try_read() {
+                               format!("it was not registered in `Runtime` even after {:?}", TIMEOUT)
+                           } else {
+                               format!("`Runtime` seems to be locked elsewhere for the past {:?}", TIMEOUT)
+                        });
+                }
+            } else {
+                start = Some(SystemTime::now());
+                debug!("Runtime: `{}` is not (yet?) available. Waiting for up to {:?} for main.rs to finish instantiating it and placing it here with its `register_...()` function",
+                       field_name, TIMEOUT);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
         }
     }
 }
 
 // implements getters and setters for all `Option` fields that are to be set/get asynchronously
 ///////////////////////////////////////////////////////////////////////////////////////////////
-// impl_runtime!("logic_component", logic_component, YourLogicComponent,      register_LOGIC_COMPONENT, do_for_LOGIC_COMPONENT, do_if_LOGIC_COMPONENT_is_present);
-impl_runtime!("telegram_ui",     telegram_ui,     TelegramUI,              register_telegram_ui,     do_for_telegram_ui,     do_if_telegram_ui_is_present);
 impl_runtime!("web_server",      web_server,      WebServer,               register_web_server,      do_for_web_server,      do_if_web_server_is_present);
 impl_runtime!("socket_server",   socket_server,   SocketServer<'static>,   register_socket_server,   do_for_socket_server,   do_if_socket_server_is_present);
+impl_runtime!("socket_client",   socket_client,   SocketClient,            register_socket_client,   do_for_socket_client,   do_if_socket_client_is_present);
+impl_runtime!("grpc_server",     grpc_server,     GrpcServer,              register_grpc_server,     do_for_grpc_server,     do_if_grpc_server_is_present);
+
+
+/// Unit tests the [runtime](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::config::DegradeConfig;
+
+
+    struct Counter(u32);
+    struct Greeter(String);
+    struct LateComponent;
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn components() {
+        let degrade_config = DegradeConfig { disable_telegram_notifications: false, read_only_api: false, maintenance: false, maintenance_message: String::new() };
+        let runtime = RwLock::new(Runtime::new(String::from("test"), String::new(), DegradeSwitches::new(&degrade_config), SocketServerStats::new(0)));
+        assert_eq!(Runtime::do_if_component_is_present(&runtime, |counter: &mut Counter| Box::pin(async move { counter.0 })).await, None,
+                   "Components should not be present before being registered");
+
+        Runtime::register_component(&runtime, Counter(0)).await;
+        Runtime::register_component(&runtime, Greeter(String::from("hello"))).await;
+        for _ in 0..3 {
+            Runtime::do_for_component(&runtime, |counter: &mut Counter| Box::pin(async move { counter.0 += 1 })).await;
+        }
+        assert_eq!(Runtime::do_for_component(&runtime, |counter: &mut Counter| Box::pin(async move { counter.0 })).await, 3, "Wrong state for the `Counter` component");
+        assert_eq!(Runtime::do_if_component_is_present(&runtime, |greeter: &mut Greeter| Box::pin(async move { greeter.0.clone() })).await,
+                   Some(String::from("hello")), "Wrong state for the `Greeter` component");
+
+        // retrieving waits for components not yet registered
+        let late_registration = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Runtime::register_component(&runtime, Counter(42)).await;
+            Runtime::register_component(&runtime, LateComponent).await;
+        };
+        let waiting_for_registration = Runtime::do_for_component(&runtime, |_late_component: &mut LateComponent| Box::pin(async { "retrieved" }));
+        let ((), retrieved) = tokio::join!(late_registration, waiting_for_registration);
+        assert_eq!(retrieved, "retrieved", "A component registered while being waited for should be retrieved");
+        assert_eq!(Runtime::do_for_component(&runtime, |counter: &mut Counter| Box::pin(async move { counter.0 })).await,
+                   42, "Registering a component of the same type should replace the previous one");
+    }
+}
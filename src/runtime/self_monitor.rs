@@ -0,0 +1,209 @@
+//! Watches the process' own resources -- resident memory, CPU usage & how late Tokio's timers fire (the scheduling delay, telling
+//! how busy the workers are) -- warning when they are above the limits in [crate::config::Config::self_monitor] and, once a limit stays
+//! breached for long enough, alerting Telegram's notification chats (through the [super::Outbox], if enabled) or gracefully shutting the
+//! services down, for the process manager to start a fresh instance. Tokio's own metrics -- how busy its workers were, the depth of its global queue & the alive tasks -- are sampled
+//! along, for the diagnostics. The samples are published in the metrics & the latest one on `/api/stats/self-monitor`.\
+//! Memory & CPU are read from `/proc` (see [super::procfs]), so they are only sampled on Linux.
+
+use super::{Runtime, OutboxIntent, procfs};
+use crate::{
+    config::{SelfMonitorConfig, WatchdogAction},
+    frontend,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use metrics::{gauge, describe_gauge, Unit};
+use log::{warn, error};
+
+
+/// Gauge of the process' resident memory (RSS)
+pub const RESIDENT_MEMORY_METRIC: &str = "self_monitor_resident_memory_bytes";
+/// Gauge of the process' CPU usage -- user + system -- in percent of a single CPU
+pub const CPU_USAGE_METRIC: &str = "self_monitor_cpu_usage_percent";
+/// Gauge of how late Tokio's timers fire
+pub const SCHEDULING_DELAY_METRIC: &str = "self_monitor_scheduling_delay_seconds";
+/// Gauge of how busy Tokio's workers were -- in percent of all of them
+pub const WORKERS_BUSY_METRIC: &str = "self_monitor_tokio_workers_busy_percent";
+/// Gauge of the tasks waiting in Tokio's global queue
+pub const GLOBAL_QUEUE_DEPTH_METRIC: &str = "self_monitor_tokio_global_queue_depth";
+/// Gauge of the Tokio tasks alive
+pub const ALIVE_TASKS_METRIC: &str = "self_monitor_tokio_alive_tasks";
+
+
+/// registers the descriptions of the self-monitor's metrics -- to be called once the recorder is installed
+pub fn describe_self_monitor_metrics() {
+    describe_gauge!(RESIDENT_MEMORY_METRIC, Unit::Bytes, "The process' resident memory (RSS)");
+    describe_gauge!(CPU_USAGE_METRIC, Unit::Percent, "The process' CPU usage (user + system), in percent of a single CPU");
+    describe_gauge!(SCHEDULING_DELAY_METRIC, Unit::Seconds, "How late Tokio's timers fire -- high values mean busy (or blocked) workers");
+    describe_gauge!(WORKERS_BUSY_METRIC, Unit::Percent, "How busy Tokio's workers were since the previous sample, in percent of all of them");
+    describe_gauge!(GLOBAL_QUEUE_DEPTH_METRIC, Unit::Count, "The tasks waiting in Tokio's global (injection) queue");
+    describe_gauge!(ALIVE_TASKS_METRIC, Unit::Count, "The Tokio tasks alive -- spawned & not yet completed");
+}
+
+
+/// A sample of the process' resources -- as answered by `/api/stats/self-monitor`
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourcesSample {
+    pub timestamp:               String,
+    /// `None` if unknown -- like on non-Linux platforms
+    pub resident_memory_bytes:   Option<u64>,
+    /// since the previous sample -- `None` if unknown
+    pub cpu_percent:             Option<f64>,
+    pub scheduling_delay_millis: f64,
+    /// how busy Tokio's workers were since the previous sample -- in percent of all of them
+    pub workers_busy_percent:    f64,
+    pub global_queue_depth:      usize,
+    pub alive_tasks:             usize,
+    /// the limits breached by this sample -- explained
+    pub breaches:                Vec<String>,
+    /// for how many consecutive samples a limit was breached -- see [SelfMonitorConfig::breached_samples_to_act]
+    pub breached_samples:        u32,
+}
+
+/// The self-monitor -- see [super]
+pub struct SelfMonitor {
+    config: SelfMonitorConfig,
+    latest: Mutex<Option<ResourcesSample>>,
+}
+
+impl SelfMonitor {
+
+    pub fn new(config: &SelfMonitorConfig) -> Self {
+        Self {
+            config: config.clone(),
+            latest: Mutex::new(None),
+        }
+    }
+
+    /// the latest sample -- `None` until the first one is taken
+    pub fn latest(&self) -> Option<ResourcesSample> {
+        self.latest.lock().clone()
+    }
+
+    /// Samples the resources on the configured interval, acting on the breaches as configured -- `runtime` is used to reach Telegram &
+    /// to shut the services down. Never returns: spawn it as a Tokio task
+    pub async fn run(self: Arc<Self>, runtime: Arc<RwLock<Runtime>>) {
+        let interval = Duration::from_secs(self.config.sampling_interval_seconds.max(1));
+        let tokio_metrics = tokio::runtime::Handle::current().metrics();
+        let mut last_cpu = procfs::cpu_seconds().map(|cpu_seconds| (cpu_seconds, Instant::now()));
+        let mut last_busy = (workers_busy_duration(&tokio_metrics), Instant::now());
+        let mut breached_samples = 0;
+        loop {
+            let sleep_start = Instant::now();
+            tokio::time::sleep(interval).await;
+            let scheduling_delay = sleep_start.elapsed().saturating_sub(interval);
+            let resident_memory_bytes = procfs::resident_memory_bytes();
+            let cpu = procfs::cpu_seconds().map(|cpu_seconds| (cpu_seconds, Instant::now()));
+            let cpu_percent = match (last_cpu, cpu) {
+                (Some((last_cpu_seconds, last_instant)), Some((cpu_seconds, instant))) =>
+                    Some(100.0 * (cpu_seconds - last_cpu_seconds) / instant.duration_since(last_instant).as_secs_f64().max(f64::EPSILON)),
+                _ => None,
+            };
+            last_cpu = cpu;
+            let busy = (workers_busy_duration(&tokio_metrics), Instant::now());
+            let workers_capacity = busy.1.duration_since(last_busy.1).as_secs_f64() * tokio_metrics.num_workers() as f64;
+            let workers_busy_percent = 100.0 * busy.0.saturating_sub(last_busy.0).as_secs_f64() / workers_capacity.max(f64::EPSILON);
+            last_busy = busy;
+            let global_queue_depth = tokio_metrics.global_queue_depth();
+            let alive_tasks = tokio_metrics.num_alive_tasks();
+
+            if let Some(resident_memory_bytes) = resident_memory_bytes {
+                gauge!(RESIDENT_MEMORY_METRIC).set(resident_memory_bytes as f64);
+            }
+            if let Some(cpu_percent) = cpu_percent {
+                gauge!(CPU_USAGE_METRIC).set(cpu_percent);
+            }
+            gauge!(SCHEDULING_DELAY_METRIC).set(scheduling_delay.as_secs_f64());
+            gauge!(WORKERS_BUSY_METRIC).set(workers_busy_percent);
+            gauge!(GLOBAL_QUEUE_DEPTH_METRIC).set(global_queue_depth as f64);
+            gauge!(ALIVE_TASKS_METRIC).set(alive_tasks as f64);
+
+            let breaches = self.breaches(resident_memory_bytes, cpu_percent, scheduling_delay);
+            breached_samples = if breaches.is_empty() { 0 } else { breached_samples + 1 };
+            for breach in &breaches {
+                warn!("Self-monitor: {} (breached for {} sample(s))", breach, breached_samples);
+            }
+            let sample = ResourcesSample {
+                timestamp:               chrono::Local::now().to_rfc3339(),
+                resident_memory_bytes,
+                cpu_percent,
+                scheduling_delay_millis: scheduling_delay.as_secs_f64() * 1000.0,
+                workers_busy_percent,
+                global_queue_depth,
+                alive_tasks,
+                breaches,
+                breached_samples,
+            };
+            if breached_samples == self.config.breached_samples_to_act.max(1) {
+                self.act(&sample, &runtime).await;
+            }
+            *self.latest.lock() = Some(sample);
+        }
+    }
+
+    /// the limits breached by the sampled values -- explained
+    fn breaches(&self, resident_memory_bytes: Option<u64>, cpu_percent: Option<f64>, scheduling_delay: Duration) -> Vec<String> {
+        let mut breaches = Vec::new();
+        if let Some(resident_memory_bytes) = resident_memory_bytes {
+            let resident_memory_mib = resident_memory_bytes / (1024 * 1024);
+            if self.config.max_resident_memory_mib > 0 && resident_memory_mib > self.config.max_resident_memory_mib {
+                breaches.push(format!("resident memory is {}MiB -- above the limit of {}MiB", resident_memory_mib, self.config.max_resident_memory_mib));
+            }
+        }
+        if let Some(cpu_percent) = cpu_percent {
+            if self.config.max_cpu_percent > 0.0 && cpu_percent > self.config.max_cpu_percent {
+                breaches.push(format!("CPU usage is {:.1}% -- above the limit of {:.1}%", cpu_percent, self.config.max_cpu_percent));
+            }
+        }
+        let scheduling_delay_millis = scheduling_delay.as_millis() as u64;
+        if self.config.max_scheduling_delay_millis > 0 && scheduling_delay_millis > self.config.max_scheduling_delay_millis {
+            breaches.push(format!("Tokio's scheduling delay is {}ms -- above the limit of {}ms", scheduling_delay_millis, self.config.max_scheduling_delay_millis));
+        }
+        breaches
+    }
+
+    /// does what [SelfMonitorConfig::on_breach] says -- once per streak of breaching samples
+    async fn act(&self, sample: &ResourcesSample, runtime: &RwLock<Runtime>) {
+        if self.config.on_breach == WatchdogAction::LogOnly {
+            return
+        }
+        let restarting = self.config.on_breach == WatchdogAction::Restart;
+        let alert = format!("⚠ <b>{}</b>'s resources are above their limits for {} samples{}:\n{}",
+                            env!("CARGO_PKG_NAME"),
+                            sample.breached_samples,
+                            if restarting { " -- RESTARTING" } else { "" },
+                            sample.breaches.join("\n"));
+        let (outbox, telegram_notifier) = {
+            let runtime = runtime.read().await;
+            (runtime.outbox.clone(), runtime.telegram_notifier.clone())
+        };
+        match (outbox, telegram_notifier) {
+            // through the outbox, the alert is delivered even if the restart happens first
+            (Some(outbox), Some(_)) => {
+                if let Err(err) = outbox.enqueue(OutboxIntent::telegram(alert, true), &format!("self-monitor-breach-{}", sample.timestamp)) {
+                    error!("Self-monitor: could not enqueue the Telegram alert in the outbox: {}", err);
+                }
+            },
+            (None, Some(telegram_notifier)) => telegram_notifier.notify(alert, true),
+            (_, None) => warn!("Self-monitor: the limits are still breached, but Telegram is not enabled to be alerted"),
+        }
+        if restarting {
+            error!("Self-monitor: shutting the services down, for the process manager to start a fresh instance: {}", sample.breaches.join("; "));
+            if let Err(err) = frontend::shutdown_tokio_services(runtime).await {
+                error!("Self-monitor: could not shut the services down: {}", err);
+            }
+        }
+    }
+}
+
+/// the time all of Tokio's workers spent busy so far
+fn workers_busy_duration(tokio_metrics: &tokio::runtime::RuntimeMetrics) -> Duration {
+    (0..tokio_metrics.num_workers())
+        .map(|worker| tokio_metrics.worker_total_busy_duration(worker))
+        .sum()
+}
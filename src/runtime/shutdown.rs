@@ -0,0 +1,111 @@
+//! Graceful shutdown on SIGTERM & SIGINT (Ctrl+C): the first signal requests the shutdown -- through [Shutdown], which the long running
+//! jobs listen to -- & stops the services; a second one forces the exit, for when the graceful path hangs.\
+//! On non-Unix platforms, only Ctrl+C is handled.
+
+use std::{
+    future::Future,
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+};
+use tokio::sync::Notify;
+use log::{error, warn};
+
+
+/// Tells whether the application was asked to shut down -- for the jobs & tasks that should then stop what they are doing
+pub struct Shutdown {
+    requested: AtomicBool,
+    notify:    Notify,
+}
+
+impl Shutdown {
+
+    pub fn new() -> Self {
+        Self {
+            requested: AtomicBool::new(false),
+            notify:    Notify::new(),
+        }
+    }
+
+    /// wakes up everyone in [Self::wait()] -- requesting it again is a no-op
+    pub fn request(&self) {
+        if !self.requested.swap(true, Ordering::Relaxed) {
+            self.notify.notify_waiters();
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    /// returns once the shutdown is requested -- immediately, if it already was
+    pub async fn wait(&self) {
+        // created before checking, so a request made in between is not missed
+        let notified = self.notify.notified();
+        if self.is_requested() {
+            return
+        }
+        notified.await
+    }
+}
+
+/// Waits for the termination signals: the first one requests the `shutdown` & runs `graceful_shutdown` (stopping the services);
+/// the second one exits right away with the conventional `128 + signal number` exit code. Runs until the process ends.
+pub async fn handle_signals<GracefulShutdown: Future<Output = ()> + Send + 'static>(shutdown: Arc<Shutdown>, graceful_shutdown: GracefulShutdown) {
+    let mut signals = match Signals::new() {
+        Ok(signals) => signals,
+        Err(err) => return error!("Termination signals won't be handled -- the application won't shutdown gracefully on them: {}", err),
+    };
+    let (signal_name, _signal_number) = signals.next().await;
+    warn!("{} received: shutting down gracefully... (send it again to force the exit)", signal_name);
+    shutdown.request();
+    tokio::spawn(graceful_shutdown);
+    let (signal_name, signal_number) = signals.next().await;
+    error!("{} received again: exiting without finishing the graceful shutdown", signal_name);
+    std::process::exit(128 + signal_number);
+}
+
+/// The termination signals we listen to
+#[cfg(unix)]
+struct Signals {
+    sigterm: tokio::signal::unix::Signal,
+    sigint:  tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+
+    fn new() -> Result<Self, std::io::Error> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            sigterm: signal(SignalKind::terminate())?,
+            sigint:  signal(SignalKind::interrupt())?,
+        })
+    }
+
+    /// the (name, number) of the next signal received
+    async fn next(&mut self) -> (&'static str, i32) {
+        tokio::select! {
+            _ = self.sigterm.recv() => ("SIGTERM", 15),
+            _ = self.sigint.recv()  => ("SIGINT", 2),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+
+    fn new() -> Result<Self, std::io::Error> {
+        Ok(Self)
+    }
+
+    /// the (name, number) of the next Ctrl+C received -- waiting forever if it can't be listened to
+    async fn next(&mut self) -> (&'static str, i32) {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Could not listen to Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+        ("Ctrl+C", 2)
+    }
+}
@@ -0,0 +1,115 @@
+//! The deterministic simulation mode -- for reproducible tests of the logic's interactions with the event bus & the scheduler.
+//! Scenarios run on a single threaded Tokio runtime whose time is paused, so:
+//!   * tasks are polled in the same order on every run -- there is a single thread
+//!   * time only moves forward when all tasks are waiting on timers -- jumping straight to the next one due, in order.
+//!     See [Simulation::run_for()]
+//!   * the wall clock follows it: components taking their time from [Simulation::clock()] see hours go by in milliseconds
+//!   * the [super::EventBus] delivers the events in publishing order, as its subscribers get polled
+//!
+//! Example:
+//! ```no_compile
+//!     simulate(start, |simulation| async move {
+//!         let scheduler = Arc::new(Scheduler::new(&config, job_accounting, simulation.clock())?);
+//!         scheduler.register("report", || async { ... });
+//!         scheduler.start(None, None, Arc::clone(&shutdown));
+//!         simulation.run_for(Duration::from_secs(24 * 3600)).await;
+//!         assert_eq!(scheduler.report()[0].runs, 24);
+//!     })
+//! ```
+//! Notice anything waiting on real I/O (sockets, files, ...) won't let the time move while it waits -- simulate them with channels.\
+//! Only available for tests (& docs), as it requires Tokio's `test-util` feature.
+
+use super::{Clock, SimulatedClock};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+use chrono::{DateTime, Local};
+
+
+/// The handle for the scenario running in [simulate()]
+pub struct Simulation {
+    clock: Arc<SimulatedClock>,
+}
+
+impl Simulation {
+
+    /// the simulated wall clock -- to be given to the components under simulation
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        Arc::clone(&self.clock) as Arc<dyn Clock>
+    }
+
+    /// lets the spawned tasks run for `duration` of simulated time -- the timers due in between firing in order
+    pub async fn run_for(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// runs `scenario` in a new simulation, whose wall clock starts at `start`
+pub fn simulate<ScenarioFuture: Future>(start: DateTime<Local>, scenario: impl FnOnce(Simulation) -> ScenarioFuture) -> ScenarioFuture::Output {
+    let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("Could not build the Tokio runtime for the simulation");
+    tokio_runtime.block_on(async move {
+        let simulation = Simulation {
+            clock: Arc::new(SimulatedClock::new(start)),
+        };
+        scenario(simulation).await
+    })
+}
+
+
+/// Unit tests the [simulation](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{SchedulerConfig, ScheduledJobConfig, JobSchedule},
+        runtime::{EventBus, JobAccounting, Shutdown},
+        scheduler::Scheduler,
+    };
+    use chrono::TimeZone;
+
+
+    /// assures a day of scheduled runs -- & the events they publish -- plays out the same, in order, in no time
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn deterministic_day() {
+        let start = Local.with_ymd_and_hms(2026, 1, 15, 0, 0, 30).unwrap();
+        let (runs, events) = simulate(start, |simulation| async move {
+            let config = SchedulerConfig { jobs: vec![
+                ScheduledJobConfig { name: String::from("hourly"),     schedule: JobSchedule::Cron(String::from("0 * * * *")), critical: false },
+                ScheduledJobConfig { name: String::from("ten-minute"), schedule: JobSchedule::Every { seconds: 600 },        critical: false },
+            ]};
+            let scheduler = Arc::new(Scheduler::new(&config, Arc::new(JobAccounting::new()), simulation.clock()).expect("Creating the scheduler"));
+            let bus = Arc::new(EventBus::new());
+            let ran = bus.topic::<String>("ran").expect("Creating the topic");
+            let mut subscription = ran.subscribe();
+            for name in ["hourly", "ten-minute"] {
+                let (ran, clock) = (ran.clone(), simulation.clock());
+                scheduler.register(name, move || {
+                    let (ran, clock) = (ran.clone(), Arc::clone(&clock));
+                    async move {
+                        ran.publish(format!("{} {}", name, clock.now().format("%H:%M")));
+                        Ok(())
+                    }
+                });
+            }
+            let shutdown = Arc::new(Shutdown::new());
+            scheduler.start(None, None, Arc::clone(&shutdown));
+            simulation.run_for(Duration::from_secs(24 * 3600)).await;
+            shutdown.request();
+            let mut events = vec![];
+            while let Ok(event) = subscription.try_recv() {
+                events.push(event.event);
+            }
+            (scheduler.report().iter().map(|job| job.runs).collect::<Vec<_>>(), events)
+        });
+        assert_eq!(runs, vec![24, 143], "Wrong number of runs for a simulated day");
+        assert_eq!(events.len(), 24 + 143, "Each run should have published its event");
+        assert_eq!(&events[..3], &["ten-minute 00:10", "ten-minute 00:20", "ten-minute 00:30"], "Events should be delivered in order, at their simulated times");
+        assert!(events.contains(&String::from("hourly 13:00")), "Cron jobs should run on the simulated wall clock: {:?}", events);
+    }
+}
@@ -0,0 +1,188 @@
+//! Embedded key-value store for the application's persistent state -- see [crate::config::Config::storage]: typed values (serialized
+//! in RON) kept in memory & snapshotted to a single file, periodically & on the graceful shutdown. Usage:
+//! ```no_compile
+//!     storage.put("last-report", &report)?;
+//!     let visits = storage.update("visits", |visits: Option<u64>| Some(visits.unwrap_or(0) + 1))?;
+//! ```
+//! Changes made after the last snapshot are lost if the process crashes -- shorten `autosave_interval_seconds` if that matters.
+
+use crate::config::StorageConfig;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use parking_lot::RwLock;
+use serde::{Serialize, de::DeserializeOwned};
+use log::{debug, info, error};
+
+
+/// The persistent state -- see [super::Runtime::storage]
+pub struct Storage {
+    file_path:         String,
+    autosave_interval: Duration,
+    /// the values, in RON, by key
+    entries:           RwLock<BTreeMap<String, String>>,
+    /// if there are changes not yet saved
+    dirty:             AtomicBool,
+}
+
+impl Storage {
+
+    /// Loads the snapshot left by previous runs -- starting empty if there is none
+    pub fn new(config: &StorageConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let entries = if Path::new(&config.file_path).exists() {
+            let contents = std::fs::read_to_string(&config.file_path)
+                .map_err(|err| format!("Storage: could not read the snapshot '{}': {}", config.file_path, err))?;
+            let entries: BTreeMap<String, String> = ron::from_str(&contents)
+                .map_err(|err| format!("Storage: the snapshot '{}' is corrupted -- fix or remove it: {}", config.file_path, err))?;
+            info!("Storage: {} entries loaded from '{}'", entries.len(), config.file_path);
+            entries
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            file_path:         config.file_path.clone(),
+            autosave_interval: Duration::from_secs(config.autosave_interval_seconds),
+            entries:           RwLock::new(entries),
+            dirty:             AtomicBool::new(false),
+        })
+    }
+
+    /// the value stored under `key` -- failing if it is not a `T`
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Box<dyn std::error::Error + Send + Sync>> {
+        self.entries.read().get(key)
+            .map(|value| deserialize(key, value))
+            .transpose()
+    }
+
+    /// stores `value` under `key` -- replacing the previous one
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let value = serialize(key, value)?;
+        self.entries.write().insert(key.to_string(), value);
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// removes the value under `key`, telling if there was one
+    pub fn remove(&self, key: &str) -> bool {
+        let removed = self.entries.write().remove(key).is_some();
+        if removed {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    /// atomically replaces the value under `key` by what `updater` returns for the current one -- `None` removing it.
+    /// No other changes happen in between, so `updater` should be quick. Returns the new value
+    pub fn update<T: Serialize + DeserializeOwned>(&self, key: &str, updater: impl FnOnce(Option<T>) -> Option<T>) -> Result<Option<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut entries = self.entries.write();
+        let current = entries.get(key)
+            .map(|value| deserialize(key, value))
+            .transpose()?;
+        let updated = updater(current);
+        match &updated {
+            Some(value) => entries.insert(key.to_string(), serialize(key, value)?),
+            None => entries.remove(key),
+        };
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(updated)
+    }
+
+    /// the stored keys starting with `prefix` -- in order
+    pub fn keys(&self, prefix: &str) -> Vec<String> {
+        self.entries.read().range(prefix.to_string()..)
+            .map(|(key, _value)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// writes the snapshot, if there were changes since the last one -- atomically, through a temporary file, then renamed -- so a crash
+    /// never leaves a truncated snapshot behind
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(())
+        }
+        let contents = {
+            let entries = self.entries.read();
+            ron::ser::to_string_pretty(&*entries, ron::ser::PrettyConfig::new())
+                .map_err(|err| format!("Storage: could not serialize the snapshot: {}", err))?
+        };
+        let temporary_path = format!("{}.tmp", self.file_path);
+        std::fs::write(&temporary_path, contents)
+            .and_then(|_| std::fs::rename(&temporary_path, &self.file_path))
+            .map_err(|err| {
+                // to be tried again on the next save
+                self.dirty.store(true, Ordering::Relaxed);
+                format!("Storage: could not write the snapshot '{}': {}", self.file_path, err)
+            })?;
+        debug!("Storage: snapshot saved to '{}'", self.file_path);
+        Ok(())
+    }
+
+    /// saves the snapshot every `autosave_interval_seconds` (if not 0) -- the final one is saved on shutdown, by whoever calls [Self::save()]
+    pub fn start_autosave(self: &Arc<Self>) {
+        if self.autosave_interval.is_zero() {
+            return
+        }
+        let storage = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(storage.autosave_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(err) = storage.save() {
+                    error!("{}", err);
+                }
+            }
+        });
+    }
+}
+
+fn serialize<T: Serialize>(key: &str, value: &T) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    ron::to_string(value)
+        .map_err(|err| Box::from(format!("Storage: could not serialize the value for '{}': {}", key, err)))
+}
+
+fn deserialize<T: DeserializeOwned>(key: &str, value: &str) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    ron::from_str(value)
+        .map_err(|err| Box::from(format!("Storage: the value stored under '{}' is not of the requested type: {}", key, err)))
+}
+
+
+/// Unit tests the [storage](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn snapshots_survive_restarts() {
+        let config = StorageConfig {
+            file_path:                 String::from("/tmp/kickass-app-template.storage-test.ron"),
+            autosave_interval_seconds: 0,
+        };
+        std::fs::remove_file(&config.file_path).ok();
+        let storage = Storage::new(&config).expect("Could not create the storage");
+        storage.put("greeting", &String::from("hello")).expect("Could not put");
+        for _ in 0..3 {
+            storage.update("counter", |counter: Option<u32>| Some(counter.unwrap_or(0) + 1)).expect("Could not update");
+        }
+        storage.put("other", &true).expect("Could not put");
+        assert!(storage.remove("other"), "`other` should have been removed");
+        assert!(storage.get::<u32>("greeting").is_err(), "Getting a value with the wrong type should fail");
+        storage.save().expect("Could not save");
+
+        let storage = Storage::new(&config).expect("Could not reload the storage");
+        assert_eq!(storage.get::<String>("greeting").unwrap(), Some(String::from("hello")), "Wrong reloaded value");
+        assert_eq!(storage.get::<u32>("counter").unwrap(), Some(3), "Wrong reloaded counter");
+        assert_eq!(storage.keys(""), vec!["counter".to_string(), "greeting".to_string()], "Wrong keys");
+        assert_eq!(storage.keys("gr"), vec!["greeting".to_string()], "Wrong keys for a prefix");
+        std::fs::remove_file(&config.file_path).ok();
+    }
+}
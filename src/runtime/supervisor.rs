@@ -0,0 +1,205 @@
+//! Keeps the services' Tokio tasks running: each one is spawned with a [RestartPolicy], being restarted -- after an exponential backoff --
+//! when it ends (or panics) as the policy says. Failures & restarts are counted in the metrics & crash loops (too many restarts in
+//! a short while) are alerted to the Telegram notification chats.\
+//! Tasks are never restarted once a shutdown is requested -- see [Shutdown].
+
+use super::Shutdown;
+use crate::{
+    config::{SupervisorConfig, RestartPolicy},
+    frontend::{ServiceResult, telegram::TelegramNotifier},
+};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+    collections::VecDeque,
+};
+use tokio::task::JoinHandle;
+use metrics::{counter, describe_counter, Unit};
+use log::{info, warn, error};
+
+
+/// Counter of the supervised tasks ending with failure (errors & panics), labeled by `task`
+pub const FAILURES_METRIC: &str = "supervisor_task_failures_total";
+/// Counter of the supervised tasks restarts, labeled by `task`
+pub const RESTARTS_METRIC: &str = "supervisor_task_restarts_total";
+/// Counter of the crash loops detected, labeled by `task` -- see [SupervisorConfig::crash_loop_restarts]
+pub const CRASH_LOOPS_METRIC: &str = "supervisor_crash_loops_total";
+
+
+/// registers the descriptions of the supervisor's metrics -- to be called once the recorder is installed
+pub fn describe_supervisor_metrics() {
+    describe_counter!(FAILURES_METRIC, Unit::Count, "Supervised tasks (the services) ending with an error or panic, by task");
+    describe_counter!(RESTARTS_METRIC, Unit::Count, "Restarts of the supervised tasks (the services), by task");
+    describe_counter!(CRASH_LOOPS_METRIC, Unit::Count, "Crash loops of the supervised tasks (the services) -- too many restarts in a short while -- by task");
+}
+
+/// Spawns the supervised tasks -- see [super]
+pub struct Supervisor {
+    /// if `None`, tasks are never restarted
+    config:            Option<SupervisorConfig>,
+    shutdown:          Arc<Shutdown>,
+    /// where crash loops are alerted to, if the Telegram service is enabled
+    telegram_notifier: Option<Arc<TelegramNotifier>>,
+}
+
+impl Supervisor {
+
+    pub fn new(config: Option<SupervisorConfig>, shutdown: Arc<Shutdown>, telegram_notifier: Option<Arc<TelegramNotifier>>) -> Self {
+        Self {
+            config,
+            shutdown,
+            telegram_notifier,
+        }
+    }
+
+    /// the restart policy for `task_name` -- as set in [SupervisorConfig::restart_policies]
+    pub fn restart_policy(&self, task_name: &str) -> RestartPolicy {
+        self.config.as_ref()
+            .map_or(RestartPolicy::Never, |config| config.restart_policies.get(task_name).copied().unwrap_or(config.default_restart_policy))
+    }
+
+    /// Spawns the Tokio task running the future built by `task` -- building & running it again, as `restart_policy` says, when it ends.\
+    /// The returned handle resolves once the task ends for good: with the outcome of its last run
+    pub fn spawn<Task, TaskFuture>(self: &Arc<Self>, task_name: &'static str, restart_policy: RestartPolicy, task: Task) -> JoinHandle<ServiceResult>
+                                  where Task:       Fn() -> TaskFuture + Send + Sync + 'static,
+                                        TaskFuture: Future<Output = ServiceResult> + Send + 'static {
+        let supervisor = Arc::clone(self);
+        tokio::spawn(async move {
+            let (initial_backoff, max_backoff, crash_loop_restarts, crash_loop_window) = supervisor.config.as_ref()
+                .map_or((Duration::ZERO, Duration::ZERO, 0, Duration::ZERO), |config| (Duration::from_millis(config.initial_backoff_millis),
+                                                                                       Duration::from_millis(config.max_backoff_millis),
+                                                                                       config.crash_loop_restarts as usize,
+                                                                                       Duration::from_secs(config.crash_loop_window_seconds)));
+            let mut backoff = initial_backoff;
+            let mut restarts: VecDeque<Instant> = VecDeque::new();
+            let mut alerted_at: Option<Instant> = None;
+            loop {
+                let started = Instant::now();
+                // ran in its own task, so panics are caught as failures
+                let outcome = match tokio::spawn(task()).await {
+                    Ok(outcome) => outcome,
+                    Err(join_err) => Err(Box::from(format!("'{}' task panicked: {}", task_name, join_err))),
+                };
+                if let Err(err) = &outcome {
+                    counter!(FAILURES_METRIC, "task" => task_name).increment(1);
+                    if restart_policy != RestartPolicy::Never {
+                        error!("Supervisor: '{}' ended with failure: {}", task_name, err);
+                    }
+                }
+                let restart = match restart_policy {
+                    RestartPolicy::Never     => false,
+                    RestartPolicy::OnFailure => outcome.is_err(),
+                    RestartPolicy::Always    => true,
+                };
+                if !restart || supervisor.shutdown.is_requested() {
+                    return outcome
+                }
+
+                // a run long enough means the previous failures are over
+                if started.elapsed() > max_backoff {
+                    backoff = initial_backoff;
+                }
+                let now = Instant::now();
+                restarts.push_back(now);
+                while restarts.front().is_some_and(|restarted| now.duration_since(*restarted) > crash_loop_window) {
+                    restarts.pop_front();
+                }
+                if crash_loop_restarts > 0 && restarts.len() >= crash_loop_restarts && alerted_at.map_or(true, |alerted_at| now.duration_since(alerted_at) > crash_loop_window) {
+                    alerted_at = Some(now);
+                    supervisor.on_crash_loop(task_name, restarts.len(), crash_loop_window, &outcome);
+                }
+                warn!("Supervisor: restarting '{}' in {:?}", task_name, backoff);
+                tokio::select! {
+                    () = tokio::time::sleep(backoff) => (),
+                    () = supervisor.shutdown.wait() => return outcome,
+                }
+                counter!(RESTARTS_METRIC, "task" => task_name).increment(1);
+                info!("Supervisor: restarting '{}'", task_name);
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        })
+    }
+
+    /// reports that `task_name` was restarted `restarts` times within `window` -- the last run ending with `outcome`
+    fn on_crash_loop(&self, task_name: &'static str, restarts: usize, window: Duration, outcome: &ServiceResult) {
+        counter!(CRASH_LOOPS_METRIC, "task" => task_name).increment(1);
+        let reason = match outcome {
+            Ok(()) => String::from("ended gracefully"),
+            Err(err) => format!("failed with: {}", err),
+        };
+        error!("Supervisor: '{}' is crash looping -- {} restarts in the last {:?}. Its last run {}", task_name, restarts, window, reason);
+        if let Some(telegram_notifier) = &self.telegram_notifier {
+            telegram_notifier.notify(format!("🔁 <b>{}</b>: '{}' is crash looping -- {} restarts in the last {:?}. Its last run {}",
+                                             env!("CARGO_PKG_NAME"), escape_html(task_name), restarts, window, escape_html(&reason)), true);
+        }
+    }
+}
+
+/// escapes `text` to be placed in Telegram's HTML messages
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+
+/// Unit tests the [supervisor](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn supervisor(default_restart_policy: RestartPolicy) -> Arc<Supervisor> {
+        Arc::new(Supervisor::new(Some(SupervisorConfig {
+            default_restart_policy,
+            restart_policies:          BTreeMap::new(),
+            initial_backoff_millis:    1,
+            max_backoff_millis:        10,
+            crash_loop_restarts:       3,
+            crash_loop_window_seconds: 60,
+        }), Arc::new(Shutdown::new()), None))
+    }
+
+    /// assures failing tasks are restarted -- & only as their policies say
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn restart_policies() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted_runs = Arc::clone(&runs);
+        let outcome = supervisor(RestartPolicy::OnFailure).spawn("flaky", RestartPolicy::OnFailure, move || {
+            let runs = Arc::clone(&counted_runs);
+            async move {
+                if runs.fetch_add(1, Ordering::Relaxed) < 4 {
+                    Err(Box::from("not yet"))
+                } else {
+                    Ok(())
+                }
+            }
+        }).await.expect("The supervised task panicked");
+        assert!(outcome.is_ok(), "The task should have ended gracefully on its 5th run");
+        assert_eq!(runs.load(Ordering::Relaxed), 5, "The task should have been restarted until it succeeded");
+
+        let runs = Arc::new(AtomicU32::new(0));
+        let counted_runs = Arc::clone(&runs);
+        let outcome = supervisor(RestartPolicy::OnFailure).spawn("failing", RestartPolicy::Never, move || {
+            counted_runs.fetch_add(1, Ordering::Relaxed);
+            async { Err(Box::from("failed")) }
+        }).await.expect("The supervised task panicked");
+        assert!(outcome.is_err(), "The failure should have been reported");
+        assert_eq!(runs.load(Ordering::Relaxed), 1, "Tasks under the `Never` policy should not be restarted");
+    }
+
+    /// assures tasks are not restarted once a shutdown is requested -- even under the `Always` policy
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn no_restarts_on_shutdown() {
+        let supervisor = supervisor(RestartPolicy::Always);
+        let shutdown = Arc::clone(&supervisor.shutdown);
+        let outcome = supervisor.spawn("stopping", RestartPolicy::Always, move || {
+            let shutdown = Arc::clone(&shutdown);
+            async move {
+                shutdown.request();
+                Ok(())
+            }
+        }).await.expect("The supervised task panicked");
+        assert!(outcome.is_ok(), "The task should have ended gracefully");
+    }
+}
@@ -0,0 +1,116 @@
+//! Warmup hooks -- ran once, after the services are started, so the first real requests don't pay for cold starts:
+//! pre-populating caches, establishing outbound connections, loading datasets, ...\
+//! Each hook is timed & the overall status is exposed through the readiness route `/api/ready`, which only answers "200 OK" once all
+//! hooks are done. Failing hooks are logged & reported, but don't hold the readiness back: they are optimizations, after all.
+
+use std::{
+    future::Future,
+    time::Instant,
+};
+use futures::future::{self, BoxFuture};
+use parking_lot::Mutex;
+use serde::Serialize;
+use log::{info, warn};
+
+
+type WarmupHook = Box<dyn FnOnce() -> BoxFuture<'static, Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + Sync>;
+
+/// Where the warmup phase is at -- see [Warmup::report()]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum WarmupStatus {
+    /// waiting for the services to start
+    Pending,
+    Running,
+    /// all hooks ran -- the application is ready
+    Done,
+}
+
+/// The outcome of a warmup hook
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupHookRun {
+    pub name:           String,
+    pub elapsed_micros: u64,
+    /// present if the hook failed
+    pub error:          Option<String>,
+}
+
+/// As answered by `/api/ready`
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupReport {
+    pub status: WarmupStatus,
+    pub hooks:  Vec<WarmupHookRun>,
+}
+
+/// Holds the warmup hooks -- see [Warmup::register()] -- until they are ran by [Warmup::run()]
+pub struct Warmup {
+    hooks:  Mutex<Vec<(String, WarmupHook)>>,
+    report: Mutex<WarmupReport>,
+}
+
+impl Warmup {
+
+    pub fn new() -> Self {
+        Self {
+            hooks:  Mutex::new(Vec::new()),
+            report: Mutex::new(WarmupReport { status: WarmupStatus::Pending, hooks: Vec::new() }),
+        }
+    }
+
+    /// Registers `hook`, named `hook_name`, to be ran in the warmup phase -- hooks registered after it started are ignored
+    pub fn register<HookFuture: Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>
+                   (&self, hook_name: &str, hook: impl FnOnce() -> HookFuture + Send + Sync + 'static) {
+        self.hooks.lock().push((hook_name.to_string(), Box::new(move || Box::pin(hook()))));
+    }
+
+    /// Runs all registered hooks, concurrently -- to be called once the services are started
+    pub async fn run(&self) {
+        let hooks = std::mem::take(&mut *self.hooks.lock());
+        self.report.lock().status = WarmupStatus::Running;
+        info!("Warming up: running {} hook{}...", hooks.len(), if hooks.len() != 1 {"s"} else {""});
+        let start = Instant::now();
+        let hook_runs = future::join_all(hooks.into_iter()
+            .map(|(name, hook)| async move {
+                let start = Instant::now();
+                let result = hook().await;
+                let elapsed_micros = start.elapsed().as_micros() as u64;
+                match &result {
+                    Ok(())   => info!("Warmup hook '{}' done in {:.3}s", name, elapsed_micros as f64 / 1e6),
+                    Err(err) => warn!("Warmup hook '{}' FAILED in {:.3}s: {}", name, elapsed_micros as f64 / 1e6, err),
+                }
+                WarmupHookRun { name, elapsed_micros, error: result.err().map(|err| err.to_string()) }
+            })).await;
+        info!("Warmup done in {:.3}s: the application is ready", start.elapsed().as_secs_f64());
+        *self.report.lock() = WarmupReport { status: WarmupStatus::Done, hooks: hook_runs };
+    }
+
+    /// tells if the warmup is done
+    pub fn is_ready(&self) -> bool {
+        self.report.lock().status == WarmupStatus::Done
+    }
+
+    pub fn report(&self) -> WarmupReport {
+        self.report.lock().clone()
+    }
+}
+
+
+/// Unit tests the [warmup](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn hooks_are_ran_and_reported() {
+        let warmup = Warmup::new();
+        warmup.register("cache", || async { Ok(()) });
+        warmup.register("dataset", || async { Err(Box::from("expected failure")) });
+        assert!(!warmup.is_ready(), "Readiness should only be reported after the warmup ran");
+        warmup.run().await;
+        assert!(warmup.is_ready(), "Failing hooks should not hold the readiness back");
+        let report = warmup.report();
+        assert_eq!(report.hooks.iter().map(|hook_run| (hook_run.name.as_str(), hook_run.error.is_some())).collect::<Vec<_>>(),
+                   vec![("cache", false), ("dataset", true)],
+                   "All hooks should have been reported, along with their outcomes");
+    }
+}
@@ -0,0 +1,320 @@
+//! Outbound webhooks: application events POSTed, as JSON, to the external integrations in [crate::config::Config::webhooks].
+//!
+//! Deliveries are retried (with exponential backoff) & the most recent ones are kept for inspection through `/api/webhooks/deliveries`.
+//! Payloads are signed with HMAC-SHA256 -- receivers should check the `X-Webhook-Signature` header against their copy of the secret.
+//! The correlation id in scope when the event was published, if any, goes in the `X-Correlation-Id` header -- see [super::correlation].\
+//! All events published to the event bus are delivered -- named after their topics, see [Webhooks::forward_events()] -- but the business logic
+//! may, as well, call [Webhooks::publish()] directly -- or go through the [super::Outbox], for events that must survive crashes & restarts.
+
+use super::{PublishedEvent, correlated, correlation_id, CORRELATION_ID_HEADER};
+use crate::config::{WebhooksConfig, WebhookTarget};
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use log::{debug, warn};
+
+
+/// How long to wait for each target to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// The result of delivering an event to a target -- as listed by `/api/webhooks/deliveries`
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    /// unique within this process -- also sent in the `X-Webhook-Delivery` header
+    pub id:        u64,
+    pub target:    String,
+    pub event:     String,
+    /// when the delivery was, first, attempted
+    pub timestamp: String,
+    pub attempts:  u32,
+    /// `true` if the target answered with a 2xx status
+    pub delivered: bool,
+    /// the target's last answer or error
+    pub outcome:   String,
+}
+
+/// Dispatches events to the configured webhook targets -- see [Webhooks::publish()]
+pub struct Webhooks {
+    config:           WebhooksConfig,
+    client:           reqwest::Client,
+    next_delivery_id: AtomicU64,
+    /// the most recent deliveries, including the ones still being retried -- the newest at the back
+    deliveries:       Mutex<VecDeque<Delivery>>,
+}
+
+impl Webhooks {
+
+    pub fn new(config: &WebhooksConfig) -> Self {
+        Self {
+            config:           config.clone(),
+            client:           reqwest::Client::new(),
+            next_delivery_id: AtomicU64::new(1),
+            deliveries:       Mutex::new(VecDeque::with_capacity(config.deliveries_kept)),
+        }
+    }
+
+    /// POSTs `payload` to all targets interested in `event` -- like "order.created". Deliveries happen on background tasks, so this
+    /// returns immediately: check [Webhooks::recent_deliveries()] for their outcomes
+    pub fn publish(self: &Arc<Self>, event: &str, payload: &impl Serialize) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let body = rocket::serde::json::to_string(payload)
+            .map_err(|err| format!("Webhooks: could not serialize the payload for event '{}': {}", event, err))?;
+        let correlation_id = correlation_id();
+        for (target_name, target) in &self.config.targets {
+            if !is_interested(target, event) {
+                continue
+            }
+            let id = self.next_delivery_id.fetch_add(1, Ordering::Relaxed);
+            self.record(Delivery {
+                id,
+                target:    target_name.clone(),
+                event:     event.to_string(),
+                timestamp: chrono::Local::now().to_rfc3339(),
+                attempts:  0,
+                delivered: false,
+                outcome:   String::from("pending"),
+            });
+            let webhooks = Arc::clone(self);
+            let target = target.clone();
+            let event = event.to_string();
+            let body = body.clone();
+            let correlation_id = correlation_id.clone();
+            tokio::spawn(correlated(correlation_id, async move { webhooks.deliver(id, &target, &event, body).await }));
+        }
+        Ok(())
+    }
+
+    /// publishes (the payload of) each event followed through `events` -- see [crate::runtime::EventBus::follow()] -- until the bus is gone
+    pub async fn forward_events(self: Arc<Self>, mut events: broadcast::Receiver<PublishedEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => if let Err(err) = correlated(event.correlation_id.clone(), async { self.publish(event.topic, &event.payload) }).await {
+                    warn!("Webhooks: could not forward event #{} of topic '{}': {}", event.seq, event.topic, err);
+                },
+                Err(RecvError::Lagged(missed)) => warn!("Webhooks: fell behind the event bus -- {} events won't be delivered", missed),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// the most recent deliveries -- the newest first
+    pub fn recent_deliveries(&self) -> Vec<Delivery> {
+        self.deliveries.lock().iter().rev().cloned().collect()
+    }
+
+    /// POSTs the JSON `body` to all targets interested in `event` -- a single attempt each, awaited, sending `idempotency_key` in the
+    /// `Idempotency-Key` header, so receivers may discard repetitions -- failing if any of them did not take it. Used by the [super::Outbox],
+    /// which does its own retrying
+    pub async fn deliver_once(&self, event: &str, body: &str, idempotency_key: &str) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+        let mut failures = vec![];
+        for (target_name, target) in &self.config.targets {
+            if !is_interested(target, event) {
+                continue
+            }
+            let id = self.next_delivery_id.fetch_add(1, Ordering::Relaxed);
+            let (delivered, outcome) = self.attempt(id, target, event, body, Some(idempotency_key)).await;
+            if !delivered {
+                failures.push(format!("'{}' answered {}", target_name, outcome));
+            }
+            self.record(Delivery {
+                id,
+                target:    target_name.clone(),
+                event:     event.to_string(),
+                timestamp: chrono::Local::now().to_rfc3339(),
+                attempts:  1,
+                delivered,
+                outcome,
+            });
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::from(format!("Webhooks: event '{}' was not delivered: {}", event, failures.join("; "))))
+        }
+    }
+
+    /// tries to deliver `body` to `target`, retrying as configured
+    async fn deliver(&self, id: u64, target: &WebhookTarget, event: &str, body: String) {
+        let mut retry_delay = Duration::from_millis(self.config.retry_delay_millis);
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            let (delivered, outcome) = self.attempt(id, target, event, &body, None).await;
+            self.update(id, |delivery| {
+                delivery.attempts  = attempt;
+                delivery.delivered = delivered;
+                delivery.outcome   = outcome.clone();
+            });
+            if delivered {
+                debug!("Webhooks: event '{}' delivered to '{}' (delivery #{}, attempt {})", event, target.url, id, attempt);
+                return
+            }
+            warn!("Webhooks: attempt {} of {} to deliver event '{}' to '{}' failed (delivery #{}): {}", attempt, self.config.max_attempts, event, target.url, id, outcome);
+            if attempt < self.config.max_attempts {
+                tokio::time::sleep(retry_delay).await;
+                retry_delay *= 2;
+            }
+        }
+    }
+
+    /// POSTs `body` to `target` once -- telling if it was taken (answered with a 2xx status), along with the answer or error
+    async fn attempt(&self, id: u64, target: &WebhookTarget, event: &str, body: &str, idempotency_key: Option<&str>) -> (bool, String) {
+        let mut request = self.client.post(&target.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", event)
+            .header("X-Webhook-Delivery", id.to_string());
+        if let Some(correlation_id) = correlation_id() {
+            request = request.header(CORRELATION_ID_HEADER, correlation_id);
+        }
+        if let Some(idempotency_key) = idempotency_key {
+            request = request.header("Idempotency-Key", idempotency_key);
+        }
+        if !target.secret.is_empty() {
+            request = request.header("X-Webhook-Signature", format!("sha256={}", hmac_sha256_hex(&target.secret, body.as_bytes())));
+        }
+        match request.body(body.to_string()).send().await {
+            Ok(response) => (response.status().is_success(), response.status().to_string()),
+            Err(err) => (false, err.to_string()),
+        }
+    }
+
+    fn record(&self, delivery: Delivery) {
+        let mut deliveries = self.deliveries.lock();
+        while deliveries.len() >= self.config.deliveries_kept.max(1) {
+            deliveries.pop_front();
+        }
+        deliveries.push_back(delivery);
+    }
+
+    /// applies `operation` to the delivery with the given `id` -- if it is still being kept
+    fn update(&self, id: u64, operation: impl FnOnce(&mut Delivery)) {
+        if let Some(delivery) = self.deliveries.lock().iter_mut().rev().find(|delivery| delivery.id == id) {
+            operation(delivery);
+        }
+    }
+}
+
+/// tells if `target` should receive `event`
+fn is_interested(target: &WebhookTarget, event: &str) -> bool {
+    target.events.is_empty() || target.events.iter()
+        .any(|filter| match filter.strip_suffix('*') {
+            Some(prefix) => event.starts_with(prefix),
+            None => filter == event,
+        })
+}
+
+/// the HMAC-SHA256 signature of `payload`, in lowercase hex
+pub fn hmac_sha256_hex(secret: &str, payload: &[u8]) -> String {
+    hex::encode(hmac_sha256(secret, payload).finalize().into_bytes())
+}
+
+/// tells if the hex encoded `signature` is the HMAC-SHA256 of `payload` -- comparing them in constant time
+pub fn verify_hmac_sha256_hex(secret: &str, payload: &[u8], signature: &str) -> bool {
+    hex::decode(signature)
+        .map(|signature| hmac_sha256(secret, payload).verify_slice(&signature).is_ok())
+        .unwrap_or(false)
+}
+
+fn hmac_sha256(secret: &str, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("BUG! HMAC accepts keys of any size");
+    mac.update(payload);
+    mac
+}
+
+
+/// Unit tests the [webhooks](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+
+    /// assures the payloads are signed in the `X-Webhook-Signature` header -- checked against the HMAC-SHA256 test vector from RFC 4231 (test case 2)
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn signature_header() {
+        let (url, requests) = receiver("200 OK").await;
+        let webhooks = webhooks(&url, "Jefe", 1);
+        webhooks.deliver_once("order.created", "what do ya want for nothing?", "key-1").await
+            .expect("The event should have been delivered");
+        let requests = requests.lock();
+        assert_eq!(requests.len(), 1, "A single request should have been received");
+        assert!(requests[0].lines().any(|header| header == "x-webhook-signature: sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+                "The signature header is missing or wrong: {}", requests[0]);
+    }
+
+    /// assures failing deliveries are retried up to the configured attempts -- then given up, as not delivered
+    #[cfg_attr(not(feature = "dox"), tokio::test)]
+    async fn retry_exhaustion() {
+        let (url, requests) = receiver("500 Internal Server Error").await;
+        let webhooks = Arc::new(webhooks(&url, "", 3));
+        webhooks.publish("order.created", &"payload").expect("Couldn't publish");
+        let start = tokio::time::Instant::now();
+        while webhooks.recent_deliveries()[0].attempts < 3 && start.elapsed() < Duration::from_secs(5) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let delivery = &webhooks.recent_deliveries()[0];
+        assert_eq!((delivery.attempts, delivery.delivered), (3, false), "The delivery should have been given up after all attempts");
+        assert_eq!(delivery.outcome, "500 Internal Server Error", "The target's last answer should have been kept");
+        assert_eq!(requests.lock().len(), 3, "No attempts should have been made after the last one");
+    }
+
+    /// webhooks for a single target, at `url`, retrying quickly
+    fn webhooks(url: &str, secret: &str, max_attempts: u32) -> Webhooks {
+        Webhooks::new(&WebhooksConfig {
+            targets:            BTreeMap::from([(String::from("crm"), WebhookTarget { url: url.to_string(), secret: secret.to_string(), events: vec![] })]),
+            max_attempts,
+            retry_delay_millis: 1,
+            deliveries_kept:    10,
+        })
+    }
+
+    /// spawns a minimal HTTP server answering every request with `status` -- returning its url & the heads (request line & headers)
+    /// of the requests received
+    async fn receiver(status: &'static str) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .expect("Binding the receiver");
+        let url = format!("http://{}/hooks", listener.local_addr().expect("Getting the receiver's address"));
+        let requests = Arc::new(Mutex::new(vec![]));
+        let received = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![];
+                let mut buffer = [0u8; 4096];
+                // reads the head & the body -- whose length is told in the head
+                let head = loop {
+                    let read = stream.read(&mut buffer).await.unwrap_or(0);
+                    request.extend_from_slice(&buffer[..read]);
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let Some(head_end) = request.find("\r\n\r\n") else {
+                        if read == 0 { break request } else { continue }
+                    };
+                    let content_length = request.lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if read == 0 || request.len() >= head_end + 4 + content_length {
+                        break request[..head_end].to_string()
+                    }
+                };
+                received.lock().push(head);
+                let _ = stream.write_all(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+}
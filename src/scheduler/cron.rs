@@ -0,0 +1,171 @@
+//! Cron expressions -- see [crate::config::JobSchedule::Cron]
+
+use std::{
+    fmt,
+    ops::RangeInclusive,
+};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+
+/// How far ahead to look for the next run -- long enough for any Feb 29th to fall on any day of the week.
+/// Expressions matching no date (like "0 0 30 2 *") are, then, reported as never running
+const MAX_DAYS_AHEAD: i64 = 366 * 28;
+
+
+/// A parsed cron expression: "minute hour day-of-month month day-of-week"
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    expression:    String,
+    /// bit `n` is set if `n` matches
+    minutes:       u64,
+    hours:         u64,
+    days_of_month: u64,
+    months:        u64,
+    /// 0 is Sunday
+    days_of_week:  u64,
+    /// as in the classic cron: when both day fields are restricted, days matching either of them match
+    either_day:    bool,
+}
+
+impl CronSchedule {
+
+    pub fn parse(expression: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(Box::from(format!("Invalid cron expression '{}': 5 fields (minute hour day-of-month month day-of-week) were expected, but {} were found", expression, fields.len())))
+        };
+        let field = |name: &str, field: &str, range: RangeInclusive<u32>| parse_field(field, range)
+            .map_err(|err| format!("Invalid cron expression '{}': bad {} field '{}': {}", expression, name, field, err));
+        let mut days_of_week_bits = field("day-of-week", days_of_week, 0..=7)?;
+        // 7 is Sunday as well
+        if days_of_week_bits & (1 << 7) != 0 {
+            days_of_week_bits |= 1;
+        }
+        Ok(Self {
+            expression:    expression.to_string(),
+            minutes:       field("minute", minutes, 0..=59)?,
+            hours:         field("hour", hours, 0..=23)?,
+            days_of_month: field("day-of-month", days_of_month, 1..=31)?,
+            months:        field("month", months, 1..=12)?,
+            days_of_week:  days_of_week_bits,
+            either_day:    !days_of_month.starts_with('*') && !days_of_week.starts_with('*'),
+        })
+    }
+
+    /// The first time, after `after`, matching this expression in `after`'s timezone -- `None` if there is none in the foreseeable future.\
+    /// Local times skipped by DST changes never match & the repeated ones only match once (on their first occurrence)
+    pub fn next_after<Zone: TimeZone>(&self, after: DateTime<Zone>) -> Option<DateTime<Zone>> {
+        let mut candidate = self.next_naive(after.naive_local())?;
+        loop {
+            match after.timezone().from_local_datetime(&candidate).earliest() {
+                Some(next) if next > after => return Some(next),
+                _ => candidate = self.next_naive(candidate)?,
+            }
+        }
+    }
+
+    /// the first minute, after `after`, matching this expression -- regardless of timezones
+    fn next_naive(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut candidate = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = candidate + chrono::Duration::days(MAX_DAYS_AHEAD);
+        while candidate < limit {
+            let date = candidate.date();
+            if !self.matches_date(date) {
+                candidate = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !is_set(self.hours, candidate.hour()) {
+                candidate = date.and_hms_opt(candidate.hour(), 0, 0)? + chrono::Duration::hours(1);
+            } else if !is_set(self.minutes, candidate.minute()) {
+                candidate += chrono::Duration::minutes(1);
+            } else {
+                return Some(candidate)
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let day_of_month = is_set(self.days_of_month, date.day());
+        let day_of_week = is_set(self.days_of_week, date.weekday().num_days_from_sunday());
+        is_set(self.months, date.month()) && if self.either_day { day_of_month || day_of_week } else { day_of_month && day_of_week }
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+fn is_set(bits: u64, n: u32) -> bool {
+    bits & (1 << n) != 0
+}
+
+/// parses a comma separated list of `*`, `n`, `a-b`, each optionally followed by a `/step` -- into the bits of the values within `range`
+fn parse_field(field: &str, range: RangeInclusive<u32>) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (values, step) = match part.split_once('/') {
+            Some((values, step)) => (values, Some(step.parse::<u32>().ok().filter(|step| *step > 0)
+                                                      .ok_or_else(|| format!("invalid step '{}'", step))?)),
+            None => (part, None),
+        };
+        let value = |value: &str| value.parse::<u32>().ok().filter(|value| range.contains(value))
+            .ok_or_else(|| format!("'{}' is not within {}-{}", value, range.start(), range.end()));
+        let (first, last) = match values.split_once('-') {
+            _ if values == "*" => (*range.start(), *range.end()),
+            Some((first, last)) => (value(first)?, value(last)?),
+            // "n/step" means from `n` to the end
+            None if step.is_some() => (value(values)?, *range.end()),
+            None => (value(values)?, value(values)?),
+        };
+        if first > last {
+            return Err(format!("empty range {}-{}", first, last))
+        }
+        for n in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+
+/// Unit tests the [cron](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn next_runs() {
+        let at = |date_time: &str| NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M").expect("Bad test date");
+        let next = |expression: &str, after: &str| CronSchedule::parse(expression).expect("Valid expression refused").next_naive(at(after));
+        // 2024-01-01 was a Monday
+        assert_eq!(next("*/15 * * * *", "2024-01-01 10:07"), Some(at("2024-01-01 10:15")), "Steps are off");
+        assert_eq!(next("0,30 9-17 * * *", "2024-01-01 17:30"), Some(at("2024-01-02 09:00")), "Lists & ranges are off");
+        assert_eq!(next("30 3 * * 1-5", "2024-01-05 04:00"), Some(at("2024-01-08 03:30")), "Weekends should have been skipped");
+        assert_eq!(next("0 0 * * 7", "2024-01-01 00:00"), Some(at("2024-01-07 00:00")), "7 should be Sunday");
+        assert_eq!(next("0 0 13 * 5", "2024-01-01 00:00"), Some(at("2024-01-05 00:00")), "Restricted day fields should match either");
+        assert_eq!(next("0 0 29 2 *", "2024-03-01 00:00"), Some(at("2028-02-29 00:00")), "Leap days are off");
+        assert_eq!(next("0 0 30 2 *", "2024-01-01 00:00"), None, "Impossible dates should never run");
+        for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(CronSchedule::parse(invalid).is_err(), "'{}' should have been refused", invalid);
+        }
+    }
+
+    /// assures DST transitions are handled: local times skipped by them never match & the repeated ones only match once
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn dst_transitions() {
+        use chrono_tz::Europe::Berlin;
+        let at = |date_time: &str| Berlin.from_local_datetime(&NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M").expect("Bad test date"))
+            .earliest().expect("Test date skipped by DST");
+        let daily = CronSchedule::parse("30 2 * * *").expect("Valid expression refused");
+        // 2024-03-31 02:00 became 03:00 in Berlin
+        assert_eq!(daily.next_after(at("2024-03-30 12:00")), Some(at("2024-04-01 02:30")), "Local times skipped by DST should never match");
+        // 2024-10-27 03:00 became 02:00 in Berlin
+        let repeated = daily.next_after(at("2024-10-26 12:00")).expect("Next run not found");
+        assert_eq!(repeated, at("2024-10-27 02:30"), "Local times repeated by DST should match their first occurrence");
+        assert_eq!(daily.next_after(repeated), Some(at("2024-10-28 02:30")), "Local times repeated by DST should match only once");
+        let hourly = CronSchedule::parse("0 * * * *").expect("Valid expression refused");
+        assert_eq!(hourly.next_after(at("2024-03-31 01:30")), Some(at("2024-03-31 03:00")), "Hourly runs should go on after the skipped hour");
+    }
+}
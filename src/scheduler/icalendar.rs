@@ -0,0 +1,90 @@
+//! Renders the upcoming runs of the scheduled jobs as an iCalendar (RFC 5545) feed -- served on `/api/schedule.ics`, so operators
+//! may subscribe to it from their calendar apps & see when the heavy jobs will run
+
+use super::UpcomingRun;
+use crate::config::APP_NAME;
+use chrono::{DateTime, Utc};
+
+
+/// Content lines longer than this (in octets) must be folded -- see RFC 5545, section 3.1
+const MAX_LINE_OCTETS: usize = 75;
+
+
+/// the calendar with one event for each of the `upcoming_runs` -- timestamped `now`
+pub fn render(upcoming_runs: &[UpcomingRun], now: DateTime<Utc>) -> String {
+    let mut calendar = String::new();
+    let mut line = |contents: String| {
+        fold_into(&mut calendar, &contents);
+    };
+    line(String::from("BEGIN:VCALENDAR"));
+    line(String::from("VERSION:2.0"));
+    line(format!("PRODID:-//{}//Scheduler//EN", APP_NAME));
+    line(String::from("CALSCALE:GREGORIAN"));
+    line(format!("X-WR-CALNAME:{} scheduled jobs", escape(APP_NAME)));
+    for run in upcoming_runs {
+        let start = run.at.with_timezone(&Utc);
+        line(String::from("BEGIN:VEVENT"));
+        line(format!("UID:{}-{}@{}", escape(&run.job), start.timestamp(), APP_NAME));
+        line(format!("DTSTAMP:{}", timestamp(now)));
+        line(format!("DTSTART:{}", timestamp(start)));
+        line(format!("SUMMARY:{}", escape(&run.job)));
+        line(format!("DESCRIPTION:{}", escape(&format!("Scheduled job '{}' -- {}", run.job, run.schedule))));
+        line(String::from("TRANSP:TRANSPARENT"));
+        line(String::from("END:VEVENT"));
+    }
+    line(String::from("END:VCALENDAR"));
+    calendar
+}
+
+/// `instant` in the iCalendar UTC format -- like `20261016T093000Z`
+fn timestamp(instant: DateTime<Utc>) -> String {
+    instant.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// escapes the characters with special meaning in iCalendar's TEXT values
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// appends the content `line` to `calendar` -- CRLF terminated & folded every [MAX_LINE_OCTETS], without splitting UTF-8 characters
+fn fold_into(calendar: &mut String, line: &str) {
+    let mut line_octets = 0;
+    for character in line.chars() {
+        if line_octets + character.len_utf8() > MAX_LINE_OCTETS {
+            calendar.push_str("\r\n ");
+            // (the leading space counts)
+            line_octets = 1;
+        }
+        calendar.push(character);
+        line_octets += character.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+
+/// Unit tests the [icalendar](self) module
+#[cfg(any(test, feature = "dox"))]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+
+    #[cfg_attr(not(feature = "dox"), test)]
+    fn well_formed_feed() {
+        let at = Local.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        let upcoming_runs = vec![
+            UpcomingRun { job: String::from("purge, sessions"), schedule: String::from("cron '0 30 9 * * *'"), at },
+            UpcomingRun { job: String::from("report"),          schedule: "every 60s -- ".repeat(10),           at },
+        ];
+        let calendar = render(&upcoming_runs, Utc.with_ymd_and_hms(2026, 10, 15, 0, 0, 0).unwrap());
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n") && calendar.ends_with("END:VCALENDAR\r\n"), "Wrong calendar envelope:\n{}", calendar);
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 2, "Each upcoming run should be an event");
+        assert!(calendar.contains(&format!("DTSTART:{}\r\n", timestamp(at.with_timezone(&Utc)))), "The runs should start at their UTC times:\n{}", calendar);
+        assert!(calendar.contains("SUMMARY:purge\\, sessions\r\n"), "Special characters should be escaped:\n{}", calendar);
+        assert!(calendar.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS), "Long lines should be folded:\n{}", calendar);
+        assert!(calendar.contains("\r\n "), "Folded lines should continue with a space:\n{}", calendar);
+    }
+}
@@ -0,0 +1,14 @@
+//! The job scheduler: runs the business logic's periodic jobs -- on cron-like expressions or fixed intervals, as set in
+//! [crate::config::Config::scheduler] -- on the Tokio runtime, keeping their statuses for inspection through `/api/stats/scheduler`
+//! & the `check-config` console job.
+//!
+//! The jobs' code is registered by name -- see [Scheduler::register()] -- so their schedules may be changed in the config file
+//! without recompiling. Their upcoming runs are also offered as an iCalendar feed, on `/api/schedule.ics`, & listed by the
+//! `schedule list` console job -- while the admin API may disable (& re-enable) them.
+
+mod cron;
+
+mod icalendar;
+
+mod scheduler;
+pub use scheduler::*;
@@ -0,0 +1,294 @@
+//! see [super]
+
+use super::{cron::CronSchedule, icalendar};
+use crate::{
+    config::{SchedulerConfig, JobSchedule},
+    runtime::{JobAccounting, LeaderElection, DegradeSwitches, Shutdown, Clock},
+};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+use chrono::{DateTime, Local};
+use futures::future::BoxFuture;
+use parking_lot::{Mutex, RwLock};
+use serde::{Serialize, Deserialize};
+use tracing::{info_span, Instrument};
+use log::{debug, info, warn};
+
+
+/// The longest sleep between checks of the wall clock while waiting for the next run -- so clock adjustments (like NTP's) are followed
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+
+type ScheduledJobFn = Arc<dyn Fn() -> BoxFuture<'static, Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + Sync>;
+
+/// The state of a scheduled job -- as shown by `/api/stats/scheduler` & the `check-config` console job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJobStatus {
+    pub name:       String,
+    pub schedule:   String,
+    /// `false` if the business logic didn't register the job's code -- in which case it never runs
+    pub registered: bool,
+    /// `false` while disabled through the admin API -- see [Scheduler::set_enabled()]
+    pub enabled:    bool,
+    /// if it keeps running in maintenance mode -- see [crate::config::ScheduledJobConfig::critical]
+    #[serde(default)]
+    pub critical:   bool,
+    pub running:    bool,
+    pub runs:       u64,
+    pub failures:   u64,
+    /// when the last run started
+    pub last_run:   Option<String>,
+    /// present if the last run failed
+    pub last_error: Option<String>,
+    pub next_run:   Option<String>,
+}
+
+/// A run of a scheduled job, yet to happen -- see [Scheduler::upcoming_runs()]
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpcomingRun {
+    pub job:      String,
+    /// as in [ScheduledJobStatus::schedule]
+    pub schedule: String,
+    pub at:       DateTime<Local>,
+}
+
+/// [JobSchedule], parsed
+enum Schedule {
+    Cron(CronSchedule),
+    Every(chrono::Duration),
+}
+
+struct ScheduledJob {
+    schedule: Schedule,
+    status:   Mutex<ScheduledJobStatus>,
+}
+
+/// Runs the jobs configured in [SchedulerConfig] -- whose code is registered by the business logic. Usage:
+/// ```no_compile
+///     scheduler.register("purge-sessions", || async move { sessions::purge_expired().await });
+///     scheduler.start(leader_election, Some(degrade), shutdown);
+/// Runs are accounted for by [JobAccounting] & a job never overlaps itself: runs due while the previous one is still going are skipped.\
+/// When leader election is enabled, jobs only run on the leader.\
+/// While in maintenance mode (see [DegradeSwitches::maintenance()]), only the critical jobs run.\
+/// Time is told by the given [Clock] -- so the schedules may be simulated (see [crate::runtime::simulation::simulate()]).
+pub struct Scheduler {
+    jobs:           Vec<Arc<ScheduledJob>>,
+    handlers:       RwLock<HashMap<String, ScheduledJobFn>>,
+    job_accounting: Arc<JobAccounting>,
+    clock:          Arc<dyn Clock>,
+}
+
+impl Scheduler {
+
+    /// fails if any of the schedules in `config` is invalid
+    pub fn new(config: &SchedulerConfig, job_accounting: Arc<JobAccounting>, clock: Arc<dyn Clock>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let jobs = config.jobs.iter()
+            .map(|job_config| {
+                let (schedule, description) = match &job_config.schedule {
+                    JobSchedule::Cron(expression) => {
+                        let cron = CronSchedule::parse(expression)
+                            .map_err(|err| format!("Scheduled job '{}': {}", job_config.name, err))?;
+                        let description = format!("cron '{}'", cron);
+                        (Schedule::Cron(cron), description)
+                    },
+                    JobSchedule::Every { seconds: 0 } => return Err(Box::from(format!("Scheduled job '{}': the interval must be greater than 0", job_config.name))),
+                    JobSchedule::Every { seconds } => {
+                        let interval = chrono::Duration::from_std(Duration::from_secs(*seconds))
+                            .map_err(|err| format!("Scheduled job '{}': invalid interval of {}s: {}", job_config.name, seconds, err))?;
+                        (Schedule::Every(interval), format!("every {}s", seconds))
+                    },
+                };
+                // (the intervals are only counted once started)
+                let next_run = match &schedule {
+                    Schedule::Cron(cron) => cron.next_after(clock.now()).map(|next_run| next_run.to_rfc3339()),
+                    Schedule::Every(_) => None,
+                };
+                Ok(Arc::new(ScheduledJob {
+                    schedule,
+                    status: Mutex::new(ScheduledJobStatus {
+                        name:       job_config.name.clone(),
+                        schedule:   description,
+                        registered: false,
+                        enabled:    true,
+                        critical:   job_config.critical,
+                        running:    false,
+                        runs:       0,
+                        failures:   0,
+                        last_run:   None,
+                        last_error: None,
+                        next_run,
+                    }),
+                }))
+            })
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error + Send + Sync>>>()?;
+        Ok(Self {
+            jobs,
+            handlers: RwLock::new(HashMap::new()),
+            job_accounting,
+            clock,
+        })
+    }
+
+    /// Registers the code of the scheduled job `name` -- to be called before [Self::start()]
+    pub fn register<JobFuture: Future<Output = Result<(), Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>
+                   (&self, name: &str, job: impl Fn() -> JobFuture + Send + Sync + 'static) {
+        self.handlers.write().insert(name.to_string(), Arc::new(move || Box::pin(job())));
+    }
+
+    /// Spawns a task for each of the registered jobs, running them on their schedules until a shutdown is requested -- the non-critical
+    /// ones being skipped while `degrade` is in maintenance mode.
+    /// Registered jobs missing from the config -- and configured jobs missing a registration -- are warned about
+    pub fn start(self: &Arc<Self>, leader_election: Option<Arc<LeaderElection>>, degrade: Option<Arc<DegradeSwitches>>, shutdown: Arc<Shutdown>) {
+        let handlers = self.handlers.read();
+        for name in handlers.keys() {
+            if !self.jobs.iter().any(|job| job.status.lock().name == *name) {
+                warn!("Scheduler: job '{}' was registered, but is not in the config -- it won't run", name);
+            }
+        }
+        for job in &self.jobs {
+            let name = job.status.lock().name.clone();
+            let Some(handler) = handlers.get(&name).cloned() else {
+                warn!("Scheduler: job '{}' is in the config, but its code was not registered -- it won't run", name);
+                continue
+            };
+            job.status.lock().registered = true;
+            tokio::spawn(run_on_schedule(Arc::clone(self), Arc::clone(job), handler, leader_election.clone(), degrade.clone(), Arc::clone(&shutdown)));
+        }
+        debug!("Scheduler: started");
+    }
+
+    /// The state of all configured jobs
+    pub fn report(&self) -> Vec<ScheduledJobStatus> {
+        self.jobs.iter()
+            .map(|job| job.status.lock().clone())
+            .collect()
+    }
+
+    /// Disables (or re-enables) the job `name` -- runs due while disabled are skipped. Tells if there is such a job
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        let Some(job) = self.jobs.iter().find(|job| job.status.lock().name == name) else {
+            return false
+        };
+        job.status.lock().enabled = enabled;
+        info!("Scheduler: job '{}' {}", name, if enabled { "enabled" } else { "disabled" });
+        true
+    }
+
+    /// The runs due until `until` -- at most `max_runs_per_job` of each job -- in chronological order.
+    /// Jobs that won't run (disabled or not registered) are left out, as are the ones on intervals not started yet
+    pub fn upcoming_runs(&self, until: DateTime<Local>, max_runs_per_job: usize) -> Vec<UpcomingRun> {
+        let mut upcoming_runs = vec![];
+        for job in &self.jobs {
+            let status = job.status.lock().clone();
+            if !status.enabled || !status.registered {
+                continue
+            }
+            let next_run = status.next_run.as_ref()
+                .and_then(|next_run| DateTime::parse_from_rfc3339(next_run).ok())
+                .map(|next_run| next_run.with_timezone(&Local));
+            let mut at = match &job.schedule {
+                Schedule::Cron(cron) => next_run.or_else(|| cron.next_after(self.clock.now())),
+                Schedule::Every(_) => next_run,
+            };
+            for _ in 0..max_runs_per_job {
+                let Some(run_at) = at.filter(|run_at| *run_at <= until) else { break };
+                upcoming_runs.push(UpcomingRun { job: status.name.clone(), schedule: status.schedule.clone(), at: run_at });
+                at = match &job.schedule {
+                    Schedule::Cron(cron) => cron.next_after(run_at),
+                    Schedule::Every(interval) => Some(run_at + *interval),
+                };
+            }
+        }
+        upcoming_runs.sort_by_key(|upcoming_run| upcoming_run.at);
+        upcoming_runs
+    }
+
+    /// The [Self::upcoming_runs()] as an iCalendar feed -- see [icalendar]
+    pub fn icalendar(&self, until: DateTime<Local>, max_runs_per_job: usize) -> String {
+        icalendar::render(&self.upcoming_runs(until, max_runs_per_job), self.clock.now().with_timezone(&chrono::Utc))
+    }
+}
+
+/// Runs `job` whenever it is due, until a `shutdown` is requested
+async fn run_on_schedule(scheduler:       Arc<Scheduler>,
+                         job:             Arc<ScheduledJob>,
+                         handler:         ScheduledJobFn,
+                         leader_election: Option<Arc<LeaderElection>>,
+                         degrade:         Option<Arc<DegradeSwitches>>,
+                         shutdown:        Arc<Shutdown>) {
+    let name = job.status.lock().name.clone();
+    let clock = Arc::clone(&scheduler.clock);
+    let mut due = clock.now();
+    loop {
+        due = match &job.schedule {
+            Schedule::Cron(cron) => match cron.next_after(clock.now()) {
+                Some(next) => next,
+                None => {
+                    warn!("Scheduler: job '{}' has no upcoming runs -- it won't run again", name);
+                    job.status.lock().next_run = None;
+                    return
+                },
+            },
+            // the missed runs (while the previous run took longer than the interval) are skipped
+            Schedule::Every(interval) => {
+                let mut next = due + *interval;
+                while next <= clock.now() {
+                    next = next + *interval;
+                }
+                next
+            },
+        };
+        job.status.lock().next_run = Some(due.to_rfc3339());
+        tokio::select! {
+            () = sleep_until(&*clock, due) => (),
+            () = shutdown.wait() => return,
+        }
+        if let Some(leader_election) = &leader_election {
+            if !leader_election.is_leader() {
+                debug!("Scheduler: skipping job '{}' -- this instance is not the leader", name);
+                continue
+            }
+        }
+        if !job.status.lock().enabled {
+            debug!("Scheduler: skipping job '{}' -- it is disabled", name);
+            continue
+        }
+        if degrade.as_ref().is_some_and(|degrade| degrade.maintenance()) && !job.status.lock().critical {
+            debug!("Scheduler: skipping job '{}' -- it is not critical & maintenance mode is on", name);
+            continue
+        }
+        {
+            let mut status = job.status.lock();
+            status.running = true;
+            status.last_run = Some(clock.now().to_rfc3339());
+        }
+        info!("Scheduler: running job '{}'", name);
+        let result = scheduler.job_accounting.account(&format!("scheduled: {}", name), handler().instrument(info_span!("scheduled_job", job = %name))).await;
+        let mut status = job.status.lock();
+        status.running = false;
+        status.runs += 1;
+        match result {
+            Ok(()) => status.last_error = None,
+            Err(err) => {
+                warn!("Scheduler: job '{}' FAILED: {}", name, err);
+                status.failures += 1;
+                status.last_error = Some(err.to_string());
+            },
+        }
+    }
+}
+
+/// sleeps until the `clock` reaches `instant` -- rechecking it every [MAX_SLEEP]
+async fn sleep_until(clock: &dyn Clock, instant: DateTime<Local>) {
+    loop {
+        let Ok(remaining) = (instant - clock.now()).to_std() else { return };
+        if remaining.is_zero() {
+            return
+        }
+        clock.sleep(remaining.min(MAX_SLEEP)).await;
+    }
+}
@@ -3,12 +3,12 @@
 
 #[path = "../../src/frontend/egui/mod.rs"]
 mod app_egui;
-use app_egui::Egui;
+use app_egui::{Egui, Settings, RuntimePanel};
 
 
 fn main() -> eframe::Result<()> {
     #[cfg(not(target_arch = "wasm32"))]
-    return Egui::run_egui_native_app();
+    return Egui::run_egui_native_app(Settings::new(), RuntimePanel::default());
     #[cfg(target_arch = "wasm32")]
     return Egui::run_egui_web_app();
 }